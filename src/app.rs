@@ -1,15 +1,29 @@
 //! アプリケーション状態管理
 
-use crate::config::Settings;
+use crate::config::{Settings, SizeCalibration, ThumbnailCache};
 use crate::ffmpeg::{FfmpegDetector, FfmpegInfo, ProbeResult};
 use crate::transcoder::{
-    estimate_compression_ratio_advanced, ContentType, TranscodeJob, TranscodeSettings,
-    VideoMetadata,
+    estimate_compression_ratio_advanced, ContentType, CropMode, CropSettings, QualityScore,
+    RateControlMode, TranscodeJob, TranscodeSettings, VideoMetadata,
 };
 use gpui::*;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::Arc;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 保持するFFmpegログの最大行数
+const MAX_LOG_LINES: usize = 500;
+
+/// ファイルごとに保持するFFmpegログの最大サイズ（バイト）
+const MAX_ENTRY_LOG_BYTES: usize = 256 * 1024;
+
+/// 補助処理を同時に実行する最大数
+const MAX_AUX_WORKERS: usize = 4;
 
 /// 現在の進捗情報（スレッド間共有用）
 #[derive(Clone)]
@@ -22,12 +36,26 @@ pub struct CurrentProgress {
     pub remaining_centisecs: Arc<AtomicU32>,
     /// 現在のFPS * 100
     pub fps_centi: Arc<AtomicU32>,
+    /// 変換速度（倍速 * 100）
+    pub speed_centi: Arc<AtomicU32>,
+    /// 現在の出力サイズ（バイト）
+    pub output_size: Arc<AtomicU64>,
+    /// 現在の出力ビットレート（bps）
+    pub bitrate_bps: Arc<AtomicU64>,
     /// 総時間（秒 * 100）
     pub total_duration_centisecs: Arc<AtomicU32>,
     /// 現在の処理時間位置（秒 * 100）
     pub current_time_centisecs: Arc<AtomicU32>,
     /// キャンセルフラグ
     pub cancelled: Arc<AtomicBool>,
+    /// 現在のファイルのみスキップするフラグ
+    pub skip_current: Arc<AtomicBool>,
+    /// FFmpegのstderrログ（最大500行）
+    pub log_lines: Arc<Mutex<VecDeque<String>>>,
+    /// これまでに追加されたログの行数（表示の更新判定用）
+    pub log_count: Arc<AtomicU32>,
+    /// ソフトウェアエンコードに切り替えた回数（通知の表示判定用、resetでは戻さない）
+    pub fallback_count: Arc<AtomicU32>,
 }
 
 impl Default for CurrentProgress {
@@ -37,9 +65,16 @@ impl Default for CurrentProgress {
             elapsed_centisecs: Arc::new(AtomicU32::new(0)),
             remaining_centisecs: Arc::new(AtomicU32::new(0xFFFFFFFF)),
             fps_centi: Arc::new(AtomicU32::new(0)),
+            speed_centi: Arc::new(AtomicU32::new(0)),
+            output_size: Arc::new(AtomicU64::new(0)),
+            bitrate_bps: Arc::new(AtomicU64::new(0)),
             total_duration_centisecs: Arc::new(AtomicU32::new(0)),
             current_time_centisecs: Arc::new(AtomicU32::new(0)),
             cancelled: Arc::new(AtomicBool::new(false)),
+            skip_current: Arc::new(AtomicBool::new(false)),
+            log_lines: Arc::new(Mutex::new(VecDeque::with_capacity(MAX_LOG_LINES))),
+            log_count: Arc::new(AtomicU32::new(0)),
+            fallback_count: Arc::new(AtomicU32::new(0)),
         }
     }
 }
@@ -94,6 +129,38 @@ impl CurrentProgress {
         self.fps_centi.store(centi, Ordering::Relaxed);
     }
 
+    /// 変換速度を取得（倍速）
+    pub fn get_speed(&self) -> f32 {
+        self.speed_centi.load(Ordering::Relaxed) as f32 / 100.0
+    }
+
+    /// 変換速度を設定（倍速）
+    pub fn set_speed(&self, speed: f32) {
+        let centi = (speed * 100.0) as u32;
+        self.speed_centi.store(centi, Ordering::Relaxed);
+    }
+
+    /// 現在の出力サイズを取得（バイト）
+    pub fn get_output_size(&self) -> u64 {
+        self.output_size.load(Ordering::Relaxed)
+    }
+
+    /// 現在の出力サイズを設定（バイト）
+    pub fn set_output_size(&self, bytes: u64) {
+        self.output_size.store(bytes, Ordering::Relaxed);
+    }
+
+    /// 現在の出力ビットレートを取得（bps、0は不明）
+    pub fn get_bitrate_bps(&self) -> u64 {
+        self.bitrate_bps.load(Ordering::Relaxed)
+    }
+
+    /// 現在の出力ビットレートを設定（kbps）
+    pub fn set_bitrate_kbps(&self, kbps: f32) {
+        let bps = (kbps.max(0.0) as f64 * 1000.0) as u64;
+        self.bitrate_bps.store(bps, Ordering::Relaxed);
+    }
+
     /// リセット
     pub fn reset(&self) {
         self.progress_permyriad.store(0, Ordering::Relaxed);
@@ -101,9 +168,13 @@ impl CurrentProgress {
         self.remaining_centisecs
             .store(0xFFFFFFFF, Ordering::Relaxed);
         self.fps_centi.store(0, Ordering::Relaxed);
+        self.speed_centi.store(0, Ordering::Relaxed);
+        self.output_size.store(0, Ordering::Relaxed);
+        self.bitrate_bps.store(0, Ordering::Relaxed);
         self.total_duration_centisecs.store(0, Ordering::Relaxed);
         self.current_time_centisecs.store(0, Ordering::Relaxed);
         self.cancelled.store(false, Ordering::Relaxed);
+        self.skip_current.store(false, Ordering::Relaxed);
     }
 
     /// キャンセルフラグを設定
@@ -116,6 +187,58 @@ impl CurrentProgress {
         self.cancelled.load(Ordering::SeqCst)
     }
 
+    /// 現在のファイルのスキップを要求
+    pub fn skip_current(&self) {
+        self.skip_current.store(true, Ordering::SeqCst);
+    }
+
+    /// 現在のファイルのスキップが要求されたか確認
+    pub fn is_skip_requested(&self) -> bool {
+        self.skip_current.load(Ordering::SeqCst)
+    }
+
+    /// ログを1行追加（古い行から破棄）
+    pub fn push_log(&self, line: impl Into<String>) {
+        if let Ok(mut lines) = self.log_lines.lock() {
+            if lines.len() >= MAX_LOG_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line.into());
+        }
+        self.log_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// ログの全行を取得
+    pub fn log_snapshot(&self) -> Vec<String> {
+        self.log_lines
+            .lock()
+            .map(|lines| lines.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// これまでに追加されたログの行数
+    pub fn log_count(&self) -> u32 {
+        self.log_count.load(Ordering::Relaxed)
+    }
+
+    /// ソフトウェアエンコードへの切り替えを記録
+    pub fn record_fallback(&self) {
+        self.fallback_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// これまでにソフトウェアエンコードへ切り替えた回数
+    pub fn fallback_count(&self) -> u32 {
+        self.fallback_count.load(Ordering::Relaxed)
+    }
+
+    /// ログを消去
+    pub fn clear_log(&self) {
+        if let Ok(mut lines) = self.log_lines.lock() {
+            lines.clear();
+        }
+        self.log_count.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// 総時間を設定（秒）
     pub fn set_total_duration_secs(&self, secs: f64) {
         let centisecs = (secs * 100.0) as u32;
@@ -149,6 +272,180 @@ impl CurrentProgress {
             self.set_progress(progress);
         }
     }
+
+    /// 並列に変換するジョブ用の進捗（ログとソフトウェアエンコードへの切り替え回数は共有する）
+    pub fn for_parallel_job(&self) -> Self {
+        Self {
+            log_lines: self.log_lines.clone(),
+            log_count: self.log_count.clone(),
+            fallback_count: self.fallback_count.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// 別のジョブの進捗の値を写す（並列変換で表示するジョブを切り替えるため）
+    /// キャンセル・スキップのフラグとログは写さない
+    pub fn mirror(&self, other: &CurrentProgress) {
+        let u32_values = [
+            (&self.progress_permyriad, &other.progress_permyriad),
+            (&self.elapsed_centisecs, &other.elapsed_centisecs),
+            (&self.remaining_centisecs, &other.remaining_centisecs),
+            (&self.fps_centi, &other.fps_centi),
+            (&self.speed_centi, &other.speed_centi),
+            (
+                &self.total_duration_centisecs,
+                &other.total_duration_centisecs,
+            ),
+            (&self.current_time_centisecs, &other.current_time_centisecs),
+        ];
+        for (to, from) in u32_values {
+            to.store(from.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+        for (to, from) in [
+            (&self.output_size, &other.output_size),
+            (&self.bitrate_bps, &other.bitrate_bps),
+        ] {
+            to.store(from.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+    }
+}
+
+/// バックグラウンド処理の中断トークン
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// 中断を要求
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// 中断が要求されたか
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// FFmpegの実行に渡す中断フラグ
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.0.clone()
+    }
+}
+
+/// 行に表示するサムネイル
+#[derive(Clone)]
+pub enum Thumbnail {
+    /// 生成済み
+    Ready(Arc<Image>),
+    /// 生成に失敗した
+    Failed,
+}
+
+/// FFmpeg/ffprobeの補助処理（メタデータ取得・クロップ検出・サムネイル生成・エンコーダーテスト）の
+/// 同時実行数を制限するプール
+/// 変換中に大量のファイルを追加してもプロセスが増えすぎないようにする
+#[derive(Clone)]
+pub struct WorkerPool {
+    /// 同時に実行できる数
+    size: usize,
+    /// 実行枠
+    slots: Arc<smol::lock::Semaphore>,
+    /// 空きを待っている処理の数
+    queued: Arc<AtomicUsize>,
+    /// 実行中の処理の数
+    active: Arc<AtomicUsize>,
+    /// ファイルごとの中断トークン
+    tokens: Arc<Mutex<HashMap<PathBuf, CancelToken>>>,
+}
+
+impl WorkerPool {
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1);
+        Self {
+            size,
+            slots: Arc::new(smol::lock::Semaphore::new(size)),
+            queued: Arc::new(AtomicUsize::new(0)),
+            active: Arc::new(AtomicUsize::new(0)),
+            tokens: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// CPUコア数の半分（最大4）で作成
+    pub fn with_default_size() -> Self {
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(2);
+        Self::new((cores / 2).min(MAX_AUX_WORKERS))
+    }
+
+    /// 同時に実行できる数
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// 空きを待ってから処理をバックグラウンドスレッドで実行
+    /// 開始前に中断された場合はNone
+    pub async fn run<T, F>(&self, token: &CancelToken, f: F) -> Option<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        if token.is_cancelled() {
+            return None;
+        }
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        let _slot = self.slots.acquire().await;
+        self.queued.fetch_sub(1, Ordering::Relaxed);
+        if token.is_cancelled() {
+            return None;
+        }
+
+        let active = self.active.fetch_add(1, Ordering::Relaxed) + 1;
+        log::debug!(
+            "Worker pool: {} active, {} queued",
+            active,
+            self.queued.load(Ordering::Relaxed)
+        );
+        let result = smol::unblock(f).await;
+        self.active.fetch_sub(1, Ordering::Relaxed);
+        Some(result)
+    }
+
+    /// ファイルの処理に使う中断トークンを取得
+    pub fn token(&self, path: &Path) -> CancelToken {
+        let Ok(mut tokens) = self.tokens.lock() else {
+            return CancelToken::default();
+        };
+        tokens.entry(path.to_path_buf()).or_default().clone()
+    }
+
+    /// キューに残っていないファイルの処理を中断
+    pub fn cancel_removed(&self, queued: &HashSet<PathBuf>) {
+        let Ok(mut tokens) = self.tokens.lock() else {
+            return;
+        };
+        let mut cancelled = 0;
+        tokens.retain(|path, token| {
+            let keep = queued.contains(path);
+            if !keep {
+                token.cancel();
+                cancelled += 1;
+            }
+            keep
+        });
+        if cancelled > 0 {
+            log::info!(
+                "Cancelled background work for {} files ({} active, {} queued)",
+                cancelled,
+                self.active.load(Ordering::Relaxed),
+                self.queued.load(Ordering::Relaxed)
+            );
+        }
+    }
+
+    /// すべてのファイルの処理を中断
+    pub fn cancel_all(&self) {
+        self.cancel_removed(&HashSet::new());
+    }
 }
 
 /// アプリケーションのグローバル状態
@@ -168,6 +465,16 @@ pub struct AppState {
     pub ffmpeg_info: Entity<Option<FfmpegInfo>>,
     /// 現在の進捗情報（スレッド間共有）
     pub current_progress: CurrentProgress,
+    /// サムネイル（未登録のファイルは生成中）
+    pub thumbnails: Entity<HashMap<PathBuf, Thumbnail>>,
+    /// サムネイルのディスクキャッシュ（開けなければ毎回生成）
+    pub thumbnail_cache: Option<ThumbnailCache>,
+    /// FFmpeg/ffprobeの補助処理用のプール
+    pub workers: WorkerPool,
+    /// 変換結果から学習した予測サイズの補正
+    pub calibration: Entity<SizeCalibration>,
+    /// 前回の自動保存以降にキューが変更されたか
+    queue_dirty: Arc<AtomicBool>,
 }
 
 impl AppState {
@@ -175,82 +482,468 @@ impl AppState {
         // 設定をロード
         let settings = Settings::load().unwrap_or_default();
 
-        // FFmpegを検出
-        let ffmpeg_info = FfmpegDetector::detect().ok();
+        // FFmpegを検出（ユーザー指定のパスを優先）
+        let ffmpeg_info =
+            FfmpegDetector::detect_with_custom_path(settings.ffmpeg_custom_path.as_ref()).ok();
+
+        // 予測サイズの補正（読み込めなければ補正なしで始める）
+        let calibration = SizeCalibration::open().unwrap_or_else(|e| {
+            log::warn!("Failed to load size calibration: {}", e);
+            SizeCalibration::default()
+        });
+
+        let thumbnail_cache = ThumbnailCache::open()
+            .map_err(|e| log::warn!("Failed to open thumbnail cache: {}", e))
+            .ok();
+
+        // 上書きポリシーは前回の選択を引き継ぐ
+        let transcode_settings = TranscodeSettings {
+            overwrite_policy: settings.overwrite_policy,
+            ..Default::default()
+        };
 
         Self {
             files: cx.new(|_| Vec::new()),
-            transcode_settings: cx.new(|_| TranscodeSettings::default()),
+            transcode_settings: cx.new(|_| transcode_settings),
             current_job: cx.new(|_| None),
             settings: cx.new(|_| settings),
             ffmpeg_path: cx.new(|_| None),
             ffmpeg_info: cx.new(|_| ffmpeg_info),
             current_progress: CurrentProgress::default(),
+            thumbnails: cx.new(|_| HashMap::new()),
+            thumbnail_cache,
+            workers: WorkerPool::with_default_size(),
+            calibration: cx.new(|_| calibration),
+            queue_dirty: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// キューを更新し、自動保存の対象にする
+    pub fn update_files<C: AppContext, R>(
+        &self,
+        cx: &mut C,
+        update: impl FnOnce(&mut Vec<FileEntry>, &mut Context<Vec<FileEntry>>) -> R,
+    ) -> C::Result<R> {
+        self.queue_dirty.store(true, Ordering::Release);
+        self.files.update(cx, update)
+    }
+
+    /// 前回の確認以降にキューが変更されたか（確認すると未変更に戻る）
+    pub fn take_queue_dirty(&self) -> bool {
+        self.queue_dirty.swap(false, Ordering::AcqRel)
+    }
+
     /// ファイルをキューに追加
-    pub fn add_files(&self, paths: Vec<PathBuf>, cx: &mut App) {
+    /// メタデータはバックグラウンドで取得し、取得できたものから表示に反映する
+    /// 既にキューにあるファイルは追加せず、その件数を返す
+    pub fn add_files(&self, paths: Vec<PathBuf>, cx: &mut App) -> usize {
         let settings = self.transcode_settings.read(cx).clone();
-        let ffmpeg_info = self.ffmpeg_info.read(cx).clone();
-        log::info!(
-            "Adding {} files, ffmpeg_info available: {}",
-            paths.len(),
-            ffmpeg_info.is_some()
-        );
-        self.files.update(cx, |files, _| {
+        let calibration = self.calibration.read(cx).clone();
+        let can_probe = self.ffmpeg_info.read(cx).is_some();
+        if !can_probe {
+            log::warn!("ffmpeg_info not available, skipping probe");
+        }
+        let mut added = Vec::new();
+        let mut skipped = 0;
+        self.update_files(cx, |files, cx| {
+            // 一覧の行や選択はパスで識別するため、同じファイルは二重に追加しない
+            let canonical =
+                |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            let mut queued: HashSet<PathBuf> = files.iter().map(|f| canonical(&f.path)).collect();
             for path in paths {
+                if !queued.insert(canonical(&path)) {
+                    log::info!("Skipping already queued file: {:?}", path);
+                    skipped += 1;
+                    continue;
+                }
                 if Self::is_supported_format(&path) {
-                    let mut entry = FileEntry::new(path.clone());
-                    // ffprobeでメタデータを取得
-                    if let Some(ref info) = ffmpeg_info {
-                        entry.probe_metadata(info);
-                        log::info!(
-                            "Probed {}: duration={:?}",
-                            entry.name,
-                            entry.metadata.duration
-                        );
-                    } else {
-                        log::warn!(
-                            "ffmpeg_info not available, skipping probe for {}",
-                            entry.name
-                        );
-                    }
-                    entry.update_estimated_size(&settings);
+                    let mut entry = FileEntry::new(path);
+                    entry.probing = can_probe;
+                    entry.update_estimated_size(&settings, &calibration);
+                    added.push(entry.path.clone());
                     files.push(entry);
                 }
             }
+            cx.notify();
         });
+        log::info!("Added {} files", added.len());
+        self.probe_files(added, cx);
+        skipped
     }
 
-    /// ファイルをキューから削除
-    pub fn remove_file(&self, index: usize, cx: &mut App) {
-        self.files.update(cx, |files, _| {
-            if index < files.len() {
-                files.remove(index);
+    /// 前回のキューを復元
+    /// 見つからなくなったファイルはエラーのまま残し、それ以外はメタデータを取り直す
+    pub fn restore_files(&self, entries: Vec<FileEntry>, cx: &mut App) {
+        let settings = self.transcode_settings.read(cx).clone();
+        let calibration = self.calibration.read(cx).clone();
+        let can_probe = self.ffmpeg_info.read(cx).is_some();
+        let mut restored = Vec::new();
+        self.update_files(cx, |files, cx| {
+            for mut entry in entries {
+                // 復元前に追加済みのファイルは重複させない
+                if files.iter().any(|f| f.path == entry.path) {
+                    continue;
+                }
+                if entry.path.exists() {
+                    entry.probing = can_probe;
+                    entry.update_estimated_size(&settings, &calibration);
+                    restored.push(entry.path.clone());
+                }
+                files.push(entry);
             }
+            cx.notify();
+        });
+        log::info!("Restored {} files from the previous queue", restored.len());
+        self.probe_files(restored, cx);
+    }
+
+    /// メタデータをバックグラウンドで取得し、取得できたものから順にサムネイルを生成する
+    /// 全件終わったらクロップ検出を行う
+    /// 取得中に行が削除・並べ替えされることがあるため、結果はパスで対応付ける
+    fn probe_files(&self, targets: Vec<PathBuf>, cx: &mut App) {
+        let Some(ffmpeg_info) = self.ffmpeg_info.read(cx).clone() else {
+            return;
+        };
+        if targets.is_empty() {
+            return;
+        }
+
+        let pending = Rc::new(RefCell::new(VecDeque::from(targets.clone())));
+        let workers = self.workers.size().min(targets.len());
+        let running = Rc::new(Cell::new(workers));
+        let targets = Rc::new(targets);
+        for _ in 0..workers {
+            let state = self.clone();
+            let ffmpeg_info = ffmpeg_info.clone();
+            let pending = pending.clone();
+            let running = running.clone();
+            let targets = targets.clone();
+            cx.spawn(async move |cx| {
+                loop {
+                    let Some(path) = pending.borrow_mut().pop_front() else {
+                        break;
+                    };
+                    // 取得待ちの間に削除されたファイルは飛ばす
+                    let queued = cx
+                        .update(|cx| state.files.read(cx).iter().any(|f| f.path == path))
+                        .unwrap_or(false);
+                    if !queued {
+                        continue;
+                    }
+
+                    // ffprobeはワーカーで実行し、キューは借用しない（変換中の追加・削除を妨げない）
+                    let token = state.workers.token(&path);
+                    let info = ffmpeg_info.with_cancel(token.flag());
+                    let probe_path = path.clone();
+                    let probed = state
+                        .workers
+                        .run(&token, move || info.probe_video(&probe_path))
+                        .await;
+                    // 取得中にキューから削除された
+                    let Some(probed) = probed.filter(|_| !token.is_cancelled()) else {
+                        continue;
+                    };
+                    match &probed {
+                        Ok(probe) => log::info!("Probed {:?}: duration={:?}", path, probe.duration),
+                        Err(e) => log::warn!("Failed to probe {:?}: {:#}", path, e),
+                    }
+                    let duration = probed.as_ref().ok().map(|probe| probe.duration);
+
+                    cx.update(|cx| {
+                        let settings = state.transcode_settings.read(cx).clone();
+                        let calibration = state.calibration.read(cx).clone();
+                        state.update_files(cx, |files, cx| {
+                            if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                                if let Ok(probe) = probed {
+                                    f.apply_probe(probe);
+                                }
+                                f.probing = false;
+                                f.update_estimated_size(&settings, &calibration);
+                            }
+                            cx.notify();
+                        });
+                        // 読み込めないファイルはFFmpegを起動せずに失敗として表示
+                        match duration {
+                            Some(duration) => {
+                                state.generate_thumbnails(vec![(path.clone(), duration)], cx)
+                            }
+                            None => state.thumbnails.update(cx, |thumbnails, cx| {
+                                thumbnails.insert(path.clone(), Thumbnail::Failed);
+                                cx.notify();
+                            }),
+                        }
+                    })
+                    .ok();
+                }
+
+                // 最後に終わったワーカーが後続の処理を始める
+                running.set(running.get() - 1);
+                if running.get() == 0 {
+                    cx.update(|cx| state.after_probe(&targets, cx)).ok();
+                }
+            })
+            .detach();
+        }
+    }
+
+    /// メタデータの取得後に、長さや解像度が必要な処理を行う
+    fn after_probe(&self, targets: &[PathBuf], cx: &mut App) {
+        if self.transcode_settings.read(cx).crop_mode == CropMode::AutoDetect {
+            self.detect_crops(targets.to_vec(), cx);
+        }
+    }
+
+    /// 黒帯をバックグラウンドで1件ずつ検出してファイルに設定
+    pub fn detect_crops(&self, targets: Vec<PathBuf>, cx: &mut App) {
+        let Some(ffmpeg_info) = self.ffmpeg_info.read(cx).clone() else {
+            return;
+        };
+        if targets.is_empty() {
+            return;
+        }
+
+        let state = self.clone();
+        let workers = self.workers.clone();
+        cx.spawn(async move |cx| {
+            for path in targets {
+                // 検出待ちの間に削除されたファイルは飛ばす
+                let Ok(Some(entry)) = cx.update(|cx| {
+                    state
+                        .files
+                        .read(cx)
+                        .iter()
+                        .find(|f| f.path == path)
+                        .cloned()
+                }) else {
+                    continue;
+                };
+
+                let token = workers.token(&path);
+                let info = ffmpeg_info.with_cancel(token.flag());
+                let Some(result) = workers
+                    .run(&token, move || {
+                        let mut entry = entry;
+                        entry.detect_crop(&info)
+                    })
+                    .await
+                else {
+                    continue;
+                };
+
+                match result {
+                    Err(_) if token.is_cancelled() => {}
+                    Ok(crop) => {
+                        log::info!("Detected crop for {:?}: {}", path, crop.display());
+                        cx.update(|cx| {
+                            state.update_files(cx, |files, cx| {
+                                if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                                    f.crop = Some(crop);
+                                }
+                                cx.notify();
+                            });
+                        })
+                        .ok();
+                    }
+                    Err(e) => log::warn!("Crop detection failed for {:?}: {:#}", path, e),
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// サムネイルをバックグラウンドで1件ずつ生成（ディスクキャッシュにあれば読み込むだけ）
+    fn generate_thumbnails(&self, targets: Vec<(PathBuf, Option<f64>)>, cx: &mut App) {
+        let Some(ffmpeg_info) = self.ffmpeg_info.read(cx).clone() else {
+            return;
+        };
+        if targets.is_empty() {
+            return;
+        }
+
+        let thumbnails = self.thumbnails.clone();
+        let cache = self.thumbnail_cache.clone();
+        let files = self.files.clone();
+        let workers = self.workers.clone();
+        cx.spawn(async move |cx| {
+            // 順番に処理して変換処理やUIへの負荷を抑える
+            for (path, duration) in targets {
+                // 生成待ちの間に削除されたファイルは飛ばす
+                let still_queued = cx
+                    .update(|cx| files.read(cx).iter().any(|f| f.path == path))
+                    .unwrap_or(false);
+                if !still_queued {
+                    continue;
+                }
+
+                // 冒頭のタイトルや黒画面を避けて、長さの10%の位置から取得
+                let at_secs = duration.map(|d| d * 0.1).unwrap_or(0.0);
+                let token = workers.token(&path);
+                let info = ffmpeg_info.with_cancel(token.flag());
+                let cache = cache.clone();
+                let input = path.clone();
+                let Some(result) = workers
+                    .run(&token, move || {
+                        load_or_generate_thumbnail(&info, cache.as_ref(), &input, at_secs)
+                    })
+                    .await
+                else {
+                    continue;
+                };
+
+                let thumbnail = match result {
+                    Err(_) if token.is_cancelled() => continue,
+                    Ok(bytes) => {
+                        Thumbnail::Ready(Arc::new(Image::from_bytes(ImageFormat::Png, bytes)))
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to generate thumbnail for {:?}: {:#}", path, e);
+                        Thumbnail::Failed
+                    }
+                };
+                cx.update(|cx| {
+                    thumbnails.update(cx, |thumbnails, cx| {
+                        thumbnails.insert(path, thumbnail);
+                        cx.notify();
+                    });
+                })
+                .ok();
+            }
+        })
+        .detach();
+    }
+
+    /// FFmpegを設定し、メタデータ未取得のファイルを再プローブ
+    pub fn set_ffmpeg(&self, info: FfmpegInfo, cx: &mut App) {
+        self.ffmpeg_path.update(cx, |path, _| {
+            *path = Some(info.ffmpeg_path.clone());
+        });
+        self.ffmpeg_info.update(cx, |ffmpeg_info, _| {
+            *ffmpeg_info = Some(info);
+        });
+
+        let mut unprobed = Vec::new();
+        self.update_files(cx, |files, cx| {
+            for file in files.iter_mut() {
+                if file.metadata.duration.is_none() {
+                    file.probing = true;
+                    unprobed.push(file.path.clone());
+                }
+            }
+            cx.notify();
+        });
+
+        // 取得済みのファイルはすぐにサムネイルを生成（未取得のものは取得後に生成）
+        // 以前のFFmpegで失敗したものも作り直す
+        let cached = self.thumbnails.read(cx);
+        let targets = self
+            .files
+            .read(cx)
+            .iter()
+            .filter(|f| !f.probing && !matches!(cached.get(&f.path), Some(Thumbnail::Ready(_))))
+            .map(|f| (f.path.clone(), f.metadata.duration))
+            .collect();
+        self.generate_thumbnails(targets, cx);
+        self.probe_files(unprobed, cx);
+    }
+
+    /// 指定したファイルが既にキューにあるか（正規化したパスで比較）
+    pub fn is_queued(&self, path: &Path, cx: &App) -> bool {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.files
+            .read(cx)
+            .iter()
+            .any(|f| f.path.canonicalize().unwrap_or_else(|_| f.path.clone()) == canonical)
+    }
+
+    /// 複数のファイルをキューから削除
+    pub fn remove_files(&self, indices: &HashSet<usize>, cx: &mut App) {
+        self.update_files(cx, |files, _| {
+            let mut index = 0;
+            files.retain(|_| {
+                let keep = !indices.contains(&index);
+                index += 1;
+                keep
+            });
+        });
+        self.prune_removed(cx);
+    }
+
+    /// 変換が完了したファイルをキューから削除
+    pub fn remove_completed(&self, cx: &mut App) {
+        self.update_files(cx, |files, _| {
+            files.retain(|f| !f.status.is_completed());
+        });
+        self.prune_removed(cx);
+    }
+
+    /// キューに残っていないファイルのサムネイルを破棄し、バックグラウンド処理を中断
+    fn prune_removed(&self, cx: &mut App) {
+        let queued: HashSet<PathBuf> = self.files.read(cx).iter().map(|f| f.path.clone()).collect();
+        self.workers.cancel_removed(&queued);
+        self.thumbnails.update(cx, |thumbnails, _| {
+            thumbnails.retain(|path, _| queued.contains(path));
+        });
+    }
+
+    /// キューを並べ替え（変換中はインデックスがずれるため呼ばないこと）
+    pub fn sort_files(&self, key: FileSortKey, descending: bool, cx: &mut App) {
+        self.update_files(cx, |files, _| {
+            files.sort_by(|a, b| {
+                let ordering = key.compare(a, b);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
         });
     }
 
     /// キューをクリア
     pub fn clear_files(&self, cx: &mut App) {
-        self.files.update(cx, |files, _| {
+        self.update_files(cx, |files, _| {
             files.clear();
         });
+        self.workers.cancel_all();
+        self.thumbnails.update(cx, |thumbnails, _| {
+            thumbnails.clear();
+        });
     }
 
     /// すべてのファイルの予測サイズを更新
     pub fn update_all_estimated_sizes(&self, cx: &mut App) {
         let settings = self.transcode_settings.read(cx).clone();
-        self.files.update(cx, |files, _| {
+        let calibration = self.calibration.read(cx).clone();
+        self.update_files(cx, |files, _| {
             for file in files.iter_mut() {
-                file.update_estimated_size(&settings);
+                file.update_estimated_size(&settings, &calibration);
+            }
+        });
+    }
+
+    /// コンテンツタイプを設定して予測サイズを更新（パスを指定しなければすべてのファイル）
+    pub fn set_content_type(
+        &self,
+        paths: Option<&HashSet<PathBuf>>,
+        content_type: ContentType,
+        cx: &mut App,
+    ) {
+        let settings = self.transcode_settings.read(cx).clone();
+        let calibration = self.calibration.read(cx).clone();
+        self.update_files(cx, |files, cx| {
+            for file in files
+                .iter_mut()
+                .filter(|f| paths.is_none_or(|paths| paths.contains(&f.path)))
+            {
+                file.set_content_type(content_type);
+                file.update_estimated_size(&settings, &calibration);
             }
+            cx.notify();
         });
     }
 
     /// サポートされている入力形式かチェック
-    fn is_supported_format(path: &PathBuf) -> bool {
+    pub fn is_supported_format(path: &Path) -> bool {
         const SUPPORTED_EXTENSIONS: &[&str] = &[
             "mp4", "mkv", "avi", "mov", "webm", "flv", "wmv", "m4v", "ts",
         ];
@@ -262,10 +955,35 @@ impl AppState {
     }
 }
 
+/// キャッシュ済みのサムネイルを読み込み、なければFFmpegで生成して保存
+fn load_or_generate_thumbnail(
+    ffmpeg_info: &FfmpegInfo,
+    cache: Option<&ThumbnailCache>,
+    path: &Path,
+    at_secs: f64,
+) -> anyhow::Result<Vec<u8>> {
+    if let Some(bytes) = cache.and_then(|cache| cache.load(path)) {
+        return Ok(bytes);
+    }
+    let bytes = ffmpeg_info.generate_thumbnail(path, at_secs)?;
+    if let Some(cache) = cache {
+        // 保存できなくても表示には使う
+        if let Err(e) = cache.store(path, &bytes) {
+            log::warn!("Failed to cache thumbnail for {:?}: {}", path, e);
+        }
+    }
+    Ok(bytes)
+}
+
 /// ファイルエントリ
-#[derive(Clone, Debug)]
+/// 変換結果のレポート（JSON）としても書き出すため、表示用の状態は含めない
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FileEntry {
     /// ファイルパス
+    #[serde(
+        serialize_with = "serialize_path",
+        deserialize_with = "deserialize_path"
+    )]
     pub path: PathBuf,
     /// ファイル名
     pub name: String,
@@ -274,11 +992,81 @@ pub struct FileEntry {
     /// 処理状態
     pub status: FileStatus,
     /// 進捗（0.0 - 1.0）
+    #[serde(skip)]
     pub progress: f32,
     /// 予測出力サイズ（バイト）
     pub estimated_size: Option<u64>,
     /// 動画メタデータ
     pub metadata: VideoMetadata,
+    /// 出力先に関する補足（別名保存・上書きなど）
+    pub note: Option<String>,
+    /// 自動検出したクロップ（Noneなら設定値を使用）
+    pub crop: Option<CropSettings>,
+    /// 変換に失敗したときのFFmpegのstderr
+    #[serde(skip)]
+    pub log: Option<String>,
+    /// 変換後の画質評価の結果
+    pub quality: Option<QualityScore>,
+    /// 変換後の出力ファイル
+    #[serde(
+        default,
+        serialize_with = "serialize_optional_path",
+        deserialize_with = "deserialize_optional_path"
+    )]
+    pub output_path: Option<PathBuf>,
+    /// 変換後の出力サイズ（バイト）
+    pub output_size: Option<u64>,
+    /// このファイルに固定した設定（Noneなら全体の設定に従う）
+    pub settings_override: Option<TranscodeSettings>,
+    /// メタデータをバックグラウンドで取得中
+    #[serde(skip)]
+    pub probing: bool,
+}
+
+/// パスを書き出す
+/// UTF-8のパスはレポートで読めるよう文字列に、それ以外はOsStrとして失わずに書き出す
+fn serialize_path<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    match path.to_str() {
+        Some(text) => serializer.serialize_str(text),
+        None => path.as_os_str().serialize(serializer),
+    }
+}
+
+fn serialize_optional_path<S: Serializer>(
+    path: &Option<PathBuf>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match path {
+        Some(path) => serialize_path(path, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// 書き出したパス（文字列またはOsStr）
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredPath {
+    Text(String),
+    Raw(OsString),
+}
+
+impl From<StoredPath> for PathBuf {
+    fn from(path: StoredPath) -> Self {
+        match path {
+            StoredPath::Text(text) => PathBuf::from(text),
+            StoredPath::Raw(raw) => PathBuf::from(raw),
+        }
+    }
+}
+
+fn deserialize_path<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    StoredPath::deserialize(deserializer).map(PathBuf::from)
+}
+
+fn deserialize_optional_path<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<PathBuf>, D::Error> {
+    Option::<StoredPath>::deserialize(deserializer).map(|path| path.map(PathBuf::from))
 }
 
 impl FileEntry {
@@ -290,6 +1078,7 @@ impl FileEntry {
             .to_string();
 
         let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let content_type = ContentType::guess_from_file_name(&name);
 
         Self {
             path,
@@ -298,45 +1087,118 @@ impl FileEntry {
             status: FileStatus::Pending,
             progress: 0.0,
             estimated_size: None,
-            metadata: VideoMetadata::default(),
+            metadata: VideoMetadata {
+                content_type,
+                ..Default::default()
+            },
+            note: None,
+            crop: None,
+            log: None,
+            quality: None,
+            output_path: None,
+            output_size: None,
+            settings_override: None,
+            probing: false,
         }
     }
 
+    /// cropdetectで黒帯を検出
+    pub fn detect_crop(&mut self, ffmpeg_info: &FfmpegInfo) -> anyhow::Result<CropSettings> {
+        // 各辺の値を求めるためにソース解像度が必要
+        if self.metadata.resolution.is_none() {
+            self.probe_metadata(ffmpeg_info);
+        }
+        let source = self
+            .metadata
+            .resolution
+            .ok_or_else(|| anyhow::anyhow!("resolution unknown"))?;
+        // 冒頭の黒画面を避けるため少し先から解析する
+        let at_secs = self.metadata.duration.map(|d| d / 10.0).unwrap_or(0.0);
+        let area = ffmpeg_info.detect_crop(&self.path, at_secs)?;
+        Ok(CropSettings::from_area(area, source))
+    }
+
     /// ffprobeでメタデータを取得
     pub fn probe_metadata(&mut self, ffmpeg_info: &FfmpegInfo) {
         if let Ok(probe) = ffmpeg_info.probe_video(&self.path) {
-            // 解像度
-            if let Some((w, h)) = probe.resolution {
-                self.metadata.resolution = Some((w, h));
-            }
-            // フレームレート
-            if let Some(fps) = probe.fps {
-                self.metadata.fps = Some(fps);
-            }
-            // 動画の長さ
-            if let Some(duration) = probe.duration {
-                self.metadata.duration = Some(duration);
-            }
-            // ビットレート
-            if let Some(video_br) = probe.video_bitrate {
-                self.metadata.source_video_bitrate = Some(video_br);
-            }
-            if let Some(audio_br) = probe.audio_bitrate {
-                self.metadata.source_audio_bitrate = Some(audio_br);
-            }
-            if let Some(overall_br) = probe.overall_bitrate {
-                self.metadata.source_overall_bitrate = Some(overall_br);
-            }
+            self.apply_probe(probe);
+        }
+    }
 
-            log::debug!(
-                "Probed {}: resolution={:?}, fps={:?}, duration={:?}, video_br={:?}, audio_br={:?}",
-                self.name,
-                self.metadata.resolution,
-                self.metadata.fps,
-                self.metadata.duration,
-                self.metadata.source_video_bitrate,
-                self.metadata.source_audio_bitrate
-            );
+    /// ffprobeの結果をメタデータに反映（取得できなかった項目とコンテンツタイプは元のまま）
+    /// ffprobeの実行はバックグラウンドで行い、結果の反映だけをUIスレッドで行えるよう分けている
+    pub fn apply_probe(&mut self, probe: ProbeResult) {
+        // 解像度
+        if let Some((w, h)) = probe.resolution {
+            self.metadata.resolution = Some((w, h));
+        }
+        // フレームレート
+        if let Some(fps) = probe.fps {
+            self.metadata.fps = Some(fps);
+        }
+        // 動画の長さ
+        if let Some(duration) = probe.duration {
+            self.metadata.duration = Some(duration);
+        }
+        // ビットレート
+        if let Some(video_br) = probe.video_bitrate {
+            self.metadata.source_video_bitrate = Some(video_br);
+        }
+        if let Some(audio_br) = probe.audio_bitrate {
+            self.metadata.source_audio_bitrate = Some(audio_br);
+        }
+        if let Some(overall_br) = probe.overall_bitrate {
+            self.metadata.source_overall_bitrate = Some(overall_br);
+        }
+        // コーデック・ビット深度
+        self.metadata.video_codec = probe.video_codec;
+        self.metadata.audio_codec = probe.audio_codec;
+        self.metadata.video_bit_depth = probe.video_bit_depth;
+        self.metadata.audio_bit_depth = probe.audio_bit_depth;
+        self.metadata.source_audio_channels = probe.audio_channels;
+        // HDR・色情報
+        self.metadata.pix_fmt = probe.pix_fmt;
+        self.metadata.color_primaries = probe.color_primaries;
+        self.metadata.color_trc = probe.color_trc;
+        self.metadata.color_space = probe.color_space;
+        self.metadata.field_order = probe.field_order;
+        self.metadata.master_display = probe.master_display;
+        self.metadata.max_cll = probe.max_cll;
+        self.metadata.chapter_count = probe.chapter_count;
+
+        log::debug!(
+            "Probed {}: resolution={:?}, fps={:?}, duration={:?}, video_br={:?}, audio_br={:?}",
+            self.name,
+            self.metadata.resolution,
+            self.metadata.fps,
+            self.metadata.duration,
+            self.metadata.source_video_bitrate,
+            self.metadata.source_audio_bitrate
+        );
+    }
+
+    /// メタデータを取得済みか
+    pub fn is_probed(&self) -> bool {
+        self.metadata.duration.is_some() || self.metadata.resolution.is_some()
+    }
+
+    /// FFmpegのログを保存（上限を超えた場合は中間を省略）
+    pub fn set_log(&mut self, stderr: &str) {
+        self.log = Some(truncate_log(stderr, MAX_ENTRY_LOG_BYTES));
+    }
+
+    /// 総フレーム数（長さ × フレームレート）
+    pub fn frame_count(&self) -> Option<u64> {
+        let duration = self.metadata.duration?;
+        let fps = self.metadata.fps?;
+        Some((duration * fps).round() as u64)
+    }
+
+    /// 予測圧縮率（予測サイズ / 元サイズ）
+    pub fn estimated_ratio(&self) -> Option<f64> {
+        match self.estimated_size {
+            Some(estimated) if self.size > 0 => Some(estimated as f64 / self.size as f64),
+            _ => None,
         }
     }
 
@@ -345,23 +1207,36 @@ impl FileEntry {
         self.metadata.content_type = content_type;
     }
 
-    /// フレームレートを設定
-    pub fn set_fps(&mut self, fps: f64) {
-        self.metadata.fps = Some(fps);
+    /// このファイルに適用する設定（固定した設定があればそれを使う）
+    pub fn effective_settings<'a>(
+        &'a self,
+        settings: &'a TranscodeSettings,
+    ) -> &'a TranscodeSettings {
+        self.settings_override.as_ref().unwrap_or(settings)
     }
 
-    /// 解像度を設定
-    pub fn set_resolution(&mut self, width: u32, height: u32) {
-        self.metadata.resolution = Some((width, height));
-    }
-
-    /// 動画の長さを設定
-    pub fn set_duration(&mut self, duration: f64) {
-        self.metadata.duration = Some(duration);
+    /// 予測サイズを更新（同じ種類の変換結果から学習した補正があれば掛ける）
+    /// 設定を固定したファイルは全体の設定を変えても予測が変わらない
+    pub fn update_estimated_size(
+        &mut self,
+        settings: &TranscodeSettings,
+        calibration: &SizeCalibration,
+    ) {
+        let settings = self.effective_settings(settings);
+        let predicted = self.predict_size(settings);
+        // 目標サイズモードは予測から目標に合うCRFを決めているため補正しない
+        let factor = match settings.rate_control {
+            RateControlMode::TargetSize => None,
+            _ => calibration.factor(settings, self.metadata.content_type),
+        };
+        self.estimated_size = Some(match factor {
+            Some(factor) => (predicted as f64 * factor) as u64,
+            None => predicted,
+        });
     }
 
-    /// 予測サイズを計算・更新（高精度版）
-    pub fn update_estimated_size(&mut self, settings: &TranscodeSettings) {
+    /// 補正前の予測モデルによる出力サイズ（バイト）
+    pub fn predict_size(&self, settings: &TranscodeSettings) -> u64 {
         // メタデータが不完全な場合はデフォルト値を使用
         let mut metadata = self.metadata.clone();
         if metadata.resolution.is_none() {
@@ -371,11 +1246,21 @@ impl FileEntry {
             metadata.fps = Some(30.0); // デフォルト30fps
         }
 
-        // 高精度予測モデルを使用
-        let ratio = estimate_compression_ratio_advanced(settings, &metadata);
+        // 高精度予測モデルを使用（目標サイズモードは算出したCRFで予測）
+        let settings = settings.resolve_target_size(self.size, &metadata);
+        let ratio = estimate_compression_ratio_advanced(&settings, &metadata);
 
         // 予測サイズを計算
-        self.estimated_size = Some((self.size as f64 * ratio) as u64);
+        (self.size as f64 * ratio) as u64
+    }
+
+    /// 変換前に対する出力サイズの増減（%、削減なら負の値）
+    pub fn size_change_percent(&self) -> Option<f64> {
+        let output_size = self.output_size?;
+        if self.size == 0 {
+            return None;
+        }
+        Some((output_size as f64 / self.size as f64 - 1.0) * 100.0)
     }
 
     /// ファイルサイズを人間が読める形式にフォーマット
@@ -396,29 +1281,358 @@ impl FileEntry {
     }
 }
 
+/// ログを上限サイズに収める
+/// 入力情報のある先頭とエラーの出る末尾を残し、中間を省略する
+fn truncate_log(log: &str, max_bytes: usize) -> String {
+    if log.len() <= max_bytes {
+        return log.to_string();
+    }
+
+    let mut head_end = max_bytes / 4;
+    while !log.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = log.len() - (max_bytes - max_bytes / 4);
+    while !log.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+
+    format!(
+        "{}\n... （{} バイト省略） ...\n{}",
+        &log[..head_end],
+        tail_start - head_end,
+        &log[tail_start..]
+    )
+}
+
 /// ファイル処理状態
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum FileStatus {
     /// 待機中
     Pending,
     /// 処理中
     Processing,
     /// 完了
-    Completed,
+    Completed {
+        /// VMAFスコア（評価しなかった場合やSSIMで評価した場合はNone）
+        vmaf: Option<f32>,
+        /// 元のサイズからの削減率（%、大きくなった場合は負の値）
+        size_reduction: f32,
+    },
     /// エラー
     Error(String),
     /// キャンセル
     Cancelled,
+    /// スキップ（理由）
+    Skipped(String),
 }
 
 impl FileStatus {
+    /// 変換結果から完了状態を作成
+    pub fn completed(
+        quality: Option<QualityScore>,
+        input_size: u64,
+        output_size: Option<u64>,
+    ) -> Self {
+        let size_reduction = match output_size {
+            Some(output_size) if input_size > 0 => {
+                (1.0 - output_size as f64 / input_size as f64) * 100.0
+            }
+            _ => 0.0,
+        };
+        FileStatus::Completed {
+            vmaf: quality.and_then(|q| q.vmaf()).map(|score| score as f32),
+            size_reduction: size_reduction as f32,
+        }
+    }
+
+    /// 完了したか
+    pub fn is_completed(&self) -> bool {
+        matches!(self, FileStatus::Completed { .. })
+    }
+
+    /// 完了時のVMAFスコアとサイズの増減（例: "VMAF 94.2 | -60%"）
+    pub fn completion_summary(&self) -> Option<String> {
+        let FileStatus::Completed {
+            vmaf,
+            size_reduction,
+        } = self
+        else {
+            return None;
+        };
+        // 削減率0%を"-0%"と表示しないよう、符号反転ではなく0から引く
+        let change = format!("{:+.0}%", 0.0 - size_reduction);
+        Some(match vmaf {
+            Some(vmaf) => format!("VMAF {:.1} | {}", vmaf, change),
+            None => change,
+        })
+    }
+
     pub fn label(&self) -> &str {
         match self {
             FileStatus::Pending => "待機中",
             FileStatus::Processing => "処理中",
-            FileStatus::Completed => "完了",
+            FileStatus::Completed { .. } => "完了",
             FileStatus::Error(_) => "エラー",
             FileStatus::Cancelled => "キャンセル",
+            FileStatus::Skipped(_) => "スキップ",
         }
     }
+
+    /// 並べ替え時の順序（未処理のものを先に）
+    fn sort_rank(&self) -> u8 {
+        match self {
+            FileStatus::Processing => 0,
+            FileStatus::Pending => 1,
+            FileStatus::Error(_) => 2,
+            FileStatus::Cancelled => 3,
+            FileStatus::Skipped(_) => 4,
+            FileStatus::Completed { .. } => 5,
+        }
+    }
+}
+
+/// ファイルリストの並べ替えキー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileSortKey {
+    /// ファイル名
+    Name,
+    /// ファイルサイズ
+    Size,
+    /// 動画の長さ
+    Duration,
+    /// 処理状態
+    Status,
+}
+
+impl FileSortKey {
+    /// 列ヘッダーの表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileSortKey::Name => "ファイル名",
+            FileSortKey::Size => "サイズ",
+            FileSortKey::Duration => "長さ",
+            FileSortKey::Status => "状態",
+        }
+    }
+
+    /// 昇順で比較（長さ不明のファイルは後ろ、同じ値はファイル名順）
+    pub fn compare(&self, a: &FileEntry, b: &FileEntry) -> std::cmp::Ordering {
+        let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        match self {
+            FileSortKey::Name => by_name(),
+            FileSortKey::Size => a.size.cmp(&b.size).then_with(by_name),
+            FileSortKey::Duration => match (a.metadata.duration, b.metadata.duration) {
+                (Some(x), Some(y)) => x.total_cmp(&y).then_with(by_name),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => by_name(),
+            },
+            FileSortKey::Status => a
+                .status
+                .sort_rank()
+                .cmp(&b.status.sort_rank())
+                .then_with(by_name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // gpui::*にはtest属性マクロが含まれるため、super::*ではなく個別に読み込む
+    use super::{
+        truncate_log, CurrentProgress, FileEntry, FileSortKey, FileStatus, SizeCalibration,
+        TranscodeSettings, WorkerPool, MAX_LOG_LINES,
+    };
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_worker_pool_cancel() {
+        let pool = WorkerPool::new(1);
+        let path = PathBuf::from("/videos/a.mkv");
+        let token = pool.token(&path);
+        assert_eq!(smol::block_on(pool.run(&token, || 1)), Some(1));
+        // 同じファイルには同じトークンを使う
+        assert!(!pool.token(&path).is_cancelled());
+
+        // キューから消えたファイルの処理は開始しない
+        pool.cancel_removed(&HashSet::from([PathBuf::from("/videos/b.mkv")]));
+        assert!(token.is_cancelled());
+        assert_eq!(smol::block_on(pool.run(&token, || 2)), None);
+
+        // 再追加されたファイルは新しいトークンで処理できる
+        let token = pool.token(&path);
+        assert_eq!(smol::block_on(pool.run(&token, || 3)), Some(3));
+        pool.cancel_all();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn test_log_lines_are_capped() {
+        let progress = CurrentProgress::default();
+        for i in 0..(MAX_LOG_LINES + 20) {
+            progress.push_log(format!("line {}", i));
+        }
+
+        let lines = progress.log_snapshot();
+        assert_eq!(lines.len(), MAX_LOG_LINES);
+        assert_eq!(lines.first().map(String::as_str), Some("line 20"));
+        assert_eq!(
+            lines.last().cloned(),
+            Some(format!("line {}", MAX_LOG_LINES + 19))
+        );
+
+        // リセットしてもログは残る（完了後に確認できるように）
+        progress.reset();
+        assert_eq!(progress.log_snapshot().len(), MAX_LOG_LINES);
+    }
+
+    #[test]
+    fn test_parallel_job_progress() {
+        let progress = CurrentProgress::default();
+        let job = progress.for_parallel_job();
+
+        // ログは共有し、進捗とフラグはジョブごと
+        job.push_log("job");
+        assert_eq!(progress.log_snapshot(), vec!["job".to_string()]);
+        job.set_progress(0.5);
+        job.set_fps(30.0);
+        job.skip_current();
+        assert_eq!(progress.get_progress(), 0.0);
+        assert!(!progress.is_skip_requested());
+
+        progress.mirror(&job);
+        assert_eq!(progress.get_progress(), 0.5);
+        assert_eq!(progress.get_fps(), 30.0);
+        assert!(!progress.is_skip_requested());
+    }
+
+    #[test]
+    fn test_file_sort_keys() {
+        let entry = |name: &str, size: u64, duration: Option<f64>, status: FileStatus| {
+            let mut entry = FileEntry::new(PathBuf::from(name));
+            entry.size = size;
+            entry.metadata.duration = duration;
+            entry.status = status;
+            entry
+        };
+        let mut files = vec![
+            entry("b.mp4", 300, None, FileStatus::completed(None, 300, None)),
+            entry("C.mp4", 100, Some(90.0), FileStatus::Pending),
+            entry("a.mp4", 200, Some(30.0), FileStatus::Error("x".to_string())),
+        ];
+        let names = |files: &[FileEntry]| files.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+
+        files.sort_by(|a, b| FileSortKey::Name.compare(a, b));
+        assert_eq!(names(&files), ["a.mp4", "b.mp4", "C.mp4"]);
+        files.sort_by(|a, b| FileSortKey::Size.compare(a, b));
+        assert_eq!(names(&files), ["C.mp4", "a.mp4", "b.mp4"]);
+        // 長さ不明のファイルは後ろ
+        files.sort_by(|a, b| FileSortKey::Duration.compare(a, b));
+        assert_eq!(names(&files), ["a.mp4", "C.mp4", "b.mp4"]);
+        files.sort_by(|a, b| FileSortKey::Status.compare(a, b));
+        assert_eq!(names(&files), ["C.mp4", "a.mp4", "b.mp4"]);
+    }
+
+    #[test]
+    fn test_truncate_log_keeps_head_and_tail() {
+        assert_eq!(truncate_log("short", 16), "short");
+
+        let log = format!("{}{}{}", "a".repeat(10), "あ".repeat(20), "z".repeat(10));
+        let truncated = truncate_log(&log, 40);
+        assert!(truncated.starts_with("aaaaaaaaaa\n... （"));
+        assert!(truncated.ends_with("ああああああzzzzzzzzzz"));
+        assert!(truncated.contains("バイト省略"));
+        // マルチバイト文字の途中では切らない
+        let tail = truncated.rsplit('\n').next().unwrap();
+        assert!(tail.len() <= 30);
+    }
+
+    #[test]
+    fn test_frame_count() {
+        let mut entry = FileEntry::new(PathBuf::from("a.mp4"));
+        assert_eq!(entry.frame_count(), None);
+        entry.metadata.duration = Some(10.0);
+        entry.metadata.fps = Some(30000.0 / 1001.0);
+        assert_eq!(entry.frame_count(), Some(300));
+    }
+
+    #[test]
+    fn test_settings_override_keeps_estimate() {
+        let mut entry = FileEntry::new(PathBuf::from("a.mp4"));
+        entry.size = 1_000_000_000;
+        entry.metadata.duration = Some(600.0);
+        let calibration = SizeCalibration::default();
+        let low = TranscodeSettings {
+            crf: 35,
+            ..Default::default()
+        };
+        let high = TranscodeSettings {
+            crf: 18,
+            ..Default::default()
+        };
+
+        entry.update_estimated_size(&low, &calibration);
+        let low_estimate = entry.estimated_size;
+        entry.update_estimated_size(&high, &calibration);
+        assert_ne!(entry.estimated_size, low_estimate);
+
+        // 固定した設定で予測し、全体の設定を変えても変わらない
+        entry.settings_override = Some(low.clone());
+        entry.update_estimated_size(&high, &calibration);
+        assert_eq!(entry.estimated_size, low_estimate);
+        assert_eq!(entry.effective_settings(&high).crf, 35);
+    }
+
+    #[test]
+    fn test_size_change_percent() {
+        let mut entry = FileEntry::new(PathBuf::from("a.mp4"));
+        entry.size = 1000;
+        assert_eq!(entry.size_change_percent(), None);
+        entry.output_size = Some(400);
+        assert_eq!(entry.size_change_percent(), Some(-60.0));
+        entry.output_size = Some(1100);
+        assert!((entry.size_change_percent().unwrap() - 10.0).abs() < 1e-9);
+        entry.size = 0;
+        assert_eq!(entry.size_change_percent(), None);
+    }
+
+    #[test]
+    fn test_completed_status_summary() {
+        let status = FileStatus::completed(None, 1000, Some(400));
+        assert_eq!(
+            status,
+            FileStatus::Completed {
+                vmaf: None,
+                size_reduction: 60.0,
+            }
+        );
+        assert!(status.is_completed());
+        assert_eq!(status.completion_summary().as_deref(), Some("-60%"));
+
+        let status = FileStatus::Completed {
+            vmaf: Some(94.21),
+            size_reduction: 60.0,
+        };
+        assert_eq!(
+            status.completion_summary().as_deref(),
+            Some("VMAF 94.2 | -60%")
+        );
+
+        // サイズが増えた場合や元のサイズが不明な場合
+        assert_eq!(
+            FileStatus::completed(None, 1000, Some(1100))
+                .completion_summary()
+                .as_deref(),
+            Some("+10%")
+        );
+        assert_eq!(
+            FileStatus::completed(None, 0, Some(400))
+                .completion_summary()
+                .as_deref(),
+            Some("+0%")
+        );
+        assert_eq!(FileStatus::Pending.completion_summary(), None);
+    }
 }
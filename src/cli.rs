@@ -0,0 +1,770 @@
+//! ヘッドレスモード（GUIを起動せずに一括変換）
+//!
+//! `kamaitachi --cli <入力...> --codec h265 --crf 22 --output-dir D:\out`
+//! `kamaitachi --cli --input in.mkv --output out.mp4 --dry-run`
+//! 変換の手順はGUIと同じく `queue` モジュールを使う
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::app::{AppState, CurrentProgress, FileEntry, FileStatus};
+use crate::config::{import_handbrake_preset, Settings};
+use crate::ffmpeg::FfmpegDetector;
+use crate::history::record_completed;
+use crate::queue::{
+    prepare_job, prepare_job_with_output, run_job, PreparedJob, QueueOptions, SessionSlots,
+    MAX_PARALLEL_JOBS,
+};
+use crate::transcoder::{
+    format_duration, format_size, AudioCodec, ContainerFormat, CropMode, FfmpegErrorKind,
+    HwAccelType, OverwritePolicy, RateControlMode, TranscodeSettings, VideoCodec,
+};
+
+/// 引数の誤りや変換を始められない場合の終了コード
+const EXIT_USAGE: i32 = 2;
+
+/// 変換に失敗したファイルがある場合の終了コード
+const EXIT_FAILED: i32 = 1;
+
+/// 進捗表示の更新間隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 並列変換中の進捗を1行ずつ表示する間隔（行を上書きできないため長めにする）
+const PARALLEL_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+const USAGE: &str = "\
+使い方: kamaitachi --cli <入力...> [オプション]
+
+入力にはファイルまたはフォルダ（直下の動画のみ）を指定します。
+
+オプション:
+  --input <パス>                             入力（複数回指定できます）
+  --output <ファイル>                        出力ファイル（入力が1つの場合のみ）
+  --codec <h264|h265|vp9|av1|copy>           映像コーデック
+  --crf <0-51>                               品質（CRF、x264の尺度。VP9/AV1は換算）
+  --container <mp4|mkv>                      コンテナ形式
+  --hwaccel <auto|nvenc|qsv|amf|software>    HWアクセラレーション
+  --audio-codec <aac|mp3|flac|copy>          音声コーデック
+  --output-dir <フォルダ>                    出力先（省略時は入力と同じフォルダ）
+  --suffix <文字列>                          出力ファイル名のサフィックス
+  --overwrite <overwrite|skip|rename>        同名の出力ファイルがある場合
+  --jobs <1-16>                              同時に変換するファイル数
+  --preset-file, --preset <ファイル>         エンコード設定（JSONまたはHandBrakeプリセット）を読み込む
+  --dry-run                                  変換せずにFFmpegのコマンドを表示
+  -h, --help                                 この説明を表示";
+
+/// コマンドライン引数
+#[derive(Debug, Default, PartialEq)]
+struct CliArgs {
+    /// 入力ファイル・フォルダ
+    inputs: Vec<PathBuf>,
+    /// 出力ファイル（入力が1つの場合のみ）
+    output: Option<PathBuf>,
+    /// 映像コーデック
+    codec: Option<VideoCodec>,
+    /// CRF値
+    crf: Option<u8>,
+    /// コンテナ形式
+    container: Option<ContainerFormat>,
+    /// HWアクセラレーション
+    hwaccel: Option<HwAccelType>,
+    /// 音声コーデック
+    audio_codec: Option<AudioCodec>,
+    /// 出力ディレクトリ
+    output_dir: Option<PathBuf>,
+    /// 出力ファイル名サフィックス
+    suffix: Option<String>,
+    /// 同名の出力ファイルがある場合の動作
+    overwrite: Option<OverwritePolicy>,
+    /// 同時に変換するファイル数（0なら1）
+    jobs: usize,
+    /// エンコード設定のJSONファイル
+    preset_file: Option<PathBuf>,
+    /// 変換せずにコマンドを表示
+    dry_run: bool,
+    /// 使い方を表示
+    help: bool,
+}
+
+/// ヘッドレスモードを実行し、終了コードを返す
+/// `args` には `--cli` より後の引数を渡す
+pub fn run(args: impl IntoIterator<Item = OsString>) -> i32 {
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("エラー: {:#}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    if args.help {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    match run_batch(&args) {
+        Ok(0) => 0,
+        Ok(failed) => {
+            eprintln!("{} 件のファイルの変換に失敗しました", failed);
+            EXIT_FAILED
+        }
+        Err(e) => {
+            eprintln!("エラー: {:#}", e);
+            EXIT_USAGE
+        }
+    }
+}
+
+/// 引数を解析
+/// パスの引数はUTF-8に変換せずに受け取る
+fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<CliArgs> {
+    let mut parsed = CliArgs::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let Some(name) = arg.to_str().filter(|arg| arg.starts_with('-')) else {
+            parsed.inputs.push(PathBuf::from(arg));
+            continue;
+        };
+        if name == "-h" || name == "--help" {
+            parsed.help = true;
+            continue;
+        }
+        if name == "--dry-run" {
+            parsed.dry_run = true;
+            continue;
+        }
+
+        let value = args
+            .next()
+            .with_context(|| format!("{} の値がありません", name))?;
+        // パス以外の値は文字列として解釈する
+        let text = |value: OsString| {
+            value
+                .into_string()
+                .map_err(|value| anyhow!("{} の値が不正です: {}", name, value.to_string_lossy()))
+        };
+        match name {
+            "--input" => parsed.inputs.push(PathBuf::from(value)),
+            "--output" => parsed.output = Some(PathBuf::from(value)),
+            "--codec" => parsed.codec = Some(parse_codec(&text(value)?)?),
+            "--crf" => parsed.crf = Some(parse_crf(&text(value)?)?),
+            "--container" => parsed.container = Some(parse_container(&text(value)?)?),
+            "--hwaccel" => parsed.hwaccel = Some(parse_hwaccel(&text(value)?)?),
+            "--audio-codec" => parsed.audio_codec = Some(parse_audio_codec(&text(value)?)?),
+            "--output-dir" => parsed.output_dir = Some(PathBuf::from(value)),
+            "--suffix" => parsed.suffix = Some(text(value)?),
+            "--overwrite" => parsed.overwrite = Some(parse_overwrite(&text(value)?)?),
+            "--jobs" => parsed.jobs = parse_jobs(&text(value)?)?,
+            "--preset-file" | "--preset" => parsed.preset_file = Some(PathBuf::from(value)),
+            _ => bail!("不明なオプションです: {}", name),
+        }
+    }
+
+    if parsed.inputs.is_empty() && !parsed.help {
+        bail!("入力ファイルを指定してください");
+    }
+    if parsed.output.is_some() && parsed.inputs.len() > 1 {
+        bail!("--output は入力が1つの場合のみ指定できます");
+    }
+    Ok(parsed)
+}
+
+fn parse_codec(value: &str) -> Result<VideoCodec> {
+    Ok(match value.to_lowercase().as_str() {
+        "h264" | "avc" => VideoCodec::H264,
+        "h265" | "hevc" => VideoCodec::H265,
+        "vp9" => VideoCodec::Vp9,
+        "av1" => VideoCodec::Av1,
+        "copy" => VideoCodec::Copy,
+        _ => bail!("不明なコーデックです: {}", value),
+    })
+}
+
+fn parse_crf(value: &str) -> Result<u8> {
+    match value.parse::<u8>() {
+        Ok(crf) if crf <= 51 => Ok(crf),
+        _ => bail!("CRFは0〜51で指定してください: {}", value),
+    }
+}
+
+fn parse_container(value: &str) -> Result<ContainerFormat> {
+    Ok(match value.to_lowercase().as_str() {
+        "mp4" => ContainerFormat::Mp4,
+        "mkv" => ContainerFormat::Mkv,
+        _ => bail!("不明なコンテナ形式です: {}", value),
+    })
+}
+
+fn parse_hwaccel(value: &str) -> Result<HwAccelType> {
+    Ok(match value.to_lowercase().as_str() {
+        "auto" => HwAccelType::Auto,
+        "nvenc" => HwAccelType::Nvenc,
+        "qsv" => HwAccelType::Qsv,
+        "amf" => HwAccelType::Amf,
+        "software" | "sw" => HwAccelType::Software,
+        _ => bail!("不明なHWアクセラレーションです: {}", value),
+    })
+}
+
+fn parse_audio_codec(value: &str) -> Result<AudioCodec> {
+    Ok(match value.to_lowercase().as_str() {
+        "aac" => AudioCodec::Aac,
+        "mp3" => AudioCodec::Mp3,
+        "flac" => AudioCodec::Flac,
+        "copy" => AudioCodec::Copy,
+        _ => bail!("不明な音声コーデックです: {}", value),
+    })
+}
+
+fn parse_overwrite(value: &str) -> Result<OverwritePolicy> {
+    Ok(match value.to_lowercase().as_str() {
+        "overwrite" => OverwritePolicy::Overwrite,
+        "skip" => OverwritePolicy::Skip,
+        "rename" => OverwritePolicy::Rename,
+        _ => bail!("不明な上書きポリシーです: {}", value),
+    })
+}
+
+fn parse_jobs(value: &str) -> Result<usize> {
+    match value.parse::<usize>() {
+        Ok(jobs) if (1..=MAX_PARALLEL_JOBS).contains(&jobs) => Ok(jobs),
+        _ => bail!(
+            "同時変換数は1〜{}で指定してください: {}",
+            MAX_PARALLEL_JOBS,
+            value
+        ),
+    }
+}
+
+/// 基準の設定に引数で指定した項目を反映
+fn build_settings(args: &CliArgs, base: TranscodeSettings) -> TranscodeSettings {
+    let mut settings = base;
+    if let Some(codec) = args.codec {
+        settings.video_codec = codec;
+    }
+    if let Some(crf) = args.crf {
+        settings.crf = crf;
+        // ビットレート指定のプリセットでもCRFを指定したら品質指定にする
+        if !settings.rate_control.uses_quality() {
+            settings.rate_control = RateControlMode::Crf;
+        }
+    }
+    // コンテナ形式の指定がなければ出力ファイルの拡張子に合わせる
+    let output_container = args
+        .output
+        .as_ref()
+        .and_then(|path| path.extension())
+        .and_then(|ext| parse_container(&ext.to_string_lossy()).ok());
+    if let Some(container) = args.container.or(output_container) {
+        settings.container = container;
+    }
+    if let Some(hwaccel) = args.hwaccel {
+        settings.hwaccel = hwaccel;
+    }
+    if let Some(audio_codec) = args.audio_codec {
+        settings.audio_codec = audio_codec;
+    }
+    if let Some(dir) = &args.output_dir {
+        settings.output_dir = Some(dir.clone());
+    }
+    if let Some(suffix) = &args.suffix {
+        settings.output_suffix = suffix.clone();
+    }
+    if let Some(policy) = args.overwrite {
+        settings.overwrite_policy = policy;
+    }
+    settings
+}
+
+/// エンコード設定のJSONファイルを読み込む（HandBrakeのプリセットにも対応）
+fn load_preset_file(path: &Path) -> Result<TranscodeSettings> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("{} を読み込めません", path.display()))?;
+    if let Ok(settings) = import_handbrake_preset(&content) {
+        return Ok(settings);
+    }
+    serde_json::from_str(&content)
+        .with_context(|| format!("{} はエンコード設定ではありません", path.display()))
+}
+
+/// 入力のフォルダを直下の動画ファイルに展開
+fn collect_inputs(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            let mut videos: Vec<PathBuf> = std::fs::read_dir(input)
+                .with_context(|| format!("{} を読み込めません", input.display()))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.is_file() && AppState::is_supported_format(path))
+                .collect();
+            videos.sort();
+            paths.extend(videos);
+        } else if input.is_file() {
+            paths.push(input.clone());
+        } else {
+            bail!("{} が見つかりません", input.display());
+        }
+    }
+    Ok(paths)
+}
+
+/// キューを実行し、失敗したファイルの数を返す
+fn run_batch(args: &CliArgs) -> Result<usize> {
+    let app_settings = Settings::load().unwrap_or_else(|e| {
+        warn!("Failed to load settings, using defaults: {:#}", e);
+        Settings::default()
+    });
+    let base = match &args.preset_file {
+        Some(path) => load_preset_file(path)?,
+        None => TranscodeSettings {
+            overwrite_policy: app_settings.overwrite_policy,
+            ..Default::default()
+        },
+    };
+    let settings = build_settings(args, base);
+
+    let ffmpeg_info =
+        FfmpegDetector::detect_with_custom_path(app_settings.ffmpeg_custom_path.as_ref())
+            .context("FFmpegが見つかりません")?;
+
+    let paths = collect_inputs(&args.inputs)?;
+    if paths.is_empty() {
+        bail!("変換できる動画ファイルがありません");
+    }
+    // フォルダを指定した場合は展開後に確認する
+    if args.output.is_some() && paths.len() > 1 {
+        bail!("--output は入力が1つの場合のみ指定できます");
+    }
+    let files: Vec<FileEntry> = paths
+        .into_iter()
+        .map(|path| {
+            let mut entry = FileEntry::new(path);
+            entry.probe_metadata(&ffmpeg_info);
+            if settings.crop_mode == CropMode::AutoDetect {
+                match entry.detect_crop(&ffmpeg_info) {
+                    Ok(crop) => entry.crop = Some(crop),
+                    Err(e) => warn!("Crop detection failed for {}: {:#}", entry.name, e),
+                }
+            }
+            entry
+        })
+        .collect();
+
+    let options = QueueOptions::new(ffmpeg_info.ffmpeg_path.clone(), &settings)
+        .with_app_settings(&app_settings, Some(&ffmpeg_info));
+    if let Some(message) = options.preflight(&files) {
+        bail!(message);
+    }
+    let validation = options.validate_files(&files);
+    for warning in &validation.warnings {
+        println!("警告: {}", warning);
+    }
+    let encode_plan = options.resolve_encode_plan();
+    // ドライランは標準出力をコマンドだけにする
+    if !args.dry_run {
+        println!(
+            "{} 件のファイルを {} で変換します",
+            files.len(),
+            encode_plan.encoder
+        );
+    }
+
+    let total = files.len();
+    let jobs = args.jobs.clamp(1, total);
+    let parallel = jobs > 1 && !args.dry_run;
+    // 並列変換ではHWエンコーダーの同時セッション数を超えた分をソフトウェアで変換する
+    let slots = SessionSlots::new(if parallel {
+        options.session_limit(&encode_plan.hwaccel)
+    } else {
+        None
+    });
+    let software_plan = options.session_fallback(&encode_plan);
+    let next = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    // 並列変換中のジョブ（見出し, 進捗）
+    let running: Mutex<Vec<(String, CurrentProgress)>> = Mutex::new(Vec::new());
+
+    let worker = || {
+        while !stop.load(Ordering::SeqCst) {
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            let Some(file) = files.get(index) else {
+                break;
+            };
+            let header = format!("[{}/{}] {}", index + 1, total, file.name);
+            if let Some(message) = validation.rejection(index) {
+                failed.fetch_add(1, Ordering::SeqCst);
+                println!("{}: 失敗 - {}", header, message);
+                continue;
+            }
+            let prepared = match &args.output {
+                Some(output) => {
+                    prepare_job_with_output(&options, file, &encode_plan, output.clone())
+                }
+                None => prepare_job(&options, file, &encode_plan),
+            };
+            let mut job = match prepared {
+                PreparedJob::Run { job, note } => {
+                    if let Some(note) = note {
+                        println!("{}: {}", header, note);
+                    }
+                    *job
+                }
+                PreparedJob::Skip { note } => {
+                    println!(
+                        "{}: スキップ（{}）",
+                        header,
+                        note.unwrap_or_else(|| "出力ファイルが既に存在します".to_string())
+                    );
+                    continue;
+                }
+            };
+            if args.dry_run {
+                println!("{}", job.command_line(&ffmpeg_info.ffmpeg_path));
+                continue;
+            }
+
+            let (plan, _slot) = slots.assign(&encode_plan, software_plan.as_ref());
+            if plan != encode_plan {
+                println!(
+                    "{}: {} の同時セッション数が上限のため {} で変換します",
+                    header, encode_plan.encoder, plan.encoder
+                );
+                job = job.with_encode_plan(plan);
+            }
+
+            let progress = CurrentProgress::default();
+            let outcome = if parallel {
+                println!("{}: 変換を開始します", header);
+                running
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push((header.clone(), progress.clone()));
+                let outcome = run_job(&options, file, &mut job, &progress);
+                running
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .retain(|(running, _)| *running != header);
+                outcome
+            } else {
+                let done = Arc::new(AtomicBool::new(false));
+                let printer =
+                    spawn_progress_printer(header.clone(), progress.clone(), done.clone());
+                let outcome = run_job(&options, file, &mut job, &progress);
+                done.store(true, Ordering::SeqCst);
+                let _ = printer.join();
+                outcome
+            };
+            record_completed(file, &outcome);
+
+            match &outcome.status {
+                FileStatus::Completed { .. } => {
+                    let size = outcome.output_size.map(format_size).unwrap_or_default();
+                    let quality = outcome
+                        .quality
+                        .map(|q| format!(", {}", q.label()))
+                        .unwrap_or_default();
+                    let note = outcome
+                        .note
+                        .as_ref()
+                        .map(|note| format!(", {}", note))
+                        .unwrap_or_default();
+                    println!(
+                        "\r{}: 完了 → {} ({}{}{})",
+                        header,
+                        job.output_path.display(),
+                        size,
+                        quality,
+                        note
+                    );
+                }
+                FileStatus::Error(message) => {
+                    failed.fetch_add(1, Ordering::SeqCst);
+                    println!("\r{}: 失敗 - {}", header, message);
+                }
+                status => println!("\r{}: {:?}", header, status),
+            }
+            if outcome.error_kind == Some(FfmpegErrorKind::DiskFull)
+                && !stop.swap(true, Ordering::SeqCst)
+            {
+                eprintln!("出力先の空き容量が不足したため、残りのファイルの変換を中止しました");
+            }
+        }
+    };
+
+    if parallel {
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs).map(|_| scope.spawn(worker)).collect();
+            scope.spawn(|| print_parallel_progress(&running, &done));
+            for handle in workers {
+                let _ = handle.join();
+            }
+            done.store(true, Ordering::SeqCst);
+        });
+    } else {
+        worker();
+    }
+
+    Ok(failed.into_inner())
+}
+
+/// 並列変換中のジョブの進捗を一定間隔でまとめて表示
+fn print_parallel_progress(running: &Mutex<Vec<(String, CurrentProgress)>>, done: &AtomicBool) {
+    let mut last_printed = Instant::now();
+    while !done.load(Ordering::SeqCst) {
+        std::thread::sleep(PROGRESS_INTERVAL);
+        if last_printed.elapsed() < PARALLEL_PROGRESS_INTERVAL {
+            continue;
+        }
+        last_printed = Instant::now();
+        if let Some(line) =
+            parallel_progress_line(&running.lock().unwrap_or_else(|e| e.into_inner()))
+        {
+            println!("{}", line);
+        }
+    }
+}
+
+/// 並列変換中のジョブの進捗をまとめた1行（実行中のジョブがなければNone）
+fn parallel_progress_line(running: &[(String, CurrentProgress)]) -> Option<String> {
+    if running.is_empty() {
+        return None;
+    }
+    let jobs: Vec<String> = running
+        .iter()
+        .map(|(header, progress)| {
+            let remaining = progress
+                .get_remaining_secs()
+                .map(|secs| format_duration(Duration::from_secs_f32(secs)))
+                .unwrap_or_else(|| "--:--".to_string());
+            format!(
+                "{} {:.1}% ETA: {}",
+                header,
+                progress.get_progress() * 100.0,
+                remaining
+            )
+        })
+        .collect();
+    Some(format!("Progress: {}", jobs.join(" | ")))
+}
+
+/// 変換中の進捗を1行で上書き表示するスレッドを起動
+fn spawn_progress_printer(
+    header: String,
+    progress: CurrentProgress,
+    done: Arc<AtomicBool>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        while !done.load(Ordering::SeqCst) {
+            let remaining = progress
+                .get_remaining_secs()
+                .map(|secs| format_duration(Duration::from_secs_f32(secs)))
+                .unwrap_or_else(|| "--:--".to_string());
+            print!(
+                "\r{}  Progress: {:5.1}%  ETA: {}  FPS: {:.1}   ",
+                header,
+                progress.get_progress() * 100.0,
+                remaining,
+                progress.get_fps()
+            );
+            let _ = std::io::stdout().flush();
+            std::thread::sleep(PROGRESS_INTERVAL);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<OsString> {
+        list.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = parse_args(args(&[
+            "a.mkv",
+            "--codec",
+            "h265",
+            "--crf",
+            "22",
+            "--output-dir",
+            "out",
+            "b.mp4",
+        ]))
+        .unwrap();
+        assert_eq!(
+            parsed,
+            CliArgs {
+                inputs: vec![PathBuf::from("a.mkv"), PathBuf::from("b.mp4")],
+                codec: Some(VideoCodec::H265),
+                crf: Some(22),
+                output_dir: Some(PathBuf::from("out")),
+                ..Default::default()
+            }
+        );
+
+        assert!(parse_args(args(&["--help"])).unwrap().help);
+        // 入力がない・値がない・不明な値
+        assert!(parse_args(args(&["--codec", "h265"])).is_err());
+        assert!(parse_args(args(&["a.mkv", "--crf"])).is_err());
+        assert!(parse_args(args(&["a.mkv", "--codec", "mpeg2"])).is_err());
+        assert!(parse_args(args(&["a.mkv", "--crf", "70"])).is_err());
+        assert!(parse_args(args(&["a.mkv", "--verbose", "1"])).is_err());
+
+        // --input・--output・音声コーデック・ドライラン
+        let parsed = parse_args(args(&[
+            "--input",
+            "a.mkv",
+            "--output",
+            "out.mkv",
+            "--audio-codec",
+            "flac",
+            "--preset",
+            "web.json",
+            "--dry-run",
+        ]))
+        .unwrap();
+        assert_eq!(
+            parsed,
+            CliArgs {
+                inputs: vec![PathBuf::from("a.mkv")],
+                output: Some(PathBuf::from("out.mkv")),
+                audio_codec: Some(AudioCodec::Flac),
+                preset_file: Some(PathBuf::from("web.json")),
+                dry_run: true,
+                ..Default::default()
+            }
+        );
+        // 出力ファイルは入力が1つの場合のみ
+        assert!(parse_args(args(&["a.mkv", "b.mkv", "--output", "out.mp4"])).is_err());
+        assert!(parse_args(args(&["a.mkv", "--audio-codec", "opus"])).is_err());
+
+        // 同時変換数
+        assert_eq!(parse_args(args(&["a.mkv", "--jobs", "4"])).unwrap().jobs, 4);
+        assert!(parse_args(args(&["a.mkv", "--jobs", "0"])).is_err());
+        assert!(parse_args(args(&["a.mkv", "--jobs", "17"])).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parse_non_utf8_paths() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let input = OsString::from_vec(b"clip\xff.mkv".to_vec());
+        let output = OsString::from_vec(b"out\xff.mp4".to_vec());
+        let parsed = parse_args(vec![
+            input.clone(),
+            OsString::from("--output"),
+            output.clone(),
+        ])
+        .unwrap();
+        assert_eq!(parsed.inputs, vec![PathBuf::from(input)]);
+        assert_eq!(parsed.output, Some(PathBuf::from(output)));
+
+        // パス以外の値はUTF-8でなければエラー
+        let codec = OsString::from_vec(b"h26\xff".to_vec());
+        assert!(parse_args(vec![
+            OsString::from("a.mkv"),
+            OsString::from("--codec"),
+            codec
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_build_settings() {
+        let parsed = parse_args(args(&[
+            "a.mkv",
+            "--codec",
+            "av1",
+            "--crf",
+            "30",
+            "--overwrite",
+            "skip",
+        ]))
+        .unwrap();
+        let base = TranscodeSettings {
+            rate_control: RateControlMode::Vbr,
+            ..Default::default()
+        };
+        let settings = build_settings(&parsed, base);
+        assert_eq!(settings.video_codec, VideoCodec::Av1);
+        assert_eq!(settings.crf, 30);
+        // CRFを指定したらビットレート指定から品質指定に切り替える
+        assert_eq!(settings.rate_control, RateControlMode::Crf);
+        assert_eq!(settings.overwrite_policy, OverwritePolicy::Skip);
+
+        // 指定していない項目は基準の設定のまま
+        let base = TranscodeSettings::default();
+        let settings = build_settings(&parse_args(args(&["a.mkv"])).unwrap(), base.clone());
+        assert_eq!(settings.video_codec, base.video_codec);
+        assert_eq!(settings.crf, base.crf);
+        assert_eq!(settings.output_dir, base.output_dir);
+
+        // 出力ファイルの拡張子からコンテナ形式を決める（--containerが優先）
+        let settings = build_settings(
+            &parse_args(args(&["a.mp4", "--output", "b.MKV"])).unwrap(),
+            base.clone(),
+        );
+        assert_eq!(settings.container, ContainerFormat::Mkv);
+        let settings = build_settings(
+            &parse_args(args(&["a.mp4", "--output", "b.mkv", "--container", "mp4"])).unwrap(),
+            base,
+        );
+        assert_eq!(settings.container, ContainerFormat::Mp4);
+    }
+
+    #[test]
+    fn test_load_preset_file_accepts_handbrake_presets() {
+        let dir =
+            std::env::temp_dir().join(format!("kamaitachi_cli_preset_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let handbrake = dir.join("handbrake.json");
+        std::fs::write(
+            &handbrake,
+            r#"{ "PresetList": [{ "VideoEncoder": "x265", "FileFormat": "av_mkv" }] }"#,
+        )
+        .unwrap();
+        let settings = load_preset_file(&handbrake).unwrap();
+        assert_eq!(settings.video_codec, VideoCodec::H265);
+        assert_eq!(settings.container, ContainerFormat::Mkv);
+
+        let own = dir.join("settings.json");
+        let saved = TranscodeSettings {
+            crf: 31,
+            ..Default::default()
+        };
+        std::fs::write(&own, serde_json::to_string(&saved).unwrap()).unwrap();
+        assert_eq!(load_preset_file(&own).unwrap().crf, 31);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_progress_line() {
+        assert_eq!(parallel_progress_line(&[]), None);
+
+        let first = CurrentProgress::default();
+        first.set_progress(0.5);
+        first.set_remaining_secs(Some(90.0));
+        let second = CurrentProgress::default();
+        let line = parallel_progress_line(&[
+            ("[1/3] a.mkv".to_string(), first),
+            ("[2/3] b.mkv".to_string(), second),
+        ]);
+        assert_eq!(
+            line.as_deref(),
+            Some("Progress: [1/3] a.mkv 50.0% ETA: 01:30 | [2/3] b.mkv 0.0% ETA: --:--")
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! 予測サイズの補正（JSON保存）
+//!
+//! 変換後の実際のサイズと予測サイズの比を、コーデック・HWアクセラレーション・コンテンツタイプごとに蓄積する
+//! 手元のライブラリで予測が一貫してずれる場合に、次のファイルの予測を実際の結果に近づける
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use super::Settings;
+use crate::transcoder::{ContentType, TranscodeSettings};
+
+/// 補正を使い始めるサンプル数
+pub const MIN_CALIBRATION_SAMPLES: u32 = 3;
+
+/// 平均に使うサンプル数の上限（設定や素材の傾向が変わっても追従できるよう、古い結果の重みを下げる）
+const MAX_WEIGHTED_SAMPLES: u32 = 20;
+
+/// 1件の比として受け付ける範囲（極端な結果で補正が振り切れないようにする）
+const RATIO_RANGE: (f64, f64) = (0.2, 5.0);
+
+/// 補正の単位ごとの蓄積
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationBucket {
+    /// 記録した変換の数
+    pub samples: u32,
+    /// 実際 / 予測 の比の対数の移動平均
+    pub mean_log_ratio: f64,
+}
+
+impl CalibrationBucket {
+    /// 比を1件追加
+    fn add(&mut self, ratio: f64) {
+        let ratio = ratio.clamp(RATIO_RANGE.0, RATIO_RANGE.1);
+        self.samples += 1;
+        let weight = self.samples.min(MAX_WEIGHTED_SAMPLES) as f64;
+        self.mean_log_ratio += (ratio.ln() - self.mean_log_ratio) / weight;
+    }
+
+    /// 予測に掛ける補正係数（サンプルが足りなければNone）
+    fn factor(&self) -> Option<f64> {
+        (self.samples >= MIN_CALIBRATION_SAMPLES).then(|| self.mean_log_ratio.exp())
+    }
+}
+
+/// 予測サイズの補正
+#[derive(Clone, Debug, Default)]
+pub struct SizeCalibration {
+    /// 保存先ファイルのパス
+    path: PathBuf,
+    /// 補正の単位（コーデック/HWアクセラレーション/コンテンツタイプ）→ 蓄積
+    buckets: BTreeMap<String, CalibrationBucket>,
+}
+
+impl SizeCalibration {
+    /// 設定ディレクトリの補正ファイルを開く
+    pub fn open() -> Result<Self> {
+        let path = Settings::config_path()?.with_file_name("calibration.json");
+        Self::open_at(path)
+    }
+
+    /// 指定したファイルの補正を開く（存在しなければ空）
+    pub fn open_at(path: PathBuf) -> Result<Self> {
+        let buckets = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, buckets })
+    }
+
+    /// 補正の単位（HWアクセラレーションは設定値のまま。「自動検出」は1つの単位として扱う）
+    fn key(settings: &TranscodeSettings, content_type: ContentType) -> String {
+        format!(
+            "{:?}/{:?}/{:?}",
+            settings.video_codec, settings.hwaccel, content_type
+        )
+    }
+
+    /// 予測に掛ける補正係数（サンプルが足りなければNone）
+    pub fn factor(&self, settings: &TranscodeSettings, content_type: ContentType) -> Option<f64> {
+        self.buckets
+            .get(&Self::key(settings, content_type))?
+            .factor()
+    }
+
+    /// 変換結果を記録（予測は補正前の値を渡す）
+    pub fn record(
+        &mut self,
+        settings: &TranscodeSettings,
+        content_type: ContentType,
+        predicted_size: u64,
+        actual_size: u64,
+    ) -> Result<()> {
+        if predicted_size == 0 || actual_size == 0 {
+            return Ok(());
+        }
+        self.buckets
+            .entry(Self::key(settings, content_type))
+            .or_default()
+            .add(actual_size as f64 / predicted_size as f64);
+        self.write()
+    }
+
+    /// 記録した変換の総数
+    pub fn sample_count(&self) -> u32 {
+        self.buckets.values().map(|bucket| bucket.samples).sum()
+    }
+
+    /// 補正をすべて削除
+    pub fn clear(&mut self) -> Result<()> {
+        self.buckets.clear();
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+
+    /// ファイルに書き込む
+    fn write(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.buckets)?;
+        std::fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcoder::{HwAccelType, VideoCodec};
+
+    #[test]
+    fn test_factor_needs_samples_and_persists() {
+        let path = std::env::temp_dir().join(format!(
+            "kamaitachi-calibration-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let settings = TranscodeSettings {
+            video_codec: VideoCodec::H265,
+            hwaccel: HwAccelType::Nvenc,
+            ..Default::default()
+        };
+
+        let mut calibration = SizeCalibration::open_at(path.clone()).unwrap();
+        calibration
+            .record(&settings, ContentType::Anime, 1_000, 700)
+            .unwrap();
+        calibration
+            .record(&settings, ContentType::Anime, 2_000, 1_400)
+            .unwrap();
+        // サンプルが足りないうちは補正しない
+        assert_eq!(calibration.factor(&settings, ContentType::Anime), None);
+        calibration
+            .record(&settings, ContentType::Anime, 500, 350)
+            .unwrap();
+
+        let mut calibration = SizeCalibration::open_at(path.clone()).unwrap();
+        assert_eq!(calibration.sample_count(), 3);
+        let factor = calibration.factor(&settings, ContentType::Anime).unwrap();
+        assert!((factor - 0.7).abs() < 1e-9);
+        // 別の単位には影響しない
+        assert_eq!(calibration.factor(&settings, ContentType::Normal), None);
+
+        calibration.clear().unwrap();
+        assert!(!path.exists());
+        assert_eq!(calibration.sample_count(), 0);
+    }
+
+    #[test]
+    fn test_bucket_follows_recent_results() {
+        let mut bucket = CalibrationBucket::default();
+        for _ in 0..MAX_WEIGHTED_SAMPLES {
+            bucket.add(1.0);
+        }
+        for _ in 0..40 {
+            bucket.add(0.5);
+        }
+        // 古い結果の重みは下がり、最近の傾向に近づく
+        let factor = bucket.factor().unwrap();
+        assert!((factor - 0.5).abs() < 0.1, "factor: {}", factor);
+        // 極端な比は範囲内に収める
+        let mut bucket = CalibrationBucket::default();
+        for _ in 0..3 {
+            bucket.add(100.0);
+        }
+        assert!((bucket.factor().unwrap() - RATIO_RANGE.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,660 @@
+//! HandBrakeのプリセット（JSON書き出し）の読み込み
+//!
+//! HandBrakeの項目をTranscodeSettingsに対応付ける
+//! 反映した項目と無視した項目は`read_handbrake_preset`で確認できる
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+
+use crate::transcoder::{
+    audio_bitrate_options, crf_from_encoder_crf, AudioChannels, AudioCodec, ContainerFormat,
+    Deinterlace, FrameRate, HwAccelType, PixelFormat, RateControlMode, TranscodeSettings,
+    VideoCodec, VideoPreset, VideoResolution, X264Profile, X264Tune,
+};
+
+/// FFmpeg側に対応する設定がないHandBrakeのフィルター（"off"以外なら無視した項目に入れる）
+const UNSUPPORTED_FILTERS: &[&str] = &[
+    "PictureDenoiseFilter",
+    "PictureDetelecine",
+    "PictureSharpenFilter",
+    "PictureDeblockPreset",
+    "PictureChromaSmoothPreset",
+    "PictureColorspacePreset",
+];
+
+/// HandBrakeプリセットの読み込み結果
+#[derive(Clone, Debug)]
+pub struct HandBrakeImport {
+    /// プリセット名
+    pub name: String,
+    /// 対応付けたエンコード設定（対応しない項目は既定値）
+    pub settings: TranscodeSettings,
+    /// 反映した項目（「項目: 値 → 設定」）
+    pub mapped: Vec<String>,
+    /// 反映できなかった項目
+    pub ignored: Vec<String>,
+}
+
+/// HandBrakeのプリセットJSONをエンコード設定として読み込む
+/// 複数のプリセットやフォルダーを含む場合は最初のプリセットを使う
+pub fn import_handbrake_preset(json: &str) -> Result<TranscodeSettings> {
+    read_handbrake_preset(json).map(|import| import.settings)
+}
+
+/// HandBrakeのプリセットJSONを読み込み、反映した項目と無視した項目も返す
+pub fn read_handbrake_preset(json: &str) -> Result<HandBrakeImport> {
+    let root: Value =
+        serde_json::from_str(json).context("HandBrakeプリセットのJSONを読み込めません")?;
+    let preset = find_preset(&root).context("HandBrakeのプリセットが見つかりません")?;
+
+    let mut import = HandBrakeImport {
+        name: string(preset, "PresetName")
+            .unwrap_or("HandBrake")
+            .to_string(),
+        settings: TranscodeSettings::default(),
+        mapped: Vec::new(),
+        ignored: Vec::new(),
+    };
+    import.map_container(preset);
+    import.map_video(preset);
+    import.map_picture(preset);
+    import.map_audio(preset);
+    import.map_unsupported(preset);
+    Ok(import)
+}
+
+/// 最初のプリセットを探す（PresetListとフォルダーのChildrenArrayをたどる）
+fn find_preset(value: &Value) -> Option<&Map<String, Value>> {
+    match value {
+        Value::Array(items) => items.iter().find_map(find_preset),
+        Value::Object(map) => {
+            if let Some(list) = map.get("PresetList") {
+                return find_preset(list);
+            }
+            if map.get("Folder").and_then(Value::as_bool) == Some(true) {
+                return map.get("ChildrenArray").and_then(find_preset);
+            }
+            map.contains_key("VideoEncoder").then_some(map)
+        }
+        _ => None,
+    }
+}
+
+/// 文字列の項目（空文字はなし）
+fn string<'a>(preset: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
+    preset
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+/// 数値の項目（HandBrakeは文字列で書き出すこともある）
+fn number(preset: &Map<String, Value>, key: &str) -> Option<f64> {
+    match preset.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// HandBrakeのエンコーダー名を（コーデック, HWアクセラレーション, 10bit以上か）に変換
+fn parse_encoder(encoder: &str) -> Option<(VideoCodec, HwAccelType, bool)> {
+    let encoder = encoder.to_ascii_lowercase();
+    let high_bit_depth = encoder.ends_with("_10bit") || encoder.ends_with("_12bit");
+    let base = encoder
+        .trim_end_matches("_10bit")
+        .trim_end_matches("_12bit");
+    let (hwaccel, name) = if let Some(name) = base.strip_prefix("nvenc_") {
+        (HwAccelType::Nvenc, name)
+    } else if let Some(name) = base.strip_prefix("qsv_") {
+        (HwAccelType::Qsv, name)
+    } else if let Some(name) = base.strip_prefix("vce_") {
+        (HwAccelType::Amf, name)
+    } else {
+        (HwAccelType::Software, base)
+    };
+    let codec = match name {
+        "x264" | "h264" => VideoCodec::H264,
+        "x265" | "h265" | "hevc" => VideoCodec::H265,
+        "vp9" => VideoCodec::Vp9,
+        "svt_av1" | "av1" => VideoCodec::Av1,
+        _ => return None,
+    };
+    Some((codec, hwaccel, high_bit_depth))
+}
+
+/// HandBrakeのエンコーダープリセットを速度プリセットに変換
+/// x264系の名前、SVT-AV1の数値（0-13）、NVENCのp1-p7、AMFのspeed/balanced/qualityに対応
+fn parse_video_preset(preset: &str) -> Option<VideoPreset> {
+    let preset = preset.to_ascii_lowercase();
+    if let Ok(level) = preset.parse::<u8>() {
+        // SVT-AV1は数値が小さいほど低速
+        return Some(match level {
+            0..=3 => VideoPreset::Veryslow,
+            4..=5 => VideoPreset::Slow,
+            6..=7 => VideoPreset::Medium,
+            8..=10 => VideoPreset::Fast,
+            _ => VideoPreset::Ultrafast,
+        });
+    }
+    if let Some(level) = preset.strip_prefix('p').and_then(|l| l.parse::<u8>().ok()) {
+        // NVENCは数値が大きいほど低速
+        return Some(match level {
+            1..=2 => VideoPreset::Ultrafast,
+            3 => VideoPreset::Fast,
+            4 => VideoPreset::Medium,
+            5..=6 => VideoPreset::Slow,
+            _ => VideoPreset::Veryslow,
+        });
+    }
+    match preset.as_str() {
+        "ultrafast" | "superfast" => Some(VideoPreset::Ultrafast),
+        "veryfast" | "faster" | "fast" | "speed" => Some(VideoPreset::Fast),
+        "medium" | "balanced" => Some(VideoPreset::Medium),
+        "slow" | "slower" | "quality" => Some(VideoPreset::Slow),
+        "veryslow" | "placebo" => Some(VideoPreset::Veryslow),
+        _ => None,
+    }
+}
+
+/// HandBrakeの品質スライダーをCRF（x264の尺度）に変換
+/// VP9/AV1はエンコーダーの尺度のため、換算後にスライダーの値以上になる最小のCRFを選ぶ
+fn crf_from_slider(slider: f64, encoder: &str) -> u8 {
+    crf_from_encoder_crf(slider.round().clamp(0.0, 63.0) as u8, encoder)
+}
+
+impl HandBrakeImport {
+    /// 反映した項目を記録
+    fn record_mapped(
+        &mut self,
+        key: &str,
+        value: impl std::fmt::Display,
+        setting: impl AsRef<str>,
+    ) {
+        self.mapped
+            .push(format!("{}: {} → {}", key, value, setting.as_ref()));
+    }
+
+    /// 反映できなかった項目を記録
+    fn record_ignored(&mut self, key: &str, value: impl std::fmt::Display, reason: &str) {
+        self.ignored
+            .push(format!("{}: {}（{}）", key, value, reason));
+    }
+
+    /// 出力形式・メタデータ
+    fn map_container(&mut self, preset: &Map<String, Value>) {
+        if let Some(format) = string(preset, "FileFormat") {
+            let container = match format {
+                "av_mp4" => Some(ContainerFormat::Mp4),
+                "av_mkv" => Some(ContainerFormat::Mkv),
+                _ => None,
+            };
+            match container {
+                Some(container) => {
+                    self.settings.container = container;
+                    self.record_mapped("FileFormat", format, container.display_name());
+                }
+                None => self.record_ignored("FileFormat", format, "対応していないコンテナ"),
+            }
+        }
+        if let Some(chapters) = preset.get("ChapterMarkers").and_then(Value::as_bool) {
+            self.settings.preserve_chapters = chapters;
+            let setting = if chapters {
+                "チャプターを保持"
+            } else {
+                "チャプターを保持しない"
+            };
+            self.record_mapped("ChapterMarkers", chapters, setting);
+        }
+        if let Some(metadata) = preset.get("MetadataPassthru").and_then(Value::as_bool) {
+            self.settings.preserve_metadata = metadata;
+            let setting = if metadata {
+                "メタデータを保持"
+            } else {
+                "メタデータを保持しない"
+            };
+            self.record_mapped("MetadataPassthru", metadata, setting);
+        }
+    }
+
+    /// 映像エンコーダー・品質
+    fn map_video(&mut self, preset: &Map<String, Value>) {
+        if let Some(encoder) = string(preset, "VideoEncoder") {
+            match parse_encoder(encoder) {
+                Some((codec, hwaccel, high_bit_depth)) => {
+                    self.settings.video_codec = codec;
+                    self.settings.hwaccel = hwaccel;
+                    if high_bit_depth {
+                        self.settings.pixel_format = PixelFormat::Yuv420p10le;
+                    }
+                    self.record_mapped(
+                        "VideoEncoder",
+                        encoder,
+                        format!(
+                            "{} / {}{}",
+                            codec.display_name(),
+                            hwaccel.display_name(),
+                            if high_bit_depth { " / 10bit" } else { "" }
+                        ),
+                    );
+                }
+                None => self.record_ignored("VideoEncoder", encoder, "対応していないエンコーダー"),
+            }
+        }
+
+        let quality_type = number(preset, "VideoQualityType").map(|t| t as i64);
+        match quality_type {
+            // 固定品質
+            Some(2) | None => {
+                if let Some(slider) = number(preset, "VideoQualitySlider") {
+                    let encoder = self
+                        .settings
+                        .video_codec
+                        .encoder_name(&self.settings.hwaccel);
+                    self.settings.rate_control = RateControlMode::Crf;
+                    self.settings.crf = crf_from_slider(slider, encoder);
+                    let crf = self.settings.crf;
+                    self.record_mapped("VideoQualitySlider", slider, format!("CRF {}", crf));
+                }
+            }
+            // 平均ビットレート
+            Some(1) => {
+                if let Some(bitrate) = number(preset, "VideoAvgBitrate").filter(|b| *b > 0.0) {
+                    let bitrate = bitrate.round() as u32;
+                    self.settings.rate_control = RateControlMode::Vbr;
+                    self.settings.target_bitrate = bitrate;
+                    self.settings.max_bitrate = self.settings.max_bitrate.max(bitrate);
+                    self.record_mapped("VideoAvgBitrate", bitrate, format!("VBR {} kbps", bitrate));
+                }
+                if preset.get("VideoMultiPass").and_then(Value::as_bool) == Some(true) {
+                    self.record_ignored(
+                        "VideoMultiPass",
+                        true,
+                        "2パスエンコードには対応していません",
+                    );
+                }
+            }
+            Some(other) => {
+                self.record_ignored("VideoQualityType", other, "対応していない品質の指定")
+            }
+        }
+
+        if let Some(value) = string(preset, "VideoPreset") {
+            match parse_video_preset(value) {
+                Some(video_preset) => {
+                    self.settings.preset = video_preset;
+                    self.record_mapped("VideoPreset", value, video_preset.display_name());
+                }
+                None => {
+                    self.record_ignored("VideoPreset", value, "対応する速度プリセットがありません")
+                }
+            }
+        }
+
+        // チューンはカンマ区切りで複数指定できるため、最初に対応するものを使う
+        if let Some(value) = string(preset, "VideoTune").filter(|t| *t != "none") {
+            let tune = value.split(',').find_map(|t| match t.trim() {
+                "film" => Some(X264Tune::Film),
+                "animation" => Some(X264Tune::Animation),
+                "grain" => Some(X264Tune::Grain),
+                "stillimage" => Some(X264Tune::StillImage),
+                "psnr" => Some(X264Tune::Psnr),
+                "ssim" => Some(X264Tune::Ssim),
+                "fastdecode" => Some(X264Tune::FastDecode),
+                "zerolatency" => Some(X264Tune::ZeroLatency),
+                _ => None,
+            });
+            match tune {
+                Some(tune) => {
+                    self.settings.x264_tune = tune;
+                    self.record_mapped("VideoTune", value, tune.display_name());
+                }
+                None => self.record_ignored("VideoTune", value, "対応するチューニングがありません"),
+            }
+        }
+
+        if let Some(value) = string(preset, "VideoProfile").filter(|p| *p != "auto") {
+            let profile = match value {
+                "baseline" => Some(X264Profile::Baseline),
+                "main" => Some(X264Profile::Main),
+                "high" => Some(X264Profile::High),
+                "high10" => Some(X264Profile::High10),
+                "high444" => Some(X264Profile::High444),
+                _ => None,
+            };
+            match profile {
+                Some(profile) => {
+                    self.settings.x264_profile = profile;
+                    self.record_mapped("VideoProfile", value, profile.display_name());
+                }
+                None => {
+                    self.record_ignored("VideoProfile", value, "対応するプロファイルがありません")
+                }
+            }
+        }
+
+        if let Some(value) = string(preset, "VideoFramerate").filter(|f| *f != "auto") {
+            let frame_rate = match value {
+                "23.976" => Some(FrameRate::Fps23_976),
+                "24" => Some(FrameRate::Fps24),
+                "25" => Some(FrameRate::Fps25),
+                "29.97" => Some(FrameRate::Fps29_97),
+                "30" => Some(FrameRate::Fps30),
+                "50" => Some(FrameRate::Fps50),
+                "59.94" => Some(FrameRate::Fps59_94),
+                "60" => Some(FrameRate::Fps60),
+                other => other
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|fps| *fps > 0.0)
+                    .map(FrameRate::Custom),
+            };
+            match frame_rate {
+                Some(frame_rate) => {
+                    self.settings.frame_rate = frame_rate;
+                    self.record_mapped("VideoFramerate", value, frame_rate.display_name());
+                }
+                None => {
+                    self.record_ignored("VideoFramerate", value, "フレームレートを読み取れません")
+                }
+            }
+        }
+
+        if preset.get("VideoGrayScale").and_then(Value::as_bool) == Some(true) {
+            self.record_ignored("VideoGrayScale", true, "グレースケールには対応していません");
+        }
+    }
+
+    /// 解像度・インターレース解除
+    fn map_picture(&mut self, preset: &Map<String, Value>) {
+        let width = number(preset, "PictureWidth").map_or(0, |w| w.max(0.0) as u32);
+        let height = number(preset, "PictureHeight").map_or(0, |h| h.max(0.0) as u32);
+        if width > 0 || height > 0 {
+            // HandBrakeの幅・高さは最大サイズのため、標準の解像度以外は高さ（なければ幅）に合わせる
+            let resolution = [
+                VideoResolution::Uhd4K,
+                VideoResolution::Fhd1080,
+                VideoResolution::Hd720,
+                VideoResolution::Sd480,
+            ]
+            .into_iter()
+            .find(|res| res.dimensions() == (width, height))
+            .unwrap_or(if height > 0 {
+                VideoResolution::Custom(0, height)
+            } else {
+                VideoResolution::Custom(width, 0)
+            });
+            self.settings.resolution = resolution;
+            self.record_mapped(
+                "PictureWidth/PictureHeight",
+                format!("{}x{}", width, height),
+                resolution.display_name(),
+            );
+        }
+
+        if let Some(value) = string(preset, "PictureDeinterlaceFilter") {
+            let deinterlace = match value {
+                "off" => Some(Deinterlace::Off),
+                // decombはインターレースを検出したときだけ解除する
+                "decomb" => Some(Deinterlace::Auto),
+                "yadif" => Some(Deinterlace::Yadif),
+                "bwdif" => Some(Deinterlace::Bwdif),
+                _ => None,
+            };
+            match deinterlace {
+                Some(deinterlace) => {
+                    self.settings.deinterlace = deinterlace;
+                    self.record_mapped(
+                        "PictureDeinterlaceFilter",
+                        value,
+                        deinterlace.display_name(),
+                    );
+                }
+                None => self.record_ignored(
+                    "PictureDeinterlaceFilter",
+                    value,
+                    "対応するインターレース解除がありません",
+                ),
+            }
+        }
+    }
+
+    /// 音声（最初のトラックのみ）
+    fn map_audio(&mut self, preset: &Map<String, Value>) {
+        let Some(tracks) = preset.get("AudioList").and_then(Value::as_array) else {
+            return;
+        };
+        if tracks.len() > 1 {
+            self.record_ignored(
+                "AudioList",
+                format!("{}トラック", tracks.len()),
+                "2番目以降の音声トラックは使いません",
+            );
+        }
+        let Some(track) = tracks.first().and_then(Value::as_object) else {
+            return;
+        };
+
+        if let Some(encoder) = string(track, "AudioEncoder") {
+            let codec = match encoder {
+                "av_aac" | "ca_aac" | "ca_haac" | "fdk_aac" | "fdk_haac" => Some(AudioCodec::Aac),
+                "mp3" => Some(AudioCodec::Mp3),
+                "flac16" | "flac24" => Some(AudioCodec::Flac),
+                e if e.starts_with("copy") => Some(AudioCodec::Copy),
+                _ => None,
+            };
+            match codec {
+                Some(codec) => {
+                    self.settings.audio_codec = codec;
+                    self.record_mapped("AudioEncoder", encoder, codec.display_name());
+                }
+                None => {
+                    self.record_ignored("AudioEncoder", encoder, "対応していない音声コーデック");
+                    return;
+                }
+            }
+        }
+        if self.settings.audio_codec == AudioCodec::Copy {
+            return;
+        }
+
+        // ビットレートは選択肢のうち最も近いものにする
+        if let Some(bitrate) = number(track, "AudioBitrate").filter(|b| *b > 0.0) {
+            if let Some(&(value, name)) = audio_bitrate_options()
+                .iter()
+                .min_by_key(|(value, _)| (*value as f64 - bitrate).abs() as u64)
+            {
+                self.settings.audio_bitrate = value;
+                self.record_mapped("AudioBitrate", bitrate, name);
+            }
+        }
+
+        if let Some(mixdown) = string(track, "AudioMixdown").filter(|m| *m != "none") {
+            let channels = match mixdown {
+                "mono" | "left_only" | "right_only" => Some(AudioChannels::Mono),
+                "stereo" | "dpl1" | "dpl2" => Some(AudioChannels::Stereo),
+                "5point1" => Some(AudioChannels::Surround51),
+                _ => None,
+            };
+            match channels {
+                Some(channels) => {
+                    self.settings.audio_channels = channels;
+                    self.record_mapped("AudioMixdown", mixdown, channels.display_name());
+                }
+                None => {
+                    self.record_ignored("AudioMixdown", mixdown, "対応するチャンネル数がありません")
+                }
+            }
+        }
+
+        if let Some(value) = string(track, "AudioSamplerate").filter(|r| *r != "auto" && *r != "0")
+        {
+            let rate = match value {
+                "44.1" => Some(44_100),
+                "48" => Some(48_000),
+                _ => None,
+            };
+            match rate {
+                Some(rate) => {
+                    self.settings.audio_sample_rate = Some(rate);
+                    self.record_mapped("AudioSamplerate", value, format!("{} Hz", rate));
+                }
+                None => {
+                    self.record_ignored("AudioSamplerate", value, "対応していないサンプルレート")
+                }
+            }
+        }
+    }
+
+    /// 対応する設定がないフィルター・字幕
+    fn map_unsupported(&mut self, preset: &Map<String, Value>) {
+        for key in UNSUPPORTED_FILTERS {
+            if let Some(value) = string(preset, key).filter(|v| *v != "off") {
+                self.record_ignored(key, value, "対応するフィルターがありません");
+            }
+        }
+        if let Some(subtitles) = preset
+            .get("SubtitleList")
+            .and_then(Value::as_array)
+            .filter(|list| !list.is_empty())
+        {
+            self.record_ignored(
+                "SubtitleList",
+                format!("{}トラック", subtitles.len()),
+                "字幕の設定には対応していません",
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcoder::encoder_crf;
+
+    const PRESET: &str = r#"{
+    "PresetList": [
+        {
+            "Folder": true,
+            "PresetName": "My Presets",
+            "ChildrenArray": [
+                {
+                    "PresetName": "Archive x265",
+                    "FileFormat": "av_mkv",
+                    "ChapterMarkers": true,
+                    "VideoEncoder": "x265_10bit",
+                    "VideoQualityType": 2,
+                    "VideoQualitySlider": 20.0,
+                    "VideoPreset": "slower",
+                    "VideoTune": "animation",
+                    "VideoFramerate": "23.976",
+                    "PictureWidth": 1920,
+                    "PictureHeight": 1080,
+                    "PictureDeinterlaceFilter": "decomb",
+                    "PictureDenoiseFilter": "nlmeans",
+                    "AudioList": [
+                        {
+                            "AudioEncoder": "av_aac",
+                            "AudioBitrate": 200,
+                            "AudioMixdown": "stereo",
+                            "AudioSamplerate": "48"
+                        },
+                        {
+                            "AudioEncoder": "copy:ac3"
+                        }
+                    ]
+                }
+            ]
+        }
+    ],
+    "VersionMajor": 47
+}"#;
+
+    #[test]
+    fn test_import_handbrake_preset() {
+        let import = read_handbrake_preset(PRESET).unwrap();
+        assert_eq!(import.name, "Archive x265");
+        assert_eq!(
+            import_handbrake_preset(PRESET).unwrap().crf,
+            import.settings.crf
+        );
+
+        let settings = &import.settings;
+        assert_eq!(settings.container, ContainerFormat::Mkv);
+        assert_eq!(settings.video_codec, VideoCodec::H265);
+        assert_eq!(settings.hwaccel, HwAccelType::Software);
+        assert_eq!(settings.pixel_format, PixelFormat::Yuv420p10le);
+        assert_eq!(settings.rate_control, RateControlMode::Crf);
+        assert_eq!(settings.crf, 20);
+        assert_eq!(settings.preset, VideoPreset::Slow);
+        assert_eq!(settings.x264_tune, X264Tune::Animation);
+        assert_eq!(settings.frame_rate, FrameRate::Fps23_976);
+        assert_eq!(settings.resolution, VideoResolution::Fhd1080);
+        assert_eq!(settings.deinterlace, Deinterlace::Auto);
+        assert_eq!(settings.audio_codec, AudioCodec::Aac);
+        assert_eq!(settings.audio_bitrate, 192);
+        assert_eq!(settings.audio_channels, AudioChannels::Stereo);
+        assert_eq!(settings.audio_sample_rate, Some(48_000));
+
+        // ノイズ除去と2番目の音声トラックは反映できない
+        assert_eq!(import.ignored.len(), 2, "{:?}", import.ignored);
+        assert!(import
+            .ignored
+            .iter()
+            .any(|i| i.starts_with("PictureDenoiseFilter")));
+        assert!(import.ignored.iter().any(|i| i.starts_with("AudioList")));
+    }
+
+    #[test]
+    fn test_import_handbrake_bitrate_and_hw_encoder() {
+        let json = r#"{
+    "PresetList": [
+        {
+            "PresetName": "NVENC AV1",
+            "VideoEncoder": "nvenc_av1",
+            "VideoQualityType": 1,
+            "VideoAvgBitrate": 6000,
+            "VideoMultiPass": true,
+            "VideoPreset": "p6",
+            "PictureWidth": 1440,
+            "PictureHeight": 1080,
+            "AudioList": [{ "AudioEncoder": "opus", "AudioBitrate": 128 }]
+        }
+    ]
+}"#;
+        let import = read_handbrake_preset(json).unwrap();
+        let settings = &import.settings;
+        assert_eq!(settings.video_codec, VideoCodec::Av1);
+        assert_eq!(settings.hwaccel, HwAccelType::Nvenc);
+        assert_eq!(settings.rate_control, RateControlMode::Vbr);
+        assert_eq!(settings.target_bitrate, 6000);
+        assert!(settings.max_bitrate >= 6000);
+        assert_eq!(settings.preset, VideoPreset::Slow);
+        assert_eq!(settings.resolution, VideoResolution::Custom(0, 1080));
+        // Opusは使えないため音声は既定のまま
+        assert_eq!(settings.audio_codec, AudioCodec::Aac);
+        assert_eq!(import.ignored.len(), 2, "{:?}", import.ignored);
+    }
+
+    #[test]
+    fn test_import_handbrake_av1_slider_uses_encoder_scale() {
+        let json =
+            r#"{ "VideoEncoder": "svt_av1", "VideoQualityType": 2, "VideoQualitySlider": 30 }"#;
+        let import = read_handbrake_preset(json).unwrap();
+        assert_eq!(import.name, "HandBrake");
+        // 換算するとスライダーの値（AV1の尺度）以上になる
+        let crf = import.settings.crf;
+        assert!(encoder_crf(crf, "libsvtav1") >= 30);
+        assert!(crf == 0 || encoder_crf(crf - 1, "libsvtav1") < 30);
+    }
+
+    #[test]
+    fn test_import_handbrake_rejects_non_preset() {
+        assert!(import_handbrake_preset("not json").is_err());
+        assert!(import_handbrake_preset(r#"{ "PresetList": [] }"#).is_err());
+        // 変換設定のJSONはHandBrakeのプリセットとして扱わない
+        let settings = serde_json::to_string(&TranscodeSettings::default()).unwrap();
+        assert!(import_handbrake_preset(&settings).is_err());
+    }
+}
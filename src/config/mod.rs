@@ -1,5 +1,17 @@
 //! 設定モジュール
 
+mod calibration;
+mod handbrake;
+mod presets;
+mod saved_queue;
 mod settings;
+mod thumbnail_cache;
+mod watch_history;
 
-pub use settings::Settings;
+pub use calibration::SizeCalibration;
+pub use handbrake::{import_handbrake_preset, read_handbrake_preset, HandBrakeImport};
+pub use presets::PresetStore;
+pub use saved_queue::SavedQueue;
+pub use settings::{open_path, PostFileAction, PostTranscodeAction, Settings};
+pub use thumbnail_cache::ThumbnailCache;
+pub use watch_history::WatchHistory;
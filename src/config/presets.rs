@@ -0,0 +1,143 @@
+//! ユーザー定義のエンコードプリセット（JSON保存）
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::Settings;
+use crate::transcoder::TranscodeSettings;
+
+/// 名前付きプリセット
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct NamedPreset {
+    /// プリセット名
+    name: String,
+    /// 保存時のエンコード設定
+    settings: TranscodeSettings,
+}
+
+/// プリセットの保存先
+#[derive(Clone, Debug, Default)]
+pub struct PresetStore {
+    /// 保存先ファイルのパス
+    path: PathBuf,
+    /// 保存順のプリセット一覧
+    presets: Vec<NamedPreset>,
+}
+
+impl PresetStore {
+    /// 設定ディレクトリのプリセットファイルを開く
+    pub fn open() -> Result<Self> {
+        let path = Settings::config_path()?.with_file_name("presets.json");
+        Self::open_at(path)
+    }
+
+    /// 指定したファイルのプリセットを開く（存在しなければ空）
+    pub fn open_at(path: PathBuf) -> Result<Self> {
+        let presets = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, presets })
+    }
+
+    /// プリセット名の一覧（保存順）
+    pub fn list_presets(&self) -> Vec<&str> {
+        self.presets.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    /// プリセットを取得
+    pub fn load_preset(&self, name: &str) -> Option<TranscodeSettings> {
+        self.presets
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.settings.clone())
+    }
+
+    /// プリセットを保存（同名があれば上書き）
+    pub fn save_preset(&mut self, name: &str, settings: &TranscodeSettings) -> Result<()> {
+        match self.presets.iter_mut().find(|p| p.name == name) {
+            Some(preset) => preset.settings = settings.clone(),
+            None => self.presets.push(NamedPreset {
+                name: name.to_string(),
+                settings: settings.clone(),
+            }),
+        }
+        self.write()
+    }
+
+    /// プリセットを削除（存在しなかった場合はfalse）
+    pub fn delete_preset(&mut self, name: &str) -> Result<bool> {
+        let len = self.presets.len();
+        self.presets.retain(|p| p.name != name);
+        if self.presets.len() == len {
+            return Ok(false);
+        }
+        self.write()?;
+        Ok(true)
+    }
+
+    /// 未使用の「プリセット N」形式の名前を生成
+    pub fn next_preset_name(&self) -> String {
+        (1..)
+            .map(|n| format!("プリセット {}", n))
+            .find(|name| self.presets.iter().all(|p| &p.name != name))
+            .unwrap_or_default()
+    }
+
+    /// ファイルに書き込む
+    fn write(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.presets)?;
+        std::fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcoder::VideoCodec;
+
+    #[test]
+    fn test_presets_survive_reopen() {
+        let dir = std::env::temp_dir().join(format!("kamaitachi-presets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("presets.json");
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = PresetStore::open_at(path.clone()).unwrap();
+        assert!(store.list_presets().is_empty());
+        assert_eq!(store.next_preset_name(), "プリセット 1");
+
+        let mut archive = TranscodeSettings {
+            video_codec: VideoCodec::Av1,
+            crf: 30,
+            ..Default::default()
+        };
+        store.save_preset("Archive AV1", &archive).unwrap();
+        store
+            .save_preset("Phone H.264", &TranscodeSettings::default())
+            .unwrap();
+
+        let mut store = PresetStore::open_at(path.clone()).unwrap();
+        assert_eq!(store.list_presets(), vec!["Archive AV1", "Phone H.264"]);
+        let loaded = store.load_preset("Archive AV1").unwrap();
+        assert_eq!(loaded.video_codec, VideoCodec::Av1);
+        assert_eq!(loaded.crf, 30);
+
+        // 同名は上書き
+        archive.crf = 28;
+        store.save_preset("Archive AV1", &archive).unwrap();
+        assert_eq!(store.list_presets().len(), 2);
+        assert_eq!(store.load_preset("Archive AV1").unwrap().crf, 28);
+
+        assert!(store.delete_preset("Phone H.264").unwrap());
+        assert!(!store.delete_preset("Phone H.264").unwrap());
+        let store = PresetStore::open_at(path).unwrap();
+        assert_eq!(store.list_presets(), vec!["Archive AV1"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
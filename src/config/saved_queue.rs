@@ -0,0 +1,145 @@
+//! 未完了の変換キュー（JSON保存）
+//!
+//! 変換中にアプリが終了・クラッシュしても、次回起動時にキューを復元できるようにする
+
+use anyhow::Result;
+use std::path::PathBuf;
+
+use super::Settings;
+use crate::app::{FileEntry, FileStatus};
+
+/// 保存された変換キュー
+#[derive(Clone, Debug)]
+pub struct SavedQueue {
+    /// 保存先ファイルのパス
+    path: PathBuf,
+}
+
+impl SavedQueue {
+    /// 設定ディレクトリのキューファイルを開く
+    pub fn open() -> Result<Self> {
+        let path = Settings::config_path()?.with_file_name("queue.json");
+        Ok(Self::open_at(path))
+    }
+
+    /// 指定したファイルを保存先にする
+    pub fn open_at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// 保存されたキューを読み込む（なければ空）
+    /// 処理中だったファイルは待機中に戻し、見つからなくなったファイルはエラーにする
+    pub fn load(&self) -> Result<Vec<FileEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&self.path)?;
+        let mut files: Vec<FileEntry> = serde_json::from_str(&content)?;
+        for file in &mut files {
+            if !file.path.exists() {
+                file.status =
+                    FileStatus::Error(format!("ファイルが見つかりません: {}", file.path.display()));
+            } else if file.status == FileStatus::Processing {
+                file.status = FileStatus::Pending;
+            }
+        }
+        Ok(files)
+    }
+
+    /// キューを保存（未完了のファイルがなければ保存済みのキューを削除）
+    pub fn save(&self, files: &[FileEntry]) -> Result<()> {
+        if files.iter().all(|f| f.status.is_completed()) {
+            return self.clear();
+        }
+        let files: Vec<FileEntry> = files
+            .iter()
+            .cloned()
+            .map(|mut file| {
+                // 途中で終了した変換は最初からやり直す
+                if file.status == FileStatus::Processing {
+                    file.status = FileStatus::Pending;
+                }
+                file
+            })
+            .collect();
+        let content = serde_json::to_string_pretty(&files)?;
+        std::fs::write(&self.path, content)?;
+        Ok(())
+    }
+
+    /// 保存済みのキューを削除
+    pub fn clear(&self) -> Result<()> {
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_restores_unfinished_queue() {
+        let dir =
+            std::env::temp_dir().join(format!("kamaitachi-saved-queue-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("queue.json");
+        let existing = dir.join("a.mkv");
+        std::fs::write(&existing, b"").unwrap();
+
+        let mut processing = FileEntry::new(existing.clone());
+        processing.status = FileStatus::Processing;
+        let mut missing = FileEntry::new(dir.join("missing.mkv"));
+        missing.status = FileStatus::Pending;
+
+        let store = SavedQueue::open_at(path.clone());
+        assert!(store.load().unwrap().is_empty());
+        store.save(&[processing, missing]).unwrap();
+
+        let files = store.load().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, existing);
+        assert_eq!(files[0].status, FileStatus::Pending);
+        assert!(matches!(&files[1].status, FileStatus::Error(msg) if msg.contains("missing.mkv")));
+
+        // すべて完了したら保存済みのキューは残さない
+        let mut completed = FileEntry::new(existing);
+        completed.status = FileStatus::completed(None, 0, None);
+        store.save(&[completed]).unwrap();
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_keeps_non_utf8_paths() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = std::env::temp_dir().join(format!(
+            "kamaitachi-saved-queue-non-utf8-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join(OsStr::from_bytes(b"movie_\xff.mkv"));
+        std::fs::write(&input, b"").unwrap();
+
+        let mut entry = FileEntry::new(input.clone());
+        entry.output_path = Some(dir.join(OsStr::from_bytes(b"out_\xfe.mp4")));
+        let store = SavedQueue::open_at(dir.join("queue.json"));
+        store.save(&[entry]).unwrap();
+
+        let files = store.load().unwrap();
+        assert_eq!(files[0].path, input);
+        assert_eq!(files[0].status, FileStatus::Pending);
+        assert_eq!(
+            files[0].output_path.as_deref(),
+            Some(dir.join(OsStr::from_bytes(b"out_\xfe.mp4")).as_path())
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,11 +1,20 @@
 //! アプリケーション設定（JSON保存）
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::ffmpeg::ProcessPriority;
+use crate::transcoder::{is_same_path, OverwritePolicy};
+
+/// 最近使ったファイルとして残す件数
+pub const MAX_RECENT_FILES: usize = 20;
 
 /// アプリケーション設定
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     /// FFmpegのカスタムパス
     pub ffmpeg_custom_path: Option<PathBuf>,
@@ -23,10 +32,50 @@ pub struct Settings {
     pub window_height: Option<u32>,
     /// ダークモード
     pub dark_mode: bool,
-    /// 処理完了時に通知
+    /// 設定パネルの幅
+    pub settings_panel_width: f32,
+    /// 処理完了時にデスクトップ通知を表示
     pub notify_on_complete: bool,
+    /// ファイルごとのエラーをデスクトップ通知で表示
+    pub notify_on_error: bool,
     /// 処理完了後にシャットダウン
     pub shutdown_on_complete: bool,
+    /// キュー完了後の動作
+    pub post_transcode_action: PostTranscodeAction,
+    /// 変換に成功したファイルの元ファイルの扱い
+    pub post_file_action: PostFileAction,
+    /// 監視フォルダ（新しい動画を自動でキューに追加）
+    pub watch_folder: Option<PathBuf>,
+    /// 監視フォルダからの追加時に自動で変換を開始
+    pub watch_folder_auto_start: bool,
+    /// 監視フォルダのファイルに適用するプリセット名（Noneなら現在の設定）
+    pub watch_folder_preset: Option<String>,
+    /// エラー時に途中までの出力ファイルを残す
+    pub keep_partial_on_error: bool,
+    /// 起動時の上書きポリシー（前回の選択。変換には変換設定の値を使う）
+    pub overwrite_policy: OverwritePolicy,
+    /// 変換後に出力ファイルを検証
+    pub verify_output: bool,
+    /// 変換後に元の動画と比較して画質を評価（VMAF/SSIM）
+    /// 変換時間がおよそ倍になるため既定では無効
+    #[serde(alias = "quality_analysis")]
+    pub run_vmaf_after_encode: bool,
+    /// 画質評価でサンプリングする区間の数
+    pub quality_samples: u32,
+    /// 変換プロセスの優先度（Windowsは優先度クラス、それ以外はnice値）
+    pub encode_priority: ProcessPriority,
+    /// 最近変換に成功したファイル（新しい順）
+    pub recent_files: VecDeque<PathBuf>,
+    /// HWエンコーダーが変換中に失敗したらソフトウェアエンコードで再試行
+    pub auto_fallback_to_software: bool,
+    /// 変換に失敗したファイルを自動で再試行する回数（0なら再試行しない）
+    pub retry_on_failure: u8,
+    /// 再試行までの待ち時間（秒）
+    pub retry_delay_secs: u32,
+    /// 同時に変換するファイル数
+    pub parallel_jobs: u32,
+    /// NVENCの同時セッション数の上限（0ならGPUとドライバーから自動で判定）
+    pub nvenc_session_limit: u32,
 }
 
 impl Default for Settings {
@@ -40,8 +89,27 @@ impl Default for Settings {
             window_width: Some(1200),
             window_height: Some(800),
             dark_mode: true,
+            settings_panel_width: 360.0,
             notify_on_complete: true,
+            notify_on_error: false,
             shutdown_on_complete: false,
+            post_transcode_action: PostTranscodeAction::Nothing,
+            post_file_action: PostFileAction::Nothing,
+            watch_folder: None,
+            watch_folder_auto_start: false,
+            watch_folder_preset: None,
+            keep_partial_on_error: false,
+            overwrite_policy: OverwritePolicy::default(),
+            verify_output: false,
+            run_vmaf_after_encode: false,
+            quality_samples: 4,
+            encode_priority: ProcessPriority::default(),
+            recent_files: VecDeque::new(),
+            auto_fallback_to_software: true,
+            retry_on_failure: 0,
+            retry_delay_secs: 5,
+            parallel_jobs: 1,
+            nvenc_session_limit: 0,
         }
     }
 }
@@ -93,7 +161,11 @@ impl Settings {
 
         if path.exists() {
             let content = std::fs::read_to_string(&path)?;
-            let settings: Settings = serde_json::from_str(&content)?;
+            let mut settings: Settings = serde_json::from_str(&content)?;
+            // スリープ・シャットダウンなどはその日の一括変換向けのため次回起動時に引き継がない
+            if settings.post_transcode_action.is_one_shot() {
+                settings.post_transcode_action = PostTranscodeAction::Nothing;
+            }
             Ok(settings)
         } else {
             Ok(Self::default())
@@ -107,4 +179,289 @@ impl Settings {
         std::fs::write(&path, content)?;
         Ok(())
     }
+
+    /// 最近使ったファイルの先頭に追加（同じファイルは先頭に移動し、上限を超えた古いものは削除）
+    pub fn add_recent_file(&mut self, path: PathBuf) {
+        self.recent_files.retain(|p| *p != path);
+        self.recent_files.push_front(path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+}
+
+/// キュー完了後の動作
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PostTranscodeAction {
+    /// 何もしない
+    Nothing,
+    /// 出力フォルダを開く
+    OpenFolder,
+    /// スリープ
+    Sleep,
+    /// シャットダウン
+    Shutdown,
+    /// アプリを終了
+    CloseApp,
+    /// 任意のコマンドを実行
+    RunScript(String),
+}
+
+impl PostTranscodeAction {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PostTranscodeAction::Nothing => "何もしない",
+            PostTranscodeAction::OpenFolder => "フォルダを開く",
+            PostTranscodeAction::Sleep => "スリープ",
+            PostTranscodeAction::Shutdown => "シャットダウン",
+            PostTranscodeAction::CloseApp => "アプリを終了",
+            PostTranscodeAction::RunScript(_) => "スクリプト実行",
+        }
+    }
+
+    /// 実行前に猶予を設け、次回起動時に引き継がない動作か
+    pub fn is_one_shot(&self) -> bool {
+        matches!(
+            self,
+            PostTranscodeAction::Sleep
+                | PostTranscodeAction::Shutdown
+                | PostTranscodeAction::CloseApp
+        )
+    }
+
+    /// 動作を実行
+    pub fn execute(&self, output_dir: Option<&Path>) -> Result<()> {
+        match self {
+            PostTranscodeAction::Nothing => Ok(()),
+            PostTranscodeAction::OpenFolder => {
+                let dir = output_dir.context("No output folder to open")?;
+                open_path(dir)
+            }
+            PostTranscodeAction::Sleep => sleep_system(),
+            PostTranscodeAction::Shutdown => shutdown_system(),
+            // アプリの終了は呼び出し側（UI）で行う
+            PostTranscodeAction::CloseApp => Ok(()),
+            PostTranscodeAction::RunScript(script) => {
+                #[cfg(target_os = "windows")]
+                let mut command = {
+                    let mut command = Command::new("cmd");
+                    command.args(["/C", script]);
+                    command
+                };
+                #[cfg(not(target_os = "windows"))]
+                let mut command = {
+                    let mut command = Command::new("sh");
+                    command.args(["-c", script]);
+                    command
+                };
+                command
+                    .spawn()
+                    .with_context(|| format!("Failed to run script: {}", script))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Default for PostTranscodeAction {
+    fn default() -> Self {
+        PostTranscodeAction::Nothing
+    }
+}
+
+/// 変換に成功したファイルの元ファイルの扱い
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PostFileAction {
+    /// 何もしない
+    Nothing,
+    /// 元ファイルを削除
+    DeleteSource,
+    /// 元ファイルを指定したフォルダに移動
+    MoveSourceTo(PathBuf),
+}
+
+impl PostFileAction {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PostFileAction::Nothing => "何もしない",
+            PostFileAction::DeleteSource => "元ファイルを削除",
+            PostFileAction::MoveSourceTo(_) => "元ファイルを移動",
+        }
+    }
+
+    /// 元ファイルに動作を適用
+    /// 出力先が元ファイルと同じ場合は変換結果を失うため何もせずエラーにする
+    pub fn apply(&self, source: &Path, output: &Path) -> Result<()> {
+        if *self == PostFileAction::Nothing {
+            return Ok(());
+        }
+        if is_same_path(source, output) {
+            bail!("出力先が元ファイルと同じため、元ファイルを処理しません");
+        }
+
+        match self {
+            PostFileAction::Nothing => Ok(()),
+            PostFileAction::DeleteSource => std::fs::remove_file(source)
+                .with_context(|| format!("{} を削除できません", source.display())),
+            PostFileAction::MoveSourceTo(dir) => {
+                let file_name = source.file_name().context("元ファイルの名前がありません")?;
+                let dest = dir.join(file_name);
+                if dest.exists() {
+                    bail!("移動先に同名のファイルがあります: {}", dest.display());
+                }
+                std::fs::create_dir_all(dir)?;
+                // 別のドライブへはrenameできないため、コピーしてから削除する
+                if std::fs::rename(source, &dest).is_err() {
+                    std::fs::copy(source, &dest)
+                        .with_context(|| format!("{} に移動できません", dest.display()))?;
+                    std::fs::remove_file(source)
+                        .with_context(|| format!("{} を削除できません", source.display()))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Default for PostFileAction {
+    fn default() -> Self {
+        PostFileAction::Nothing
+    }
+}
+
+/// ファイルを既定のアプリで開く（フォルダはファイルマネージャーで開く）
+/// Windowsのexplorerはファイルを渡すと関連付けられたアプリで開く（startと同じ動作）
+pub fn open_path(path: &Path) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+    let program = "xdg-open";
+
+    Command::new(program)
+        .arg(path)
+        .spawn()
+        .with_context(|| format!("Failed to open: {:?}", path))?;
+    Ok(())
+}
+
+/// システムをスリープ
+fn sleep_system() -> Result<()> {
+    #[cfg(target_os = "windows")]
+    let result = Command::new("rundll32.exe")
+        .args(["powrprof.dll,SetSuspendState", "0,1,0"])
+        .spawn();
+    #[cfg(target_os = "macos")]
+    let result = Command::new("pmset").arg("sleepnow").spawn();
+    #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+    let result = Command::new("systemctl").arg("suspend").spawn();
+
+    result.context("Failed to put the system to sleep")?;
+    Ok(())
+}
+
+/// システムをシャットダウン
+/// 取り消し用の猶予はアプリ側のカウントダウンで設けるため、ここでは即時に実行する
+fn shutdown_system() -> Result<()> {
+    #[cfg(target_os = "windows")]
+    let result = Command::new("shutdown").args(["/s", "/t", "0"]).spawn();
+    #[cfg(target_os = "macos")]
+    let result = Command::new("osascript")
+        .args(["-e", "tell app \"System Events\" to shut down"])
+        .spawn();
+    #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+    let result = Command::new("systemctl").arg("poweroff").spawn();
+
+    result.context("Failed to shut down the system")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_file_action() {
+        let dir = std::env::temp_dir().join(format!(
+            "kamaitachi-post-file-action-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("in.mkv");
+        let output = dir.join("in_converted.mp4");
+        std::fs::write(&source, b"source").unwrap();
+        std::fs::write(&output, b"output").unwrap();
+
+        // 出力先が元ファイルと同じなら削除しない
+        assert!(PostFileAction::DeleteSource
+            .apply(&source, &source)
+            .is_err());
+        assert!(source.exists());
+
+        // 移動（移動先のフォルダは作成する）
+        let archive = dir.join("archive");
+        PostFileAction::MoveSourceTo(archive.clone())
+            .apply(&source, &output)
+            .unwrap();
+        assert!(!source.exists());
+        assert_eq!(std::fs::read(archive.join("in.mkv")).unwrap(), b"source");
+
+        // 移動先に同名のファイルがあれば上書きしない
+        std::fs::write(&source, b"second").unwrap();
+        assert!(PostFileAction::MoveSourceTo(archive.clone())
+            .apply(&source, &output)
+            .is_err());
+        assert!(source.exists());
+
+        PostFileAction::DeleteSource
+            .apply(&source, &output)
+            .unwrap();
+        assert!(!source.exists());
+        assert!(output.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_add_recent_file() {
+        let mut settings = Settings::default();
+        for i in 0..MAX_RECENT_FILES + 5 {
+            settings.add_recent_file(PathBuf::from(format!("{}.mkv", i)));
+        }
+        assert_eq!(settings.recent_files.len(), MAX_RECENT_FILES);
+        assert_eq!(
+            settings.recent_files.front(),
+            Some(&PathBuf::from(format!("{}.mkv", MAX_RECENT_FILES + 4)))
+        );
+        assert_eq!(settings.recent_files.back(), Some(&PathBuf::from("5.mkv")));
+
+        // 同じファイルは重複させずに先頭へ移動
+        settings.add_recent_file(PathBuf::from("10.mkv"));
+        assert_eq!(settings.recent_files.len(), MAX_RECENT_FILES);
+        assert_eq!(
+            settings.recent_files.front(),
+            Some(&PathBuf::from("10.mkv"))
+        );
+        assert_eq!(
+            settings
+                .recent_files
+                .iter()
+                .filter(|p| p.as_path() == Path::new("10.mkv"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_retry_defaults_for_old_settings() {
+        // 再試行・フォールバックの項目がない古い設定ファイルでも既定値で読み込める
+        let settings: Settings = serde_json::from_str(r#"{"dark_mode": true}"#).unwrap();
+        assert_eq!(settings.retry_on_failure, 0);
+        assert_eq!(settings.retry_delay_secs, 5);
+        assert!(settings.auto_fallback_to_software);
+        assert_eq!(settings.parallel_jobs, 1);
+        assert_eq!(settings.nvenc_session_limit, 0);
+    }
 }
@@ -0,0 +1,102 @@
+//! サムネイルのディスクキャッシュ（PNG保存）
+//!
+//! 動画のパスと更新日時から求めたキーで保存し、同じファイルを再び追加したときはFFmpegを起動せずに読み込む
+
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Settings;
+
+/// サムネイルのキャッシュ
+#[derive(Clone, Debug)]
+pub struct ThumbnailCache {
+    /// 保存先ディレクトリ
+    dir: PathBuf,
+}
+
+impl ThumbnailCache {
+    /// アプリケーションデータディレクトリのキャッシュを開く
+    pub fn open() -> Result<Self> {
+        Self::open_at(Settings::app_data_dir()?.join("thumbnails"))
+    }
+
+    /// 指定したディレクトリのキャッシュを開く（存在しなければ作成）
+    pub fn open_at(dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// 保存済みのサムネイルを読み込む（未保存・動画が更新された場合はNone）
+    pub fn load(&self, video: &Path) -> Option<Vec<u8>> {
+        let bytes = std::fs::read(self.entry_path(video)?).ok()?;
+        (!bytes.is_empty()).then_some(bytes)
+    }
+
+    /// サムネイルを保存
+    pub fn store(&self, video: &Path, png: &[u8]) -> Result<()> {
+        let path = self
+            .entry_path(video)
+            .context("Failed to read modification time")?;
+        std::fs::write(path, png)?;
+        Ok(())
+    }
+
+    /// 動画に対応するキャッシュファイルのパス
+    fn entry_path(&self, video: &Path) -> Option<PathBuf> {
+        let modified = std::fs::metadata(video).and_then(|m| m.modified()).ok()?;
+        Some(self.dir.join(format!("{}.png", cache_key(video, modified))))
+    }
+}
+
+/// パスと更新日時から求めたキー
+/// ハッシュの実装が変わってもサムネイルを作り直すだけなので、標準のハッシュを使う
+fn cache_key(video: &Path, modified: SystemTime) -> String {
+    let mut hasher = DefaultHasher::new();
+    video.hash(&mut hasher);
+    modified
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_store_and_load_by_path_and_mtime() {
+        let dir =
+            std::env::temp_dir().join(format!("kamaitachi-thumbnail-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = ThumbnailCache::open_at(dir.join("cache")).unwrap();
+        let video = dir.join("episode 01.mkv");
+        let other = dir.join("episode 02.mkv");
+        std::fs::write(&video, b"video").unwrap();
+        std::fs::write(&other, b"video").unwrap();
+
+        assert_eq!(cache.load(&video), None);
+        cache.store(&video, b"png").unwrap();
+        assert_eq!(cache.load(&video), Some(b"png".to_vec()));
+        assert_eq!(cache.load(&other), None);
+
+        // 動画が更新されたら作り直す
+        std::fs::File::options()
+            .write(true)
+            .open(&video)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(cache.load(&video), None);
+
+        // 存在しない動画は保存しない
+        assert!(cache.store(&dir.join("missing.mkv"), b"png").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
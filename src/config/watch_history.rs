@@ -0,0 +1,120 @@
+//! 監視フォルダから追加済みのファイル（JSON保存）
+//!
+//! 再起動後も同じファイルを再びキューに追加しないように記録する
+
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use super::Settings;
+
+/// 監視フォルダの処理履歴
+#[derive(Clone, Debug, Default)]
+pub struct WatchHistory {
+    /// 保存先ファイルのパス
+    path: PathBuf,
+    /// 監視フォルダ → 追加済みのファイル
+    folders: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl WatchHistory {
+    /// 設定ディレクトリの履歴ファイルを開く
+    pub fn open() -> Result<Self> {
+        let path = Settings::config_path()?.with_file_name("watch_history.json");
+        Self::open_at(path)
+    }
+
+    /// 指定したファイルの履歴を開く（存在しなければ空）
+    pub fn open_at(path: PathBuf) -> Result<Self> {
+        let folders = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, folders })
+    }
+
+    /// 以前に監視したことのあるフォルダか
+    pub fn has_folder(&self, folder: &Path) -> bool {
+        self.folders.contains_key(folder)
+    }
+
+    /// 追加済みのファイルか
+    pub fn contains(&self, folder: &Path, file: &Path) -> bool {
+        self.folders
+            .get(folder)
+            .is_some_and(|files| files.contains(file))
+    }
+
+    /// 追加済みとして記録
+    pub fn record(
+        &mut self,
+        folder: &Path,
+        files: impl IntoIterator<Item = PathBuf>,
+    ) -> Result<()> {
+        self.folders
+            .entry(folder.to_path_buf())
+            .or_default()
+            .extend(files);
+        self.write()
+    }
+
+    /// フォルダから消えたファイルの記録を削除
+    pub fn retain_existing(&mut self, folder: &Path, existing: &[PathBuf]) -> Result<()> {
+        let Some(files) = self.folders.get_mut(folder) else {
+            return Ok(());
+        };
+        let len = files.len();
+        files.retain(|file| existing.contains(file));
+        if files.len() == len {
+            return Ok(());
+        }
+        self.write()
+    }
+
+    /// ファイルに書き込む
+    fn write(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.folders)?;
+        std::fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_persists_across_open() {
+        let path = std::env::temp_dir().join(format!(
+            "kamaitachi-watch-history-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let folder = PathBuf::from("/incoming");
+
+        let mut history = WatchHistory::open_at(path.clone()).unwrap();
+        assert!(!history.has_folder(&folder));
+        history.record(&folder, Vec::new()).unwrap();
+        history
+            .record(&folder, vec![folder.join("a.mkv"), folder.join("b.mkv")])
+            .unwrap();
+
+        let mut history = WatchHistory::open_at(path.clone()).unwrap();
+        assert!(history.has_folder(&folder));
+        assert!(history.contains(&folder, &folder.join("a.mkv")));
+        assert!(!history.contains(&folder, &folder.join("c.mkv")));
+        assert!(!history.contains(Path::new("/other"), &folder.join("a.mkv")));
+
+        // 消えたファイルは記録から外す
+        history
+            .retain_existing(&folder, &[folder.join("b.mkv")])
+            .unwrap();
+        let history = WatchHistory::open_at(path.clone()).unwrap();
+        assert!(!history.contains(&folder, &folder.join("a.mkv")));
+        assert!(history.contains(&folder, &folder.join("b.mkv")));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
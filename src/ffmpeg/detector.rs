@@ -2,8 +2,45 @@
 
 use anyhow::{anyhow, Context, Result};
 use log::{debug, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use super::background_command;
+
+/// 中断フラグを確認する間隔
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// エンコーダー情報のキャッシュ
+static ENCODER_CACHE: OnceLock<Mutex<EncoderCache>> = OnceLock::new();
+
+/// FFmpegバイナリのパスと更新日時ごとのエンコーダー情報
+/// バイナリが差し替えられた場合は更新日時が変わるため再取得する
+#[derive(Default)]
+struct EncoderCache {
+    /// バイナリごとのエンコーダー一覧
+    encoders: HashMap<PathBuf, (Option<SystemTime>, Arc<HashSet<String>>)>,
+    /// (バイナリ, エンコーダー) ごとの動作テスト結果
+    tests: HashMap<(PathBuf, String), (Option<SystemTime>, bool)>,
+    /// バイナリごとのフィルター一覧
+    filters: HashMap<PathBuf, (Option<SystemTime>, Arc<HashSet<String>>)>,
+}
+
+impl EncoderCache {
+    /// プロセス全体で共有するキャッシュ
+    fn global() -> &'static Mutex<EncoderCache> {
+        ENCODER_CACHE.get_or_init(|| Mutex::new(EncoderCache::default()))
+    }
+}
+
+/// バイナリの更新日時（PATH上のコマンド名など取得できない場合はNone）
+fn modified_time(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
 
 /// FFmpeg検出器
 pub struct FfmpegDetector;
@@ -23,6 +60,8 @@ pub struct FfmpegInfo {
     pub minor_version: u32,
     /// GPLビルドかどうか
     pub is_gpl: bool,
+    /// 中断フラグ（立つと実行中のFFmpeg/ffprobeを終了する）
+    pub cancel: Option<Arc<AtomicBool>>,
 }
 
 impl FfmpegDetector {
@@ -91,6 +130,22 @@ impl FfmpegDetector {
         Err(anyhow!("FFmpeg not found on this system"))
     }
 
+    /// ユーザー指定のパスを優先してFFmpegを検出
+    /// 指定パスが使えない場合は通常の検出にフォールバック
+    pub fn detect_with_custom_path(custom_path: Option<&PathBuf>) -> Result<FfmpegInfo> {
+        if let Some(path) = custom_path {
+            debug!("Checking custom FFmpeg path: {:?}", path);
+            match Self::check_ffmpeg_at_path(path) {
+                Ok(info) => {
+                    info!("Using custom FFmpeg: {:?}", info.ffmpeg_path);
+                    return Ok(info);
+                }
+                Err(e) => warn!("Custom FFmpeg path is not usable: {:#}", e),
+            }
+        }
+        Self::detect()
+    }
+
     /// 指定したパスのFFmpegをチェック
     pub fn check_ffmpeg_at_path(path: &PathBuf) -> Result<FfmpegInfo> {
         Self::get_ffmpeg_info(path)
@@ -125,7 +180,7 @@ impl FfmpegDetector {
         #[cfg(not(target_os = "windows"))]
         let ffmpeg_name = "ffmpeg";
 
-        let output = Command::new(ffmpeg_name)
+        let output = background_command(ffmpeg_name)
             .arg("-version")
             .output()
             .context("Failed to execute ffmpeg")?;
@@ -133,9 +188,9 @@ impl FfmpegDetector {
         if output.status.success() {
             // whereコマンドでパスを取得
             #[cfg(target_os = "windows")]
-            let path_output = Command::new("where").arg("ffmpeg").output()?;
+            let path_output = background_command("where").arg("ffmpeg").output()?;
             #[cfg(not(target_os = "windows"))]
-            let path_output = Command::new("which").arg("ffmpeg").output()?;
+            let path_output = background_command("which").arg("ffmpeg").output()?;
 
             let path_str = String::from_utf8_lossy(&path_output.stdout);
             let ffmpeg_path = PathBuf::from(path_str.lines().next().unwrap_or("ffmpeg").trim());
@@ -148,7 +203,7 @@ impl FfmpegDetector {
 
     /// FFmpegの情報を取得
     fn get_ffmpeg_info(ffmpeg_path: &PathBuf) -> Result<FfmpegInfo> {
-        let output = Command::new(ffmpeg_path)
+        let output = background_command(ffmpeg_path)
             .arg("-version")
             .output()
             .context(format!("Failed to execute {:?}", ffmpeg_path))?;
@@ -210,6 +265,7 @@ impl FfmpegDetector {
             major_version,
             minor_version,
             is_gpl,
+            cancel: None,
         })
     }
 
@@ -217,6 +273,174 @@ impl FfmpegDetector {
     pub fn check_version_requirement(info: &FfmpegInfo, min_major: u32) -> bool {
         info.major_version >= min_major
     }
+
+    /// FFmpegバイナリに含まれるエンコーダー一覧を取得
+    /// バイナリごとにキャッシュし、取得に失敗した場合はキャッシュしない
+    pub fn available_encoders(ffmpeg_path: &PathBuf) -> Arc<HashSet<String>> {
+        let modified = modified_time(ffmpeg_path);
+        if let Ok(cache) = EncoderCache::global().lock() {
+            if let Some((cached_modified, encoders)) = cache.encoders.get(ffmpeg_path) {
+                if *cached_modified == modified {
+                    return encoders.clone();
+                }
+            }
+        }
+
+        let output = match background_command(ffmpeg_path)
+            .args(["-hide_banner", "-encoders"])
+            .output()
+        {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                warn!("ffmpeg -encoders failed with status {}", output.status);
+                return Arc::new(HashSet::new());
+            }
+            Err(e) => {
+                warn!("Failed to run ffmpeg -encoders: {}", e);
+                return Arc::new(HashSet::new());
+            }
+        };
+
+        let encoders = Arc::new(Self::parse_encoder_list(&String::from_utf8_lossy(
+            &output.stdout,
+        )));
+        info!(
+            "Available encoders detected for {:?}: {} encoders",
+            ffmpeg_path,
+            encoders.len()
+        );
+        if let Ok(mut cache) = EncoderCache::global().lock() {
+            cache
+                .encoders
+                .insert(ffmpeg_path.clone(), (modified, encoders.clone()));
+        }
+        encoders
+    }
+
+    /// エンコーダーの動作テスト結果をキャッシュして返す
+    /// 同じバイナリとエンコーダーの組み合わせではテストを1回だけ実行する
+    pub fn cached_encoder_test(
+        ffmpeg_path: &PathBuf,
+        encoder: &str,
+        test: impl FnOnce() -> bool,
+    ) -> bool {
+        let key = (ffmpeg_path.clone(), encoder.to_string());
+        let modified = modified_time(ffmpeg_path);
+        if let Ok(cache) = EncoderCache::global().lock() {
+            if let Some((cached_modified, works)) = cache.tests.get(&key) {
+                if *cached_modified == modified {
+                    return *works;
+                }
+            }
+        }
+
+        // テスト中はロックを保持しない
+        let works = test();
+        if let Ok(mut cache) = EncoderCache::global().lock() {
+            cache.tests.insert(key, (modified, works));
+        }
+        works
+    }
+
+    /// FFmpegバイナリに含まれるフィルター一覧を取得
+    /// エンコーダー一覧と同様にバイナリごとにキャッシュする
+    pub fn available_filters(ffmpeg_path: &PathBuf) -> Arc<HashSet<String>> {
+        let modified = modified_time(ffmpeg_path);
+        if let Ok(cache) = EncoderCache::global().lock() {
+            if let Some((cached_modified, filters)) = cache.filters.get(ffmpeg_path) {
+                if *cached_modified == modified {
+                    return filters.clone();
+                }
+            }
+        }
+
+        let output = match background_command(ffmpeg_path)
+            .args(["-hide_banner", "-filters"])
+            .output()
+        {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                warn!("ffmpeg -filters failed with status {}", output.status);
+                return Arc::new(HashSet::new());
+            }
+            Err(e) => {
+                warn!("Failed to run ffmpeg -filters: {}", e);
+                return Arc::new(HashSet::new());
+            }
+        };
+
+        let filters = Arc::new(Self::parse_filter_list(&String::from_utf8_lossy(
+            &output.stdout,
+        )));
+        debug!(
+            "Available filters detected for {:?}: {} filters",
+            ffmpeg_path,
+            filters.len()
+        );
+        if let Ok(mut cache) = EncoderCache::global().lock() {
+            cache
+                .filters
+                .insert(ffmpeg_path.clone(), (modified, filters.clone()));
+        }
+        filters
+    }
+
+    /// エンコーダー情報のキャッシュを破棄（FFmpegを入れ替えた後に呼ぶ）
+    pub fn invalidate_encoder_cache() {
+        if let Ok(mut cache) = EncoderCache::global().lock() {
+            *cache = EncoderCache::default();
+        }
+        info!("Encoder cache invalidated");
+    }
+
+    /// 指定したFFmpegバイナリのエンコーダー情報だけを破棄（ユーザーがFFmpegを選び直した時に呼ぶ）
+    /// 同じパスのまま中身が差し替えられていても、次回の取得で必ず調べ直す
+    pub fn clear_encoder_cache(ffmpeg_path: &PathBuf) {
+        if let Ok(mut cache) = EncoderCache::global().lock() {
+            cache.encoders.remove(ffmpeg_path);
+            cache.filters.remove(ffmpeg_path);
+            cache.tests.retain(|(path, _), _| path != ffmpeg_path);
+        }
+        debug!("Encoder cache cleared for {:?}", ffmpeg_path);
+    }
+
+    /// FFmpegバイナリに指定したエンコーダーが含まれているかチェック
+    pub fn check_encoder_present(encoder: &str, ffmpeg_path: &PathBuf) -> bool {
+        Self::available_encoders(ffmpeg_path).contains(encoder)
+    }
+
+    /// `ffmpeg -encoders` の出力からエンコーダー名を抽出
+    /// 形式: " V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)"
+    fn parse_encoder_list(stdout: &str) -> HashSet<String> {
+        stdout
+            .lines()
+            // 凡例の後の "------" 区切り以降がエンコーダー一覧
+            .skip_while(|line| !line.trim_start().starts_with("---"))
+            .skip(1)
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let flags = parts.next()?;
+                let name = parts.next()?;
+                (flags.len() == 6).then(|| name.to_string())
+            })
+            .collect()
+    }
+
+    /// `ffmpeg -filters` の出力からフィルター名を抽出
+    /// 形式: " ... libvmaf           VV->V      Calculate the VMAF between two video streams."
+    fn parse_filter_list(stdout: &str) -> HashSet<String> {
+        stdout
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let flags = parts.next()?;
+                let name = parts.next()?;
+                let io = parts.next()?;
+                // 凡例の行（"T.. = Timeline support" など）は入出力の列がない
+                (flags.len() == 3 && io.contains("->")).then(|| name.to_string())
+            })
+            .collect()
+    }
 }
 
 /// ffprobeで取得した動画メタデータ
@@ -238,9 +462,85 @@ pub struct ProbeResult {
     pub video_codec: Option<String>,
     /// 音声コーデック
     pub audio_codec: Option<String>,
+    /// 映像のビット深度
+    pub video_bit_depth: Option<u32>,
+    /// 音声のビット深度（非圧縮・ロスレスのみ）
+    pub audio_bit_depth: Option<u32>,
+    /// 音声のチャンネル数
+    pub audio_channels: Option<u32>,
+    /// ピクセルフォーマット
+    pub pix_fmt: Option<String>,
+    /// 色域（例: bt2020）
+    pub color_primaries: Option<String>,
+    /// 伝達特性（例: smpte2084）
+    pub color_trc: Option<String>,
+    /// 色空間（例: bt2020nc）
+    pub color_space: Option<String>,
+    /// フィールドオーダー（例: progressive, tt）
+    pub field_order: Option<String>,
+    /// HDR10のマスタリングディスプレイ（x265の--master-display形式）
+    pub master_display: Option<String>,
+    /// HDR10のコンテンツ輝度（MaxCLL, MaxFALL）
+    pub max_cll: Option<(u32, u32)>,
+    /// チャプター数
+    pub chapter_count: usize,
 }
 
 impl FfmpegInfo {
+    /// 中断フラグを設定したコピーを取得
+    pub fn with_cancel(&self, cancel: Arc<AtomicBool>) -> Self {
+        Self {
+            cancel: Some(cancel),
+            ..self.clone()
+        }
+    }
+
+    /// コマンドを実行して出力を取得（中断フラグが立ったらプロセスを終了してエラーを返す）
+    fn output(&self, command: &mut Command) -> std::io::Result<Output> {
+        let Some(cancel) = &self.cancel else {
+            return command.output();
+        };
+        if cancel.load(Ordering::Relaxed) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "cancelled",
+            ));
+        }
+
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // パイプが詰まってプロセスが止まらないように別スレッドで読み出す
+        let stdout = child.stdout.take().map(read_to_end_in_thread);
+        let stderr = child.stderr.take().map(read_to_end_in_thread);
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if cancel.load(Ordering::Relaxed) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Interrupted,
+                    "cancelled",
+                ));
+            }
+            std::thread::sleep(CANCEL_POLL_INTERVAL);
+        };
+
+        let join = |handle: Option<std::thread::JoinHandle<Vec<u8>>>| {
+            handle.and_then(|h| h.join().ok()).unwrap_or_default()
+        };
+        Ok(Output {
+            status,
+            stdout: join(stdout),
+            stderr: join(stderr),
+        })
+    }
+
     /// ffprobeで動画のメタデータを取得
     pub fn probe_video(&self, path: &std::path::Path) -> Result<ProbeResult> {
         let ffprobe_path = self
@@ -249,17 +549,20 @@ impl FfmpegInfo {
             .ok_or_else(|| anyhow!("ffprobe not found"))?;
 
         // JSON形式で詳細情報を取得
-        let output = Command::new(ffprobe_path)
-            .args([
-                "-v",
-                "quiet",
-                "-print_format",
-                "json",
-                "-show_format",
-                "-show_streams",
-            ])
-            .arg(path)
-            .output()
+        let output = self
+            .output(
+                background_command(ffprobe_path)
+                    .args([
+                        "-v",
+                        "quiet",
+                        "-print_format",
+                        "json",
+                        "-show_format",
+                        "-show_streams",
+                        "-show_chapters",
+                    ])
+                    .arg(path),
+            )
             .context("Failed to execute ffprobe")?;
 
         if !output.status.success() {
@@ -273,11 +576,122 @@ impl FfmpegInfo {
         Self::parse_probe_json(&json_str)
     }
 
+    /// 指定位置の1フレームを幅160px（縦横比を保持）のPNGとして取得
+    pub fn generate_thumbnail(&self, path: &std::path::Path, at_secs: f64) -> Result<Vec<u8>> {
+        let output = self
+            .output(
+                background_command(&self.ffmpeg_path)
+                    .args([
+                        "-v",
+                        "error",
+                        "-ss",
+                        &format!("{:.2}", at_secs.max(0.0)),
+                        "-i",
+                    ])
+                    .arg(path)
+                    .args([
+                        "-frames:v",
+                        "1",
+                        "-vf",
+                        "scale=160:-1",
+                        "-f",
+                        "image2pipe",
+                        "-c:v",
+                        "png",
+                        "-",
+                    ]),
+            )
+            .context("Failed to execute ffmpeg")?;
+
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(anyhow!(
+                "thumbnail generation failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(output.stdout)
+    }
+
+    /// 末尾の数秒をデコードしてエラーが出ないか確認（途中で切れたファイルの検出用）
+    pub fn decode_tail(&self, path: &std::path::Path, secs: u32) -> Result<()> {
+        let output = self
+            .output(
+                background_command(&self.ffmpeg_path)
+                    .args(["-v", "error", "-sseof", &format!("-{}", secs), "-i"])
+                    .arg(path)
+                    .args(["-f", "null", "-"]),
+            )
+            .context("Failed to execute ffmpeg")?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() || !stderr.trim().is_empty() {
+            return Err(anyhow!("{}", stderr.trim()));
+        }
+
+        Ok(())
+    }
+
+    /// cropdetectで黒帯を検出し、最も多く出現したクロップ領域（幅, 高さ, X, Y）を返す
+    pub fn detect_crop(
+        &self,
+        path: &std::path::Path,
+        at_secs: f64,
+    ) -> Result<(u32, u32, u32, u32)> {
+        let output = self
+            .output(
+                background_command(&self.ffmpeg_path)
+                    .args(["-ss", &format!("{:.2}", at_secs.max(0.0)), "-i"])
+                    .arg(path)
+                    .args(["-vf", "cropdetect", "-frames:v", "200", "-f", "null", "-"]),
+            )
+            .context("Failed to execute ffmpeg")?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            return Err(anyhow!("cropdetect failed: {}", stderr));
+        }
+
+        Self::parse_cropdetect(&stderr).ok_or_else(|| anyhow!("cropdetect produced no result"))
+    }
+
+    /// cropdetectの出力から最頻出の crop=W:H:X:Y をパース
+    fn parse_cropdetect(stderr: &str) -> Option<(u32, u32, u32, u32)> {
+        use std::collections::HashMap;
+
+        let mut counts: HashMap<(u32, u32, u32, u32), usize> = HashMap::new();
+        for line in stderr.lines() {
+            let Some(pos) = line.rfind("crop=") else {
+                continue;
+            };
+            // 黒一色のフレームでは負の値が出るため、パースできないものは無視する
+            let values: Vec<u32> = line[pos + 5..]
+                .trim()
+                .split(':')
+                .map_while(|v| v.parse().ok())
+                .collect();
+            if let [w, h, x, y] = values[..] {
+                if w > 0 && h > 0 {
+                    *counts.entry((w, h, x, y)).or_default() += 1;
+                }
+            }
+        }
+
+        // 同数の場合は面積の大きい方を優先（切り取りすぎを防ぐ）
+        counts
+            .into_iter()
+            .max_by_key(|&((w, h, _, _), count)| (count, w as u64 * h as u64))
+            .map(|(area, _)| area)
+    }
+
     /// ffprobeのJSON出力をパース
     fn parse_probe_json(json_str: &str) -> Result<ProbeResult> {
         use std::collections::HashMap;
 
-        let mut result = ProbeResult::default();
+        let mut result = ProbeResult {
+            chapter_count: Self::count_chapters(json_str),
+            ..Default::default()
+        };
 
         // 簡易JSONパース（serde_json依存を避けるため）
         // format セクションから duration と bit_rate を取得
@@ -351,6 +765,34 @@ impl FfmpegInfo {
                         {
                             result.video_codec = Some(codec);
                         }
+
+                        // ピクセルフォーマットと色情報（HDRの引き継ぎ用）
+                        result.pix_fmt = Self::extract_json_string(&current_stream, "pix_fmt");
+                        result.color_primaries =
+                            Self::extract_json_string(&current_stream, "color_primaries");
+                        result.color_trc =
+                            Self::extract_json_string(&current_stream, "color_transfer");
+                        result.color_space =
+                            Self::extract_json_string(&current_stream, "color_space");
+                        // インターレース判定用
+                        result.field_order =
+                            Self::extract_json_string(&current_stream, "field_order");
+                        // HDR10の静的メタデータ（side_data_list）
+                        result.master_display = Self::parse_master_display(&current_stream);
+                        result.max_cll = Self::extract_json_int(&current_stream, "max_content")
+                            .zip(Self::extract_json_int(&current_stream, "max_average"))
+                            .map(|(cll, fall)| (cll.max(0) as u32, fall.max(0) as u32));
+
+                        // ビット深度（bits_per_raw_sampleがなければpix_fmtから推定）
+                        result.video_bit_depth = Self::extract_json_string_number(
+                            &current_stream,
+                            "bits_per_raw_sample",
+                        )
+                        .map(|d| d as u32)
+                        .or_else(|| {
+                            Self::extract_json_string(&current_stream, "pix_fmt")
+                                .map(|f| Self::bit_depth_from_pix_fmt(&f))
+                        });
                     } else if current_stream.contains("\"codec_type\": \"audio\"")
                         || current_stream.contains("\"codec_type\":\"audio\"")
                     {
@@ -369,6 +811,22 @@ impl FfmpegInfo {
                             {
                                 result.audio_codec = Some(codec);
                             }
+
+                            // 非可逆圧縮の音声は0になるため除外
+                            result.audio_bit_depth = Self::extract_json_string_number(
+                                &current_stream,
+                                "bits_per_raw_sample",
+                            )
+                            .or_else(|| {
+                                Self::extract_json_int(&current_stream, "bits_per_sample")
+                                    .map(|d| d.max(0) as u64)
+                            })
+                            .filter(|&d| d > 0)
+                            .map(|d| d as u32);
+                            result.audio_channels =
+                                Self::extract_json_int(&current_stream, "channels")
+                                    .filter(|&c| c > 0)
+                                    .map(|c| c as u32);
                         }
                     }
 
@@ -392,6 +850,50 @@ impl FfmpegInfo {
         Ok(result)
     }
 
+    /// chapters 配列の要素数を数える（タイトル内の括弧は文字列として無視）
+    fn count_chapters(json_str: &str) -> usize {
+        let Some(start) = json_str.find("\"chapters\"") else {
+            return 0;
+        };
+        let Some(array_start) = json_str[start..].find('[') else {
+            return 0;
+        };
+
+        let mut count = 0;
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        for c in json_str[start + array_start..].chars() {
+            if in_string {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match c {
+                '"' => in_string = true,
+                '[' | '{' => {
+                    // 配列直下のオブジェクトが1つのチャプター
+                    if c == '{' && depth == 1 {
+                        count += 1;
+                    }
+                    depth += 1;
+                }
+                ']' | '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        count
+    }
+
     /// JSON文字列から数値を抽出 ("key": 123.45)
     fn extract_json_number(json: &str, key: &str) -> Option<f64> {
         let pattern = format!("\"{}\":", key);
@@ -428,8 +930,7 @@ impl FfmpegInfo {
         if let Some(pos) = json.find(&pattern) {
             let start = pos + pattern.len();
             let rest = json[start..].trim_start();
-            if rest.starts_with('"') {
-                let content = &rest[1..];
+            if let Some(content) = rest.strip_prefix('"') {
                 if let Some(end) = content.find('"') {
                     return Some(content[..end].to_string());
                 }
@@ -443,6 +944,52 @@ impl FfmpegInfo {
         Self::extract_json_string(json, key)?.parse().ok()
     }
 
+    /// マスタリングディスプレイのside dataをx265の--master-display形式に変換
+    /// 色度は0.00002、輝度は0.0001cd/m²単位
+    /// （例: "G(13250,34500)B(7500,3000)R(34000,16000)WP(15635,16450)L(10000000,1)"）
+    fn parse_master_display(stream: &str) -> Option<String> {
+        // ffprobeは "34000/50000" のような分数で出力する
+        let value = |key: &str, scale: f64| -> Option<u64> {
+            let rational = Self::extract_json_string(stream, key)?;
+            let (num, den) = rational.split_once('/')?;
+            let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+            (den > 0.0).then(|| (num / den * scale).round() as u64)
+        };
+        const CHROMA: f64 = 50000.0;
+        const LUMINANCE: f64 = 10000.0;
+        Some(format!(
+            "G({},{})B({},{})R({},{})WP({},{})L({},{})",
+            value("green_x", CHROMA)?,
+            value("green_y", CHROMA)?,
+            value("blue_x", CHROMA)?,
+            value("blue_y", CHROMA)?,
+            value("red_x", CHROMA)?,
+            value("red_y", CHROMA)?,
+            value("white_point_x", CHROMA)?,
+            value("white_point_y", CHROMA)?,
+            value("max_luminance", LUMINANCE)?,
+            value("min_luminance", LUMINANCE)?,
+        ))
+    }
+
+    /// ピクセルフォーマットからビット深度を推定 ("yuv420p10le" -> 10, "p016le" -> 16)
+    /// 末尾のle/beの直前の数字を使う（"nv12"のような8ビットの名前の数字は深度ではない）
+    fn bit_depth_from_pix_fmt(pix_fmt: &str) -> u32 {
+        let Some(name) = pix_fmt
+            .strip_suffix("le")
+            .or_else(|| pix_fmt.strip_suffix("be"))
+        else {
+            return 8;
+        };
+        let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        name[name.len() - digits..]
+            .parse()
+            .ok()
+            // rgb48leなどはピクセルあたりのビット数のため対象外
+            .filter(|depth| (9..=16).contains(depth))
+            .unwrap_or(8)
+    }
+
     /// フレームレート文字列をパース ("30/1" -> 30.0)
     fn parse_frame_rate(fps_str: &str) -> Option<f64> {
         let parts: Vec<&str> = fps_str.split('/').collect();
@@ -457,6 +1004,15 @@ impl FfmpegInfo {
     }
 }
 
+/// パイプの内容を別スレッドで最後まで読み出す
+fn read_to_end_in_thread(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -469,4 +1025,314 @@ mod tests {
             assert!(!info.version.is_empty());
         }
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_killed_on_cancel() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let info = FfmpegInfo {
+            ffmpeg_path: PathBuf::from("sleep"),
+            ffprobe_path: None,
+            version: String::new(),
+            major_version: 0,
+            minor_version: 0,
+            is_gpl: false,
+            cancel: None,
+        }
+        .with_cancel(cancel.clone());
+
+        let output = info.output(Command::new("echo").arg("ok")).unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+
+        let flag = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            flag.store(true, Ordering::Relaxed);
+        });
+        let started = std::time::Instant::now();
+        let result = info.output(Command::new("sleep").arg("30"));
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::Interrupted);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_bit_depth_from_pix_fmt() {
+        assert_eq!(FfmpegInfo::bit_depth_from_pix_fmt("yuv420p"), 8);
+        assert_eq!(FfmpegInfo::bit_depth_from_pix_fmt("nv12"), 8);
+        assert_eq!(FfmpegInfo::bit_depth_from_pix_fmt("nv16"), 8);
+        assert_eq!(FfmpegInfo::bit_depth_from_pix_fmt("p010le"), 10);
+        assert_eq!(FfmpegInfo::bit_depth_from_pix_fmt("p016le"), 16);
+        assert_eq!(FfmpegInfo::bit_depth_from_pix_fmt("yuv420p10le"), 10);
+        assert_eq!(FfmpegInfo::bit_depth_from_pix_fmt("yuv422p12be"), 12);
+    }
+
+    #[test]
+    fn test_parse_probe_json_bit_depth() {
+        let json = r#"{
+    "streams": [
+        {
+            "index": 0,
+            "codec_name": "hevc",
+            "codec_type": "video",
+            "width": 3840,
+            "height": 2160,
+            "pix_fmt": "yuv420p10le",
+            "color_space": "bt2020nc",
+            "color_transfer": "smpte2084",
+            "color_primaries": "bt2020",
+            "field_order": "progressive",
+            "r_frame_rate": "24000/1001"
+        },
+        {
+            "index": 1,
+            "codec_name": "flac",
+            "codec_type": "audio",
+            "channels": 6,
+            "bits_per_sample": 0,
+            "bits_per_raw_sample": "24"
+        }
+    ],
+    "format": {
+        "duration": "60.000000",
+        "bit_rate": "20000000"
+    }
+}"#;
+
+        let result = FfmpegInfo::parse_probe_json(json).unwrap();
+        assert_eq!(result.video_codec.as_deref(), Some("hevc"));
+        assert_eq!(result.audio_codec.as_deref(), Some("flac"));
+        assert_eq!(result.resolution, Some((3840, 2160)));
+        assert_eq!(result.video_bit_depth, Some(10));
+        assert_eq!(result.audio_bit_depth, Some(24));
+        assert_eq!(result.audio_channels, Some(6));
+        assert_eq!(result.pix_fmt.as_deref(), Some("yuv420p10le"));
+        assert_eq!(result.color_primaries.as_deref(), Some("bt2020"));
+        assert_eq!(result.color_trc.as_deref(), Some("smpte2084"));
+        assert_eq!(result.color_space.as_deref(), Some("bt2020nc"));
+        assert_eq!(result.field_order.as_deref(), Some("progressive"));
+        assert_eq!(result.master_display, None);
+        assert_eq!(result.max_cll, None);
+    }
+
+    #[test]
+    fn test_parse_probe_json_hdr10_side_data() {
+        let json = r#"{
+    "streams": [
+        {
+            "index": 0,
+            "codec_name": "hevc",
+            "codec_type": "video",
+            "pix_fmt": "yuv420p10le",
+            "color_transfer": "smpte2084",
+            "side_data_list": [
+                {
+                    "side_data_type": "Mastering display metadata",
+                    "red_x": "34000/50000",
+                    "red_y": "16000/50000",
+                    "green_x": "13250/50000",
+                    "green_y": "34500/50000",
+                    "blue_x": "7500/50000",
+                    "blue_y": "3000/50000",
+                    "white_point_x": "15635/50000",
+                    "white_point_y": "16450/50000",
+                    "min_luminance": "50/10000",
+                    "max_luminance": "10000000/10000"
+                },
+                {
+                    "side_data_type": "Content light level metadata",
+                    "max_content": 1000,
+                    "max_average": 400
+                }
+            ]
+        }
+    ],
+    "format": {
+        "duration": "60.000000"
+    }
+}"#;
+
+        let result = FfmpegInfo::parse_probe_json(json).unwrap();
+        assert_eq!(
+            result.master_display.as_deref(),
+            Some("G(13250,34500)B(7500,3000)R(34000,16000)WP(15635,16450)L(10000000,50)")
+        );
+        assert_eq!(result.max_cll, Some((1000, 400)));
+    }
+
+    #[test]
+    fn test_parse_probe_json_chapters() {
+        let json = r#"{
+    "streams": [
+        {
+            "index": 0,
+            "codec_name": "h264",
+            "codec_type": "video",
+            "width": 1920,
+            "height": 1080
+        }
+    ],
+    "chapters": [
+        {
+            "id": 0,
+            "start_time": "0.000000",
+            "end_time": "90.000000",
+            "tags": {
+                "title": "Opening {Part 1]"
+            }
+        },
+        {
+            "id": 1,
+            "start_time": "90.000000",
+            "end_time": "600.000000",
+            "tags": {
+                "title": "Chapter \"2\""
+            }
+        }
+    ],
+    "format": {
+        "duration": "600.000000"
+    }
+}"#;
+
+        let result = FfmpegInfo::parse_probe_json(json).unwrap();
+        assert_eq!(result.chapter_count, 2);
+        assert_eq!(result.resolution, Some((1920, 1080)));
+        assert_eq!(result.duration, Some(600.0));
+
+        let json = r#"{ "chapters": [], "format": { "duration": "1.0" } }"#;
+        assert_eq!(FfmpegInfo::parse_probe_json(json).unwrap().chapter_count, 0);
+    }
+
+    #[test]
+    fn test_parse_cropdetect() {
+        let stderr = "\
+[Parsed_cropdetect_0 @ 0x600] x1:0 x2:1919 y1:0 y2:1079 w:1920 h:1072 x:0 y:4 pts:0 t:0.000000 limit:0.094118 crop=1920:1072:0:4
+[Parsed_cropdetect_0 @ 0x600] x1:0 x2:1919 y1:140 y2:939 w:1920 h:800 x:0 y:140 pts:1 t:0.041667 limit:0.094118 crop=1920:800:0:140
+[Parsed_cropdetect_0 @ 0x600] x1:0 x2:1919 y1:140 y2:939 w:1920 h:800 x:0 y:140 pts:2 t:0.083333 limit:0.094118 crop=1920:800:0:140
+[Parsed_cropdetect_0 @ 0x600] x1:1919 x2:0 y1:1079 y2:0 w:-1904 h:-1056 x:1912 y:1070 pts:3 t:0.125000 limit:0.094118 crop=-1904:-1056:1912:1070
+frame=  200 fps=0.0 q=-0.0 Lsize=N/A time=00:00:08.33 bitrate=N/A speed=  20x
+";
+
+        assert_eq!(
+            FfmpegInfo::parse_cropdetect(stderr),
+            Some((1920, 800, 0, 140))
+        );
+        assert_eq!(FfmpegInfo::parse_cropdetect("no crop here"), None);
+    }
+
+    #[test]
+    fn test_encoder_test_results_are_cached() {
+        let dir =
+            std::env::temp_dir().join(format!("kamaitachi-encoder-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ffmpeg = dir.join("ffmpeg");
+        std::fs::write(&ffmpeg, b"").unwrap();
+
+        let runs = std::cell::Cell::new(0);
+        let test = || {
+            runs.set(runs.get() + 1);
+            true
+        };
+        assert!(FfmpegDetector::cached_encoder_test(
+            &ffmpeg,
+            "h264_nvenc",
+            test
+        ));
+        assert!(FfmpegDetector::cached_encoder_test(
+            &ffmpeg,
+            "h264_nvenc",
+            test
+        ));
+        assert_eq!(runs.get(), 1);
+
+        // バイナリが差し替えられたら再テスト
+        std::fs::File::options()
+            .write(true)
+            .open(&ffmpeg)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(60))
+            .unwrap();
+        assert!(FfmpegDetector::cached_encoder_test(
+            &ffmpeg,
+            "h264_nvenc",
+            test
+        ));
+        assert_eq!(runs.get(), 2);
+
+        // 選び直したバイナリだけ再テストし、別のバイナリの結果は残す
+        let other = dir.join("ffmpeg-other");
+        std::fs::write(&other, b"").unwrap();
+        assert!(FfmpegDetector::cached_encoder_test(
+            &other,
+            "h264_nvenc",
+            test
+        ));
+        assert_eq!(runs.get(), 3);
+        FfmpegDetector::clear_encoder_cache(&ffmpeg);
+        assert!(FfmpegDetector::cached_encoder_test(
+            &ffmpeg,
+            "h264_nvenc",
+            test
+        ));
+        assert!(FfmpegDetector::cached_encoder_test(
+            &other,
+            "h264_nvenc",
+            test
+        ));
+        assert_eq!(runs.get(), 4);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_encoder_list() {
+        let stdout = "\
+Encoders:
+ V..... = Video
+ A..... = Audio
+ S..... = Subtitle
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)
+ V....D h264_nvenc           NVIDIA NVENC H.264 encoder (codec h264)
+ V....D libsvtav1            SVT-AV1(Scalable Video Technology for AV1) encoder (codec av1)
+ A....D aac                  AAC (Advanced Audio Coding)
+ A....D flac                 FLAC (Free Lossless Audio Codec)
+ S..... ass                  ASS (Advanced SubStation Alpha) subtitle (codec ass)
+";
+
+        let encoders = FfmpegDetector::parse_encoder_list(stdout);
+        assert_eq!(encoders.len(), 6);
+        for name in ["libx264", "h264_nvenc", "libsvtav1", "aac", "flac", "ass"] {
+            assert!(encoders.contains(name), "{} missing", name);
+        }
+        // 凡例の行は含めない
+        assert!(!encoders.contains("="));
+        assert!(!encoders.contains("libx265"));
+    }
+
+    #[test]
+    fn test_parse_filter_list() {
+        let stdout = "\
+Filters:
+  T.. = Timeline support
+  .S. = Slice threading
+  ..C = Command support
+  A = Audio input/output
+  V = Video input/output
+  N = Dynamic number and/or type of input/output
+  | = Source or sink filter
+ ... abench            A->A       Benchmark part of a filtergraph.
+ TSC scale             V->V       Scale the input video size and/or convert the image format.
+ ... libvmaf           VV->V      Calculate the VMAF between two video streams.
+ TS. ssim              VV->V      Calculate the SSIM between two video streams.
+ ... nullsink          V->|       Do absolutely nothing with the input video.
+";
+
+        let filters = FfmpegDetector::parse_filter_list(stdout);
+        assert_eq!(filters.len(), 5);
+        for name in ["abench", "scale", "libvmaf", "ssim", "nullsink"] {
+            assert!(filters.contains(name), "{} missing", name);
+        }
+        assert!(!filters.contains("="));
+    }
 }
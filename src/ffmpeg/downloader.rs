@@ -5,6 +5,8 @@ use log::{debug, info, warn};
 use std::fs::{self, File};
 use std::io::{self, BufReader, Read, Write};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use crate::config::Settings;
 
@@ -38,8 +40,6 @@ pub enum DownloadStatus {
     Extracting,
     /// 完了
     Completed,
-    /// エラー
-    Error(String),
 }
 
 impl FfmpegDownloader {
@@ -57,7 +57,11 @@ impl FfmpegDownloader {
         "https://evermeet.cx/ffmpeg/getrelease/ffmpeg/zip";
 
     /// FFmpegをダウンロードして展開
-    pub fn download(progress_callback: Option<ProgressCallback>) -> Result<PathBuf> {
+    /// `cancel_flag` が立つとダウンロードを中断し、途中のアーカイブを削除する
+    pub fn download(
+        progress_callback: Option<ProgressCallback>,
+        cancel_flag: Arc<AtomicBool>,
+    ) -> Result<PathBuf> {
         let ffmpeg_dir = Settings::ffmpeg_dir()?;
         let archive_path = ffmpeg_dir.join("ffmpeg-download.zip");
 
@@ -97,6 +101,13 @@ impl FfmpegDownloader {
         let mut buffer = [0; 8192];
 
         loop {
+            if cancel_flag.load(Ordering::SeqCst) {
+                drop(file);
+                let _ = fs::remove_file(&archive_path);
+                info!("FFmpeg download cancelled");
+                return Err(anyhow!("Download cancelled"));
+            }
+
             let bytes_read = reader.read(&mut buffer)?;
             if bytes_read == 0 {
                 break;
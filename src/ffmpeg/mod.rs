@@ -2,6 +2,8 @@
 
 mod detector;
 mod downloader;
+mod process;
 
 pub use detector::{FfmpegDetector, FfmpegInfo, ProbeResult};
-pub use downloader::FfmpegDownloader;
+pub use downloader::{DownloadProgress, DownloadStatus, FfmpegDownloader};
+pub use process::{background_command, spawn_with_priority, ProcessPriority};
@@ -0,0 +1,113 @@
+//! 外部プロセスの起動
+//!
+//! Windowsではコンソールアプリを起動するたびにウィンドウが一瞬表示されるため、
+//! FFmpeg・ffprobe・nvidia-smiなどはここで作成したコマンドから起動する
+
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::process::{Child, Command};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+/// コンソールウィンドウを作成しない
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// 変換プロセスの優先度
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcessPriority {
+    /// 低（変換中もデスクトップの操作を優先）
+    #[default]
+    Low,
+    /// 標準
+    Normal,
+    /// 高
+    High,
+}
+
+impl ProcessPriority {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ProcessPriority::Low => "低",
+            ProcessPriority::Normal => "標準",
+            ProcessPriority::High => "高",
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [ProcessPriority] {
+        &[
+            ProcessPriority::Low,
+            ProcessPriority::Normal,
+            ProcessPriority::High,
+        ]
+    }
+
+    /// Windowsの優先度クラス（「高」でもシステム全体を止めないようABOVE_NORMALにする）
+    #[cfg(target_os = "windows")]
+    fn priority_class(&self) -> u32 {
+        match self {
+            ProcessPriority::Low => 0x0000_4000, // BELOW_NORMAL_PRIORITY_CLASS
+            ProcessPriority::Normal => 0x0000_0020, // NORMAL_PRIORITY_CLASS
+            ProcessPriority::High => 0x0000_8000, // ABOVE_NORMAL_PRIORITY_CLASS
+        }
+    }
+
+    /// Windows以外のnice値（負の値は権限がなければ設定できない）
+    #[cfg(not(target_os = "windows"))]
+    fn nice_value(&self) -> i32 {
+        match self {
+            ProcessPriority::Low => 10,
+            ProcessPriority::Normal => 0,
+            ProcessPriority::High => -5,
+        }
+    }
+}
+
+/// コマンドを作成（Windowsではコンソールウィンドウを表示しない）
+pub fn background_command(program: impl AsRef<OsStr>) -> Command {
+    #[allow(unused_mut)]
+    let mut command = Command::new(program);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+/// 優先度を指定してプロセスを起動
+/// Windowsは起動時の優先度クラス、それ以外は起動直後にreniceでnice値を設定する
+pub fn spawn_with_priority(
+    command: &mut Command,
+    priority: ProcessPriority,
+) -> std::io::Result<Child> {
+    #[cfg(target_os = "windows")]
+    {
+        command.creation_flags(CREATE_NO_WINDOW | priority.priority_class());
+        command.spawn()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let child = command.spawn()?;
+        let nice = priority.nice_value();
+        if nice != 0 {
+            // 優先度を変更できなくても変換は続ける
+            match Command::new("renice")
+                .args(["-n", &nice.to_string(), "-p", &child.id().to_string()])
+                .output()
+            {
+                Ok(output) if output.status.success() => {
+                    log::debug!("Set nice value {} for process {}", nice, child.id());
+                }
+                Ok(output) => log::warn!(
+                    "Failed to set nice value {}: {}",
+                    nice,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                Err(e) => log::warn!("Failed to run renice: {}", e),
+            }
+        }
+        Ok(child)
+    }
+}
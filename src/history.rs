@@ -0,0 +1,402 @@
+//! 変換履歴（JSON Lines保存）
+//!
+//! 完了したファイルごとに1行追記する。予測サイズも残し、見積もりの精度を確認できるようにする
+
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::app::FileEntry;
+use crate::config::Settings;
+use crate::queue::FileOutcome;
+
+/// 履歴の集計期間（秒）
+pub const RECENT_PERIOD_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// 変換1件の記録
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// 完了日時（UNIX時間、秒）
+    pub finished_at: u64,
+    /// 入力ファイル
+    pub input_path: String,
+    /// 出力ファイル
+    pub output_path: String,
+    /// 入力サイズ（バイト）
+    pub input_size: u64,
+    /// 出力サイズ（バイト）
+    pub output_size: u64,
+    /// 変換前に予測した出力サイズ（バイト）
+    pub estimated_size: Option<u64>,
+    /// 動画の長さ（秒）
+    pub duration: Option<f64>,
+    /// 変換にかかった時間（秒）
+    pub elapsed_secs: f64,
+    /// 使用したエンコーダー
+    pub encoder: String,
+    /// 平均エンコード速度（fps）
+    pub average_fps: Option<f64>,
+}
+
+impl HistoryRecord {
+    /// 変換結果から記録を作成（完了していなければNone）
+    pub fn new(file: &FileEntry, outcome: &FileOutcome) -> Option<Self> {
+        if !outcome.status.is_completed() {
+            return None;
+        }
+        let output_size = outcome.output_size?;
+        let elapsed_secs = outcome.elapsed.as_secs_f64();
+        let frames = match (file.metadata.duration, file.metadata.fps) {
+            (Some(duration), Some(fps)) => Some(duration * fps),
+            _ => None,
+        };
+        Some(Self {
+            finished_at: now_secs(),
+            input_path: file.path.to_string_lossy().into_owned(),
+            output_path: outcome
+                .output_path
+                .as_ref()
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            input_size: file.size,
+            output_size,
+            estimated_size: file.estimated_size,
+            duration: file.metadata.duration,
+            elapsed_secs,
+            encoder: outcome
+                .encode_plan
+                .as_ref()
+                .map(|plan| plan.encoder.clone())
+                .unwrap_or_default(),
+            average_fps: frames
+                .filter(|_| elapsed_secs > 0.0)
+                .map(|f| f / elapsed_secs),
+        })
+    }
+
+    /// ファイル名
+    pub fn file_name(&self) -> &str {
+        self.input_path
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(&self.input_path)
+    }
+
+    /// 実際の圧縮率（出力 / 入力）
+    pub fn compression_ratio(&self) -> Option<f64> {
+        (self.input_size > 0).then(|| self.output_size as f64 / self.input_size as f64)
+    }
+
+    /// 予測した圧縮率（予測 / 入力）
+    pub fn predicted_ratio(&self) -> Option<f64> {
+        let estimated = self.estimated_size?;
+        (self.input_size > 0).then(|| estimated as f64 / self.input_size as f64)
+    }
+
+    /// 予測との差（実際 / 予測 - 1、正なら予測より大きい）
+    pub fn prediction_error(&self) -> Option<f64> {
+        let estimated = self.estimated_size.filter(|&size| size > 0)?;
+        Some(self.output_size as f64 / estimated as f64 - 1.0)
+    }
+}
+
+/// 履歴の並べ替えキー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistorySortKey {
+    /// 完了日時
+    FinishedAt,
+    /// ファイル名
+    Name,
+    /// 入力サイズ
+    InputSize,
+    /// 圧縮率
+    Ratio,
+    /// 予測との差
+    PredictionError,
+    /// エンコーダー
+    Encoder,
+    /// 平均エンコード速度
+    Fps,
+}
+
+impl HistorySortKey {
+    /// 列ヘッダーの表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            HistorySortKey::FinishedAt => "日時 (UTC)",
+            HistorySortKey::Name => "ファイル名",
+            HistorySortKey::InputSize => "サイズ",
+            HistorySortKey::Ratio => "圧縮率",
+            HistorySortKey::PredictionError => "予測との差",
+            HistorySortKey::Encoder => "エンコーダー",
+            HistorySortKey::Fps => "平均fps",
+        }
+    }
+
+    /// 昇順で比較（値のない記録は後ろ、同じ値は完了日時順）
+    pub fn compare(&self, a: &HistoryRecord, b: &HistoryRecord) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+
+        let by_time = || a.finished_at.cmp(&b.finished_at);
+        let by_value = |x: Option<f64>, y: Option<f64>| match (x, y) {
+            (Some(x), Some(y)) => x.total_cmp(&y).then_with(by_time),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => by_time(),
+        };
+        match self {
+            HistorySortKey::FinishedAt => by_time(),
+            HistorySortKey::Name => a
+                .file_name()
+                .to_lowercase()
+                .cmp(&b.file_name().to_lowercase())
+                .then_with(by_time),
+            HistorySortKey::InputSize => a.input_size.cmp(&b.input_size).then_with(by_time),
+            HistorySortKey::Ratio => by_value(a.compression_ratio(), b.compression_ratio()),
+            HistorySortKey::PredictionError => by_value(a.prediction_error(), b.prediction_error()),
+            HistorySortKey::Encoder => a.encoder.cmp(&b.encoder).then_with(by_time),
+            HistorySortKey::Fps => by_value(a.average_fps, b.average_fps),
+        }
+    }
+}
+
+/// 履歴の集計
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HistorySummary {
+    /// ファイル数
+    pub count: usize,
+    /// 入力サイズの合計（バイト）
+    pub input_size: u64,
+    /// 出力サイズの合計（バイト）
+    pub output_size: u64,
+}
+
+/// 指定日時以降に完了した記録を集計
+pub fn summarize(records: &[HistoryRecord], since: u64) -> HistorySummary {
+    records
+        .iter()
+        .filter(|record| record.finished_at >= since)
+        .fold(HistorySummary::default(), |summary, record| {
+            HistorySummary {
+                count: summary.count + 1,
+                input_size: summary.input_size + record.input_size,
+                output_size: summary.output_size + record.output_size,
+            }
+        })
+}
+
+/// 現在のUNIX時間（秒）
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// UNIX時間を "YYYY-MM-DD HH:MM"（UTC）に変換
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let minutes = (secs % 86_400) / 60;
+    // 1970-01-01からの日数を年月日に変換
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// 変換履歴
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    /// 保存先ファイルのパス
+    path: PathBuf,
+    /// 記録（古い順）
+    records: Vec<HistoryRecord>,
+}
+
+impl History {
+    /// 設定ディレクトリの履歴ファイルを開く
+    pub fn open() -> Result<Self> {
+        let path = Settings::config_path()?.with_file_name("history.jsonl");
+        Self::open_at(path)
+    }
+
+    /// 指定したファイルの履歴を開く（存在しなければ空）
+    /// 書き込み途中で終了した行などは読み飛ばす
+    pub fn open_at(path: PathBuf) -> Result<Self> {
+        let records = if path.exists() {
+            std::fs::read_to_string(&path)?
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .filter_map(|line| {
+                    serde_json::from_str(line)
+                        .inspect_err(|e| warn!("Skipping broken history line: {}", e))
+                        .ok()
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, records })
+    }
+
+    /// 記録（古い順）
+    pub fn records(&self) -> &[HistoryRecord] {
+        &self.records
+    }
+
+    /// 記録を追記
+    pub fn append(&mut self, record: HistoryRecord) -> Result<()> {
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// 記録を削除
+    pub fn remove(&mut self, record: &HistoryRecord) -> Result<()> {
+        let len = self.records.len();
+        self.records.retain(|r| r != record);
+        if self.records.len() == len {
+            return Ok(());
+        }
+        self.write()
+    }
+
+    /// すべての記録を削除
+    pub fn clear(&mut self) -> Result<()> {
+        self.records.clear();
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+
+    /// ファイルに書き込む
+    fn write(&self) -> Result<()> {
+        let mut content = String::new();
+        for record in &self.records {
+            content.push_str(&serde_json::to_string(record)?);
+            content.push('\n');
+        }
+        std::fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+/// 完了したファイルを履歴に追記（失敗しても変換結果には影響させない）
+pub fn record_completed(file: &FileEntry, outcome: &FileOutcome) {
+    let Some(record) = HistoryRecord::new(file, outcome) else {
+        return;
+    };
+    if let Err(e) = History::open().and_then(|mut history| history.append(record)) {
+        warn!("Failed to record history for {:?}: {:#}", file.path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(finished_at: u64, input_size: u64, output_size: u64) -> HistoryRecord {
+        HistoryRecord {
+            finished_at,
+            input_path: "/videos/a.mkv".to_string(),
+            output_path: "/videos/a_transcoded.mp4".to_string(),
+            input_size,
+            output_size,
+            estimated_size: Some(output_size / 2),
+            duration: Some(60.0),
+            elapsed_secs: 30.0,
+            encoder: "libx264".to_string(),
+            average_fps: Some(48.0),
+        }
+    }
+
+    #[test]
+    fn test_history_persists_across_open() {
+        let path =
+            std::env::temp_dir().join(format!("kamaitachi-history-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut history = History::open_at(path.clone()).unwrap();
+        assert!(history.records().is_empty());
+        history.append(record(100, 1_000, 400)).unwrap();
+        history.append(record(200, 2_000, 500)).unwrap();
+
+        let mut history = History::open_at(path.clone()).unwrap();
+        assert_eq!(history.records().len(), 2);
+        let first = history.records()[0].clone();
+        assert_eq!(first.file_name(), "a.mkv");
+        assert_eq!(first.compression_ratio(), Some(0.4));
+        assert_eq!(first.predicted_ratio(), Some(0.2));
+        assert_eq!(first.prediction_error(), Some(1.0));
+
+        history.remove(&first).unwrap();
+        let mut history = History::open_at(path.clone()).unwrap();
+        assert_eq!(history.records(), &[record(200, 2_000, 500)]);
+
+        history.clear().unwrap();
+        assert!(History::open_at(path).unwrap().records().is_empty());
+    }
+
+    #[test]
+    fn test_summarize_recent_records() {
+        let records = [
+            record(100, 1_000, 400),
+            record(200, 2_000, 500),
+            record(300, 4_000, 1_000),
+        ];
+        assert_eq!(
+            summarize(&records, 200),
+            HistorySummary {
+                count: 2,
+                input_size: 6_000,
+                output_size: 1_500,
+            }
+        );
+        assert_eq!(summarize(&records, 400), HistorySummary::default());
+    }
+
+    #[test]
+    fn test_sort_by_prediction_error() {
+        let mut accurate = record(100, 1_000, 400);
+        accurate.estimated_size = Some(400);
+        let unknown = HistoryRecord {
+            estimated_size: None,
+            ..record(200, 1_000, 400)
+        };
+        let over = record(300, 1_000, 400);
+        let mut records = [unknown.clone(), over.clone(), accurate.clone()];
+        records.sort_by(|a, b| HistorySortKey::PredictionError.compare(a, b));
+        // 予測のない記録は後ろ
+        assert_eq!(records, [accurate, over, unknown]);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(951_782_400 + 3_660), "2000-02-29 01:01");
+        assert_eq!(format_timestamp(1_790_000_000), "2026-09-21 14:13");
+    }
+}
@@ -6,8 +6,13 @@
 //! GPL-3.0 (GPLビルドのFFmpegを使用するため)
 
 mod app;
+mod cli;
 mod config;
 mod ffmpeg;
+mod history;
+mod notification;
+mod queue;
+mod report;
 mod transcoder;
 mod ui;
 
@@ -17,8 +22,18 @@ use gpui_component::Root;
 use log::info;
 
 fn main() -> Result<()> {
-    // ロガー初期化
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    // --cli が指定されたらGUIを起動せずに変換する
+    // パスはUTF-8とは限らないのでOsStringのまま渡す
+    let headless = std::env::args_os().nth(1).is_some_and(|arg| arg == "--cli");
+
+    // ロガー初期化（ヘッドレスモードでは進捗表示を妨げないよう警告以上のみ）
+    let default_filter = if headless { "warn" } else { "info" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter))
+        .init();
+
+    if headless {
+        std::process::exit(cli::run(std::env::args_os().skip(2)));
+    }
 
     info!("kamaitachi v{} starting...", env!("CARGO_PKG_VERSION"));
 
@@ -30,6 +45,9 @@ fn main() -> Result<()> {
         // アプリケーション状態を初期化
         let app_state = app::AppState::new(cx);
 
+        // 前回のウィンドウ位置・サイズを復元
+        let window_bounds = ui::MainWindow::initial_bounds(app_state.settings.read(cx), cx);
+
         // メインウィンドウを開く
         cx.open_window(
             WindowOptions {
@@ -38,18 +56,14 @@ fn main() -> Result<()> {
                     appears_transparent: false,
                     ..Default::default()
                 }),
-                window_bounds: Some(WindowBounds::Windowed(Bounds {
-                    origin: Point::default(),
-                    size: Size {
-                        width: px(1200.0),
-                        height: px(800.0),
-                    },
-                })),
+                window_bounds: Some(WindowBounds::Windowed(window_bounds)),
                 ..Default::default()
             },
             |window, cx| {
                 // メインウィンドウビューを作成
-                let main_view = cx.new(|cx| ui::MainWindow::new(app_state, cx));
+                let main_view = cx.new(|cx| ui::MainWindow::new(app_state, window, cx));
+                // キーボードショートカットを受け取れるようにフォーカス
+                window.focus(&main_view.focus_handle(cx));
                 // gpui-componentではRootでラップする必要がある
                 cx.new(|cx| Root::new(main_view, window, cx))
             },
@@ -0,0 +1,203 @@
+//! デスクトップ通知
+//!
+//! `notifications` フィーチャー有効時に各OSのネイティブ通知を使用する
+//! - macOS: mac-notification-sys（「フォルダを開く」ボタン付き）
+//! - Windows: tauri-winrt-notification（「フォルダを開く」ボタン付き）
+//! - Linux: notify-rust（「フォルダを開く」アクション付き）
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::transcoder::format_duration;
+
+/// キュー完了時の通知内容
+#[derive(Clone, Debug)]
+pub struct CompletionSummary {
+    /// 変換に成功したファイル数
+    pub completed: usize,
+    /// エラーになったファイル数
+    pub failed: usize,
+    /// 経過時間
+    pub elapsed: Duration,
+    /// 出力フォルダ（アクションで開く）
+    pub output_dir: Option<PathBuf>,
+}
+
+impl CompletionSummary {
+    /// 通知のタイトル
+    pub fn title(&self) -> String {
+        if self.failed > 0 {
+            format!(
+                "変換が完了しました（{}件完了・{}件エラー）",
+                self.completed, self.failed
+            )
+        } else {
+            format!("変換が完了しました（{}件完了）", self.completed)
+        }
+    }
+
+    /// 通知の本文
+    pub fn body(&self) -> String {
+        let mut body = format!(
+            "{}件のファイルを変換しました（経過時間 {}）",
+            self.completed,
+            format_duration(self.elapsed)
+        );
+        if self.failed > 0 {
+            body.push_str(&format!(
+                "\n{}件のファイルでエラーが発生しました",
+                self.failed
+            ));
+        }
+        body
+    }
+}
+
+/// 表示する通知
+#[derive(Clone, Debug)]
+struct Notice {
+    title: String,
+    body: String,
+    /// 「フォルダを開く」で開くフォルダ
+    output_dir: Option<PathBuf>,
+}
+
+/// キュー完了を通知
+pub fn notify_completion(summary: CompletionSummary) {
+    send(Notice {
+        title: summary.title(),
+        body: summary.body(),
+        output_dir: summary.output_dir,
+    });
+}
+
+/// ファイルごとのエラーを通知
+pub fn notify_failure(file_name: &str, message: &str) {
+    send(Notice {
+        title: failure_title(file_name),
+        body: message.to_string(),
+        output_dir: None,
+    });
+}
+
+/// エラー通知のタイトル
+fn failure_title(file_name: &str) -> String {
+    format!("変換に失敗しました: {}", file_name)
+}
+
+/// 通知を表示（ボタン操作を待つ場合があるため別スレッドで実行）
+fn send(notice: Notice) {
+    std::thread::spawn(move || {
+        if let Err(e) = show(&notice) {
+            log::warn!("Failed to show notification: {:#}", e);
+        }
+    });
+}
+
+/// 出力フォルダを開く
+#[cfg(all(
+    feature = "notifications",
+    any(target_os = "macos", target_os = "windows", target_os = "linux")
+))]
+fn open_output_dir(notice: &Notice) {
+    use crate::config::PostTranscodeAction;
+
+    if let Err(e) = PostTranscodeAction::OpenFolder.execute(notice.output_dir.as_deref()) {
+        log::warn!("Failed to open output folder: {:#}", e);
+    }
+}
+
+#[cfg(all(feature = "notifications", target_os = "macos"))]
+fn show(notice: &Notice) -> anyhow::Result<()> {
+    use mac_notification_sys::{send_notification, MainButton, Notification, NotificationResponse};
+
+    let mut options = Notification::new();
+    if notice.output_dir.is_some() {
+        options.main_button(MainButton::SingleAction("フォルダを開く"));
+    }
+    let response = send_notification(&notice.title, None, &notice.body, Some(&options))?;
+    if matches!(response, NotificationResponse::ActionButton(_)) {
+        open_output_dir(notice);
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "notifications", target_os = "windows"))]
+fn show(notice: &Notice) -> anyhow::Result<()> {
+    use winrt_notification::{Duration as ToastDuration, Toast};
+
+    let mut toast = Toast::new(Toast::POWERSHELL_APP_ID)
+        .title(&notice.title)
+        .text1(&notice.body)
+        .duration(ToastDuration::Short);
+    if notice.output_dir.is_some() {
+        // ボタンでも通知本体のクリックでもフォルダを開く
+        let activated = notice.clone();
+        toast = toast
+            .add_button("フォルダを開く", "open")
+            .on_activated(move |_action| {
+                open_output_dir(&activated);
+                Ok(())
+            });
+    }
+    toast.show().map_err(|e| anyhow::anyhow!("{:?}", e))
+}
+
+#[cfg(all(feature = "notifications", target_os = "linux"))]
+fn show(notice: &Notice) -> anyhow::Result<()> {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname("kamaitachi")
+        .summary(&notice.title)
+        .body(&notice.body);
+    if notice.output_dir.is_some() {
+        notification.action("open", "フォルダを開く");
+    }
+
+    let handle = notification.show()?;
+    if notice.output_dir.is_some() {
+        handle.wait_for_action(|action| {
+            if action == "open" {
+                open_output_dir(notice);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(all(
+    feature = "notifications",
+    any(target_os = "macos", target_os = "windows", target_os = "linux")
+)))]
+fn show(notice: &Notice) -> anyhow::Result<()> {
+    log::info!("{}: {}", notice.title, notice.body);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_summary_text() {
+        let mut summary = CompletionSummary {
+            completed: 3,
+            failed: 0,
+            elapsed: Duration::from_secs(3725),
+            output_dir: None,
+        };
+        assert_eq!(summary.title(), "変換が完了しました（3件完了）");
+        assert_eq!(
+            summary.body(),
+            "3件のファイルを変換しました（経過時間 1:02:05）"
+        );
+
+        summary.failed = 2;
+        assert_eq!(summary.title(), "変換が完了しました（3件完了・2件エラー）");
+        assert!(summary
+            .body()
+            .ends_with("\n2件のファイルでエラーが発生しました"));
+
+        assert_eq!(failure_title("movie.mkv"), "変換に失敗しました: movie.mkv");
+    }
+}
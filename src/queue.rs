@@ -0,0 +1,1193 @@
+//! 変換キューの実行
+//!
+//! GUI（MainWindow）とCLIで同じ手順を踏むよう、GPUIに依存しない処理をまとめる
+//! ここにある関数はプロセスの起動を伴うため、GUIからはバックグラウンドで呼ぶ
+
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Output, Stdio};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::app::{CurrentProgress, FileEntry, FileStatus};
+use crate::config::Settings;
+use crate::ffmpeg::{background_command, spawn_with_priority, FfmpegInfo, ProcessPriority};
+use crate::report::FileCommand;
+use crate::transcoder::{
+    analyze_quality, check_file_space, verify_output, FfmpegError, FfmpegErrorKind,
+    FfmpegProgressInfo, HwAccelDetector, HwAccelType, OverwritePolicy, QualityScore,
+    ResolvedEncodePlan, SpeedEstimator, TranscodeJob, TranscodeSettings,
+};
+
+/// 設定を試すサンプルの長さ（秒）
+pub const SAMPLE_SECS: f64 = 30.0;
+
+/// 同時に変換できるファイル数の上限
+pub const MAX_PARALLEL_JOBS: usize = 16;
+
+/// バッチ全体で共通のオプション
+#[derive(Clone)]
+pub struct QueueOptions {
+    /// FFmpegの実行ファイル
+    pub ffmpeg_path: PathBuf,
+    /// HWアクセラレーションを解決済みの設定
+    pub settings: TranscodeSettings,
+    /// 「自動検出」を解決した結果（全体の設定が自動検出の場合のみ）
+    auto_hwaccel: Option<HwAccelType>,
+    /// エラー時に途中までの出力ファイルを残す
+    pub keep_partial_on_error: bool,
+    /// HWエンコーダーが変換中に失敗したらソフトウェアエンコードで再試行
+    pub auto_fallback_to_software: bool,
+    /// 出力の検証に使うFFmpeg（Noneなら検証しない）
+    pub verify_ffmpeg_info: Option<FfmpegInfo>,
+    /// 画質評価に使うFFmpeg（Noneなら評価しない）
+    pub quality_ffmpeg_info: Option<FfmpegInfo>,
+    /// 画質評価の区間数
+    pub quality_samples: u32,
+    /// FFmpegプロセスの優先度
+    pub priority: ProcessPriority,
+    /// NVENCの同時セッション数の上限（Noneなら自動で判定）
+    pub nvenc_session_limit: Option<usize>,
+    /// このバッチで割り当てた出力先（クローンしたオプションと共有する）
+    reserved_outputs: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl QueueOptions {
+    /// 「自動検出」のHWアクセラレーションを解決してオプションを作成
+    pub fn new(ffmpeg_path: PathBuf, settings: &TranscodeSettings) -> Self {
+        let mut settings = settings.clone();
+        let is_auto = settings.hwaccel == HwAccelType::Auto;
+        settings.hwaccel = HwAccelDetector::resolve_auto(settings.hwaccel, Some(&ffmpeg_path));
+        Self {
+            ffmpeg_path,
+            auto_hwaccel: is_auto.then_some(settings.hwaccel),
+            settings,
+            keep_partial_on_error: false,
+            auto_fallback_to_software: true,
+            verify_ffmpeg_info: None,
+            quality_ffmpeg_info: None,
+            quality_samples: 4,
+            priority: ProcessPriority::default(),
+            nvenc_session_limit: None,
+            reserved_outputs: Arc::default(),
+        }
+    }
+
+    /// アプリ設定の検証・画質評価などを反映
+    /// 検証・画質評価はffprobeを使うため、FFmpeg情報がなければ行わない
+    pub fn with_app_settings(
+        mut self,
+        app_settings: &Settings,
+        ffmpeg_info: Option<&FfmpegInfo>,
+    ) -> Self {
+        self.keep_partial_on_error = app_settings.keep_partial_on_error;
+        self.auto_fallback_to_software = app_settings.auto_fallback_to_software;
+        if app_settings.verify_output {
+            self.verify_ffmpeg_info = ffmpeg_info.cloned();
+        }
+        if app_settings.run_vmaf_after_encode {
+            self.quality_ffmpeg_info = ffmpeg_info.cloned();
+        }
+        self.quality_samples = app_settings.quality_samples;
+        self.priority = app_settings.encode_priority;
+        self.nvenc_session_limit = (app_settings.nvenc_session_limit > 0)
+            .then_some(app_settings.nvenc_session_limit as usize);
+        self
+    }
+
+    /// 同時セッション数が上限に達したときに代わりに使うソフトウェアエンコーダー
+    pub fn session_fallback(&self, plan: &ResolvedEncodePlan) -> Option<ResolvedEncodePlan> {
+        if !self.auto_fallback_to_software {
+            return None;
+        }
+        plan.software_fallback(&self.settings, Some(&self.ffmpeg_path))
+    }
+
+    /// エンコーダーの同時セッション数の上限（Noneなら制限なし）
+    /// NVENCは設定した上限を優先し、自動ならGPUとドライバーから判定する
+    pub fn session_limit(&self, hwaccel: &HwAccelType) -> Option<usize> {
+        match (hwaccel, self.nvenc_session_limit) {
+            (HwAccelType::Nvenc, Some(limit)) => Some(limit),
+            _ => HwAccelDetector::max_concurrent_sessions(hwaccel),
+        }
+    }
+
+    /// ファイルに固定した設定があれば、その設定に差し替えたオプションを作成
+    /// 「自動検出」のHWアクセラレーションは、全体の設定も自動検出ならその結果を使う
+    pub fn for_file(&self, file: &FileEntry) -> Self {
+        let Some(settings) = &file.settings_override else {
+            return self.clone();
+        };
+        let mut options = self.clone();
+        options.settings = settings.clone();
+        if settings.hwaccel == HwAccelType::Auto {
+            options.settings.hwaccel = self.auto_hwaccel.unwrap_or_else(|| {
+                HwAccelDetector::resolve_auto(settings.hwaccel, Some(&self.ffmpeg_path))
+            });
+        }
+        options
+    }
+
+    /// ファイルに適用する設定（HWアクセラレーションは未解決のまま）
+    fn settings_for<'a>(&'a self, file: &'a FileEntry) -> &'a TranscodeSettings {
+        file.effective_settings(&self.settings)
+    }
+
+    /// 変換を始められない理由があればメッセージを返す
+    pub fn preflight(&self, files: &[FileEntry]) -> Option<String> {
+        let pending: Vec<&FileEntry> = files.iter().filter(|f| !f.status.is_completed()).collect();
+        if pending.is_empty() {
+            return None;
+        }
+
+        // ストリームコピーで出力コンテナに格納できないファイル
+        let copy_issues: Vec<String> = pending
+            .iter()
+            .filter_map(|f| {
+                self.settings_for(f)
+                    .stream_copy_issue(
+                        f.metadata.video_codec.as_deref(),
+                        f.metadata.audio_codec.as_deref(),
+                    )
+                    .map(|issue| format!("{}: {}", f.name, issue))
+            })
+            .collect();
+        if !copy_issues.is_empty() {
+            warn!(
+                "Stream copy is not possible for {} files",
+                copy_issues.len()
+            );
+            return Some(format!(
+                "コピー（再エンコードなし）では変換できないファイルがあります。\nコンテナ形式を変更するか、コーデックを選択してください。\n\n{}",
+                copy_issues.join("\n")
+            ));
+        }
+
+        // FFmpegビルドに必要なエンコーダーが含まれていない（設定を固定したファイルはその設定で確認）
+        let mut missing = Vec::new();
+        for file in &pending {
+            let settings = self.settings_for(file);
+            for encoder in HwAccelDetector::missing_encoders(settings, &self.ffmpeg_path) {
+                if !missing.contains(&encoder) {
+                    missing.push(encoder);
+                }
+            }
+        }
+        if !missing.is_empty() {
+            warn!(
+                "FFmpeg build is missing encoders: {:?}",
+                missing
+                    .iter()
+                    .map(|m| m.encoder.as_str())
+                    .collect::<Vec<_>>()
+            );
+            return Some(format!(
+                "使用中のFFmpegには次のエンコーダーが含まれていません。\n別のコーデックを選択するか、対応するFFmpegを指定してください。\n\n{}",
+                missing
+                    .iter()
+                    .map(|m| m.describe())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ));
+        }
+
+        None
+    }
+
+    /// 変換前にファイルごとに設定の組み合わせを検証
+    /// FFmpegが失敗する組み合わせのファイルは変換せず、警告は重複を除いてまとめる
+    pub fn validate_files(&self, files: &[FileEntry]) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        for (index, file) in files.iter().enumerate() {
+            if file.status.is_completed() {
+                continue;
+            }
+            let issues = self.settings_for(file).validate(Some(&file.metadata));
+            let (errors, warnings): (Vec<_>, Vec<_>) =
+                issues.into_iter().partition(|issue| issue.is_error());
+            for warning in warnings {
+                if !report.warnings.contains(&warning.message) {
+                    warn!("Settings warning: {}", warning.message);
+                    report.warnings.push(warning.message);
+                }
+            }
+            if !errors.is_empty() {
+                let message = errors
+                    .into_iter()
+                    .map(|issue| issue.message)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                warn!("Invalid settings for {}: {}", file.name, message);
+                report.rejected.push((index, message));
+            }
+        }
+        report
+    }
+
+    /// バッチで使うエンコーダーを解決（HWエンコーダーのテストを含む）
+    pub fn resolve_encode_plan(&self) -> ResolvedEncodePlan {
+        let plan = ResolvedEncodePlan::resolve(&self.settings, Some(&self.ffmpeg_path));
+        info!(
+            "Resolved encoder for batch: {} ({:?})",
+            plan.encoder, plan.hwaccel
+        );
+        plan
+    }
+}
+
+/// 変換前の設定の検証結果
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// 変換しないファイル（キューの位置, 理由）
+    pub rejected: Vec<(usize, String)>,
+    /// 警告（重複を除く）
+    pub warnings: Vec<String>,
+}
+
+impl ValidationReport {
+    /// 変換しないファイルの理由（変換してよければNone）
+    pub fn rejection(&self, index: usize) -> Option<&str> {
+        self.rejected
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, message)| message.as_str())
+    }
+}
+
+/// HWエンコーダーの同時セッション数を制限する割り当て
+/// 並列ジョブのうち上限を超えた分は、ソフトウェアエンコーダーで変換するか空くまで待つ
+pub struct SessionSlots {
+    /// 同時に使えるセッション数（Noneなら制限なし）
+    limit: Option<usize>,
+    /// 使用中のセッション数
+    in_use: Mutex<usize>,
+    /// セッションが空いたことの通知
+    released: Condvar,
+}
+
+impl SessionSlots {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            in_use: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// 空いていればセッションを確保
+    pub fn try_acquire(&self) -> Option<SessionSlot<'_>> {
+        let mut in_use = self.in_use.lock().unwrap_or_else(|e| e.into_inner());
+        if self.limit.is_some_and(|limit| *in_use >= limit) {
+            return None;
+        }
+        *in_use += 1;
+        Some(SessionSlot { slots: self })
+    }
+
+    /// セッションが空くまで待って確保
+    pub fn acquire(&self) -> SessionSlot<'_> {
+        let mut in_use = self.in_use.lock().unwrap_or_else(|e| e.into_inner());
+        while self.limit.is_some_and(|limit| *in_use >= limit) {
+            in_use = self
+                .released
+                .wait(in_use)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *in_use += 1;
+        SessionSlot { slots: self }
+    }
+
+    /// ジョブに使うエンコーダーを決め、HWエンコーダーならセッションを確保
+    /// 上限に達している場合は `software` があればそれを使い、なければ空くまで待つ
+    pub fn assign(
+        &self,
+        plan: &ResolvedEncodePlan,
+        software: Option<&ResolvedEncodePlan>,
+    ) -> (ResolvedEncodePlan, Option<SessionSlot<'_>>) {
+        if let Some(assigned) = self.try_assign(plan, software) {
+            return assigned;
+        }
+        info!("Waiting for a free {} session", plan.encoder);
+        (plan.clone(), Some(self.acquire()))
+    }
+
+    /// 待たずに割り当てられればエンコーダーとセッションを返す
+    /// 上限に達していて `software` もなければNone（GUIは非同期に待つため）
+    pub fn try_assign(
+        &self,
+        plan: &ResolvedEncodePlan,
+        software: Option<&ResolvedEncodePlan>,
+    ) -> Option<(ResolvedEncodePlan, Option<SessionSlot<'_>>)> {
+        if plan.hwaccel == HwAccelType::Software {
+            return Some((plan.clone(), None));
+        }
+        if let Some(slot) = self.try_acquire() {
+            return Some((plan.clone(), Some(slot)));
+        }
+        let software = software?;
+        info!(
+            "{} sessions are in use, encoding with {}",
+            plan.encoder, software.encoder
+        );
+        Some((software.clone(), None))
+    }
+}
+
+/// 確保したHWエンコーダーのセッション（dropで解放）
+pub struct SessionSlot<'a> {
+    slots: &'a SessionSlots,
+}
+
+impl Drop for SessionSlot<'_> {
+    fn drop(&mut self) {
+        let mut in_use = self.slots.in_use.lock().unwrap_or_else(|e| e.into_inner());
+        *in_use -= 1;
+        self.slots.released.notify_one();
+    }
+}
+
+/// キューから次に変換するファイル
+pub enum NextFile {
+    /// 変換する
+    Ready(Box<FileEntry>),
+    /// メタデータを取得中のファイルだけが残っている（取得を待つ）
+    Probing,
+    /// 変換するファイルが残っていない
+    Done,
+}
+
+/// キューを先頭から見て次に変換するファイルを選ぶ
+/// 変換中に追加されたファイルも拾えるよう、バッチ開始時の一覧ではなく現在のキューから選ぶ
+/// 変換済みのファイルと、このバッチで扱ったファイル（handled）は飛ばす
+pub fn next_file(files: &[FileEntry], handled: &HashSet<PathBuf>) -> NextFile {
+    let mut probing = false;
+    for file in files {
+        if file.status.is_completed() || handled.contains(&file.path) {
+            continue;
+        }
+        // 長さや解像度が分からないと出力の設定を決められない
+        if file.probing {
+            probing = true;
+            continue;
+        }
+        return NextFile::Ready(Box::new(file.clone()));
+    }
+    if probing {
+        NextFile::Probing
+    } else {
+        NextFile::Done
+    }
+}
+
+/// ジョブの準備結果
+pub enum PreparedJob {
+    /// 変換する（出力先の補足があれば一覧に表示する）
+    Run {
+        job: Box<TranscodeJob>,
+        note: Option<String>,
+    },
+    /// 出力先に既存ファイルがあるためスキップ
+    Skip { note: Option<String> },
+}
+
+/// 出力先を上書きポリシーに従って決め、ジョブを作成
+pub fn prepare_job(
+    options: &QueueOptions,
+    file: &FileEntry,
+    encode_plan: &ResolvedEncodePlan,
+) -> PreparedJob {
+    // 出力ディレクトリ（設定がなければ入力ファイルと同じディレクトリ）
+    let out_dir = options.settings.output_dir.clone().unwrap_or_else(|| {
+        file.path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+    });
+    let output_path = TranscodeJob::generate_output_path(
+        &file.path,
+        &out_dir,
+        &options.settings.output_suffix,
+        &options.settings,
+    );
+    prepare_job_with_output(options, file, encode_plan, output_path)
+}
+
+/// 出力先を指定してジョブを作成（既存ファイルとの衝突は上書きポリシーに従う）
+pub fn prepare_job_with_output(
+    options: &QueueOptions,
+    file: &FileEntry,
+    encode_plan: &ResolvedEncodePlan,
+    output_path: PathBuf,
+) -> PreparedJob {
+    // 既存ファイルと、このバッチの他のファイルに割り当てた出力先との衝突を解決
+    // 並列ジョブが同じ出力先を選ばないよう、ロックしたまま決めて予約する
+    // 他のファイルの出力は上書きする設定でも上書きしない（別名にする）
+    let resolution = {
+        let mut reserved = options
+            .reserved_outputs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let policy = match options.settings.overwrite_policy {
+            OverwritePolicy::Overwrite if reserved.contains(&output_path) => {
+                OverwritePolicy::Rename
+            }
+            policy => policy,
+        };
+        let resolution =
+            TranscodeJob::resolve_output_path_with(&file.path, output_path, policy, |path| {
+                reserved.contains(path) || path.exists()
+            });
+        if let Some(path) = resolution.path() {
+            reserved.insert(path.clone());
+        }
+        resolution
+    };
+    let note = resolution.note();
+    let Some(output_path) = resolution.path().cloned() else {
+        info!("Output already exists, skipping: {}", file.name);
+        return PreparedJob::Skip { note };
+    };
+
+    // 目標サイズモードはファイルごとにCRFを算出
+    let settings = options
+        .settings
+        .resolve_target_size(file.size, &file.metadata);
+    let job = TranscodeJob::new(file.path.clone(), output_path, settings)
+        .with_metadata(file.metadata.clone())
+        .with_crop(file.crop)
+        .with_encode_plan(encode_plan.clone());
+    PreparedJob::Run {
+        job: Box::new(job),
+        note,
+    }
+}
+
+/// ファイルを変換するFFmpegコマンドを、実行時と同じ手順で生成
+/// 出力先が既に存在してスキップする場合はその理由を返す
+pub fn command_line_for(
+    options: &QueueOptions,
+    file: &FileEntry,
+    encode_plan: &ResolvedEncodePlan,
+) -> Result<String, String> {
+    match prepare_job(options, file, encode_plan) {
+        PreparedJob::Run { job, .. } => Ok(job.command_line(&options.ffmpeg_path)),
+        PreparedJob::Skip { note } => Err(note.unwrap_or_default()),
+    }
+}
+
+/// キューのすべてのファイルのFFmpegコマンドを生成
+/// 設定を固定したファイルはその設定を使い、エンコーダーは設定の組み合わせごとに一度だけ解決する
+pub fn command_lines(options: &QueueOptions, files: &[FileEntry]) -> Vec<FileCommand> {
+    let mut encode_plans: Vec<(_, ResolvedEncodePlan)> = Vec::new();
+    files
+        .iter()
+        .map(|file| {
+            let options = options.for_file(file);
+            let key = (options.settings.video_codec, options.settings.hwaccel);
+            let encode_plan = match encode_plans.iter().find(|(k, _)| *k == key) {
+                Some((_, plan)) => plan.clone(),
+                None => {
+                    let plan = options.resolve_encode_plan();
+                    encode_plans.push((key, plan.clone()));
+                    plan
+                }
+            };
+            FileCommand {
+                name: file.name.clone(),
+                summary: options.settings.short_summary(),
+                command: command_line_for(&options, file, &encode_plan),
+            }
+        })
+        .collect()
+}
+
+/// 設定を試すためのサンプル（ファイル中央の30秒）のジョブを作成
+/// 一時ディレクトリに「_sample」を付けて書き出し、前回のサンプルは上書きする
+pub fn prepare_sample_job(
+    options: &QueueOptions,
+    file: &FileEntry,
+    encode_plan: &ResolvedEncodePlan,
+) -> Result<TranscodeJob> {
+    let duration = file
+        .metadata
+        .duration
+        .filter(|d| *d > 0.0)
+        .context("長さが不明なファイルはサンプルを作成できません")?;
+    let out_dir = std::env::temp_dir().join("kamaitachi_samples");
+    std::fs::create_dir_all(&out_dir)
+        .with_context(|| format!("{} を作成できません", out_dir.display()))?;
+    let output_path =
+        TranscodeJob::generate_output_path(&file.path, &out_dir, "_sample", &options.settings);
+
+    let mut settings = options
+        .settings
+        .resolve_target_size(file.size, &file.metadata);
+    settings.overwrite_policy = OverwritePolicy::Overwrite;
+    let (start_secs, duration_secs) = sample_segment(duration);
+    Ok(TranscodeJob::new(file.path.clone(), output_path, settings)
+        .with_metadata(file.metadata.clone())
+        .with_crop(file.crop)
+        .with_encode_plan(encode_plan.clone())
+        .with_segment(start_secs, duration_secs))
+}
+
+/// サンプルにする区間（開始秒, 長さ秒）。短いファイルは全体
+fn sample_segment(duration: f64) -> (f64, f64) {
+    let secs = SAMPLE_SECS.min(duration);
+    ((duration - secs) / 2.0, secs)
+}
+
+/// サンプルのサイズからファイル全体のサイズを見積もる
+pub fn extrapolate_sample_size(sample_size: u64, sample_secs: f64, duration: f64) -> u64 {
+    if sample_secs <= 0.0 {
+        return sample_size;
+    }
+    (sample_size as f64 * duration / sample_secs).round() as u64
+}
+
+/// ファイル1件の変換結果
+pub struct FileOutcome {
+    /// 変換後の状態
+    pub status: FileStatus,
+    /// 失敗したときのFFmpegのstderr
+    pub log: Option<String>,
+    /// 画質評価の結果
+    pub quality: Option<QualityScore>,
+    /// 失敗の通知に使う短いメッセージ
+    pub failure: Option<String>,
+    /// 完了した場合の出力ファイル
+    pub output_path: Option<PathBuf>,
+    /// 完了した場合の出力サイズ（バイト）
+    pub output_size: Option<u64>,
+    /// 状態に添える補足（代替エンコーダーで再試行した場合など）
+    pub note: Option<String>,
+    /// 実際に使ったエンコーダー（スマートコピーした場合はストリームコピー）
+    pub encode_plan: Option<ResolvedEncodePlan>,
+    /// FFmpegでの変換にかかった時間（検証・画質評価を除く）
+    pub elapsed: Duration,
+    /// 失敗の原因（容量不足ならバッチを中止する）
+    pub error_kind: Option<FfmpegErrorKind>,
+}
+
+impl FileOutcome {
+    fn new(status: FileStatus) -> Self {
+        Self {
+            status,
+            log: None,
+            quality: None,
+            failure: None,
+            output_path: None,
+            output_size: None,
+            note: None,
+            encode_plan: None,
+            elapsed: Duration::ZERO,
+            error_kind: None,
+        }
+    }
+
+    fn failed(status_message: String, failure: String) -> Self {
+        Self {
+            failure: Some(failure),
+            ..Self::new(FileStatus::Error(status_message))
+        }
+    }
+}
+
+/// ジョブを実行し、検証・画質評価まで行う
+/// エンコーダーの初期化に失敗した場合は代替エンコーダーで一度だけ再試行し、`job` を更新する
+pub fn run_job(
+    options: &QueueOptions,
+    file: &FileEntry,
+    job: &mut TranscodeJob,
+    progress: &CurrentProgress,
+) -> FileOutcome {
+    let total_duration_secs = match job.segment {
+        Some((_, duration_secs)) => duration_secs,
+        None => file.metadata.duration.unwrap_or(0.0),
+    };
+    info!(
+        "Total duration for {}: {:.2}s",
+        file.name, total_duration_secs
+    );
+
+    // 前のファイルで出力先が埋まっていれば、FFmpegが途中で失敗する前に止める
+    // サンプルは短いため確認しない
+    if job.segment.is_none() {
+        if let Some(outcome) = check_output_space(file, job) {
+            return outcome;
+        }
+    }
+
+    // 実行前からあった出力は利用者のファイルなので、失敗・中止しても消さない
+    // （-n でFFmpegが上書きを拒否した場合や、上書きする前に失敗した場合）
+    let output_existed = job.output_path.exists();
+    let mut fallback_attempted = false;
+    // 再試行した場合は最後の実行の時間を記録する
+    let mut started;
+    let result = loop {
+        progress.push_log(format!("=== {} ===", file.name));
+        started = Instant::now();
+        let result = run_ffmpeg(options, job, total_duration_secs, progress);
+
+        if fallback_attempted || progress.is_cancelled() || progress.is_skip_requested() {
+            break result;
+        }
+        let Some(fallback) = fallback_plan(options, job, &result) else {
+            break result;
+        };
+
+        let failed_encoder = job
+            .encode_plan
+            .as_ref()
+            .map(|plan| plan.encoder.clone())
+            .unwrap_or_default();
+        warn!(
+            "Encoder {} failed for {}, retrying with {}",
+            failed_encoder, file.name, fallback.encoder
+        );
+        progress.push_log(format!(
+            "=== {} が使用できないため {} で再試行 ===",
+            failed_encoder, fallback.encoder
+        ));
+        if !output_existed {
+            job.remove_partial_output();
+        }
+        *job = job.clone().with_encode_plan(fallback);
+        fallback_attempted = true;
+        progress.reset();
+        progress.record_fallback();
+    };
+
+    let elapsed = started.elapsed();
+    let outcome = finish_job(options, file, job, progress, result, output_existed);
+    let encode_plan = job.actual_encode_plan(Some(&options.ffmpeg_path));
+    // 代替エンコーダーで再試行した場合は状態に添えて表示する
+    let note = fallback_attempted
+        .then(|| format!("ソフトウェアにフォールバック ({})", encode_plan.encoder));
+    FileOutcome {
+        note,
+        encode_plan: Some(encode_plan),
+        elapsed,
+        ..outcome
+    }
+}
+
+/// 出力先に1ファイル分（予測サイズ）の空き容量がなければ、容量不足として失敗させる
+fn check_output_space(file: &FileEntry, job: &TranscodeJob) -> Option<FileOutcome> {
+    let dir = job.output_path.parent()?;
+    let shortage = check_file_space(dir, file.estimated_size.unwrap_or(file.size))?;
+    warn!("{} ({:?})", shortage.message(), shortage.dir);
+    let error = FfmpegError::disk_full(&shortage.message());
+    Some(FileOutcome {
+        error_kind: Some(error.kind.clone()),
+        ..FileOutcome::failed(error.format_user_message(), shortage.message())
+    })
+}
+
+/// FFmpegの実行結果から変換結果をまとめ、検証・画質評価まで行う
+/// `output_existed` は実行前から出力先にファイルがあったか（あれば失敗しても削除しない）
+fn finish_job(
+    options: &QueueOptions,
+    file: &FileEntry,
+    job: &TranscodeJob,
+    progress: &CurrentProgress,
+    result: std::io::Result<Output>,
+    output_existed: bool,
+) -> FileOutcome {
+    // キャンセル・スキップされた場合
+    if progress.is_cancelled() || progress.is_skip_requested() {
+        info!("Transcode was cancelled: {}", file.name);
+        // 途中まで書き込まれた出力ファイルを削除
+        if !output_existed {
+            job.remove_partial_output();
+        }
+        return FileOutcome::new(FileStatus::Cancelled);
+    }
+
+    let output = match result {
+        Ok(output) => output,
+        Err(e) => {
+            error!("Failed to run FFmpeg: {}", e);
+            return FileOutcome::failed(e.to_string(), e.to_string());
+        }
+    };
+
+    if !output.status.success() {
+        // FFmpegエラーを解析してユーザーフレンドリーなメッセージを生成
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let parsed_error = FfmpegError::parse(&stderr);
+
+        // ログには詳細を出力
+        error!("Transcode failed: {}", stderr);
+        error!("Parsed error: {:?}", parsed_error.kind);
+
+        // 途中まで書き込まれた出力ファイルを削除
+        if !options.keep_partial_on_error && !output_existed {
+            job.remove_partial_output();
+        }
+
+        // 失敗時はstderr全体を保存してリストから確認できるようにする
+        return FileOutcome {
+            log: Some(stderr.to_string()),
+            error_kind: Some(parsed_error.kind.clone()),
+            ..FileOutcome::failed(
+                parsed_error.format_user_message(),
+                parsed_error.user_message.clone(),
+            )
+        };
+    }
+
+    // 出力ファイルを検証（終了コードが0でも途中で切れている場合がある）
+    // 区間だけのサンプルは元の動画と長さが違うため、検証・画質評価はしない
+    if let Some(ffmpeg_info) = options
+        .verify_ffmpeg_info
+        .as_ref()
+        .filter(|_| job.segment.is_none())
+    {
+        progress.push_log(format!("=== {} を検証中 ===", file.name));
+        if let Err(e) = verify_output(ffmpeg_info, &job.output_path, &job.settings, &file.metadata)
+        {
+            error!(
+                "Output verification failed for {:?}: {:#}",
+                job.output_path, e
+            );
+            let message = format!("検証失敗: {:#}", e);
+            progress.push_log(message.clone());
+            return FileOutcome::failed(message.clone(), message);
+        }
+    }
+
+    info!("Transcode completed: {:?}", job.output_path);
+    let quality = analyze(options, file, job, progress);
+    let output_size = std::fs::metadata(&job.output_path).map(|m| m.len()).ok();
+    FileOutcome {
+        quality,
+        output_path: Some(job.output_path.clone()),
+        output_size,
+        ..FileOutcome::new(FileStatus::completed(quality, file.size, output_size))
+    }
+}
+
+/// 画質を評価（失敗しても変換結果には影響させない）
+fn analyze(
+    options: &QueueOptions,
+    file: &FileEntry,
+    job: &TranscodeJob,
+    progress: &CurrentProgress,
+) -> Option<QualityScore> {
+    let ffmpeg_info = options
+        .quality_ffmpeg_info
+        .as_ref()
+        .filter(|_| job.segment.is_none())?;
+    progress.push_log(format!("=== {} の画質を評価中 ===", file.name));
+    match analyze_quality(
+        ffmpeg_info,
+        &file.path,
+        &job.output_path,
+        &job.settings,
+        &file.metadata,
+        options.quality_samples,
+    ) {
+        Ok(score) => {
+            info!("Quality of {:?}: {}", job.output_path, score.label());
+            progress.push_log(score.label());
+            Some(score)
+        }
+        Err(e) => {
+            warn!("Quality analysis failed for {:?}: {:#}", job.output_path, e);
+            progress.push_log(format!("画質を評価できません: {:#}", e));
+            None
+        }
+    }
+}
+
+/// エンコーダー起因の失敗なら代替のエンコーダーを返す
+/// ソフトウェアエンコードへの自動切り替えを無効にしている場合は返さない
+fn fallback_plan(
+    options: &QueueOptions,
+    job: &TranscodeJob,
+    result: &std::io::Result<Output>,
+) -> Option<ResolvedEncodePlan> {
+    if !options.auto_fallback_to_software {
+        return None;
+    }
+    let error_kind = match result {
+        Ok(output) if !output.status.success() => {
+            FfmpegError::parse(&String::from_utf8_lossy(&output.stderr)).kind
+        }
+        _ => return None,
+    };
+    job.encode_plan.as_ref()?.fallback_for(
+        &options.settings,
+        &error_kind,
+        Some(&options.ffmpeg_path),
+    )
+}
+
+/// FFmpegを実行し、-progressの出力から進捗を更新する
+/// キャンセル・スキップが要求されたらプロセスを終了する
+fn run_ffmpeg(
+    options: &QueueOptions,
+    job: &TranscodeJob,
+    total_duration_secs: f64,
+    current_progress: &CurrentProgress,
+) -> std::io::Result<Output> {
+    // FFmpegコマンドを構築（解決済みのエンコーダーを使用）
+    let args = job.build_ffmpeg_args_with_path(Some(&options.ffmpeg_path));
+    info!("Running FFmpeg: {:?} {:?}", options.ffmpeg_path, args);
+
+    let start_time = Instant::now();
+    // 総時間を設定
+    current_progress.set_total_duration_secs(total_duration_secs);
+
+    let mut child = spawn_with_priority(
+        background_command(&options.ffmpeg_path)
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+        options.priority,
+    )?;
+
+    // stderrは別スレッドで読み取り、ログに流しつつエラー解析用に保持する
+    let log_progress = current_progress.clone();
+    let stderr_reader = child.stderr.take().map(|stderr| {
+        std::thread::spawn(move || {
+            let mut collected = String::new();
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                collected.push_str(&line);
+                collected.push('\n');
+                log_progress.push_log(line);
+            }
+            collected
+        })
+    });
+
+    // stdoutから進捗情報を読み取る（-progress pipe:1形式）
+    if let Some(stdout) = child.stdout.take() {
+        let reader = BufReader::new(stdout);
+        let mut progress_info = FfmpegProgressInfo::default();
+        let mut speed_estimator = SpeedEstimator::default();
+
+        for line_result in reader.lines() {
+            // キャンセル・スキップチェック
+            if current_progress.is_cancelled() || current_progress.is_skip_requested() {
+                info!("Transcode cancelled, killing FFmpeg process");
+                let _ = child.kill();
+                break;
+            }
+
+            let Ok(line) = line_result else {
+                continue;
+            };
+            // 行を累積的にパースして、進捗ブロックが完了したら更新
+            if !progress_info.parse_progress_line(&line) {
+                continue;
+            }
+            // progress=continue または progress=end が来たらブロック完了
+            if progress_info.is_valid() {
+                // time_secsベースで進捗を更新
+                current_progress.update_progress_from_time(progress_info.time_secs);
+                let progress = current_progress.get_progress();
+
+                current_progress.set_elapsed_secs(start_time.elapsed().as_secs_f32());
+                current_progress.set_fps(progress_info.fps);
+                // total_size=N/A の間は前回の値を維持
+                if progress_info.size > 0 {
+                    current_progress.set_output_size(progress_info.size);
+                }
+                if progress_info.bitrate > 0.0 {
+                    current_progress.set_bitrate_kbps(progress_info.bitrate);
+                }
+
+                // 残り時間を計算（変換速度が分かれば速度から、なければ経過時間から外挿）
+                if let Some(speed) = speed_estimator.update(progress_info.speed) {
+                    current_progress.set_speed(speed as f32);
+                    let remaining = speed_estimator.remaining_secs(
+                        current_progress.get_total_duration_secs(),
+                        progress_info.time_secs,
+                    );
+                    current_progress.set_remaining_secs(remaining.map(|s| s as f32));
+                } else if progress > 0.01 {
+                    let elapsed = start_time.elapsed().as_secs_f32();
+                    let total_estimated = elapsed / progress;
+                    let remaining = (total_estimated - elapsed).max(0.0);
+                    current_progress.set_remaining_secs(Some(remaining));
+                }
+
+                log::debug!(
+                    "Progress: frame={}, time={:.2}s, total={:.2}s, progress={:.1}%",
+                    progress_info.frame,
+                    progress_info.time_secs,
+                    current_progress.get_total_duration_secs(),
+                    progress * 100.0
+                );
+            }
+            // 次のブロック用にリセット（フレーム・FPS・時間は保持）
+            progress_info.start_next_block();
+        }
+    }
+
+    let status = child.wait()?;
+    let stderr = stderr_reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    Ok(Output {
+        status,
+        stdout: Vec::new(),
+        stderr: stderr.into_bytes(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_file_picks_up_added_files() {
+        let entry = |name: &str, status: FileStatus| {
+            let mut entry = FileEntry::new(PathBuf::from(name));
+            entry.status = status;
+            entry
+        };
+        let mut files = vec![
+            entry("done.mp4", FileStatus::completed(None, 0, None)),
+            entry("first.mp4", FileStatus::Pending),
+        ];
+        let mut handled = HashSet::new();
+
+        let NextFile::Ready(file) = next_file(&files, &handled) else {
+            panic!("first file should be ready");
+        };
+        assert_eq!(file.path, PathBuf::from("first.mp4"));
+        handled.insert(file.path);
+        assert!(matches!(next_file(&files, &handled), NextFile::Done));
+
+        // 変換中に追加されたファイルはメタデータの取得を待ってから変換する
+        let mut added = entry("added.mp4", FileStatus::Pending);
+        added.probing = true;
+        files.push(added);
+        assert!(matches!(next_file(&files, &handled), NextFile::Probing));
+        files[2].probing = false;
+        let NextFile::Ready(file) = next_file(&files, &handled) else {
+            panic!("added file should be ready");
+        };
+        assert_eq!(file.path, PathBuf::from("added.mp4"));
+    }
+
+    #[test]
+    fn test_sample_segment_and_extrapolation() {
+        // 中央の30秒
+        assert_eq!(sample_segment(3600.0), (1785.0, 30.0));
+        // 30秒未満のファイルは全体
+        assert_eq!(sample_segment(12.0), (0.0, 12.0));
+
+        assert_eq!(
+            extrapolate_sample_size(10_000_000, 30.0, 3600.0),
+            1_200_000_000
+        );
+        assert_eq!(extrapolate_sample_size(10_000_000, 12.0, 12.0), 10_000_000);
+        assert_eq!(extrapolate_sample_size(10_000_000, 0.0, 3600.0), 10_000_000);
+    }
+
+    #[test]
+    fn test_prepare_sample_job() {
+        use crate::transcoder::HwAccelType;
+
+        let settings = TranscodeSettings {
+            hwaccel: HwAccelType::Software,
+            ..Default::default()
+        };
+        let options = QueueOptions::new(PathBuf::from("ffmpeg"), &settings);
+        let plan = ResolvedEncodePlan {
+            encoder: "libx265".to_string(),
+            hwaccel: HwAccelType::Software,
+        };
+        let mut file = FileEntry::new(PathBuf::from("/videos/episode 01.mkv"));
+        assert!(prepare_sample_job(&options, &file, &plan).is_err());
+
+        file.metadata.duration = Some(1440.0);
+        let job = prepare_sample_job(&options, &file, &plan).unwrap();
+        assert_eq!(job.segment, Some((705.0, 30.0)));
+        assert_eq!(job.settings.overwrite_policy, OverwritePolicy::Overwrite);
+        assert!(job.output_path.starts_with(std::env::temp_dir()));
+        let name = job.output_path.file_stem().unwrap().to_string_lossy();
+        assert_eq!(name, "episode 01_sample");
+    }
+
+    #[test]
+    fn test_for_file_uses_settings_override() {
+        use crate::transcoder::VideoCodec;
+
+        let settings = TranscodeSettings {
+            hwaccel: HwAccelType::Software,
+            ..Default::default()
+        };
+        let options = QueueOptions::new(PathBuf::from("ffmpeg"), &settings);
+        let mut file = FileEntry::new(PathBuf::from("/videos/screen.mkv"));
+        assert_eq!(
+            options.for_file(&file).settings.video_codec,
+            VideoCodec::H264
+        );
+
+        file.settings_override = Some(TranscodeSettings {
+            video_codec: VideoCodec::Av1,
+            crf: 35,
+            hwaccel: HwAccelType::Software,
+            ..Default::default()
+        });
+        let file_options = options.for_file(&file);
+        assert_eq!(file_options.settings.video_codec, VideoCodec::Av1);
+        assert_eq!(file_options.settings.crf, 35);
+        assert_eq!(options.settings.video_codec, VideoCodec::H264);
+    }
+
+    #[test]
+    fn test_finish_job_keeps_existing_output() {
+        let dir = std::env::temp_dir().join(format!("kamaitachi-queue-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output_path = dir.join("movie_transcoded.mp4");
+        let options = QueueOptions::new(PathBuf::from("ffmpeg"), &TranscodeSettings::default());
+        let file = FileEntry::new(dir.join("movie.mkv"));
+        let job = TranscodeJob::new(
+            file.path.clone(),
+            output_path.clone(),
+            options.settings.clone(),
+        );
+        let progress = CurrentProgress::default();
+        progress.cancel();
+        let result = || Err(std::io::Error::other("interrupted"));
+
+        // 実行前からあった出力（-n で上書きを拒否された場合など）は残す
+        std::fs::write(&output_path, b"existing").unwrap();
+        finish_job(&options, &file, &job, &progress, result(), true);
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"existing");
+
+        // この実行で書き込んだ出力は削除する
+        finish_job(&options, &file, &job, &progress, result(), false);
+        assert!(!output_path.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_session_slots_limit_hw_jobs() {
+        let nvenc = ResolvedEncodePlan {
+            encoder: "hevc_nvenc".to_string(),
+            hwaccel: HwAccelType::Nvenc,
+        };
+        let software = ResolvedEncodePlan {
+            encoder: "libx265".to_string(),
+            hwaccel: HwAccelType::Software,
+        };
+
+        // 6件の並列ジョブのうち3件がNVENC、残りはソフトウェアで変換する
+        let slots = SessionSlots::new(Some(3));
+        let assigned: Vec<_> = (0..6)
+            .map(|_| slots.assign(&nvenc, Some(&software)))
+            .collect();
+        let on_nvenc = assigned.iter().filter(|(plan, _)| *plan == nvenc).count();
+        assert_eq!(on_nvenc, 3);
+        assert!(assigned
+            .iter()
+            .all(|(plan, slot)| (*plan == nvenc) == slot.is_some()));
+        drop(assigned);
+
+        // ソフトウェアエンコーダーがなければ空くまで待つ
+        let held: Vec<_> = (0..3).map(|_| slots.assign(&nvenc, None)).collect();
+        assert!(slots.try_acquire().is_none());
+        assert!(slots.try_assign(&nvenc, None).is_none());
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let (plan, slot) = slots.assign(&nvenc, None);
+                sender.send((plan, slot.is_some())).unwrap();
+            });
+            assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+            drop(held);
+            assert_eq!(
+                receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+                (nvenc.clone(), true)
+            );
+        });
+
+        // ソフトウェアエンコーダーと上限のないHWアクセラレーションはセッションを数えない
+        let (plan, slot) = slots.assign(&software, None);
+        assert_eq!(plan, software);
+        assert!(slot.is_none());
+        let unlimited = SessionSlots::new(None);
+        let held: Vec<_> = (0..10).filter_map(|_| unlimited.try_acquire()).collect();
+        assert_eq!(held.len(), 10);
+    }
+
+    #[test]
+    fn test_session_limit_prefers_app_setting() {
+        let settings = TranscodeSettings {
+            hwaccel: HwAccelType::Software,
+            ..Default::default()
+        };
+        let app_settings = Settings {
+            nvenc_session_limit: 5,
+            ..Default::default()
+        };
+        let options = QueueOptions::new(PathBuf::from("ffmpeg"), &settings)
+            .with_app_settings(&app_settings, None);
+        assert_eq!(options.nvenc_session_limit, Some(5));
+        assert_eq!(options.session_limit(&HwAccelType::Nvenc), Some(5));
+        assert_eq!(options.session_limit(&HwAccelType::Software), None);
+
+        // 0は自動判定
+        let options = options.with_app_settings(&Settings::default(), None);
+        assert_eq!(options.nvenc_session_limit, None);
+    }
+
+    #[test]
+    fn test_prepare_job_reserves_output_paths() {
+        let dir =
+            std::env::temp_dir().join(format!("kamaitachi-queue-reserve-{}", std::process::id()));
+        let out_dir = dir.join("out");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let plan = ResolvedEncodePlan {
+            encoder: "libx264".to_string(),
+            hwaccel: HwAccelType::Software,
+        };
+        // 別のフォルダにある同じ名前のファイルは同じ出力先になる
+        let first = FileEntry::new(dir.join("a").join("movie.mkv"));
+        let second = FileEntry::new(dir.join("b").join("movie.mkv"));
+        let output_of = |prepared| match prepared {
+            PreparedJob::Run { job, .. } => Some(job.output_path),
+            PreparedJob::Skip { .. } => None,
+        };
+
+        for policy in [OverwritePolicy::Rename, OverwritePolicy::Overwrite] {
+            let settings = TranscodeSettings {
+                hwaccel: HwAccelType::Software,
+                output_dir: Some(out_dir.clone()),
+                overwrite_policy: policy,
+                ..Default::default()
+            };
+            // 同じバッチ（オプションを共有するジョブ）では、出力前でも別の出力先を割り当てる
+            let options = QueueOptions::new(PathBuf::from("ffmpeg"), &settings);
+            let first_output = output_of(prepare_job(&options, &first, &plan)).unwrap();
+            let second_output =
+                output_of(prepare_job(&options.for_file(&second), &second, &plan)).unwrap();
+            assert_eq!(first_output, out_dir.join("movie_transcoded.mp4"));
+            assert_eq!(second_output, out_dir.join("movie_transcoded_1.mp4"));
+        }
+
+        // スキップする設定では、他のファイルの出力先と重なるファイルをスキップ
+        let settings = TranscodeSettings {
+            hwaccel: HwAccelType::Software,
+            output_dir: Some(out_dir.clone()),
+            overwrite_policy: OverwritePolicy::Skip,
+            ..Default::default()
+        };
+        let options = QueueOptions::new(PathBuf::from("ffmpeg"), &settings);
+        assert!(output_of(prepare_job(&options, &first, &plan)).is_some());
+        assert!(output_of(prepare_job(&options, &second, &plan)).is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,250 @@
+//! 変換結果のレポート（CSV/JSON）とFFmpegコマンドのスクリプト
+//!
+//! JSONはファイルエントリをそのまま書き出すため、後から読み込んで結果を確認できる
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::app::{FileEntry, FileStatus};
+
+/// レポート形式のバージョン（項目を変更したら上げる）
+const REPORT_VERSION: u32 = 1;
+
+/// レポートの形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+impl ReportFormat {
+    /// 拡張子から形式を判定（不明ならJSON）
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => ReportFormat::Csv,
+            _ => ReportFormat::Json,
+        }
+    }
+}
+
+/// JSONレポート
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueueReport {
+    /// レポート形式のバージョン
+    pub version: u32,
+    /// 書き出した日時（UNIX時間、秒）
+    pub exported_at: u64,
+    /// キューのファイル
+    pub files: Vec<FileEntry>,
+}
+
+/// キューのファイルをレポートとして保存（形式は拡張子で判定）
+pub fn export_report(files: &[FileEntry], path: &Path) -> Result<()> {
+    let content = match ReportFormat::from_path(path) {
+        ReportFormat::Csv => to_csv(files),
+        ReportFormat::Json => {
+            let report = QueueReport {
+                version: REPORT_VERSION,
+                exported_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+                files: files.to_vec(),
+            };
+            serde_json::to_string_pretty(&report)?
+        }
+    };
+    std::fs::write(path, content).with_context(|| format!("{} に保存できません", path.display()))
+}
+
+/// スクリプトに書き出す1ファイル分のFFmpegコマンド
+#[derive(Clone, Debug)]
+pub struct FileCommand {
+    /// ファイル名
+    pub name: String,
+    /// 設定の概要
+    pub summary: String,
+    /// コマンドライン（出力先が既にあって変換しない場合はその理由）
+    pub command: Result<String, String>,
+}
+
+/// FFmpegコマンドをスクリプトとして保存（Windowsはバッチファイル、それ以外はシェルスクリプト）
+pub fn export_command_script(commands: &[FileCommand], path: &Path) -> Result<()> {
+    let content = command_script(commands, cfg!(windows));
+    std::fs::write(path, content)
+        .with_context(|| format!("{} に保存できません", path.display()))?;
+    // シェルスクリプトはそのまま実行できるようにする
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("{} に実行権限を付けられません", path.display()))?;
+    }
+    Ok(())
+}
+
+/// 1ファイルにつき1回FFmpegを実行するスクリプトを生成（ファイル名と設定をコメントに残す）
+fn command_script(commands: &[FileCommand], windows: bool) -> String {
+    let (header, comment) = if windows {
+        // 日本語のファイル名を扱えるようコードページをUTF-8にする
+        ("@echo off\r\nchcp 65001 > nul\r\n", "REM")
+    } else {
+        ("#!/bin/sh\n", "#")
+    };
+    let newline = if windows { "\r\n" } else { "\n" };
+
+    let mut script = String::from(header);
+    for file in commands {
+        script.push_str(newline);
+        script.push_str(&format!("{} {}{}", comment, file.name, newline));
+        script.push_str(&format!("{} {}{}", comment, file.summary, newline));
+        match &file.command {
+            // バッチファイルでは%が変数の展開に使われる
+            Ok(command) if windows => script.push_str(&command.replace('%', "%%")),
+            Ok(command) => script.push_str(command),
+            Err(reason) => script.push_str(&format!("{} スキップ: {}", comment, reason)),
+        }
+        script.push_str(newline);
+    }
+    script
+}
+
+/// CSVに変換（1行目は見出し）
+fn to_csv(files: &[FileEntry]) -> String {
+    let mut csv = String::from("path,input_size,output_size,duration,status,error\n");
+    for file in files {
+        let error = match &file.status {
+            FileStatus::Error(message) | FileStatus::Skipped(message) => message.as_str(),
+            _ => "",
+        };
+        let fields = [
+            file.path.to_string_lossy().to_string(),
+            file.size.to_string(),
+            file.output_size.map(|s| s.to_string()).unwrap_or_default(),
+            file.metadata
+                .duration
+                .map(|d| format!("{:.2}", d))
+                .unwrap_or_default(),
+            file.status.label().to_string(),
+            error.to_string(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// カンマ・引用符・改行を含む値を引用符で囲む
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entries() -> Vec<FileEntry> {
+        let mut done = FileEntry::new(PathBuf::from("/videos/a, b.mkv"));
+        done.size = 1000;
+        done.status = FileStatus::completed(None, 1000, Some(400));
+        done.metadata.duration = Some(12.5);
+        done.output_path = Some(PathBuf::from("/out/a, b.mp4"));
+        done.output_size = Some(400);
+
+        let mut failed = FileEntry::new(PathBuf::from("/videos/c.mp4"));
+        failed.size = 2000;
+        failed.status =
+            FileStatus::Error("エンコーダー \"hevc_nvenc\" を使用できません".to_string());
+        failed.log = Some("stderr".to_string());
+        vec![done, failed]
+    }
+
+    #[test]
+    fn test_to_csv() {
+        assert_eq!(
+            to_csv(&entries()),
+            "path,input_size,output_size,duration,status,error\n\
+             \"/videos/a, b.mkv\",1000,400,12.50,完了,\n\
+             /videos/c.mp4,2000,,,エラー,\"エンコーダー \"\"hevc_nvenc\"\" を使用できません\"\n"
+        );
+    }
+
+    #[test]
+    fn test_json_report_round_trip() {
+        let report = QueueReport {
+            version: REPORT_VERSION,
+            exported_at: 0,
+            files: entries(),
+        };
+        let json = serde_json::to_string(&report).unwrap();
+        let loaded: QueueReport = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.files.len(), 2);
+        assert_eq!(loaded.files[0].path, PathBuf::from("/videos/a, b.mkv"));
+        assert_eq!(loaded.files[0].output_size, Some(400));
+        assert_eq!(loaded.files[0].metadata.duration, Some(12.5));
+        assert_eq!(loaded.files[1].status, entries()[1].status);
+        // stderrはレポートに含めない
+        assert_eq!(loaded.files[1].log, None);
+    }
+
+    #[test]
+    fn test_command_script() {
+        let commands = [
+            FileCommand {
+                name: "a.mkv".to_string(),
+                summary: "AV1 CRF 35・MKV".to_string(),
+                command: Ok("ffmpeg -i a.mkv -vf scale=iw*50%:-2 a_converted.mkv".to_string()),
+            },
+            FileCommand {
+                name: "b.mp4".to_string(),
+                summary: "H.264 CRF 23・MP4".to_string(),
+                command: Err("出力先に同名のファイルがあります".to_string()),
+            },
+        ];
+
+        assert_eq!(
+            command_script(&commands, false),
+            "#!/bin/sh\n\
+             \n\
+             # a.mkv\n\
+             # AV1 CRF 35・MKV\n\
+             ffmpeg -i a.mkv -vf scale=iw*50%:-2 a_converted.mkv\n\
+             \n\
+             # b.mp4\n\
+             # H.264 CRF 23・MP4\n\
+             # スキップ: 出力先に同名のファイルがあります\n"
+        );
+
+        // バッチファイルは%をエスケープし、改行はCRLF
+        let bat = command_script(&commands, true);
+        assert!(bat.starts_with("@echo off\r\nchcp 65001 > nul\r\n"));
+        assert!(bat.contains("REM a.mkv\r\n"));
+        assert!(bat.contains("scale=iw*50%%:-2"));
+        assert!(bat.contains("REM スキップ: 出力先に同名のファイルがあります\r\n"));
+    }
+
+    #[test]
+    fn test_report_format_from_path() {
+        assert_eq!(
+            ReportFormat::from_path(Path::new("report.CSV")),
+            ReportFormat::Csv
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("report.json")),
+            ReportFormat::Json
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("report")),
+            ReportFormat::Json
+        );
+    }
+}
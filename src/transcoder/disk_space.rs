@@ -0,0 +1,175 @@
+//! 出力先の空き容量チェック
+
+use log::warn;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::format_size;
+
+/// 推定サイズに対して確保しておく余裕（110%）
+const SPACE_MARGIN: f64 = 1.1;
+
+/// 空き容量の不足
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiskSpaceShortage {
+    /// 出力先ディレクトリ
+    pub dir: PathBuf,
+    /// 必要な容量（余裕込み）
+    pub required: u64,
+    /// 空き容量
+    pub available: u64,
+}
+
+impl DiskSpaceShortage {
+    /// 不足している容量
+    pub fn shortfall(&self) -> u64 {
+        self.required.saturating_sub(self.available)
+    }
+
+    /// ダイアログ表示用のメッセージ
+    pub fn message(&self) -> String {
+        format!(
+            "空き容量が {} 不足しています: 必要 {}, 空き {}",
+            format_size(self.shortfall()),
+            format_size(self.required),
+            format_size(self.available)
+        )
+    }
+}
+
+/// 出力先のボリュームごとに推定サイズを集計して空き容量と比較
+/// 空き容量を取得できなかった出力先は判定しない
+pub fn check_disk_space(outputs: Vec<(PathBuf, u64)>) -> Option<DiskSpaceShortage> {
+    find_shortage(outputs, volume_key, available_space)
+}
+
+/// 1ファイル分の空き容量があるか確認（変換の合間に使うため余裕は見込まない）
+pub fn check_file_space(dir: &Path, size: u64) -> Option<DiskSpaceShortage> {
+    let available = available_space(dir)?;
+    (available < size).then(|| DiskSpaceShortage {
+        dir: dir.to_path_buf(),
+        required: size,
+        available,
+    })
+}
+
+/// 出力先の空き容量
+fn available_space(dir: &Path) -> Option<u64> {
+    match fs2::available_space(existing_ancestor(dir)) {
+        Ok(space) => Some(space),
+        Err(e) => {
+            warn!("Failed to get available space for {:?}: {}", dir, e);
+            None
+        }
+    }
+}
+
+/// ボリュームごとに集計し、最初に容量が足りないボリュームを返す
+/// 同じドライブの別フォルダに出力する場合も合計で判定する（表示には最初の出力先を使う）
+fn find_shortage(
+    outputs: Vec<(PathBuf, u64)>,
+    volume_of: impl Fn(&Path) -> String,
+    available_space: impl Fn(&Path) -> Option<u64>,
+) -> Option<DiskSpaceShortage> {
+    let mut totals: BTreeMap<String, (PathBuf, u64)> = BTreeMap::new();
+    for (dir, size) in outputs {
+        totals.entry(volume_of(&dir)).or_insert_with(|| (dir, 0)).1 += size;
+    }
+
+    totals.into_values().find_map(|(dir, total)| {
+        let required = (total as f64 * SPACE_MARGIN) as u64;
+        let available = available_space(&dir)?;
+        (available < required).then_some(DiskSpaceShortage {
+            dir,
+            required,
+            available,
+        })
+    })
+}
+
+/// 出力先のボリュームを識別するキー
+/// Unixはデバイス番号、Windowsはドライブ（UNCパスは共有名）、取得できなければフォルダ自体
+fn volume_key(dir: &Path) -> String {
+    let dir = existing_ancestor(dir);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let Ok(metadata) = std::fs::metadata(dir) {
+            return format!("dev:{}", metadata.dev());
+        }
+    }
+    #[cfg(windows)]
+    {
+        if let Some(std::path::Component::Prefix(prefix)) = std::path::absolute(dir)
+            .ok()
+            .and_then(|path| path.components().next())
+        {
+            return prefix.as_os_str().to_string_lossy().to_uppercase();
+        }
+    }
+    dir.to_string_lossy().to_string()
+}
+
+/// まだ作成されていない出力先は存在する親ディレクトリで判定する
+fn existing_ancestor(dir: &Path) -> &Path {
+    dir.ancestors().find(|p| p.exists()).unwrap_or(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_shortage_sums_per_directory() {
+        const GB: u64 = 1024 * 1024 * 1024;
+        let outputs = vec![
+            (PathBuf::from("/videos/a"), 3 * GB),
+            (PathBuf::from("/videos/b"), GB),
+            (PathBuf::from("/videos/a"), 2 * GB),
+        ];
+        let space = |dir: &Path| Some(if dir.ends_with("a") { 5 * GB } else { 10 * GB });
+        let per_dir = |dir: &Path| dir.to_string_lossy().to_string();
+
+        // /videos/a は 5GB × 110% が必要だが空きは 5GB
+        let shortage = find_shortage(outputs.clone(), per_dir, space).unwrap();
+        assert_eq!(shortage.dir, PathBuf::from("/videos/a"));
+        assert_eq!(shortage.required, (5.0 * GB as f64 * 1.1) as u64);
+        assert_eq!(shortage.available, 5 * GB);
+        assert_eq!(shortage.shortfall(), shortage.required - 5 * GB);
+        assert_eq!(
+            shortage.message(),
+            "空き容量が 512.00 MB 不足しています: 必要 5.50 GB, 空き 5.00 GB"
+        );
+
+        assert_eq!(
+            find_shortage(outputs.clone(), per_dir, |_| Some(6 * GB)),
+            None
+        );
+        // 空き容量を取得できない場合は判定しない
+        assert_eq!(find_shortage(outputs, per_dir, |_| None), None);
+    }
+
+    #[test]
+    fn test_find_shortage_sums_per_volume() {
+        const GB: u64 = 1024 * 1024 * 1024;
+        let outputs = vec![
+            (PathBuf::from("/videos/a"), 3 * GB),
+            (PathBuf::from("/videos/b"), 3 * GB),
+        ];
+        // フォルダごとでは足りるが、同じボリュームの合計 6GB × 110% には足りない
+        let same_volume = |_: &Path| "dev:1".to_string();
+        let shortage = find_shortage(outputs, same_volume, |_| Some(6 * GB)).unwrap();
+        assert_eq!(shortage.dir, PathBuf::from("/videos/a"));
+        assert_eq!(shortage.required, (6.0 * GB as f64 * 1.1) as u64);
+    }
+
+    #[test]
+    fn test_volume_key_for_missing_dir() {
+        // まだ作成されていない出力先は親と同じボリューム
+        let dir = std::env::temp_dir();
+        assert_eq!(
+            volume_key(&dir.join("kamaitachi-missing/out")),
+            volume_key(&dir)
+        );
+    }
+}
@@ -54,6 +54,15 @@ impl FfmpegError {
             return Self::encoder_not_supported(&encoder, stderr);
         }
 
+        // ディスク容量不足はHWエンコーダーの出力にも現れるため先に判定する
+        // （エンコーダーの失敗と誤判定するとソフトウェアで再試行してしまう）
+        if stderr_lower.contains("no space left")
+            || stderr_lower.contains("disk full")
+            || stderr_lower.contains("not enough space")
+        {
+            return Self::disk_full(stderr);
+        }
+
         // 特定のHWエンコーダーエラー（より具体的なエラーメッセージを先にチェック）
         // Intel QSV関連エラー
         if stderr_lower.contains("no qsv-supporting device")
@@ -82,6 +91,8 @@ impl FfmpegError {
         if stderr_lower.contains("no nvenc capable devices found")
             || stderr_lower.contains("cannot load nvcuda.dll")
             || stderr_lower.contains("cannot load nvencodeapi")
+            // 同時セッション数の上限・ドライバーのリセットなど実行時の失敗
+            || stderr_lower.contains("openencodesessionex failed")
             || stderr_lower.contains("h264_nvenc")
                 && (stderr_lower.contains("error")
                     || stderr_lower.contains("failed")
@@ -90,6 +101,10 @@ impl FfmpegError {
                 && (stderr_lower.contains("error")
                     || stderr_lower.contains("failed")
                     || stderr_lower.contains("not found"))
+            || stderr_lower.contains("av1_nvenc")
+                && (stderr_lower.contains("error")
+                    || stderr_lower.contains("failed")
+                    || stderr_lower.contains("not found"))
         {
             return Self::hwaccel_not_available("NVIDIA NVENC", stderr);
         }
@@ -110,22 +125,20 @@ impl FfmpegError {
         }
 
         // HWアクセラレーションエラー（一般的なパターン - 上記で判定されなかった場合）
-        if stderr_lower.contains("nvenc")
+        if (stderr_lower.contains("nvenc")
             || stderr_lower.contains("qsv")
             || stderr_lower.contains("amf")
             || stderr_lower.contains("cuda")
             || stderr_lower.contains("d3d11")
-            || stderr_lower.contains("vaapi")
-        {
-            if stderr_lower.contains("cannot load")
+            || stderr_lower.contains("vaapi"))
+            && (stderr_lower.contains("cannot load")
                 || stderr_lower.contains("failed to")
                 || stderr_lower.contains("not found")
                 || stderr_lower.contains("unavailable")
-                || stderr_lower.contains("no capable devices")
-            {
-                let hwaccel = Self::extract_hwaccel_name(stderr);
-                return Self::hwaccel_not_available(&hwaccel, stderr);
-            }
+                || stderr_lower.contains("no capable devices"))
+        {
+            let hwaccel = Self::extract_hwaccel_name(stderr);
+            return Self::hwaccel_not_available(&hwaccel, stderr);
         }
 
         // デコーダーがサポートされていない
@@ -157,13 +170,6 @@ impl FfmpegError {
             return Self::permission_denied(stderr);
         }
 
-        if stderr_lower.contains("no space left")
-            || stderr_lower.contains("disk full")
-            || stderr_lower.contains("not enough space")
-        {
-            return Self::disk_full(stderr);
-        }
-
         if stderr_lower.contains("cannot open")
             && (stderr_lower.contains("output") || stderr_lower.contains("writing"))
         {
@@ -278,7 +284,7 @@ impl FfmpegError {
     }
 
     /// ディスク容量不足エラーを作成
-    fn disk_full(raw: &str) -> Self {
+    pub fn disk_full(raw: &str) -> Self {
         Self {
             kind: FfmpegErrorKind::DiskFull,
             user_message: "ディスク容量が不足しています".to_string(),
@@ -325,20 +331,20 @@ impl FfmpegError {
         }
     }
 
+    /// 最後の有意なエラー行を抽出
+    pub fn error_line(stderr: &str) -> Option<&str> {
+        stderr.lines().rfind(|line| {
+            let lower = line.to_lowercase();
+            lower.contains("error")
+                || lower.contains("failed")
+                || lower.contains("cannot")
+                || lower.contains("unable")
+        })
+    }
+
     /// 不明なエラーを作成
     fn unknown(raw: &str) -> Self {
-        // 最後の有意なエラー行を抽出
-        let error_line = raw
-            .lines()
-            .filter(|line| {
-                let lower = line.to_lowercase();
-                lower.contains("error")
-                    || lower.contains("failed")
-                    || lower.contains("cannot")
-                    || lower.contains("unable")
-            })
-            .last()
-            .unwrap_or("変換中にエラーが発生しました");
+        let error_line = Self::error_line(raw).unwrap_or("変換中にエラーが発生しました");
 
         Self {
             kind: FfmpegErrorKind::Unknown(error_line.to_string()),
@@ -541,6 +547,25 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_nvenc_runtime_failures() {
+        // 同時セッション数の上限を超えた場合
+        let stderr =
+            "[av1_nvenc @ 0x55d0] OpenEncodeSessionEx failed: out of memory (10): (no details)\n\
+                      [av1_nvenc @ 0x55d0] No capable devices found";
+        let error = FfmpegError::parse(stderr);
+        assert_eq!(
+            error.kind,
+            FfmpegErrorKind::HwAccelNotAvailable("NVIDIA NVENC".to_string())
+        );
+
+        // ディスク容量不足はNVENCの出力に含まれていても再試行の対象にしない
+        let stderr = "[hevc_nvenc @ 0x55d0] Error submitting video frame\n\
+                      av_interleaved_write_frame(): No space left on device";
+        let error = FfmpegError::parse(stderr);
+        assert_eq!(error.kind, FfmpegErrorKind::DiskFull);
+    }
+
     #[test]
     fn test_parse_input_not_found() {
         let stderr = "No such file or directory";
@@ -554,4 +579,17 @@ mod tests {
         let error = FfmpegError::parse(stderr);
         assert!(matches!(error.kind, FfmpegErrorKind::PermissionDenied));
     }
+
+    #[test]
+    fn test_error_line_is_last_matching_line() {
+        let stderr = "Input #0, matroska\n\
+                      [vist#0:0] Error while decoding stream\n\
+                      frame=  10\n\
+                      Conversion failed!\n";
+        assert_eq!(
+            FfmpegError::error_line(stderr).map(str::trim),
+            Some("Conversion failed!")
+        );
+        assert_eq!(FfmpegError::error_line("frame=  10\n"), None);
+    }
 }
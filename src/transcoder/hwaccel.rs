@@ -4,13 +4,45 @@ use anyhow::Result;
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::process::Command;
-use std::sync::OnceLock;
-
-use super::VideoCodec;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::{AudioCodec, TranscodeSettings, VideoCodec};
+use crate::ffmpeg::{background_command, FfmpegDetector};
+
+/// GeForceなどコンシューマー向けGPUで同時に開けるNVENCセッション数（ドライバーのメジャーバージョン, 上限）
+/// ドライバー530で3から5に、550で8に引き上げられた
+const GEFORCE_NVENC_SESSIONS: &[(u32, usize)] = &[(550, 8), (530, 5)];
+
+/// ドライバー530より前（バージョンが分からない場合を含む）のNVENCセッション数
+const LEGACY_GEFORCE_NVENC_SESSIONS: usize = 3;
+
+/// FFmpegビルドに含まれていないエンコーダー
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingEncoder {
+    /// コーデックの表示名
+    pub codec: &'static str,
+    /// 必要なエンコーダー名
+    pub encoder: String,
+    /// 代わりに使えるコーデックの表示名
+    pub alternatives: Vec<&'static str>,
+}
 
-/// 利用可能なエンコーダーのキャッシュ
-static AVAILABLE_ENCODERS: OnceLock<HashSet<String>> = OnceLock::new();
+impl MissingEncoder {
+    /// ダイアログ表示用の説明文
+    pub fn describe(&self) -> String {
+        if self.alternatives.is_empty() {
+            format!("{}（{}）", self.codec, self.encoder)
+        } else {
+            format!(
+                "{}（{}） → 代替: {}",
+                self.codec,
+                self.encoder,
+                self.alternatives.join(" / ")
+            )
+        }
+    }
+}
 
 /// HWアクセラレーションタイプ
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -38,22 +70,69 @@ impl HwAccelType {
             HwAccelType::Software => "ソフトウェア",
         }
     }
+}
+
+impl Default for HwAccelType {
+    fn default() -> Self {
+        HwAccelType::Auto
+    }
+}
+
+/// HWデコード（-hwaccel）の使い方
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HwDecodeMode {
+    /// GPUのデコーダーが対応しているソースのみ使用
+    Auto,
+    /// 常に使用
+    ForceOn,
+    /// 使用しない
+    Off,
+}
+
+impl HwDecodeMode {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            HwDecodeMode::Auto => "自動",
+            HwDecodeMode::ForceOn => "常に使用",
+            HwDecodeMode::Off => "オフ",
+        }
+    }
 
     /// すべてのバリアントを取得
-    pub fn all() -> &'static [HwAccelType] {
-        &[
-            HwAccelType::Auto,
-            HwAccelType::Nvenc,
-            HwAccelType::Qsv,
-            HwAccelType::Amf,
-            HwAccelType::Software,
-        ]
+    pub fn all() -> &'static [HwDecodeMode] {
+        &[HwDecodeMode::Auto, HwDecodeMode::ForceOn, HwDecodeMode::Off]
     }
 }
 
-impl Default for HwAccelType {
+impl Default for HwDecodeMode {
     fn default() -> Self {
-        HwAccelType::Auto
+        HwDecodeMode::Auto
+    }
+}
+
+/// HWデコーダーが確実に対応しているコーデックと最大ビット深度
+/// 世代によって対応が異なるもの（10bit VP9、AV1など）は含めない
+fn hw_decode_support(hwaccel: &HwAccelType) -> &'static [(&'static str, u32)] {
+    match hwaccel {
+        // NVDEC
+        HwAccelType::Nvenc => &[
+            ("h264", 8),
+            ("hevc", 10),
+            ("vp9", 8),
+            ("mpeg2video", 8),
+            ("vc1", 8),
+        ],
+        HwAccelType::Qsv => &[
+            ("h264", 8),
+            ("hevc", 10),
+            ("vp9", 8),
+            ("mpeg2video", 8),
+            ("vc1", 8),
+        ],
+        // D3D11VA
+        HwAccelType::Amf => &[("h264", 8), ("hevc", 10), ("vp9", 8), ("mpeg2video", 8)],
+        HwAccelType::Auto | HwAccelType::Software => &[],
     }
 }
 
@@ -117,7 +196,7 @@ impl HwAccelDetector {
         // nvidia-smiで確認
         #[cfg(target_os = "windows")]
         {
-            let nvidia_smi = Command::new("nvidia-smi").output();
+            let nvidia_smi = background_command("nvidia-smi").output();
             if let Ok(output) = nvidia_smi {
                 if output.status.success() {
                     debug!("nvidia-smi succeeded");
@@ -137,6 +216,76 @@ impl HwAccelDetector {
         false
     }
 
+    /// 同時に開けるエンコードセッション数の目安（Noneなら制限なし）
+    /// コンシューマー向けのNVIDIA GPUはドライバーでNVENCのセッション数が制限されている
+    pub fn max_concurrent_sessions(hwaccel: &HwAccelType) -> Option<usize> {
+        match hwaccel {
+            HwAccelType::Nvenc => {
+                let (name, driver) = Self::nvidia_gpu_info().unzip();
+                Self::nvenc_session_limit(name.as_deref(), driver.flatten())
+            }
+            // QSV・AMFには公開された上限がなく、ソフトウェアは制限なし
+            _ => None,
+        }
+    }
+
+    /// nvidia-smiで最初のGPUの名前とドライバーのメジャーバージョンを取得
+    fn nvidia_gpu_info() -> Option<(String, Option<u32>)> {
+        let output = background_command("nvidia-smi")
+            .args(["--query-gpu=name,driver_version", "--format=csv,noheader"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let line = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()?
+            .to_string();
+        debug!("NVIDIA GPU: {}", line);
+        Self::parse_nvidia_gpu_info(&line)
+    }
+
+    /// nvidia-smiの出力（"NVIDIA GeForce RTX 4070, 560.94"）を名前とドライバーのメジャーバージョンに分ける
+    fn parse_nvidia_gpu_info(line: &str) -> Option<(String, Option<u32>)> {
+        let (name, driver) = match line.rsplit_once(',') {
+            Some((name, driver)) => (name.trim(), Some(driver.trim())),
+            None => (line.trim(), None),
+        };
+        let driver = driver
+            .and_then(|driver| driver.split('.').next())
+            .and_then(|major| major.parse().ok());
+        (!name.is_empty()).then(|| (name.to_string(), driver))
+    }
+
+    /// GPU名とドライバーのバージョンからNVENCのセッション数の上限を判定
+    /// Quadro・RTX A・データセンター向けは制限なし、それ以外（不明を含む）はGeForceとみなす
+    fn nvenc_session_limit(gpu_name: Option<&str>, driver_major: Option<u32>) -> Option<usize> {
+        let professional = gpu_name.is_some_and(|name| {
+            let name = name.to_lowercase();
+            // "NVIDIA A100" "NVIDIA L40" "NVIDIA H100" などのデータセンター向け
+            let datacenter = name.strip_prefix("nvidia ").is_some_and(|model| {
+                let mut chars = model.chars();
+                matches!(chars.next(), Some('a' | 'l' | 'h'))
+                    && chars.next().is_some_and(|c| c.is_ascii_digit())
+            });
+            datacenter
+                || ["quadro", "tesla", "rtx a", "ada generation"]
+                    .iter()
+                    .any(|keyword| name.contains(keyword))
+        });
+        if professional {
+            return None;
+        }
+        let limit = driver_major
+            .and_then(|major| {
+                GEFORCE_NVENC_SESSIONS
+                    .iter()
+                    .find(|(min_major, _)| major >= *min_major)
+                    .map(|(_, limit)| *limit)
+            })
+            .unwrap_or(LEGACY_GEFORCE_NVENC_SESSIONS);
+        Some(limit)
+    }
+
     /// Intel QSVを検出
     fn detect_qsv(ffmpeg_path: Option<&std::path::PathBuf>) -> bool {
         Self::check_encoder_available("h264_qsv", ffmpeg_path)
@@ -149,18 +298,22 @@ impl HwAccelDetector {
 
     /// FFmpegでエンコーダーが利用可能かチェック
     fn check_encoder_available(encoder: &str, ffmpeg_path: Option<&std::path::PathBuf>) -> bool {
-        let ffmpeg = ffmpeg_path
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "ffmpeg".to_string());
-
-        let output = Command::new(&ffmpeg).args(["-encoders"]).output();
-
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            return stdout.contains(encoder);
-        }
+        Self::get_available_encoders(ffmpeg_path).contains(encoder)
+    }
 
-        false
+    /// ソースをHWデコーダーでデコードできるか（不明なコーデックは非対応扱い）
+    pub fn is_hw_decode_supported(
+        hwaccel: &HwAccelType,
+        source_codec: Option<&str>,
+        source_bit_depth: Option<u32>,
+    ) -> bool {
+        let Some(codec) = source_codec else {
+            return false;
+        };
+        let bit_depth = source_bit_depth.unwrap_or(8);
+        hw_decode_support(hwaccel)
+            .iter()
+            .any(|(supported, max_depth)| *supported == codec && bit_depth <= *max_depth)
     }
 
     /// 自動選択されたHWアクセラレーションを解決
@@ -179,38 +332,14 @@ impl HwAccelDetector {
     }
 
     /// 利用可能なすべてのエンコーダーをキャッシュから取得
-    /// 初回呼び出し時にFFmpegから取得してキャッシュ
+    /// FFmpegバイナリごとに初回呼び出し時に取得してキャッシュ
     pub fn get_available_encoders(
         ffmpeg_path: Option<&std::path::PathBuf>,
-    ) -> &'static HashSet<String> {
-        AVAILABLE_ENCODERS.get_or_init(|| Self::fetch_available_encoders(ffmpeg_path))
-    }
-
-    /// FFmpegから利用可能なエンコーダー一覧を取得
-    fn fetch_available_encoders(ffmpeg_path: Option<&std::path::PathBuf>) -> HashSet<String> {
+    ) -> Arc<HashSet<String>> {
         let ffmpeg = ffmpeg_path
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "ffmpeg".to_string());
-
-        let output = Command::new(&ffmpeg).args(["-encoders"]).output();
-
-        let mut encoders = HashSet::new();
-        if let Ok(output) = output {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.lines() {
-                // エンコーダー行のパース: " V..... h264_nvenc           NVIDIA NVENC H.264 encoder"
-                let trimmed = line.trim();
-                if trimmed.len() > 7 && (trimmed.starts_with("V") || trimmed.starts_with(" V")) {
-                    let parts: Vec<&str> = trimmed.split_whitespace().collect();
-                    if parts.len() >= 2 {
-                        encoders.insert(parts[1].to_string());
-                    }
-                }
-            }
-        }
-
-        info!("Available encoders detected: {:?}", encoders);
-        encoders
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("ffmpeg"));
+        FfmpegDetector::available_encoders(&ffmpeg)
     }
 
     /// 特定のエンコーダーが実際に動作するかをテスト
@@ -238,13 +367,18 @@ impl HwAccelDetector {
             return true;
         }
 
-        // HWエンコーダーは実際にテストが必要
+        // HWエンコーダーは実際にテストが必要（結果はバッチ中の各ジョブで再利用）
         let ffmpeg = ffmpeg_path
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "ffmpeg".to_string());
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("ffmpeg"));
+        FfmpegDetector::cached_encoder_test(&ffmpeg, encoder, || {
+            Self::run_encoder_test(encoder, &ffmpeg)
+        })
+    }
 
-        // ダミーの入力でエンコーダーの初期化をテスト
-        let result = Command::new(&ffmpeg)
+    /// ダミーの入力でエンコーダーの初期化をテスト
+    fn run_encoder_test(encoder: &str, ffmpeg: &PathBuf) -> bool {
+        let result = background_command(ffmpeg)
             .args([
                 "-f",
                 "lavfi",
@@ -302,7 +436,68 @@ impl HwAccelDetector {
             // libsvtav1: 高速、品質良好（推奨）
             // libaom-av1: 遅いが高品質、互換性高い
             VideoCodec::Av1 => vec!["libsvtav1", "libaom-av1"],
+            // ストリームコピーはエンコーダーを使わない
+            VideoCodec::Copy => vec![],
+        }
+    }
+
+    /// 設定に必要なエンコーダーのうちFFmpegビルドに含まれていないものを列挙
+    /// HWエンコーダーがなくてもソフトウェアエンコーダーがあれば不足とはしない
+    pub fn missing_encoders(
+        settings: &TranscodeSettings,
+        ffmpeg_path: &PathBuf,
+    ) -> Vec<MissingEncoder> {
+        if FfmpegDetector::available_encoders(ffmpeg_path).is_empty() {
+            // 一覧を取得できなかった場合は判定しない
+            return Vec::new();
         }
+        Self::missing_encoders_with(settings, |encoder| {
+            FfmpegDetector::check_encoder_present(encoder, ffmpeg_path)
+        })
+    }
+
+    /// 設定に必要なエンコーダーのうち `is_present` が含まれていないと判定したものを列挙
+    fn missing_encoders_with(
+        settings: &TranscodeSettings,
+        is_present: impl Fn(&str) -> bool,
+    ) -> Vec<MissingEncoder> {
+        let video_present = |codec: &VideoCodec| {
+            is_present(codec.encoder_name(&settings.hwaccel))
+                || Self::get_fallback_encoders(codec)
+                    .iter()
+                    .any(|e| is_present(e))
+        };
+        let audio_present = |codec: &AudioCodec| codec.encoder_name().is_some_and(&is_present);
+
+        let mut missing = Vec::new();
+        if settings.video_codec != VideoCodec::Copy && !video_present(&settings.video_codec) {
+            missing.push(MissingEncoder {
+                codec: settings.video_codec.display_name(),
+                encoder: settings
+                    .video_codec
+                    .encoder_name(&settings.hwaccel)
+                    .to_string(),
+                alternatives: VideoCodec::all()
+                    .iter()
+                    .filter(|c| **c != VideoCodec::Copy && video_present(c))
+                    .map(|c| c.display_name())
+                    .collect(),
+            });
+        }
+        if let Some(encoder) = settings.audio_codec.encoder_name() {
+            if !is_present(encoder) {
+                missing.push(MissingEncoder {
+                    codec: settings.audio_codec.display_name(),
+                    encoder: encoder.to_string(),
+                    alternatives: AudioCodec::all()
+                        .iter()
+                        .filter(|c| audio_present(c))
+                        .map(|c| c.display_name())
+                        .collect(),
+                });
+            }
+        }
+        missing
     }
 
     /// 指定されたビデオコーデックとHWアクセラレーションの組み合わせが利用可能かチェック
@@ -341,6 +536,20 @@ impl HwAccelDetector {
         );
         (last_resort.to_string(), HwAccelType::Software)
     }
+
+    /// エンコード中に初期化に失敗したエンコーダーの代わりを探す
+    /// 失敗したエンコーダー以外で利用可能なソフトウェアエンコーダーを返す
+    pub fn get_fallback_encoder(
+        video_codec: &VideoCodec,
+        failed_encoder: &str,
+        ffmpeg_path: Option<&std::path::PathBuf>,
+    ) -> Option<String> {
+        Self::get_fallback_encoders(video_codec)
+            .into_iter()
+            .filter(|encoder| *encoder != failed_encoder)
+            .find(|encoder| Self::test_encoder_availability(encoder, ffmpeg_path))
+            .map(|encoder| encoder.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -355,4 +564,142 @@ mod tests {
             assert!(!info.available.is_empty());
         }
     }
+
+    #[test]
+    fn test_hw_decode_support_table() {
+        let nvenc = HwAccelType::Nvenc;
+        assert!(HwAccelDetector::is_hw_decode_supported(
+            &nvenc,
+            Some("h264"),
+            Some(8)
+        ));
+        assert!(HwAccelDetector::is_hw_decode_supported(
+            &nvenc,
+            Some("hevc"),
+            Some(10)
+        ));
+        // 古いNVDECは10bit VP9に対応していない
+        assert!(!HwAccelDetector::is_hw_decode_supported(
+            &nvenc,
+            Some("vp9"),
+            Some(10)
+        ));
+        assert!(!HwAccelDetector::is_hw_decode_supported(
+            &nvenc,
+            Some("h264"),
+            Some(10)
+        ));
+        assert!(!HwAccelDetector::is_hw_decode_supported(&nvenc, None, None));
+        assert!(!HwAccelDetector::is_hw_decode_supported(
+            &HwAccelType::Software,
+            Some("h264"),
+            None
+        ));
+    }
+
+    #[test]
+    fn test_missing_encoder_description() {
+        let mut missing = MissingEncoder {
+            codec: VideoCodec::Av1.display_name(),
+            encoder: "libsvtav1".to_string(),
+            alternatives: vec![
+                VideoCodec::H264.display_name(),
+                VideoCodec::H265.display_name(),
+            ],
+        };
+        assert_eq!(
+            missing.describe(),
+            format!(
+                "{}（libsvtav1） → 代替: {} / {}",
+                VideoCodec::Av1.display_name(),
+                VideoCodec::H264.display_name(),
+                VideoCodec::H265.display_name()
+            )
+        );
+
+        missing.alternatives.clear();
+        assert!(!missing.describe().contains("代替"));
+    }
+
+    #[test]
+    fn test_missing_encoders() {
+        let present = |encoders: &'static [&'static str]| move |e: &str| encoders.contains(&e);
+        let settings = TranscodeSettings {
+            video_codec: VideoCodec::Av1,
+            hwaccel: HwAccelType::Nvenc,
+            audio_codec: AudioCodec::Mp3,
+            ..Default::default()
+        };
+
+        // HWエンコーダーがなくてもソフトウェアエンコーダーがあれば不足としない
+        let full = present(&["libaom-av1", "libmp3lame", "libx264"]);
+        assert!(HwAccelDetector::missing_encoders_with(&settings, full).is_empty());
+
+        let missing = HwAccelDetector::missing_encoders_with(
+            &settings,
+            present(&["libx264", "libx265", "aac"]),
+        );
+        let descriptions: Vec<String> = missing.iter().map(|m| m.describe()).collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                format!(
+                    "{}（av1_nvenc） → 代替: {} / {}",
+                    VideoCodec::Av1.display_name(),
+                    VideoCodec::H264.display_name(),
+                    VideoCodec::H265.display_name()
+                ),
+                format!(
+                    "{}（libmp3lame） → 代替: {}",
+                    AudioCodec::Mp3.display_name(),
+                    AudioCodec::Aac.display_name()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nvenc_session_limit() {
+        let limit = |name| HwAccelDetector::nvenc_session_limit(name, Some(560));
+        assert_eq!(limit(Some("NVIDIA GeForce RTX 4070")), Some(8));
+        assert_eq!(limit(Some("NVIDIA GeForce GTX 1660 SUPER")), Some(8));
+        // GPUを特定できない場合はGeForceとみなす
+        assert_eq!(limit(None), Some(8));
+
+        assert_eq!(limit(Some("Quadro RTX 4000")), None);
+        assert_eq!(limit(Some("NVIDIA RTX A4000")), None);
+        assert_eq!(limit(Some("NVIDIA RTX 6000 Ada Generation")), None);
+        assert_eq!(limit(Some("NVIDIA A100-SXM4-40GB")), None);
+        assert_eq!(limit(Some("NVIDIA L4")), None);
+
+        // 上限はドライバーのバージョンで変わる（不明なら古いドライバーの上限）
+        let geforce = |driver| HwAccelDetector::nvenc_session_limit(Some("GeForce"), driver);
+        assert_eq!(geforce(Some(550)), Some(8));
+        assert_eq!(geforce(Some(536)), Some(5));
+        assert_eq!(geforce(Some(525)), Some(3));
+        assert_eq!(geforce(None), Some(3));
+
+        assert_eq!(
+            HwAccelDetector::max_concurrent_sessions(&HwAccelType::Software),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_nvidia_gpu_info() {
+        let parse = HwAccelDetector::parse_nvidia_gpu_info;
+        assert_eq!(
+            parse("NVIDIA GeForce RTX 4070, 560.94"),
+            Some(("NVIDIA GeForce RTX 4070".to_string(), Some(560)))
+        );
+        assert_eq!(
+            parse("Quadro RTX 4000, 535.183.01"),
+            Some(("Quadro RTX 4000".to_string(), Some(535)))
+        );
+        assert_eq!(
+            parse("NVIDIA GeForce GTX 1080"),
+            Some(("NVIDIA GeForce GTX 1080".to_string(), None))
+        );
+        assert_eq!(parse(""), None);
+    }
 }
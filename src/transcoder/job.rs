@@ -2,15 +2,20 @@
 
 use anyhow::{Context, Result};
 use log::{info, warn};
-use std::path::PathBuf;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use super::{
-    AqMode, HwAccelDetector, HwAccelType, RateControlMode, TranscodeProgress, TranscodeSettings,
-    VideoCodec,
+    AqMode, ContentType, CropSettings, FfmpegErrorKind, HwAccelDetector, HwAccelType, HwDecodeMode,
+    OverwritePolicy, PixelFormat, RateControlMode, SvtAv1PredStructure, SvtAv1Tune,
+    TranscodeSettings, VideoCodec, VideoMetadata,
 };
 
+/// スマートコピーで同じとみなす音声ビットレートの差（目標に対する割合）
+const SMART_COPY_BITRATE_TOLERANCE: f64 = 0.1;
+
 /// トランスコードジョブ
 #[derive(Clone)]
 pub struct TranscodeJob {
@@ -20,12 +25,101 @@ pub struct TranscodeJob {
     pub output_path: PathBuf,
     /// トランスコード設定
     pub settings: TranscodeSettings,
+    /// 入力ファイルのメタデータ（ビット深度・色情報の引き継ぎ用）
+    pub metadata: VideoMetadata,
+    /// ファイルごとのクロップ（自動検出結果。Noneなら設定値を使用）
+    pub crop: Option<CropSettings>,
+    /// 解決済みのエンコーダー（Noneなら引数生成時に解決）
+    pub encode_plan: Option<ResolvedEncodePlan>,
+    /// 変換する区間（開始秒, 長さ秒）。Noneならファイル全体
+    pub segment: Option<(f64, f64)>,
     /// キャンセルフラグ
     pub cancelled: Arc<AtomicBool>,
     /// ジョブ状態
     pub state: JobState,
 }
 
+/// 実際に使用するエンコーダーとHWアクセラレーション
+/// HWエンコーダーのテストはプロセス起動を伴うため、バッチ開始時に一度だけ解決する
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedEncodePlan {
+    /// エンコーダー名
+    pub encoder: String,
+    /// HWアクセラレーション（フォールバック時はSoftware）
+    pub hwaccel: HwAccelType,
+}
+
+impl ResolvedEncodePlan {
+    /// 設定からエンコーダーを解決
+    /// ストリームコピーはデコードもエンコードもしないため検出不要
+    pub fn resolve(settings: &TranscodeSettings, ffmpeg_path: Option<&PathBuf>) -> Self {
+        if settings.video_codec == VideoCodec::Copy {
+            return Self::stream_copy();
+        }
+
+        let (encoder, hwaccel) = HwAccelDetector::get_available_encoder(
+            &settings.video_codec,
+            &settings.hwaccel,
+            ffmpeg_path,
+        );
+        Self { encoder, hwaccel }
+    }
+
+    /// 映像をストリームコピーする
+    fn stream_copy() -> Self {
+        Self {
+            encoder: "copy".to_string(),
+            hwaccel: HwAccelType::Software,
+        }
+    }
+
+    /// 映像を再エンコードせずにコピーするか
+    pub fn is_stream_copy(&self) -> bool {
+        self.encoder == "copy"
+    }
+
+    /// HWエンコーダーの代わりに使うソフトウェアエンコーダーを解決
+    /// HWエンコーダーのセッション数が上限に達した並列ジョブで使う
+    pub fn software_fallback(
+        &self,
+        settings: &TranscodeSettings,
+        ffmpeg_path: Option<&PathBuf>,
+    ) -> Option<Self> {
+        if self.hwaccel == HwAccelType::Software || settings.video_codec == VideoCodec::Copy {
+            return None;
+        }
+
+        HwAccelDetector::get_fallback_encoder(&settings.video_codec, &self.encoder, ffmpeg_path)
+            .map(|encoder| Self {
+                encoder,
+                hwaccel: HwAccelType::Software,
+            })
+    }
+
+    /// エンコーダーの初期化に失敗したときの代替を解決
+    /// エンコーダー起因のエラーでない場合や代替がない場合はNone
+    pub fn fallback_for(
+        &self,
+        settings: &TranscodeSettings,
+        error: &FfmpegErrorKind,
+        ffmpeg_path: Option<&PathBuf>,
+    ) -> Option<Self> {
+        let encoder_failed = matches!(
+            error,
+            FfmpegErrorKind::EncoderNotSupported(_) | FfmpegErrorKind::HwAccelNotAvailable(_)
+        );
+        if !encoder_failed || settings.video_codec == VideoCodec::Copy {
+            return None;
+        }
+
+        HwAccelDetector::get_fallback_encoder(&settings.video_codec, &self.encoder, ffmpeg_path)
+            .map(|encoder| Self {
+                encoder,
+                hwaccel: HwAccelType::Software,
+            })
+    }
+}
+
 /// ジョブ状態
 #[derive(Clone, Debug, PartialEq)]
 pub enum JobState {
@@ -41,33 +135,172 @@ pub enum JobState {
     Cancelled,
 }
 
+/// 出力パスの衝突解決結果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputResolution {
+    /// 衝突なし（そのまま使用）
+    Use(PathBuf),
+    /// 既存ファイルを上書き
+    Overwrite(PathBuf),
+    /// 連番を付けた別名で保存
+    Renamed(PathBuf),
+    /// 既存ファイルがあるためスキップ
+    Skip,
+}
+
+impl OutputResolution {
+    /// 実際に書き込む出力パスを取得（スキップ時はNone）
+    pub fn path(&self) -> Option<&PathBuf> {
+        match self {
+            OutputResolution::Use(path)
+            | OutputResolution::Overwrite(path)
+            | OutputResolution::Renamed(path) => Some(path),
+            OutputResolution::Skip => None,
+        }
+    }
+
+    /// ファイル一覧に表示する説明文を取得
+    pub fn note(&self) -> Option<String> {
+        match self {
+            OutputResolution::Use(_) => None,
+            OutputResolution::Overwrite(_) => Some("既存ファイルを上書き".to_string()),
+            OutputResolution::Renamed(path) => Some(format!(
+                "別名で保存: {}",
+                path.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            )),
+            OutputResolution::Skip => Some("出力ファイルが既に存在します".to_string()),
+        }
+    }
+}
+
 impl TranscodeJob {
     /// 新しいジョブを作成
+    /// 出力パスが入力パスと同一の場合はソースを破壊しないよう自動で別名にする
     pub fn new(input_path: PathBuf, output_path: PathBuf, settings: TranscodeSettings) -> Self {
+        let output_path = if is_same_path(&input_path, &output_path) {
+            warn!(
+                "Output path equals input path, renaming: {}",
+                output_path.display()
+            );
+            avoid_collision(&output_path)
+        } else {
+            output_path
+        };
+
         Self {
             input_path,
             output_path,
             settings,
+            metadata: VideoMetadata::default(),
+            crop: None,
+            encode_plan: None,
+            segment: None,
             cancelled: Arc::new(AtomicBool::new(false)),
             state: JobState::Pending,
         }
     }
 
+    /// 入力ファイルのメタデータを設定
+    pub fn with_metadata(mut self, metadata: VideoMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// ファイルごとのクロップを設定
+    pub fn with_crop(mut self, crop: Option<CropSettings>) -> Self {
+        self.crop = crop;
+        self
+    }
+
+    /// 解決済みのエンコーダーを設定
+    pub fn with_encode_plan(mut self, plan: ResolvedEncodePlan) -> Self {
+        self.encode_plan = Some(plan);
+        self
+    }
+
+    /// 指定した区間だけを変換する（設定を試すサンプル用）
+    pub fn with_segment(mut self, start_secs: f64, duration_secs: f64) -> Self {
+        self.segment = Some((start_secs.max(0.0), duration_secs));
+        self
+    }
+
     /// 出力パスを生成
     pub fn generate_output_path(
-        input_path: &PathBuf,
-        output_dir: &PathBuf,
+        input_path: &Path,
+        output_dir: &Path,
         suffix: &str,
         settings: &TranscodeSettings,
     ) -> PathBuf {
-        let stem = input_path
+        // UTF-8でないファイル名もそのまま引き継ぐ
+        let mut file_name = input_path
             .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("output");
+            .unwrap_or(OsStr::new("output"))
+            .to_os_string();
+        file_name.push(suffix);
+        file_name.push(".");
+        file_name.push(settings.container.extension());
 
-        let extension = settings.container.extension();
+        let output_path = output_dir.join(file_name);
+
+        // サフィックスが空で同じコンテナの場合、入力ファイルと同じパスになる
+        if is_same_path(input_path, &output_path) {
+            return avoid_collision(&output_path);
+        }
+
+        output_path
+    }
+
+    /// 上書きポリシーに従って出力パスを解決
+    pub fn resolve_output_path(
+        input_path: &Path,
+        output_path: PathBuf,
+        policy: OverwritePolicy,
+    ) -> OutputResolution {
+        Self::resolve_output_path_with(input_path, output_path, policy, |path| path.exists())
+    }
+
+    /// 使用中とみなすパスを `taken` で判定して出力パスを解決
+    /// （既存ファイルに加えて、同じバッチの他のジョブが決めた出力先も避けられる）
+    pub fn resolve_output_path_with(
+        input_path: &Path,
+        output_path: PathBuf,
+        policy: OverwritePolicy,
+        taken: impl Fn(&Path) -> bool,
+    ) -> OutputResolution {
+        // 入力ファイルの上書きはポリシーに関係なく許可しない
+        if is_same_path(input_path, &output_path) {
+            return OutputResolution::Renamed(avoid_collision_with(&output_path, taken));
+        }
+
+        if !taken(&output_path) {
+            return OutputResolution::Use(output_path);
+        }
+
+        match policy {
+            OverwritePolicy::Overwrite => OutputResolution::Overwrite(output_path),
+            OverwritePolicy::Skip => OutputResolution::Skip,
+            OverwritePolicy::Rename => {
+                OutputResolution::Renamed(avoid_collision_with(&output_path, taken))
+            }
+        }
+    }
+
+    /// 途中まで書き込まれた出力ファイルを削除
+    /// 入力ファイルと同じパスの場合は削除しない
+    pub fn remove_partial_output(&self) {
+        if is_same_path(&self.input_path, &self.output_path) || !self.output_path.exists() {
+            return;
+        }
 
-        output_dir.join(format!("{}{}.{}", stem, suffix, extension))
+        match std::fs::remove_file(&self.output_path) {
+            Ok(()) => info!("Removed partial output: {:?}", self.output_path),
+            Err(e) => warn!(
+                "Failed to remove partial output {:?}: {}",
+                self.output_path, e
+            ),
+        }
     }
 
     /// ジョブをキャンセル
@@ -82,30 +315,56 @@ impl TranscodeJob {
 
     /// FFmpegコマンド引数を生成
     /// ffmpeg_pathを渡すとエンコーダーの利用可能性をチェックしてフォールバック
-    pub fn build_ffmpeg_args(&self) -> Vec<String> {
+    pub fn build_ffmpeg_args(&self) -> Vec<OsString> {
         self.build_ffmpeg_args_with_path(None)
     }
 
+    /// 実行するコマンドラインをシェルに貼り付けられる形式で生成
+    pub fn command_line(&self, ffmpeg_path: &Path) -> String {
+        let args = self.build_ffmpeg_args_with_path(Some(&ffmpeg_path.to_path_buf()));
+        std::iter::once(ffmpeg_path.as_os_str().to_os_string())
+            .chain(args)
+            .map(|arg| quote_arg(&arg.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// 実際に使うエンコーダー（スマートコピーする場合はストリームコピー）
+    /// 解決済みのエンコーダーがあればそれを使い、なければここで解決する
+    pub fn actual_encode_plan(&self, ffmpeg_path: Option<&PathBuf>) -> ResolvedEncodePlan {
+        match &self.encode_plan {
+            // 元の映像をそのまま使える場合はストリームコピー（デコードもしない）
+            _ if self.smart_video_copy() => ResolvedEncodePlan::stream_copy(),
+            Some(plan) => plan.clone(),
+            None => ResolvedEncodePlan::resolve(&self.settings, ffmpeg_path),
+        }
+    }
+
     /// FFmpegコマンド引数を生成（FFmpegパス指定版）
+    /// オプションは文字列で組み立て、ファイルパスはOsStringのまま渡す（UTF-8以外の名前も壊さない）
     pub fn build_ffmpeg_args_with_path(
         &self,
         ffmpeg_path: Option<&std::path::PathBuf>,
-    ) -> Vec<String> {
+    ) -> Vec<OsString> {
+        let mut input_options = Vec::new();
         let mut args = Vec::new();
 
         // 実際に使用するエンコーダーとHWアクセラレーションを決定
-        let (actual_encoder, actual_hwaccel) = HwAccelDetector::get_available_encoder(
-            &self.settings.video_codec,
-            &self.settings.hwaccel,
-            ffmpeg_path,
-        );
+        let ResolvedEncodePlan {
+            encoder: actual_encoder,
+            hwaccel: actual_hwaccel,
+        } = self.actual_encode_plan(ffmpeg_path);
 
         // HWアクセラレーション設定（入力オプションなので -i の前に配置）
-        self.add_hwaccel_args(&mut args, &actual_hwaccel);
-
-        // 入力ファイル
-        args.push("-i".to_string());
-        args.push(self.input_path.to_string_lossy().to_string());
+        self.add_hwaccel_args(&mut input_options, &actual_encoder, &actual_hwaccel);
+
+        // 区間の指定（-ss は入力側に置いてキーフレームまで高速にシークする）
+        if let Some((start_secs, duration_secs)) = self.segment {
+            input_options.push("-ss".to_string());
+            input_options.push(format!("{:.3}", start_secs));
+            args.push("-t".to_string());
+            args.push(format!("{:.3}", duration_secs));
+        }
 
         // ビデオコーデック設定
         self.add_video_args_with_encoder(&mut args, &actual_encoder, &actual_hwaccel);
@@ -113,6 +372,9 @@ impl TranscodeJob {
         // オーディオコーデック設定
         self.add_audio_args(&mut args);
 
+        // メタデータ・チャプター
+        self.add_metadata_args(&mut args);
+
         // 進捗情報をstdoutに構造化フォーマットで出力
         args.push("-progress".to_string());
         args.push("pipe:1".to_string());
@@ -121,17 +383,170 @@ impl TranscodeJob {
         args.push("-stats_period".to_string());
         args.push("0.5".to_string());
 
-        // 上書き確認なし
-        args.push("-y".to_string());
+        // stderrの統計行はログ表示の邪魔になるため出力しない（進捗は-progressで取得）
+        args.push("-nostats".to_string());
+
+        // 上書きするのは「上書き」ポリシーのときだけ
+        // それ以外は確認後に別のプロセスが作ったファイルも上書きしない
+        args.push(
+            match self.settings.overwrite_policy {
+                OverwritePolicy::Overwrite => "-y",
+                OverwritePolicy::Skip | OverwritePolicy::Rename => "-n",
+            }
+            .to_string(),
+        );
+
+        // 入力ファイル → 出力オプション → 出力ファイル
+        input_options
+            .into_iter()
+            .map(OsString::from)
+            .chain([OsString::from("-i"), path_arg(&self.input_path)])
+            .chain(args.into_iter().map(OsString::from))
+            .chain([path_arg(&self.output_path)])
+            .collect()
+    }
+
+    /// 映像を再エンコードせずにコピーするか
+    /// 元のコーデックが変換先と同じで、フィルター・ピクセルフォーマットを変更しない場合のみ
+    fn smart_video_copy(&self) -> bool {
+        if !self.settings.smart_video_copy {
+            return false;
+        }
+        let Some(target) = self.settings.video_codec.probe_name() else {
+            return false;
+        };
+        if self.metadata.video_codec.as_deref() != Some(target)
+            || self.settings.pixel_format != PixelFormat::Auto
+            || !self.build_video_filters().is_empty()
+        {
+            return false;
+        }
+        info!(
+            "Smart copy: {} video is already {}, copying the stream",
+            self.input_path.display(),
+            target
+        );
+        true
+    }
+
+    /// 音声を再エンコードせずにコピーするか
+    /// 元のコーデックが変換先と同じで、ビットレートの差が10%以内、チャンネル数・サンプルレートを変更しない場合のみ
+    fn smart_audio_copy(&self) -> bool {
+        use super::AudioCodec;
+
+        let settings = &self.settings;
+        if !settings.smart_audio_copy || settings.audio_sample_rate.is_some() {
+            return false;
+        }
+        let Some(target) = settings.audio_codec.probe_name() else {
+            return false;
+        };
+        if self.metadata.audio_codec.as_deref() != Some(target) {
+            return false;
+        }
+        if let Some(channels) = settings.audio_channels.count() {
+            if self.metadata.source_audio_channels != Some(channels) {
+                return false;
+            }
+        }
+        let copy = match settings.audio_codec {
+            // ロスレスはビットレートに関係なく同じ内容になる
+            AudioCodec::Flac => true,
+            _ => self.metadata.source_audio_bitrate.is_some_and(|source| {
+                let target_bitrate = settings.audio_bitrate as f64 * 1000.0;
+                (source as f64 - target_bitrate).abs()
+                    <= target_bitrate * SMART_COPY_BITRATE_TOLERANCE
+            }),
+        };
+        if copy {
+            info!(
+                "Smart copy: {} audio is already {}, copying the stream",
+                self.input_path.display(),
+                target
+            );
+        }
+        copy
+    }
+
+    /// HWデコードを使用するか
+    /// ソフトウェアフィルターを通すとフレームはシステムメモリに戻るため、
+    /// 自動ではGPUのデコーダーが対応しているソースのみ使用する
+    fn uses_hw_decode(&self, hwaccel: &HwAccelType) -> bool {
+        match self.settings.hw_decode {
+            HwDecodeMode::Off => false,
+            HwDecodeMode::ForceOn => true,
+            HwDecodeMode::Auto => HwAccelDetector::is_hw_decode_supported(
+                hwaccel,
+                self.metadata.video_codec.as_deref(),
+                self.metadata.video_bit_depth,
+            ),
+        }
+    }
+
+    /// GPU上でリサイズするスケールフィルタ（デコードからエンコードまでGPUに置く場合）
+    /// CPUのみのフィルタ（インターレース解除・フレームレート変換・クロップ）や
+    /// ピクセルフォーマットの変換が必要な場合はソフトウェアのスケールを使う
+    fn gpu_scale_filter(&self, encoder: &str, hwaccel: &HwAccelType) -> Option<String> {
+        use super::VideoResolution;
+
+        if self.settings.resolution == VideoResolution::Original
+            || !self.uses_hw_decode(hwaccel)
+            || self.metadata.is_high_bit_depth()
+            || self.settings.pixel_format != PixelFormat::Auto
+        {
+            return None;
+        }
 
-        // 出力ファイル
-        args.push(self.output_path.to_string_lossy().to_string());
+        let needs_cpu_filter = self
+            .settings
+            .deinterlace
+            .filter(self.metadata.is_interlaced())
+            .is_some()
+            || self.settings.frame_rate.fps().is_some()
+            || self.effective_crop().filter().is_some()
+            || self.tone_maps_to_sdr();
+        if needs_cpu_filter {
+            return None;
+        }
 
-        args
+        // GPUのスケーラーは比率を保つ指定や余白を扱えないため、元の解像度から出力サイズを決める
+        // 余白が必要な場合や元の解像度が不明な場合はソフトウェアのスケールを使う
+        let source = self.metadata.resolution?;
+        let (w, h) = self.settings.resolution.fit_dimensions(source)?;
+        if self.settings.resolution.output_dimensions(source) != Some((w, h)) {
+            return None;
+        }
+        match hwaccel {
+            HwAccelType::Nvenc if encoder.ends_with("_nvenc") => {
+                Some(format!("scale_cuda={}:{}", w, h))
+            }
+            HwAccelType::Qsv if encoder.ends_with("_qsv") => {
+                Some(format!("scale_qsv=w={}:h={}", w, h))
+            }
+            _ => None,
+        }
     }
 
     /// HWアクセラレーション引数を追加
-    fn add_hwaccel_args(&self, args: &mut Vec<String>, hwaccel: &HwAccelType) {
+    fn add_hwaccel_args(&self, args: &mut Vec<String>, encoder: &str, hwaccel: &HwAccelType) {
+        if !self.uses_hw_decode(hwaccel) {
+            return;
+        }
+
+        // GPUでリサイズする場合はデコード済みフレームをGPUメモリに置いたままにする
+        if self.gpu_scale_filter(encoder, hwaccel).is_some() {
+            let device = if *hwaccel == HwAccelType::Nvenc {
+                "cuda"
+            } else {
+                "qsv"
+            };
+            args.push("-hwaccel".to_string());
+            args.push(device.to_string());
+            args.push("-hwaccel_output_format".to_string());
+            args.push(device.to_string());
+            return;
+        }
+
         match hwaccel {
             HwAccelType::Auto => {
                 // 自動検出は実行時に決定
@@ -161,22 +576,28 @@ impl TranscodeJob {
         encoder: &str,
         hwaccel: &HwAccelType,
     ) {
-        use super::VideoResolution;
-
         // コーデック
         args.push("-c:v".to_string());
         args.push(encoder.to_string());
 
-        // 解像度
-        if let VideoResolution::Custom(w, h) = self.settings.resolution {
-            args.push("-vf".to_string());
-            args.push(format!("scale={}:{}", w, h));
-        } else if self.settings.resolution != VideoResolution::Original {
-            let (w, h) = self.settings.resolution.dimensions();
+        // ストリームコピーではフィルター・品質・プリセットを指定できない
+        if encoder == "copy" {
+            return;
+        }
+
+        // ビデオフィルター（フレームレート・解像度）
+        let filters = match self.gpu_scale_filter(encoder, hwaccel) {
+            Some(filter) => vec![filter],
+            None => self.build_video_filters(),
+        };
+        if !filters.is_empty() {
             args.push("-vf".to_string());
-            args.push(format!("scale={}:{}", w, h));
+            args.push(filters.join(","));
         }
 
+        // ピクセルフォーマット・色情報
+        self.add_color_args(args, encoder);
+
         // エンコーダー固有のオプション設定
         match encoder {
             // NVIDIA NVENC H.264
@@ -196,37 +617,37 @@ impl TranscodeJob {
 
             // Intel QSV H.264
             "h264_qsv" => {
-                self.add_qsv_args(args);
+                self.add_qsv_args(args, encoder);
             }
 
             // Intel QSV HEVC
             "hevc_qsv" => {
-                self.add_qsv_args(args);
+                self.add_qsv_args(args, encoder);
             }
 
             // Intel QSV AV1
             "av1_qsv" => {
-                self.add_qsv_args(args);
+                self.add_qsv_args(args, encoder);
             }
 
             // Intel QSV VP9
             "vp9_qsv" => {
-                self.add_qsv_args(args);
+                self.add_qsv_args(args, encoder);
             }
 
             // AMD AMF H.264
             "h264_amf" => {
-                self.add_amf_args(args);
+                self.add_amf_args(args, encoder);
             }
 
             // AMD AMF HEVC
             "hevc_amf" => {
-                self.add_amf_args(args);
+                self.add_amf_args(args, encoder);
             }
 
             // AMD AMF AV1
             "av1_amf" => {
-                self.add_amf_args(args);
+                self.add_amf_args(args, encoder);
             }
 
             // libx264
@@ -270,10 +691,135 @@ impl TranscodeJob {
         }
     }
 
+    /// ビデオフィルターチェーンを構築（-vfに渡す順序で返す）
+    fn build_video_filters(&self) -> Vec<String> {
+        let mut filters = Vec::new();
+
+        // インターレース解除（他のフィルタより先に適用）
+        // CPUフィルタを使う場合は-hwaccel_output_formatを指定しないため、
+        // デコード済みフレームはシステムメモリに転送されCPUフィルタをそのまま使える
+        if let Some(filter) = self
+            .settings
+            .deinterlace
+            .filter(self.metadata.is_interlaced())
+        {
+            filters.push(filter.to_string());
+        }
+
+        // フレームレート（スケール前に間引いて処理量を減らす）
+        if let Some(fps) = self.settings.frame_rate.ffmpeg_value() {
+            filters.push(self.settings.fps_filter_mode.filter(&fps));
+        }
+
+        // クロップ（スケールより前に適用）
+        if let Some(filter) = self.effective_crop().filter() {
+            filters.push(filter);
+        }
+
+        // 解像度（アスペクト比を保つ）
+        if let Some(filter) = self.settings.resolution.scale_filter() {
+            filters.push(filter);
+        }
+
+        // HDR→SDRトーンマッピング（縮小後の方が処理が軽い）
+        if self.tone_maps_to_sdr() {
+            filters.push(self.settings.tone_mapping_mode.filter());
+        }
+
+        filters
+    }
+
+    /// クロップ方式に従って適用するクロップを取得
+    fn effective_crop(&self) -> CropSettings {
+        self.settings
+            .crop_mode
+            .resolve(self.crop, self.settings.crop)
+    }
+
+    /// HDRソースをSDRにトーンマッピングするか
+    fn tone_maps_to_sdr(&self) -> bool {
+        self.settings.hdr_to_sdr && self.metadata.is_hdr()
+    }
+
+    /// ピクセルフォーマットと色情報の引数を追加
+    /// 10bitソースは対応エンコーダーなら10bitのまま出力し、HDRメタデータを引き継ぐ
+    /// トーンマッピングした場合はBT.709としてタグ付けする
+    fn add_color_args(&self, args: &mut Vec<String>, encoder: &str) {
+        let metadata = &self.metadata;
+        let pixel_format = self.settings.pixel_format;
+        let tone_mapped = self.tone_maps_to_sdr();
+
+        if let Some(value) = pixel_format.ffmpeg_value() {
+            // 明示的な指定（出力できないエンコーダーでは4:2:0 8bitにする）
+            let pix_fmt = if !pixel_format.supported_by(encoder) {
+                warn!(
+                    "Encoder {} does not support {}, falling back to yuv420p",
+                    encoder, value
+                );
+                "yuv420p"
+            } else if pixel_format == PixelFormat::Yuv420p10le {
+                // HWエンコーダーはp010leで受け取る
+                Self::high_bit_depth_pix_fmt(encoder).unwrap_or(value)
+            } else {
+                value
+            };
+            args.push("-pix_fmt".to_string());
+            args.push(pix_fmt.to_string());
+        } else if metadata.is_high_bit_depth() && !tone_mapped {
+            let pix_fmt = match Self::high_bit_depth_pix_fmt(encoder) {
+                Some(pix_fmt) => pix_fmt,
+                None => {
+                    if metadata.is_hdr() {
+                        warn!(
+                            "Encoder {} does not support 10-bit output, HDR source will be encoded as 8-bit",
+                            encoder
+                        );
+                    }
+                    "yuv420p"
+                }
+            };
+            args.push("-pix_fmt".to_string());
+            args.push(pix_fmt.to_string());
+        }
+
+        if tone_mapped {
+            for flag in ["-color_primaries", "-color_trc", "-colorspace"] {
+                args.push(flag.to_string());
+                args.push("bt709".to_string());
+            }
+            return;
+        }
+
+        // 色情報の引き継ぎ（不明な値は出力しない）
+        let color_args = [
+            ("-color_primaries", &metadata.color_primaries),
+            ("-color_trc", &metadata.color_trc),
+            ("-colorspace", &metadata.color_space),
+        ];
+        for (flag, value) in color_args {
+            if let Some(value) = value.as_deref().filter(|v| *v != "unknown") {
+                args.push(flag.to_string());
+                args.push(value.to_string());
+            }
+        }
+    }
+
+    /// 10bit出力に対応したエンコーダーのピクセルフォーマットを取得
+    fn high_bit_depth_pix_fmt(encoder: &str) -> Option<&'static str> {
+        match encoder {
+            "hevc_nvenc" | "av1_nvenc" | "hevc_qsv" | "av1_qsv" | "hevc_amf" | "av1_amf" => {
+                Some("p010le")
+            }
+            "libx265" | "libsvtav1" | "libaom-av1" | "libvpx-vp9" => Some("yuv420p10le"),
+            _ => None,
+        }
+    }
+
     /// レートコントロール引数を追加
     fn add_rate_control_args(&self, args: &mut Vec<String>, hwaccel: &HwAccelType) {
         match self.settings.rate_control {
-            RateControlMode::Crf => {
+            // 目標サイズはジョブ作成時に算出したCRFで指定する
+            RateControlMode::Crf | RateControlMode::TargetSize | RateControlMode::IcqQsv => {
                 match hwaccel {
                     HwAccelType::Nvenc | HwAccelType::Qsv | HwAccelType::Amf => {
                         // HWエンコーダーでは-cqを使用
@@ -294,7 +840,7 @@ impl TranscodeJob {
                 args.push("-bufsize".to_string());
                 args.push(format!("{}k", self.settings.target_bitrate * 2));
             }
-            RateControlMode::Vbr => {
+            RateControlMode::Vbr | RateControlMode::AvbrQsv => {
                 args.push("-b:v".to_string());
                 args.push(format!("{}k", self.settings.target_bitrate));
                 args.push("-maxrate".to_string());
@@ -317,7 +863,7 @@ impl TranscodeJob {
 
         // レートコントロール
         match self.settings.rate_control {
-            RateControlMode::Crf => {
+            RateControlMode::Crf | RateControlMode::TargetSize | RateControlMode::IcqQsv => {
                 args.push("-rc".to_string());
                 args.push("vbr".to_string());
                 args.push("-cq".to_string());
@@ -329,7 +875,7 @@ impl TranscodeJob {
                 args.push("-b:v".to_string());
                 args.push(format!("{}k", self.settings.target_bitrate));
             }
-            RateControlMode::Vbr => {
+            RateControlMode::Vbr | RateControlMode::AvbrQsv => {
                 args.push("-rc".to_string());
                 args.push("vbr".to_string());
                 args.push("-b:v".to_string());
@@ -370,26 +916,30 @@ impl TranscodeJob {
             args.push(self.settings.nvenc_b_ref_mode.ffmpeg_value().to_string());
         }
 
-        // ルックアヘッド
-        if self.settings.lookahead > 0 {
+        // ルックアヘッド（NVENCの上限は32）
+        if self.settings.nvenc_rc_lookahead > 0 {
             args.push("-rc-lookahead".to_string());
-            args.push(self.settings.lookahead.to_string());
+            args.push(self.settings.nvenc_rc_lookahead.min(32).to_string());
         }
 
-        // AQ設定
-        if self.settings.aq_mode != AqMode::None {
+        // 空間AQ・時間AQはそれぞれ独立して指定
+        if self.settings.nvenc_spatial_aq {
             args.push("-spatial-aq".to_string());
             args.push("1".to_string());
             args.push("-aq-strength".to_string());
-            args.push(self.settings.aq_strength.to_string());
+            args.push(self.settings.nvenc_aq_strength.clamp(1, 15).to_string());
+        }
+        if self.settings.nvenc_temporal_aq {
+            args.push("-temporal-aq".to_string());
+            args.push("1".to_string());
         }
     }
 
     /// QSV固有引数を追加
-    fn add_qsv_args(&self, args: &mut Vec<String>) {
+    fn add_qsv_args(&self, args: &mut Vec<String>, encoder: &str) {
         // レートコントロール
         match self.settings.rate_control {
-            RateControlMode::Crf => {
+            RateControlMode::Crf | RateControlMode::TargetSize => {
                 args.push("-global_quality".to_string());
                 args.push(self.settings.crf.to_string());
             }
@@ -409,6 +959,21 @@ impl TranscodeJob {
                 args.push("-q".to_string());
                 args.push(self.settings.crf.to_string());
             }
+            // FFmpegのQSVエンコーダーは指定されたオプションからモードを選ぶ
+            // ICQ: ビットレートなしの-global_quality
+            RateControlMode::IcqQsv => {
+                args.push("-global_quality".to_string());
+                args.push(self.settings.crf.to_string());
+            }
+            // AVBR: 最大ビットレートなしの-b:v + avbr_accuracy/avbr_convergence
+            RateControlMode::AvbrQsv => {
+                args.push("-b:v".to_string());
+                args.push(format!("{}k", self.settings.target_bitrate));
+                args.push("-avbr_accuracy".to_string());
+                args.push("100".to_string());
+                args.push("-avbr_convergence".to_string());
+                args.push("1".to_string());
+            }
         }
 
         // プリセット
@@ -440,6 +1005,28 @@ impl TranscodeJob {
             args.push("1".to_string());
         }
 
+        // 外部ビットレート制御（VP9は非対応）
+        if self.settings.qsv_extbrc && encoder != "vp9_qsv" {
+            args.push("-extbrc".to_string());
+            args.push("1".to_string());
+        }
+
+        // 非同期処理の深さ
+        if self.settings.qsv_async_depth > 0 {
+            args.push("-async_depth".to_string());
+            args.push(self.settings.qsv_async_depth.to_string());
+        }
+
+        // SEIはH.264/HEVCのみ
+        if matches!(encoder, "h264_qsv" | "hevc_qsv") {
+            args.push("-pic_timing_sei".to_string());
+            args.push(u8::from(self.settings.qsv_pic_timing_sei).to_string());
+            if self.settings.qsv_recovery_point_sei {
+                args.push("-recovery_point_sei".to_string());
+                args.push("1".to_string());
+            }
+        }
+
         // Bフレーム
         if self.settings.bframes > 0 {
             args.push("-bf".to_string());
@@ -454,7 +1041,7 @@ impl TranscodeJob {
     }
 
     /// AMF固有引数を追加
-    fn add_amf_args(&self, args: &mut Vec<String>) {
+    fn add_amf_args(&self, args: &mut Vec<String>, encoder: &str) {
         // 使用法
         args.push("-usage".to_string());
         args.push(self.settings.amf_usage.ffmpeg_value().to_string());
@@ -465,7 +1052,7 @@ impl TranscodeJob {
 
         // レートコントロール
         match self.settings.rate_control {
-            RateControlMode::Crf => {
+            RateControlMode::Crf | RateControlMode::TargetSize | RateControlMode::IcqQsv => {
                 args.push("-rc".to_string());
                 args.push("cqp".to_string());
                 args.push("-qp_i".to_string());
@@ -481,7 +1068,7 @@ impl TranscodeJob {
                 args.push("-b:v".to_string());
                 args.push(format!("{}k", self.settings.target_bitrate));
             }
-            RateControlMode::Vbr => {
+            RateControlMode::Vbr | RateControlMode::AvbrQsv => {
                 args.push("-rc".to_string());
                 args.push("vbr_peak".to_string());
                 args.push("-b:v".to_string());
@@ -499,10 +1086,41 @@ impl TranscodeJob {
             }
         }
 
+        // プリアナリシス（シーンの複雑さを事前に解析）
+        if self.settings.amf_pre_analysis {
+            args.push("-preanalysis".to_string());
+            args.push("1".to_string());
+            args.push("-pa_initial_qp_after_reset".to_string());
+            args.push("23".to_string());
+        }
+
+        // プリエンコードによるレートコントロール
+        if self.settings.amf_rate_control_preanalysis {
+            args.push("-preencode".to_string());
+            args.push("1".to_string());
+        }
+
+        // VBAQ（AV1は非対応）
+        if self.settings.amf_vbaq && encoder != "av1_amf" {
+            args.push("-vbaq".to_string());
+            args.push("1".to_string());
+        }
+
         // Bフレーム
         if self.settings.bframes > 0 {
             args.push("-bf".to_string());
             args.push(self.settings.bframes.to_string());
+
+            // BフレームのQP差分
+            if encoder == "h264_amf" {
+                args.push("-bf_delta_qp".to_string());
+                args.push(
+                    self.settings
+                        .amf_b_frame_delta_qp
+                        .clamp(-10, 10)
+                        .to_string(),
+                );
+            }
         }
     }
 
@@ -600,6 +1218,22 @@ impl TranscodeJob {
             x265_params.push(format!("rc-lookahead={}", self.settings.lookahead));
         }
 
+        // HDR10の静的メタデータ（手動指定がなければ元の動画から引き継ぐ）
+        if self.metadata.is_hdr() && !self.tone_maps_to_sdr() {
+            let master_display = self
+                .settings
+                .x265_master_display
+                .as_ref()
+                .or(self.metadata.master_display.as_ref());
+            if let Some(master_display) = master_display {
+                x265_params.push(format!("master-display={}", master_display));
+            }
+            if let Some((max_cll, max_fall)) = self.settings.x265_max_cll.or(self.metadata.max_cll)
+            {
+                x265_params.push(format!("max-cll={},{}", max_cll, max_fall));
+            }
+        }
+
         if !x265_params.is_empty() {
             args.push("-x265-params".to_string());
             args.push(x265_params.join(":"));
@@ -610,11 +1244,14 @@ impl TranscodeJob {
     fn add_vp9_args(&self, args: &mut Vec<String>) {
         // VP9では -b:v 0 + -crf でCRFモードを使用
         match self.settings.rate_control {
-            RateControlMode::Crf | RateControlMode::Cqp => {
+            RateControlMode::Crf
+            | RateControlMode::TargetSize
+            | RateControlMode::Cqp
+            | RateControlMode::IcqQsv => {
                 args.push("-b:v".to_string());
                 args.push("0".to_string());
                 args.push("-crf".to_string());
-                args.push(self.settings.crf.to_string());
+                args.push(self.settings.effective_crf("libvpx-vp9").to_string());
             }
             RateControlMode::Cbr => {
                 args.push("-b:v".to_string());
@@ -624,7 +1261,7 @@ impl TranscodeJob {
                 args.push("-maxrate".to_string());
                 args.push(format!("{}k", self.settings.target_bitrate));
             }
-            RateControlMode::Vbr => {
+            RateControlMode::Vbr | RateControlMode::AvbrQsv => {
                 args.push("-b:v".to_string());
                 args.push(format!("{}k", self.settings.target_bitrate));
                 args.push("-maxrate".to_string());
@@ -632,20 +1269,32 @@ impl TranscodeJob {
             }
         }
 
-        // VP9は -cpu-used オプションを使用（0-8、値が大きいほど高速）
-        args.push("-cpu-used".to_string());
-        let cpu_used = match self.settings.preset {
-            super::VideoPreset::Ultrafast => "8",
-            super::VideoPreset::Fast => "6",
-            super::VideoPreset::Medium => "4",
-            super::VideoPreset::Slow => "2",
-            super::VideoPreset::Veryslow => "0",
-        };
-        args.push(cpu_used.to_string());
-
         // VP9は -deadline を設定
+        let deadline = self.settings.vp9_deadline;
         args.push("-deadline".to_string());
-        args.push("good".to_string());
+        args.push(deadline.ffmpeg_value().to_string());
+
+        // VP9は -cpu-used オプションを使用（値が大きいほど高速）
+        // 未指定なら速度プリセットに合わせる
+        let cpu_used = self
+            .settings
+            .vp9_cpu_used
+            .unwrap_or(match self.settings.preset {
+                super::VideoPreset::Ultrafast => 8,
+                super::VideoPreset::Fast => 6,
+                super::VideoPreset::Medium => 4,
+                super::VideoPreset::Slow => 2,
+                super::VideoPreset::Veryslow => 0,
+            });
+        let range = deadline.cpu_used_range();
+        args.push("-cpu-used".to_string());
+        args.push(cpu_used.clamp(*range.start(), *range.end()).to_string());
+
+        // 静止部分の符号化を省略しない（画質優先）
+        if self.settings.vp9_static_thresh_zero {
+            args.push("-static-thresh".to_string());
+            args.push("0".to_string());
+        }
 
         // row-mt を有効にしてマルチスレッド化
         args.push("-row-mt".to_string());
@@ -684,15 +1333,18 @@ impl TranscodeJob {
     fn add_libaom_av1_args(&self, args: &mut Vec<String>) {
         // レートコントロール
         match self.settings.rate_control {
-            RateControlMode::Crf | RateControlMode::Cqp => {
+            RateControlMode::Crf
+            | RateControlMode::TargetSize
+            | RateControlMode::Cqp
+            | RateControlMode::IcqQsv => {
                 args.push("-crf".to_string());
-                args.push(self.settings.crf.to_string());
+                args.push(self.settings.effective_crf("libaom-av1").to_string());
             }
             RateControlMode::Cbr => {
                 args.push("-b:v".to_string());
                 args.push(format!("{}k", self.settings.target_bitrate));
             }
-            RateControlMode::Vbr => {
+            RateControlMode::Vbr | RateControlMode::AvbrQsv => {
                 args.push("-b:v".to_string());
                 args.push(format!("{}k", self.settings.target_bitrate));
                 args.push("-maxrate".to_string());
@@ -715,22 +1367,56 @@ impl TranscodeJob {
         args.push("-row-mt".to_string());
         args.push("1".to_string());
 
-        // タイル設定
-        let tile_setting = format!(
-            "{}x{}",
-            self.settings.av1_tile_columns, self.settings.av1_tile_rows
-        );
-        args.push("-tiles".to_string());
-        args.push(tile_setting);
+        args.push("-usage".to_string());
+        args.push(self.settings.libaom_usage.ffmpeg_value().to_string());
+        args.push("-lag-in-frames".to_string());
+        args.push(self.settings.libaom_lag_in_frames.min(35).to_string());
+        args.push("-auto-alt-ref".to_string());
+        args.push(u8::from(self.settings.libaom_auto_alt_ref).to_string());
+        args.push("-arnr-maxframes".to_string());
+        args.push(self.settings.libaom_arnr_maxframes.to_string());
+        args.push("-arnr-strength".to_string());
+        args.push(self.settings.libaom_arnr_strength.to_string());
+
+        // コンテンツの種類に合わせたチューニング
+        // FFmpegの -tune は psnr/ssim のみのため、libaomのtune-contentを -aom-params で渡す
+        let tune_content = match self.metadata.content_type {
+            ContentType::Anime => Some("animation"),
+            ContentType::ScreenRecord => Some("screen"),
+            _ => None,
+        };
+        if let Some(tune_content) = tune_content {
+            args.push("-aom-params".to_string());
+            args.push(format!("tune-content={}", tune_content));
+        }
+
+        // フィルムグレイン（libaomはノイズ除去とグレイン合成が一体）
+        if self.settings.svtav1_film_grain > 0 {
+            args.push("-denoise-noise-level".to_string());
+            args.push(self.settings.svtav1_film_grain.to_string());
+        }
+
+        // タイル設定（log2指定）
+        if self.settings.av1_tile_columns > 0 {
+            args.push("-tile-columns".to_string());
+            args.push(self.settings.av1_tile_columns.to_string());
+        }
+        if self.settings.av1_tile_rows > 0 {
+            args.push("-tile-rows".to_string());
+            args.push(self.settings.av1_tile_rows.to_string());
+        }
     }
 
     /// SVT-AV1固有引数を追加
     fn add_svtav1_args(&self, args: &mut Vec<String>) {
         // レートコントロール
         match self.settings.rate_control {
-            RateControlMode::Crf | RateControlMode::Cqp => {
+            RateControlMode::Crf
+            | RateControlMode::TargetSize
+            | RateControlMode::Cqp
+            | RateControlMode::IcqQsv => {
                 args.push("-crf".to_string());
-                args.push(self.settings.crf.to_string());
+                args.push(self.settings.effective_crf("libsvtav1").to_string());
             }
             RateControlMode::Cbr => {
                 args.push("-b:v".to_string());
@@ -738,7 +1424,7 @@ impl TranscodeJob {
                 args.push("-rc".to_string());
                 args.push("1".to_string()); // CBR mode
             }
-            RateControlMode::Vbr => {
+            RateControlMode::Vbr | RateControlMode::AvbrQsv => {
                 args.push("-b:v".to_string());
                 args.push(format!("{}k", self.settings.target_bitrate));
                 args.push("-maxrate".to_string());
@@ -757,22 +1443,44 @@ impl TranscodeJob {
         };
         args.push(preset.to_string());
 
-        // フィルムグレイン
+        // フィルムグレイン・タイル設定は -svtav1-params にまとめて渡す
+        // （-tile_columns / -tile_rows は新しいFFmpegで削除済み）
+        let mut params = Vec::new();
         if self.settings.svtav1_film_grain > 0 {
-            args.push("-svtav1-params".to_string());
-            let mut params = format!("film-grain={}", self.settings.svtav1_film_grain);
-            if self.settings.svtav1_film_grain_denoise {
-                params.push_str(":film-grain-denoise=1");
-            }
-            args.push(params);
+            params.push(format!("film-grain={}", self.settings.svtav1_film_grain));
+            params.push(format!(
+                "film-grain-denoise={}",
+                u8::from(self.settings.svtav1_film_grain_denoise)
+            ));
         }
-
-        // タイル設定
-        if self.settings.av1_tile_columns > 0 || self.settings.av1_tile_rows > 0 {
-            args.push("-tile_columns".to_string());
-            args.push(self.settings.av1_tile_columns.to_string());
-            args.push("-tile_rows".to_string());
-            args.push(self.settings.av1_tile_rows.to_string());
+        if self.settings.av1_tile_columns > 0 {
+            params.push(format!("tile-columns={}", self.settings.av1_tile_columns));
+        }
+        if self.settings.av1_tile_rows > 0 {
+            params.push(format!("tile-rows={}", self.settings.av1_tile_rows));
+        }
+        // SVT-AV1の既定値と異なるものだけ指定する
+        if self.settings.svtav1_tune != SvtAv1Tune::default() {
+            params.push(format!("tune={}", self.settings.svtav1_tune.svt_value()));
+        }
+        if !self.settings.svtav1_enable_dlf {
+            params.push("enable-dlf=0".to_string());
+        }
+        if self.settings.svtav1_pred_structure != SvtAv1PredStructure::default() {
+            params.push(format!(
+                "pred-struct={}",
+                self.settings.svtav1_pred_structure.svt_value()
+            ));
+        }
+        if (2..=5).contains(&self.settings.svtav1_hierarchical_levels) {
+            params.push(format!(
+                "hierarchical-levels={}",
+                self.settings.svtav1_hierarchical_levels
+            ));
+        }
+        if !params.is_empty() {
+            args.push("-svtav1-params".to_string());
+            args.push(params.join(":"));
         }
     }
 
@@ -789,7 +1497,14 @@ impl TranscodeJob {
     fn add_audio_args(&self, args: &mut Vec<String>) {
         use super::AudioCodec;
 
-        match self.settings.audio_codec {
+        // 元の音声をそのまま使える場合はコピー
+        let audio_codec = if self.smart_audio_copy() {
+            AudioCodec::Copy
+        } else {
+            self.settings.audio_codec
+        };
+
+        match audio_codec {
             AudioCodec::Copy => {
                 args.push("-c:a".to_string());
                 args.push("copy".to_string());
@@ -811,5 +1526,1723 @@ impl TranscodeJob {
                 args.push("flac".to_string());
             }
         }
+
+        // ダウンミックス・リサンプル（コピー時は再エンコードしないため指定できない）
+        if audio_codec == AudioCodec::Copy {
+            return;
+        }
+        // エンコーダーが扱えないチャンネル数は指定しない（検証でエラーにする）
+        if let Some(channels) = self.settings.audio_channels.count().filter(|&n| {
+            self.settings
+                .audio_codec
+                .max_channels()
+                .is_none_or(|max| n <= max)
+        }) {
+            args.push("-ac".to_string());
+            args.push(channels.to_string());
+        }
+        if let Some(rate) = self.settings.audio_sample_rate {
+            args.push("-ar".to_string());
+            args.push(rate.to_string());
+        }
+    }
+
+    /// メタデータ・チャプターの引き継ぎ引数を追加
+    /// コンテナの既定に任せると変換先によって失われるため、どちらの場合も明示する
+    fn add_metadata_args(&self, args: &mut Vec<String>) {
+        let source = |preserve: bool| if preserve { "0" } else { "-1" };
+        args.push("-map_metadata".to_string());
+        args.push(source(self.settings.preserve_metadata).to_string());
+        args.push("-map_chapters".to_string());
+        args.push(source(self.settings.preserve_chapters).to_string());
+    }
+}
+
+/// FFmpegに渡すファイルパス
+/// Windowsでは長いパスに \\?\ を付ける（FFmpegがMAX_PATHを超えるパスを開けるように）
+fn path_arg(path: &Path) -> OsString {
+    #[cfg(target_os = "windows")]
+    {
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir()
+                .map(|dir| dir.join(path))
+                .unwrap_or_else(|_| path.to_path_buf())
+        };
+        if let Some(long) = absolute.to_str().and_then(windows_long_path) {
+            return long.into();
+        }
+    }
+    path.as_os_str().to_os_string()
+}
+
+/// MAX_PATHを超えるWindowsの絶対パスに \\?\ を付ける（不要ならNone）
+/// \\?\ 付きのパスは区切り文字が正規化されないため、/ は \ に置き換える
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn windows_long_path(path: &str) -> Option<String> {
+    /// \\?\ を付けずに扱えるパスの長さ（MAX_PATH、終端のNULを含む）
+    const MAX_PATH: usize = 260;
+
+    if path.encode_utf16().count() < MAX_PATH || path.starts_with(r"\\?\") {
+        return None;
+    }
+    let path = path.replace('/', r"\");
+    if let Some(share) = path.strip_prefix(r"\\") {
+        // UNCパス（\\server\share\...）
+        return Some(format!(r"\\?\UNC\{}", share));
+    }
+    let bytes = path.as_bytes();
+    let has_drive =
+        bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\';
+    has_drive.then(|| format!(r"\\?\{}", path))
+}
+
+/// シェル用に引数をクォート
+fn quote_arg(arg: &str) -> String {
+    if cfg!(windows) {
+        quote_arg_windows(arg)
+    } else {
+        quote_arg_posix(arg)
+    }
+}
+
+/// POSIXシェル用（必要な場合のみシングルクォートで囲む）
+fn quote_arg_posix(arg: &str) -> String {
+    let is_safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=+,@%".contains(c));
+    if is_safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Windowsのコマンドプロンプト用（必要な場合のみダブルクォートで囲む）
+fn quote_arg_windows(arg: &str) -> String {
+    let needs_quote = arg.is_empty()
+        || arg
+            .chars()
+            .any(|c| c.is_whitespace() || "\"&|<>^()%!,;=".contains(c));
+    if needs_quote {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// 2つのパスが同じファイルを指しているか判定
+pub fn is_same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// 既存ファイルと衝突しないパスを生成（stem_1.ext, stem_2.ext, ...）
+pub fn avoid_collision(path: &Path) -> PathBuf {
+    avoid_collision_with(path, |path| path.exists())
+}
+
+/// `taken` で使用中と判定されないパスを生成
+fn avoid_collision_with(path: &Path, taken: impl Fn(&Path) -> bool) -> PathBuf {
+    if !taken(path) {
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().unwrap_or(OsStr::new("output"));
+    let extension = path.extension();
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut index = 1u32;
+    loop {
+        let mut file_name = stem.to_os_string();
+        file_name.push(format!("_{}", index));
+        if let Some(ext) = extension {
+            file_name.push(".");
+            file_name.push(ext);
+        }
+        let candidate = parent.join(file_name);
+        if !taken(&candidate) {
+            return candidate;
+        }
+        index += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcoder::NvencBRefMode;
+    use std::ffi::OsStr;
+    use std::fs;
+
+    /// テスト用の一時ディレクトリを作成
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("kamaitachi_job_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 比率を保って枠に収めるスケールフィルタ
+    fn fit_scale(w: u32, h: u32) -> String {
+        format!(
+            "scale={}:{}:force_original_aspect_ratio=decrease:force_divisible_by=2",
+            w, h
+        )
+    }
+
+    #[test]
+    fn test_avoid_collision() {
+        let dir = temp_dir("collision");
+        let path = dir.join("video.mp4");
+
+        // 存在しなければそのまま
+        assert_eq!(avoid_collision(&path), path);
+
+        fs::write(&path, b"").unwrap();
+        assert_eq!(avoid_collision(&path), dir.join("video_1.mp4"));
+
+        fs::write(dir.join("video_1.mp4"), b"").unwrap();
+        assert_eq!(avoid_collision(&path), dir.join("video_2.mp4"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_path_keeps_non_utf8_names() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = temp_dir("non_utf8");
+        let input = dir.join(OsStr::from_bytes(b"clip\xff.mkv"));
+        let other = dir.join(OsStr::from_bytes(b"clip\xfe.mkv"));
+        let settings = TranscodeSettings::default();
+
+        // 別のファイルが同じ出力名（output_transcoded.mp4など）にならない
+        let output = TranscodeJob::generate_output_path(&input, &dir, "_transcoded", &settings);
+        assert_eq!(
+            output.file_name().unwrap().as_bytes(),
+            b"clip\xff_transcoded.mp4"
+        );
+        assert_ne!(
+            TranscodeJob::generate_output_path(&other, &dir, "_transcoded", &settings),
+            output
+        );
+
+        fs::write(&output, b"").unwrap();
+        assert_eq!(
+            avoid_collision(&output).file_name().unwrap().as_bytes(),
+            b"clip\xff_transcoded_1.mp4"
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_empty_suffix_never_overwrites_input() {
+        let dir = temp_dir("empty_suffix");
+        let input = dir.join("video.mp4");
+        fs::write(&input, b"source").unwrap();
+
+        let settings = TranscodeSettings::default();
+        let output = TranscodeJob::generate_output_path(&input, &dir, "", &settings);
+        assert_ne!(output, input);
+
+        // 既存の出力パスを直接渡しても入力と同じにはならない
+        let job = TranscodeJob::new(input.clone(), input.clone(), settings);
+        assert_ne!(job.output_path, input);
+        let args = job.build_ffmpeg_args();
+        assert_ne!(
+            args.last().map(PathBuf::from),
+            Some(input.clone()),
+            "output argument must not be the input file"
+        );
+
+        for policy in OverwritePolicy::all() {
+            let resolution = TranscodeJob::resolve_output_path(&input, input.clone(), *policy);
+            assert_ne!(resolution.path(), Some(&input));
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// HDR10ソースのメタデータ
+    fn hdr10_metadata() -> VideoMetadata {
+        VideoMetadata {
+            video_bit_depth: Some(10),
+            pix_fmt: Some("yuv420p10le".to_string()),
+            color_primaries: Some("bt2020".to_string()),
+            color_trc: Some("smpte2084".to_string()),
+            color_space: Some("bt2020nc".to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// 引数列から指定フラグの値を取得
+    fn arg_value<'a, S: AsRef<OsStr>>(args: &'a [S], flag: &str) -> Option<&'a str> {
+        args.iter()
+            .position(|a| a.as_ref() == OsStr::new(flag))
+            .and_then(|i| args.get(i + 1))
+            .and_then(|s| s.as_ref().to_str())
+    }
+
+    #[test]
+    fn test_hdr_passthrough_args() {
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.mkv"),
+            TranscodeSettings::default(),
+        )
+        .with_metadata(hdr10_metadata());
+
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx265", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-pix_fmt"), Some("yuv420p10le"));
+        assert_eq!(arg_value(&args, "-color_primaries"), Some("bt2020"));
+        assert_eq!(arg_value(&args, "-color_trc"), Some("smpte2084"));
+        assert_eq!(arg_value(&args, "-colorspace"), Some("bt2020nc"));
+
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "hevc_nvenc", &HwAccelType::Nvenc);
+        assert_eq!(arg_value(&args, "-pix_fmt"), Some("p010le"));
+
+        // 8bitのみのエンコーダーは8bitに変換し、色情報はタグ付けする
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-pix_fmt"), Some("yuv420p"));
+        assert_eq!(arg_value(&args, "-color_trc"), Some("smpte2084"));
+    }
+
+    #[test]
+    fn test_hdr_to_sdr_tone_mapping() {
+        use crate::transcoder::HdrToneMappingMode;
+
+        let settings = TranscodeSettings {
+            hdr_to_sdr: true,
+            tone_mapping_mode: HdrToneMappingMode::Mobius,
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        )
+        .with_metadata(hdr10_metadata());
+
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx265", &HwAccelType::Software);
+        let vf = arg_value(&args, "-vf").unwrap();
+        assert!(vf.starts_with("zscale=transfer=linear"));
+        assert!(vf.contains("tonemap=tonemap=mobius"));
+        assert!(vf.ends_with("format=yuv420p"));
+        // 10bitのまま出力せず、BT.709としてタグ付けする
+        assert_eq!(arg_value(&args, "-pix_fmt"), None);
+        assert_eq!(arg_value(&args, "-color_primaries"), Some("bt709"));
+        assert_eq!(arg_value(&args, "-color_trc"), Some("bt709"));
+        assert_eq!(arg_value(&args, "-colorspace"), Some("bt709"));
+
+        // SDRのソースには適用しない
+        let job = TranscodeJob::new(PathBuf::from("in.mp4"), PathBuf::from("out.mp4"), settings);
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx265", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-vf"), None);
+        assert_eq!(arg_value(&args, "-color_trc"), None);
+    }
+
+    #[test]
+    fn test_crf_is_translated_per_codec() {
+        use crate::transcoder::encoder_crf;
+
+        // x264/x265とHWエンコーダーはそのまま
+        for encoder in ["libx264", "libx265", "hevc_nvenc", "av1_qsv"] {
+            assert_eq!(encoder_crf(23, encoder), 23);
+        }
+        // 対応表の値と、その間の線形補間
+        assert_eq!(encoder_crf(23, "libvpx-vp9"), 31);
+        assert_eq!(encoder_crf(23, "libsvtav1"), 30);
+        assert_eq!(encoder_crf(19, "libsvtav1"), 24);
+        assert_eq!(encoder_crf(20, "libvpx-vp9"), 27);
+        assert_eq!(encoder_crf(0, "libaom-av1"), 0);
+        assert_eq!(encoder_crf(60, "libvpx-vp9"), 63);
+        // 数値が大きいほど低画質という順序を保つ
+        for table_encoder in ["libvpx-vp9", "libsvtav1"] {
+            for crf in 0..51 {
+                assert!(encoder_crf(crf, table_encoder) <= encoder_crf(crf + 1, table_encoder));
+            }
+        }
+
+        // H.264からAV1/VP9に切り替えても同じ設定値で同程度の画質になる
+        let mut settings = TranscodeSettings {
+            crf: 23,
+            ..Default::default()
+        };
+        let crf_arg = |settings: &TranscodeSettings, encoder: &str| {
+            let job = TranscodeJob::new(
+                PathBuf::from("in.mkv"),
+                PathBuf::from("out.mkv"),
+                settings.clone(),
+            );
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, encoder, &HwAccelType::Software);
+            arg_value(&args, "-crf").map(str::to_string)
+        };
+        assert_eq!(crf_arg(&settings, "libx264").as_deref(), Some("23"));
+        settings.video_codec = VideoCodec::Av1;
+        assert_eq!(crf_arg(&settings, "libsvtav1").as_deref(), Some("30"));
+        assert_eq!(crf_arg(&settings, "libaom-av1").as_deref(), Some("30"));
+        settings.video_codec = VideoCodec::Vp9;
+        assert_eq!(crf_arg(&settings, "libvpx-vp9").as_deref(), Some("31"));
+    }
+
+    #[test]
+    fn test_x265_hdr10_static_metadata() {
+        let x265_params = |settings: TranscodeSettings, metadata: VideoMetadata| {
+            let job =
+                TranscodeJob::new(PathBuf::from("in.mkv"), PathBuf::from("out.mkv"), settings)
+                    .with_metadata(metadata);
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, "libx265", &HwAccelType::Software);
+            arg_value(&args, "-x265-params")
+                .unwrap_or_default()
+                .to_string()
+        };
+        let source = VideoMetadata {
+            master_display: Some(
+                "G(13250,34500)B(7500,3000)R(34000,16000)WP(15635,16450)L(10000000,50)".to_string(),
+            ),
+            max_cll: Some((1000, 400)),
+            ..hdr10_metadata()
+        };
+
+        // 元の動画から引き継ぐ
+        let params = x265_params(TranscodeSettings::default(), source.clone());
+        assert!(params.contains(
+            ":master-display=G(13250,34500)B(7500,3000)R(34000,16000)WP(15635,16450)L(10000000,50)"
+        ));
+        assert!(params.ends_with(":max-cll=1000,400"));
+
+        // 手動指定が優先
+        let mut settings = TranscodeSettings {
+            x265_master_display: Some(
+                "G(8500,39850)B(6550,2300)R(35400,14600)WP(15635,16450)L(40000000,50)".to_string(),
+            ),
+            x265_max_cll: crate::transcoder::parse_max_cll(" 4000, 1200 "),
+            ..Default::default()
+        };
+        let params = x265_params(settings.clone(), source.clone());
+        assert!(params.contains("master-display=G(8500,39850)"));
+        assert!(params.ends_with(":max-cll=4000,1200"));
+
+        // SDRのソースやトーンマッピングする場合は付けない
+        let params = x265_params(settings.clone(), VideoMetadata::default());
+        assert!(!params.contains("master-display") && !params.contains("max-cll"));
+        settings.hdr_to_sdr = true;
+        let params = x265_params(settings, source);
+        assert!(!params.contains("master-display") && !params.contains("max-cll"));
+
+        assert_eq!(crate::transcoder::parse_max_cll("1000"), None);
+        assert_eq!(crate::transcoder::parse_max_cll("1000,abc"), None);
+    }
+
+    #[test]
+    fn test_pixel_format_selection() {
+        let pix_fmt = |pixel_format: PixelFormat, encoder: &str| {
+            let settings = TranscodeSettings {
+                pixel_format,
+                ..Default::default()
+            };
+            let job =
+                TranscodeJob::new(PathBuf::from("in.mp4"), PathBuf::from("out.mp4"), settings);
+            let mut args = Vec::new();
+            job.add_color_args(&mut args, encoder);
+            arg_value(&args, "-pix_fmt").map(str::to_string)
+        };
+
+        // 自動では8bitソースに-pix_fmtを付けない
+        assert_eq!(pix_fmt(PixelFormat::Auto, "libx264"), None);
+        assert_eq!(
+            pix_fmt(PixelFormat::Yuv422p10le, "libx265").as_deref(),
+            Some("yuv422p10le")
+        );
+        assert_eq!(
+            pix_fmt(PixelFormat::Yuv420p10le, "hevc_nvenc").as_deref(),
+            Some("p010le")
+        );
+        // 8bitのみのエンコーダーは4:2:0 8bitに戻す
+        assert_eq!(
+            pix_fmt(PixelFormat::Yuv420p10le, "libx264").as_deref(),
+            Some("yuv420p")
+        );
+
+        let mut settings = TranscodeSettings {
+            pixel_format: PixelFormat::Yuv420p10le,
+            ..Default::default()
+        };
+        assert!(settings.pixel_format_warning("libx264").is_some());
+        assert!(settings.pixel_format_warning("libx265").is_none());
+        settings.pixel_format = PixelFormat::Yuv444p;
+        settings.x264_profile = crate::transcoder::X264Profile::Baseline;
+        assert!(settings.pixel_format_warning("libx264").is_some());
+        settings.x264_profile = crate::transcoder::X264Profile::High444;
+        assert!(settings.pixel_format_warning("libx264").is_none());
+    }
+
+    #[test]
+    fn test_sdr_source_has_no_color_args() {
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mp4"),
+            PathBuf::from("out.mp4"),
+            TranscodeSettings::default(),
+        );
+
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-pix_fmt"), None);
+        assert_eq!(arg_value(&args, "-color_primaries"), None);
+    }
+
+    #[test]
+    fn test_video_filter_chain() {
+        use crate::transcoder::{FrameRate, VideoResolution};
+
+        let settings = TranscodeSettings {
+            frame_rate: FrameRate::Fps30,
+            resolution: VideoResolution::Hd720,
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(PathBuf::from("in.mp4"), PathBuf::from("out.mp4"), settings);
+
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+        assert_eq!(
+            arg_value(&args, "-vf"),
+            Some(format!("fps=30,{}", fit_scale(1280, 720)).as_str())
+        );
+
+        // 元のFPS・解像度の場合は-vfを出力しない
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mp4"),
+            PathBuf::from("out.mp4"),
+            TranscodeSettings::default(),
+        );
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-vf"), None);
+    }
+
+    #[test]
+    fn test_fps_filter_modes() {
+        use crate::transcoder::{FpsFilterMode, FrameRate};
+
+        let vf = |frame_rate: FrameRate, mode: FpsFilterMode| {
+            let settings = TranscodeSettings {
+                frame_rate,
+                fps_filter_mode: mode,
+                ..Default::default()
+            };
+            let job =
+                TranscodeJob::new(PathBuf::from("in.mp4"), PathBuf::from("out.mp4"), settings);
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+            arg_value(&args, "-vf").map(str::to_string)
+        };
+
+        // NTSC系は分数で指定する
+        assert_eq!(
+            vf(FrameRate::Fps23_976, FpsFilterMode::Simple).as_deref(),
+            Some("fps=24000/1001")
+        );
+        assert_eq!(
+            vf(FrameRate::Fps50, FpsFilterMode::Blend).as_deref(),
+            Some("framerate=fps=50")
+        );
+        assert_eq!(
+            vf(FrameRate::Fps59_94, FpsFilterMode::Minterpolate).as_deref(),
+            Some("minterpolate=fps=60000/1001:mi_mode=mci:mc_mode=aobmc")
+        );
+        // 元のFPSでは方式に関係なくフィルタを追加しない
+        assert_eq!(vf(FrameRate::Original, FpsFilterMode::Minterpolate), None);
+    }
+
+    #[test]
+    fn test_deinterlace_auto_follows_field_order() {
+        use crate::transcoder::{Deinterlace, FrameRate};
+
+        let mut settings = TranscodeSettings {
+            deinterlace: Deinterlace::Auto,
+            frame_rate: FrameRate::Fps30,
+            ..Default::default()
+        };
+        let interlaced = VideoMetadata {
+            field_order: Some("tt".to_string()),
+            ..Default::default()
+        };
+        let progressive = VideoMetadata {
+            field_order: Some("progressive".to_string()),
+            ..Default::default()
+        };
+
+        // インターレースのソースは他のフィルタより先に解除する
+        let job = TranscodeJob::new(
+            PathBuf::from("in.ts"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        )
+        .with_metadata(interlaced.clone());
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+        assert_eq!(
+            arg_value(&args, "-vf"),
+            Some("bwdif=mode=send_frame,fps=30")
+        );
+
+        // プログレッシブのソースはフィルタを追加しない
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mp4"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        )
+        .with_metadata(progressive.clone());
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-vf"), Some("fps=30"));
+
+        // 明示指定はソースに関係なく適用、オフはインターレースでも適用しない
+        settings.deinterlace = Deinterlace::Yadif;
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mp4"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        )
+        .with_metadata(progressive);
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+        assert_eq!(
+            arg_value(&args, "-vf"),
+            Some("yadif=mode=send_frame,fps=30")
+        );
+
+        settings.deinterlace = Deinterlace::Off;
+        let job = TranscodeJob::new(PathBuf::from("in.ts"), PathBuf::from("out.mp4"), settings)
+            .with_metadata(interlaced);
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-vf"), Some("fps=30"));
+    }
+
+    #[test]
+    fn test_nvenc_spatial_and_temporal_aq() {
+        let build = |settings: TranscodeSettings| {
+            let job =
+                TranscodeJob::new(PathBuf::from("in.mp4"), PathBuf::from("out.mp4"), settings);
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, "hevc_nvenc", &HwAccelType::Nvenc);
+            args
+        };
+
+        let mut settings = TranscodeSettings {
+            nvenc_spatial_aq: false,
+            nvenc_temporal_aq: true,
+            // x264用のAQモードはNVENCには影響しない
+            aq_mode: AqMode::AutoVariance,
+            ..Default::default()
+        };
+        let args = build(settings.clone());
+        assert_eq!(arg_value(&args, "-spatial-aq"), None);
+        assert_eq!(arg_value(&args, "-aq-strength"), None);
+        assert_eq!(arg_value(&args, "-temporal-aq"), Some("1"));
+
+        settings.nvenc_spatial_aq = true;
+        settings.nvenc_temporal_aq = false;
+        settings.nvenc_aq_strength = 12;
+        settings.aq_mode = AqMode::None;
+        let args = build(settings);
+        assert_eq!(arg_value(&args, "-spatial-aq"), Some("1"));
+        assert_eq!(arg_value(&args, "-aq-strength"), Some("12"));
+        assert_eq!(arg_value(&args, "-temporal-aq"), None);
+    }
+
+    #[test]
+    fn test_nvenc_lookahead_and_b_ref_mode() {
+        let build = |settings: TranscodeSettings| {
+            let job =
+                TranscodeJob::new(PathBuf::from("in.mp4"), PathBuf::from("out.mp4"), settings);
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, "hevc_nvenc", &HwAccelType::Nvenc);
+            args
+        };
+
+        let mut settings = TranscodeSettings {
+            // 共通のルックアヘッドはNVENCには影響しない
+            lookahead: 40,
+            nvenc_rc_lookahead: 16,
+            bframes: 3,
+            nvenc_b_ref_mode: NvencBRefMode::Middle,
+            ..Default::default()
+        };
+        let args = build(settings.clone());
+        assert_eq!(arg_value(&args, "-rc-lookahead"), Some("16"));
+        assert_eq!(arg_value(&args, "-b_ref_mode"), Some("middle"));
+
+        settings.nvenc_rc_lookahead = 0;
+        settings.bframes = 0;
+        let args = build(settings);
+        assert_eq!(arg_value(&args, "-rc-lookahead"), None);
+        assert_eq!(arg_value(&args, "-b_ref_mode"), None);
+    }
+
+    #[test]
+    fn test_qsv_panel_settings_reach_args() {
+        let mut settings = TranscodeSettings {
+            hwaccel: HwAccelType::Qsv,
+            qsv_la_depth: 40,
+            qsv_adaptive_b: false,
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mp4"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        );
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "h264_qsv", &HwAccelType::Qsv);
+        assert_eq!(arg_value(&args, "-look_ahead_depth"), Some("40"));
+        assert_eq!(arg_value(&args, "-adaptive_b"), None);
+
+        settings.qsv_adaptive_b = true;
+        let job = TranscodeJob::new(PathBuf::from("in.mp4"), PathBuf::from("out.mp4"), settings);
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "h264_qsv", &HwAccelType::Qsv);
+        assert_eq!(arg_value(&args, "-adaptive_b"), Some("1"));
+    }
+
+    #[test]
+    fn test_amf_pre_analysis_and_vbaq_args() {
+        let mut settings = TranscodeSettings {
+            hwaccel: HwAccelType::Amf,
+            bframes: 2,
+            ..Default::default()
+        };
+        let build = |settings: &TranscodeSettings, encoder: &str| {
+            let job = TranscodeJob::new(
+                PathBuf::from("in.mp4"),
+                PathBuf::from("out.mp4"),
+                settings.clone(),
+            );
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, encoder, &HwAccelType::Amf);
+            args
+        };
+
+        // 既定ではプリアナリシスとVBAQを有効にする
+        let args = build(&settings, "h264_amf");
+        assert_eq!(arg_value(&args, "-preanalysis"), Some("1"));
+        assert_eq!(arg_value(&args, "-pa_initial_qp_after_reset"), Some("23"));
+        assert_eq!(arg_value(&args, "-vbaq"), Some("1"));
+        assert_eq!(arg_value(&args, "-preencode"), None);
+        assert_eq!(arg_value(&args, "-bf_delta_qp"), Some("-4"));
+
+        settings.amf_pre_analysis = false;
+        settings.amf_rate_control_preanalysis = true;
+        settings.amf_b_frame_delta_qp = 6;
+        let args = build(&settings, "h264_amf");
+        assert_eq!(arg_value(&args, "-preanalysis"), None);
+        assert_eq!(arg_value(&args, "-preencode"), Some("1"));
+        assert_eq!(arg_value(&args, "-bf_delta_qp"), Some("6"));
+
+        // AV1はVBAQを持たず、HEVC/AV1はBフレームのQP差分を指定しない
+        let args = build(&settings, "av1_amf");
+        assert_eq!(arg_value(&args, "-vbaq"), None);
+        assert_eq!(arg_value(&args, "-bf_delta_qp"), None);
+        let args = build(&settings, "hevc_amf");
+        assert_eq!(arg_value(&args, "-vbaq"), Some("1"));
+        assert_eq!(arg_value(&args, "-bf_delta_qp"), None);
+    }
+
+    #[test]
+    fn test_qsv_brc_and_sei_args() {
+        let mut settings = TranscodeSettings {
+            hwaccel: HwAccelType::Qsv,
+            ..Default::default()
+        };
+        let build = |settings: &TranscodeSettings, encoder: &str| {
+            let job = TranscodeJob::new(
+                PathBuf::from("in.mp4"),
+                PathBuf::from("out.mp4"),
+                settings.clone(),
+            );
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, encoder, &HwAccelType::Qsv);
+            args
+        };
+
+        let args = build(&settings, "h264_qsv");
+        assert_eq!(arg_value(&args, "-extbrc"), None);
+        assert_eq!(arg_value(&args, "-async_depth"), Some("4"));
+        assert_eq!(arg_value(&args, "-pic_timing_sei"), Some("1"));
+        assert_eq!(arg_value(&args, "-recovery_point_sei"), None);
+
+        settings.qsv_extbrc = true;
+        settings.qsv_async_depth = 8;
+        settings.qsv_pic_timing_sei = false;
+        settings.qsv_recovery_point_sei = true;
+        let args = build(&settings, "hevc_qsv");
+        assert_eq!(arg_value(&args, "-extbrc"), Some("1"));
+        assert_eq!(arg_value(&args, "-async_depth"), Some("8"));
+        assert_eq!(arg_value(&args, "-pic_timing_sei"), Some("0"));
+        assert_eq!(arg_value(&args, "-recovery_point_sei"), Some("1"));
+
+        // AV1はSEIを持たない
+        let args = build(&settings, "av1_qsv");
+        assert_eq!(arg_value(&args, "-extbrc"), Some("1"));
+        assert_eq!(arg_value(&args, "-pic_timing_sei"), None);
+        assert_eq!(arg_value(&args, "-recovery_point_sei"), None);
+    }
+
+    #[test]
+    fn test_qsv_icq_and_avbr_modes() {
+        let build = |settings: &TranscodeSettings, encoder: &str, hwaccel: HwAccelType| {
+            let job = TranscodeJob::new(
+                PathBuf::from("in.mp4"),
+                PathBuf::from("out.mp4"),
+                settings.clone(),
+            );
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, encoder, &hwaccel);
+            args
+        };
+
+        let mut settings = TranscodeSettings {
+            rate_control: RateControlMode::IcqQsv,
+            crf: 21,
+            ..Default::default()
+        };
+        let args = build(&settings, "hevc_qsv", HwAccelType::Qsv);
+        assert_eq!(arg_value(&args, "-global_quality"), Some("21"));
+        assert_eq!(arg_value(&args, "-b:v"), None);
+
+        // ソフトウェアにフォールバックした場合はCRFとして扱う
+        let args = build(&settings, "libx265", HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-crf"), Some("21"));
+
+        settings.rate_control = RateControlMode::AvbrQsv;
+        settings.target_bitrate = 4000;
+        let args = build(&settings, "h264_qsv", HwAccelType::Qsv);
+        assert_eq!(arg_value(&args, "-b:v"), Some("4000k"));
+        assert_eq!(arg_value(&args, "-avbr_accuracy"), Some("100"));
+        assert_eq!(arg_value(&args, "-avbr_convergence"), Some("1"));
+        assert_eq!(arg_value(&args, "-maxrate"), None);
+    }
+
+    #[test]
+    fn test_vp9_panel_settings_reach_args() {
+        let mut settings = TranscodeSettings {
+            video_codec: VideoCodec::Vp9,
+            vp9_tile_columns: 3,
+            vp9_tile_rows: 0,
+            vp9_frame_parallel: false,
+            vp9_lag_in_frames: 16,
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.mkv"),
+            settings.clone(),
+        );
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libvpx-vp9", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-tile-columns"), Some("3"));
+        assert_eq!(arg_value(&args, "-tile-rows"), None);
+        assert_eq!(arg_value(&args, "-frame-parallel"), None);
+        assert_eq!(arg_value(&args, "-lag-in-frames"), Some("16"));
+
+        // 自動ALTフレームがオフならラグインフレームは指定しない
+        settings.vp9_auto_alt_ref = false;
+        let job = TranscodeJob::new(PathBuf::from("in.mkv"), PathBuf::from("out.mkv"), settings);
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libvpx-vp9", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-auto-alt-ref"), None);
+        assert_eq!(arg_value(&args, "-lag-in-frames"), None);
+    }
+
+    #[test]
+    fn test_vp9_deadline_and_cpu_used() {
+        use crate::transcoder::{VideoPreset, Vp9Deadline};
+
+        let mut settings = TranscodeSettings {
+            video_codec: VideoCodec::Vp9,
+            preset: VideoPreset::Slow,
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.webm"),
+            settings.clone(),
+        );
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libvpx-vp9", &HwAccelType::Software);
+        // 未指定なら速度プリセットから決める
+        assert_eq!(arg_value(&args, "-deadline"), Some("good"));
+        assert_eq!(arg_value(&args, "-cpu-used"), Some("2"));
+        assert_eq!(arg_value(&args, "-static-thresh"), Some("0"));
+
+        settings.vp9_deadline = Vp9Deadline::Best;
+        settings.vp9_cpu_used = Some(-3);
+        settings.vp9_static_thresh_zero = false;
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.webm"),
+            settings.clone(),
+        );
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libvpx-vp9", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-deadline"), Some("best"));
+        assert_eq!(arg_value(&args, "-cpu-used"), Some("-3"));
+        assert_eq!(arg_value(&args, "-static-thresh"), None);
+
+        // realtimeでは負の値を使えない
+        settings.vp9_deadline = Vp9Deadline::Realtime;
+        let job = TranscodeJob::new(PathBuf::from("in.mkv"), PathBuf::from("out.webm"), settings);
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libvpx-vp9", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-deadline"), Some("realtime"));
+        assert_eq!(arg_value(&args, "-cpu-used"), Some("0"));
+    }
+
+    #[test]
+    fn test_av1_film_grain_and_tiles_reach_args() {
+        let settings = TranscodeSettings {
+            video_codec: VideoCodec::Av1,
+            svtav1_film_grain: 8,
+            svtav1_film_grain_denoise: true,
+            av1_tile_columns: 2,
+            av1_tile_rows: 0,
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(PathBuf::from("in.mkv"), PathBuf::from("out.mkv"), settings);
+
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libsvtav1", &HwAccelType::Software);
+        assert_eq!(
+            arg_value(&args, "-svtav1-params"),
+            Some("film-grain=8:film-grain-denoise=1:tile-columns=2")
+        );
+
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libaom-av1", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-denoise-noise-level"), Some("8"));
+        assert_eq!(arg_value(&args, "-tile-columns"), Some("2"));
+        assert_eq!(arg_value(&args, "-tile-rows"), None);
+
+        // HWエンコーダーにはソフトウェア専用のオプションを渡さない
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "av1_nvenc", &HwAccelType::Nvenc);
+        assert_eq!(arg_value(&args, "-svtav1-params"), None);
+        assert_eq!(arg_value(&args, "-denoise-noise-level"), None);
+    }
+
+    #[test]
+    fn test_libaom_av1_args() {
+        use crate::transcoder::LibaomUsage;
+
+        let settings = TranscodeSettings {
+            video_codec: VideoCodec::Av1,
+            libaom_usage: LibaomUsage::AllIntra,
+            libaom_lag_in_frames: 48,
+            libaom_auto_alt_ref: false,
+            libaom_arnr_maxframes: 15,
+            libaom_arnr_strength: 3,
+            ..Default::default()
+        };
+        let metadata = VideoMetadata {
+            content_type: ContentType::Anime,
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(PathBuf::from("in.mkv"), PathBuf::from("out.mkv"), settings)
+            .with_metadata(metadata);
+
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libaom-av1", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-usage"), Some("allintra"));
+        // ラグインフレームはlibaomの上限（35）に収める
+        assert_eq!(arg_value(&args, "-lag-in-frames"), Some("35"));
+        assert_eq!(arg_value(&args, "-auto-alt-ref"), Some("0"));
+        assert_eq!(arg_value(&args, "-arnr-maxframes"), Some("15"));
+        assert_eq!(arg_value(&args, "-arnr-strength"), Some("3"));
+        assert_eq!(
+            arg_value(&args, "-aom-params"),
+            Some("tune-content=animation")
+        );
+
+        // 実写ではtune-contentを指定しない
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.mkv"),
+            TranscodeSettings::default(),
+        );
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libaom-av1", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-usage"), Some("good"));
+        assert_eq!(arg_value(&args, "-aom-params"), None);
+    }
+
+    #[test]
+    fn test_svtav1_advanced_params() {
+        let settings = TranscodeSettings {
+            video_codec: VideoCodec::Av1,
+            av1_tile_columns: 0,
+            av1_tile_rows: 0,
+            svtav1_tune: SvtAv1Tune::VisualQuality,
+            svtav1_enable_dlf: false,
+            svtav1_pred_structure: SvtAv1PredStructure::LowDelay,
+            svtav1_hierarchical_levels: 3,
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(PathBuf::from("in.mkv"), PathBuf::from("out.mkv"), settings);
+
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libsvtav1", &HwAccelType::Software);
+        assert_eq!(
+            arg_value(&args, "-svtav1-params"),
+            Some("tune=0:enable-dlf=0:pred-struct=1:hierarchical-levels=3")
+        );
+
+        // 既定値のままなら何も指定しない
+        let settings = TranscodeSettings {
+            video_codec: VideoCodec::Av1,
+            av1_tile_columns: 0,
+            av1_tile_rows: 0,
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(PathBuf::from("in.mkv"), PathBuf::from("out.mkv"), settings);
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libsvtav1", &HwAccelType::Software);
+        assert_eq!(arg_value(&args, "-svtav1-params"), None);
+    }
+
+    #[test]
+    fn test_crop_before_scale() {
+        use crate::transcoder::{CropMode, VideoResolution};
+
+        let mut settings = TranscodeSettings {
+            resolution: VideoResolution::Hd720,
+            crop: CropSettings {
+                left: 8,
+                right: 8,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // 設定値のクロップ
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mp4"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        );
+        let mut args = Vec::new();
+        job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+        assert_eq!(
+            arg_value(&args, "-vf"),
+            Some(format!("crop=iw-16:ih-0:8:0,{}", fit_scale(1280, 720)).as_str())
+        );
+
+        // 自動検出結果（1920x1080のレターボックス → 1920x800）が設定値より優先される
+        let detected = CropSettings::from_area((1920, 800, 0, 140), (1920, 1080));
+        let vf = |settings: &TranscodeSettings, crop: Option<CropSettings>| {
+            let job = TranscodeJob::new(
+                PathBuf::from("in.mp4"),
+                PathBuf::from("out.mp4"),
+                settings.clone(),
+            )
+            .with_crop(crop);
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, "libx264", &HwAccelType::Software);
+            arg_value(&args, "-vf").map(str::to_string)
+        };
+        assert_eq!(
+            vf(&settings, Some(detected)).as_deref(),
+            Some(format!("crop=iw-0:ih-280:0:140,{}", fit_scale(1280, 720)).as_str())
+        );
+
+        // 自動検出モードでは未検出のファイルに設定値を使わない
+        settings.crop_mode = CropMode::AutoDetect;
+        assert_eq!(
+            vf(&settings, None).as_deref(),
+            Some(fit_scale(1280, 720).as_str())
+        );
+        assert_eq!(
+            vf(&settings, Some(detected)).as_deref(),
+            Some(format!("crop=iw-0:ih-280:0:140,{}", fit_scale(1280, 720)).as_str())
+        );
+
+        // なしの場合は検出結果も無視する
+        settings.crop_mode = CropMode::Off;
+        assert_eq!(
+            vf(&settings, Some(detected)).as_deref(),
+            Some(fit_scale(1280, 720).as_str())
+        );
+    }
+
+    #[test]
+    fn test_resolve_output_path_policy() {
+        let dir = temp_dir("policy");
+        let input = dir.join("input.mkv");
+        let output = dir.join("input_transcoded.mp4");
+        fs::write(&input, b"").unwrap();
+
+        // 出力が存在しなければポリシーに関係なくそのまま
+        assert_eq!(
+            TranscodeJob::resolve_output_path(&input, output.clone(), OverwritePolicy::Skip),
+            OutputResolution::Use(output.clone())
+        );
+
+        fs::write(&output, b"").unwrap();
+        assert_eq!(
+            TranscodeJob::resolve_output_path(&input, output.clone(), OverwritePolicy::Overwrite),
+            OutputResolution::Overwrite(output.clone())
+        );
+        assert_eq!(
+            TranscodeJob::resolve_output_path(&input, output.clone(), OverwritePolicy::Skip),
+            OutputResolution::Skip
+        );
+        assert_eq!(
+            TranscodeJob::resolve_output_path(&input, output.clone(), OverwritePolicy::Rename),
+            OutputResolution::Renamed(dir.join("input_transcoded_1.mp4"))
+        );
+
+        // -y を渡すのは上書きのときだけ
+        for policy in OverwritePolicy::all() {
+            let settings = TranscodeSettings {
+                overwrite_policy: *policy,
+                ..Default::default()
+            };
+            let args = TranscodeJob::new(input.clone(), output.clone(), settings)
+                .with_encode_plan(ResolvedEncodePlan {
+                    encoder: "libx264".to_string(),
+                    hwaccel: HwAccelType::Software,
+                })
+                .build_ffmpeg_args();
+            let overwrite = *policy == OverwritePolicy::Overwrite;
+            assert_eq!(args.contains(&OsString::from("-y")), overwrite);
+            assert_eq!(args.contains(&OsString::from("-n")), !overwrite);
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_remux_emits_stream_copy_only() {
+        use crate::transcoder::{AudioCodec, ContainerFormat, FrameRate, VideoResolution};
+
+        let mut settings = TranscodeSettings {
+            video_codec: VideoCodec::Copy,
+            audio_codec: AudioCodec::Copy,
+            hwaccel: HwAccelType::Nvenc,
+            // コピー時は映像の設定を無視する
+            resolution: VideoResolution::Hd720,
+            frame_rate: FrameRate::Fps30,
+            ..Default::default()
+        };
+        assert!(settings.is_remux());
+
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        );
+        let args = job.build_ffmpeg_args();
+        assert_eq!(arg_value(&args, "-c:v"), Some("copy"));
+        assert_eq!(arg_value(&args, "-c:a"), Some("copy"));
+        for flag in ["-hwaccel", "-vf", "-crf", "-preset", "-b:a"] {
+            assert_eq!(arg_value(&args, flag), None, "{} should not be set", flag);
+        }
+
+        // VP9はMP4にコピーできないがMKVなら可能
+        assert!(settings
+            .stream_copy_issue(Some("h264"), Some("aac"))
+            .is_none());
+        assert!(settings
+            .stream_copy_issue(Some("vp9"), Some("opus"))
+            .is_some());
+        settings.container = ContainerFormat::Mkv;
+        assert!(settings
+            .stream_copy_issue(Some("vp9"), Some("opus"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_audio_downmix_and_sample_rate() {
+        use crate::transcoder::{AudioChannels, AudioCodec};
+
+        let mut settings = TranscodeSettings {
+            audio_channels: AudioChannels::Stereo,
+            audio_sample_rate: Some(48_000),
+            ..Default::default()
+        };
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        );
+        let args = job.build_ffmpeg_args();
+        assert_eq!(arg_value(&args, "-c:a"), Some("aac"));
+        assert_eq!(arg_value(&args, "-ac"), Some("2"));
+        assert_eq!(arg_value(&args, "-ar"), Some("48000"));
+
+        // MP3は5.1chを出力できない
+        settings.audio_codec = AudioCodec::Mp3;
+        settings.audio_channels = AudioChannels::Surround51;
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        );
+        assert_eq!(arg_value(&job.build_ffmpeg_args(), "-ac"), None);
+
+        // コピー時は指定しない
+        settings.audio_codec = AudioCodec::Copy;
+        let job = TranscodeJob::new(PathBuf::from("in.mkv"), PathBuf::from("out.mp4"), settings);
+        let args = job.build_ffmpeg_args();
+        assert_eq!(arg_value(&args, "-ac"), None);
+        assert_eq!(arg_value(&args, "-ar"), None);
+    }
+
+    #[test]
+    fn test_segment_args() {
+        let job = TranscodeJob::new(
+            PathBuf::from("/videos/input.mkv"),
+            PathBuf::from("/tmp/input_sample.mp4"),
+            TranscodeSettings::default(),
+        );
+        let args = job.build_ffmpeg_args();
+        assert_eq!(arg_value(&args, "-ss"), None);
+        assert_eq!(arg_value(&args, "-t"), None);
+
+        let args = job.with_segment(1785.0, 30.0).build_ffmpeg_args();
+        assert_eq!(arg_value(&args, "-ss"), Some("1785.000"));
+        assert_eq!(arg_value(&args, "-t"), Some("30.000"));
+        // -ss は入力側、-t は出力側
+        let position = |flag: &str| args.iter().position(|a| a == flag).unwrap();
+        assert!(position("-ss") < position("-i"));
+        assert!(position("-t") > position("-i"));
+    }
+
+    #[test]
+    fn test_windows_long_path() {
+        // 短いパスはそのまま
+        assert_eq!(windows_long_path(r"C:\Videos\テスト 🎬 (final).mkv"), None);
+
+        let long_dir = "深い階層のフォルダ ".repeat(30);
+        let drive = format!(r"C:\Videos\{}\in.mkv", long_dir);
+        assert_eq!(
+            windows_long_path(&drive),
+            Some(format!(r"\\?\C:\Videos\{}\in.mkv", long_dir))
+        );
+        // 区切り文字は \ にそろえる
+        let slashes = format!("C:/Videos/{}/in.mkv", long_dir);
+        assert_eq!(windows_long_path(&slashes), windows_long_path(&drive));
+        let unc = format!(r"\\nas\share\{}\in.mkv", long_dir);
+        assert_eq!(
+            windows_long_path(&unc),
+            Some(format!(r"\\?\UNC\nas\share\{}\in.mkv", long_dir))
+        );
+        // 付与済みのパスと相対パスは変更しない
+        assert_eq!(windows_long_path(&format!(r"\\?\{}", drive)), None);
+        assert_eq!(windows_long_path(&format!(r"{}\in.mkv", long_dir)), None);
+    }
+
+    #[test]
+    fn test_unicode_path_transcodes_with_ffmpeg() {
+        use crate::transcoder::{AudioCodec, ContainerFormat};
+
+        // このテストは実際のFFmpegがインストールされている環境でのみ実行する
+        let Ok(info) = crate::ffmpeg::FfmpegDetector::detect() else {
+            return;
+        };
+
+        // 空白・日本語・絵文字・括弧を含む深いフォルダ（WindowsではMAX_PATHを超える）
+        let root = temp_dir("unicode_path");
+        let dir = (0..16).fold(root.clone(), |dir, i| {
+            dir.join(format!("フォルダ {} 🎬 (深い階層)", i))
+        });
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("テスト 🎬 (final).mkv");
+        let status = std::process::Command::new(&info.ffmpeg_path)
+            .args(["-v", "error", "-f", "lavfi", "-i"])
+            .arg("testsrc=duration=1:size=64x64:rate=10")
+            .args(["-c:v", "mpeg4", "-y"])
+            .arg(&input)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let settings = TranscodeSettings {
+            container: ContainerFormat::Mkv,
+            video_codec: VideoCodec::Copy,
+            audio_codec: AudioCodec::Copy,
+            ..Default::default()
+        };
+        let output = dir.join("テスト 🎬 (final)_transcoded.mkv");
+        let job = TranscodeJob::new(input, output.clone(), settings);
+        let result = std::process::Command::new(&info.ffmpeg_path)
+            .args(job.build_ffmpeg_args())
+            .output()
+            .unwrap();
+        assert!(
+            result.status.success(),
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        assert!(fs::metadata(&output).unwrap().len() > 0);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_smart_stream_copy() {
+        use crate::transcoder::{AudioChannels, AudioCodec, VideoResolution};
+
+        let metadata = VideoMetadata {
+            video_codec: Some("h264".to_string()),
+            audio_codec: Some("aac".to_string()),
+            source_audio_bitrate: Some(200_000),
+            source_audio_channels: Some(2),
+            ..Default::default()
+        };
+        let build = |settings: &TranscodeSettings| {
+            TranscodeJob::new(
+                PathBuf::from("in.mkv"),
+                PathBuf::from("out.mp4"),
+                settings.clone(),
+            )
+            .with_metadata(metadata.clone())
+            .with_encode_plan(ResolvedEncodePlan {
+                encoder: settings
+                    .video_codec
+                    .encoder_name(&settings.hwaccel)
+                    .to_string(),
+                hwaccel: settings.hwaccel,
+            })
+            .build_ffmpeg_args()
+        };
+
+        // 同じコーデックでビットレートの差が10%以内なら音声はコピー
+        let mut settings = TranscodeSettings {
+            hwaccel: HwAccelType::Software,
+            ..Default::default()
+        };
+        let args = build(&settings);
+        assert_eq!(arg_value(&args, "-c:a"), Some("copy"));
+        assert_eq!(arg_value(&args, "-b:a"), None);
+        // 映像のコピーは既定では無効
+        assert_eq!(arg_value(&args, "-c:v"), Some("libx264"));
+
+        // ビットレート・チャンネル数が変わる場合は再エンコード
+        settings.audio_bitrate = 128;
+        assert_eq!(arg_value(&build(&settings), "-c:a"), Some("aac"));
+        settings.audio_bitrate = 192;
+        settings.audio_channels = AudioChannels::Mono;
+        assert_eq!(arg_value(&build(&settings), "-c:a"), Some("aac"));
+        settings.audio_channels = AudioChannels::Stereo;
+        assert_eq!(arg_value(&build(&settings), "-c:a"), Some("copy"));
+        settings.audio_codec = AudioCodec::Mp3;
+        assert_eq!(arg_value(&build(&settings), "-c:a"), Some("libmp3lame"));
+        settings.audio_codec = AudioCodec::Aac;
+        settings.smart_audio_copy = false;
+        assert_eq!(arg_value(&build(&settings), "-c:a"), Some("aac"));
+
+        // 映像は同じコーデックでフィルターがなければコピー
+        settings.smart_video_copy = true;
+        let args = build(&settings);
+        assert_eq!(arg_value(&args, "-c:v"), Some("copy"));
+        assert_eq!(arg_value(&args, "-crf"), None);
+        // サイズ予測の補正・履歴には実際に使ったストリームコピーを渡す
+        let job = TranscodeJob::new(
+            PathBuf::from("in.mkv"),
+            PathBuf::from("out.mp4"),
+            settings.clone(),
+        )
+        .with_metadata(metadata.clone())
+        .with_encode_plan(ResolvedEncodePlan {
+            encoder: "libx264".to_string(),
+            hwaccel: HwAccelType::Software,
+        });
+        assert!(job.actual_encode_plan(None).is_stream_copy());
+        settings.resolution = VideoResolution::Hd720;
+        assert_eq!(arg_value(&build(&settings), "-c:v"), Some("libx264"));
+        settings.resolution = VideoResolution::Original;
+        settings.video_codec = VideoCodec::H265;
+        assert_eq!(arg_value(&build(&settings), "-c:v"), Some("libx265"));
+    }
+
+    #[test]
+    fn test_metadata_and_chapter_mapping() {
+        use crate::transcoder::ContainerFormat;
+
+        let build = |container: ContainerFormat, preserve: bool| {
+            let settings = TranscodeSettings {
+                container,
+                preserve_metadata: preserve,
+                preserve_chapters: preserve,
+                ..Default::default()
+            };
+            let job = TranscodeJob::new(
+                PathBuf::from("/videos/in.mkv"),
+                PathBuf::from(format!("/videos/out.{}", container.extension())),
+                settings,
+            )
+            .with_encode_plan(ResolvedEncodePlan {
+                encoder: "libx264".to_string(),
+                hwaccel: HwAccelType::Software,
+            });
+            job.build_ffmpeg_args()
+        };
+
+        // MP4でもMKVでも既定では引き継ぐ
+        for container in [ContainerFormat::Mkv, ContainerFormat::Mp4] {
+            let args = build(container, true);
+            assert_eq!(arg_value(&args, "-map_metadata"), Some("0"));
+            assert_eq!(arg_value(&args, "-map_chapters"), Some("0"));
+        }
+
+        let args = build(ContainerFormat::Mkv, false);
+        assert_eq!(arg_value(&args, "-map_metadata"), Some("-1"));
+        assert_eq!(arg_value(&args, "-map_chapters"), Some("-1"));
+    }
+
+    #[test]
+    fn test_encode_plan_skips_encoder_detection() {
+        let mut settings = TranscodeSettings {
+            video_codec: VideoCodec::H265,
+            hwaccel: HwAccelType::Nvenc,
+            ..Default::default()
+        };
+        let plan = ResolvedEncodePlan {
+            encoder: "libx265".to_string(),
+            hwaccel: HwAccelType::Software,
+        };
+        let job = TranscodeJob::new(
+            PathBuf::from("/videos/in.mp4"),
+            PathBuf::from("/videos/out.mp4"),
+            settings.clone(),
+        )
+        .with_encode_plan(plan.clone());
+
+        // 存在しないFFmpegを指定しても解決済みのエンコーダーがそのまま使われる
+        let args = job.build_ffmpeg_args_with_path(Some(&PathBuf::from("/nonexistent/ffmpeg")));
+        assert_eq!(arg_value(&args, "-c:v"), Some("libx265"));
+        assert_eq!(arg_value(&args, "-hwaccel"), None);
+
+        // エンコーダー起因でないエラーやストリームコピーでは再試行しない
+        assert_eq!(
+            plan.fallback_for(&settings, &FfmpegErrorKind::DiskFull, None),
+            None
+        );
+        settings.video_codec = VideoCodec::Copy;
+        assert_eq!(
+            plan.fallback_for(
+                &settings,
+                &FfmpegErrorKind::EncoderNotSupported("copy".to_string()),
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hw_decode_mode_controls_hwaccel_flag() {
+        let vp9_10bit = VideoMetadata {
+            video_codec: Some("vp9".to_string()),
+            video_bit_depth: Some(10),
+            ..Default::default()
+        };
+        let hwaccel_args = |mode: HwDecodeMode, metadata: &VideoMetadata| {
+            let settings = TranscodeSettings {
+                hw_decode: mode,
+                ..Default::default()
+            };
+            let job =
+                TranscodeJob::new(PathBuf::from("in.webm"), PathBuf::from("out.mp4"), settings)
+                    .with_metadata(metadata.clone());
+            let mut args = Vec::new();
+            job.add_hwaccel_args(&mut args, "h264_nvenc", &HwAccelType::Nvenc);
+            args
+        };
+
+        // 10bit VP9はNVDECで失敗する場合があるため自動ではソフトウェアデコード
+        assert!(hwaccel_args(HwDecodeMode::Auto, &vp9_10bit).is_empty());
+        assert_eq!(
+            hwaccel_args(HwDecodeMode::ForceOn, &vp9_10bit),
+            vec!["-hwaccel", "cuda"]
+        );
+
+        let h264 = VideoMetadata {
+            video_codec: Some("h264".to_string()),
+            video_bit_depth: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(
+            hwaccel_args(HwDecodeMode::Auto, &h264),
+            vec!["-hwaccel", "cuda"]
+        );
+        assert!(hwaccel_args(HwDecodeMode::Off, &h264).is_empty());
+    }
+
+    #[test]
+    fn test_gpu_scale_pipeline_matrix() {
+        use crate::transcoder::{Deinterlace, FrameRate, VideoResolution};
+
+        let source = VideoMetadata {
+            video_codec: Some("h264".to_string()),
+            video_bit_depth: Some(8),
+            resolution: Some((3840, 2160)),
+            ..Default::default()
+        };
+        // (入力オプション, -vf)
+        let build = |settings: TranscodeSettings, encoder: &str, hwaccel: HwAccelType| {
+            let job =
+                TranscodeJob::new(PathBuf::from("in.mp4"), PathBuf::from("out.mp4"), settings)
+                    .with_metadata(source.clone());
+            let mut input_args = Vec::new();
+            job.add_hwaccel_args(&mut input_args, encoder, &hwaccel);
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, encoder, &hwaccel);
+            (input_args, arg_value(&args, "-vf").map(str::to_string))
+        };
+        let resized = |hwaccel: HwAccelType| TranscodeSettings {
+            hwaccel,
+            resolution: VideoResolution::Fhd1080,
+            ..Default::default()
+        };
+
+        // NVENC + リサイズ: デコードからエンコードまでGPU上
+        let (input, vf) = build(
+            resized(HwAccelType::Nvenc),
+            "h264_nvenc",
+            HwAccelType::Nvenc,
+        );
+        assert_eq!(arg_value(&input, "-hwaccel_output_format"), Some("cuda"));
+        assert_eq!(vf.as_deref(), Some("scale_cuda=1920:1080"));
+
+        // QSV + リサイズ
+        let (input, vf) = build(resized(HwAccelType::Qsv), "hevc_qsv", HwAccelType::Qsv);
+        assert_eq!(arg_value(&input, "-hwaccel_output_format"), Some("qsv"));
+        assert_eq!(vf.as_deref(), Some("scale_qsv=w=1920:h=1080"));
+
+        // リサイズなし: フィルタなし、GPUメモリ指定もしない
+        let mut settings = resized(HwAccelType::Nvenc);
+        settings.resolution = VideoResolution::Original;
+        let (input, vf) = build(settings, "h264_nvenc", HwAccelType::Nvenc);
+        assert_eq!(arg_value(&input, "-hwaccel"), Some("cuda"));
+        assert_eq!(arg_value(&input, "-hwaccel_output_format"), None);
+        assert_eq!(vf, None);
+
+        // CPUのみのフィルタがあればソフトウェアのスケールに戻す
+        let mut with_fps = resized(HwAccelType::Nvenc);
+        with_fps.frame_rate = FrameRate::Fps30;
+        let mut with_crop = resized(HwAccelType::Qsv);
+        with_crop.crop.top = 8;
+        let mut with_deinterlace = resized(HwAccelType::Nvenc);
+        with_deinterlace.deinterlace = Deinterlace::Bwdif;
+        for (settings, encoder, hwaccel) in [
+            (with_fps, "h264_nvenc", HwAccelType::Nvenc),
+            (with_crop, "h264_qsv", HwAccelType::Qsv),
+            (with_deinterlace, "hevc_nvenc", HwAccelType::Nvenc),
+        ] {
+            let (input, vf) = build(settings, encoder, hwaccel);
+            assert_eq!(arg_value(&input, "-hwaccel_output_format"), None);
+            assert!(vf.is_some_and(|vf| vf.ends_with(&fit_scale(1920, 1080))));
+        }
+
+        // ソフトウェアエンコーダーにフォールバックした場合もソフトウェアのスケール
+        let (input, vf) = build(
+            resized(HwAccelType::Software),
+            "libx264",
+            HwAccelType::Software,
+        );
+        assert!(input.is_empty());
+        assert_eq!(vf.as_deref(), Some(fit_scale(1920, 1080).as_str()));
+    }
+
+    #[test]
+    fn test_scale_preserves_aspect_ratio() {
+        use crate::transcoder::VideoResolution;
+
+        // プリセットは枠に収めるだけで余白を付けない
+        assert_eq!(
+            VideoResolution::Hd720.scale_filter().as_deref(),
+            Some(fit_scale(1280, 720).as_str())
+        );
+        // カスタムは余白で指定サイズに合わせる（奇数は偶数に切り捨て）
+        assert_eq!(
+            VideoResolution::Custom(1081, 1921)
+                .scale_filter()
+                .as_deref(),
+            Some(
+                "scale=1080:1920:force_original_aspect_ratio=decrease:force_divisible_by=2,\
+                 pad=1080:1920:(ow-iw)/2:(oh-ih)/2"
+            )
+        );
+        // 片方だけ指定した場合はもう一方を比率から決める
+        assert_eq!(
+            VideoResolution::Custom(0, 720).scale_filter().as_deref(),
+            Some("scale=-2:720")
+        );
+        assert_eq!(
+            VideoResolution::Custom(1279, 0).scale_filter().as_deref(),
+            Some("scale=1278:-2")
+        );
+        assert_eq!(VideoResolution::Original.scale_filter(), None);
+        assert_eq!(VideoResolution::Custom(0, 0).scale_filter(), None);
+
+        // 横長: 枠いっぱい
+        let hd = VideoResolution::Hd720;
+        assert_eq!(hd.fit_dimensions((1920, 1080)), Some((1280, 720)));
+        // シネスコ: 幅で制限
+        assert_eq!(hd.fit_dimensions((1920, 800)), Some((1280, 532)));
+        // 縦長: 高さ720のまま潰れない
+        assert_eq!(hd.fit_dimensions((1080, 1920)), Some((404, 720)));
+        // 奇数の元解像度でも偶数になる
+        assert_eq!(hd.fit_dimensions((721, 481)), Some((1078, 720)));
+        assert_eq!(
+            VideoResolution::Custom(0, 720).fit_dimensions((1080, 1920)),
+            Some((404, 720))
+        );
+        assert_eq!(hd.fit_dimensions((0, 0)), None);
+
+        // カスタムの出力サイズは余白を含む
+        let custom = VideoResolution::Custom(1280, 720);
+        assert_eq!(custom.fit_dimensions((1080, 1920)), Some((404, 720)));
+        assert_eq!(custom.output_dimensions((1080, 1920)), Some((1280, 720)));
+    }
+
+    #[test]
+    fn test_gpu_scale_keeps_portrait_aspect() {
+        use crate::transcoder::VideoResolution;
+
+        let mut settings = TranscodeSettings {
+            hwaccel: HwAccelType::Nvenc,
+            resolution: VideoResolution::Hd720,
+            ..Default::default()
+        };
+        let portrait = VideoMetadata {
+            video_codec: Some("h264".to_string()),
+            video_bit_depth: Some(8),
+            resolution: Some((1080, 1920)),
+            ..Default::default()
+        };
+        let vf = |settings: &TranscodeSettings, metadata: &VideoMetadata| {
+            let job = TranscodeJob::new(
+                PathBuf::from("in.mp4"),
+                PathBuf::from("out.mp4"),
+                settings.clone(),
+            )
+            .with_metadata(metadata.clone());
+            let mut args = Vec::new();
+            job.add_video_args_with_encoder(&mut args, "h264_nvenc", &HwAccelType::Nvenc);
+            arg_value(&args, "-vf").map(str::to_string)
+        };
+        assert_eq!(
+            vf(&settings, &portrait).as_deref(),
+            Some("scale_cuda=404:720")
+        );
+
+        // 余白が必要なカスタム解像度はソフトウェアのスケールとpadを使う
+        settings.resolution = VideoResolution::Custom(1280, 720);
+        assert!(vf(&settings, &portrait)
+            .is_some_and(|vf| vf.ends_with(",pad=1280:720:(ow-iw)/2:(oh-ih)/2")));
+
+        // 元の解像度が不明ならGPUで拡大縮小しない
+        settings.resolution = VideoResolution::Hd720;
+        let unknown = VideoMetadata {
+            resolution: None,
+            ..portrait
+        };
+        assert_eq!(
+            vf(&settings, &unknown).as_deref(),
+            Some(fit_scale(1280, 720).as_str())
+        );
+    }
+
+    #[test]
+    fn test_factory_presets_produce_sane_args() {
+        use crate::transcoder::preset::WEB_SIZE_LIMIT_BYTES;
+
+        let build = |settings: TranscodeSettings| {
+            TranscodeJob::new(PathBuf::from("in.mkv"), PathBuf::from("out"), settings)
+                .build_ffmpeg_args()
+        };
+
+        let args = build(TranscodeSettings::preset_fast_1080p());
+        assert_eq!(
+            arg_value(&args, "-vf"),
+            Some(fit_scale(1920, 1080).as_str())
+        );
+        assert_eq!(arg_value(&args, "-b:a"), Some("160k"));
+
+        // ソフトウェアエンコーダーのため-hwaccelは付かない
+        let args = build(TranscodeSettings::preset_hq_archive_av1());
+        assert!(arg_value(&args, "-c:v").is_some_and(|e| e.contains("av1")));
+        assert_eq!(arg_value(&args, "-crf"), Some("24"));
+        assert_eq!(arg_value(&args, "-c:a"), Some("copy"));
+        assert_eq!(arg_value(&args, "-hwaccel"), None);
+
+        let args = build(TranscodeSettings::preset_anime());
+        assert_eq!(arg_value(&args, "-crf"), Some("20"));
+        assert_eq!(arg_value(&args, "-tune"), Some("animation"));
+
+        // 2分の動画で映像+音声が8MBを超えない
+        let settings = TranscodeSettings::preset_web_8mb(Some(120.0));
+        let total_kbps = (settings.target_bitrate + settings.audio_bitrate) as f64;
+        assert!(total_kbps * 1000.0 / 8.0 * 120.0 <= WEB_SIZE_LIMIT_BYTES as f64);
+        let args = build(settings.clone());
+        assert_eq!(
+            arg_value(&args, "-b:v"),
+            Some(format!("{}k", settings.target_bitrate).as_str())
+        );
+        assert_eq!(arg_value(&args, "-vf"), Some(fit_scale(1280, 720).as_str()));
+    }
+
+    #[test]
+    fn test_command_line_quoting() {
+        assert_eq!(quote_arg_posix("libx264"), "libx264");
+        assert_eq!(quote_arg_posix("/videos/a.mp4"), "/videos/a.mp4");
+        assert_eq!(
+            quote_arg_posix("/videos/my movie.mp4"),
+            "'/videos/my movie.mp4'"
+        );
+        assert_eq!(quote_arg_posix("it's.mp4"), "'it'\\''s.mp4'");
+        assert_eq!(quote_arg_posix("0|-1"), "'0|-1'");
+        assert_eq!(quote_arg_posix(""), "''");
+
+        assert_eq!(quote_arg_windows("libx264"), "libx264");
+        assert_eq!(
+            quote_arg_windows("C:\\My Videos\\a.mp4"),
+            "\"C:\\My Videos\\a.mp4\""
+        );
+        assert_eq!(quote_arg_windows("a\"b"), "\"a\\\"b\"");
+
+        let job = TranscodeJob::new(
+            PathBuf::from("/videos/my movie.mkv"),
+            PathBuf::from("/out/my movie.mp4"),
+            TranscodeSettings::default(),
+        )
+        .with_encode_plan(ResolvedEncodePlan {
+            encoder: "libx264".to_string(),
+            hwaccel: HwAccelType::Software,
+        });
+        let command = job.command_line(Path::new("/usr/bin/ffmpeg"));
+        assert!(command.starts_with("/usr/bin/ffmpeg "));
+        if !cfg!(windows) {
+            assert!(command.contains(" -i '/videos/my movie.mkv' "));
+            assert!(command.ends_with(" '/out/my movie.mp4'"));
+        }
+        assert!(command.contains(" libx264 "));
     }
 }
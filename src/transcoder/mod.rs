@@ -1,20 +1,30 @@
 //! トランスコーダーモジュール
 
+mod disk_space;
 mod error;
 mod hwaccel;
 mod job;
 mod preset;
 pub mod progress;
+mod quality;
+mod validate;
+mod verify;
 
+pub use disk_space::{check_disk_space, check_file_space, DiskSpaceShortage};
 pub use error::{FfmpegError, FfmpegErrorKind};
-pub use hwaccel::{HwAccelDetector, HwAccelType};
-pub use job::TranscodeJob;
+pub use hwaccel::{HwAccelDetector, HwAccelType, HwDecodeMode};
+pub use job::{is_same_path, ResolvedEncodePlan, TranscodeJob};
 pub use preset::{
-    AmfQuality, AmfUsage, AqMode, AudioCodec, ContainerFormat, NvencBRefMode, NvencMultipass,
-    NvencTune, RateControlMode, TranscodeSettings, VideoCodec, VideoPreset, VideoResolution,
+    audio_bitrate_options, crf_from_encoder_crf, encoder_crf, encoder_crf_max, parse_max_cll,
+    AmfUsage, AqMode, AudioChannels, AudioCodec, ContainerFormat, CropMode, CropSettings,
+    Deinterlace, FpsFilterMode, FrameRate, HdrToneMappingMode, LibaomUsage, NvencBRefMode,
+    NvencMultipass, NvencTune, OverwritePolicy, PixelFormat, RateControlMode, SvtAv1PredStructure,
+    SvtAv1Tune, TranscodeSettings, VideoCodec, VideoPreset, VideoResolution, Vp9Deadline,
     X264Profile, X264Tune,
 };
 pub use progress::{
-    estimate_compression_ratio, estimate_compression_ratio_advanced, format_duration, format_size,
-    ContentType, FfmpegProgressInfo, TranscodeProgress, VideoMetadata,
+    estimate_compression_ratio, estimate_compression_ratio_advanced, format_bitrate,
+    format_duration, format_size, ContentType, FfmpegProgressInfo, SpeedEstimator, VideoMetadata,
 };
+pub use quality::{analyze_quality, QualityScore};
+pub use verify::verify_output;
@@ -2,10 +2,12 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::HwAccelType;
+use super::{HwAccelType, HwDecodeMode};
 
 /// トランスコード設定
+/// プリセットとして保存されるため、項目が増えても読み込めるよう既定値で補う
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TranscodeSettings {
     /// 出力コンテナ形式
     pub container: ContainerFormat,
@@ -13,20 +15,53 @@ pub struct TranscodeSettings {
     pub video_codec: VideoCodec,
     /// 解像度
     pub resolution: VideoResolution,
+    /// フレームレート
+    pub frame_rate: FrameRate,
+    /// フレームレート変換の方式
+    pub fps_filter_mode: FpsFilterMode,
+    /// クロップ方式
+    pub crop_mode: CropMode,
+    /// 手動クロップ（ファイルごとの自動検出結果がない場合に使用）
+    pub crop: CropSettings,
+    /// インターレース解除
+    pub deinterlace: Deinterlace,
+    /// 出力ピクセルフォーマット
+    pub pixel_format: PixelFormat,
+    /// HDRソースをSDRに変換する
+    pub hdr_to_sdr: bool,
+    /// HDR→SDR変換のトーンマッピング方式
+    pub tone_mapping_mode: HdrToneMappingMode,
     /// CRF値（品質）
     pub crf: u8,
     /// エンコードプリセット
     pub preset: VideoPreset,
     /// HWアクセラレーション
     pub hwaccel: HwAccelType,
+    /// HWデコード
+    pub hw_decode: HwDecodeMode,
     /// オーディオコーデック
     pub audio_codec: AudioCodec,
     /// オーディオビットレート (kbps)
     pub audio_bitrate: u32,
+    /// オーディオのチャンネル数（ダウンミックス）
+    pub audio_channels: AudioChannels,
+    /// オーディオのサンプルレート (Hz、Noneなら元のまま)
+    pub audio_sample_rate: Option<u32>,
     /// 出力ディレクトリ
     pub output_dir: Option<std::path::PathBuf>,
     /// 出力ファイル名サフィックス
     pub output_suffix: String,
+    /// 出力ファイルが既に存在する場合の動作
+    pub overwrite_policy: OverwritePolicy,
+    /// タイトルなどのメタデータを引き継ぐ
+    pub preserve_metadata: bool,
+    /// チャプターを引き継ぐ
+    pub preserve_chapters: bool,
+    /// 元の音声が変換先と同じコーデック・ビットレートならコピーする
+    pub smart_audio_copy: bool,
+    /// 元の映像が変換先と同じコーデックで、解像度・フィルターを変更しないならコピーする
+    /// 同じコーデックで圧縮し直す用途もあるため既定では無効
+    pub smart_video_copy: bool,
 
     // === エンコーダー固有設定 ===
     /// レートコントロールモード
@@ -35,6 +70,8 @@ pub struct TranscodeSettings {
     pub target_bitrate: u32,
     /// 最大ビットレート (kbps) - VBRモード用
     pub max_bitrate: u32,
+    /// 目標サイズ (MB) - 目標サイズモード用（ファイルごとにCRFを算出）
+    pub target_size_mb: u32,
     /// Bフレーム数
     pub bframes: u8,
     /// 参照フレーム数
@@ -55,6 +92,14 @@ pub struct TranscodeSettings {
     pub nvenc_multipass: NvencMultipass,
     /// NVENC B参照モード
     pub nvenc_b_ref_mode: NvencBRefMode,
+    /// NVENC空間AQ
+    pub nvenc_spatial_aq: bool,
+    /// NVENC時間AQ
+    pub nvenc_temporal_aq: bool,
+    /// NVENC空間AQ強度 (1-15)
+    pub nvenc_aq_strength: u8,
+    /// NVENCルックアヘッドフレーム数 (0-32、0で無効)
+    pub nvenc_rc_lookahead: u8,
 
     // === QSV固有設定 ===
     /// QSVルックアヘッド深度
@@ -63,18 +108,38 @@ pub struct TranscodeSettings {
     pub qsv_adaptive_i: bool,
     /// QSVアダプティブB
     pub qsv_adaptive_b: bool,
+    /// QSV外部ビットレート制御（新しいドライバーのみ）
+    pub qsv_extbrc: bool,
+    /// QSVの非同期処理の深さ
+    pub qsv_async_depth: u8,
+    /// QSVピクチャタイミングSEIを出力（H.264/HEVCのみ）
+    pub qsv_pic_timing_sei: bool,
+    /// QSVリカバリーポイントSEIを出力（H.264/HEVCのみ）
+    pub qsv_recovery_point_sei: bool,
 
     // === AMF固有設定 ===
     /// AMF使用法（品質 vs 速度）
     pub amf_usage: AmfUsage,
     /// AMF品質プリセット
     pub amf_quality: AmfQuality,
+    /// AMFプリアナリシス
+    pub amf_pre_analysis: bool,
+    /// AMFプリエンコードによるレートコントロール
+    pub amf_rate_control_preanalysis: bool,
+    /// AMF VBAQ（分散ベースの適応量子化、H.264/HEVCのみ）
+    pub amf_vbaq: bool,
+    /// AMF BフレームのQP差分 (-10〜10、H.264のみ)
+    pub amf_b_frame_delta_qp: i8,
 
     // === libx264/libx265固有設定 ===
     /// チューニング設定
     pub x264_tune: X264Tune,
     /// プロファイル
     pub x264_profile: X264Profile,
+    /// x265のHDR10マスタリングディスプレイ（Noneなら元の動画から）
+    pub x265_master_display: Option<String>,
+    /// x265のHDR10コンテンツ輝度（MaxCLL, MaxFALL、Noneなら元の動画から）
+    pub x265_max_cll: Option<(u32, u32)>,
 
     // === VP9固有設定 ===
     /// タイル列数
@@ -87,12 +152,36 @@ pub struct TranscodeSettings {
     pub vp9_auto_alt_ref: bool,
     /// ラグインフレーム数
     pub vp9_lag_in_frames: u8,
+    /// エンコード速度の基準（-deadline）
+    pub vp9_deadline: Vp9Deadline,
+    /// -cpu-used（Noneなら速度プリセットから決める）
+    pub vp9_cpu_used: Option<i8>,
+    /// -static-thresh 0 を指定（静止部分も省略せずに符号化）
+    pub vp9_static_thresh_zero: bool,
 
     // === AV1固有設定 ===
     /// SVT-AV1フィルムグレイン
     pub svtav1_film_grain: u8,
     /// SVT-AV1フィルムグレイン合成
     pub svtav1_film_grain_denoise: bool,
+    /// SVT-AV1チューニング
+    pub svtav1_tune: SvtAv1Tune,
+    /// SVT-AV1デブロッキングループフィルター
+    pub svtav1_enable_dlf: bool,
+    /// SVT-AV1予測構造
+    pub svtav1_pred_structure: SvtAv1PredStructure,
+    /// SVT-AV1階層レベル（2-5、0は自動）
+    pub svtav1_hierarchical_levels: u8,
+    /// libaom-av1の用途
+    pub libaom_usage: LibaomUsage,
+    /// libaom-av1のラグインフレーム数（0-35）
+    pub libaom_lag_in_frames: u8,
+    /// libaom-av1の自動ALTフレーム
+    pub libaom_auto_alt_ref: bool,
+    /// libaom-av1のARNRフィルターの最大フレーム数
+    pub libaom_arnr_maxframes: u8,
+    /// libaom-av1のARNRフィルターの強度（0-6）
+    pub libaom_arnr_strength: u8,
     /// AV1タイル設定
     pub av1_tile_columns: u8,
     /// AV1タイル行数
@@ -105,17 +194,34 @@ impl Default for TranscodeSettings {
             container: ContainerFormat::Mp4,
             video_codec: VideoCodec::H264,
             resolution: VideoResolution::Original,
+            frame_rate: FrameRate::Original,
+            fps_filter_mode: FpsFilterMode::Simple,
+            crop_mode: CropMode::Manual,
+            crop: CropSettings::default(),
+            deinterlace: Deinterlace::Auto,
+            pixel_format: PixelFormat::Auto,
+            hdr_to_sdr: false,
+            tone_mapping_mode: HdrToneMappingMode::Hable,
             crf: 23,
             preset: VideoPreset::Medium,
             hwaccel: HwAccelType::Auto,
+            hw_decode: HwDecodeMode::Auto,
             audio_codec: AudioCodec::Aac,
             audio_bitrate: 192,
+            audio_channels: AudioChannels::Original,
+            audio_sample_rate: None,
             output_dir: None,
             output_suffix: "_transcoded".to_string(),
+            overwrite_policy: OverwritePolicy::Rename,
+            preserve_metadata: true,
+            preserve_chapters: true,
+            smart_audio_copy: true,
+            smart_video_copy: false,
 
             // エンコーダー固有設定のデフォルト
             rate_control: RateControlMode::Crf,
             target_bitrate: 5000,
+            target_size_mb: 2048,
             max_bitrate: 10000,
             bframes: 3,
             ref_frames: 4,
@@ -128,19 +234,33 @@ impl Default for TranscodeSettings {
             nvenc_tune: NvencTune::HighQuality,
             nvenc_multipass: NvencMultipass::Qres,
             nvenc_b_ref_mode: NvencBRefMode::Each,
+            nvenc_spatial_aq: true,
+            nvenc_temporal_aq: false,
+            nvenc_aq_strength: 8,
+            nvenc_rc_lookahead: 20,
 
             // QSV
             qsv_la_depth: 40,
             qsv_adaptive_i: true,
             qsv_adaptive_b: true,
+            qsv_extbrc: false,
+            qsv_async_depth: 4,
+            qsv_pic_timing_sei: true,
+            qsv_recovery_point_sei: false,
 
             // AMF
             amf_usage: AmfUsage::Transcoding,
             amf_quality: AmfQuality::Balanced,
+            amf_pre_analysis: true,
+            amf_rate_control_preanalysis: false,
+            amf_vbaq: true,
+            amf_b_frame_delta_qp: -4,
 
             // libx264/libx265
             x264_tune: X264Tune::None,
             x264_profile: X264Profile::High,
+            x265_master_display: None,
+            x265_max_cll: None,
 
             // VP9
             vp9_tile_columns: 2,
@@ -148,16 +268,222 @@ impl Default for TranscodeSettings {
             vp9_frame_parallel: true,
             vp9_auto_alt_ref: true,
             vp9_lag_in_frames: 25,
+            vp9_deadline: Vp9Deadline::default(),
+            vp9_cpu_used: None,
+            vp9_static_thresh_zero: true,
 
             // AV1
             svtav1_film_grain: 0,
             svtav1_film_grain_denoise: false,
+            svtav1_tune: SvtAv1Tune::default(),
+            svtav1_enable_dlf: true,
+            svtav1_pred_structure: SvtAv1PredStructure::default(),
+            svtav1_hierarchical_levels: 0,
+            libaom_usage: LibaomUsage::default(),
+            libaom_lag_in_frames: 35,
+            libaom_auto_alt_ref: true,
+            libaom_arnr_maxframes: 7,
+            libaom_arnr_strength: 5,
             av1_tile_columns: 2,
             av1_tile_rows: 2,
         }
     }
 }
 
+/// Web/Discord向けプリセットのサイズ上限（8MB）
+pub const WEB_SIZE_LIMIT_BYTES: u64 = 8_000_000;
+
+impl TranscodeSettings {
+    /// 高速 1080p: H.264 / 1080p / CRF23 / fast
+    pub fn preset_fast_1080p() -> Self {
+        Self {
+            video_codec: VideoCodec::H264,
+            resolution: VideoResolution::Fhd1080,
+            crf: 23,
+            preset: VideoPreset::Fast,
+            audio_codec: AudioCodec::Aac,
+            audio_bitrate: 160,
+            ..Default::default()
+        }
+    }
+
+    /// 高画質アーカイブ: SVT-AV1 / 元の解像度 / CRF24（x264の19相当） / slow / MKV（音声はそのまま）
+    pub fn preset_hq_archive_av1() -> Self {
+        Self {
+            container: ContainerFormat::Mkv,
+            video_codec: VideoCodec::Av1,
+            crf: 19,
+            preset: VideoPreset::Slow,
+            hwaccel: HwAccelType::Software,
+            audio_codec: AudioCodec::Copy,
+            ..Default::default()
+        }
+    }
+
+    /// アニメ: H.265 / CRF20 / slow / animationチューン / MKV
+    pub fn preset_anime() -> Self {
+        Self {
+            container: ContainerFormat::Mkv,
+            video_codec: VideoCodec::H265,
+            crf: 20,
+            preset: VideoPreset::Slow,
+            hwaccel: HwAccelType::Software,
+            x264_tune: X264Tune::Animation,
+            bframes: 4,
+            audio_codec: AudioCodec::Aac,
+            audio_bitrate: 192,
+            ..Default::default()
+        }
+    }
+
+    /// Web/Discord: H.264 / 720p / MP4 で8MB以内に収まるビットレートを指定
+    /// 長さが分からない場合は2Mbpsとする
+    pub fn preset_web_8mb(duration_secs: Option<f64>) -> Self {
+        const AUDIO_KBPS: u32 = 96;
+        let video_kbps = duration_secs
+            .filter(|d| *d > 0.0)
+            .map(|d| size_capped_video_kbps(WEB_SIZE_LIMIT_BYTES, d, AUDIO_KBPS))
+            .unwrap_or(2000);
+
+        Self {
+            container: ContainerFormat::Mp4,
+            video_codec: VideoCodec::H264,
+            resolution: VideoResolution::Hd720,
+            rate_control: RateControlMode::Vbr,
+            target_bitrate: video_kbps,
+            max_bitrate: video_kbps,
+            audio_codec: AudioCodec::Aac,
+            audio_bitrate: AUDIO_KBPS,
+            ..Default::default()
+        }
+    }
+
+    /// エンコーダーに渡すCRF（設定値はx264の尺度）
+    pub fn effective_crf(&self, encoder: &str) -> u8 {
+        encoder_crf(self.crf, encoder)
+    }
+
+    /// 映像・音声ともにストリームコピー（リムックス）か
+    pub fn is_remux(&self) -> bool {
+        self.video_codec == VideoCodec::Copy && self.audio_codec == AudioCodec::Copy
+    }
+
+    /// 一覧に表示する短い説明（例: "AV1 CRF 35・MKV"）
+    pub fn short_summary(&self) -> String {
+        let video = match (self.video_codec, self.rate_control) {
+            (VideoCodec::Copy, _) => self.video_codec.display_name().to_string(),
+            (codec, RateControlMode::Crf) => format!("{} CRF {}", codec.display_name(), self.crf),
+            (codec, mode) => format!("{} {}", codec.display_name(), mode.display_name()),
+        };
+        format!("{}・{}", video, self.container.display_name())
+    }
+
+    /// ストリームコピー時に出力コンテナへ格納できないソースのコーデックがあれば理由を返す
+    pub fn stream_copy_issue(
+        &self,
+        source_video_codec: Option<&str>,
+        source_audio_codec: Option<&str>,
+    ) -> Option<String> {
+        if self.video_codec == VideoCodec::Copy {
+            if let Some(codec) = source_video_codec {
+                if !self.container.supports_video_codec(codec) {
+                    return Some(format!(
+                        "映像コーデック {} は{}に格納できません",
+                        codec,
+                        self.container.display_name()
+                    ));
+                }
+            }
+        }
+        if self.audio_codec == AudioCodec::Copy {
+            if let Some(codec) = source_audio_codec {
+                if !self.container.supports_audio_codec(codec) {
+                    return Some(format!(
+                        "音声コーデック {} は{}に格納できません",
+                        codec,
+                        self.container.display_name()
+                    ));
+                }
+            }
+        }
+        None
+    }
+
+    /// 出力ピクセルフォーマットがエンコーダーやH.264プロファイルと合わない場合は警告を返す
+    pub fn pixel_format_warning(&self, encoder: &str) -> Option<String> {
+        let pixel_format = self.pixel_format;
+        if !pixel_format.supported_by(encoder) {
+            return Some(format!(
+                "{}は{}を出力できないため、4:2:0 8bitで出力します",
+                encoder,
+                pixel_format.display_name()
+            ));
+        }
+        if encoder == "libx264" && !self.x264_profile.supports(pixel_format) {
+            return Some(format!(
+                "H.264の{}プロファイルは{}に対応していません",
+                self.x264_profile.display_name(),
+                pixel_format.display_name()
+            ));
+        }
+        None
+    }
+}
+
+/// サイズ上限に収まる映像ビットレート（kbps）を計算
+/// コンテナのオーバーヘッドとレート制御の誤差を見込んで5%の余裕を持たせる
+pub fn size_capped_video_kbps(size_limit_bytes: u64, duration_secs: f64, audio_kbps: u32) -> u32 {
+    const MIN_VIDEO_KBPS: f64 = 100.0;
+    let total_kbps = size_limit_bytes as f64 * 8.0 / 1000.0 * 0.95 / duration_secs;
+    (total_kbps - audio_kbps as f64).max(MIN_VIDEO_KBPS) as u32
+}
+
+/// VP9のCRF対応表（x264のCRF, libvpx-vp9のCRF）
+/// 同じ数値でもVP9はx264より高画質・大容量になるため、体感の画質がそろうように換算する
+const VP9_CRF_TABLE: &[(u8, u8)] = &[(0, 0), (18, 24), (23, 31), (28, 37), (32, 42), (51, 63)];
+
+/// AV1のCRF対応表（x264のCRF, libsvtav1/libaom-av1のCRF）
+const AV1_CRF_TABLE: &[(u8, u8)] = &[(0, 0), (18, 23), (23, 30), (28, 36), (32, 41), (51, 63)];
+
+/// x264の尺度のCRFをエンコーダーの尺度に換算（対応表の間は線形補間）
+/// x264/x265とHWエンコーダーはそのまま使う
+pub fn encoder_crf(crf: u8, encoder: &str) -> u8 {
+    let table = match encoder {
+        "libvpx-vp9" => VP9_CRF_TABLE,
+        "libsvtav1" | "libaom-av1" => AV1_CRF_TABLE,
+        _ => return crf,
+    };
+    let crf = crf.min(51);
+    let upper = table
+        .iter()
+        .position(|&(x264, _)| x264 >= crf)
+        .unwrap_or(table.len() - 1);
+    if upper == 0 {
+        return table[0].1;
+    }
+    let ((x0, y0), (x1, y1)) = (table[upper - 1], table[upper]);
+    let t = (crf - x0) as f64 / (x1 - x0) as f64;
+    (y0 as f64 + t * (y1 as f64 - y0 as f64)).round() as u8
+}
+
+/// エンコーダーの尺度のCRFをx264の尺度に戻す（換算後にその値以上になる最小のCRF）
+pub fn crf_from_encoder_crf(value: u8, encoder: &str) -> u8 {
+    (0..=51)
+        .find(|&crf| encoder_crf(crf, encoder) >= value)
+        .unwrap_or(51)
+}
+
+/// エンコーダーの尺度でのCRFの上限（VP9/AV1は63、それ以外は51）
+pub fn encoder_crf_max(encoder: &str) -> u8 {
+    encoder_crf(51, encoder)
+}
+
+/// "MaxCLL,MaxFALL" 形式の文字列をパース（例: "1000,400"）
+pub fn parse_max_cll(value: &str) -> Option<(u32, u32)> {
+    let (cll, fall) = value.split_once(',')?;
+    Some((cll.trim().parse().ok()?, fall.trim().parse().ok()?))
+}
+
 /// レートコントロールモード
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RateControlMode {
@@ -169,6 +495,12 @@ pub enum RateControlMode {
     Vbr,
     /// CQP（固定量子化パラメータ）- HWエンコーダー向け
     Cqp,
+    /// ICQ（Intel品質）- QSV専用
+    IcqQsv,
+    /// AVBR（Intel平均ビットレート）- QSV専用
+    AvbrQsv,
+    /// 目標サイズ（ファイルごとに目標サイズに近づくCRFを算出）
+    TargetSize,
 }
 
 impl RateControlMode {
@@ -179,6 +511,9 @@ impl RateControlMode {
             RateControlMode::Cbr => "CBR (固定レート)",
             RateControlMode::Vbr => "VBR (可変レート)",
             RateControlMode::Cqp => "CQP (固定QP)",
+            RateControlMode::IcqQsv => "ICQ (Intel品質)",
+            RateControlMode::AvbrQsv => "AVBR (Intel平均レート)",
+            RateControlMode::TargetSize => "目標サイズ",
         }
     }
 
@@ -186,11 +521,44 @@ impl RateControlMode {
     pub fn all() -> &'static [RateControlMode] {
         &[
             RateControlMode::Crf,
+            RateControlMode::TargetSize,
             RateControlMode::Cbr,
             RateControlMode::Vbr,
             RateControlMode::Cqp,
+            RateControlMode::IcqQsv,
+            RateControlMode::AvbrQsv,
         ]
     }
+
+    /// QSVでのみ使用できるモードか
+    pub fn is_qsv_only(&self) -> bool {
+        matches!(self, RateControlMode::IcqQsv | RateControlMode::AvbrQsv)
+    }
+
+    /// QSV専用モードを他のエンコーダーで使える同等のモードに置き換える
+    pub fn without_qsv(&self) -> RateControlMode {
+        match self {
+            RateControlMode::IcqQsv => RateControlMode::Crf,
+            RateControlMode::AvbrQsv => RateControlMode::Vbr,
+            mode => *mode,
+        }
+    }
+
+    /// 品質値（CRF/QP/ICQ）で指定するモードか
+    pub fn uses_quality(&self) -> bool {
+        matches!(
+            self,
+            RateControlMode::Crf | RateControlMode::Cqp | RateControlMode::IcqQsv
+        )
+    }
+
+    /// ターゲットビットレートで指定するモードか
+    pub fn uses_bitrate(&self) -> bool {
+        matches!(
+            self,
+            RateControlMode::Cbr | RateControlMode::Vbr | RateControlMode::AvbrQsv
+        )
+    }
 }
 
 impl Default for RateControlMode {
@@ -612,12 +980,211 @@ impl X264Profile {
     }
 }
 
+impl X264Profile {
+    /// プロファイルが出力ピクセルフォーマットに対応しているか
+    pub fn supports(&self, pixel_format: PixelFormat) -> bool {
+        match self {
+            X264Profile::Baseline | X264Profile::Main | X264Profile::High => {
+                matches!(pixel_format, PixelFormat::Auto | PixelFormat::Yuv420p)
+            }
+            X264Profile::High10 => matches!(
+                pixel_format,
+                PixelFormat::Auto | PixelFormat::Yuv420p | PixelFormat::Yuv420p10le
+            ),
+            X264Profile::High444 => true,
+        }
+    }
+}
+
 impl Default for X264Profile {
     fn default() -> Self {
         X264Profile::High
     }
 }
 
+/// SVT-AV1チューニング
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SvtAv1Tune {
+    /// 主観画質（VQ）
+    VisualQuality,
+    /// PSNR（SVT-AV1の既定値）
+    Psnr,
+    /// SSIM
+    Ssim,
+}
+
+impl SvtAv1Tune {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SvtAv1Tune::VisualQuality => "主観画質 (VQ)",
+            SvtAv1Tune::Psnr => "PSNR",
+            SvtAv1Tune::Ssim => "SSIM",
+        }
+    }
+
+    /// -svtav1-params の tune の値を取得
+    pub fn svt_value(&self) -> u8 {
+        match self {
+            SvtAv1Tune::VisualQuality => 0,
+            SvtAv1Tune::Psnr => 1,
+            SvtAv1Tune::Ssim => 2,
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [SvtAv1Tune] {
+        &[
+            SvtAv1Tune::VisualQuality,
+            SvtAv1Tune::Psnr,
+            SvtAv1Tune::Ssim,
+        ]
+    }
+}
+
+impl Default for SvtAv1Tune {
+    fn default() -> Self {
+        SvtAv1Tune::Psnr
+    }
+}
+
+/// SVT-AV1予測構造
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SvtAv1PredStructure {
+    /// 低遅延（ストリーミング向け）
+    LowDelay,
+    /// ランダムアクセス（保存用向け、SVT-AV1の既定値）
+    RandomAccess,
+}
+
+impl SvtAv1PredStructure {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SvtAv1PredStructure::LowDelay => "低遅延",
+            SvtAv1PredStructure::RandomAccess => "ランダムアクセス",
+        }
+    }
+
+    /// -svtav1-params の pred-struct の値を取得
+    pub fn svt_value(&self) -> u8 {
+        match self {
+            SvtAv1PredStructure::LowDelay => 1,
+            SvtAv1PredStructure::RandomAccess => 2,
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [SvtAv1PredStructure] {
+        &[
+            SvtAv1PredStructure::LowDelay,
+            SvtAv1PredStructure::RandomAccess,
+        ]
+    }
+}
+
+impl Default for SvtAv1PredStructure {
+    fn default() -> Self {
+        SvtAv1PredStructure::RandomAccess
+    }
+}
+
+/// VP9のエンコード速度の基準（-deadline）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Vp9Deadline {
+    /// 最高品質（非常に遅い）
+    Best,
+    /// 標準
+    Good,
+    /// リアルタイム
+    Realtime,
+}
+
+impl Vp9Deadline {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Vp9Deadline::Best => "最高品質",
+            Vp9Deadline::Good => "標準",
+            Vp9Deadline::Realtime => "リアルタイム",
+        }
+    }
+
+    /// FFmpeg引数値を取得
+    pub fn ffmpeg_value(&self) -> &'static str {
+        match self {
+            Vp9Deadline::Best => "best",
+            Vp9Deadline::Good => "good",
+            Vp9Deadline::Realtime => "realtime",
+        }
+    }
+
+    /// -cpu-used の範囲（realtimeは負の値を使えない）
+    pub fn cpu_used_range(&self) -> std::ops::RangeInclusive<i8> {
+        match self {
+            Vp9Deadline::Realtime => 0..=8,
+            _ => -8..=8,
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [Vp9Deadline] {
+        &[Vp9Deadline::Best, Vp9Deadline::Good, Vp9Deadline::Realtime]
+    }
+}
+
+impl Default for Vp9Deadline {
+    fn default() -> Self {
+        Vp9Deadline::Good
+    }
+}
+
+/// libaom-av1の用途（-usage）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LibaomUsage {
+    /// 通常（good）
+    Default,
+    /// リアルタイム
+    Realtime,
+    /// 全フレームをイントラ符号化
+    AllIntra,
+}
+
+impl LibaomUsage {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            LibaomUsage::Default => "通常",
+            LibaomUsage::Realtime => "リアルタイム",
+            LibaomUsage::AllIntra => "オールイントラ",
+        }
+    }
+
+    /// FFmpeg引数値を取得
+    pub fn ffmpeg_value(&self) -> &'static str {
+        match self {
+            LibaomUsage::Default => "good",
+            LibaomUsage::Realtime => "realtime",
+            LibaomUsage::AllIntra => "allintra",
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [LibaomUsage] {
+        &[
+            LibaomUsage::Default,
+            LibaomUsage::Realtime,
+            LibaomUsage::AllIntra,
+        ]
+    }
+}
+
+impl Default for LibaomUsage {
+    fn default() -> Self {
+        LibaomUsage::Default
+    }
+}
+
 /// コンテナ形式
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ContainerFormat {
@@ -642,9 +1209,59 @@ impl ContainerFormat {
         }
     }
 
+    /// 映像ストリームをそのまま格納できるか（ffprobeのcodec_nameで判定）
+    pub fn supports_video_codec(&self, codec: &str) -> bool {
+        match self {
+            ContainerFormat::Mp4 => matches!(codec, "h264" | "hevc" | "av1" | "mpeg4"),
+            ContainerFormat::Mkv => true,
+        }
+    }
+
+    /// 音声ストリームをそのまま格納できるか（ffprobeのcodec_nameで判定）
+    pub fn supports_audio_codec(&self, codec: &str) -> bool {
+        match self {
+            ContainerFormat::Mp4 => {
+                matches!(codec, "aac" | "mp3" | "ac3" | "eac3" | "alac" | "opus")
+            }
+            ContainerFormat::Mkv => true,
+        }
+    }
+}
+
+/// 出力ファイルが既に存在する場合の動作
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverwritePolicy {
+    /// 上書き
+    Overwrite,
+    /// スキップ（確認ダイアログがないため「確認」もこれとして扱う）
+    Skip,
+    /// 連番を付けて別名で保存
+    Rename,
+}
+
+impl OverwritePolicy {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            OverwritePolicy::Overwrite => "上書き",
+            OverwritePolicy::Skip => "スキップ",
+            OverwritePolicy::Rename => "別名で保存",
+        }
+    }
+
     /// すべてのバリアントを取得
-    pub fn all() -> &'static [ContainerFormat] {
-        &[ContainerFormat::Mp4, ContainerFormat::Mkv]
+    pub fn all() -> &'static [OverwritePolicy] {
+        &[
+            OverwritePolicy::Overwrite,
+            OverwritePolicy::Skip,
+            OverwritePolicy::Rename,
+        ]
+    }
+}
+
+impl Default for OverwritePolicy {
+    fn default() -> Self {
+        OverwritePolicy::Rename
     }
 }
 
@@ -655,6 +1272,8 @@ pub enum VideoCodec {
     H265,
     Vp9,
     Av1,
+    /// 再エンコードせずにストリームをコピー
+    Copy,
 }
 
 impl VideoCodec {
@@ -665,12 +1284,16 @@ impl VideoCodec {
             VideoCodec::H265 => "H.265 (HEVC)",
             VideoCodec::Vp9 => "VP9",
             VideoCodec::Av1 => "AV1",
+            VideoCodec::Copy => "コピー (再エンコードなし)",
         }
     }
 
     /// FFmpegエンコーダー名を取得
     pub fn encoder_name(&self, hwaccel: &HwAccelType) -> &'static str {
         match (self, hwaccel) {
+            // ストリームコピーはHWアクセラレーションに関係なく同じ
+            (VideoCodec::Copy, _) => "copy",
+
             // NVIDIA NVENC
             (VideoCodec::H264, HwAccelType::Nvenc) => "h264_nvenc",
             (VideoCodec::H265, HwAccelType::Nvenc) => "hevc_nvenc",
@@ -697,6 +1320,17 @@ impl VideoCodec {
         }
     }
 
+    /// ffprobeが出力するコーデック名（コピーは元のコーデックのため不明）
+    pub fn probe_name(&self) -> Option<&'static str> {
+        match self {
+            VideoCodec::H264 => Some("h264"),
+            VideoCodec::H265 => Some("hevc"),
+            VideoCodec::Vp9 => Some("vp9"),
+            VideoCodec::Av1 => Some("av1"),
+            VideoCodec::Copy => None,
+        }
+    }
+
     /// すべてのバリアントを取得
     pub fn all() -> &'static [VideoCodec] {
         &[
@@ -704,6 +1338,7 @@ impl VideoCodec {
             VideoCodec::H265,
             VideoCodec::Vp9,
             VideoCodec::Av1,
+            VideoCodec::Copy,
         ]
     }
 }
@@ -734,7 +1369,16 @@ impl VideoResolution {
             VideoResolution::Fhd1080 => "1080p".to_string(),
             VideoResolution::Hd720 => "720p".to_string(),
             VideoResolution::Sd480 => "480p".to_string(),
-            VideoResolution::Custom(w, h) => format!("{}x{}", w, h),
+            VideoResolution::Custom(w, h) => {
+                let side = |v: u32| {
+                    if v == 0 {
+                        "自動".to_string()
+                    } else {
+                        v.to_string()
+                    }
+                };
+                format!("{}x{}", side(*w), side(*h))
+            }
         }
     }
 
@@ -750,18 +1394,498 @@ impl VideoResolution {
         }
     }
 
+    /// アスペクト比を保つスケールフィルタ（元の解像度ならNone）
+    /// プリセットは枠に収まるように縮小し、カスタムで幅と高さを指定した場合は余白を加えて合わせる
+    /// 片方が0のカスタムはもう一方に合わせて比率を保つ（x264のため常に偶数）
+    pub fn scale_filter(&self) -> Option<String> {
+        const FIT: &str = "force_original_aspect_ratio=decrease:force_divisible_by=2";
+        match *self {
+            VideoResolution::Original | VideoResolution::Custom(0, 0) => None,
+            VideoResolution::Custom(0, h) => Some(format!("scale=-2:{}", even(h))),
+            VideoResolution::Custom(w, 0) => Some(format!("scale={}:-2", even(w))),
+            VideoResolution::Custom(w, h) => {
+                let (w, h) = (even(w), even(h));
+                Some(format!(
+                    "scale={w}:{h}:{FIT},pad={w}:{h}:(ow-iw)/2:(oh-ih)/2"
+                ))
+            }
+            res => {
+                let (w, h) = res.dimensions();
+                Some(format!("scale={}:{}:{}", w, h, FIT))
+            }
+        }
+    }
+
+    /// 元の動画を枠に収めたときの映像部分の解像度（余白を含まない）
+    pub fn fit_dimensions(&self, source: (u32, u32)) -> Option<(u32, u32)> {
+        let (max_w, max_h) = match self {
+            VideoResolution::Original => return None,
+            res => res.dimensions(),
+        };
+        let (src_w, src_h) = (source.0 as u64, source.1 as u64);
+        if src_w == 0 || src_h == 0 || (max_w == 0 && max_h == 0) {
+            return None;
+        }
+
+        let (max_w, max_h) = (max_w as u64, max_h as u64);
+        // 枠より横長なら幅、縦長なら高さで制限される
+        let (w, h) = if max_h == 0 || (max_w > 0 && src_w * max_h >= src_h * max_w) {
+            (max_w, src_h * max_w / src_w)
+        } else {
+            (src_w * max_h / src_h, max_h)
+        };
+        Some((even(w as u32), even(h as u32)))
+    }
+
+    /// 出力される解像度（余白を含む）
+    pub fn output_dimensions(&self, source: (u32, u32)) -> Option<(u32, u32)> {
+        match *self {
+            VideoResolution::Custom(w, h) if w > 0 && h > 0 => Some((even(w), even(h))),
+            _ => self.fit_dimensions(source),
+        }
+    }
+}
+
+/// 偶数に切り捨てる（4:2:0のエンコーダーは奇数の幅・高さを扱えない）
+fn even(value: u32) -> u32 {
+    (value & !1).max(2)
+}
+
+/// 出力フレームレート
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FrameRate {
+    /// 元のフレームレートを維持
+    Original,
+    /// 23.976fps (24000/1001)
+    Fps23_976,
+    /// 24fps
+    Fps24,
+    /// 25fps
+    Fps25,
+    /// 29.97fps (30000/1001)
+    Fps29_97,
+    /// 30fps
+    Fps30,
+    /// 50fps
+    Fps50,
+    /// 59.94fps (60000/1001)
+    Fps59_94,
+    /// 60fps
+    Fps60,
+    /// カスタム
+    Custom(f32),
+}
+
+impl FrameRate {
+    /// 表示名を取得
+    pub fn display_name(&self) -> String {
+        match self {
+            FrameRate::Original => "元のFPS".to_string(),
+            FrameRate::Fps23_976 => "23.976".to_string(),
+            FrameRate::Fps24 => "24".to_string(),
+            FrameRate::Fps25 => "25".to_string(),
+            FrameRate::Fps29_97 => "29.97".to_string(),
+            FrameRate::Fps30 => "30".to_string(),
+            FrameRate::Fps50 => "50".to_string(),
+            FrameRate::Fps59_94 => "59.94".to_string(),
+            FrameRate::Fps60 => "60".to_string(),
+            FrameRate::Custom(fps) => format!("{}", fps),
+        }
+    }
+
+    /// フレームレートを取得（元のFPSを維持する場合はNone）
+    pub fn fps(&self) -> Option<f64> {
+        match self {
+            FrameRate::Original => None,
+            FrameRate::Fps23_976 => Some(24000.0 / 1001.0),
+            FrameRate::Fps24 => Some(24.0),
+            FrameRate::Fps25 => Some(25.0),
+            FrameRate::Fps29_97 => Some(30000.0 / 1001.0),
+            FrameRate::Fps30 => Some(30.0),
+            FrameRate::Fps50 => Some(50.0),
+            FrameRate::Fps59_94 => Some(60000.0 / 1001.0),
+            FrameRate::Fps60 => Some(60.0),
+            FrameRate::Custom(fps) => Some(*fps as f64),
+        }
+    }
+
+    /// FFmpegに渡す値を取得（NTSC系は誤差が出ないよう分数で指定）
+    pub fn ffmpeg_value(&self) -> Option<String> {
+        match self {
+            FrameRate::Fps23_976 => Some("24000/1001".to_string()),
+            FrameRate::Fps29_97 => Some("30000/1001".to_string()),
+            FrameRate::Fps59_94 => Some("60000/1001".to_string()),
+            other => other.fps().map(|fps| fps.to_string()),
+        }
+    }
+
     /// すべてのバリアントを取得（カスタムを除く）
-    pub fn all() -> &'static [VideoResolution] {
+    pub fn all() -> &'static [FrameRate] {
         &[
-            VideoResolution::Original,
-            VideoResolution::Uhd4K,
-            VideoResolution::Fhd1080,
-            VideoResolution::Hd720,
-            VideoResolution::Sd480,
+            FrameRate::Original,
+            FrameRate::Fps23_976,
+            FrameRate::Fps24,
+            FrameRate::Fps25,
+            FrameRate::Fps29_97,
+            FrameRate::Fps30,
+            FrameRate::Fps50,
+            FrameRate::Fps59_94,
+            FrameRate::Fps60,
         ]
     }
 }
 
+impl Default for FrameRate {
+    fn default() -> Self {
+        FrameRate::Original
+    }
+}
+
+/// フレームレート変換の方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FpsFilterMode {
+    /// フレームの間引き・複製（高速）
+    Simple,
+    /// 前後のフレームを合成（間引きによるカクつきを抑える）
+    Blend,
+    /// 動き補間（24→60fpsなどの変換向け、非常に遅い）
+    Minterpolate,
+}
+
+impl FpsFilterMode {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            FpsFilterMode::Simple => "シンプル",
+            FpsFilterMode::Blend => "ブレンド",
+            FpsFilterMode::Minterpolate => "動き補間",
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [FpsFilterMode] {
+        &[
+            FpsFilterMode::Simple,
+            FpsFilterMode::Blend,
+            FpsFilterMode::Minterpolate,
+        ]
+    }
+
+    /// FFmpegフィルタを取得
+    pub fn filter(&self, fps: &str) -> String {
+        match self {
+            FpsFilterMode::Simple => format!("fps={}", fps),
+            FpsFilterMode::Blend => format!("framerate=fps={}", fps),
+            FpsFilterMode::Minterpolate => {
+                format!("minterpolate=fps={}:mi_mode=mci:mc_mode=aobmc", fps)
+            }
+        }
+    }
+}
+
+impl Default for FpsFilterMode {
+    fn default() -> Self {
+        FpsFilterMode::Simple
+    }
+}
+
+/// インターレース解除
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Deinterlace {
+    /// 無効
+    Off,
+    /// 自動（ソースがインターレースの場合のみbwdif）
+    Auto,
+    /// yadif（高速）
+    Yadif,
+    /// bwdif（高品質）
+    Bwdif,
+}
+
+impl Deinterlace {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Deinterlace::Off => "オフ",
+            Deinterlace::Auto => "自動",
+            Deinterlace::Yadif => "yadif",
+            Deinterlace::Bwdif => "bwdif",
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [Deinterlace] {
+        &[
+            Deinterlace::Off,
+            Deinterlace::Auto,
+            Deinterlace::Yadif,
+            Deinterlace::Bwdif,
+        ]
+    }
+
+    /// FFmpegフィルタを取得（フレームレートを変えないよう1フレーム出力モード）
+    pub fn filter(&self, source_interlaced: bool) -> Option<&'static str> {
+        match self {
+            Deinterlace::Off => None,
+            Deinterlace::Auto if !source_interlaced => None,
+            Deinterlace::Auto | Deinterlace::Bwdif => Some("bwdif=mode=send_frame"),
+            Deinterlace::Yadif => Some("yadif=mode=send_frame"),
+        }
+    }
+}
+
+impl Default for Deinterlace {
+    fn default() -> Self {
+        Deinterlace::Auto
+    }
+}
+
+/// HDR→SDR変換のトーンマッピング方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HdrToneMappingMode {
+    /// Hable（フィルム調、ハイライトを自然に圧縮）
+    Hable,
+    /// Reinhard（シンプル、全体がやや暗め）
+    Reinhard,
+    /// Mobius（SDR範囲内の色を保ちやすい）
+    Mobius,
+    /// BT.2390（ITU推奨のEETF、libplaceboが必要）
+    Bt2390,
+}
+
+impl HdrToneMappingMode {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            HdrToneMappingMode::Hable => "Hable",
+            HdrToneMappingMode::Reinhard => "Reinhard",
+            HdrToneMappingMode::Mobius => "Mobius",
+            HdrToneMappingMode::Bt2390 => "BT.2390",
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [HdrToneMappingMode] {
+        &[
+            HdrToneMappingMode::Hable,
+            HdrToneMappingMode::Reinhard,
+            HdrToneMappingMode::Mobius,
+            HdrToneMappingMode::Bt2390,
+        ]
+    }
+
+    /// SDR（BT.709 8bit）に変換するFFmpegフィルタを取得
+    /// tonemapフィルタはリニアな浮動小数点RGBでしか動作しないため、zscaleで前後を変換する
+    /// BT.2390はtonemapフィルタにないためlibplaceboを使用する
+    pub fn filter(&self) -> String {
+        let tonemap = match self {
+            HdrToneMappingMode::Hable => "hable",
+            HdrToneMappingMode::Reinhard => "reinhard",
+            HdrToneMappingMode::Mobius => "mobius",
+            HdrToneMappingMode::Bt2390 => {
+                return "libplacebo=tonemapping=bt.2390:colorspace=bt709:color_primaries=bt709:color_trc=bt709:format=yuv420p".to_string();
+            }
+        };
+        format!(
+            "zscale=transfer=linear:npl=100,format=gbrpf32le,zscale=primaries=bt709,tonemap=tonemap={}:desat=0,zscale=transfer=bt709:matrix=bt709:range=tv,format=yuv420p",
+            tonemap
+        )
+    }
+}
+
+impl Default for HdrToneMappingMode {
+    fn default() -> Self {
+        HdrToneMappingMode::Hable
+    }
+}
+
+/// 出力ピクセルフォーマット
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PixelFormat {
+    /// 自動（ソースが10bitなら対応エンコーダーで10bitのまま出力）
+    Auto,
+    /// 4:2:0 8bit
+    Yuv420p,
+    /// 4:2:2 8bit
+    Yuv422p,
+    /// 4:4:4 8bit
+    Yuv444p,
+    /// 4:2:0 10bit
+    Yuv420p10le,
+    /// 4:2:2 10bit
+    Yuv422p10le,
+}
+
+impl PixelFormat {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PixelFormat::Auto => "自動",
+            PixelFormat::Yuv420p => "4:2:0 8bit",
+            PixelFormat::Yuv422p => "4:2:2 8bit",
+            PixelFormat::Yuv444p => "4:4:4 8bit",
+            PixelFormat::Yuv420p10le => "4:2:0 10bit",
+            PixelFormat::Yuv422p10le => "4:2:2 10bit",
+        }
+    }
+
+    /// FFmpegの-pix_fmt値（自動はNone）
+    pub fn ffmpeg_value(&self) -> Option<&'static str> {
+        match self {
+            PixelFormat::Auto => None,
+            PixelFormat::Yuv420p => Some("yuv420p"),
+            PixelFormat::Yuv422p => Some("yuv422p"),
+            PixelFormat::Yuv444p => Some("yuv444p"),
+            PixelFormat::Yuv420p10le => Some("yuv420p10le"),
+            PixelFormat::Yuv422p10le => Some("yuv422p10le"),
+        }
+    }
+
+    /// 10bit出力か
+    pub fn is_high_bit_depth(&self) -> bool {
+        matches!(self, PixelFormat::Yuv420p10le | PixelFormat::Yuv422p10le)
+    }
+
+    /// エンコーダーが出力できるか
+    pub fn supported_by(&self, encoder: &str) -> bool {
+        match self {
+            PixelFormat::Auto | PixelFormat::Yuv420p => true,
+            PixelFormat::Yuv422p => matches!(encoder, "libx264" | "libx265" | "libvpx-vp9"),
+            PixelFormat::Yuv444p => matches!(
+                encoder,
+                "libx264" | "libx265" | "libvpx-vp9" | "libaom-av1" | "h264_nvenc" | "hevc_nvenc"
+            ),
+            PixelFormat::Yuv420p10le => matches!(
+                encoder,
+                "libx265"
+                    | "libvpx-vp9"
+                    | "libaom-av1"
+                    | "libsvtav1"
+                    | "hevc_nvenc"
+                    | "av1_nvenc"
+                    | "hevc_qsv"
+                    | "av1_qsv"
+                    | "hevc_amf"
+                    | "av1_amf"
+            ),
+            PixelFormat::Yuv422p10le => matches!(encoder, "libx265" | "libvpx-vp9"),
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [PixelFormat] {
+        &[
+            PixelFormat::Auto,
+            PixelFormat::Yuv420p,
+            PixelFormat::Yuv422p,
+            PixelFormat::Yuv444p,
+            PixelFormat::Yuv420p10le,
+            PixelFormat::Yuv422p10le,
+        ]
+    }
+}
+
+impl Default for PixelFormat {
+    fn default() -> Self {
+        PixelFormat::Auto
+    }
+}
+
+/// クロップ方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CropMode {
+    /// クロップしない（検出済みの値も無視）
+    Off,
+    /// 手動で指定した値（ファイルごとに検出した値があればそちらを優先）
+    Manual,
+    /// キューへの追加時に黒帯を自動検出
+    AutoDetect,
+}
+
+impl CropMode {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CropMode::Off => "なし",
+            CropMode::Manual => "手動",
+            CropMode::AutoDetect => "自動検出",
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [CropMode] {
+        &[CropMode::Off, CropMode::Manual, CropMode::AutoDetect]
+    }
+
+    /// ファイルに適用するクロップを決定
+    pub fn resolve(&self, detected: Option<CropSettings>, manual: CropSettings) -> CropSettings {
+        match self {
+            CropMode::Off => CropSettings::default(),
+            CropMode::Manual => detected.unwrap_or(manual),
+            // 未検出（検出中・失敗）のファイルはクロップしない
+            CropMode::AutoDetect => detected.unwrap_or_default(),
+        }
+    }
+}
+
+impl Default for CropMode {
+    fn default() -> Self {
+        CropMode::Manual
+    }
+}
+
+/// クロップ設定（各辺から切り取るピクセル数）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropSettings {
+    pub top: u32,
+    pub bottom: u32,
+    pub left: u32,
+    pub right: u32,
+}
+
+impl CropSettings {
+    /// クロップなしか
+    pub fn is_empty(&self) -> bool {
+        self.top == 0 && self.bottom == 0 && self.left == 0 && self.right == 0
+    }
+
+    /// cropdetectの結果（幅, 高さ, X, Y）とソース解像度から各辺の値を計算
+    pub fn from_area(area: (u32, u32, u32, u32), source: (u32, u32)) -> Self {
+        let (width, height, x, y) = area;
+        Self {
+            top: y,
+            bottom: source.1.saturating_sub(height + y),
+            left: x,
+            right: source.0.saturating_sub(width + x),
+        }
+    }
+
+    /// FFmpegのcropフィルタ（ソース解像度に依存しない式で指定）
+    pub fn filter(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "crop=iw-{}:ih-{}:{}:{}",
+            self.left + self.right,
+            self.top + self.bottom,
+            self.left,
+            self.top
+        ))
+    }
+
+    /// 表示用文字列
+    pub fn display(&self) -> String {
+        if self.is_empty() {
+            return "なし".to_string();
+        }
+        format!(
+            "上{} 下{} 左{} 右{}",
+            self.top, self.bottom, self.left, self.right
+        )
+    }
+}
+
 /// エンコードプリセット
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum VideoPreset {
@@ -794,17 +1918,6 @@ impl VideoPreset {
             VideoPreset::Veryslow => "veryslow",
         }
     }
-
-    /// すべてのバリアントを取得
-    pub fn all() -> &'static [VideoPreset] {
-        &[
-            VideoPreset::Ultrafast,
-            VideoPreset::Fast,
-            VideoPreset::Medium,
-            VideoPreset::Slow,
-            VideoPreset::Veryslow,
-        ]
-    }
 }
 
 /// オーディオコーデック
@@ -831,6 +1944,34 @@ impl AudioCodec {
         }
     }
 
+    /// FFmpegのエンコーダー名を取得（コピーはエンコーダーを使わない）
+    pub fn encoder_name(&self) -> Option<&'static str> {
+        match self {
+            AudioCodec::Aac => Some("aac"),
+            AudioCodec::Mp3 => Some("libmp3lame"),
+            AudioCodec::Flac => Some("flac"),
+            AudioCodec::Copy => None,
+        }
+    }
+
+    /// ffprobeが出力するコーデック名（コピーは元のコーデックのため不明）
+    pub fn probe_name(&self) -> Option<&'static str> {
+        match self {
+            AudioCodec::Aac => Some("aac"),
+            AudioCodec::Mp3 => Some("mp3"),
+            AudioCodec::Flac => Some("flac"),
+            AudioCodec::Copy => None,
+        }
+    }
+
+    /// エンコードできる最大チャンネル数（MP3はステレオまで）
+    pub fn max_channels(&self) -> Option<u32> {
+        match self {
+            AudioCodec::Mp3 => Some(2),
+            _ => None,
+        }
+    }
+
     /// すべてのバリアントを取得
     pub fn all() -> &'static [AudioCodec] {
         &[
@@ -842,6 +1983,51 @@ impl AudioCodec {
     }
 }
 
+/// オーディオのチャンネル数
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AudioChannels {
+    /// 元のまま
+    Original,
+    /// ステレオ (2ch)
+    Stereo,
+    /// モノラル (1ch)
+    Mono,
+    /// 5.1ch
+    Surround51,
+}
+
+impl AudioChannels {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            AudioChannels::Original => "元のまま",
+            AudioChannels::Stereo => "ステレオ",
+            AudioChannels::Mono => "モノラル",
+            AudioChannels::Surround51 => "5.1ch",
+        }
+    }
+
+    /// -ac に渡すチャンネル数（元のままならNone）
+    pub fn count(&self) -> Option<u32> {
+        match self {
+            AudioChannels::Original => None,
+            AudioChannels::Stereo => Some(2),
+            AudioChannels::Mono => Some(1),
+            AudioChannels::Surround51 => Some(6),
+        }
+    }
+
+    /// すべてのバリアントを取得
+    pub fn all() -> &'static [AudioChannels] {
+        &[
+            AudioChannels::Original,
+            AudioChannels::Stereo,
+            AudioChannels::Mono,
+            AudioChannels::Surround51,
+        ]
+    }
+}
+
 /// オーディオビットレートの選択肢
 pub fn audio_bitrate_options() -> &'static [(u32, &'static str)] {
     &[
@@ -1,5 +1,6 @@
 //! 進捗フィルター
 
+use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -231,6 +232,59 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// ビットレート（bps）をフォーマット
+pub fn format_bitrate(bps: u64) -> String {
+    if bps >= 1_000_000 {
+        format!("{:.2} Mbps", bps as f64 / 1_000_000.0)
+    } else {
+        format!("{} kbps", bps / 1000)
+    }
+}
+
+/// フレームレートをフォーマット（整数ならそのまま、それ以外は小数2桁）
+pub fn format_fps(fps: f64) -> String {
+    if (fps - fps.round()).abs() < 0.005 {
+        format!("{}fps", fps.round())
+    } else {
+        format!("{:.2}fps", fps)
+    }
+}
+
+/// 変換速度の平滑化係数（0.5秒ごとの更新で直近数秒分を重視）
+const SPEED_EMA_ALPHA: f64 = 0.2;
+
+/// FFmpegの speed= から残り時間を推定
+/// 経過時間と進捗率からの外挿は序盤や複雑さが変わる場面で大きくずれるため、
+/// 指数移動平均で平滑化した変換速度を使う
+#[derive(Clone, Debug, Default)]
+pub struct SpeedEstimator {
+    /// 平滑化した変換速度（倍速）
+    smoothed: Option<f64>,
+}
+
+impl SpeedEstimator {
+    /// 速度のサンプルを追加して平滑化した速度を返す（N/Aなど0以下は無視）
+    pub fn update(&mut self, speed: f32) -> Option<f64> {
+        if speed > 0.0 && speed.is_finite() {
+            let speed = speed as f64;
+            self.smoothed = Some(match self.smoothed {
+                Some(prev) => prev + SPEED_EMA_ALPHA * (speed - prev),
+                None => speed,
+            });
+        }
+        self.smoothed
+    }
+
+    /// 残り時間（秒）
+    pub fn remaining_secs(&self, total_duration_secs: f64, current_time_secs: f64) -> Option<f64> {
+        let speed = self.smoothed?;
+        if total_duration_secs <= 0.0 {
+            return None;
+        }
+        Some((total_duration_secs - current_time_secs).max(0.0) / speed)
+    }
+}
+
 /// FFmpegの進捗出力をパース
 /// FFmpegは stderr に以下のような形式で出力する:
 /// frame=  123 fps= 30 q=28.0 size=    1234kB time=00:00:05.12 bitrate=1234.5kbits/s speed=1.23x
@@ -248,6 +302,8 @@ pub struct FfmpegProgressInfo {
     pub bitrate: f32,
     /// 速度（倍速）
     pub speed: f32,
+    /// このブロックで時間を取得したキーの優先度（0は未取得）
+    time_priority: u8,
 }
 
 impl FfmpegProgressInfo {
@@ -280,7 +336,7 @@ impl FfmpegProgressInfo {
 
         // time= の値を抽出 (HH:MM:SS.ms 形式)
         if let Some(time_str) = Self::extract_value(line, "time=") {
-            info.time_secs = Self::parse_time(time_str.trim());
+            info.time_secs = Self::parse_time(time_str.trim()).max(0.0);
         }
 
         // bitrate= の値を抽出
@@ -309,6 +365,9 @@ impl FfmpegProgressInfo {
     /// fps=30.00
     /// out_time_us=5120000
     /// progress=continue
+    ///
+    /// 時間は out_time_us > out_time_ms > out_time の順に優先し、N/A は前の値を維持する
+    /// -ss で切り出した場合などの負の時間は0として扱う
     pub fn parse_progress_line(&mut self, line: &str) -> bool {
         let line = line.trim();
         if line.is_empty() {
@@ -328,17 +387,17 @@ impl FfmpegProgressInfo {
                     self.size = value.trim().parse().unwrap_or(0);
                 }
                 "out_time_us" => {
-                    // マイクロ秒を秒に変換
-                    let us: i64 = value.trim().parse().unwrap_or(0);
-                    if us > 0 {
-                        self.time_secs = us as f64 / 1_000_000.0;
-                    }
+                    self.update_time(Self::parse_micros(value), 3);
+                }
+                "out_time_ms" => {
+                    // 名前に反してFFmpegはマイクロ秒で出力する
+                    self.update_time(Self::parse_micros(value), 2);
                 }
                 "out_time" => {
-                    // HH:MM:SS.mmmmmmフォーマット（out_time_usがない場合のフォールバック）
-                    if self.time_secs <= 0.0 {
-                        self.time_secs = Self::parse_time(value.trim());
-                    }
+                    // HH:MM:SS.mmmmmmフォーマット
+                    let value = value.trim();
+                    let secs = value.contains(':').then(|| Self::parse_time(value));
+                    self.update_time(secs, 1);
                 }
                 "bitrate" => {
                     // kbits/s形式
@@ -360,6 +419,34 @@ impl FfmpegProgressInfo {
         false
     }
     
+    /// マイクロ秒の値を秒に変換（N/Aなど数値でなければNone）
+    fn parse_micros(value: &str) -> Option<f64> {
+        let us: i64 = value.trim().parse().ok()?;
+        Some(us as f64 / 1_000_000.0)
+    }
+
+    /// 時間を更新（値がない場合と、同じブロックでより優先度の高いキーから取得済みの場合は維持）
+    fn update_time(&mut self, secs: Option<f64>, priority: u8) {
+        let Some(secs) = secs else {
+            return;
+        };
+        if priority < self.time_priority {
+            return;
+        }
+        self.time_secs = secs.max(0.0);
+        self.time_priority = priority;
+    }
+
+    /// 次のブロックに備えてリセット（フレーム・FPS・時間は N/A の場合に備えて保持）
+    pub fn start_next_block(&mut self) {
+        *self = Self {
+            frame: self.frame,
+            fps: self.fps,
+            time_secs: self.time_secs,
+            ..Self::default()
+        };
+    }
+
     /// 有効なデータがあるかチェック
     pub fn is_valid(&self) -> bool {
         self.frame > 0 || self.time_secs > 0.0
@@ -394,8 +481,11 @@ impl FfmpegProgressInfo {
         Some(rest[..end].trim().to_string())
     }
 
-    /// HH:MM:SS.ms 形式の時間をパース
+    /// HH:MM:SS.ms 形式の時間をパース（先頭の-は負の時間）
     fn parse_time(time_str: &str) -> f64 {
+        if let Some(time_str) = time_str.strip_prefix('-') {
+            return -Self::parse_time(time_str);
+        }
         let parts: Vec<&str> = time_str.split(':').collect();
         if parts.len() != 3 {
             return 0.0;
@@ -418,11 +508,13 @@ impl FfmpegProgressInfo {
     }
 }
 
-use super::preset::{AudioCodec, TranscodeSettings, VideoCodec, VideoPreset, VideoResolution};
+use super::preset::{
+    encoder_crf, AudioCodec, RateControlMode, TranscodeSettings, VideoCodec, VideoPreset,
+};
 use super::HwAccelType;
 
 /// コンテンツタイプ（動き量補正用）
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum ContentType {
     /// 静止画・スライドショー（動きほぼなし）
     Static,
@@ -470,10 +562,23 @@ impl ContentType {
             ContentType::HighMotion,
         ]
     }
+
+    /// ファイル名からコンテンツタイプを推測（該当しなければ実写）
+    pub fn guess_from_file_name(name: &str) -> Self {
+        let lower = name.to_lowercase();
+        if lower.contains("anime") || name.contains("アニメ") {
+            ContentType::Anime
+        } else if lower.contains("screen") || name.contains("録画") {
+            ContentType::ScreenRecord
+        } else {
+            ContentType::Normal
+        }
+    }
 }
 
 /// 動画メタデータ（予測精度向上のため）
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct VideoMetadata {
     /// 解像度（幅, 高さ）
     pub resolution: Option<(u32, u32)>,
@@ -489,6 +594,74 @@ pub struct VideoMetadata {
     pub source_audio_bitrate: Option<u64>,
     /// 元の全体ビットレート（bps）
     pub source_overall_bitrate: Option<u64>,
+    /// 映像コーデック
+    pub video_codec: Option<String>,
+    /// 音声コーデック
+    pub audio_codec: Option<String>,
+    /// 映像のビット深度
+    pub video_bit_depth: Option<u32>,
+    /// 音声のビット深度
+    pub audio_bit_depth: Option<u32>,
+    /// 音声のチャンネル数
+    pub source_audio_channels: Option<u32>,
+    /// ピクセルフォーマット
+    pub pix_fmt: Option<String>,
+    /// 色域
+    pub color_primaries: Option<String>,
+    /// 伝達特性
+    pub color_trc: Option<String>,
+    /// 色空間
+    pub color_space: Option<String>,
+    /// フィールドオーダー（progressive / tt / bb など）
+    pub field_order: Option<String>,
+    /// HDR10のマスタリングディスプレイ（x265の--master-display形式）
+    pub master_display: Option<String>,
+    /// HDR10のコンテンツ輝度（MaxCLL, MaxFALL）
+    pub max_cll: Option<(u32, u32)>,
+    /// チャプター数
+    pub chapter_count: usize,
+}
+
+impl VideoMetadata {
+    /// 10bit以上のソースか
+    pub fn is_high_bit_depth(&self) -> bool {
+        self.video_bit_depth.map(|d| d >= 10).unwrap_or(false)
+    }
+
+    /// インターレースのソースか
+    pub fn is_interlaced(&self) -> bool {
+        matches!(
+            self.field_order.as_deref(),
+            Some("tt") | Some("bb") | Some("tb") | Some("bt")
+        )
+    }
+
+    /// HDR（PQ / HLG）のソースか
+    pub fn is_hdr(&self) -> bool {
+        matches!(
+            self.color_trc.as_deref(),
+            Some("smpte2084") | Some("arib-std-b67")
+        )
+    }
+
+    /// 一覧表示用の概要（例: "1920x1080 · 23.98fps · hevc · 8.20 Mbps"）
+    /// 取得できなかった項目は省略し、何もなければ "—"
+    pub fn summary(&self) -> String {
+        let parts: Vec<String> = [
+            self.resolution.map(|(w, h)| format!("{}x{}", w, h)),
+            self.fps.map(format_fps),
+            self.video_codec.clone(),
+            self.source_video_bitrate.map(format_bitrate),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if parts.is_empty() {
+            "—".to_string()
+        } else {
+            parts.join(" · ")
+        }
+    }
 }
 
 /// 設定から予測圧縮率を計算（2024-2025年実測値準拠の改良版）
@@ -511,44 +684,191 @@ pub fn estimate_compression_ratio_advanced(
     settings: &TranscodeSettings,
     metadata: &VideoMetadata,
 ) -> f64 {
+    // ストリームコピーは映像を再エンコードしない
+    if settings.video_codec == VideoCodec::Copy {
+        return estimate_stream_copy(settings, metadata);
+    }
+
+    // ビットレート指定の場合は目標ビットレートから直接計算
+    if settings.rate_control.uses_bitrate() {
+        if let Some(ratio) = estimate_from_target_bitrate(settings, metadata) {
+            return ratio;
+        }
+    }
+
     let source_resolution = metadata.resolution.unwrap_or((1920, 1080));
     let source_fps = metadata.fps.unwrap_or(30.0);
+    // フレームレート変換が指定されていればその値を使用
+    let target_fps = settings.frame_rate.fps().unwrap_or(source_fps);
 
     // ターゲット解像度を計算
-    let target_resolution = match settings.resolution {
-        VideoResolution::Original => source_resolution,
-        res => {
-            let dims = res.dimensions();
-            if dims.0 == 0 || dims.1 == 0 {
-                source_resolution
-            } else {
-                dims
-            }
-        }
-    };
+    let target_resolution = settings
+        .resolution
+        .output_dimensions(source_resolution)
+        .unwrap_or(source_resolution);
 
     // === ソースビットレートがある場合、より正確な予測を行う ===
-    if let Some(source_video_bitrate) = metadata.source_video_bitrate {
-        return estimate_from_source_bitrate(
+    let ratio = if let Some(source_video_bitrate) = metadata.source_video_bitrate {
+        estimate_from_source_bitrate(
             settings,
             metadata,
             source_video_bitrate,
             source_resolution,
             target_resolution,
             source_fps,
-        );
+            target_fps,
+        )
+    } else {
+        // === ソースビットレートがない場合、従来の圧縮率ベースの予測 ===
+        estimate_from_compression_ratio(
+            settings,
+            metadata,
+            source_resolution,
+            target_resolution,
+            target_fps,
+        )
+    };
+
+    // 10bit出力は同じ品質設定で20〜30%大きくなる
+    if settings.pixel_format.is_high_bit_depth() {
+        ratio * HIGH_BIT_DEPTH_SIZE_FACTOR
+    } else {
+        ratio
+    }
+}
+
+/// 目標サイズに最も近い出力になるCRFを予測モデルから二分探索で求める
+/// CRFが大きいほど出力は小さくなるため、目標以下に収まる最小のCRFとその1つ手前を比べる
+pub fn crf_from_target_size(
+    source_size: u64,
+    target_size: u64,
+    settings: &TranscodeSettings,
+    metadata: &VideoMetadata,
+) -> u8 {
+    const MAX_CRF: u8 = 51;
+
+    let mut candidate = settings.clone();
+    candidate.rate_control = RateControlMode::Crf;
+    let mut predicted_size = |crf: u8| {
+        candidate.crf = crf;
+        (source_size as f64 * estimate_compression_ratio_advanced(&candidate, metadata)) as u64
+    };
+
+    let (mut low, mut high) = (0, MAX_CRF);
+    while low < high {
+        let mid = (low + high) / 2;
+        if predicted_size(mid) <= target_size {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+
+    if low > 0 {
+        let below = predicted_size(low).abs_diff(target_size);
+        let above = predicted_size(low - 1).abs_diff(target_size);
+        if above < below {
+            return low - 1;
+        }
     }
+    low
+}
 
-    // === ソースビットレートがない場合、従来の圧縮率ベースの予測 ===
-    estimate_from_compression_ratio(
-        settings,
-        metadata,
-        source_resolution,
-        target_resolution,
-        source_fps,
+impl TranscodeSettings {
+    /// 目標サイズモードの場合、ファイルに合わせて算出したCRFモードの設定に置き換える
+    /// 元のサイズより大きい目標は元のサイズに抑える（サイズ不明なら設定のCRFのまま）
+    pub fn resolve_target_size(&self, source_size: u64, metadata: &VideoMetadata) -> Self {
+        let mut settings = self.clone();
+        if settings.rate_control == RateControlMode::TargetSize {
+            if source_size > 0 {
+                let target_size = (settings.target_size_mb as u64 * 1024 * 1024).min(source_size);
+                settings.crf = crf_from_target_size(source_size, target_size, self, metadata);
+            }
+            settings.rate_control = RateControlMode::Crf;
+        }
+        settings
+    }
+}
+
+/// 10bit出力時のサイズ係数
+const HIGH_BIT_DEPTH_SIZE_FACTOR: f64 = 1.25;
+
+/// ストリームコピー時の予測
+/// 映像はそのまま、コンテナのオーバーヘッド差分で約2%小さくなる
+fn estimate_stream_copy(settings: &TranscodeSettings, metadata: &VideoMetadata) -> f64 {
+    const REMUX_FACTOR: f64 = 0.98;
+
+    if settings.audio_codec == AudioCodec::Copy {
+        return REMUX_FACTOR;
+    }
+
+    // 音声のみ再エンコードする場合は音声部分だけ比率を変える
+    let source_audio_bitrate = metadata.source_audio_bitrate.unwrap_or(192_000);
+    let audio_portion = match (
+        metadata.source_overall_bitrate,
+        metadata.source_video_bitrate,
+    ) {
+        (Some(overall), _) if overall > 0 => {
+            (source_audio_bitrate as f64 / overall as f64).min(1.0)
+        }
+        (_, Some(video)) => source_audio_bitrate as f64 / (video + source_audio_bitrate) as f64,
+        _ => 0.10, // 不明な場合は約10%がオーディオと仮定
+    };
+    let target_audio_bitrate =
+        target_audio_bitrate(settings, metadata, source_audio_bitrate as f64);
+    let audio_ratio = target_audio_bitrate / source_audio_bitrate as f64;
+
+    ((1.0 - audio_portion) + audio_portion * audio_ratio) * REMUX_FACTOR
+}
+
+/// 出力する音声のビットレート（bps）
+/// 非可逆コーデックは-b:aで決まるため、ロスレスのみチャンネル数の増減を反映する
+fn target_audio_bitrate(
+    settings: &TranscodeSettings,
+    metadata: &VideoMetadata,
+    source_audio_bitrate: f64,
+) -> f64 {
+    match settings.audio_codec {
+        AudioCodec::Copy => source_audio_bitrate,
+        AudioCodec::Aac | AudioCodec::Mp3 => settings.audio_bitrate as f64 * 1000.0,
+        // FLACは約2.5倍
+        AudioCodec::Flac => source_audio_bitrate * 2.5 * audio_channel_ratio(settings, metadata),
+    }
+}
+
+/// ダウンミックス・アップミックスによるチャンネル数の比（元のチャンネル数が不明ならステレオとみなす）
+fn audio_channel_ratio(settings: &TranscodeSettings, metadata: &VideoMetadata) -> f64 {
+    let source_channels = metadata.source_audio_channels.unwrap_or(2).max(1);
+    settings
+        .audio_channels
+        .count()
+        .map_or(1.0, |channels| channels as f64 / source_channels as f64)
+}
+
+/// 目標ビットレートからの予測（元の全体ビットレートが分かる場合のみ）
+fn estimate_from_target_bitrate(
+    settings: &TranscodeSettings,
+    metadata: &VideoMetadata,
+) -> Option<f64> {
+    let source_overall_bitrate = metadata.source_overall_bitrate.filter(|b| *b > 0)?;
+    let source_audio_bitrate = metadata.source_audio_bitrate.unwrap_or(192_000) as f64;
+    let target_audio_bitrate = target_audio_bitrate(settings, metadata, source_audio_bitrate);
+    let target_video_bitrate = settings.target_bitrate as f64 * 1000.0;
+
+    Some(
+        ((target_video_bitrate + target_audio_bitrate) / source_overall_bitrate as f64)
+            .clamp(0.01, 5.0),
     )
 }
 
+/// 基準（x264のCRF 23相当）に対するビットレートの倍率
+/// CRF±divisorで約2倍の変化とし、VP9/AV1はエンコーダーの尺度に換算して計算する
+fn crf_factor(settings: &TranscodeSettings, divisor: f64) -> f64 {
+    let encoder = settings.video_codec.encoder_name(&settings.hwaccel);
+    let reference = encoder_crf(23, encoder) as f64;
+    2.0_f64.powf((reference - settings.effective_crf(encoder) as f64) / divisor)
+}
+
 /// ソースビットレートを基にした予測（高精度）
 fn estimate_from_source_bitrate(
     settings: &TranscodeSettings,
@@ -557,6 +877,7 @@ fn estimate_from_source_bitrate(
     source_resolution: (u32, u32),
     target_resolution: (u32, u32),
     source_fps: f64,
+    target_fps: f64,
 ) -> f64 {
     // === 1. ターゲットビットレートを推定 ===
     // CRF→ビットレートの変換は動画の特性に依存するが、
@@ -569,8 +890,9 @@ fn estimate_from_source_bitrate(
         VideoCodec::H265 => 5.5,
         VideoCodec::Vp9 => 5.8,
         VideoCodec::Av1 => 5.0,
+        VideoCodec::Copy => 1.0, // 未使用（estimate_stream_copyで計算）
     };
-    let crf_factor = 2.0_f64.powf((23.0 - settings.crf as f64) / crf_divisor);
+    let crf_factor = crf_factor(settings, crf_divisor);
 
     // === 2. 解像度変換による影響 ===
     let source_pixels = source_resolution.0 as f64 * source_resolution.1 as f64;
@@ -589,6 +911,7 @@ fn estimate_from_source_bitrate(
         VideoCodec::H265 => 4.0, // H.265: 約4Mbps
         VideoCodec::Vp9 => 3.6,  // VP9: 約3.6Mbps
         VideoCodec::Av1 => 2.3,  // AV1: 約2.3Mbps
+        VideoCodec::Copy => 0.0, // 未使用
     };
 
     // 元のビットレートと典型値の比率から、動画の複雑さを推定
@@ -601,9 +924,12 @@ fn estimate_from_source_bitrate(
     // 典型的な値（10Mbps程度）との比較
     let complexity_factor = (normalized_source_mbps / 10.0).sqrt().clamp(0.5, 2.0);
 
+    // フレームレート変換による影響（元のFPSを維持する場合は1.0）
+    let fps_factor = (target_fps / source_fps.max(1.0)).powf(0.9);
+
     // === 4. ターゲットビットレートを計算 ===
     let base_target_mbps =
-        typical_bitrate_mbps * crf_factor * resolution_factor * complexity_factor;
+        typical_bitrate_mbps * crf_factor * resolution_factor * complexity_factor * fps_factor;
 
     // === 5. プリセット係数 ===
     let preset_factor = match settings.preset {
@@ -637,11 +963,8 @@ fn estimate_from_source_bitrate(
 
     // === 9. 音声部分の処理 ===
     let source_audio_bitrate = metadata.source_audio_bitrate.unwrap_or(192_000); // デフォルト192kbps
-    let target_audio_bitrate = match settings.audio_codec {
-        AudioCodec::Copy => source_audio_bitrate as f64,
-        AudioCodec::Aac | AudioCodec::Mp3 => settings.audio_bitrate as f64 * 1000.0,
-        AudioCodec::Flac => source_audio_bitrate as f64 * 2.5, // FLACは約2.5倍
-    };
+    let target_audio_bitrate =
+        target_audio_bitrate(settings, metadata, source_audio_bitrate as f64);
 
     // 全体に対する音声の割合
     let total_source_bitrate = metadata
@@ -663,7 +986,7 @@ fn estimate_from_compression_ratio(
     metadata: &VideoMetadata,
     source_resolution: (u32, u32),
     target_resolution: (u32, u32),
-    source_fps: f64,
+    target_fps: f64,
 ) -> f64 {
     // === 1. CRF係数（コーデック別の減衰率を使用）===
     let crf_divisor = match settings.video_codec {
@@ -671,8 +994,9 @@ fn estimate_from_compression_ratio(
         VideoCodec::H265 => 5.5,
         VideoCodec::Vp9 => 5.8,
         VideoCodec::Av1 => 5.0,
+        VideoCodec::Copy => 1.0, // 未使用（estimate_stream_copyで計算）
     };
-    let crf_factor = 2.0_f64.powf((23.0 - settings.crf as f64) / crf_divisor);
+    let crf_factor = crf_factor(settings, crf_divisor);
 
     // === 2. 解像度係数（改良版）===
     // ピクセル数比率^0.95 × 短辺比率^0.05（極端なアスペクト比で補正）
@@ -694,7 +1018,7 @@ fn estimate_from_compression_ratio(
 
     // === 3. フレームレート係数 ===
     // (fps / 30)^0.9 - 60fpsでも単純に2倍にはならない（GOP効率）
-    let fps_factor = (source_fps / 30.0).powf(0.9);
+    let fps_factor = (target_fps / 30.0).powf(0.9);
 
     // === 4. 動き量補正（最重要）===
     let motion_factor = metadata.content_type.motion_factor();
@@ -706,6 +1030,7 @@ fn estimate_from_compression_ratio(
         VideoCodec::H265 => 1.00, // H.265を基準
         VideoCodec::Vp9 => 0.90,  // VP9は10%小さい
         VideoCodec::Av1 => 0.57,  // AV1は43%小さい（SVT-AV1基準）
+        VideoCodec::Copy => 1.00, // 未使用
     };
 
     // === 6. プリセット係数（実測値）===
@@ -763,7 +1088,8 @@ fn estimate_from_compression_ratio(
                 };
                 mb_per_hour * duration_hours
             }
-            AudioCodec::Flac => 100.0 * duration_hours, // FLAC: 約100MB/時間
+            // FLAC: 約100MB/時間
+            AudioCodec::Flac => 100.0 * duration_hours * audio_channel_ratio(settings, metadata),
         }
     } else {
         0.0 // 長さ不明の場合は後で比率で計算
@@ -796,7 +1122,7 @@ fn estimate_from_compression_ratio(
                 let bitrate_ratio = settings.audio_bitrate as f64 / 192.0;
                 audio_ratio * bitrate_ratio * 0.8
             }
-            AudioCodec::Flac => audio_ratio * 2.0,
+            AudioCodec::Flac => audio_ratio * 2.0 * audio_channel_ratio(settings, metadata),
         };
 
         (video_ratio * video_compression + audio_factor)
@@ -817,7 +1143,7 @@ fn estimate_from_compression_ratio(
                 let bitrate_ratio = settings.audio_bitrate as f64 / 192.0;
                 audio_ratio * bitrate_ratio * 0.8
             }
-            AudioCodec::Flac => audio_ratio * 2.0,
+            AudioCodec::Flac => audio_ratio * 2.0 * audio_channel_ratio(settings, metadata),
         };
 
         (video_ratio * video_compression + audio_factor)
@@ -844,3 +1170,365 @@ pub fn estimate_compression_ratio_simple(
 
     (crf_factor * resolution_factor).max(0.05).min(2.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcoder::FrameRate;
+
+    #[test]
+    fn test_crf_factor_uses_encoder_scale() {
+        let mut settings = TranscodeSettings {
+            hwaccel: HwAccelType::Software,
+            ..Default::default()
+        };
+        // 基準のCRFではどのコーデックでも1倍
+        for codec in [VideoCodec::H264, VideoCodec::Vp9, VideoCodec::Av1] {
+            settings.video_codec = codec;
+            assert_eq!(crf_factor(&settings, 5.0), 1.0);
+        }
+
+        // AV1はCRF 28（x264の尺度）を36として計算する
+        settings.crf = 28;
+        assert!((crf_factor(&settings, 5.0) - 2.0_f64.powf(-6.0 / 5.0)).abs() < 1e-9);
+        settings.video_codec = VideoCodec::H264;
+        assert!((crf_factor(&settings, 6.0) - 2.0_f64.powf(-5.0 / 6.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_progress_line_size_and_bitrate() {
+        let mut info = FfmpegProgressInfo::default();
+        assert!(!info.parse_progress_line("frame=120"));
+        assert!(!info.parse_progress_line("total_size=1048576"));
+        assert!(!info.parse_progress_line("bitrate=1234.5kbits/s"));
+        assert!(info.parse_progress_line("progress=continue"));
+        assert_eq!(info.size, 1_048_576);
+        assert_eq!(info.bitrate, 1234.5);
+
+        // 開始直後はN/Aになる
+        assert!(!info.parse_progress_line("bitrate=N/A"));
+        assert!(!info.parse_progress_line("total_size=N/A"));
+        assert_eq!(info.bitrate, 0.0);
+        assert_eq!(info.size, 0);
+    }
+
+    #[test]
+    fn test_parse_progress_line_time_variants() {
+        // 実際の-progress出力（エンコーダーやFFmpegのバージョンで出力されるキーが異なる）
+        let cases: &[(&str, &str, &[f64])] = &[
+            (
+                // libx264、-ss で切り出した直後は負の時間になる
+                "x264 with -ss",
+                "frame=0\nfps=0.00\nstream_0_0_q=0.0\nbitrate=N/A\ntotal_size=48\n\
+                 out_time_us=-66667\nout_time_ms=-66667\nout_time=-00:00:00.066667\n\
+                 dup_frames=0\ndrop_frames=0\nspeed=N/A\nprogress=continue\n\
+                 frame=142\nfps=47.21\nstream_0_0_q=28.0\nbitrate=1024.3kbits/s\n\
+                 total_size=589824\nout_time_us=4604000\nout_time_ms=4604000\n\
+                 out_time=00:00:04.604000\ndup_frames=0\ndrop_frames=0\nspeed=1.53x\n\
+                 progress=continue",
+                &[0.0, 4.604],
+            ),
+            (
+                // h264_nvenc、開始直後は時間がN/A
+                "nvenc",
+                "frame=0\nfps=0.00\nstream_0_0_q=0.0\nbitrate=N/A\ntotal_size=N/A\n\
+                 out_time_us=N/A\nout_time_ms=N/A\nout_time=N/A\ndup_frames=0\n\
+                 drop_frames=0\nspeed=N/A\nprogress=continue\n\
+                 frame=1290\nfps=429.87\nstream_0_0_q=30.0\nbitrate=5230.1kbits/s\n\
+                 total_size=28180480\nout_time_us=43043000\nout_time_ms=43043000\n\
+                 out_time=00:00:43.043000\ndup_frames=0\ndrop_frames=0\nspeed=14.3x\n\
+                 progress=continue",
+                &[0.0, 43.043],
+            ),
+            (
+                // libvpx-vp9、先読みのためフレームが進んでも時間がN/Aのブロックがある
+                "libvpx",
+                "frame=24\nfps=8.00\nstream_0_0_q=0.0\nbitrate=2811.2kbits/s\n\
+                 total_size=351232\nout_time_us=1000000\nout_time_ms=1000000\n\
+                 out_time=00:00:01.000000\ndup_frames=0\ndrop_frames=0\nspeed=0.333x\n\
+                 progress=continue\n\
+                 frame=48\nfps=8.10\nstream_0_0_q=0.0\nbitrate=N/A\ntotal_size=351232\n\
+                 out_time_us=N/A\nout_time_ms=N/A\nout_time=N/A\ndup_frames=0\n\
+                 drop_frames=0\nspeed=N/A\nprogress=continue\n\
+                 frame=72\nfps=8.05\nstream_0_0_q=0.0\nbitrate=2760.4kbits/s\n\
+                 total_size=1036288\nout_time_us=3003000\nout_time_ms=3003000\n\
+                 out_time=00:00:03.003000\ndup_frames=0\ndrop_frames=0\nspeed=0.334x\n\
+                 progress=end",
+                &[1.0, 1.0, 3.003],
+            ),
+            (
+                // 古いビルドなど out_time_ms だけを出力する場合（単位はマイクロ秒）
+                "out_time_ms only",
+                "frame=300\nfps=60.00\nout_time_ms=10010000\nspeed=2.00x\nprogress=continue",
+                &[10.01],
+            ),
+            (
+                // out_time_us を優先（out_time が先に来ても上書きしない）
+                "out_time_us preferred",
+                "frame=300\nout_time=00:00:09.000000\nout_time_us=10010000\n\
+                 out_time=00:00:09.000000\nprogress=continue",
+                &[10.01],
+            ),
+        ];
+
+        for (name, output, expected) in cases {
+            let mut info = FfmpegProgressInfo::default();
+            let mut times = Vec::new();
+            for line in output.lines() {
+                if info.parse_progress_line(line) {
+                    times.push(info.time_secs);
+                    info.start_next_block();
+                }
+            }
+            assert_eq!(times.len(), expected.len(), "{}", name);
+            for (time, expected) in times.iter().zip(expected.iter()) {
+                assert!((time - expected).abs() < 1e-9, "{}: {}", name, time);
+            }
+        }
+    }
+
+    #[test]
+    fn test_speed_estimator_smooths_speed() {
+        let mut estimator = SpeedEstimator::default();
+        assert_eq!(estimator.update(0.0), None);
+        assert_eq!(estimator.remaining_secs(100.0, 10.0), None);
+
+        assert_eq!(estimator.update(2.0), Some(2.0));
+        // 残り90秒を2倍速で変換
+        assert_eq!(estimator.remaining_secs(100.0, 10.0), Some(45.0));
+
+        // 一時的な速度低下は平滑化される
+        let speed = estimator.update(1.0).unwrap();
+        assert!((speed - 1.8).abs() < 1e-9);
+        // N/A（0）は前回の値を維持
+        assert_eq!(estimator.update(0.0), Some(speed));
+
+        // 速度低下が続けば追従する
+        for _ in 0..30 {
+            estimator.update(1.0);
+        }
+        assert!((estimator.update(1.0).unwrap() - 1.0).abs() < 0.01);
+        // 総時間が不明なら推定しない
+        assert_eq!(estimator.remaining_secs(0.0, 10.0), None);
+    }
+
+    #[test]
+    fn test_guess_content_type_from_file_name() {
+        assert_eq!(
+            ContentType::guess_from_file_name("[Sub] Anime S01E01.mkv"),
+            ContentType::Anime
+        );
+        assert_eq!(
+            ContentType::guess_from_file_name("アニメ第1話.mp4"),
+            ContentType::Anime
+        );
+        assert_eq!(
+            ContentType::guess_from_file_name("ScreenCapture 2024-01-01.mp4"),
+            ContentType::ScreenRecord
+        );
+        assert_eq!(
+            ContentType::guess_from_file_name("会議の録画.mp4"),
+            ContentType::ScreenRecord
+        );
+        assert_eq!(
+            ContentType::guess_from_file_name("holiday.mov"),
+            ContentType::Normal
+        );
+    }
+
+    #[test]
+    fn test_metadata_summary() {
+        let metadata = VideoMetadata {
+            resolution: Some((1920, 1080)),
+            fps: Some(24000.0 / 1001.0),
+            video_codec: Some("hevc".to_string()),
+            source_video_bitrate: Some(8_200_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            metadata.summary(),
+            "1920x1080 · 23.98fps · hevc · 8.20 Mbps"
+        );
+
+        // 取得できた項目だけを表示
+        let metadata = VideoMetadata {
+            fps: Some(30.0),
+            source_video_bitrate: Some(850_000),
+            ..Default::default()
+        };
+        assert_eq!(metadata.summary(), "30fps · 850 kbps");
+        assert_eq!(VideoMetadata::default().summary(), "—");
+    }
+
+    #[test]
+    fn test_frame_rate_conversion_reduces_estimate() {
+        let with_bitrate = VideoMetadata {
+            resolution: Some((1920, 1080)),
+            fps: Some(60.0),
+            duration: Some(600.0),
+            source_video_bitrate: Some(12_000_000),
+            source_audio_bitrate: Some(192_000),
+            ..Default::default()
+        };
+        let without_bitrate = VideoMetadata {
+            source_video_bitrate: None,
+            source_audio_bitrate: None,
+            ..with_bitrate.clone()
+        };
+
+        let original = TranscodeSettings::default();
+        let converted = TranscodeSettings {
+            frame_rate: FrameRate::Fps30,
+            ..Default::default()
+        };
+
+        for metadata in [&with_bitrate, &without_bitrate] {
+            let original_ratio = estimate_compression_ratio_advanced(&original, metadata);
+            let converted_ratio = estimate_compression_ratio_advanced(&converted, metadata);
+            assert!(
+                converted_ratio < original_ratio,
+                "30fps ({}) should be smaller than 60fps ({})",
+                converted_ratio,
+                original_ratio
+            );
+        }
+    }
+
+    #[test]
+    fn test_crf_from_target_size() {
+        const GB: u64 = 1024 * 1024 * 1024;
+        let metadata = VideoMetadata {
+            resolution: Some((1920, 1080)),
+            fps: Some(30.0),
+            duration: Some(3600.0),
+            source_video_bitrate: Some(22_000_000),
+            source_audio_bitrate: Some(192_000),
+            ..Default::default()
+        };
+        let mut settings = TranscodeSettings {
+            video_codec: VideoCodec::H265,
+            ..Default::default()
+        };
+        let predicted = |crf: u8| {
+            let mut settings = settings.clone();
+            settings.crf = crf;
+            (10.0 * GB as f64 * estimate_compression_ratio_advanced(&settings, &metadata)) as u64
+        };
+
+        // 隣のCRFより目標サイズに近い
+        let crf = crf_from_target_size(10 * GB, 2 * GB, &settings, &metadata);
+        assert!(crf > 0 && crf < 51);
+        let diff = |crf: u8| predicted(crf).abs_diff(2 * GB);
+        assert!(diff(crf) <= diff(crf - 1));
+        assert!(diff(crf) <= diff(crf + 1));
+
+        // 目標が小さいほどCRFは大きくなる
+        assert!(crf_from_target_size(10 * GB, GB, &settings, &metadata) > crf);
+
+        // 目標サイズモードはCRFモードに置き換わる
+        settings.rate_control = RateControlMode::TargetSize;
+        settings.target_size_mb = 2048;
+        let resolved = settings.resolve_target_size(10 * GB, &metadata);
+        assert_eq!(resolved.rate_control, RateControlMode::Crf);
+        assert_eq!(resolved.crf, crf);
+    }
+
+    #[test]
+    fn test_10bit_output_increases_estimate() {
+        let metadata = VideoMetadata {
+            resolution: Some((1920, 1080)),
+            fps: Some(30.0),
+            duration: Some(600.0),
+            source_video_bitrate: Some(12_000_000),
+            source_audio_bitrate: Some(192_000),
+            ..Default::default()
+        };
+
+        let mut settings = TranscodeSettings {
+            video_codec: VideoCodec::H265,
+            ..Default::default()
+        };
+        let ratio_8bit = estimate_compression_ratio_advanced(&settings, &metadata);
+        settings.pixel_format = crate::transcoder::PixelFormat::Yuv420p10le;
+        let ratio_10bit = estimate_compression_ratio_advanced(&settings, &metadata);
+        assert!((ratio_10bit / ratio_8bit - 1.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_remux_estimate_keeps_source_size() {
+        use crate::transcoder::{AudioCodec, VideoCodec};
+
+        let metadata = VideoMetadata {
+            resolution: Some((1920, 1080)),
+            source_video_bitrate: Some(8_000_000),
+            source_audio_bitrate: Some(320_000),
+            ..Default::default()
+        };
+        let mut settings = TranscodeSettings {
+            video_codec: VideoCodec::Copy,
+            audio_codec: AudioCodec::Copy,
+            ..Default::default()
+        };
+        assert!((estimate_compression_ratio_advanced(&settings, &metadata) - 0.98).abs() < 1e-9);
+
+        // 音声だけ再エンコードする場合は音声の分だけ小さくなる
+        settings.audio_codec = AudioCodec::Aac;
+        settings.audio_bitrate = 128;
+        let ratio = estimate_compression_ratio_advanced(&settings, &metadata);
+        assert!(ratio < 0.98 && ratio > 0.9, "ratio = {}", ratio);
+    }
+
+    #[test]
+    fn test_flac_estimate_follows_channel_count() {
+        use crate::transcoder::{AudioChannels, AudioCodec, ContainerFormat};
+
+        let metadata = VideoMetadata {
+            resolution: Some((1920, 1080)),
+            source_video_bitrate: Some(8_000_000),
+            source_audio_bitrate: Some(1_500_000),
+            source_overall_bitrate: Some(9_500_000),
+            source_audio_channels: Some(6),
+            ..Default::default()
+        };
+        let mut settings = TranscodeSettings {
+            container: ContainerFormat::Mkv,
+            audio_codec: AudioCodec::Flac,
+            ..Default::default()
+        };
+        let surround = estimate_compression_ratio_advanced(&settings, &metadata);
+
+        // 5.1chをステレオにすると音声は1/3になる
+        settings.audio_channels = AudioChannels::Stereo;
+        let stereo = estimate_compression_ratio_advanced(&settings, &metadata);
+        assert!(stereo < surround, "{} >= {}", stereo, surround);
+
+        // AACは-b:aで決まるためチャンネル数に依らない
+        settings.audio_codec = AudioCodec::Aac;
+        let aac_stereo = estimate_compression_ratio_advanced(&settings, &metadata);
+        settings.audio_channels = AudioChannels::Original;
+        let aac_original = estimate_compression_ratio_advanced(&settings, &metadata);
+        assert!((aac_stereo - aac_original).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_size_capped_preset_estimate() {
+        let metadata = VideoMetadata {
+            resolution: Some((1920, 1080)),
+            duration: Some(120.0),
+            source_video_bitrate: Some(10_000_000),
+            source_audio_bitrate: Some(192_000),
+            source_overall_bitrate: Some(10_192_000),
+            ..Default::default()
+        };
+        let settings = TranscodeSettings::preset_web_8mb(metadata.duration);
+
+        // 元ファイル（約153MB）に対して8MB以内に収まる予測になる
+        let source_size = 10_192_000.0 / 8.0 * 120.0;
+        let estimated = source_size * estimate_compression_ratio_advanced(&settings, &metadata);
+        assert!(
+            estimated < 8_000_000.0 && estimated > 7_000_000.0,
+            "estimated = {}",
+            estimated
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! 変換後の画質評価（VMAF/SSIM）
+//!
+//! 動画全体をVMAFで評価すると変換以上に時間がかかるため、
+//! 等間隔に選んだ数秒の区間だけを元の動画と比較する
+
+use anyhow::{bail, Context, Result};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use super::{CropMode, FrameRate, TranscodeSettings, VideoCodec, VideoMetadata};
+use crate::ffmpeg::{background_command, FfmpegDetector, FfmpegInfo};
+
+/// 1区間の長さ（秒）
+const SEGMENT_SECS: f64 = 5.0;
+
+/// 画質の評価指標
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QualityMetric {
+    /// VMAF（0〜100）
+    Vmaf,
+    /// SSIM（0〜1、libvmafを含まないFFmpegビルド用）
+    Ssim,
+}
+
+impl QualityMetric {
+    /// 表示名を取得
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            QualityMetric::Vmaf => "VMAF",
+            QualityMetric::Ssim => "SSIM",
+        }
+    }
+
+    /// FFmpegのフィルター名
+    fn filter_name(&self) -> &'static str {
+        match self {
+            QualityMetric::Vmaf => "libvmaf",
+            QualityMetric::Ssim => "ssim",
+        }
+    }
+
+    /// FFmpegビルドで使える指標を選ぶ
+    fn detect(ffmpeg_path: &PathBuf) -> Self {
+        if FfmpegDetector::available_filters(ffmpeg_path).contains("libvmaf") {
+            QualityMetric::Vmaf
+        } else {
+            QualityMetric::Ssim
+        }
+    }
+}
+
+/// 画質の評価結果
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QualityScore {
+    /// 評価指標
+    pub metric: QualityMetric,
+    /// 全区間の平均スコア
+    pub score: f64,
+}
+
+impl QualityScore {
+    /// VMAFのスコア（SSIMで評価した場合はNone）
+    pub fn vmaf(&self) -> Option<f64> {
+        (self.metric == QualityMetric::Vmaf).then_some(self.score)
+    }
+
+    /// 表示用のラベル（例: "VMAF 94.2"）
+    pub fn label(&self) -> String {
+        match self.metric {
+            QualityMetric::Vmaf => format!("VMAF {:.1}", self.score),
+            QualityMetric::Ssim => format!("SSIM {:.3}", self.score),
+        }
+    }
+}
+
+/// 出力ファイルの画質を元の動画と比較して評価
+pub fn analyze_quality(
+    ffmpeg_info: &FfmpegInfo,
+    source_path: &Path,
+    output_path: &Path,
+    settings: &TranscodeSettings,
+    source: &VideoMetadata,
+    samples: u32,
+) -> Result<QualityScore> {
+    // フレームの対応が崩れる変換では比較できない
+    if settings.video_codec == VideoCodec::Copy {
+        bail!("映像を再エンコードしていないため評価しません");
+    }
+    if settings.frame_rate != FrameRate::Original {
+        bail!("フレームレートを変換した場合は評価できません");
+    }
+    if settings.crop_mode != CropMode::Off {
+        bail!("クロップした場合は評価できません");
+    }
+
+    let duration = source
+        .duration
+        .filter(|d| *d > 0.0)
+        .context("元の動画の長さが不明です")?;
+    // 元の動画を出力の解像度に合わせて比較する
+    let resolution = ffmpeg_info
+        .probe_video(output_path)
+        .context("出力ファイルを読み込めません")?
+        .resolution
+        .context("出力ファイルの解像度が不明です")?;
+
+    let metric = QualityMetric::detect(&ffmpeg_info.ffmpeg_path);
+    let segments = sample_segments(duration, samples);
+    info!(
+        "Analyzing quality of {:?} with {} ({} segments)",
+        output_path,
+        metric.display_name(),
+        segments.len()
+    );
+
+    let mut weighted_sum = 0.0;
+    let mut total_secs = 0.0;
+    for (start, secs) in segments {
+        let output = background_command(&ffmpeg_info.ffmpeg_path)
+            .args(build_args(
+                metric,
+                source_path,
+                output_path,
+                resolution,
+                start,
+                secs,
+            ))
+            .output()
+            .context("Failed to execute ffmpeg")?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            bail!(
+                "{}の計算に失敗しました: {}",
+                metric.display_name(),
+                stderr.trim()
+            );
+        }
+        let score = parse_score(metric, &stderr)
+            .with_context(|| format!("{}のスコアを読み取れません", metric.display_name()))?;
+        debug!("{} at {:.1}s: {}", metric.display_name(), start, score);
+
+        weighted_sum += score * secs;
+        total_secs += secs;
+    }
+
+    Ok(QualityScore {
+        metric,
+        score: weighted_sum / total_secs,
+    })
+}
+
+/// 評価する区間（開始位置, 長さ）を等間隔に選ぶ
+/// 短い動画は全体を1区間として評価する
+fn sample_segments(duration: f64, samples: u32) -> Vec<(f64, f64)> {
+    let samples = samples.max(1);
+    if duration <= SEGMENT_SECS * samples as f64 {
+        return vec![(0.0, duration)];
+    }
+
+    // 各区間を均等に分けた範囲の中央に置く
+    let spacing = duration / samples as f64;
+    (0..samples)
+        .map(|i| {
+            let start = spacing * i as f64 + (spacing - SEGMENT_SECS) / 2.0;
+            (start, SEGMENT_SECS)
+        })
+        .collect()
+}
+
+/// 1区間を評価するFFmpegの引数を構築
+/// 入力0が出力ファイル（評価対象）、入力1が元の動画（基準）
+fn build_args(
+    metric: QualityMetric,
+    source_path: &Path,
+    output_path: &Path,
+    resolution: (u32, u32),
+    start: f64,
+    secs: f64,
+) -> Vec<OsString> {
+    let start = format!("{:.3}", start);
+    let secs = format!("{:.3}", secs);
+    let (width, height) = resolution;
+    let filter = format!(
+        "[0:v]format=yuv420p,setpts=PTS-STARTPTS[dist];\
+         [1:v]scale={}:{}:flags=bicubic,format=yuv420p,setpts=PTS-STARTPTS[ref];\
+         [dist][ref]{}",
+        width,
+        height,
+        metric.filter_name()
+    );
+
+    let mut args: Vec<OsString> = vec!["-hide_banner".into(), "-nostdin".into()];
+    for path in [output_path, source_path] {
+        args.extend(["-ss", start.as_str(), "-t", secs.as_str(), "-i"].map(OsString::from));
+        args.push(path.into());
+    }
+    args.extend(["-an", "-lavfi", filter.as_str(), "-f", "null", "-"].map(OsString::from));
+    args
+}
+
+/// FFmpegのstderrからスコアを読み取る
+/// libvmaf: "[Parsed_libvmaf_4 @ 0x...] VMAF score: 94.231842"
+/// ssim: "[Parsed_ssim_4 @ 0x...] SSIM Y:0.991 (20.4) U:0.995 (23.1) V:0.994 (22.6) All:0.992 (21.1)"
+fn parse_score(metric: QualityMetric, stderr: &str) -> Option<f64> {
+    let marker = match metric {
+        QualityMetric::Vmaf => "VMAF score:",
+        QualityMetric::Ssim => "All:",
+    };
+    let line = stderr.lines().rev().find(|line| match metric {
+        QualityMetric::Vmaf => line.contains(marker),
+        QualityMetric::Ssim => line.contains(" SSIM ") && line.contains(marker),
+    })?;
+    let rest = &line[line.find(marker)? + marker.len()..];
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_segments() {
+        // 100秒を4区間: 25秒ごとの中央に5秒ずつ
+        assert_eq!(
+            sample_segments(100.0, 4),
+            vec![(10.0, 5.0), (35.0, 5.0), (60.0, 5.0), (85.0, 5.0)]
+        );
+        // 区間の合計より短い動画は全体
+        assert_eq!(sample_segments(12.0, 4), vec![(0.0, 12.0)]);
+        assert_eq!(sample_segments(60.0, 0), vec![(27.5, 5.0)]);
+    }
+
+    #[test]
+    fn test_score_vmaf() {
+        let vmaf = QualityScore {
+            metric: QualityMetric::Vmaf,
+            score: 94.21,
+        };
+        assert_eq!(vmaf.vmaf(), Some(94.21));
+        assert_eq!(vmaf.label(), "VMAF 94.2");
+
+        // SSIMのスコアはVMAFとして扱わない
+        let ssim = QualityScore {
+            metric: QualityMetric::Ssim,
+            score: 0.98,
+        };
+        assert_eq!(ssim.vmaf(), None);
+        assert_eq!(ssim.label(), "SSIM 0.980");
+    }
+
+    #[test]
+    fn test_parse_score() {
+        let vmaf = "\
+frame=  120 fps= 30 q=-0.0 Lsize=N/A time=00:00:05.00 bitrate=N/A speed=1.2x
+[Parsed_libvmaf_4 @ 0x5581c7d4c0c0] VMAF score: 94.231842
+";
+        assert_eq!(parse_score(QualityMetric::Vmaf, vmaf), Some(94.231842));
+
+        let ssim = "\
+[Parsed_ssim_4 @ 0x55d0a4f3e2c0] SSIM Y:0.991325 (20.617807) U:0.995237 (23.220466) V:0.994512 (22.604812) All:0.992513 (21.255613)
+";
+        assert_eq!(parse_score(QualityMetric::Ssim, ssim), Some(0.992513));
+
+        // スコアの行がない（フィルターの初期化失敗など）
+        assert_eq!(parse_score(QualityMetric::Vmaf, ssim), None);
+        assert_eq!(parse_score(QualityMetric::Ssim, vmaf), None);
+    }
+
+    #[test]
+    fn test_build_args() {
+        let args = build_args(
+            QualityMetric::Vmaf,
+            Path::new("in.mkv"),
+            Path::new("out.mp4"),
+            (1280, 720),
+            10.0,
+            5.0,
+        );
+        let args: Vec<_> = args.iter().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(
+            args,
+            [
+                "-hide_banner",
+                "-nostdin",
+                "-ss",
+                "10.000",
+                "-t",
+                "5.000",
+                "-i",
+                "out.mp4",
+                "-ss",
+                "10.000",
+                "-t",
+                "5.000",
+                "-i",
+                "in.mkv",
+                "-an",
+                "-lavfi",
+                "[0:v]format=yuv420p,setpts=PTS-STARTPTS[dist];\
+                 [1:v]scale=1280:720:flags=bicubic,format=yuv420p,setpts=PTS-STARTPTS[ref];\
+                 [dist][ref]libvmaf",
+                "-f",
+                "null",
+                "-",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quality_score_label() {
+        let vmaf = QualityScore {
+            metric: QualityMetric::Vmaf,
+            score: 94.2318,
+        };
+        assert_eq!(vmaf.label(), "VMAF 94.2");
+        let ssim = QualityScore {
+            metric: QualityMetric::Ssim,
+            score: 0.99251,
+        };
+        assert_eq!(ssim.label(), "SSIM 0.993");
+    }
+}
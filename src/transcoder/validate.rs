@@ -0,0 +1,439 @@
+//! 変換開始前の設定の検証
+//!
+//! FFmpegを起動してから失敗する組み合わせを事前に見つけ、理由を日本語で示す
+//! 変換はできるが設定の一部が反映されない組み合わせは警告として扱う
+
+use super::{
+    AudioChannels, AudioCodec, FrameRate, PixelFormat, RateControlMode, TranscodeSettings,
+    VideoCodec, VideoMetadata, VideoResolution, X264Profile,
+};
+
+/// 問題の重大度
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationSeverity {
+    /// 変換はできるが設定の一部が反映されない
+    Warning,
+    /// FFmpegが失敗するため変換しない
+    Error,
+}
+
+/// 設定の問題
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// 重大度
+    pub severity: ValidationSeverity,
+    /// 表示用のメッセージ
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: ValidationSeverity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: ValidationSeverity::Warning,
+            message: message.into(),
+        }
+    }
+
+    /// 変換できない問題か
+    pub fn is_error(&self) -> bool {
+        self.severity == ValidationSeverity::Error
+    }
+}
+
+impl TranscodeSettings {
+    /// 設定の組み合わせを検証（メタデータがあれば元の動画との組み合わせも確認）
+    /// HWアクセラレーションは解決済みであること（「自動検出」はソフトウェアとして扱う）
+    pub fn validate(&self, metadata: Option<&VideoMetadata>) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        // コンテナに格納できない音声コーデック（MP4のFLACなど）
+        if let Some(codec) = self.audio_codec.probe_name() {
+            if !self.container.supports_audio_codec(codec) {
+                issues.push(ValidationIssue::error(format!(
+                    "{}は{}に格納できません。MKVを選ぶか、音声コーデックを変更してください",
+                    self.audio_codec.display_name(),
+                    self.container.display_name()
+                )));
+            }
+        }
+
+        // エンコーダーが出力できないチャンネル数（MP3の5.1chなど）
+        if let (Some(channels), Some(max)) =
+            (self.audio_channels.count(), self.audio_codec.max_channels())
+        {
+            if channels > max {
+                issues.push(ValidationIssue::error(format!(
+                    "{}は{}に対応していません。ステレオ以下にするか、音声コーデックを変更してください",
+                    self.audio_codec.display_name(),
+                    self.audio_channels.display_name()
+                )));
+            }
+        }
+
+        // 音声をコピーする場合はチャンネル数・サンプルレートを変えられない
+        if self.audio_codec == AudioCodec::Copy
+            && (self.audio_channels != AudioChannels::Original || self.audio_sample_rate.is_some())
+        {
+            issues.push(ValidationIssue::warning(
+                "音声をコピーするため、チャンネル数・サンプルレートの設定は反映されません",
+            ));
+        }
+
+        if let Some(metadata) = metadata {
+            if let Some(issue) = self.stream_copy_issue(
+                metadata.video_codec.as_deref(),
+                metadata.audio_codec.as_deref(),
+            ) {
+                issues.push(ValidationIssue::error(issue));
+            }
+        }
+
+        // 映像をコピーする場合はエンコーダーの設定を使わない
+        if self.video_codec == VideoCodec::Copy {
+            if self.resolution != VideoResolution::Original
+                || self.frame_rate != FrameRate::Original
+                || self.hdr_to_sdr
+            {
+                issues.push(ValidationIssue::warning(
+                    "映像をコピーするため、解像度・フレームレート・HDR変換の設定は反映されません",
+                ));
+            }
+            return issues;
+        }
+
+        let encoder = self.video_codec.encoder_name(&self.hwaccel);
+        if encoder == "libx264" {
+            self.validate_x264(metadata, &mut issues);
+        }
+
+        // 出力できないピクセルフォーマットは4:2:0 8bitにする
+        if !self.pixel_format.supported_by(encoder) {
+            issues.push(ValidationIssue::warning(format!(
+                "{}は{}を出力できないため、4:2:0 8bitで出力します",
+                encoder,
+                self.pixel_format.display_name()
+            )));
+        }
+
+        // 10bitで出力できないエンコーダーではHDRの色が正しく表示されない
+        if let Some(metadata) = metadata {
+            if metadata.is_hdr()
+                && !self.hdr_to_sdr
+                && !PixelFormat::Yuv420p10le.supported_by(encoder)
+            {
+                issues.push(ValidationIssue::warning(format!(
+                    "{}は10bitで出力できないため、HDRの動画の色が正しく表示されません。HDR→SDR変換を有効にしてください",
+                    encoder
+                )));
+            }
+        }
+
+        // レートコントロール
+        let is_qsv = encoder.ends_with("_qsv");
+        match self.rate_control {
+            RateControlMode::IcqQsv | RateControlMode::AvbrQsv if !is_qsv => {
+                let fallback = if self.rate_control == RateControlMode::IcqQsv {
+                    RateControlMode::Crf
+                } else {
+                    RateControlMode::Vbr
+                };
+                issues.push(ValidationIssue::warning(format!(
+                    "{}はIntel QSV専用のため、{}では{}として変換します",
+                    self.rate_control.display_name(),
+                    encoder,
+                    fallback.display_name()
+                )));
+            }
+            RateControlMode::Cqp if encoder == "libvpx-vp9" => {
+                issues.push(ValidationIssue::warning(
+                    "VP9はCQPに対応していないため、CRF（固定品質）として変換します",
+                ));
+            }
+            _ => {}
+        }
+        if matches!(
+            self.rate_control,
+            RateControlMode::Vbr | RateControlMode::AvbrQsv
+        ) && self.max_bitrate < self.target_bitrate
+        {
+            issues.push(ValidationIssue::warning(format!(
+                "最大ビットレート（{}kbps）が目標ビットレート（{}kbps）より低く設定されています",
+                self.max_bitrate, self.target_bitrate
+            )));
+        }
+
+        if encoder.ends_with("_amf") {
+            // AMFは-rc-lookaheadを受け付けず、先読みはプリアナリシスで行う
+            if self.lookahead > 0 && !self.amf_pre_analysis {
+                issues.push(ValidationIssue::warning(
+                    "AMFはルックアヘッドの設定を使いません。先読みするにはプリアナリシスを有効にしてください",
+                ));
+            }
+            if self.amf_rate_control_preanalysis
+                && !matches!(
+                    self.rate_control,
+                    RateControlMode::Cbr | RateControlMode::Vbr | RateControlMode::AvbrQsv
+                )
+            {
+                issues.push(ValidationIssue::warning(
+                    "AMFのプリエンコードはCBR/VBRでのみ効果があります",
+                ));
+            }
+        }
+
+        issues
+    }
+
+    /// libx264のプロファイルとBフレーム・ピクセルフォーマットの組み合わせを検証
+    fn validate_x264(&self, metadata: Option<&VideoMetadata>, issues: &mut Vec<ValidationIssue>) {
+        let profile = self.x264_profile;
+
+        if profile == X264Profile::Baseline && self.bframes > 0 {
+            issues.push(ValidationIssue::error(format!(
+                "H.264のBaselineプロファイルはBフレームに対応していません（Bフレーム数: {}）。Bフレームを0にするか、Main以上のプロファイルを選んでください",
+                self.bframes
+            )));
+        }
+
+        // 出力できないピクセルフォーマットは4:2:0 8bitになるため、出力できるものだけ確認する
+        if self.pixel_format.supported_by("libx264") && !profile.supports(self.pixel_format) {
+            issues.push(ValidationIssue::error(format!(
+                "H.264の{}プロファイルは{}に対応していません。High 4:4:4プロファイルを選ぶか、4:2:0 8bitにしてください",
+                profile.display_name(),
+                self.pixel_format.display_name()
+            )));
+        }
+
+        // 自動では8bitのソースをそのままの色差形式で渡すため、4:2:2/4:4:4はHigh 4:4:4が必要
+        if let Some(metadata) = metadata {
+            let pix_fmt = metadata.pix_fmt.as_deref().unwrap_or_default();
+            if self.pixel_format == PixelFormat::Auto
+                && !metadata.is_high_bit_depth()
+                && exceeds_420(pix_fmt)
+                && profile != X264Profile::High444
+            {
+                issues.push(ValidationIssue::error(format!(
+                    "元の動画が{}のため、H.264の{}プロファイルでは出力できません。ピクセルフォーマットを4:2:0 8bitにするか、High 4:4:4プロファイルを選んでください",
+                    pix_fmt,
+                    profile.display_name()
+                )));
+            }
+        }
+
+        // libx264は8bitでのみ出力する
+        if profile == X264Profile::High10 {
+            issues.push(ValidationIssue::warning(
+                "libx264は8bitで出力するため、High 10プロファイルを選んでも10bitにはなりません",
+            ));
+        }
+    }
+}
+
+/// 4:2:0より色差の情報が多いピクセルフォーマットか（ffprobeのpix_fmtで判定）
+fn exceeds_420(pix_fmt: &str) -> bool {
+    [
+        "yuv422", "yuvj422", "yuv444", "yuvj444", "gbr", "rgb", "bgr",
+    ]
+    .iter()
+    .any(|prefix| pix_fmt.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcoder::{ContainerFormat, HwAccelType};
+
+    fn software() -> TranscodeSettings {
+        TranscodeSettings {
+            hwaccel: HwAccelType::Software,
+            ..Default::default()
+        }
+    }
+
+    fn errors(settings: &TranscodeSettings, metadata: Option<&VideoMetadata>) -> Vec<String> {
+        settings
+            .validate(metadata)
+            .into_iter()
+            .filter(|issue| issue.is_error())
+            .map(|issue| issue.message)
+            .collect()
+    }
+
+    fn warnings(settings: &TranscodeSettings, metadata: Option<&VideoMetadata>) -> Vec<String> {
+        settings
+            .validate(metadata)
+            .into_iter()
+            .filter(|issue| !issue.is_error())
+            .map(|issue| issue.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_default_settings_are_valid() {
+        assert!(software().validate(None).is_empty());
+        assert!(software()
+            .validate(Some(&VideoMetadata {
+                video_codec: Some("h264".to_string()),
+                audio_codec: Some("aac".to_string()),
+                pix_fmt: Some("yuv420p".to_string()),
+                video_bit_depth: Some(8),
+                ..Default::default()
+            }))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_baseline_profile_rejects_bframes() {
+        let mut settings = software();
+        settings.x264_profile = X264Profile::Baseline;
+        settings.bframes = 3;
+        let errors = errors(&settings, None);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("Bフレーム数: 3"));
+
+        settings.bframes = 0;
+        assert!(settings.validate(None).is_empty());
+
+        // HWエンコーダーにはプロファイルを渡さない
+        settings.bframes = 3;
+        settings.hwaccel = HwAccelType::Nvenc;
+        assert!(settings.validate(None).is_empty());
+    }
+
+    #[test]
+    fn test_x264_profile_and_pixel_format() {
+        let mut settings = software();
+        settings.pixel_format = PixelFormat::Yuv422p;
+        assert_eq!(errors(&settings, None).len(), 1);
+        settings.x264_profile = X264Profile::High444;
+        assert!(errors(&settings, None).is_empty());
+
+        // 10bitはlibx264で出力できないため8bitで出力する（警告のみ）
+        settings.x264_profile = X264Profile::High10;
+        settings.pixel_format = PixelFormat::Yuv420p10le;
+        assert!(errors(&settings, None).is_empty());
+        assert_eq!(warnings(&settings, None).len(), 2);
+    }
+
+    #[test]
+    fn test_x264_rejects_422_source_with_auto_pixel_format() {
+        let source = |pix_fmt: &str, bit_depth: u32| VideoMetadata {
+            pix_fmt: Some(pix_fmt.to_string()),
+            video_bit_depth: Some(bit_depth),
+            ..Default::default()
+        };
+        let mut settings = software();
+        assert_eq!(errors(&settings, Some(&source("yuv422p", 8))).len(), 1);
+        assert_eq!(errors(&settings, Some(&source("yuvj444p", 8))).len(), 1);
+        // 10bitのソースは4:2:0 8bitに変換する
+        assert!(errors(&settings, Some(&source("yuv422p10le", 10))).is_empty());
+        assert!(errors(&settings, Some(&source("yuv420p", 8))).is_empty());
+
+        settings.pixel_format = PixelFormat::Yuv420p;
+        assert!(errors(&settings, Some(&source("yuv422p", 8))).is_empty());
+    }
+
+    #[test]
+    fn test_flac_in_mp4() {
+        let mut settings = software();
+        settings.audio_codec = AudioCodec::Flac;
+        assert_eq!(errors(&settings, None).len(), 1);
+        settings.container = ContainerFormat::Mkv;
+        assert!(settings.validate(None).is_empty());
+    }
+
+    #[test]
+    fn test_audio_channels_with_codec() {
+        let mut settings = software();
+        settings.audio_codec = AudioCodec::Mp3;
+        settings.audio_channels = AudioChannels::Surround51;
+        assert_eq!(errors(&settings, None).len(), 1);
+        settings.audio_channels = AudioChannels::Stereo;
+        settings.audio_sample_rate = Some(48_000);
+        assert!(settings.validate(None).is_empty());
+
+        // コピー時は反映されない
+        settings.audio_codec = AudioCodec::Copy;
+        assert!(errors(&settings, None).is_empty());
+        assert_eq!(warnings(&settings, None).len(), 1);
+    }
+
+    #[test]
+    fn test_stream_copy_issue_with_metadata() {
+        let mut settings = software();
+        settings.video_codec = VideoCodec::Copy;
+        settings.audio_codec = AudioCodec::Copy;
+        let metadata = VideoMetadata {
+            video_codec: Some("vp9".to_string()),
+            audio_codec: Some("opus".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(errors(&settings, Some(&metadata)).len(), 1);
+        // メタデータがなければ判断できない
+        assert!(settings.validate(None).is_empty());
+
+        // コピーでは映像の設定を使わない
+        settings.resolution = VideoResolution::Hd720;
+        assert_eq!(warnings(&settings, None).len(), 1);
+    }
+
+    #[test]
+    fn test_rate_control_fallbacks() {
+        let mut settings = software();
+        settings.video_codec = VideoCodec::Vp9;
+        settings.rate_control = RateControlMode::Cqp;
+        assert_eq!(warnings(&settings, None).len(), 1);
+
+        settings.video_codec = VideoCodec::H265;
+        settings.rate_control = RateControlMode::IcqQsv;
+        assert_eq!(warnings(&settings, None).len(), 1);
+        settings.hwaccel = HwAccelType::Qsv;
+        assert!(settings.validate(None).is_empty());
+
+        settings.rate_control = RateControlMode::Vbr;
+        settings.target_bitrate = 8000;
+        settings.max_bitrate = 4000;
+        assert_eq!(warnings(&settings, None).len(), 1);
+    }
+
+    #[test]
+    fn test_amf_lookahead_and_preencode() {
+        let mut settings = software();
+        settings.hwaccel = HwAccelType::Amf;
+        assert!(settings.validate(None).is_empty());
+
+        settings.amf_pre_analysis = false;
+        assert_eq!(warnings(&settings, None).len(), 1);
+        settings.lookahead = 0;
+        assert!(settings.validate(None).is_empty());
+
+        settings.amf_rate_control_preanalysis = true;
+        assert_eq!(warnings(&settings, None).len(), 1);
+        settings.rate_control = RateControlMode::Cbr;
+        assert!(settings.validate(None).is_empty());
+    }
+
+    #[test]
+    fn test_hdr_source_with_8bit_encoder() {
+        let metadata = VideoMetadata {
+            color_trc: Some("smpte2084".to_string()),
+            pix_fmt: Some("yuv420p10le".to_string()),
+            video_bit_depth: Some(10),
+            ..Default::default()
+        };
+        let mut settings = software();
+        assert_eq!(warnings(&settings, Some(&metadata)).len(), 1);
+        settings.hdr_to_sdr = true;
+        assert!(settings.validate(Some(&metadata)).is_empty());
+        settings.hdr_to_sdr = false;
+        settings.video_codec = VideoCodec::H265;
+        assert!(settings.validate(Some(&metadata)).is_empty());
+    }
+}
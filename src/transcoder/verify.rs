@@ -0,0 +1,119 @@
+//! 変換後の出力ファイルの検証
+//!
+//! 終了コードが0でも、ディスクの不調やコンテナの書き込み失敗で
+//! 途中までしか書き込まれていないことがあるため、ffprobeとデコードで確認する
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use super::{TranscodeSettings, VideoCodec, VideoMetadata};
+use crate::ffmpeg::{FfmpegInfo, ProbeResult};
+
+/// 長さの許容誤差（元の長さに対する割合）
+const DURATION_TOLERANCE: f64 = 0.01;
+
+/// 長さの許容誤差の下限（秒）
+/// 短い動画ではフレーム単位の差でも1%を超えるため
+const MIN_DURATION_TOLERANCE_SECS: f64 = 0.5;
+
+/// 末尾のデコード確認に使う秒数
+const TAIL_DECODE_SECS: u32 = 5;
+
+/// 出力ファイルを検証
+pub fn verify_output(
+    ffmpeg_info: &FfmpegInfo,
+    output_path: &Path,
+    settings: &TranscodeSettings,
+    source: &VideoMetadata,
+) -> Result<()> {
+    let probe = ffmpeg_info
+        .probe_video(output_path)
+        .context("出力ファイルを読み込めません")?;
+
+    let expected_codec = match settings.video_codec {
+        VideoCodec::Copy => source.video_codec.as_deref(),
+        codec => codec.probe_name(),
+    };
+    check_probe(&probe, source.duration, expected_codec)?;
+
+    ffmpeg_info
+        .decode_tail(output_path, TAIL_DECODE_SECS)
+        .context("末尾をデコードできません（途中で切れている可能性があります）")?;
+
+    Ok(())
+}
+
+/// ffprobeの結果を元の動画と比較
+fn check_probe(
+    probe: &ProbeResult,
+    expected_duration: Option<f64>,
+    expected_codec: Option<&str>,
+) -> Result<()> {
+    if let Some(expected) = expected_codec {
+        match probe.video_codec.as_deref() {
+            None => bail!("映像ストリームがありません"),
+            Some(codec) if codec != expected => {
+                bail!("映像コーデックが {} です（想定: {}）", codec, expected)
+            }
+            Some(_) => {}
+        }
+    }
+
+    if let Some(expected) = expected_duration.filter(|d| *d > 0.0) {
+        let Some(actual) = probe.duration else {
+            bail!("出力ファイルの長さを取得できません");
+        };
+        let tolerance = (expected * DURATION_TOLERANCE).max(MIN_DURATION_TOLERANCE_SECS);
+        if (actual - expected).abs() > tolerance {
+            bail!(
+                "長さが {:.1} 秒です（元の長さ: {:.1} 秒）",
+                actual,
+                expected
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_probe() {
+        let probe = ProbeResult {
+            duration: Some(599.0),
+            video_codec: Some("hevc".to_string()),
+            ..Default::default()
+        };
+        // 1%以内の差は許容
+        assert!(check_probe(&probe, Some(600.0), Some("hevc")).is_ok());
+        // 元の長さやコーデックが不明なら確認しない
+        assert!(check_probe(&probe, None, None).is_ok());
+
+        // 途中で切れている
+        let err = check_probe(&probe, Some(1200.0), Some("hevc")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "長さが 599.0 秒です（元の長さ: 1200.0 秒）"
+        );
+
+        let err = check_probe(&probe, Some(600.0), Some("h264")).unwrap_err();
+        assert_eq!(err.to_string(), "映像コーデックが hevc です（想定: h264）");
+
+        let no_video = ProbeResult {
+            duration: Some(600.0),
+            ..Default::default()
+        };
+        assert!(check_probe(&no_video, Some(600.0), Some("hevc")).is_err());
+
+        // 短い動画はフレーム単位の誤差を許容
+        let short = ProbeResult {
+            duration: Some(2.3),
+            video_codec: Some("h264".to_string()),
+            ..Default::default()
+        };
+        assert!(check_probe(&short, Some(2.0), Some("h264")).is_ok());
+    }
+}
@@ -104,6 +104,37 @@ impl AboutDialog {
                                     .child(LICENSE_TEXT),
                             ),
                     )
+                    // キーボードショートカット
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap(px(8.0))
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::MEDIUM)
+                                    .child("キーボードショートカット"),
+                            )
+                            .child(div().flex().flex_col().gap(px(4.0)).children(
+                                shortcuts().into_iter().map(|(keys, action)| {
+                                    div()
+                                        .flex()
+                                        .items_center()
+                                        .justify_between()
+                                        .child(div().text_sm().child(action))
+                                        .child(
+                                            div()
+                                                .px(px(6.0))
+                                                .rounded(px(4.0))
+                                                .bg(rgb(0x313244))
+                                                .text_xs()
+                                                .font_family("monospace")
+                                                .child(keys),
+                                        )
+                                }),
+                            )),
+                    )
                     // 使用ライブラリ
                     .child(
                         div()
@@ -186,6 +217,23 @@ This software uses FFmpeg (https://ffmpeg.org/)
 licensed under the GPL v2 or later.
 FFmpeg source code: https://ffmpeg.org/download.html"#;
 
+/// キーボードショートカットの一覧（キー, 動作）
+fn shortcuts() -> Vec<(String, &'static str)> {
+    let modifier = if cfg!(target_os = "macos") {
+        "⌘"
+    } else {
+        "Ctrl+"
+    };
+    vec![
+        (format!("{}O", modifier), "ファイル追加"),
+        (format!("{}Delete", modifier), "キューをクリア"),
+        (format!("Space / {}Enter", modifier), "変換開始"),
+        ("Esc".to_string(), "変換を中止 / ダイアログを閉じる"),
+        (format!("{}A", modifier), "すべて選択（ファイルリスト）"),
+        ("F1".to_string(), "About"),
+    ]
+}
+
 const LIBRARIES: &[(&str, &str)] = &[
     ("GPUI", "Apache-2.0"),
     ("gpui-component", "Apache-2.0"),
@@ -0,0 +1,376 @@
+//! FFmpegダウンロードダイアログ
+
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
+use gpui_component::Disableable;
+use log::{error, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::app::AppState;
+use crate::ffmpeg::{DownloadProgress, DownloadStatus, FfmpegDetector, FfmpegDownloader};
+use crate::transcoder::format_size;
+
+/// ダイアログの状態
+#[derive(Clone, Debug)]
+enum DialogState {
+    /// ダウンロード確認
+    Prompt,
+    /// ダウンロード中
+    Downloading(DownloadProgress),
+    /// 失敗
+    Failed(String),
+    /// 完了
+    Completed,
+}
+
+/// FFmpegダウンロードダイアログ
+pub struct DownloadDialog {
+    /// アプリケーション状態
+    app_state: AppState,
+    /// ダウンロードが必要な理由
+    reason: SharedString,
+    /// 現在の状態
+    state: DialogState,
+    /// ダウンロード中断フラグ（ダウンロード中のみSome）
+    cancel_flag: Option<Arc<AtomicBool>>,
+}
+
+impl EventEmitter<DismissEvent> for DownloadDialog {}
+
+impl DownloadDialog {
+    pub fn new(app_state: AppState, _cx: &mut Context<Self>) -> Self {
+        Self {
+            app_state,
+            reason: "FFmpegが見つかりません".into(),
+            state: DialogState::Prompt,
+            cancel_flag: None,
+        }
+    }
+
+    /// ダウンロードが必要な理由を設定して初期状態に戻す
+    pub fn reset(&mut self, reason: impl Into<SharedString>, cx: &mut Context<Self>) {
+        if matches!(self.state, DialogState::Downloading(_)) {
+            return;
+        }
+        self.reason = reason.into();
+        self.state = DialogState::Prompt;
+        cx.notify();
+    }
+
+    /// ダウンロード中かどうか
+    pub fn is_downloading(&self) -> bool {
+        matches!(self.state, DialogState::Downloading(_))
+    }
+
+    /// ダウンロードを開始
+    fn start_download(&mut self, cx: &mut Context<Self>) {
+        if self.is_downloading() {
+            return;
+        }
+
+        self.state = DialogState::Downloading(DownloadProgress {
+            downloaded: 0,
+            total: None,
+            progress: 0.0,
+            status: DownloadStatus::Preparing,
+        });
+        cx.notify();
+
+        let app_state = self.app_state.clone();
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        self.cancel_flag = Some(cancel_flag.clone());
+
+        cx.spawn(async move |this, cx| {
+            // ワーカースレッドからの進捗をチャネル経由で受け取る
+            let (tx, rx) = smol::channel::unbounded::<DownloadProgress>();
+            let worker_cancel_flag = cancel_flag.clone();
+            let task = smol::unblock(move || {
+                FfmpegDownloader::download(
+                    Some(Box::new(move |progress| {
+                        let _ = tx.try_send(progress);
+                    })),
+                    worker_cancel_flag,
+                )
+                .and_then(|path| FfmpegDetector::check_ffmpeg_at_path(&path))
+            });
+
+            // コールバックが破棄されるとチャネルが閉じる
+            while let Ok(progress) = rx.recv().await {
+                this.update(cx, |this, cx| {
+                    this.state = DialogState::Downloading(progress);
+                    cx.notify();
+                })
+                .ok();
+            }
+
+            let result = task.await;
+            this.update(cx, |this, _| this.cancel_flag = None).ok();
+
+            match result {
+                Ok(ffmpeg_info) => {
+                    info!("FFmpeg downloaded: {:?}", ffmpeg_info.ffmpeg_path);
+                    // 新しいFFmpegのエンコーダーを取得し直す
+                    FfmpegDetector::invalidate_encoder_cache();
+                    cx.update(|cx| app_state.set_ffmpeg(ffmpeg_info, cx)).ok();
+                    // 完了したらダイアログを閉じる
+                    this.update(cx, |this, cx| {
+                        this.state = DialogState::Completed;
+                        cx.emit(DismissEvent);
+                        cx.notify();
+                    })
+                    .ok();
+                }
+                // キャンセルされた場合は確認画面に戻る
+                Err(_) if cancel_flag.load(Ordering::SeqCst) => {
+                    this.update(cx, |this, cx| {
+                        this.state = DialogState::Prompt;
+                        cx.notify();
+                    })
+                    .ok();
+                }
+                Err(e) => {
+                    error!("FFmpeg download failed: {:#}", e);
+                    this.update(cx, |this, cx| {
+                        this.state = DialogState::Failed(format!("{:#}", e));
+                        cx.notify();
+                    })
+                    .ok();
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// ダウンロードを中断
+    fn cancel_download(&mut self, cx: &mut Context<Self>) {
+        if let Some(flag) = &self.cancel_flag {
+            flag.store(true, Ordering::SeqCst);
+            cx.notify();
+        }
+    }
+
+    /// ダイアログを閉じる
+    fn dismiss(&mut self, cx: &mut Context<Self>) {
+        if self.is_downloading() {
+            return;
+        }
+        cx.emit(DismissEvent);
+    }
+
+    /// 進捗表示をレンダリング
+    fn render_progress(progress: &DownloadProgress) -> impl IntoElement {
+        let (label, ratio) = match &progress.status {
+            DownloadStatus::Preparing => ("準備中...".to_string(), 0.0),
+            DownloadStatus::Downloading => {
+                let label = match progress.total {
+                    Some(total) => format!(
+                        "ダウンロード中... {} / {} ({:.0}%)",
+                        format_size(progress.downloaded),
+                        format_size(total),
+                        progress.progress * 100.0
+                    ),
+                    None => format!("ダウンロード中... {}", format_size(progress.downloaded)),
+                };
+                (label, progress.progress)
+            }
+            DownloadStatus::Extracting => ("展開中...".to_string(), 1.0),
+            DownloadStatus::Completed => ("完了".to_string(), 1.0),
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(div().text_sm().text_color(rgb(0xa6adc8)).child(label))
+            .child(
+                div()
+                    .w_full()
+                    .h(px(6.0))
+                    .rounded(px(3.0))
+                    .bg(rgb(0x313244))
+                    .child(
+                        div()
+                            .h_full()
+                            .rounded(px(3.0))
+                            .bg(rgb(0x89b4fa))
+                            .w(relative(ratio.clamp(0.0, 1.0))),
+                    ),
+            )
+    }
+}
+
+impl Render for DownloadDialog {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let is_downloading = self.is_downloading();
+        let state = self.state.clone();
+
+        div()
+            .w(px(460.0))
+            .rounded(px(8.0))
+            .bg(rgb(0x1e1e2e))
+            .border_1()
+            .border_color(rgb(0x313244))
+            .overflow_hidden()
+            .flex()
+            .flex_col()
+            // ヘッダー
+            .child(
+                div()
+                    .w_full()
+                    .p(px(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(rgb(0x313244))
+                    .child(
+                        div()
+                            .text_lg()
+                            .font_weight(FontWeight::BOLD)
+                            .child("FFmpegのセットアップ"),
+                    )
+                    .child(
+                        Button::new("download-close")
+                            .label("✕")
+                            .with_variant(ButtonVariant::Ghost)
+                            .disabled(is_downloading)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.dismiss(cx);
+                            })),
+                    ),
+            )
+            // コンテンツ
+            .child(
+                div()
+                    .p(px(16.0))
+                    .flex()
+                    .flex_col()
+                    .gap(px(12.0))
+                    .child(div().text_sm().child(self.reason.clone()))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0x6c7086))
+                            .child("変換にはFFmpeg 7.0以上が必要です。GPLビルドをアプリのデータフォルダにダウンロードします。"),
+                    )
+                    .when_some(
+                        match &state {
+                            DialogState::Downloading(progress) => Some(progress.clone()),
+                            _ => None,
+                        },
+                        |this, progress| this.child(Self::render_progress(&progress)),
+                    )
+                    .when_some(
+                        match &state {
+                            DialogState::Failed(msg) => Some(msg.clone()),
+                            _ => None,
+                        },
+                        |this, msg| {
+                            this.child(
+                                div()
+                                    .w_full()
+                                    .px(px(12.0))
+                                    .py(px(8.0))
+                                    .bg(rgb(0x302030))
+                                    .border_1()
+                                    .border_color(rgb(0xf38ba8))
+                                    .rounded(px(4.0))
+                                    .text_xs()
+                                    .text_color(rgb(0xf5c2e7))
+                                    .child(format!("ダウンロードに失敗しました: {}", msg)),
+                            )
+                        },
+                    )
+                    .when(matches!(state, DialogState::Completed), |this| {
+                        this.child(
+                            div()
+                                .text_sm()
+                                .text_color(rgb(0xa6e3a1))
+                                .child("FFmpegの準備が完了しました"),
+                        )
+                    }),
+            )
+            // フッター
+            .child(
+                div()
+                    .w_full()
+                    .p(px(16.0))
+                    .flex()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .border_t_1()
+                    .border_color(rgb(0x313244))
+                    .map(|this| match state {
+                        DialogState::Prompt => this
+                            .child(
+                                Button::new("download-later")
+                                    .label("後で")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.dismiss(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("download-start")
+                                    .label("FFmpegをダウンロード")
+                                    .with_variant(ButtonVariant::Primary)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.start_download(cx);
+                                    })),
+                            ),
+                        DialogState::Downloading(progress) => {
+                            // 展開中は中断できない
+                            let cancelling = self
+                                .cancel_flag
+                                .as_ref()
+                                .is_some_and(|flag| flag.load(Ordering::SeqCst));
+                            let can_cancel = !cancelling
+                                && matches!(
+                                    progress.status,
+                                    DownloadStatus::Preparing | DownloadStatus::Downloading
+                                );
+                            this.child(
+                                Button::new("download-abort")
+                                    .label(if cancelling {
+                                        "キャンセル中..."
+                                    } else {
+                                        "キャンセル"
+                                    })
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .disabled(!can_cancel)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.cancel_download(cx);
+                                    })),
+                            )
+                        }
+                        DialogState::Failed(_) => this
+                            .child(
+                                Button::new("download-cancel")
+                                    .label("閉じる")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.dismiss(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("download-retry")
+                                    .label("再試行")
+                                    .with_variant(ButtonVariant::Primary)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.start_download(cx);
+                                    })),
+                            ),
+                        DialogState::Completed => this.child(
+                            Button::new("download-done")
+                                .label("閉じる")
+                                .with_variant(ButtonVariant::Primary)
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.dismiss(cx);
+                                })),
+                        ),
+                    }),
+            )
+    }
+}
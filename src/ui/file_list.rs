@@ -3,47 +3,708 @@
 use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
+use gpui_component::tooltip::Tooltip;
 use gpui_component::Disableable;
 
-use crate::app::{AppState, FileEntry, FileStatus};
-use crate::transcoder::format_size;
+use crate::app::{AppState, FileEntry, FileSortKey, FileStatus, Thumbnail};
+use crate::config::SavedQueue;
+use crate::queue::{command_line_for, command_lines, QueueOptions};
+use crate::report::{export_command_script, export_report};
+use crate::transcoder::{
+    format_bitrate, format_duration, format_size, ContentType, CropMode, FfmpegError,
+    ResolvedEncodePlan, TranscodeSettings,
+};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 長さ・メディア情報列の幅（リスト幅に対する割合）
+const MEDIA_COLUMN_RATIO: f32 = 0.22;
+/// 長さ・メディア情報列の最小幅
+const MEDIA_COLUMN_MIN_WIDTH: f32 = 90.0;
+
+/// コンテンツタイプ列の幅
+const CONTENT_TYPE_COLUMN_WIDTH: f32 = 110.0;
+
+/// 画質列の幅（"VMAF 94.2 | -60%" が収まる幅）
+const QUALITY_COLUMN_WIDTH: f32 = 130.0;
+
+/// メタデータ取得中のスピナー（1周の間に順に表示する）
+const SPINNER_FRAMES: [&str; 4] = ["◐", "◓", "◑", "◒"];
+
+/// コンテンツタイプの選択メニューの対象
+#[derive(Clone, PartialEq)]
+enum ContentTypeTarget {
+    /// すべてのファイル
+    All,
+    /// 選択中のファイル
+    Selected,
+    /// 1件のファイル
+    File(PathBuf),
+}
+
+/// ファイルリストからメインウィンドウへの要求
+pub enum FileListEvent {
+    /// 現在の設定でサンプルをエンコード
+    EncodeSample(PathBuf),
+    /// 失敗したファイルだけを変換し直す
+    RetryFile(PathBuf),
+    /// 選択中のファイルだけを変換
+    TranscodeSelected(HashSet<PathBuf>),
+}
+
+/// ファイルリストの選択状態
+/// 行の追加・削除・並べ替えで位置が変わるため、選択はパスで保持する
+/// （同じファイルはキューに二重に追加されないため、パスで行を特定できる）
+#[derive(Default)]
+struct Selection {
+    /// 選択されたファイル（詳細表示の対象・範囲選択の起点）
+    focused: Option<PathBuf>,
+    /// 複数選択されたファイル
+    paths: HashSet<PathBuf>,
+}
+
+impl Selection {
+    /// 行のクリックで選択（Shiftで範囲選択、Ctrl/Cmdで個別に追加・解除）
+    fn click(&mut self, files: &[FileEntry], index: usize, modifiers: Modifiers) {
+        let Some(path) = files.get(index).map(|f| f.path.clone()) else {
+            return;
+        };
+        if modifiers.shift {
+            // 起点は現在の位置で探す（キューから消えていればクリックした行）
+            let anchor = self
+                .focused
+                .as_ref()
+                .and_then(|anchor| files.iter().position(|f| f.path == *anchor))
+                .unwrap_or(index);
+            self.paths = files[anchor.min(index)..=anchor.max(index)]
+                .iter()
+                .map(|f| f.path.clone())
+                .collect();
+            self.focused = Some(files[anchor].path.clone());
+        } else if modifiers.secondary() {
+            if self.paths.remove(&path) {
+                if self.focused.as_ref() == Some(&path) {
+                    // 残りの選択のうちキューで先頭のファイルを詳細表示の対象にする
+                    self.focused = files
+                        .iter()
+                        .find(|f| self.paths.contains(&f.path))
+                        .map(|f| f.path.clone());
+                }
+            } else {
+                self.paths.insert(path.clone());
+                self.focused = Some(path);
+            }
+        } else {
+            self.paths = HashSet::from([path.clone()]);
+            self.focused = Some(path);
+        }
+    }
+
+    /// すべてのファイルを選択
+    fn select_all(&mut self, files: &[FileEntry]) {
+        self.paths = files.iter().map(|f| f.path.clone()).collect();
+        if self.focused.is_none() {
+            self.focused = files.first().map(|f| f.path.clone());
+        }
+    }
+
+    /// キューから消えたファイルを選択から外す
+    fn prune(&mut self, files: &[FileEntry]) {
+        let queued: HashSet<&PathBuf> = files.iter().map(|f| &f.path).collect();
+        self.paths.retain(|path| queued.contains(path));
+        if self
+            .focused
+            .as_ref()
+            .is_some_and(|path| !queued.contains(path))
+        {
+            self.focused = None;
+        }
+    }
+
+    /// 選択中のファイルのキューでの位置
+    fn indices(&self, files: &[FileEntry]) -> HashSet<usize> {
+        files
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| self.paths.contains(&f.path))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
 
 /// ファイルリスト
 pub struct FileList {
     /// アプリケーション状態
     app_state: AppState,
-    /// 選択されたインデックス
-    selected_index: Option<usize>,
+    /// 選択中のファイル
+    selection: Selection,
+    /// キーボードショートカット用のフォーカス
+    focus_handle: FocusHandle,
+    /// 詳細パネルを展開するか
+    show_details: bool,
+    /// メタデータ取得中か
+    probing: bool,
+    /// クロップ検出中か
+    detecting_crop: bool,
+    /// 並べ替えキー
+    sort_key: Option<FileSortKey>,
+    /// 降順で並べ替えるか
+    sort_descending: bool,
+    /// コマンドのプレビュー（対象ファイル, コマンドまたはスキップ理由）
+    command_preview: Option<(PathBuf, Result<String, String>)>,
+    /// コマンドを生成中か
+    building_command: bool,
+    /// ログを表示中のファイル
+    log_viewer: Option<PathBuf>,
+    /// エラーの詳細を展開しているファイル
+    expanded_errors: HashSet<PathBuf>,
+    /// 前回終了時に残っていたキュー（復元するか未選択）
+    restorable_queue: Option<Vec<FileEntry>>,
+    /// 開いているコンテンツタイプの選択メニュー
+    content_type_menu: Option<ContentTypeTarget>,
+    /// 右クリックメニューを開いている行（対象ファイル, 表示位置）
+    row_menu: Option<(PathBuf, Point<Pixels>)>,
 }
 
 impl FileList {
-    pub fn new(app_state: AppState, _cx: &mut Context<Self>) -> Self {
+    pub fn new(app_state: AppState, cx: &mut Context<Self>) -> Self {
+        // サムネイルが生成されたら再描画
+        cx.observe(&app_state.thumbnails, |_, _, cx| cx.notify())
+            .detach();
+
+        // 前回終了時（クラッシュを含む）に未完了のファイルが残っていれば復元を提案
+        let restorable_queue = SavedQueue::open()
+            .and_then(|store| store.load())
+            .inspect_err(|e| log::warn!("Failed to load saved queue: {:#}", e))
+            .ok()
+            .filter(|files| !files.is_empty());
+
         Self {
             app_state,
-            selected_index: None,
+            selection: Selection::default(),
+            focus_handle: cx.focus_handle(),
+            show_details: true,
+            probing: false,
+            detecting_crop: false,
+            sort_key: None,
+            sort_descending: false,
+            command_preview: None,
+            building_command: false,
+            log_viewer: None,
+            expanded_errors: HashSet::new(),
+            restorable_queue,
+            content_type_menu: None,
+            row_menu: None,
+        }
+    }
+
+    /// 列ヘッダーのクリックで並べ替え（同じ列なら昇順・降順を切り替え）
+    fn sort_by_column(&mut self, key: FileSortKey, cx: &mut Context<Self>) {
+        // 変換中はインデックスで状態を更新しているため並べ替えない
+        if self.app_state.current_job.read(cx).is_some() {
+            return;
         }
+
+        self.sort_descending = self.sort_key == Some(key) && !self.sort_descending;
+        self.sort_key = Some(key);
+        // 選択はパスで保持しているため、並べ替え後も同じファイルが選択されたまま
+        self.app_state.sort_files(key, self.sort_descending, cx);
+        cx.notify();
+    }
+
+    /// 行のクリックで選択（Shiftで範囲選択、Ctrl/Cmdで個別に追加・解除）
+    fn select_row(&mut self, index: usize, modifiers: Modifiers, cx: &mut Context<Self>) {
+        let files = self.app_state.files.read(cx);
+        self.selection.click(files, index, modifiers);
+        cx.notify();
+    }
+
+    /// すべてのファイルを選択
+    fn select_all(&mut self, cx: &mut Context<Self>) {
+        let files = self.app_state.files.read(cx);
+        self.selection.select_all(files);
+        cx.notify();
+    }
+
+    /// 選択を解除
+    fn clear_selection(&mut self) {
+        self.selection = Selection::default();
+    }
+
+    /// キューから消えたファイルを選択から外す
+    fn prune_selection(&mut self, cx: &App) {
+        let files = self.app_state.files.read(cx);
+        self.selection.prune(files);
+    }
+
+    /// 選択中のファイルのキューでの位置
+    fn selected_indices(&self, cx: &App) -> HashSet<usize> {
+        self.selection.indices(self.app_state.files.read(cx))
+    }
+
+    /// 詳細表示の対象のファイル（キューでの位置とエントリー）
+    fn selected_entry(&self, cx: &App) -> Option<(usize, FileEntry)> {
+        let path = self.selection.focused.as_ref()?;
+        self.app_state
+            .files
+            .read(cx)
+            .iter()
+            .enumerate()
+            .find(|(_, f)| f.path == *path)
+            .map(|(index, f)| (index, f.clone()))
     }
 
-    /// ファイルを削除
+    /// 選択中のファイルを削除
     fn remove_selected(&mut self, cx: &mut Context<Self>) {
-        if let Some(index) = self.selected_index {
-            self.app_state.files.update(cx, |files, _| {
-                if index < files.len() {
-                    files.remove(index);
+        let indices = self.selected_indices(cx);
+        if indices.is_empty() {
+            return;
+        }
+        self.app_state.remove_files(&indices, cx);
+        self.clear_selection();
+        cx.notify();
+    }
+
+    /// 選択中のファイルだけを変換（変換はメインウィンドウが行う）
+    fn transcode_selected(&mut self, cx: &mut Context<Self>) {
+        if self.selection.paths.is_empty() {
+            return;
+        }
+        cx.emit(FileListEvent::TranscodeSelected(
+            self.selection.paths.clone(),
+        ));
+    }
+
+    /// 変換が完了したファイルを削除
+    fn remove_completed(&mut self, cx: &mut Context<Self>) {
+        self.app_state.remove_completed(cx);
+        // 残ったファイルの選択はそのまま
+        self.prune_selection(cx);
+        cx.notify();
+    }
+
+    /// コンテンツタイプの選択メニューを開く
+    fn open_content_type_menu(&mut self, target: ContentTypeTarget, cx: &mut Context<Self>) {
+        self.content_type_menu = Some(target);
+        cx.notify();
+    }
+
+    /// コンテンツタイプの選択メニューを閉じる
+    fn close_content_type_menu(&mut self, cx: &mut Context<Self>) {
+        self.content_type_menu = None;
+        cx.notify();
+    }
+
+    /// コンテンツタイプを設定（予測サイズもすぐに更新する）
+    fn apply_content_type(
+        &mut self,
+        target: &ContentTypeTarget,
+        content_type: ContentType,
+        cx: &mut Context<Self>,
+    ) {
+        let paths = match target {
+            ContentTypeTarget::All => None,
+            ContentTypeTarget::Selected => Some(self.selection.paths.clone()),
+            ContentTypeTarget::File(path) => Some(HashSet::from([path.clone()])),
+        };
+        self.app_state
+            .set_content_type(paths.as_ref(), content_type, cx);
+        self.content_type_menu = None;
+        cx.notify();
+    }
+
+    /// 前回のキューを復元
+    fn restore_queue(&mut self, cx: &mut Context<Self>) {
+        if let Some(files) = self.restorable_queue.take() {
+            self.app_state.restore_files(files, cx);
+        }
+        cx.notify();
+    }
+
+    /// 前回のキューを破棄
+    fn discard_saved_queue(&mut self, cx: &mut Context<Self>) {
+        self.restorable_queue = None;
+        // 復元前に追加したファイルがあれば、そのキューは次の自動保存で書き直される
+        if self.app_state.files.read(cx).is_empty() {
+            if let Err(e) = SavedQueue::open().and_then(|store| store.clear()) {
+                log::warn!("Failed to discard saved queue: {:#}", e);
+            }
+        }
+        cx.notify();
+    }
+
+    /// キューの結果をCSV/JSONで保存
+    fn export_report(&mut self, cx: &mut Context<Self>) {
+        let files = self.app_state.files.read(cx).clone();
+        cx.spawn(async move |_, _| {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_title("変換結果をエクスポート")
+                .set_file_name("kamaitachi-report.json")
+                .add_filter("JSON", &["json"])
+                .add_filter("CSV", &["csv"])
+                .save_file()
+                .await
+            else {
+                return;
+            };
+            let path = file.path().to_path_buf();
+            let result = smol::unblock({
+                let path = path.clone();
+                move || export_report(&files, &path)
+            })
+            .await;
+            match result {
+                Ok(()) => log::info!("Exported report to {:?}", path),
+                Err(e) => log::error!("Failed to export report: {:#}", e),
+            }
+        })
+        .detach();
+    }
+
+    /// キューのすべてのファイルのFFmpegコマンドをスクリプトとしてエクスポート
+    fn export_commands(&mut self, cx: &mut Context<Self>) {
+        let Some(ffmpeg_path) = self.app_state.ffmpeg_path.read(cx).clone() else {
+            return;
+        };
+        let files = self.app_state.files.read(cx).clone();
+        let settings = self.app_state.transcode_settings.read(cx).clone();
+        let (file_name, filter_name, extension) = if cfg!(windows) {
+            ("kamaitachi-commands.bat", "バッチファイル", "bat")
+        } else {
+            ("kamaitachi-commands.sh", "シェルスクリプト", "sh")
+        };
+        cx.spawn(async move |_, _| {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_title("FFmpegコマンドをエクスポート")
+                .set_file_name(file_name)
+                .add_filter(filter_name, &[extension])
+                .save_file()
+                .await
+            else {
+                return;
+            };
+            let path = file.path().to_path_buf();
+            let result = smol::unblock({
+                let path = path.clone();
+                move || {
+                    let options = QueueOptions::new(ffmpeg_path, &settings);
+                    export_command_script(&command_lines(&options, &files), &path)
                 }
-            });
-            self.selected_index = None;
-            cx.notify();
+            })
+            .await;
+            match result {
+                Ok(()) => log::info!("Exported FFmpeg commands to {:?}", path),
+                Err(e) => log::error!("Failed to export FFmpeg commands: {:#}", e),
+            }
+        })
+        .detach();
+    }
+
+    /// 行の右クリックメニューを開く
+    fn open_row_menu(&mut self, path: PathBuf, position: Point<Pixels>, cx: &mut Context<Self>) {
+        self.row_menu = Some((path, position));
+        cx.notify();
+    }
+
+    /// 行の右クリックメニューを閉じる
+    fn close_row_menu(&mut self, cx: &mut Context<Self>) {
+        self.row_menu = None;
+        cx.notify();
+    }
+
+    /// ファイルのFFmpegコマンドを生成してクリップボードにコピー
+    fn copy_command_for(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+        self.close_row_menu(cx);
+        let Some(ffmpeg_path) = self.app_state.ffmpeg_path.read(cx).clone() else {
+            return;
+        };
+        let Some(entry) = self
+            .app_state
+            .files
+            .read(cx)
+            .iter()
+            .find(|f| f.path == path)
+            .cloned()
+        else {
+            return;
+        };
+        let settings = self.app_state.transcode_settings.read(cx).clone();
+
+        cx.spawn(async move |_, cx| {
+            let command =
+                smol::unblock(move || build_command(ffmpeg_path, &settings, &entry)).await;
+            match command {
+                Ok(command) => {
+                    cx.update(|cx| cx.write_to_clipboard(ClipboardItem::new_string(command)))
+                        .ok();
+                }
+                Err(reason) => log::warn!("No FFmpeg command for {:?}: {}", path, reason),
+            }
+        })
+        .detach();
+    }
+
+    /// ファイルのFFmpegログを表示
+    fn open_log(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+        self.log_viewer = Some(path);
+        cx.notify();
+    }
+
+    /// ログ表示を閉じる
+    fn close_log(&mut self, cx: &mut Context<Self>) {
+        self.log_viewer = None;
+        cx.notify();
+    }
+
+    /// エラーの詳細の展開・折りたたみ
+    fn toggle_error_details(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+        if !self.expanded_errors.remove(&path) {
+            self.expanded_errors.insert(path);
+        }
+        cx.notify();
+    }
+
+    /// ファイルのFFmpegコマンドを詳細パネルに表示（コピーはプレビューから行う）
+    fn show_command(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.selection.focused = self
+            .app_state
+            .files
+            .read(cx)
+            .get(index)
+            .map(|f| f.path.clone());
+        self.show_details = true;
+        self.preview_command_selected(cx);
+    }
+
+    /// キーボードショートカット（Ctrl/Cmd+Aですべて選択）
+    fn handle_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        let keystroke = &event.keystroke;
+        if keystroke.modifiers.secondary() && keystroke.key == "a" {
+            self.select_all(cx);
+            cx.stop_propagation();
+        }
+    }
+
+    /// 詳細パネルの展開・折りたたみ
+    fn toggle_details(&mut self, cx: &mut Context<Self>) {
+        self.show_details = !self.show_details;
+        cx.notify();
+    }
+
+    /// 選択中のファイルのメタデータを取得
+    fn probe_selected(&mut self, cx: &mut Context<Self>) {
+        let Some((index, entry)) = self.selected_entry(cx) else {
+            return;
+        };
+        let Some(ffmpeg_info) = self.app_state.ffmpeg_info.read(cx).clone() else {
+            return;
+        };
+
+        self.probing = true;
+        cx.notify();
+
+        let app_state = self.app_state.clone();
+        cx.spawn(async move |this, cx| {
+            let path = entry.path.clone();
+            let token = app_state.workers.token(&path);
+            let ffmpeg_info = ffmpeg_info.with_cancel(token.flag());
+            let probed = app_state
+                .workers
+                .run(&token, move || {
+                    let mut entry = entry;
+                    entry.probe_metadata(&ffmpeg_info);
+                    entry
+                })
+                .await;
+
+            if let Some(probed) = probed {
+                cx.update(|cx| {
+                    let settings = app_state.transcode_settings.read(cx).clone();
+                    let calibration = app_state.calibration.read(cx).clone();
+                    app_state.update_files(cx, |files, _| {
+                        // 取得中にキューが変更された場合に備えてパスで確認
+                        if let Some(f) = files.get_mut(index).filter(|f| f.path == path) {
+                            f.metadata = probed.metadata;
+                            f.update_estimated_size(&settings, &calibration);
+                        }
+                    });
+                })
+                .ok();
+            }
+            this.update(cx, |this, cx| {
+                this.probing = false;
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// 選択中のファイルを変換するFFmpegコマンドを生成
+    /// 変換開始時と同じ手順でHWアクセラレーション・エンコーダー・出力パスを解決する
+    fn preview_command_selected(&mut self, cx: &mut Context<Self>) {
+        let Some((_, entry)) = self.selected_entry(cx) else {
+            return;
+        };
+        let Some(ffmpeg_path) = self.app_state.ffmpeg_path.read(cx).clone() else {
+            return;
+        };
+        let settings = self.app_state.transcode_settings.read(cx).clone();
+
+        self.building_command = true;
+        cx.notify();
+
+        cx.spawn(async move |this, cx| {
+            let path = entry.path.clone();
+            let command =
+                smol::unblock(move || build_command(ffmpeg_path, &settings, &entry)).await;
+
+            this.update(cx, |this, cx| {
+                this.building_command = false;
+                this.command_preview = Some((path, command));
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// プレビュー中のコマンドをクリップボードにコピー
+    fn copy_command(&mut self, cx: &mut Context<Self>) {
+        if let Some((_, Ok(command))) = &self.command_preview {
+            cx.write_to_clipboard(ClipboardItem::new_string(command.clone()));
         }
     }
+
+    /// 選択中のファイルの黒帯をcropdetectで検出
+    fn detect_crop_selected(&mut self, cx: &mut Context<Self>) {
+        let Some((index, entry)) = self.selected_entry(cx) else {
+            return;
+        };
+        let Some(ffmpeg_info) = self.app_state.ffmpeg_info.read(cx).clone() else {
+            return;
+        };
+
+        self.detecting_crop = true;
+        cx.notify();
+
+        let app_state = self.app_state.clone();
+        cx.spawn(async move |this, cx| {
+            let path = entry.path.clone();
+            let token = app_state.workers.token(&path);
+            let ffmpeg_info = ffmpeg_info.with_cancel(token.flag());
+            let result = app_state
+                .workers
+                .run(&token, move || {
+                    let mut entry = entry;
+                    entry.detect_crop(&ffmpeg_info)
+                })
+                .await;
+
+            match result {
+                // 検出中にキューから削除された
+                None => {}
+                Some(Err(_)) if token.is_cancelled() => {}
+                Some(Ok(crop)) => {
+                    log::info!("Detected crop for {:?}: {}", path, crop.display());
+                    cx.update(|cx| {
+                        app_state.update_files(cx, |files, _| {
+                            if let Some(f) = files.get_mut(index).filter(|f| f.path == path) {
+                                f.crop = Some(crop);
+                            }
+                        });
+                    })
+                    .ok();
+                }
+                Some(Err(e)) => log::warn!("Crop detection failed for {:?}: {:#}", path, e),
+            }
+            this.update(cx, |this, cx| {
+                this.detecting_crop = false;
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// 選択中のファイルのサンプルをエンコード（変換はメインウィンドウが行う）
+    fn encode_sample_selected(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.selection.focused.clone() else {
+            return;
+        };
+        cx.emit(FileListEvent::EncodeSample(path));
+    }
+
+    /// 選択中のファイルに現在の設定を固定（全体の設定を変えてもこのファイルには反映しない）
+    fn lock_settings_selected(&mut self, cx: &mut Context<Self>) {
+        let settings = self.app_state.transcode_settings.read(cx).clone();
+        self.set_settings_override_selected(Some(settings), cx);
+    }
+
+    /// 選択中のファイルの設定の固定を解除（全体の設定に戻す）
+    fn unlock_settings_selected(&mut self, cx: &mut Context<Self>) {
+        self.set_settings_override_selected(None, cx);
+    }
+
+    /// 選択中のファイルの固定した設定を変更し、予測サイズを更新
+    fn set_settings_override_selected(
+        &mut self,
+        settings_override: Option<TranscodeSettings>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(path) = self.selection.focused.clone() else {
+            return;
+        };
+        let settings = self.app_state.transcode_settings.read(cx).clone();
+        let calibration = self.app_state.calibration.read(cx).clone();
+        self.app_state.update_files(cx, |files, _| {
+            if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                f.settings_override = settings_override;
+                f.update_estimated_size(&settings, &calibration);
+            }
+        });
+        // 表示中のコマンドは古い設定のもの
+        self.command_preview = None;
+        cx.notify();
+    }
+
+    /// 選択中のファイルの検出済みクロップを解除
+    fn clear_crop_selected(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.selection.focused.clone() else {
+            return;
+        };
+        self.app_state.update_files(cx, |files, _| {
+            if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                f.crop = None;
+            }
+        });
+        cx.notify();
+    }
+}
+
+impl EventEmitter<FileListEvent> for FileList {}
+
+impl Focusable for FileList {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
 }
 
 impl Render for FileList {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // ほかの画面からキューを変更された場合に備え、消えたファイルの選択を外す
+        self.prune_selection(cx);
         let files = self.app_state.files.read(cx).clone();
         let files_len = files.len();
         let is_empty = files.is_empty();
-        let selected = self.selected_index;
+        let selected_count = self.selection.paths.len();
+        let has_selection = selected_count > 0;
+        let is_running = self.app_state.current_job.read(cx).is_some();
+        let has_ffmpeg = self.app_state.ffmpeg_path.read(cx).is_some();
+        let has_completed = files.iter().any(|f| f.status.is_completed());
+        // 変換中は結果が確定していないため書き出さない
+        let can_export = !is_empty && !files.iter().any(|f| f.status == FileStatus::Processing);
 
         // 合計サイズを計算
         let total_size: u64 = files.iter().map(|f| f.size).sum();
@@ -64,10 +725,23 @@ impl Render for FileList {
             String::new()
         };
 
+        // ログを表示中のファイル（キューから削除された場合は表示しない）
+        let log_entry = self.log_viewer.as_ref().and_then(|path| {
+            files
+                .iter()
+                .find(|f| &f.path == path && f.log.is_some())
+                .cloned()
+        });
+
         div()
             .size_full()
+            .relative()
             .flex()
             .flex_col()
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, _, cx| {
+                this.handle_key_down(event, cx);
+            }))
             // ヘッダー
             .child(
                 div()
@@ -101,15 +775,72 @@ impl Render for FileList {
                             }),
                     )
                     .child(
-                        Button::new("remove-selected")
-                            .label("削除")
-                            .with_variant(ButtonVariant::Ghost)
-                            .disabled(selected.is_none())
-                            .on_click(cx.listener(|this, _, _, cx| {
-                                this.remove_selected(cx);
-                            })),
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(px(4.0))
+                            .child(
+                                Button::new("export-report")
+                                    .label("エクスポート")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .disabled(!can_export)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.export_report(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("export-commands")
+                                    .label("すべてのコマンドをエクスポート")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .disabled(is_empty || !has_ffmpeg)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.export_commands(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("remove-completed")
+                                    .label("完了したものを削除")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .disabled(!has_completed)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.remove_completed(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("transcode-selected")
+                                    .label(if selected_count > 1 {
+                                        format!("選択を変換 ({})", selected_count)
+                                    } else {
+                                        "選択を変換".to_string()
+                                    })
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .disabled(!has_selection || is_running)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.transcode_selected(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("remove-selected")
+                                    .label(if selected_count > 1 {
+                                        format!("削除 ({})", selected_count)
+                                    } else {
+                                        "削除".to_string()
+                                    })
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .disabled(!has_selection)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.remove_selected(cx);
+                                    })),
+                            ),
                     ),
             )
+            // 前回のキューの復元
+            .when_some(
+                self.restorable_queue.as_ref().map(|files| files.len()),
+                |this, count| this.child(self.render_restore_banner(count, cx)),
+            )
+            // 列ヘッダー
+            .when(!is_empty, |this| this.child(self.render_column_header(cx)))
             // ファイルリスト
             .child(
                 div()
@@ -133,10 +864,758 @@ impl Render for FileList {
                             .collect()
                     }),
             )
+            // 詳細パネル
+            .child(
+                self.render_details(
+                    files
+                        .iter()
+                        .find(|f| self.selection.focused.as_ref() == Some(&f.path)),
+                    cx,
+                ),
+            )
+            // FFmpegログ
+            .when_some(log_entry, |this, file| {
+                this.child(self.render_log_viewer(&file, cx))
+            })
     }
 }
 
 impl FileList {
+    /// 前回のキューを復元するかのバナーをレンダリング
+    fn render_restore_banner(&self, count: usize, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .w_full()
+            .px(px(16.0))
+            .py(px(8.0))
+            .flex()
+            .items_center()
+            .justify_between()
+            .bg(rgb(0x181825))
+            .border_b_1()
+            .border_color(rgb(0x313244))
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(rgb(0xf9e2af))
+                    .child(format!("前回終了時のキューが残っています ({} 件)", count)),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .child(
+                        Button::new("restore-queue")
+                            .label("前回のキューを復元")
+                            .with_variant(ButtonVariant::Primary)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.restore_queue(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("discard-queue")
+                            .label("破棄")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.discard_saved_queue(cx);
+                            })),
+                    ),
+            )
+    }
+
+    /// 選択中のファイルの詳細パネルをレンダリング
+    fn render_details(&self, file: Option<&FileEntry>, cx: &mut Context<Self>) -> impl IntoElement {
+        let show_details = self.show_details;
+        let can_probe = self.app_state.ffmpeg_info.read(cx).is_some() && !self.probing;
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .bg(rgb(0x181825))
+            .border_t_1()
+            .border_color(rgb(0x313244))
+            // ヘッダー（クリックで展開・折りたたみ）
+            .child(
+                div()
+                    .id("details-header")
+                    .w_full()
+                    .h(px(32.0))
+                    .px(px(16.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .cursor_pointer()
+                    .hover(|s| s.bg(rgb(0x313244)))
+                    .on_click(cx.listener(|this, _, _, cx| {
+                        this.toggle_details(cx);
+                    }))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0x6c7086))
+                            .child(if show_details { "▼" } else { "▶" }),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(rgb(0xa6adc8))
+                            .child("詳細"),
+                    ),
+            )
+            .when(show_details, |this| match file {
+                None => this.child(
+                    div()
+                        .px(px(16.0))
+                        .pb(px(12.0))
+                        .text_xs()
+                        .text_color(rgb(0x6c7086))
+                        .child("ファイルを選択すると詳細を表示します"),
+                ),
+                Some(file) if !file.is_probed() => this.child(
+                    div()
+                        .px(px(16.0))
+                        .pb(px(12.0))
+                        .flex()
+                        .items_center()
+                        .gap(px(12.0))
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0x6c7086))
+                                .child("メタデータが未取得です"),
+                        )
+                        .child(
+                            Button::new("probe-selected")
+                                .label(if self.probing {
+                                    "取得中..."
+                                } else {
+                                    "詳細を取得"
+                                })
+                                .with_variant(ButtonVariant::Ghost)
+                                .disabled(!can_probe)
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.probe_selected(cx);
+                                })),
+                        ),
+                ),
+                Some(file) => this
+                    .child(Self::render_metadata(file))
+                    .child(self.render_crop_actions(file, cx))
+                    .child(Self::render_settings_lock(file, cx))
+                    .child(self.render_command_preview(file, cx)),
+            })
+    }
+
+    /// クロップの表示と自動検出ボタンをレンダリング
+    fn render_crop_actions(&self, file: &FileEntry, cx: &mut Context<Self>) -> impl IntoElement {
+        let can_detect = self.app_state.ffmpeg_info.read(cx).is_some() && !self.detecting_crop;
+        let settings = file.effective_settings(self.app_state.transcode_settings.read(cx));
+        let crop_label = match (settings.crop_mode, file.crop) {
+            (CropMode::Off, _) => "クロップ: なし".to_string(),
+            (_, Some(crop)) => format!("クロップ: {}（自動検出）", crop.display()),
+            (CropMode::AutoDetect, None) => "クロップ: 未検出".to_string(),
+            (CropMode::Manual, None) => format!("クロップ: {}（設定値）", settings.crop.display()),
+        };
+
+        div()
+            .px(px(16.0))
+            .pb(px(12.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .child(div().text_xs().text_color(rgb(0xa6adc8)).child(crop_label))
+            .child(
+                Button::new("detect-crop")
+                    .label(if self.detecting_crop {
+                        "検出中..."
+                    } else {
+                        "自動検出"
+                    })
+                    .with_variant(ButtonVariant::Ghost)
+                    .disabled(!can_detect)
+                    .on_click(cx.listener(|this, _, _, cx| {
+                        this.detect_crop_selected(cx);
+                    })),
+            )
+            .child(
+                Button::new("clear-crop")
+                    .label("解除")
+                    .with_variant(ButtonVariant::Ghost)
+                    .disabled(file.crop.is_none() || self.detecting_crop)
+                    .on_click(cx.listener(|this, _, _, cx| {
+                        this.clear_crop_selected(cx);
+                    })),
+            )
+    }
+
+    /// 設定の固定状態と固定・解除ボタンをレンダリング
+    fn render_settings_lock(file: &FileEntry, cx: &mut Context<Self>) -> impl IntoElement {
+        let is_locked = file.settings_override.is_some();
+        let lock_label = match &file.settings_override {
+            Some(settings) => format!("設定: 固定（{}）", settings.short_summary()),
+            None => "設定: 全体の設定に従う".to_string(),
+        };
+        // 変換中のファイルは変更しても反映されない
+        let is_processing = file.status == FileStatus::Processing;
+
+        div()
+            .px(px(16.0))
+            .pb(px(12.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .child(div().text_xs().text_color(rgb(0xa6adc8)).child(lock_label))
+            .child(
+                Button::new("lock-settings")
+                    .label("この設定を固定")
+                    .with_variant(ButtonVariant::Ghost)
+                    .disabled(is_processing)
+                    .on_click(cx.listener(|this, _, _, cx| {
+                        this.lock_settings_selected(cx);
+                    })),
+            )
+            .child(
+                Button::new("unlock-settings")
+                    .label("解除")
+                    .with_variant(ButtonVariant::Ghost)
+                    .disabled(!is_locked || is_processing)
+                    .on_click(cx.listener(|this, _, _, cx| {
+                        this.unlock_settings_selected(cx);
+                    })),
+            )
+    }
+
+    /// コマンドの表示・サンプルのエンコードボタンとプレビューをレンダリング
+    fn render_command_preview(&self, file: &FileEntry, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_ffmpeg = self.app_state.ffmpeg_path.read(cx).is_some();
+        let can_build = has_ffmpeg && !self.building_command;
+        // 変換中はサンプルも作らない（長さが分からないファイルは区間を決められない）
+        let can_sample = has_ffmpeg
+            && self.app_state.current_job.read(cx).is_none()
+            && file.metadata.duration.is_some();
+        // 別のファイルを選択したら表示しない
+        let preview = self
+            .command_preview
+            .as_ref()
+            .filter(|(path, _)| *path == file.path)
+            .map(|(_, command)| command.clone());
+        let has_command = matches!(preview, Some(Ok(_)));
+
+        div()
+            .px(px(16.0))
+            .pb(px(12.0))
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(12.0))
+                    .child(
+                        Button::new("preview-command")
+                            .label(if self.building_command {
+                                "生成中..."
+                            } else {
+                                "コマンドを表示"
+                            })
+                            .with_variant(ButtonVariant::Ghost)
+                            .disabled(!can_build)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.preview_command_selected(cx);
+                            })),
+                    )
+                    .when(has_command, |this| {
+                        this.child(
+                            Button::new("copy-command")
+                                .label("コピー")
+                                .with_variant(ButtonVariant::Ghost)
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.copy_command(cx);
+                                })),
+                        )
+                    })
+                    .child(
+                        Button::new("encode-sample")
+                            .label("サンプルをエンコード")
+                            .with_variant(ButtonVariant::Ghost)
+                            .disabled(!can_sample)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.encode_sample_selected(cx);
+                            })),
+                    ),
+            )
+            .when_some(preview, |this, preview| match preview {
+                Ok(command) => this.child(
+                    div()
+                        .w_full()
+                        .px(px(8.0))
+                        .py(px(6.0))
+                        .rounded(px(4.0))
+                        .bg(rgb(0x11111b))
+                        .border_1()
+                        .border_color(rgb(0x313244))
+                        .font_family("monospace")
+                        .text_xs()
+                        .text_color(rgb(0xcdd6f4))
+                        .child(command),
+                ),
+                Err(reason) => this.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0xf9e2af))
+                        .child(format!("変換時はスキップされます: {}", reason)),
+                ),
+            })
+    }
+
+    /// ファイルのFFmpegログをレンダリング（エラー行を強調表示）
+    fn render_log_viewer(&self, file: &FileEntry, cx: &mut Context<Self>) -> impl IntoElement {
+        let log = file.log.clone().unwrap_or_default();
+        let error_line = FfmpegError::error_line(&log).map(|line| line.to_string());
+        let lines: Vec<String> = log.lines().map(|line| line.to_string()).collect();
+
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .flex_col()
+            .bg(rgb(0x11111b))
+            // ヘッダー
+            .child(
+                div()
+                    .w_full()
+                    .h(px(40.0))
+                    .px(px(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(rgb(0x313244))
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::MEDIUM)
+                            .truncate()
+                            .child(format!("FFmpegログ: {}", file.name)),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(px(4.0))
+                            .child(
+                                Button::new("copy-entry-log")
+                                    .label("コピー")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .on_click(cx.listener(move |_, _, _, cx| {
+                                        cx.write_to_clipboard(ClipboardItem::new_string(
+                                            log.clone(),
+                                        ));
+                                    })),
+                            )
+                            .child(
+                                Button::new("close-entry-log")
+                                    .label("閉じる")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.close_log(cx);
+                                    })),
+                            ),
+                    ),
+            )
+            // ログ本文
+            .child(
+                div()
+                    .id("entry-log-lines")
+                    .flex_1()
+                    .w_full()
+                    .px(px(16.0))
+                    .py(px(8.0))
+                    .overflow_y_scroll()
+                    .font_family("monospace")
+                    .text_xs()
+                    .children(lines.into_iter().map(|line| {
+                        let is_error = error_line.as_deref() == Some(line.as_str());
+                        div()
+                            .when(is_error, |this| {
+                                this.bg(rgb(0x302030)).text_color(rgb(0xf38ba8))
+                            })
+                            .when(!is_error, |this| this.text_color(rgb(0xa6adc8)))
+                            .child(line)
+                    })),
+            )
+    }
+
+    /// メタデータ一覧をレンダリング
+    fn render_metadata(file: &FileEntry) -> impl IntoElement {
+        let metadata = &file.metadata;
+        let unknown = || "-".to_string();
+        let format_bitrate = |bps: Option<u64>| {
+            bps.map(|b| format!("{} kbps", b / 1000))
+                .unwrap_or_else(unknown)
+        };
+
+        let estimated = match (file.estimated_size, file.estimated_ratio()) {
+            (Some(size), Some(ratio)) => {
+                format!("{} ({:.0}%)", format_size(size), ratio * 100.0)
+            }
+            _ => unknown(),
+        };
+
+        let rows = [
+            (
+                "解像度",
+                metadata
+                    .resolution
+                    .map(|(w, h)| format!("{}x{}", w, h))
+                    .unwrap_or_else(unknown),
+            ),
+            (
+                "フレームレート",
+                metadata
+                    .fps
+                    .map(|fps| format!("{:.3} fps", fps))
+                    .unwrap_or_else(unknown),
+            ),
+            (
+                "長さ",
+                metadata
+                    .duration
+                    .map(|d| format_duration(Duration::from_secs_f64(d.max(0.0))))
+                    .unwrap_or_else(unknown),
+            ),
+            (
+                "走査方式",
+                match metadata.field_order.as_deref() {
+                    Some("progressive") => "プログレッシブ".to_string(),
+                    Some(_) if metadata.is_interlaced() => "インターレース".to_string(),
+                    _ => unknown(),
+                },
+            ),
+            (
+                "映像コーデック",
+                match (&metadata.video_codec, metadata.video_bit_depth) {
+                    (Some(codec), Some(depth)) => format!("{} ({}bit)", codec, depth),
+                    (Some(codec), None) => codec.clone(),
+                    _ => unknown(),
+                },
+            ),
+            (
+                "音声コーデック",
+                match (&metadata.audio_codec, metadata.audio_bit_depth) {
+                    (Some(codec), Some(depth)) => format!("{} ({}bit)", codec, depth),
+                    (Some(codec), None) => codec.clone(),
+                    _ => unknown(),
+                },
+            ),
+            (
+                "映像ビットレート",
+                format_bitrate(metadata.source_video_bitrate),
+            ),
+            (
+                "音声ビットレート",
+                format_bitrate(metadata.source_audio_bitrate),
+            ),
+            (
+                "全体ビットレート",
+                format_bitrate(metadata.source_overall_bitrate),
+            ),
+            (
+                "チャプター",
+                match metadata.chapter_count {
+                    0 => "なし".to_string(),
+                    count => count.to_string(),
+                },
+            ),
+            ("予測サイズ", estimated),
+        ];
+
+        div()
+            .px(px(16.0))
+            .pb(px(12.0))
+            .flex()
+            .flex_wrap()
+            .gap(px(8.0))
+            .children(rows.into_iter().map(|(label, value)| {
+                div()
+                    .w(px(220.0))
+                    .flex()
+                    .justify_between()
+                    .gap(px(8.0))
+                    .child(div().text_xs().text_color(rgb(0x6c7086)).child(label))
+                    .child(div().text_xs().child(value))
+            }))
+    }
+
+    /// 列ヘッダーをレンダリング（クリックで並べ替え）
+    fn render_column_header(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let is_running = self.app_state.current_job.read(cx).is_some();
+
+        div()
+            .w_full()
+            .h(px(28.0))
+            .px(px(16.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .bg(rgb(0x181825))
+            .border_b_1()
+            .border_color(rgb(0x313244))
+            // サムネイル列
+            .child(div().flex_none().w(px(80.0)))
+            .child(
+                div()
+                    .flex_1()
+                    .child(self.render_sort_header(FileSortKey::Name, is_running, cx)),
+            )
+            .child(
+                div()
+                    .w(relative(MEDIA_COLUMN_RATIO))
+                    .min_w(px(MEDIA_COLUMN_MIN_WIDTH))
+                    .child(self.render_sort_header(FileSortKey::Duration, is_running, cx)),
+            )
+            .child(
+                div()
+                    .w(px(80.0))
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child("フレーム数"),
+            )
+            .child(div().w(px(140.0)).child(self.render_sort_header(
+                FileSortKey::Size,
+                is_running,
+                cx,
+            )))
+            .child(div().w(px(CONTENT_TYPE_COLUMN_WIDTH)).child(
+                if self.selection.paths.len() > 1 {
+                    // 複数選択中は選択したファイルだけに適用する
+                    self.render_content_type_pill(
+                        ContentTypeTarget::Selected,
+                        format!("選択に適用 ({}) ▾", self.selection.paths.len()),
+                        None,
+                        cx,
+                    )
+                } else {
+                    self.render_content_type_pill(
+                        ContentTypeTarget::All,
+                        "すべてに適用 ▾".to_string(),
+                        None,
+                        cx,
+                    )
+                },
+            ))
+            .child(
+                div()
+                    .w(px(QUALITY_COLUMN_WIDTH))
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child("画質"),
+            )
+            .child(div().w(px(80.0)).child(self.render_sort_header(
+                FileSortKey::Status,
+                is_running,
+                cx,
+            )))
+    }
+
+    /// コンテンツタイプのピル（クリックで選択メニューを開く）
+    fn render_content_type_pill(
+        &self,
+        target: ContentTypeTarget,
+        label: String,
+        current: Option<ContentType>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let is_open = self.content_type_menu.as_ref() == Some(&target);
+        let id = match &target {
+            ContentTypeTarget::All => SharedString::from("content-type-all"),
+            ContentTypeTarget::Selected => SharedString::from("content-type-selected"),
+            ContentTypeTarget::File(path) => {
+                SharedString::from(format!("content-type-{}", path.to_string_lossy()))
+            }
+        };
+        let menu_target = target.clone();
+
+        div()
+            .flex()
+            .flex_col()
+            .child(
+                div()
+                    .id(id)
+                    .px(px(8.0))
+                    .py(px(2.0))
+                    .rounded_full()
+                    .text_xs()
+                    .truncate()
+                    .cursor_pointer()
+                    .bg(if is_open {
+                        rgb(0x89b4fa)
+                    } else {
+                        rgb(0x313244)
+                    })
+                    .text_color(if is_open {
+                        rgb(0x1e1e2e)
+                    } else {
+                        rgb(0xcdd6f4)
+                    })
+                    .hover(|s| if is_open { s } else { s.bg(rgb(0x45475a)) })
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _, _, cx| {
+                            // 行の選択は変えない
+                            cx.stop_propagation();
+                            this.open_content_type_menu(target.clone(), cx);
+                        }),
+                    )
+                    .child(label),
+            )
+            .when(is_open, |this| {
+                this.child(self.render_content_type_menu(menu_target, current, cx))
+            })
+    }
+
+    /// 行の右クリックメニュー（クリックした位置に表示）
+    fn render_row_menu(
+        &self,
+        path: PathBuf,
+        position: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        deferred(
+            anchored().position(position).snap_to_window().child(
+                div()
+                    .p(px(4.0))
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(0x181825))
+                    .border_1()
+                    .border_color(rgb(0x45475a))
+                    // 下の行をクリックしたことにしない
+                    .occlude()
+                    .on_mouse_down_out(cx.listener(|this, _, _, cx| {
+                        this.close_row_menu(cx);
+                    }))
+                    .child(
+                        div()
+                            .px(px(8.0))
+                            .py(px(4.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .text_color(rgb(0xcdd6f4))
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgb(0x45475a)))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _, _, cx| {
+                                    cx.stop_propagation();
+                                    this.copy_command_for(path.clone(), cx);
+                                }),
+                            )
+                            .child("FFmpegコマンドをコピー"),
+                    ),
+            ),
+        )
+    }
+
+    /// コンテンツタイプの選択メニュー（ほかの行に重ねて表示）
+    fn render_content_type_menu(
+        &self,
+        target: ContentTypeTarget,
+        current: Option<ContentType>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        deferred(
+            anchored().snap_to_window().child(
+                div()
+                    .mt(px(4.0))
+                    .p(px(4.0))
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(0x181825))
+                    .border_1()
+                    .border_color(rgb(0x45475a))
+                    // 下の行をクリックしたことにしない
+                    .occlude()
+                    .on_mouse_down_out(cx.listener(|this, _, _, cx| {
+                        this.close_content_type_menu(cx);
+                    }))
+                    .children(ContentType::all().iter().map(|&content_type| {
+                        let is_selected = current == Some(content_type);
+                        let target = target.clone();
+
+                        div()
+                            .px(px(8.0))
+                            .py(px(2.0))
+                            .rounded_full()
+                            .text_xs()
+                            .cursor_pointer()
+                            .bg(if is_selected {
+                                rgb(0x89b4fa)
+                            } else {
+                                rgb(0x313244)
+                            })
+                            .text_color(if is_selected {
+                                rgb(0x1e1e2e)
+                            } else {
+                                rgb(0xcdd6f4)
+                            })
+                            .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _, _, cx| {
+                                    cx.stop_propagation();
+                                    this.apply_content_type(&target, content_type, cx);
+                                }),
+                            )
+                            .child(content_type.display_name())
+                    })),
+            ),
+        )
+    }
+
+    /// 並べ替え可能な列ヘッダー
+    fn render_sort_header(
+        &self,
+        key: FileSortKey,
+        disabled: bool,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let is_active = self.sort_key == Some(key);
+        let label = if is_active {
+            let arrow = if self.sort_descending { "▼" } else { "▲" };
+            format!("{} {}", key.label(), arrow)
+        } else {
+            key.label().to_string()
+        };
+
+        div()
+            .text_xs()
+            .text_color(if is_active {
+                rgb(0x89b4fa)
+            } else {
+                rgb(0x6c7086)
+            })
+            .when(disabled, |this| this.opacity(0.5))
+            .when(!disabled, |this| {
+                this.cursor_pointer()
+                    .hover(|s| s.text_color(rgb(0xcdd6f4)))
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(move |this, _, _, cx| {
+                            this.sort_by_column(key, cx);
+                        }),
+                    )
+            })
+            .child(label)
+    }
+
     /// ファイル行をレンダリング
     fn render_file_row(
         &self,
@@ -144,13 +1623,14 @@ impl FileList {
         file: &FileEntry,
         cx: &mut Context<Self>,
     ) -> AnyElement {
-        let is_selected = self.selected_index == Some(index);
+        let is_selected = self.selection.paths.contains(&file.path);
         let status_color = match file.status {
             FileStatus::Pending => rgb(0x6c7086),
             FileStatus::Processing => rgb(0x89b4fa),
-            FileStatus::Completed => rgb(0xa6e3a1),
+            FileStatus::Completed { .. } => rgb(0xa6e3a1),
             FileStatus::Error(_) => rgb(0xf38ba8),
             FileStatus::Cancelled => rgb(0xfab387),
+            FileStatus::Skipped(_) => rgb(0xf9e2af),
         };
 
         // ライフタイムの問題を避けるため、所有権を持つ値に変換
@@ -158,9 +1638,56 @@ impl FileList {
         let file_path = file.path.to_string_lossy().to_string();
         let file_size = file.formatted_size();
         let estimated_size = file.estimated_size.map(format_size);
+        // 変換後の実際のサイズと元のサイズからの増減（完了した場合のみ）
+        let output_size = file
+            .output_size
+            .filter(|_| file.status.is_completed())
+            .zip(file.size_change_percent())
+            .map(|(size, percent)| {
+                (
+                    format!("→ {} ({:+.0}%)", format_size(size), percent),
+                    percent <= 0.0,
+                )
+            });
+        let is_probing = file.probing;
+        let duration_label = if file.probing {
+            "解析中...".to_string()
+        } else {
+            file.metadata
+                .duration
+                .map(|secs| format_duration(Duration::from_secs_f64(secs.max(0.0))))
+                .unwrap_or_else(|| "—".to_string())
+        };
+        let media_summary = if file.probing {
+            String::new()
+        } else {
+            file.metadata.summary()
+        };
+        let media_tooltip = metadata_tooltip(file);
+        let frame_label = file
+            .frame_count()
+            .map(|frames| frames.to_string())
+            .unwrap_or_else(|| "—".to_string());
+        // 完了後はVMAFスコアとサイズの増減（SSIMで評価した場合はそのスコア）
+        let quality_label = match (file.status.completion_summary(), file.quality) {
+            (Some(summary), Some(quality)) if quality.vmaf().is_none() => {
+                format!("{} | {}", quality.label(), summary)
+            }
+            (Some(summary), _) => summary,
+            (None, quality) => quality
+                .map(|quality| quality.label())
+                .unwrap_or_else(|| "—".to_string()),
+        };
         let status_label = file.status.label().to_string();
+        let content_type = file.metadata.content_type;
         let is_processing = file.status == FileStatus::Processing;
         let progress = file.progress;
+        let note = file.note.clone();
+        let locked_summary = file
+            .settings_override
+            .as_ref()
+            .map(|settings| format!("固定した設定: {}", settings.short_summary()));
+        let thumbnail = self.app_state.thumbnails.read(cx).get(&file.path).cloned();
 
         // エラーメッセージを取得
         let error_message = match &file.status {
@@ -168,22 +1695,23 @@ impl FileList {
             _ => None,
         };
         let has_error = error_message.is_some();
-        // エラーメッセージの行数に応じて高さを調整
-        let error_height = if let Some(ref msg) = error_message {
-            // 提案が含まれている場合（💡があるか、改行が多い場合）は高さを増やす
-            let line_count = msg.lines().count().max(1);
-            if line_count > 2 {
-                px(48.0 + 20.0 * line_count as f32)
-            } else {
-                px(96.0)
-            }
-        } else {
-            px(48.0)
-        };
+        let has_log = file.log.is_some();
+        let log_path = file.path.clone();
+        let error_path = file.path.clone();
+        let retry_path = file.path.clone();
+        let can_retry = self.app_state.current_job.read(cx).is_none();
+        let error_expanded = self.expanded_errors.contains(&file.path);
+        let menu_path = file.path.clone();
+        let menu_position = self
+            .row_menu
+            .as_ref()
+            .filter(|(path, _)| *path == file.path)
+            .map(|(_, position)| *position);
 
         div()
             .w_full()
-            .h(error_height)
+            .min_h(px(48.0))
+            .when(has_error, |this| this.pb(px(8.0)))
             .px(px(16.0))
             .flex()
             .flex_col()
@@ -197,11 +1725,20 @@ impl FileList {
             .cursor_pointer()
             .on_mouse_down(
                 MouseButton::Left,
-                cx.listener(move |this, _, _, cx| {
-                    this.selected_index = Some(index);
-                    cx.notify();
+                cx.listener(move |this, event: &MouseDownEvent, window, cx| {
+                    window.focus(&this.focus_handle);
+                    this.select_row(index, event.modifiers, cx);
+                }),
+            )
+            .on_mouse_down(
+                MouseButton::Right,
+                cx.listener(move |this, event: &MouseDownEvent, _, cx| {
+                    this.open_row_menu(menu_path.clone(), event.position, cx);
                 }),
             )
+            .when_some(menu_position, |this, position| {
+                this.child(self.render_row_menu(file.path.clone(), position, cx))
+            })
             // メイン行
             .child(
                 div()
@@ -210,8 +1747,40 @@ impl FileList {
                     .flex()
                     .items_center()
                     .gap(px(12.0))
-                    // ステータスインジケーター
-                    .child(div().w(px(8.0)).h(px(8.0)).rounded_full().bg(status_color))
+                    // サムネイル（生成中はプレースホルダー、失敗したら×）
+                    .child(
+                        div()
+                            .relative()
+                            .flex_none()
+                            .w(px(80.0))
+                            .h(px(45.0))
+                            .rounded(px(2.0))
+                            .overflow_hidden()
+                            .bg(rgb(0x313244))
+                            .map(|this| match thumbnail {
+                                Some(Thumbnail::Ready(image)) => this.child(img(image).size_full()),
+                                Some(Thumbnail::Failed) => this
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .text_color(rgb(0x6c7086))
+                                    .child("×"),
+                                None => this,
+                            })
+                            // ステータスインジケーター
+                            .child(
+                                div()
+                                    .absolute()
+                                    .top(px(3.0))
+                                    .left(px(3.0))
+                                    .w(px(8.0))
+                                    .h(px(8.0))
+                                    .rounded_full()
+                                    .border_1()
+                                    .border_color(rgb(0x1e1e2e))
+                                    .bg(status_color),
+                            ),
+                    )
                     // ファイル情報
                     .child(
                         div()
@@ -222,20 +1791,102 @@ impl FileList {
                             .overflow_hidden()
                             .child(
                                 div()
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(6.0))
+                                    .overflow_hidden()
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .font_weight(FontWeight::MEDIUM)
+                                            .truncate()
+                                            .child(file_name),
+                                    )
+                                    // 設定を固定したファイル（内容はツールチップ）
+                                    .when_some(locked_summary, |this, summary| {
+                                        this.child(
+                                            div()
+                                                .id(("settings-locked", index))
+                                                .flex_none()
+                                                .px(px(6.0))
+                                                .rounded_full()
+                                                .bg(rgb(0x313244))
+                                                .text_xs()
+                                                .text_color(rgb(0x89b4fa))
+                                                .child("固定")
+                                                .tooltip(move |window, cx| {
+                                                    Tooltip::new(summary.clone()).build(window, cx)
+                                                }),
+                                        )
+                                    }),
+                            )
+                            .child(
+                                div()
+                                    .flex()
+                                    .gap(px(8.0))
+                                    .overflow_hidden()
+                                    .child(
+                                        div()
+                                            .flex_1()
+                                            .text_xs()
+                                            .text_color(rgb(0x6c7086))
+                                            .truncate()
+                                            .child(file_path),
+                                    )
+                                    // 出力先の補足（別名保存・上書き・スキップ）
+                                    .when_some(note, |this, note| {
+                                        this.child(
+                                            div()
+                                                .flex_none()
+                                                .text_xs()
+                                                .text_color(rgb(0xf9e2af))
+                                                .child(note),
+                                        )
+                                    }),
+                            ),
+                    )
+                    // 長さ・解像度など（詳細はツールチップ）
+                    .child(
+                        div()
+                            .id(("media-info", index))
+                            .w(relative(MEDIA_COLUMN_RATIO))
+                            .min_w(px(MEDIA_COLUMN_MIN_WIDTH))
+                            .flex()
+                            .flex_col()
+                            .gap(px(1.0))
+                            .overflow_hidden()
+                            .child(
+                                div()
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(4.0))
                                     .text_sm()
-                                    .font_weight(FontWeight::MEDIUM)
-                                    .truncate()
-                                    .child(file_name),
+                                    .text_color(rgb(0x6c7086))
+                                    .when(is_probing, |this| {
+                                        this.child(render_spinner(("probe-spinner", index)))
+                                    })
+                                    .child(duration_label),
                             )
                             .child(
                                 div()
                                     .text_xs()
                                     .text_color(rgb(0x6c7086))
                                     .truncate()
-                                    .child(file_path),
-                            ),
+                                    .child(media_summary),
+                            )
+                            .tooltip(move |window, cx| {
+                                Tooltip::new(media_tooltip.clone()).build(window, cx)
+                            }),
+                    )
+                    // フレーム数
+                    .child(
+                        div()
+                            .w(px(80.0))
+                            .text_sm()
+                            .text_color(rgb(0x6c7086))
+                            .child(frame_label),
                     )
-                    // サイズ（元サイズ → 予測サイズ）
+                    // サイズ（元サイズ → 予測サイズ、完了後は実際のサイズと増減）
                     .child(
                         div()
                             .w(px(140.0))
@@ -243,15 +1894,44 @@ impl FileList {
                             .flex_col()
                             .gap(px(1.0))
                             .child(div().text_sm().text_color(rgb(0x6c7086)).child(file_size))
-                            .when_some(estimated_size, |this, est| {
-                                this.child(
+                            .map(|this| match output_size {
+                                Some((label, is_smaller)) => this.child(
                                     div()
                                         .text_xs()
-                                        .text_color(rgb(0xa6e3a1))
-                                        .child(format!("→ {}", est)),
-                                )
+                                        .text_color(if is_smaller {
+                                            rgb(0xa6e3a1)
+                                        } else {
+                                            rgb(0xf9e2af)
+                                        })
+                                        .child(label),
+                                ),
+                                None => this.when_some(estimated_size, |this, est| {
+                                    this.child(
+                                        div()
+                                            .text_xs()
+                                            .text_color(rgb(0xa6e3a1))
+                                            .child(format!("→ {}", est)),
+                                    )
+                                }),
                             }),
                     )
+                    // コンテンツタイプ（クリックで変更）
+                    .child(div().w(px(CONTENT_TYPE_COLUMN_WIDTH)).child(
+                        self.render_content_type_pill(
+                            ContentTypeTarget::File(file.path.clone()),
+                            format!("{} ▾", content_type.display_name()),
+                            Some(content_type),
+                            cx,
+                        ),
+                    ))
+                    // 画質とサイズの増減（完了した場合のみ）
+                    .child(
+                        div()
+                            .w(px(QUALITY_COLUMN_WIDTH))
+                            .text_sm()
+                            .text_color(rgb(0x6c7086))
+                            .child(quality_label),
+                    )
                     // ステータス
                     .child(
                         div()
@@ -279,7 +1959,9 @@ impl FileList {
                     }),
             )
             // エラーメッセージ（エラー時のみ表示）
+            // 折りたたみ時は概要と提案を1行ずつ、展開時は全文を折り返して表示
             .when_some(error_message, |this, msg| {
+                let (summary, suggestion) = error_summary(&msg);
                 this.child(
                     div()
                         .w_full()
@@ -289,11 +1971,278 @@ impl FileList {
                         .border_1()
                         .border_color(rgb(0xf38ba8))
                         .rounded(px(4.0))
-                        .text_xs()
-                        .text_color(rgb(0xf5c2e7))
-                        .child(msg),
+                        .flex()
+                        .items_start()
+                        .gap(px(8.0))
+                        .child(
+                            div()
+                                .flex_1()
+                                .overflow_hidden()
+                                .text_xs()
+                                .text_color(rgb(0xf5c2e7))
+                                .when(error_expanded, |this| this.child(msg.clone()))
+                                .when(!error_expanded, |this| {
+                                    this.flex()
+                                        .flex_col()
+                                        .gap(px(2.0))
+                                        .child(div().truncate().child(summary))
+                                        .when_some(suggestion, |this, suggestion| {
+                                            this.child(
+                                                div()
+                                                    .truncate()
+                                                    .text_color(rgb(0xf9e2af))
+                                                    .child(suggestion),
+                                            )
+                                        })
+                                }),
+                        )
+                        .child(
+                            div()
+                                .flex_none()
+                                .flex()
+                                .gap(px(4.0))
+                                .child(
+                                    Button::new(("error-details", index))
+                                        .label(if error_expanded {
+                                            "閉じる"
+                                        } else {
+                                            "詳細"
+                                        })
+                                        .with_variant(ButtonVariant::Ghost)
+                                        .on_click(cx.listener(move |this, _, _, cx| {
+                                            this.toggle_error_details(error_path.clone(), cx);
+                                        })),
+                                )
+                                .when(can_retry, |this| {
+                                    this.child(
+                                        Button::new(("retry", index))
+                                            .label("再試行")
+                                            .with_variant(ButtonVariant::Ghost)
+                                            .on_click(cx.listener(move |_, _, _, cx| {
+                                                cx.emit(FileListEvent::RetryFile(
+                                                    retry_path.clone(),
+                                                ));
+                                            })),
+                                    )
+                                })
+                                .child(
+                                    Button::new(("error-command", index))
+                                        .label("コマンドを表示")
+                                        .with_variant(ButtonVariant::Ghost)
+                                        .on_click(cx.listener(move |this, _, _, cx| {
+                                            this.show_command(index, cx);
+                                        })),
+                                )
+                                .when(has_log, |this| {
+                                    this.child(
+                                        Button::new(("show-log", index))
+                                            .label("ログを表示")
+                                            .with_variant(ButtonVariant::Ghost)
+                                            .on_click(cx.listener(move |this, _, _, cx| {
+                                                this.open_log(log_path.clone(), cx);
+                                            })),
+                                    )
+                                }),
+                        ),
                 )
             })
             .into_any_element()
     }
 }
+
+/// ファイルを変換するFFmpegコマンドを生成
+/// 変換開始時と同じ手順でHWアクセラレーション・エンコーダー・出力パスを解決する（固定した設定も反映）
+fn build_command(
+    ffmpeg_path: PathBuf,
+    settings: &TranscodeSettings,
+    entry: &FileEntry,
+) -> Result<String, String> {
+    let options = QueueOptions::new(ffmpeg_path, settings).for_file(entry);
+    let plan = ResolvedEncodePlan::resolve(&options.settings, Some(&options.ffmpeg_path));
+    command_line_for(&options, entry, &plan)
+}
+
+/// 処理中を示す回転するスピナー
+fn render_spinner(id: impl Into<ElementId>) -> impl IntoElement {
+    div().text_color(rgb(0x89b4fa)).with_animation(
+        id,
+        Animation::new(Duration::from_millis(800)).repeat(),
+        |this, delta| {
+            let frame = (delta * SPINNER_FRAMES.len() as f32) as usize;
+            this.child(SPINNER_FRAMES[frame.min(SPINNER_FRAMES.len() - 1)])
+        },
+    )
+}
+
+/// エラーメッセージを概要（1行目）と提案（💡の行）に分ける
+fn error_summary(message: &str) -> (String, Option<String>) {
+    let mut lines = message
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let summary = lines.next().unwrap_or_default().to_string();
+    let suggestion = lines
+        .find(|line| line.starts_with('💡'))
+        .map(|line| line.to_string());
+    (summary, suggestion)
+}
+
+/// メディア情報のツールチップ（ffprobeで取得できた項目のみ）
+fn metadata_tooltip(file: &FileEntry) -> String {
+    let metadata = &file.metadata;
+    let video = metadata
+        .video_codec
+        .as_ref()
+        .map(|codec| match metadata.video_bit_depth {
+            Some(depth) => format!("映像: {} ({}bit)", codec, depth),
+            None => format!("映像: {}", codec),
+        });
+    let lines: Vec<String> = [
+        metadata.duration.map(|secs| {
+            format!(
+                "長さ: {}",
+                format_duration(Duration::from_secs_f64(secs.max(0.0)))
+            )
+        }),
+        metadata
+            .resolution
+            .map(|(w, h)| format!("解像度: {}x{}", w, h)),
+        metadata
+            .fps
+            .map(|fps| format!("フレームレート: {:.3}", fps)),
+        video,
+        metadata
+            .source_video_bitrate
+            .map(|bps| format!("映像ビットレート: {}", format_bitrate(bps))),
+        metadata
+            .audio_codec
+            .as_ref()
+            .map(|codec| format!("音声: {}", codec)),
+        metadata
+            .source_audio_bitrate
+            .map(|bps| format!("音声ビットレート: {}", format_bitrate(bps))),
+        metadata
+            .pix_fmt
+            .as_ref()
+            .map(|fmt| format!("ピクセルフォーマット: {}", fmt)),
+        metadata.is_hdr().then(|| "HDR".to_string()),
+        metadata
+            .is_interlaced()
+            .then(|| "インターレース".to_string()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if lines.is_empty() {
+        "メタデータを取得できませんでした".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // gpui::*にはtest属性マクロが含まれるため、super::*ではなく個別に読み込む
+    use super::{FileEntry, Modifiers, Selection};
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+
+    fn files(names: &[&str]) -> Vec<FileEntry> {
+        names
+            .iter()
+            .map(|name| FileEntry::new(PathBuf::from(name)))
+            .collect()
+    }
+
+    fn paths(names: &[&str]) -> HashSet<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    fn shift() -> Modifiers {
+        Modifiers {
+            shift: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_click_selects_single_row() {
+        let files = files(&["a.mp4", "b.mp4", "c.mp4"]);
+        let mut selection = Selection::default();
+        selection.click(&files, 0, Modifiers::default());
+        selection.click(&files, 2, Modifiers::default());
+        assert_eq!(selection.paths, paths(&["c.mp4"]));
+        assert_eq!(selection.focused, Some(PathBuf::from("c.mp4")));
+
+        // 範囲外のクリックは無視
+        selection.click(&files, 5, Modifiers::default());
+        assert_eq!(selection.paths, paths(&["c.mp4"]));
+    }
+
+    #[test]
+    fn test_shift_click_selects_range_from_anchor() {
+        let files = files(&["a.mp4", "b.mp4", "c.mp4", "d.mp4"]);
+        let mut selection = Selection::default();
+        selection.click(&files, 2, Modifiers::default());
+        selection.click(&files, 0, shift());
+        assert_eq!(selection.paths, paths(&["a.mp4", "b.mp4", "c.mp4"]));
+        // 起点は変わらない
+        assert_eq!(selection.focused, Some(PathBuf::from("c.mp4")));
+
+        selection.click(&files, 3, shift());
+        assert_eq!(selection.paths, paths(&["c.mp4", "d.mp4"]));
+        assert_eq!(selection.indices(&files), HashSet::from([2, 3]));
+    }
+
+    #[test]
+    fn test_shift_click_follows_anchor_after_reorder() {
+        let mut files = files(&["a.mp4", "b.mp4", "c.mp4", "d.mp4"]);
+        let mut selection = Selection::default();
+        selection.click(&files, 1, Modifiers::default());
+        // 並べ替えで起点の行が移動しても、パスで起点を探す
+        files.reverse();
+        selection.click(&files, 0, shift());
+        assert_eq!(selection.paths, paths(&["d.mp4", "c.mp4", "b.mp4"]));
+
+        // 起点がキューから消えていれば、クリックした行だけを選択
+        files.retain(|f| f.path.as_path() != Path::new("b.mp4"));
+        selection.click(&files, 1, shift());
+        assert_eq!(selection.paths, paths(&["c.mp4"]));
+        assert_eq!(selection.focused, Some(PathBuf::from("c.mp4")));
+    }
+
+    #[test]
+    fn test_secondary_click_toggles_rows() {
+        let files = files(&["a.mp4", "b.mp4", "c.mp4"]);
+        let mut selection = Selection::default();
+        selection.click(&files, 0, Modifiers::default());
+        selection.click(&files, 2, Modifiers::secondary_key());
+        assert_eq!(selection.paths, paths(&["a.mp4", "c.mp4"]));
+        assert_eq!(selection.focused, Some(PathBuf::from("c.mp4")));
+
+        // 詳細表示の対象を外すと、残りのうち先頭のファイルが対象になる
+        selection.click(&files, 2, Modifiers::secondary_key());
+        assert_eq!(selection.paths, paths(&["a.mp4"]));
+        assert_eq!(selection.focused, Some(PathBuf::from("a.mp4")));
+
+        selection.click(&files, 0, Modifiers::secondary_key());
+        assert!(selection.paths.is_empty());
+        assert_eq!(selection.focused, None);
+    }
+
+    #[test]
+    fn test_select_all_and_prune() {
+        let mut files = files(&["a.mp4", "b.mp4", "c.mp4"]);
+        let mut selection = Selection::default();
+        selection.select_all(&files);
+        assert_eq!(selection.paths, paths(&["a.mp4", "b.mp4", "c.mp4"]));
+        assert_eq!(selection.focused, Some(PathBuf::from("a.mp4")));
+
+        files.remove(0);
+        selection.prune(&files);
+        assert_eq!(selection.paths, paths(&["b.mp4", "c.mp4"]));
+        assert_eq!(selection.focused, None);
+        assert_eq!(selection.indices(&files), HashSet::from([0, 1]));
+    }
+}
@@ -0,0 +1,401 @@
+//! 変換履歴ダイアログ
+
+use gpui::prelude::FluentBuilder;
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
+use gpui_component::Disableable;
+
+use crate::history::{
+    format_timestamp, now_secs, summarize, History, HistoryRecord, HistorySortKey, HistorySummary,
+    RECENT_PERIOD_SECS,
+};
+use crate::transcoder::{format_duration, format_size};
+use std::time::Duration;
+
+/// 変換履歴ダイアログ
+pub struct HistoryView {
+    /// 変換履歴
+    history: History,
+    /// 読み込み・削除に失敗した場合のエラー
+    error: Option<String>,
+    /// 並べ替えキー
+    sort_key: HistorySortKey,
+    /// 降順で並べ替えるか
+    sort_descending: bool,
+}
+
+impl EventEmitter<DismissEvent> for HistoryView {}
+
+impl HistoryView {
+    pub fn new(_cx: &mut Context<Self>) -> Self {
+        Self {
+            history: History::default(),
+            error: None,
+            sort_key: HistorySortKey::FinishedAt,
+            sort_descending: true,
+        }
+    }
+
+    /// 履歴ファイルを読み直す（表示するたびに呼ぶ）
+    pub fn reload(&mut self, cx: &mut Context<Self>) {
+        match History::open() {
+            Ok(history) => {
+                self.history = history;
+                self.error = None;
+            }
+            Err(e) => {
+                log::warn!("Failed to load history: {:#}", e);
+                self.error = Some(format!("履歴を読み込めません: {:#}", e));
+            }
+        }
+        cx.notify();
+    }
+
+    /// 列ヘッダーのクリックで並べ替え（同じ列なら昇順・降順を切り替え）
+    fn sort_by_column(&mut self, key: HistorySortKey, cx: &mut Context<Self>) {
+        if self.sort_key == key {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.sort_key = key;
+            self.sort_descending = false;
+        }
+        cx.notify();
+    }
+
+    /// 記録を1件削除
+    fn remove_record(&mut self, record: &HistoryRecord, cx: &mut Context<Self>) {
+        if let Err(e) = self.history.remove(record) {
+            log::warn!("Failed to remove history record: {:#}", e);
+            self.error = Some(format!("履歴を削除できません: {:#}", e));
+        }
+        cx.notify();
+    }
+
+    /// すべての記録を削除
+    fn clear_history(&mut self, cx: &mut Context<Self>) {
+        if let Err(e) = self.history.clear() {
+            log::warn!("Failed to clear history: {:#}", e);
+            self.error = Some(format!("履歴を削除できません: {:#}", e));
+        }
+        cx.notify();
+    }
+
+    /// 並べ替えた記録
+    fn sorted_records(&self) -> Vec<HistoryRecord> {
+        let mut records = self.history.records().to_vec();
+        records.sort_by(|a, b| {
+            let ordering = self.sort_key.compare(a, b);
+            if self.sort_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        records
+    }
+
+    /// 並べ替え可能な列ヘッダー
+    fn render_sort_header(
+        &self,
+        key: HistorySortKey,
+        width: Option<f32>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let is_active = self.sort_key == key;
+        let label = if is_active {
+            let arrow = if self.sort_descending { "▼" } else { "▲" };
+            format!("{} {}", key.label(), arrow)
+        } else {
+            key.label().to_string()
+        };
+
+        div()
+            .map(|this| match width {
+                Some(width) => this.w(px(width)),
+                None => this.flex_1().min_w_0(),
+            })
+            .text_xs()
+            .cursor_pointer()
+            .text_color(if is_active {
+                rgb(0x89b4fa)
+            } else {
+                rgb(0x6c7086)
+            })
+            .hover(|s| s.text_color(rgb(0xcdd6f4)))
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |this, _, _, cx| {
+                    this.sort_by_column(key, cx);
+                }),
+            )
+            .child(label)
+    }
+
+    /// 記録1件の行
+    fn render_row(
+        &self,
+        index: usize,
+        record: HistoryRecord,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let ratio = record
+            .compression_ratio()
+            .map(|ratio| format!("{:.0}%", ratio * 100.0))
+            .unwrap_or_else(|| "-".to_string());
+        let predicted = record
+            .predicted_ratio()
+            .map(|ratio| format!("予測 {:.0}%", ratio * 100.0))
+            .unwrap_or_default();
+        let error = record.prediction_error();
+        // 予測から20%以上ずれた記録は目立たせる
+        let error_color = match error {
+            Some(error) if error.abs() >= 0.2 => rgb(0xf9e2af),
+            _ => rgb(0xa6adc8),
+        };
+        let error_label = error
+            .map(|error| format!("{:+.0}%", error * 100.0))
+            .unwrap_or_else(|| "-".to_string());
+        let speed = match (record.average_fps, record.duration) {
+            (Some(fps), _) => format!("{:.1} fps", fps),
+            (None, Some(duration)) if record.elapsed_secs > 0.0 => {
+                format!("{:.2}x", duration / record.elapsed_secs)
+            }
+            _ => "-".to_string(),
+        };
+
+        div()
+            .id(SharedString::from(format!("history-row-{}", index)))
+            .w_full()
+            .px(px(16.0))
+            .py(px(6.0))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .text_xs()
+            .border_b_1()
+            .border_color(rgb(0x313244))
+            .hover(|s| s.bg(rgb(0x181825)))
+            .child(
+                div()
+                    .w(px(110.0))
+                    .text_color(rgb(0xa6adc8))
+                    .child(format_timestamp(record.finished_at)),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .flex()
+                    .flex_col()
+                    .child(
+                        div()
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .whitespace_nowrap()
+                            .text_color(rgb(0xcdd6f4))
+                            .child(record.file_name().to_string()),
+                    )
+                    .child(div().text_color(rgb(0x6c7086)).child(format!(
+                        "{} / 変換時間 {}",
+                        record
+                            .duration
+                            .map(|secs| format_duration(Duration::from_secs_f64(secs.max(0.0))))
+                            .unwrap_or_else(|| "-".to_string()),
+                        format_duration(Duration::from_secs_f64(record.elapsed_secs.max(0.0)))
+                    ))),
+            )
+            .child(div().w(px(150.0)).text_color(rgb(0xcdd6f4)).child(format!(
+                "{} → {}",
+                format_size(record.input_size),
+                format_size(record.output_size)
+            )))
+            .child(
+                div()
+                    .w(px(80.0))
+                    .flex()
+                    .flex_col()
+                    .child(div().text_color(rgb(0xa6e3a1)).child(ratio))
+                    .child(div().text_color(rgb(0x6c7086)).child(predicted)),
+            )
+            .child(div().w(px(80.0)).text_color(error_color).child(error_label))
+            .child(
+                div()
+                    .w(px(100.0))
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .whitespace_nowrap()
+                    .text_color(rgb(0xa6adc8))
+                    .child(record.encoder.clone()),
+            )
+            .child(div().w(px(70.0)).text_color(rgb(0xa6adc8)).child(speed))
+            .child(
+                Button::new(SharedString::from(format!("history-remove-{}", index)))
+                    .label("削除")
+                    .with_variant(ButtonVariant::Ghost)
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.remove_record(&record, cx);
+                    })),
+            )
+    }
+
+    /// 集計のフッター
+    fn render_summary(label: &str, summary: HistorySummary) -> impl IntoElement {
+        let text = if summary.count == 0 {
+            format!("{}: なし", label)
+        } else {
+            format!(
+                "{}: {} ファイル, {} → {}",
+                label,
+                summary.count,
+                format_size(summary.input_size),
+                format_size(summary.output_size)
+            )
+        };
+        div().text_xs().text_color(rgb(0xa6e3a1)).child(text)
+    }
+}
+
+impl Render for HistoryView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let records = self.sorted_records();
+        let is_empty = records.is_empty();
+        let recent = summarize(
+            self.history.records(),
+            now_secs().saturating_sub(RECENT_PERIOD_SECS),
+        );
+        let total = summarize(self.history.records(), 0);
+
+        div()
+            .w(px(920.0))
+            .h(px(600.0))
+            .rounded(px(8.0))
+            .bg(rgb(0x1e1e2e))
+            .border_1()
+            .border_color(rgb(0x313244))
+            .overflow_hidden()
+            .flex()
+            .flex_col()
+            // ヘッダー
+            .child(
+                div()
+                    .w_full()
+                    .p(px(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(rgb(0x313244))
+                    .child(
+                        div()
+                            .text_lg()
+                            .font_weight(FontWeight::BOLD)
+                            .child("変換履歴"),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(px(4.0))
+                            .child(
+                                Button::new("history-clear")
+                                    .label("すべて削除")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .disabled(is_empty)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.clear_history(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("history-close")
+                                    .label("✕")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .on_click(cx.listener(|_, _, _, cx| {
+                                        cx.emit(DismissEvent);
+                                    })),
+                            ),
+                    ),
+            )
+            .when_some(self.error.clone(), |this, error| {
+                this.child(
+                    div()
+                        .px(px(16.0))
+                        .py(px(8.0))
+                        .text_xs()
+                        .text_color(rgb(0xf38ba8))
+                        .child(error),
+                )
+            })
+            // 列ヘッダー
+            .when(!is_empty, |this| {
+                this.child(
+                    div()
+                        .w_full()
+                        .h(px(28.0))
+                        .px(px(16.0))
+                        .flex()
+                        .items_center()
+                        .gap(px(12.0))
+                        .bg(rgb(0x181825))
+                        .border_b_1()
+                        .border_color(rgb(0x313244))
+                        .child(self.render_sort_header(HistorySortKey::FinishedAt, Some(110.0), cx))
+                        .child(self.render_sort_header(HistorySortKey::Name, None, cx))
+                        .child(self.render_sort_header(HistorySortKey::InputSize, Some(150.0), cx))
+                        .child(self.render_sort_header(HistorySortKey::Ratio, Some(80.0), cx))
+                        .child(self.render_sort_header(
+                            HistorySortKey::PredictionError,
+                            Some(80.0),
+                            cx,
+                        ))
+                        .child(self.render_sort_header(HistorySortKey::Encoder, Some(100.0), cx))
+                        .child(self.render_sort_header(HistorySortKey::Fps, Some(70.0), cx))
+                        // 削除ボタンの列
+                        .child(div().w(px(48.0))),
+                )
+            })
+            // 記録
+            .child(
+                div()
+                    .id("history-rows")
+                    .flex_1()
+                    .w_full()
+                    .overflow_y_scroll()
+                    .map(|this| {
+                        if is_empty {
+                            this.child(
+                                div()
+                                    .size_full()
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .text_sm()
+                                    .text_color(rgb(0x6c7086))
+                                    .child("完了した変換はまだありません"),
+                            )
+                        } else {
+                            this.children(
+                                records
+                                    .into_iter()
+                                    .enumerate()
+                                    .map(|(index, record)| self.render_row(index, record, cx)),
+                            )
+                        }
+                    }),
+            )
+            // 集計
+            .child(
+                div()
+                    .w_full()
+                    .px(px(16.0))
+                    .py(px(8.0))
+                    .flex()
+                    .items_center()
+                    .gap(px(24.0))
+                    .bg(rgb(0x181825))
+                    .border_t_1()
+                    .border_color(rgb(0x313244))
+                    .child(Self::render_summary("過去7日間", recent))
+                    .child(Self::render_summary("全体", total)),
+            )
+    }
+}
@@ -0,0 +1,117 @@
+//! FFmpegログ表示
+
+use gpui::*;
+use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
+use gpui_component::Disableable;
+
+use crate::app::AppState;
+
+/// ログビュー
+pub struct LogView {
+    /// アプリケーション状態
+    app_state: AppState,
+    /// スクロール位置
+    scroll_handle: ScrollHandle,
+    /// 最後に描画した時点のログ行数（自動スクロール判定用）
+    last_log_count: u32,
+}
+
+impl LogView {
+    pub fn new(app_state: AppState, _cx: &mut Context<Self>) -> Self {
+        Self {
+            app_state,
+            scroll_handle: ScrollHandle::new(),
+            last_log_count: 0,
+        }
+    }
+
+    /// すべての行をクリップボードにコピー
+    fn copy_all(&mut self, cx: &mut Context<Self>) {
+        let text = self.app_state.current_progress.log_snapshot().join("\n");
+        cx.write_to_clipboard(ClipboardItem::new_string(text));
+    }
+
+    /// 行の文字色（エラーは赤、警告は黄）
+    fn line_color(line: &str) -> Rgba {
+        let lower = line.to_lowercase();
+        if lower.contains("error") {
+            rgb(0xf38ba8)
+        } else if lower.contains("warning") {
+            rgb(0xf9e2af)
+        } else {
+            rgb(0xa6adc8)
+        }
+    }
+}
+
+impl Render for LogView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let lines = self.app_state.current_progress.log_snapshot();
+        let is_empty = lines.is_empty();
+
+        // 新しい行が追加されたら末尾までスクロール
+        let log_count = self.app_state.current_progress.log_count();
+        if log_count != self.last_log_count {
+            self.last_log_count = log_count;
+            self.scroll_handle.scroll_to_bottom();
+        }
+
+        div()
+            .w_full()
+            .h(px(220.0))
+            .flex()
+            .flex_col()
+            .bg(rgb(0x11111b))
+            // ヘッダー
+            .child(
+                div()
+                    .w_full()
+                    .h(px(32.0))
+                    .px(px(16.0))
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(rgb(0x313244))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(rgb(0xa6adc8))
+                            .child(format!("FFmpegログ ({} 行)", lines.len())),
+                    )
+                    .child(
+                        Button::new("copy-log")
+                            .label("コピー")
+                            .with_variant(ButtonVariant::Ghost)
+                            .disabled(is_empty)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.copy_all(cx);
+                            })),
+                    ),
+            )
+            // ログ本文
+            .child(
+                div()
+                    .id("log-lines")
+                    .flex_1()
+                    .w_full()
+                    .px(px(16.0))
+                    .py(px(8.0))
+                    .overflow_y_scroll()
+                    .track_scroll(&self.scroll_handle)
+                    .font_family("monospace")
+                    .text_xs()
+                    .children(if is_empty {
+                        vec![div()
+                            .text_color(rgb(0x6c7086))
+                            .child("ログはまだありません")]
+                    } else {
+                        lines
+                            .into_iter()
+                            .map(|line| div().text_color(Self::line_color(&line)).child(line))
+                            .collect()
+                    }),
+            )
+    }
+}
@@ -5,8 +5,128 @@ use gpui::*;
 use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
 use gpui_component::Disableable;
 
-use super::{AboutDialog, FileList, ProgressView, SettingsPanel};
-use crate::app::AppState;
+use super::{
+    AboutDialog, DownloadDialog, FileList, FileListEvent, HistoryView, LogView, ProgressView,
+    SettingsPanel,
+};
+use crate::app::{AppState, CurrentProgress, FileEntry};
+use crate::config::{PostFileAction, PostTranscodeAction, Settings};
+use crate::history::{format_timestamp, History};
+use crate::queue::{QueueOptions, SessionSlots, ValidationReport};
+use crate::transcoder::{
+    format_size, DiskSpaceShortage, HwAccelType, ResolvedEncodePlan, TranscodeJob,
+    TranscodeSettings, VideoCodec,
+};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// 完了後のスリープ・シャットダウンなどを実行するまでの猶予（秒）
+const POST_ACTION_DELAY_SECS: u32 = 60;
+
+/// 設定パネルの最小幅
+const SETTINGS_PANEL_MIN_WIDTH: f32 = 280.0;
+/// 設定パネルの最大幅
+const SETTINGS_PANEL_MAX_WIDTH: f32 = 600.0;
+
+/// ウィンドウの既定の幅
+const DEFAULT_WINDOW_WIDTH: u32 = 1200;
+/// ウィンドウの既定の高さ
+const DEFAULT_WINDOW_HEIGHT: u32 = 800;
+/// ウィンドウの移動・リサイズが止まってから保存するまでの時間（ミリ秒）
+const WINDOW_BOUNDS_SAVE_DELAY_MS: u64 = 1000;
+/// 通知を表示しておく時間（秒）
+const TOAST_DURATION_SECS: u64 = 6;
+
+/// サンプルのエンコード結果
+#[derive(Clone)]
+struct SampleResult {
+    /// 元のファイル名
+    name: String,
+    /// サンプルの出力ファイル
+    output_path: std::path::PathBuf,
+    /// サンプルの長さ（秒）
+    sample_secs: f64,
+    /// サンプルのサイズ（バイト）
+    sample_size: u64,
+    /// サンプルから見積もったファイル全体のサイズ
+    extrapolated_size: u64,
+    /// 変換前の予測サイズ
+    estimated_size: Option<u64>,
+}
+
+impl SampleResult {
+    /// 予測サイズに対する見積もりの差（%）
+    fn difference_percent(&self) -> Option<f64> {
+        let estimated = self.estimated_size.filter(|size| *size > 0)?;
+        Some((self.extrapolated_size as f64 / estimated as f64 - 1.0) * 100.0)
+    }
+}
+
+/// 変換中のバッチの設定（並列のジョブで共有し、変更しない）
+struct BatchConfig {
+    /// アプリケーション状態
+    app_state: AppState,
+    /// バッチ全体で共通のオプション
+    options: QueueOptions,
+    /// 変換設定
+    settings: TranscodeSettings,
+    /// バッチ開始時の対象ファイル
+    files: Vec<FileEntry>,
+    /// 再試行・選択したファイルのみの変換で対象にするファイル
+    targets: Option<HashSet<PathBuf>>,
+    /// バッチ開始時の設定の検証結果
+    validation: ValidationReport,
+    /// ファイルごとのエラーを通知する
+    notify_on_error: bool,
+    /// 変換に成功したファイルの元ファイルの扱い
+    post_file_action: PostFileAction,
+    /// 失敗時の再試行回数
+    retry_on_failure: u8,
+    /// 再試行までの待ち時間
+    retry_delay: Duration,
+    /// 複数のファイルを同時に変換する
+    parallel: bool,
+}
+
+/// 変換中のバッチで並列のジョブが共有する状態
+#[derive(Default)]
+struct BatchState {
+    /// このバッチで扱ったファイル（設定の組み合わせが不正なファイルは変換しない）
+    handled: HashSet<PathBuf>,
+    /// コーデックとHWアクセラレーションの組み合わせごとに解決したエンコーダー
+    /// （並列変換では同時セッション数が上限のときに使うソフトウェアエンコーダーも）
+    encode_plans: Vec<(
+        (VideoCodec, HwAccelType),
+        ResolvedEncodePlan,
+        Option<ResolvedEncodePlan>,
+    )>,
+    /// HWアクセラレーションごとの同時セッションの割り当て
+    session_slots: Vec<(HwAccelType, Rc<SessionSlots>)>,
+    /// 変換中のジョブと進捗（開始した順）
+    running: Vec<(TranscodeJob, CurrentProgress)>,
+    /// このバッチで変換できたファイル数
+    processed_count: usize,
+    /// 中止・容量不足のため残りのファイルを変換しない
+    stopped: bool,
+    /// すべてのジョブが終わった
+    finished: bool,
+}
+
+/// 最近使ったファイルのメニュー項目
+#[derive(Clone)]
+struct RecentFile {
+    /// ファイルパス
+    path: std::path::PathBuf,
+    /// ファイル名
+    name: String,
+    /// ファイルサイズ（見つからない場合はNone）
+    size: Option<u64>,
+    /// 最後に変換した日時（UNIX時間、秒）
+    transcoded_at: Option<u64>,
+}
 
 /// メインウィンドウ
 pub struct MainWindow {
@@ -18,59 +138,318 @@ pub struct MainWindow {
     settings_panel: Entity<SettingsPanel>,
     /// 進捗ビュー
     progress_view: Entity<ProgressView>,
+    /// FFmpegログビュー
+    log_view: Entity<LogView>,
+    /// FFmpegダウンロードダイアログ
+    download_dialog: Entity<DownloadDialog>,
+    /// 変換履歴ダイアログ
+    history_view: Entity<HistoryView>,
     /// Aboutダイアログ表示フラグ
     show_about: bool,
+    /// ログパネル表示フラグ
+    show_log: bool,
+    /// FFmpegダウンロードダイアログ表示フラグ
+    show_download: bool,
+    /// 変換履歴ダイアログ表示フラグ
+    show_history: bool,
+    /// 警告ダイアログのメッセージ
+    alert_message: Option<SharedString>,
+    /// 一定時間で消える通知（通知ごとの番号とメッセージ）
+    toast: Option<(u32, SharedString)>,
+    /// フォルダ追加の確認待ちファイル
+    pending_folder_files: Option<Vec<std::path::PathBuf>>,
+    /// 空き容量不足の確認待ち
+    pending_space_shortage: Option<DiskSpaceShortage>,
+    /// 元ファイル削除の確認待ち（削除の対象になるファイル）
+    pending_source_deletion: Option<Vec<std::path::PathBuf>>,
+    /// 今回変換するファイル（再試行・選択したファイルのみの変換、Noneならキュー全体）
+    transcode_targets: Option<HashSet<std::path::PathBuf>>,
+    /// 実行待ちの完了後の動作と残り秒数
+    pending_post_action: Option<(PostTranscodeAction, u32)>,
+    /// 表示中のサンプルのエンコード結果
+    sample_result: Option<SampleResult>,
+    /// 表示中の最近使ったファイルのメニュー
+    recent_menu: Option<Vec<RecentFile>>,
+    /// 設定パネルの幅
+    settings_panel_width: f32,
+    /// 区切り線をドラッグ中か
+    dragging_divider: bool,
+    /// ウィンドウ位置・サイズの変更回数（保存の間引きに使う）
+    window_bounds_revision: u64,
+    /// キーボードショートカット用のフォーカス
+    focus_handle: FocusHandle,
 }
 
 impl MainWindow {
-    pub fn new(app_state: AppState, cx: &mut Context<Self>) -> Self {
+    pub fn new(app_state: AppState, window: &mut Window, cx: &mut Context<Self>) -> Self {
         let file_list = cx.new(|cx| FileList::new(app_state.clone(), cx));
         let settings_panel = cx.new(|cx| SettingsPanel::new(app_state.clone(), cx));
         let progress_view = cx.new(|cx| ProgressView::new(app_state.clone(), cx));
+        let log_view = cx.new(|cx| LogView::new(app_state.clone(), cx));
+        let download_dialog = cx.new(|cx| DownloadDialog::new(app_state.clone(), cx));
+        let history_view = cx.new(HistoryView::new);
 
-        // FFmpegを検出
-        Self::detect_ffmpeg(&app_state, cx);
+        cx.subscribe(&download_dialog, |this, _, _: &DismissEvent, cx| {
+            this.show_download = false;
+            cx.notify();
+        })
+        .detach();
+        cx.subscribe(&history_view, |this, _, _: &DismissEvent, cx| {
+            this.hide_history(cx);
+        })
+        .detach();
+        cx.subscribe(
+            &file_list,
+            |this, _, event: &FileListEvent, cx| match event {
+                FileListEvent::EncodeSample(path) => this.run_sample_encode(path.clone(), cx),
+                FileListEvent::RetryFile(path) => this.retry_file(path.clone(), cx),
+                FileListEvent::TranscodeSelected(paths) => {
+                    this.start_transcode_selected(paths.clone(), cx)
+                }
+            },
+        )
+        .detach();
+
+        // FFmpegを検出（見つからなければダウンロードダイアログを表示）
+        let download_reason = Self::detect_ffmpeg(&app_state, cx);
+        let show_download = download_reason.is_some();
+        if let Some(reason) = download_reason {
+            download_dialog.update(cx, |dialog, cx| dialog.reset(reason, cx));
+        }
+
+        // 監視フォルダのポーリングを開始
+        Self::start_folder_watcher(app_state.clone(), cx);
+        // キューの自動保存を開始
+        Self::start_queue_autosave(app_state.clone(), cx);
+        // ウィンドウ位置・サイズを保存
+        cx.observe_window_bounds(window, |this, window, cx| {
+            this.schedule_window_bounds_save(window.window_bounds().get_bounds(), cx);
+        })
+        .detach();
+
+        let settings_panel_width = app_state
+            .settings
+            .read(cx)
+            .settings_panel_width
+            .clamp(SETTINGS_PANEL_MIN_WIDTH, SETTINGS_PANEL_MAX_WIDTH);
 
         Self {
             app_state,
             file_list,
             settings_panel,
             progress_view,
+            log_view,
+            download_dialog,
+            history_view,
             show_about: false,
+            show_log: false,
+            show_download,
+            show_history: false,
+            alert_message: None,
+            toast: None,
+            pending_folder_files: None,
+            pending_space_shortage: None,
+            pending_source_deletion: None,
+            transcode_targets: None,
+            pending_post_action: None,
+            sample_result: None,
+            recent_menu: None,
+            settings_panel_width,
+            dragging_divider: false,
+            window_bounds_revision: 0,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    /// キーボードショートカット
+    /// 無効なボタンに対応するショートカットは何もしない
+    fn handle_key_down(
+        &mut self,
+        event: &KeyDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let keystroke = &event.keystroke;
+        let secondary = keystroke.modifiers.secondary();
+        let has_files = !self.app_state.files.read(cx).is_empty();
+        let is_running = self.app_state.current_job.read(cx).is_some();
+        // テキスト入力中のSpaceは入力に使う
+        let list_focused = window.focused(cx).is_none()
+            || self.focus_handle.is_focused(window)
+            || self.file_list.focus_handle(cx).is_focused(window);
+
+        let handled = match keystroke.key.as_str() {
+            "o" if secondary => {
+                self.open_file_dialog(cx);
+                true
+            }
+            "delete" if secondary && has_files && !is_running => {
+                self.clear_queue(cx);
+                true
+            }
+            "space" if !secondary && list_focused && has_files && !is_running => {
+                self.start_transcode(cx);
+                true
+            }
+            "enter" if secondary && has_files && !is_running => {
+                self.start_transcode(cx);
+                true
+            }
+            "escape" if self.show_about => {
+                self.hide_about(cx);
+                true
+            }
+            "escape" if self.show_history => {
+                self.hide_history(cx);
+                true
+            }
+            "escape" if self.alert_message.is_some() => {
+                self.hide_alert(cx);
+                true
+            }
+            "escape" if is_running => {
+                log::info!("Cancel requested from keyboard");
+                self.app_state.current_progress.cancel();
+                true
+            }
+            "f1" => {
+                self.show_about(cx);
+                true
+            }
+            _ => false,
+        };
+        if handled {
+            cx.stop_propagation();
+        }
+    }
+
+    /// 区切り線のドラッグを開始
+    fn start_divider_drag(&mut self, cx: &mut Context<Self>) {
+        self.dragging_divider = true;
+        cx.notify();
+    }
+
+    /// ドラッグ中はマウス位置に合わせて設定パネルの幅を変更
+    fn drag_divider(
+        &mut self,
+        event: &MouseMoveEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.dragging_divider {
+            return;
+        }
+        // ウィンドウ外でボタンを離した場合
+        if event.pressed_button != Some(MouseButton::Left) {
+            self.end_divider_drag(cx);
+            return;
+        }
+
+        let width = f32::from(window.viewport_size().width) - f32::from(event.position.x);
+        self.settings_panel_width = width.clamp(SETTINGS_PANEL_MIN_WIDTH, SETTINGS_PANEL_MAX_WIDTH);
+        cx.notify();
+    }
+
+    /// 区切り線のドラッグを終了して幅を保存
+    fn end_divider_drag(&mut self, cx: &mut Context<Self>) {
+        if !self.dragging_divider {
+            return;
         }
+        self.dragging_divider = false;
+        let width = self.settings_panel_width;
+        SettingsPanel::update_app_settings(&self.app_state, cx, |settings| {
+            settings.settings_panel_width = width;
+        });
+        cx.notify();
+    }
+
+    /// 起動時のウィンドウ位置・サイズ
+    /// 前回の位置が見つからなければ中央に表示し、いずれも画面内に収める
+    pub fn initial_bounds(settings: &Settings, cx: &App) -> Bounds<Pixels> {
+        let size = size(
+            px(settings.window_width.unwrap_or(DEFAULT_WINDOW_WIDTH) as f32),
+            px(settings.window_height.unwrap_or(DEFAULT_WINDOW_HEIGHT) as f32),
+        );
+        let bounds = match (settings.window_x, settings.window_y) {
+            (Some(x), Some(y)) => Bounds::new(point(px(x as f32), px(y as f32)), size),
+            _ => Bounds::centered(None, size, cx),
+        };
+        // メインディスプレイを優先
+        let displays: Vec<Bounds<Pixels>> = cx
+            .primary_display()
+            .into_iter()
+            .chain(cx.displays())
+            .map(|display| display.bounds())
+            .collect();
+        fit_bounds_to_displays(bounds, &displays)
+    }
+
+    /// ウィンドウ位置・サイズの変更が1秒止まったら設定に保存
+    fn schedule_window_bounds_save(&mut self, bounds: Bounds<Pixels>, cx: &mut Context<Self>) {
+        use std::time::Duration;
+
+        self.window_bounds_revision += 1;
+        let revision = self.window_bounds_revision;
+        cx.spawn(async move |this, cx| {
+            smol::Timer::after(Duration::from_millis(WINDOW_BOUNDS_SAVE_DELAY_MS)).await;
+            this.update(cx, |this, cx| {
+                // 待っている間にさらに変更された
+                if this.window_bounds_revision != revision {
+                    return;
+                }
+                SettingsPanel::update_app_settings(&this.app_state, cx, |settings| {
+                    settings.window_x = Some(f32::from(bounds.origin.x).round() as i32);
+                    settings.window_y = Some(f32::from(bounds.origin.y).round() as i32);
+                    settings.window_width = Some(f32::from(bounds.size.width).round() as u32);
+                    settings.window_height = Some(f32::from(bounds.size.height).round() as u32);
+                });
+            })
+            .ok();
+        })
+        .detach();
     }
 
     /// FFmpegを検出
-    fn detect_ffmpeg(app_state: &AppState, cx: &mut Context<Self>) {
+    /// ダウンロードが必要な場合はその理由を返す
+    fn detect_ffmpeg(app_state: &AppState, cx: &mut Context<Self>) -> Option<String> {
         use crate::ffmpeg::{FfmpegDetector, FfmpegDownloader};
         use log::{info, warn};
 
-        // 既存のFFmpegを検出
-        match FfmpegDetector::detect() {
+        // 既存のFFmpegを検出（設定でパスが指定されていればそれを優先）
+        let custom_path = app_state.settings.read(cx).ffmpeg_custom_path.clone();
+        let reason = match FfmpegDetector::detect_with_custom_path(custom_path.as_ref()) {
+            Ok(info) if FfmpegDetector::check_version_requirement(&info, 7) => {
+                info!("Found FFmpeg {} at {:?}", info.version, info.ffmpeg_path);
+                app_state.ffmpeg_path.update(cx, |path, _| {
+                    *path = Some(info.ffmpeg_path);
+                });
+                return None;
+            }
             Ok(info) => {
-                if FfmpegDetector::check_version_requirement(&info, 7) {
-                    info!("Found FFmpeg {} at {:?}", info.version, info.ffmpeg_path);
-                    app_state.ffmpeg_path.update(cx, |path, _| {
-                        *path = Some(info.ffmpeg_path);
-                    });
-                } else {
-                    warn!("FFmpeg {} found but version 7.0+ required", info.version);
-                    // TODO: ダウンロードを促すダイアログを表示
-                }
+                warn!("FFmpeg {} found but version 7.0+ required", info.version);
+                format!(
+                    "FFmpeg {} が見つかりましたが、バージョンが古すぎます",
+                    info.version
+                )
             }
             Err(e) => {
                 warn!("FFmpeg not found: {}", e);
-                // ダウンロード済みをチェック
-                if let Ok(Some(path)) = FfmpegDownloader::is_downloaded() {
-                    info!("Found downloaded FFmpeg at {:?}", path);
-                    app_state.ffmpeg_path.update(cx, |ffmpeg_path, _| {
-                        *ffmpeg_path = Some(path);
-                    });
-                } else {
-                    // TODO: ダウンロードダイアログを表示
-                    warn!("No FFmpeg available, download required");
-                }
+                "FFmpegが見つかりません".to_string()
             }
+        };
+
+        // ダウンロード済みをチェック
+        let downloaded = FfmpegDownloader::is_downloaded()
+            .ok()
+            .flatten()
+            .and_then(|path| FfmpegDetector::check_ffmpeg_at_path(&path).ok());
+        if let Some(info) = downloaded {
+            info!("Found downloaded FFmpeg at {:?}", info.ffmpeg_path);
+            app_state.set_ffmpeg(info, cx);
+            None
+        } else {
+            warn!("No FFmpeg available, download required");
+            Some(reason)
         }
     }
 
@@ -92,16 +471,75 @@ impl MainWindow {
 
             if let Some(files) = files {
                 let paths: Vec<_> = files.into_iter().map(|f| f.path().to_path_buf()).collect();
-                cx.update(|cx| {
-                    app_state.add_files(paths, cx);
+                let skipped = cx.update(|cx| app_state.add_files(paths, cx)).unwrap_or(0);
+                this.update(cx, |this, cx| {
+                    if skipped > 0 {
+                        this.show_toast(already_queued_message(skipped), cx);
+                    }
+                    cx.notify();
+                })
+                .ok();
+            }
+        })
+        .detach();
+    }
+
+    /// フォルダ追加ダイアログを開く（サブフォルダも再帰的に検索）
+    fn open_folder_dialog(&mut self, cx: &mut Context<Self>) {
+        let settings = self.app_state.transcode_settings.read(cx);
+        let output_dir = settings.output_dir.clone();
+        let output_suffix = settings.output_suffix.clone();
+
+        cx.spawn(async move |this, cx| {
+            let folder = rfd::AsyncFileDialog::new()
+                .set_title("フォルダを選択")
+                .pick_folder()
+                .await;
+
+            if let Some(folder) = folder {
+                let root = folder.path().to_path_buf();
+                let files = smol::unblock(move || {
+                    collect_video_files(&root, output_dir.as_deref(), &output_suffix)
+                })
+                .await;
+
+                this.update(cx, |this, cx| {
+                    if files.is_empty() {
+                        this.show_alert("対応する動画ファイルが見つかりませんでした", cx);
+                    } else {
+                        this.pending_folder_files = Some(files);
+                        cx.notify();
+                    }
                 })
                 .ok();
-                this.update(cx, |_, cx| cx.notify()).ok();
             }
         })
         .detach();
     }
 
+    /// フォルダ追加の確認後、ファイルをキューに追加
+    fn confirm_folder_files(&mut self, cx: &mut Context<Self>) {
+        if let Some(files) = self.pending_folder_files.take() {
+            let app_state = self.app_state.clone();
+            let total = files.len();
+            let new_files: Vec<_> = files
+                .into_iter()
+                .filter(|path| !app_state.is_queued(path, cx))
+                .collect();
+            let skipped = total - new_files.len() + app_state.add_files(new_files, cx);
+            if skipped > 0 {
+                self.show_toast(already_queued_message(skipped), cx);
+            }
+        }
+        cx.notify();
+    }
+
+    /// フォルダ追加をキャンセル
+    fn cancel_folder_files(&mut self, cx: &mut Context<Self>) {
+        self.pending_folder_files = None;
+        cx.notify();
+    }
+
     /// 出力フォルダを選択
     fn select_output_folder(&mut self, cx: &mut Context<Self>) {
         let app_state = self.app_state.clone();
@@ -126,26 +564,168 @@ impl MainWindow {
         .detach();
     }
 
-    /// トランスコード開始
+    /// 出力先の空き容量を確認してからトランスコードを開始
     fn start_transcode(&mut self, cx: &mut Context<Self>) {
+        self.transcode_targets = None;
+        self.confirm_and_start(cx);
+    }
+
+    /// 選択したファイルだけを変換
+    fn start_transcode_selected(
+        &mut self,
+        paths: HashSet<std::path::PathBuf>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.app_state.current_job.read(cx).is_some() {
+            return;
+        }
+        self.transcode_targets = Some(paths);
+        self.confirm_and_start(cx);
+    }
+
+    /// 失敗したファイルを待機中に戻し、そのファイルだけを変換し直す
+    fn retry_file(&mut self, path: std::path::PathBuf, cx: &mut Context<Self>) {
+        use crate::app::FileStatus;
+        use log::info;
+
+        if self.app_state.current_job.read(cx).is_some() {
+            return;
+        }
+        self.app_state.update_files(cx, |files, _| {
+            if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                f.status = FileStatus::Pending;
+                f.progress = 0.0;
+                f.log = None;
+                f.note = None;
+                f.quality = None;
+                f.output_path = None;
+                f.output_size = None;
+            }
+        });
+        info!("Retrying {:?}", path);
+        self.transcode_targets = Some(HashSet::from([path]));
+        self.confirm_and_start(cx);
+    }
+
+    /// 変換の対象か（再試行・選択したファイルのみの変換では指定したファイルだけ）
+    fn is_transcode_target(&self, file: &FileEntry) -> bool {
+        !file.status.is_completed()
+            && self
+                .transcode_targets
+                .as_ref()
+                .is_none_or(|paths| paths.contains(&file.path))
+    }
+
+    /// 元ファイルを削除する設定なら、対象のファイルを確認してから開始する
+    fn confirm_and_start(&mut self, cx: &mut Context<Self>) {
+        if self.app_state.settings.read(cx).post_file_action == PostFileAction::DeleteSource {
+            let files: Vec<_> = self
+                .app_state
+                .files
+                .read(cx)
+                .iter()
+                .filter(|f| self.is_transcode_target(f))
+                .map(|f| f.path.clone())
+                .collect();
+            if !files.is_empty() {
+                self.pending_source_deletion = Some(files);
+                cx.notify();
+                return;
+            }
+        }
+        self.check_space_and_run(cx);
+    }
+
+    /// 元ファイルの削除を承知で変換を開始
+    fn confirm_source_deletion(&mut self, cx: &mut Context<Self>) {
+        self.pending_source_deletion = None;
+        self.check_space_and_run(cx);
+    }
+
+    /// 元ファイルの削除を確認して変換を中止
+    fn cancel_source_deletion(&mut self, cx: &mut Context<Self>) {
+        self.pending_source_deletion = None;
+        cx.notify();
+    }
+
+    /// 出力先の空き容量を確認してから変換を開始
+    fn check_space_and_run(&mut self, cx: &mut Context<Self>) {
+        use crate::transcoder::check_disk_space;
+        use log::warn;
+
+        // 出力先ごとの推定サイズ（推定できなければ元のサイズ）
+        let settings = self.app_state.transcode_settings.read(cx);
+        let outputs: Vec<_> = self
+            .app_state
+            .files
+            .read(cx)
+            .iter()
+            .filter(|f| self.is_transcode_target(f))
+            .filter_map(|f| {
+                let dir = f
+                    .effective_settings(settings)
+                    .output_dir
+                    .clone()
+                    .or_else(|| f.path.parent().map(|p| p.to_path_buf()))?;
+                Some((dir, f.estimated_size.unwrap_or(f.size)))
+            })
+            .collect();
+
+        cx.spawn(async move |this, cx| {
+            let shortage = smol::unblock(move || check_disk_space(outputs)).await;
+            this.update(cx, |this, cx| match shortage {
+                Some(shortage) => {
+                    warn!("{} ({:?})", shortage.message(), shortage.dir);
+                    this.pending_space_shortage = Some(shortage);
+                    cx.notify();
+                }
+                None => this.run_transcode(cx),
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// 空き容量不足を承知で変換を開始
+    fn confirm_space_shortage(&mut self, cx: &mut Context<Self>) {
+        self.pending_space_shortage = None;
+        self.run_transcode(cx);
+    }
+
+    /// 空き容量不足のため変換を中止
+    fn cancel_space_shortage(&mut self, cx: &mut Context<Self>) {
+        self.pending_space_shortage = None;
+        cx.notify();
+    }
+
+    /// トランスコード開始
+    fn run_transcode(&mut self, cx: &mut Context<Self>) {
         use crate::app::FileStatus;
-        use crate::transcoder::{FfmpegError, FfmpegProgressInfo, HwAccelDetector, TranscodeJob};
-        use log::{error, info};
-        use std::io::{BufRead, BufReader};
-        use std::process::{Command, Stdio};
-        use std::time::Instant;
+        use crate::notification::{notify_completion, CompletionSummary};
+        use crate::queue::MAX_PARALLEL_JOBS;
+        use log::{error, info, warn};
+        use std::time::{Duration, Instant};
 
         // FFmpegパスを取得
         let ffmpeg_path = match self.app_state.ffmpeg_path.read(cx).clone() {
             Some(path) => path,
             None => {
                 error!("FFmpeg not available");
+                self.show_download_dialog("FFmpegが見つかりません", cx);
                 return;
             }
         };
 
-        // ファイルがあるか確認
-        let files = self.app_state.files.read(cx).clone();
+        // ファイルがあるか確認（再試行・選択したファイルのみの変換では指定したファイルだけ）
+        let targets = self.transcode_targets.take();
+        let files: Vec<_> = self
+            .app_state
+            .files
+            .read(cx)
+            .iter()
+            .filter(|f| targets.as_ref().is_none_or(|paths| paths.contains(&f.path)))
+            .cloned()
+            .collect();
         if files.is_empty() {
             info!("No files to transcode");
             return;
@@ -154,278 +734,1922 @@ impl MainWindow {
         // 設定を取得
         let settings = self.app_state.transcode_settings.read(cx).clone();
 
-        // 出力ディレクトリを決定（設定がなければ入力ファイルと同じディレクトリ）
+        // 出力先（キュー完了後にフォルダを開くため）
         let output_dir = settings.output_dir.clone();
-        let output_suffix = settings.output_suffix.clone();
+        let notify_on_error = self.app_state.settings.read(cx).notify_on_error;
+        let post_file_action = self.app_state.settings.read(cx).post_file_action.clone();
+        let retry_on_failure = self.app_state.settings.read(cx).retry_on_failure;
+        let retry_delay =
+            Duration::from_secs(self.app_state.settings.read(cx).retry_delay_secs.into());
 
-        // HWアクセラレーションを解決
-        let resolved_hwaccel = HwAccelDetector::resolve_auto(settings.hwaccel, Some(&ffmpeg_path));
-        let mut resolved_settings = settings.clone();
-        resolved_settings.hwaccel = resolved_hwaccel;
+        // HWアクセラレーションを解決し、検証・画質評価の設定を反映
+        let options = QueueOptions::new(ffmpeg_path.clone(), &settings).with_app_settings(
+            self.app_state.settings.read(cx),
+            self.app_state.ffmpeg_info.read(cx).as_ref(),
+        );
+
+        // ストリームコピーできないファイルや、FFmpegビルドにないエンコーダーがあれば開始しない
+        if let Some(message) = options.preflight(&files) {
+            self.show_alert(message, cx);
+            return;
+        }
 
         let app_state = self.app_state.clone();
 
         // 進捗をリセット
         app_state.current_progress.reset();
+        app_state.current_progress.clear_log();
+        let batch_start = Instant::now();
+
+        // FFmpegが失敗する設定の組み合わせのファイルは変換せずにエラーにする
+        let validation = options.validate_files(&files);
+        for warning in &validation.warnings {
+            app_state
+                .current_progress
+                .push_log(format!("警告: {}", warning));
+        }
+        if !validation.rejected.is_empty() {
+            app_state.update_files(cx, |entries, _| {
+                for (index, message) in &validation.rejected {
+                    let Some(path) = files.get(*index).map(|f| &f.path) else {
+                        continue;
+                    };
+                    if let Some(f) = entries.iter_mut().find(|f| f.path == *path) {
+                        f.status = FileStatus::Error(message.clone());
+                    }
+                }
+            });
+            cx.notify();
+        }
 
         info!("Starting transcode for {} files", files.len());
 
+        // 同時に変換するファイル数（1なら1件ずつ順に変換する）
+        let jobs =
+            (self.app_state.settings.read(cx).parallel_jobs as usize).clamp(1, MAX_PARALLEL_JOBS);
+        let config = Rc::new(BatchConfig {
+            app_state: app_state.clone(),
+            options,
+            settings,
+            files,
+            targets,
+            validation,
+            notify_on_error,
+            post_file_action,
+            retry_on_failure,
+            retry_delay,
+            parallel: jobs > 1,
+        });
+
         // 非同期でトランスコード処理を実行
         cx.spawn(async move |this, cx| {
-            for (index, file) in files.iter().enumerate() {
-                // 進捗をリセット
-                app_state.current_progress.reset();
+            let batch = Rc::new(RefCell::new(BatchState {
+                handled: config
+                    .validation
+                    .rejected
+                    .iter()
+                    .filter_map(|(index, _)| config.files.get(*index))
+                    .map(|f| f.path.clone())
+                    .collect(),
+                ..Default::default()
+            }));
 
-                // ファイルの状態を「処理中」に更新
-                cx.update(|cx| {
-                    app_state.files.update(cx, |files, _| {
-                        if let Some(f) = files.get_mut(index) {
-                            f.status = FileStatus::Processing;
-                            f.progress = 0.0;
-                        }
-                    });
+            // エンコーダーはバッチ開始時に一度だけ解決（HWエンコーダーのテストを含む）
+            // 設定を固定したファイルの分は、コーデックとHWアクセラレーションの組み合わせごとに解決する
+            {
+                let options = config.options.clone();
+                let parallel = config.parallel;
+                let key = (options.settings.video_codec, options.settings.hwaccel);
+                let (plan, software) =
+                    smol::unblock(move || resolve_batch_plan(&options, parallel)).await;
+                batch.borrow_mut().encode_plans.push((key, plan, software));
+            }
+
+            // 並列変換では中止・スキップを各ジョブに伝え、進捗の表示を切り替える
+            let relay = config.parallel.then(|| {
+                let this = this.clone();
+                let app_state = app_state.clone();
+                let batch = batch.clone();
+                cx.spawn(async move |cx| {
+                    Self::relay_parallel_progress(this, app_state, batch, cx).await
                 })
-                .ok();
-                this.update(cx, |_, cx| cx.notify()).ok();
+            });
+
+            let lanes: Vec<_> = (0..jobs)
+                .map(|_| {
+                    let this = this.clone();
+                    let config = config.clone();
+                    let batch = batch.clone();
+                    // 1件ずつの変換では進捗をそのまま表示する
+                    let progress = if config.parallel {
+                        app_state.current_progress.for_parallel_job()
+                    } else {
+                        app_state.current_progress.clone()
+                    };
+                    cx.spawn(async move |cx| {
+                        Self::transcode_lane(this, config, batch, progress, cx).await
+                    })
+                })
+                .collect();
+            for lane in lanes {
+                lane.await;
+            }
+            batch.borrow_mut().finished = true;
+            if let Some(relay) = relay {
+                relay.await;
+            }
+            let processed_count = batch.borrow().processed_count;
+
+            // 完了後、現在のジョブをクリア
+            cx.update(|cx| {
+                app_state.current_job.update(cx, |current, _| {
+                    *current = None;
+                });
+            })
+            .ok();
+            this.update(cx, |_, cx| cx.notify()).ok();
 
-                // 動画の長さを取得（進捗計算用）
-                let total_duration_secs = file.metadata.duration.unwrap_or(0.0);
-                info!("Total duration for {}: {:.2}s", file.name, total_duration_secs);
+            info!("All transcoding completed");
 
-                // 出力パスを決定
-                let out_dir = output_dir.clone().unwrap_or_else(|| {
-                    file.path
-                        .parent()
-                        .map(|p| p.to_path_buf())
-                        .unwrap_or_else(|| std::path::PathBuf::from("."))
+            // キュー完了後の動作（キャンセル時は実行しない）
+            if app_state.current_progress.is_cancelled() {
+                return;
+            }
+            let Ok(queue_result) = cx.update(|cx| {
+                let files_now = app_state.files.read(cx);
+                let completed_count = files_now
+                    .iter()
+                    .filter(|f| f.status.is_completed())
+                    .count();
+                let failed_count = files_now
+                    .iter()
+                    .filter(|f| matches!(f.status, FileStatus::Error(_)))
+                    .count();
+                // 変換中に追加されたファイル
+                let pending_count = files_now
+                    .iter()
+                    .filter(|f| f.status == FileStatus::Pending)
+                    .count();
+                let last_output_dir = output_dir.clone().or_else(|| {
+                    config
+                        .files
+                        .last()
+                        .and_then(|f| f.path.parent().map(|p| p.to_path_buf()))
                 });
-                let output_path = TranscodeJob::generate_output_path(
-                    &file.path,
-                    &out_dir,
-                    &output_suffix,
-                    &resolved_settings,
-                );
+                let app_settings = app_state.settings.read(cx);
+                (
+                    app_settings.post_transcode_action.clone(),
+                    app_settings.notify_on_complete,
+                    completed_count,
+                    failed_count,
+                    pending_count,
+                    last_output_dir,
+                )
+            }) else {
+                return;
+            };
+            let (
+                post_action,
+                notify,
+                completed_count,
+                failed_count,
+                pending_count,
+                last_output_dir,
+            ) = queue_result;
 
-                // ジョブを作成
-                let job = TranscodeJob::new(
-                    file.path.clone(),
-                    output_path.clone(),
-                    resolved_settings.clone(),
+            // デスクトップ通知
+            if notify {
+                notify_completion(CompletionSummary {
+                    completed: completed_count,
+                    failed: failed_count,
+                    elapsed: batch_start.elapsed(),
+                    output_dir: last_output_dir.clone(),
+                });
+            }
+
+            if post_action == PostTranscodeAction::Nothing {
+                return;
+            }
+            if processed_count == 0 || pending_count > 0 {
+                info!(
+                    "Skipping post-transcode action {:?} (processed: {}, pending: {})",
+                    post_action, processed_count, pending_count
+                );
+                return;
+            }
+            if failed_count > 0 {
+                warn!(
+                    "{} file(s) failed, skipping post-transcode action {:?}",
+                    failed_count, post_action
                 );
+                this.update(cx, |this, cx| {
+                    this.show_alert(
+                        format!(
+                            "{}件のファイルでエラーが発生したため、完了後の動作「{}」を実行しませんでした",
+                            failed_count,
+                            post_action.display_name()
+                        ),
+                        cx,
+                    );
+                })
+                .ok();
+                return;
+            }
 
-                // 現在のジョブを設定
-                cx.update(|cx| {
-                    app_state.current_job.update(cx, |current, _| {
-                        *current = Some(job.clone());
-                    });
+            // スリープ・シャットダウンなどは取り消せるよう猶予を設ける
+            if post_action.is_one_shot() {
+                this.update(cx, |this, cx| {
+                    this.start_post_action_countdown(post_action, cx);
                 })
                 .ok();
-                this.update(cx, |_, cx| cx.notify()).ok();
+                return;
+            }
 
-                // FFmpegコマンドを構築（FFmpegパスを渡してエンコーダー利用可能性をチェック）
-                let args = job.build_ffmpeg_args_with_path(Some(&ffmpeg_path));
-                info!("Running FFmpeg: {:?} {:?}", ffmpeg_path, args);
-
-                // 進捗更新用のクロージャ
-                let current_progress = app_state.current_progress.clone();
-                let start_time = Instant::now();
-                
-                // 総時間を設定
-                current_progress.set_total_duration_secs(total_duration_secs);
-
-                // FFmpegプロセスを実行（stdoutから進捗を読み取る）
-                let ffmpeg_path_clone = ffmpeg_path.clone();
-
-                let result = smol::unblock(move || {
-                    let mut child = Command::new(&ffmpeg_path_clone)
-                        .args(&args)
-                        .stdout(Stdio::piped())
-                        .stderr(Stdio::piped())
-                        .spawn()?;
-
-                    // stdoutから進捗情報を読み取る（-progress pipe:1形式）
-                    if let Some(stdout) = child.stdout.take() {
-                        let reader = BufReader::new(stdout);
-                        let mut progress_info = FfmpegProgressInfo::default();
-
-                        for line_result in reader.lines() {
-                            // キャンセルチェック
-                            if current_progress.is_cancelled() {
-                                log::info!("Transcode cancelled, killing FFmpeg process");
-                                let _ = child.kill();
-                                break;
-                            }
+            info!("Running post-transcode action: {:?}", post_action);
+            if let Err(e) = post_action.execute(last_output_dir.as_deref()) {
+                error!("Post-transcode action failed: {}", e);
+                this.update(cx, |this, cx| {
+                    this.show_alert(format!("完了後の動作に失敗しました: {}", e), cx);
+                })
+                .ok();
+            }
+        })
+        .detach();
 
-                            if let Ok(line) = line_result {
-                                // 行を累積的にパースして、進捗ブロックが完了したら更新
-                                if progress_info.parse_progress_line(&line) {
-                                    // progress=continue または progress=end が来たらブロック完了
-                                    if progress_info.is_valid() {
-                                        // time_secsベースで進捗を更新
-                                        current_progress.update_progress_from_time(progress_info.time_secs);
-                                        let progress = current_progress.get_progress();
-
-                                        current_progress
-                                            .set_elapsed_secs(start_time.elapsed().as_secs_f32());
-                                        current_progress.set_fps(progress_info.fps);
-
-                                        // 残り時間を計算
-                                        if progress > 0.01 {
-                                            let elapsed = start_time.elapsed().as_secs_f32();
-                                            let total_estimated = elapsed / progress;
-                                            let remaining = (total_estimated - elapsed).max(0.0);
-                                            current_progress.set_remaining_secs(Some(remaining));
-                                        }
-
-                                        log::debug!(
-                                            "Progress: frame={}, time={:.2}s, total={:.2}s, progress={:.1}%",
-                                            progress_info.frame,
-                                            progress_info.time_secs,
-                                            current_progress.get_total_duration_secs(),
-                                            progress * 100.0
-                                        );
-                                    }
-                                    // 次のブロック用にリセット（フレームとFPSは保持）
-                                    let fps = progress_info.fps;
-                                    let frame = progress_info.frame;
-                                    progress_info = FfmpegProgressInfo::default();
-                                    progress_info.fps = fps;
-                                    progress_info.frame = frame;
-                                }
-                            }
-                        }
+        // 進捗表示を定期的に更新するタイマーを開始
+        self.start_progress_timer(cx);
+    }
+
+    /// キューから順にファイルを取り出して変換する（並列変換ではジョブの数だけ同時に動かす）
+    /// キューは毎回読み直し、変換中に追加されたファイルも続けて変換する
+    /// 行の追加・削除で位置が変わるため、状態の更新はパスで対応付ける
+    async fn transcode_lane(
+        this: WeakEntity<Self>,
+        config: Rc<BatchConfig>,
+        batch: Rc<RefCell<BatchState>>,
+        progress: CurrentProgress,
+        cx: &mut AsyncApp,
+    ) {
+        use crate::app::FileStatus;
+        use crate::history::record_completed;
+        use crate::notification::notify_failure;
+        use crate::queue::{next_file, prepare_job, run_job, NextFile, PreparedJob};
+        use crate::transcoder::{FfmpegErrorKind, RateControlMode};
+        use log::{info, warn};
+        use std::time::Duration;
+
+        let app_state = &config.app_state;
+        let settings = &config.settings;
+        let post_file_action = &config.post_file_action;
+        let retry_on_failure = config.retry_on_failure;
+        let retry_delay = config.retry_delay;
+
+        loop {
+            // 中止された場合や、他のジョブが中止・容量不足で止めた場合は次のファイルに進まない
+            if batch.borrow().stopped || app_state.current_progress.is_cancelled() {
+                break;
+            }
+            let next = {
+                let batch = batch.borrow();
+                cx.update(|cx| next_file(app_state.files.read(cx), &batch.handled))
+            };
+            let Ok(next) = next else {
+                break;
+            };
+            let file = match next {
+                NextFile::Ready(file) => *file,
+                NextFile::Probing => {
+                    smol::Timer::after(Duration::from_millis(200)).await;
+                    if app_state.current_progress.is_cancelled() {
+                        break;
                     }
+                    continue;
+                }
+                NextFile::Done => break,
+            };
+            batch.borrow_mut().handled.insert(file.path.clone());
+            // 再試行・選択したファイルのみの変換では指定したファイルだけを変換する
+            if config
+                .targets
+                .as_ref()
+                .is_some_and(|paths| !paths.contains(&file.path))
+            {
+                continue;
+            }
+            let path = file.path.clone();
 
-                    child.wait_with_output()
-                })
-                .await;
+            // 設定を固定したファイルはその設定で変換する
+            let file_options = if file.settings_override.is_some() {
+                let options = config.options.clone();
+                let file = file.clone();
+                smol::unblock(move || options.for_file(&file)).await
+            } else {
+                config.options.clone()
+            };
+            let plan_key = (
+                file_options.settings.video_codec,
+                file_options.settings.hwaccel,
+            );
+            let cached = batch
+                .borrow()
+                .encode_plans
+                .iter()
+                .find(|(key, _, _)| *key == plan_key)
+                .map(|(_, plan, software)| (plan.clone(), software.clone()));
+            let (encode_plan, software_plan) = match cached {
+                Some(plans) => plans,
+                None => {
+                    let options = file_options.clone();
+                    let parallel = config.parallel;
+                    let (plan, software) =
+                        smol::unblock(move || resolve_batch_plan(&options, parallel)).await;
+                    batch.borrow_mut().encode_plans.push((
+                        plan_key,
+                        plan.clone(),
+                        software.clone(),
+                    ));
+                    (plan, software)
+                }
+            };
 
-                // キャンセルされた場合
-                if app_state.current_progress.is_cancelled() {
-                    info!("Transcode was cancelled");
+            // 変換中に追加されたファイルはここで検証する
+            if !config.files.iter().any(|f| f.path == path) {
+                let report = config.options.validate_files(std::slice::from_ref(&file));
+                for warning in &report.warnings {
+                    if !config.validation.warnings.contains(warning) {
+                        progress.push_log(format!("警告: {}", warning));
+                    }
+                }
+                if let Some(message) = report.rejection(0) {
+                    let message = message.to_string();
                     cx.update(|cx| {
-                        app_state.files.update(cx, |files, _| {
-                            if let Some(f) = files.get_mut(index) {
-                                f.status = FileStatus::Cancelled;
+                        app_state.update_files(cx, |files, _| {
+                            if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                                f.status = FileStatus::Error(message);
                             }
                         });
                     })
                     .ok();
-                    // キャンセル後は残りのファイルも処理しない
-                    break;
+                    this.update(cx, |_, cx| cx.notify()).ok();
+                    continue;
                 }
+            }
 
-                match result {
-                    Ok(output) => {
-                        let final_status = if output.status.success() {
-                            info!("Transcode completed: {:?}", output_path);
-                            FileStatus::Completed
-                        } else {
-                            // FFmpegエラーを解析してユーザーフレンドリーなメッセージを生成
-                            let stderr = String::from_utf8_lossy(&output.stderr);
-                            let parsed_error = FfmpegError::parse(&stderr);
+            // 並列変換ではHWエンコーダーの同時セッション数を超えた分をソフトウェアで変換する
+            // ソフトウェアエンコーダーが使えなければセッションが空くまで待つ
+            let slots = if config.parallel {
+                Some(batch_session_slots(&batch, &file_options, encode_plan.hwaccel).await)
+            } else {
+                None
+            };
+            let (encode_plan, slot) = match &slots {
+                Some(slots) => {
+                    let assigned = loop {
+                        if let Some(assigned) =
+                            slots.try_assign(&encode_plan, software_plan.as_ref())
+                        {
+                            break Some(assigned);
+                        }
+                        if batch.borrow().stopped || app_state.current_progress.is_cancelled() {
+                            break None;
+                        }
+                        smol::Timer::after(Duration::from_millis(200)).await;
+                    };
+                    // 待っている間に中止された（ファイルは待機中のまま）
+                    let Some((plan, slot)) = assigned else {
+                        break;
+                    };
+                    if plan != encode_plan {
+                        progress.push_log(format!(
+                            "=== {}: {} の同時セッション数が上限のため {} で変換します ===",
+                            file.name, encode_plan.encoder, plan.encoder
+                        ));
+                    }
+                    (plan, slot)
+                }
+                None => (encode_plan, None),
+            };
 
-                            // ログには詳細を出力
-                            error!("Transcode failed: {}", stderr);
-                            error!("Parsed error: {:?}", parsed_error.kind);
+            // 進捗をリセット
+            progress.reset();
 
-                            // ユーザーには分かりやすいメッセージを表示
-                            FileStatus::Error(parsed_error.format_user_message())
-                        };
+            // ファイルの状態を「処理中」に更新
+            cx.update(|cx| {
+                app_state.update_files(cx, |files, _| {
+                    if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                        f.status = FileStatus::Processing;
+                        f.progress = 0.0;
+                        f.log = None;
+                        f.quality = None;
+                    }
+                });
+            })
+            .ok();
+            this.update(cx, |_, cx| cx.notify()).ok();
 
-                        // ファイルの状態を更新
+            // 出力先を上書きポリシーに従って決めてジョブを作成
+            let mut job = match prepare_job(&file_options, &file, &encode_plan) {
+                PreparedJob::Run { job, note } => {
+                    if note.is_some() {
                         cx.update(|cx| {
-                            app_state.files.update(cx, |files, _| {
-                                if let Some(f) = files.get_mut(index) {
-                                    f.status = final_status;
-                                    f.progress = 1.0;
-                                }
-                            });
-                        })
-                        .ok();
-                    }
-                    Err(e) => {
-                        error!("Failed to run FFmpeg: {}", e);
-                        cx.update(|cx| {
-                            app_state.files.update(cx, |files, _| {
-                                if let Some(f) = files.get_mut(index) {
-                                    f.status = FileStatus::Error(e.to_string());
+                            app_state.update_files(cx, |files, _| {
+                                if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                                    f.note = note;
                                 }
                             });
                         })
                         .ok();
                     }
+                    *job
+                }
+                PreparedJob::Skip { note } => {
+                    cx.update(|cx| {
+                        app_state.update_files(cx, |files, _| {
+                            if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                                f.status = FileStatus::Skipped(note.clone().unwrap_or_default());
+                                f.note = note;
+                            }
+                        });
+                    })
+                    .ok();
+                    this.update(cx, |_, cx| cx.notify()).ok();
+                    continue;
                 }
+            };
 
-                this.update(cx, |_, cx| cx.notify()).ok();
+            // 現在のジョブを設定（並列変換では最も早く始めたジョブを表示する）
+            let shown_job = if config.parallel {
+                let mut batch = batch.borrow_mut();
+                batch.running.push((job.clone(), progress.clone()));
+                batch.running[0].0.clone()
+            } else {
+                job.clone()
+            };
+            cx.update(|cx| {
+                app_state.current_job.update(cx, |current, _| {
+                    *current = Some(shown_job);
+                });
+            })
+            .ok();
+            this.update(cx, |_, cx| cx.notify()).ok();
+
+            // FFmpegを実行し、検証・画質評価まで行う
+            // 失敗した場合は設定した回数まで、待ち時間をおいて再試行する
+            // 再試行は run_job が更新したジョブ（代替エンコーダーに切り替えた場合はその設定）で行う
+            // 実行前からあった出力は、上書きしない設定で変換されずに残ったものなので消さない
+            let output_existed = job.output_path.exists();
+            let mut attempts = 0u8;
+            // 前の試行で代替エンコーダーに切り替えた場合の補足（以降の試行では付かない）
+            let mut fallback_note = None;
+            let outcome = loop {
+                let (mut outcome, ran_job) = {
+                    let options = file_options.clone();
+                    let file = file.clone();
+                    let progress = progress.clone();
+                    smol::unblock(move || {
+                        let outcome = run_job(&options, &file, &mut job, &progress);
+                        (outcome, job)
+                    })
+                    .await
+                };
+                job = ran_job;
+                fallback_note = outcome.note.clone().or(fallback_note);
+                outcome.note = fallback_note.clone();
+                // 中止・スキップと容量不足は再試行しても変わらない
+                let retryable = matches!(outcome.status, FileStatus::Error(_))
+                    && outcome.error_kind != Some(FfmpegErrorKind::DiskFull)
+                    && !app_state.current_progress.is_cancelled()
+                    && !progress.is_skip_requested();
+                if !retryable || attempts >= retry_on_failure {
+                    break outcome;
+                }
+                attempts += 1;
+                warn!(
+                    "Transcode failed for {}, retrying in {:?} ({}/{})",
+                    file.name, retry_delay, attempts, retry_on_failure
+                );
+                progress.push_log(format!(
+                    "=== {} の変換に失敗したため {} 秒後に再試行 ({}/{}) ===",
+                    file.name,
+                    retry_delay.as_secs(),
+                    attempts,
+                    retry_on_failure
+                ));
+                smol::Timer::after(retry_delay).await;
+                if app_state.current_progress.is_cancelled() || progress.is_skip_requested() {
+                    break outcome;
+                }
+                // 今回の試行で書き込んだ途中の出力があると上書きしない設定では失敗するため削除する
+                if !output_existed {
+                    job.remove_partial_output();
+                }
+                progress.reset();
+            };
+            // 後処理の間に他のジョブがセッションを使えるよう先に解放する
+            drop(slot);
+            batch
+                .borrow_mut()
+                .running
+                .retain(|(running, _)| running.input_path != path);
+
+            // 完了したら履歴に記録（再試行した場合も最終結果を1件だけ）
+            let outcome = {
+                let file = file.clone();
+                smol::unblock(move || {
+                    record_completed(&file, &outcome);
+                    outcome
+                })
+                .await
+            };
+
+            if outcome.status.is_completed() {
+                batch.borrow_mut().processed_count += 1;
+                // 最近使ったファイルの先頭に追加
+                cx.update(|cx| {
+                    SettingsPanel::update_app_settings(app_state, cx, |settings| {
+                        settings.add_recent_file(path.clone());
+                    });
+                })
+                .ok();
+            }
+            if config.notify_on_error {
+                if let Some(message) = &outcome.failure {
+                    notify_failure(&file.name, message);
+                }
             }
+            let cancelled = app_state.current_progress.is_cancelled();
+            let disk_full = outcome.error_kind == Some(FfmpegErrorKind::DiskFull);
+            // 元ファイルを処理する出力（成功した場合のみ）
+            let completed_output = outcome
+                .output_path
+                .clone()
+                .filter(|_| outcome.status.is_completed())
+                .filter(|_| *post_file_action != PostFileAction::Nothing);
+            // 予測の補正に使う実際の出力サイズ（ストリームコピーと目標サイズモードは除く）
+            // スマートコピーした場合も実際に使ったエンコーダーで判定する
+            let file_settings = file.effective_settings(settings).clone();
+            let calibration_size = outcome
+                .output_size
+                .filter(|_| outcome.status.is_completed())
+                .filter(|_| {
+                    !outcome
+                        .encode_plan
+                        .as_ref()
+                        .is_some_and(|plan| plan.is_stream_copy())
+                })
+                .filter(|_| file_settings.rate_control != RateControlMode::TargetSize);
+
+            // ファイルの状態を更新
+            cx.update(|cx| {
+                app_state.update_files(cx, |files, _| {
+                    if let Some(f) = files.iter_mut().find(|f| f.path == path) {
+                        if outcome.status != FileStatus::Cancelled {
+                            f.progress = 1.0;
+                        }
+                        f.status = outcome.status;
+                        f.quality = outcome.quality;
+                        f.output_path = outcome.output_path;
+                        f.output_size = outcome.output_size;
+                        if outcome.note.is_some() {
+                            f.note = outcome.note;
+                        }
+                        if let Some(log) = &outcome.log {
+                            f.set_log(log);
+                        }
+                    }
+                });
+            })
+            .ok();
+            this.update(cx, |_, cx| cx.notify()).ok();
+
+            // 補正前の予測との比を記録し、残りのファイルの予測に反映
+            if let Some(actual_size) = calibration_size {
+                let predicted_size = file.predict_size(&file_settings);
+                let content_type = file.metadata.content_type;
+                cx.update(|cx| {
+                    let result = app_state.calibration.update(cx, |calibration, _| {
+                        calibration.record(
+                            &file_settings,
+                            content_type,
+                            predicted_size,
+                            actual_size,
+                        )
+                    });
+                    if let Err(e) = result {
+                        warn!("Failed to record size calibration: {:#}", e);
+                    }
+                    app_state.update_all_estimated_sizes(cx);
+                })
+                .ok();
+            }
+
+            // 元ファイルの削除・移動（出力先が元ファイルと同じ場合はapplyが拒否する）
+            if let Some(output_path) = completed_output {
+                let action = post_file_action.clone();
+                let source = file.path.clone();
+                let result = smol::unblock(move || action.apply(&source, &output_path)).await;
+                let message = match result {
+                    Ok(()) => {
+                        info!("{:?} applied to {:?}", post_file_action, file.path);
+                        format!("{}: {}しました", file.name, post_file_action.display_name())
+                    }
+                    Err(e) => {
+                        warn!("Post-file action failed for {:?}: {:#}", file.path, e);
+                        format!(
+                            "{}: {}できません: {:#}",
+                            file.name,
+                            post_file_action.display_name(),
+                            e
+                        )
+                    }
+                };
+                progress.push_log(message);
+            }
+
+            if cancelled {
+                // すべて中止の場合は残りのファイルも処理しない
+                batch.borrow_mut().stopped = true;
+                break;
+            }
+            if disk_full {
+                // 残りのファイルも書き込めないため中止する（未変換のファイルは待機中のまま）
+                warn!("Output disk is full, stopping the batch");
+                progress
+                    .push_log("出力先の空き容量が不足したため、残りのファイルの変換を中止しました");
+                batch.borrow_mut().stopped = true;
+                break;
+            }
+        }
+    }
+
+    /// 並列変換中、中止・スキップを各ジョブに伝え、最も早く始めたジョブの進捗を表示する
+    /// ファイル一覧の各行には、それぞれのジョブの進捗を反映する
+    async fn relay_parallel_progress(
+        this: WeakEntity<Self>,
+        app_state: AppState,
+        batch: Rc<RefCell<BatchState>>,
+        cx: &mut AsyncApp,
+    ) {
+        use crate::app::FileStatus;
+        use std::sync::atomic::Ordering;
+        use std::time::Duration;
+
+        let current = &app_state.current_progress;
+        loop {
+            smol::Timer::after(Duration::from_millis(100)).await;
+
+            let running = {
+                let mut batch = batch.borrow_mut();
+                if batch.finished {
+                    break;
+                }
+                if current.is_cancelled() {
+                    batch.stopped = true;
+                }
+                batch.running.clone()
+            };
+            if current.is_cancelled() {
+                for (_, progress) in &running {
+                    progress.cancel();
+                }
+            }
+            // スキップは表示中のジョブだけに伝える
+            let skip = current.skip_current.swap(false, Ordering::SeqCst);
+            let Some((shown_job, shown)) = running.first() else {
+                continue;
+            };
+            if skip {
+                shown.skip_current();
+            }
+            current.mirror(shown);
+
+            cx.update(|cx| {
+                // 進捗だけの変更でキューを保存しないよう、update_filesを通さずに更新する
+                app_state.files.update(cx, |files, _| {
+                    for (job, progress) in &running {
+                        if let Some(f) = files.iter_mut().find(|f| {
+                            f.path == job.input_path && f.status == FileStatus::Processing
+                        }) {
+                            f.progress = progress.get_progress();
+                        }
+                    }
+                });
+                let is_shown = app_state
+                    .current_job
+                    .read(cx)
+                    .as_ref()
+                    .is_some_and(|job| job.input_path == shown_job.input_path);
+                if !is_shown {
+                    app_state.current_job.update(cx, |current, _| {
+                        *current = Some(shown_job.clone());
+                    });
+                }
+            })
+            .ok();
+            this.update(cx, |_, cx| cx.notify()).ok();
+        }
+    }
+
+    /// ファイル中央の30秒を現在の設定でエンコードし、全体のサイズを見積もる
+    /// キューの状態や履歴には反映せず、結果はダイアログで表示する
+    fn run_sample_encode(&mut self, path: std::path::PathBuf, cx: &mut Context<Self>) {
+        use crate::app::FileStatus;
+        use crate::queue::{extrapolate_sample_size, prepare_sample_job, run_job, QueueOptions};
+        use log::info;
+
+        let Some(ffmpeg_path) = self.app_state.ffmpeg_path.read(cx).clone() else {
+            self.show_download_dialog("FFmpegが見つかりません", cx);
+            return;
+        };
+        // キューの変換中やサンプルの作成中
+        if self.app_state.current_job.read(cx).is_some() {
+            return;
+        }
+        let Some(file) = self
+            .app_state
+            .files
+            .read(cx)
+            .iter()
+            .find(|f| f.path == path)
+            .cloned()
+        else {
+            return;
+        };
+
+        // 設定を固定したファイルはその設定で試す
+        let settings = file
+            .effective_settings(self.app_state.transcode_settings.read(cx))
+            .clone();
+        let options = QueueOptions::new(ffmpeg_path, &settings).with_app_settings(
+            self.app_state.settings.read(cx),
+            self.app_state.ffmpeg_info.read(cx).as_ref(),
+        );
+        let files = std::slice::from_ref(&file);
+        if let Some(message) = options.preflight(files) {
+            self.show_alert(message, cx);
+            return;
+        }
+        let validation = options.validate_files(files);
+        if let Some(message) = validation.rejection(0) {
+            self.show_alert(message.to_string(), cx);
+            return;
+        }
+
+        let app_state = self.app_state.clone();
+        app_state.current_progress.reset();
+        app_state.current_progress.clear_log();
+        for warning in &validation.warnings {
+            app_state
+                .current_progress
+                .push_log(format!("警告: {}", warning));
+        }
+        info!("Encoding a sample of {}", file.name);
+
+        cx.spawn(async move |this, cx| {
+            let encode_plan = {
+                let options = options.clone();
+                smol::unblock(move || options.resolve_encode_plan()).await
+            };
+            let mut job = match prepare_sample_job(&options, &file, &encode_plan) {
+                Ok(job) => job,
+                Err(e) => {
+                    this.update(cx, |this, cx| {
+                        this.show_alert(format!("サンプルを作成できません: {:#}", e), cx);
+                    })
+                    .ok();
+                    return;
+                }
+            };
+            let sample_secs = job.segment.map(|(_, secs)| secs).unwrap_or_default();
+
+            // 通常の変換と同じ進捗表示を使う
+            cx.update(|cx| {
+                app_state.current_job.update(cx, |current, _| {
+                    *current = Some(job.clone());
+                });
+            })
+            .ok();
+            this.update(cx, |this, cx| {
+                this.start_progress_timer(cx);
+                cx.notify();
+            })
+            .ok();
+
+            let outcome = {
+                let options = options.clone();
+                let file = file.clone();
+                let progress = app_state.current_progress.clone();
+                smol::unblock(move || run_job(&options, &file, &mut job, &progress)).await
+            };
 
-            // 完了後、現在のジョブをクリア
             cx.update(|cx| {
                 app_state.current_job.update(cx, |current, _| {
                     *current = None;
                 });
             })
             .ok();
-            this.update(cx, |_, cx| cx.notify()).ok();
 
-            info!("All transcoding completed");
+            this.update(cx, |this, cx| {
+                match (outcome.status, outcome.output_path, outcome.output_size) {
+                    (FileStatus::Completed { .. }, Some(output_path), Some(sample_size)) => {
+                        let duration = file.metadata.duration.unwrap_or(sample_secs);
+                        let result = SampleResult {
+                            name: file.name.clone(),
+                            output_path,
+                            sample_secs,
+                            sample_size,
+                            extrapolated_size: extrapolate_sample_size(
+                                sample_size,
+                                sample_secs,
+                                duration,
+                            ),
+                            estimated_size: file.estimated_size,
+                        };
+                        info!(
+                            "Sample of {}: {} bytes, extrapolated to {} bytes (estimated {:?})",
+                            result.name,
+                            result.sample_size,
+                            result.extrapolated_size,
+                            result.estimated_size
+                        );
+                        this.sample_result = Some(result);
+                    }
+                    (FileStatus::Cancelled, _, _) => {}
+                    _ => this.show_alert(
+                        format!(
+                            "サンプルのエンコードに失敗しました: {}",
+                            outcome.failure.unwrap_or_default()
+                        ),
+                        cx,
+                    ),
+                }
+                cx.notify();
+            })
+            .ok();
         })
         .detach();
+    }
+
+    /// サンプルを既定のプレーヤーで再生
+    fn play_sample(&mut self, cx: &mut Context<Self>) {
+        let Some(result) = &self.sample_result else {
+            return;
+        };
+        if let Err(e) = crate::config::open_path(&result.output_path) {
+            log::warn!("Failed to open sample {:?}: {:#}", result.output_path, e);
+            self.show_alert(format!("サンプルを開けません: {:#}", e), cx);
+        }
+    }
+
+    /// サンプルの結果ダイアログを閉じる
+    fn hide_sample_result(&mut self, cx: &mut Context<Self>) {
+        self.sample_result = None;
+        cx.notify();
+    }
+
+    /// 進捗更新タイマーを開始
+    fn start_progress_timer(&mut self, cx: &mut Context<Self>) {
+        use std::time::Duration;
+
+        let app_state = self.app_state.clone();
+        let mut fallback_count = app_state.current_progress.fallback_count();
+
+        cx.spawn(async move |this, cx| {
+            loop {
+                // 100msごとに更新
+                smol::Timer::after(Duration::from_millis(100)).await;
+
+                // ジョブが実行中かチェック
+                let has_job = cx
+                    .update(|cx| app_state.current_job.read(cx).is_some())
+                    .unwrap_or(false);
+
+                if !has_job {
+                    break;
+                }
+
+                // HWエンコーダーが失敗してソフトウェアエンコードに切り替えたら通知
+                let count = app_state.current_progress.fallback_count();
+                if count != fallback_count {
+                    fallback_count = count;
+                    this.update(cx, |this, cx| {
+                        this.show_toast(
+                            "HWアクセラレーションが失敗しました。ソフトウェアエンコードで再試行しています",
+                            cx,
+                        );
+                    })
+                    .ok();
+                }
+
+                // UIを更新
+                this.update(cx, |_, cx| cx.notify()).ok();
+            }
+        })
+        .detach();
+    }
+
+    /// キューが変わっていれば保存する（次回起動時に復元できるようにする）
+    /// 変換中は状態が頻繁に変わるため、一定間隔でまとめて書き込む
+    fn start_queue_autosave(app_state: AppState, cx: &mut Context<Self>) {
+        use crate::config::SavedQueue;
+        use std::time::Duration;
+
+        let store = match SavedQueue::open() {
+            Ok(store) => store,
+            Err(e) => {
+                log::warn!("Failed to open saved queue: {}", e);
+                return;
+            }
+        };
+
+        cx.spawn(async move |_, cx| {
+            // 起動直後の空のキューで前回のキューを消さないよう、最初に保存するまでは空なら保存しない
+            let mut saved = false;
+
+            loop {
+                smol::Timer::after(Duration::from_secs(1)).await;
+
+                // キューが変更されたときだけ保存する
+                if !app_state.take_queue_dirty() {
+                    continue;
+                }
+                let Ok(files) = cx.update(|cx| app_state.files.read(cx).clone()) else {
+                    break;
+                };
+                if files.is_empty() && !saved {
+                    continue;
+                }
+
+                let store = store.clone();
+                if let Err(e) = smol::unblock(move || store.save(&files)).await {
+                    log::warn!("Failed to save queue: {:#}", e);
+                }
+                saved = true;
+            }
+        })
+        .detach();
+    }
+
+    /// 監視フォルダのポーリングを開始
+    /// 新しく現れた動画ファイルを、サイズが安定した時点でキューに追加する
+    fn start_folder_watcher(app_state: AppState, cx: &mut Context<Self>) {
+        use crate::config::WatchHistory;
+        use log::{info, warn};
+        use std::collections::HashMap;
+        use std::path::PathBuf;
+        use std::time::Duration;
+
+        cx.spawn(async move |this, cx| {
+            // 現在監視中のフォルダと既知のファイル
+            let mut watched: Option<PathBuf> = None;
+            let mut known: HashSet<PathBuf> = HashSet::new();
+            // 書き込み中の可能性があるファイル（パス → 前回のサイズ）
+            let mut growing: HashMap<PathBuf, u64> = HashMap::new();
+            // 監視フォルダから追加されたファイルの変換待ち
+            let mut start_requested = false;
+            // 追加済みのファイル（再起動後も同じファイルを追加しないよう保存する）
+            let mut history = WatchHistory::open().unwrap_or_else(|e| {
+                warn!("Failed to open watch history: {}", e);
+                WatchHistory::default()
+            });
+
+            loop {
+                smol::Timer::after(Duration::from_secs(2)).await;
+
+                let Ok((folder, auto_start, preset, is_running, output_dir, output_suffix)) = cx
+                    .update(|cx| {
+                        let settings = app_state.settings.read(cx);
+                        let transcode_settings = app_state.transcode_settings.read(cx);
+                        (
+                            settings.watch_folder.clone(),
+                            settings.watch_folder_auto_start,
+                            settings.watch_folder_preset.clone(),
+                            app_state.current_job.read(cx).is_some(),
+                            transcode_settings.output_dir.clone(),
+                            transcode_settings.output_suffix.clone(),
+                        )
+                    })
+                else {
+                    break;
+                };
+
+                let Some(folder) = folder else {
+                    watched = None;
+                    known.clear();
+                    growing.clear();
+                    start_requested = false;
+                    continue;
+                };
+
+                // 変換した出力を監視フォルダに書き出す場合、それを再び追加しない
+                let entries = {
+                    let folder = folder.clone();
+                    smol::unblock(move || {
+                        list_video_files(&folder, output_dir.as_deref(), &output_suffix)
+                    })
+                    .await
+                };
+
+                if watched.as_ref() != Some(&folder) {
+                    info!("Watching folder: {:?}", folder);
+                    let paths: Vec<PathBuf> = entries.into_iter().map(|(path, _)| path).collect();
+                    let result = if history.has_folder(&folder) {
+                        // 前回の終了後に置かれたファイルは追加対象にする
+                        history.retain_existing(&folder, &paths)
+                    } else {
+                        // 初めて監視するフォルダでは既存のファイルを対象外にする
+                        history.record(&folder, paths.clone())
+                    };
+                    if let Err(e) = result {
+                        warn!("Failed to save watch history: {}", e);
+                    }
+                    known = paths
+                        .into_iter()
+                        .filter(|path| history.contains(&folder, path))
+                        .collect();
+                    growing.clear();
+                    watched = Some(folder);
+                    continue;
+                }
+
+                let mut ready = Vec::new();
+                for (path, size) in entries {
+                    if known.contains(&path) {
+                        continue;
+                    }
+                    // 前回とサイズが同じなら書き込み完了とみなす
+                    if growing.insert(path.clone(), size) == Some(size) {
+                        growing.remove(&path);
+                        known.insert(path.clone());
+                        ready.push(path);
+                    }
+                }
+
+                if !ready.is_empty() {
+                    if let Err(e) = history.record(&folder, ready.clone()) {
+                        warn!("Failed to save watch history: {}", e);
+                    }
+                    let added = cx
+                        .update(|cx| {
+                            // キューのファイルの出力先（サフィックスが空の場合など）も対象外にする
+                            let outputs: HashSet<PathBuf> = app_state
+                                .files
+                                .read(cx)
+                                .iter()
+                                .filter_map(|f| f.output_path.clone())
+                                .collect();
+                            let new_files: Vec<_> = ready
+                                .into_iter()
+                                .filter(|path| !outputs.contains(path))
+                                .filter(|path| !app_state.is_queued(path, cx))
+                                .collect();
+                            let count = new_files.len();
+                            app_state.add_files(new_files.clone(), cx);
+                            // 監視フォルダ用のプリセットは追加したファイルだけに固定する
+                            if let Some(name) = &preset {
+                                apply_watch_preset(&app_state, name, &new_files, cx);
+                            }
+                            count
+                        })
+                        .unwrap_or(0);
+                    if added > 0 {
+                        info!("Added {} file(s) from watch folder", added);
+                        start_requested = true;
+                        this.update(cx, |_, cx| cx.notify()).ok();
+                    }
+                }
+
+                if auto_start && start_requested && !is_running {
+                    start_requested = false;
+                    if this
+                        .update(cx, |this, cx| this.start_transcode(cx))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// キューをクリア
+    fn clear_queue(&mut self, cx: &mut Context<Self>) {
+        self.app_state.clear_files(cx);
+        cx.notify();
+    }
+
+    /// リムックス（映像・音声ともにストリームコピー）の設定を適用
+    fn apply_remux_preset(&mut self, cx: &mut Context<Self>) {
+        use crate::transcoder::{AudioCodec, VideoCodec};
+
+        let settings = self.app_state.transcode_settings.update(cx, |settings, _| {
+            settings.video_codec = VideoCodec::Copy;
+            settings.audio_codec = AudioCodec::Copy;
+            settings.clone()
+        });
+        // 予測サイズを更新
+        let calibration = self.app_state.calibration.read(cx).clone();
+        self.app_state.update_files(cx, |files, _| {
+            for file in files.iter_mut() {
+                file.update_estimated_size(&settings, &calibration);
+            }
+        });
+        self.settings_panel.update(cx, |_, cx| cx.notify());
+        cx.notify();
+    }
+
+    /// Aboutダイアログを表示
+    fn show_about(&mut self, cx: &mut Context<Self>) {
+        self.show_about = true;
+        cx.notify();
+    }
+
+    /// Aboutダイアログを閉じる
+    fn hide_about(&mut self, cx: &mut Context<Self>) {
+        self.show_about = false;
+        cx.notify();
+    }
+
+    /// 変換履歴ダイアログを表示（開くたびに履歴ファイルを読み直す）
+    fn show_history(&mut self, cx: &mut Context<Self>) {
+        self.history_view.update(cx, |view, cx| view.reload(cx));
+        self.show_history = true;
+        cx.notify();
+    }
+
+    /// 変換履歴ダイアログを閉じる
+    fn hide_history(&mut self, cx: &mut Context<Self>) {
+        self.show_history = false;
+        cx.notify();
+    }
+
+    /// 最近使ったファイルのメニューを開く（サイズと最後に変換した日時は開くたびに読み直す）
+    fn open_recent_menu(&mut self, cx: &mut Context<Self>) {
+        let recent_files = self.app_state.settings.read(cx).recent_files.clone();
+        let history = History::open()
+            .inspect_err(|e| log::warn!("Failed to load history: {:#}", e))
+            .ok();
+        let items = recent_files
+            .into_iter()
+            .map(|path| {
+                let input_path = path.to_string_lossy();
+                let transcoded_at = history.as_ref().and_then(|history| {
+                    history
+                        .records()
+                        .iter()
+                        .rev()
+                        .find(|record| record.input_path == input_path)
+                        .map(|record| record.finished_at)
+                });
+                RecentFile {
+                    name: path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_else(|| path.to_string_lossy().to_string()),
+                    size: std::fs::metadata(&path)
+                        .ok()
+                        .filter(|m| m.is_file())
+                        .map(|m| m.len()),
+                    transcoded_at,
+                    path,
+                }
+            })
+            .collect();
+        self.recent_menu = Some(items);
+        cx.notify();
+    }
+
+    /// 最近使ったファイルのメニューを閉じる
+    fn close_recent_menu(&mut self, cx: &mut Context<Self>) {
+        self.recent_menu = None;
+        cx.notify();
+    }
+
+    /// 最近使ったファイルをキューに追加し直す（キューにあるファイルは追加せず通知する）
+    fn add_recent_file(&mut self, path: std::path::PathBuf, cx: &mut Context<Self>) {
+        self.recent_menu = None;
+        if !path.is_file() {
+            self.show_alert(format!("ファイルが見つかりません: {}", path.display()), cx);
+            return;
+        }
+        if self.app_state.add_files(vec![path], cx) > 0 {
+            self.show_toast(already_queued_message(1), cx);
+        }
+        cx.notify();
+    }
+
+    /// 最近使ったファイルを消去
+    fn clear_recent_files(&mut self, cx: &mut Context<Self>) {
+        SettingsPanel::update_app_settings(&self.app_state, cx, |settings| {
+            settings.recent_files.clear();
+        });
+        self.recent_menu = None;
+        cx.notify();
+    }
+
+    /// ログパネルの表示・非表示を切り替え
+    fn toggle_log(&mut self, cx: &mut Context<Self>) {
+        self.show_log = !self.show_log;
+        cx.notify();
+    }
+
+    /// 警告ダイアログを表示
+    fn show_alert(&mut self, message: impl Into<SharedString>, cx: &mut Context<Self>) {
+        self.alert_message = Some(message.into());
+        cx.notify();
+    }
+
+    /// 警告ダイアログを閉じる
+    fn hide_alert(&mut self, cx: &mut Context<Self>) {
+        self.alert_message = None;
+        cx.notify();
+    }
+
+    /// 操作を妨げない通知を表示（一定時間で消える）
+    fn show_toast(&mut self, message: impl Into<SharedString>, cx: &mut Context<Self>) {
+        use std::time::Duration;
+
+        let id = self.toast.as_ref().map_or(0, |(id, _)| id.wrapping_add(1));
+        self.toast = Some((id, message.into()));
+        cx.notify();
+
+        cx.spawn(async move |this, cx| {
+            smol::Timer::after(Duration::from_secs(TOAST_DURATION_SECS)).await;
+            // 後から表示した通知は消さない
+            this.update(cx, |this, cx| {
+                if this
+                    .toast
+                    .as_ref()
+                    .is_some_and(|(current, _)| *current == id)
+                {
+                    this.hide_toast(cx);
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// 通知を閉じる
+    fn hide_toast(&mut self, cx: &mut Context<Self>) {
+        self.toast = None;
+        cx.notify();
+    }
+
+    /// ツールバーから完了後の動作を切り替え（何もしない → スリープ → シャットダウン → アプリを終了）
+    fn cycle_post_action(&mut self, cx: &mut Context<Self>) {
+        let next = match self.app_state.settings.read(cx).post_transcode_action {
+            PostTranscodeAction::Sleep => PostTranscodeAction::Shutdown,
+            PostTranscodeAction::Shutdown => PostTranscodeAction::CloseApp,
+            PostTranscodeAction::CloseApp => PostTranscodeAction::Nothing,
+            _ => PostTranscodeAction::Sleep,
+        };
+        SettingsPanel::update_app_settings(&self.app_state, cx, |settings| {
+            settings.post_transcode_action = next;
+        });
+        cx.notify();
+    }
+
+    /// 完了後の動作のカウントダウンを開始
+    fn start_post_action_countdown(&mut self, action: PostTranscodeAction, cx: &mut Context<Self>) {
+        use std::time::Duration;
+
+        log::info!(
+            "Running post-transcode action {:?} in {}s",
+            action,
+            POST_ACTION_DELAY_SECS
+        );
+        self.pending_post_action = Some((action, POST_ACTION_DELAY_SECS));
+        cx.notify();
+
+        cx.spawn(async move |this, cx| loop {
+            smol::Timer::after(Duration::from_secs(1)).await;
+
+            let tick = this.update(cx, |this, cx| {
+                let (action, remaining) = this.pending_post_action.as_mut()?;
+                *remaining = remaining.saturating_sub(1);
+                cx.notify();
+                Some((*remaining == 0).then(|| action.clone()))
+            });
+            match tick {
+                // カウントダウン中
+                Ok(Some(None)) => {}
+                Ok(Some(Some(action))) => {
+                    this.update(cx, |this, cx| this.run_post_action(action, cx))
+                        .ok();
+                    break;
+                }
+                // 取り消し済み（またはウィンドウが閉じられた）
+                Ok(None) | Err(_) => break,
+            }
+        })
+        .detach();
+    }
+
+    /// 完了後の動作を取り消し
+    fn cancel_post_action(&mut self, cx: &mut Context<Self>) {
+        if let Some((action, _)) = self.pending_post_action.take() {
+            log::info!("Post-transcode action {:?} cancelled", action);
+        }
+        cx.notify();
+    }
+
+    /// 完了後の動作を実行
+    fn run_post_action(&mut self, action: PostTranscodeAction, cx: &mut Context<Self>) {
+        self.pending_post_action = None;
+        cx.notify();
+
+        log::info!("Running post-transcode action: {:?}", action);
+        if action == PostTranscodeAction::CloseApp {
+            cx.quit();
+            return;
+        }
+        if let Err(e) = action.execute(None) {
+            log::error!("Post-transcode action failed: {}", e);
+            self.show_alert(format!("完了後の動作に失敗しました: {}", e), cx);
+        }
+    }
+
+    /// FFmpegダウンロードダイアログを表示
+    fn show_download_dialog(&mut self, reason: &str, cx: &mut Context<Self>) {
+        let reason = reason.to_string();
+        self.download_dialog
+            .update(cx, |dialog, cx| dialog.reset(reason, cx));
+        self.show_download = true;
+        cx.notify();
+    }
+
+    /// FFmpegダウンロードダイアログを閉じる（ダウンロード中は閉じない）
+    fn hide_download_dialog(&mut self, cx: &mut Context<Self>) {
+        if self.download_dialog.read(cx).is_downloading() {
+            return;
+        }
+        self.show_download = false;
+        cx.notify();
+    }
+}
+
+impl MainWindow {
+    /// モーダルの背景オーバーレイをレンダリング（背景クリックで閉じる）
+    fn render_modal(
+        content: impl IntoElement,
+        on_dismiss: fn(&mut Self, &mut Context<Self>),
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        div()
+            .absolute()
+            .inset_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(rgba(0x00000080))
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |this, _, _, cx| {
+                    on_dismiss(this, cx);
+                }),
+            )
+            .child(
+                div()
+                    .on_mouse_down(MouseButton::Left, |_, _, cx| {
+                        // ダイアログ内のクリックは伝播させない
+                        cx.stop_propagation();
+                    })
+                    .child(content),
+            )
+    }
+
+    /// フォルダ追加の確認ダイアログをレンダリング
+    fn render_folder_confirm(
+        files: &[std::path::PathBuf],
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        const PREVIEW_COUNT: usize = 8;
+
+        div()
+            .w(px(480.0))
+            .rounded(px(8.0))
+            .bg(rgb(0x1e1e2e))
+            .border_1()
+            .border_color(rgb(0x313244))
+            .p(px(16.0))
+            .flex()
+            .flex_col()
+            .gap(px(12.0))
+            .child(
+                div()
+                    .text_lg()
+                    .font_weight(FontWeight::BOLD)
+                    .child("フォルダ追加"),
+            )
+            .child(div().text_sm().child(format!(
+                "{}件の動画ファイルが見つかりました。キューに追加しますか？",
+                files.len()
+            )))
+            .child(
+                div()
+                    .p(px(8.0))
+                    .rounded(px(4.0))
+                    .bg(rgb(0x181825))
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .children(files.iter().take(PREVIEW_COUNT).map(|path| {
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0xa6adc8))
+                            .truncate()
+                            .child(path.to_string_lossy().to_string())
+                    }))
+                    .when(files.len() > PREVIEW_COUNT, |this| {
+                        this.child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0x6c7086))
+                                .child(format!("...他 {}件", files.len() - PREVIEW_COUNT)),
+                        )
+                    }),
+            )
+            .child(
+                div()
+                    .flex()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .child(
+                        Button::new("folder-cancel")
+                            .label("キャンセル")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.cancel_folder_files(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("folder-confirm")
+                            .label("追加")
+                            .with_variant(ButtonVariant::Primary)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.confirm_folder_files(cx);
+                            })),
+                    ),
+            )
+    }
+
+    /// 元ファイル削除の確認ダイアログをレンダリング
+    fn render_source_deletion_confirm(
+        files: &[std::path::PathBuf],
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        const PREVIEW_COUNT: usize = 8;
 
-        // 進捗表示を定期的に更新するタイマーを開始
-        self.start_progress_timer(cx);
+        div()
+            .w(px(480.0))
+            .rounded(px(8.0))
+            .bg(rgb(0x1e1e2e))
+            .border_1()
+            .border_color(rgb(0x313244))
+            .p(px(16.0))
+            .flex()
+            .flex_col()
+            .gap(px(12.0))
+            .child(
+                div()
+                    .text_lg()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(rgb(0xf9e2af))
+                    .child("元ファイルの削除"),
+            )
+            .child(div().text_sm().child(format!(
+                "変換に成功した次の元ファイル（{}件）を削除します。削除したファイルは元に戻せません。",
+                files.len()
+            )))
+            .child(
+                div()
+                    .p(px(8.0))
+                    .rounded(px(4.0))
+                    .bg(rgb(0x181825))
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .children(files.iter().take(PREVIEW_COUNT).map(|path| {
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0xa6adc8))
+                            .truncate()
+                            .child(path.to_string_lossy().to_string())
+                    }))
+                    .when(files.len() > PREVIEW_COUNT, |this| {
+                        this.child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0x6c7086))
+                                .child(format!("...他 {}件", files.len() - PREVIEW_COUNT)),
+                        )
+                    }),
+            )
+            .child(
+                div()
+                    .flex()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .child(
+                        Button::new("source-deletion-cancel")
+                            .label("キャンセル")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.cancel_source_deletion(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("source-deletion-confirm")
+                            .label("削除して開始")
+                            .with_variant(ButtonVariant::Primary)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.confirm_source_deletion(cx);
+                            })),
+                    ),
+            )
     }
 
-    /// 進捗更新タイマーを開始
-    fn start_progress_timer(&mut self, cx: &mut Context<Self>) {
-        use std::time::Duration;
+    /// 空き容量不足の確認ダイアログをレンダリング
+    fn render_space_confirm(
+        shortage: &DiskSpaceShortage,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        div()
+            .w(px(420.0))
+            .rounded(px(8.0))
+            .bg(rgb(0x1e1e2e))
+            .border_1()
+            .border_color(rgb(0x313244))
+            .p(px(16.0))
+            .flex()
+            .flex_col()
+            .gap(px(12.0))
+            .child(
+                div()
+                    .text_lg()
+                    .font_weight(FontWeight::BOLD)
+                    .child("空き容量の確認"),
+            )
+            .child(div().text_sm().child(shortage.message()))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0xa6adc8))
+                    .truncate()
+                    .child(format!("出力先: {}", shortage.dir.to_string_lossy())),
+            )
+            .child(
+                div().text_xs().text_color(rgb(0x6c7086)).child(
+                    "変換の合間にも空き容量を確認し、不足した時点で残りの変換を中止します。",
+                ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .child(
+                        Button::new("space-cancel")
+                            .label("キャンセル")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.cancel_space_shortage(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("space-confirm")
+                            .label("このまま続行")
+                            .with_variant(ButtonVariant::Primary)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.confirm_space_shortage(cx);
+                            })),
+                    ),
+            )
+    }
 
-        let app_state = self.app_state.clone();
+    /// 完了後の動作のカウントダウンダイアログをレンダリング
+    fn render_post_action_countdown(
+        action: &PostTranscodeAction,
+        remaining: u32,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        div()
+            .w(px(420.0))
+            .rounded(px(8.0))
+            .bg(rgb(0x1e1e2e))
+            .border_1()
+            .border_color(rgb(0x313244))
+            .p(px(16.0))
+            .flex()
+            .flex_col()
+            .gap(px(12.0))
+            .child(
+                div()
+                    .text_lg()
+                    .font_weight(FontWeight::BOLD)
+                    .child("すべての変換が完了しました"),
+            )
+            .child(div().text_sm().child(format!(
+                "{}秒後に「{}」を実行します",
+                remaining,
+                action.display_name()
+            )))
+            .child(
+                div()
+                    .flex()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .child(
+                        Button::new("post-action-cancel")
+                            .label("キャンセル")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.cancel_post_action(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("post-action-now")
+                            .label("今すぐ実行")
+                            .with_variant(ButtonVariant::Primary)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                if let Some((action, _)) = this.pending_post_action.clone() {
+                                    this.run_post_action(action, cx);
+                                }
+                            })),
+                    ),
+            )
+    }
 
-        cx.spawn(async move |this, cx| {
-            loop {
-                // 100msごとに更新
-                smol::Timer::after(Duration::from_millis(100)).await;
+    /// 最近使ったファイルのメニューをレンダリング
+    fn render_recent_menu(items: &[RecentFile], cx: &mut Context<Self>) -> impl IntoElement {
+        deferred(
+            anchored().snap_to_window().child(
+                div()
+                    .mt(px(4.0))
+                    .p(px(4.0))
+                    .w(px(340.0))
+                    .flex()
+                    .flex_col()
+                    .gap(px(2.0))
+                    .rounded(px(6.0))
+                    .bg(rgb(0x181825))
+                    .border_1()
+                    .border_color(rgb(0x45475a))
+                    // 下のファイルリストをクリックしたことにしない
+                    .occlude()
+                    .on_mouse_down_out(cx.listener(|this, _, _, cx| {
+                        this.close_recent_menu(cx);
+                    }))
+                    .when(items.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .px(px(8.0))
+                                .py(px(6.0))
+                                .text_xs()
+                                .text_color(rgb(0x6c7086))
+                                .child("最近使ったファイルはありません"),
+                        )
+                    })
+                    .child(
+                        div()
+                            .id("recent-files")
+                            .max_h(px(400.0))
+                            .overflow_y_scroll()
+                            .flex()
+                            .flex_col()
+                            .gap(px(2.0))
+                            .children(items.iter().map(|item| {
+                                let path = item.path.clone();
+                                let detail = format!(
+                                    "{}・{}",
+                                    item.size
+                                        .map(format_size)
+                                        .unwrap_or_else(|| "見つかりません".to_string()),
+                                    item.transcoded_at
+                                        .map(format_timestamp)
+                                        .unwrap_or_else(|| "—".to_string())
+                                );
 
-                // ジョブが実行中かチェック
-                let has_job = cx
-                    .update(|cx| app_state.current_job.read(cx).is_some())
-                    .unwrap_or(false);
+                                div()
+                                    .px(px(8.0))
+                                    .py(px(4.0))
+                                    .rounded(px(4.0))
+                                    .flex()
+                                    .flex_col()
+                                    .cursor_pointer()
+                                    .hover(|s| s.bg(rgb(0x45475a)))
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(move |this, _, _, cx| {
+                                            cx.stop_propagation();
+                                            this.add_recent_file(path.clone(), cx);
+                                        }),
+                                    )
+                                    .child(
+                                        div()
+                                            .text_sm()
+                                            .truncate()
+                                            .text_color(if item.size.is_some() {
+                                                rgb(0xcdd6f4)
+                                            } else {
+                                                rgb(0x6c7086)
+                                            })
+                                            .child(item.name.clone()),
+                                    )
+                                    .child(div().text_xs().text_color(rgb(0xa6adc8)).child(detail))
+                            })),
+                    )
+                    .child(
+                        div().pt(px(4.0)).flex().justify_end().child(
+                            Button::new("clear-recent-files")
+                                .label("履歴をクリア")
+                                .with_variant(ButtonVariant::Ghost)
+                                .disabled(items.is_empty())
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.clear_recent_files(cx);
+                                })),
+                        ),
+                    ),
+            ),
+        )
+    }
 
-                if !has_job {
-                    break;
-                }
+    /// サンプルのエンコード結果ダイアログをレンダリング
+    fn render_sample_result(result: &SampleResult, cx: &mut Context<Self>) -> impl IntoElement {
+        let row = |label: &'static str, value: String| {
+            div()
+                .flex()
+                .justify_between()
+                .text_sm()
+                .child(div().text_color(rgb(0xa6adc8)).child(label))
+                .child(value)
+        };
+        let estimated = result
+            .estimated_size
+            .map(|size| match result.difference_percent() {
+                Some(percent) => format!("{}（見積もりは {:+.0}%）", format_size(size), percent),
+                None => format_size(size),
+            });
 
-                // UIを更新
-                this.update(cx, |_, cx| cx.notify()).ok();
-            }
-        })
-        .detach();
+        div()
+            .w(px(420.0))
+            .rounded(px(8.0))
+            .bg(rgb(0x1e1e2e))
+            .border_1()
+            .border_color(rgb(0x313244))
+            .p(px(16.0))
+            .flex()
+            .flex_col()
+            .gap(px(12.0))
+            .child(
+                div()
+                    .text_lg()
+                    .font_weight(FontWeight::BOLD)
+                    .child("サンプルのエンコード結果"),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0xa6adc8))
+                    .truncate()
+                    .child(result.name.clone()),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .child(row(
+                        "サンプル",
+                        format!(
+                            "{}（{:.0}秒）",
+                            format_size(result.sample_size),
+                            result.sample_secs
+                        ),
+                    ))
+                    .child(row("全体の見積もり", format_size(result.extrapolated_size)))
+                    .when_some(estimated, |this, estimated| {
+                        this.child(row("予測サイズ", estimated))
+                    }),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child("中央の区間から見積もるため、場面によって実際のサイズは前後します。"),
+            )
+            .child(
+                div()
+                    .flex()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .child(
+                        Button::new("sample-close")
+                            .label("閉じる")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.hide_sample_result(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("sample-play")
+                            .label("サンプルを再生")
+                            .with_variant(ButtonVariant::Primary)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.play_sample(cx);
+                            })),
+                    ),
+            )
     }
 
-    /// キューをクリア
-    fn clear_queue(&mut self, cx: &mut Context<Self>) {
-        self.app_state.files.update(cx, |files, _| files.clear());
-        cx.notify();
+    /// 通知をレンダリング（クリックで閉じる）
+    fn render_toast(message: SharedString, cx: &mut Context<Self>) -> impl IntoElement {
+        div().absolute().right(px(16.0)).bottom(px(56.0)).child(
+            div()
+                .id("toast")
+                .max_w(px(420.0))
+                .px(px(12.0))
+                .py(px(8.0))
+                .rounded(px(6.0))
+                .bg(rgb(0x181825))
+                .border_1()
+                .border_color(rgb(0xf9e2af))
+                .text_sm()
+                .text_color(rgb(0xcdd6f4))
+                .cursor_pointer()
+                .on_click(cx.listener(|this, _, _, cx| {
+                    this.hide_toast(cx);
+                }))
+                .child(message),
+        )
     }
 
-    /// Aboutダイアログを表示
-    fn show_about(&mut self, cx: &mut Context<Self>) {
-        self.show_about = true;
-        cx.notify();
+    /// 警告ダイアログの内容をレンダリング
+    fn render_alert(message: SharedString, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .w(px(420.0))
+            .rounded(px(8.0))
+            .bg(rgb(0x1e1e2e))
+            .border_1()
+            .border_color(rgb(0x313244))
+            .p(px(16.0))
+            .flex()
+            .flex_col()
+            .gap(px(16.0))
+            .child(
+                div()
+                    .text_lg()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(rgb(0xfab387))
+                    .child("警告"),
+            )
+            .child(div().text_sm().child(message))
+            .child(
+                div().flex().justify_end().child(
+                    Button::new("alert-ok")
+                        .label("OK")
+                        .with_variant(ButtonVariant::Primary)
+                        .on_click(cx.listener(|this, _, _, cx| {
+                            this.hide_alert(cx);
+                        })),
+                ),
+            )
     }
+}
 
-    /// Aboutダイアログを閉じる
-    fn hide_about(&mut self, cx: &mut Context<Self>) {
-        self.show_about = false;
-        cx.notify();
+impl Focusable for MainWindow {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
     }
 }
 
 impl Render for MainWindow {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let has_files = self.app_state.files.read(cx).len() > 0;
+        let post_action = self
+            .app_state
+            .settings
+            .read(cx)
+            .post_transcode_action
+            .clone();
+        let is_remux = self.app_state.transcode_settings.read(cx).is_remux();
+        let watch_folder = self
+            .app_state
+            .settings
+            .read(cx)
+            .watch_folder
+            .as_ref()
+            .map(|p| {
+                p.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| p.to_string_lossy().to_string())
+            });
+        let recent_menu = self
+            .recent_menu
+            .as_deref()
+            .map(|items| Self::render_recent_menu(items, cx));
 
         div()
             .size_full()
@@ -433,6 +2657,10 @@ impl Render for MainWindow {
             .flex_col()
             .bg(rgb(0x1e1e2e))
             .text_color(rgb(0xcdd6f4))
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                this.handle_key_down(event, window, cx);
+            }))
             // ツールバー
             .child(
                 div()
@@ -458,6 +2686,33 @@ impl Render for MainWindow {
                                         this.open_file_dialog(cx);
                                     })),
                             )
+                            .child(
+                                Button::new("add-folder")
+                                    .label("フォルダ追加")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.open_folder_dialog(cx);
+                                    })),
+                            )
+                            // 最近使ったファイル（クリックでキューに追加し直す）
+                            .child(
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .child(
+                                        Button::new("recent-files")
+                                            .label("最近使ったファイル ▾")
+                                            .with_variant(if recent_menu.is_some() {
+                                                ButtonVariant::Primary
+                                            } else {
+                                                ButtonVariant::Ghost
+                                            })
+                                            .on_click(cx.listener(|this, _, _, cx| {
+                                                this.open_recent_menu(cx);
+                                            })),
+                                    )
+                                    .children(recent_menu),
+                            )
                             .child(
                                 Button::new("clear-queue")
                                     .label("クリア")
@@ -466,6 +2721,39 @@ impl Render for MainWindow {
                                     .on_click(cx.listener(|this, _, _, cx| {
                                         this.clear_queue(cx);
                                     })),
+                            )
+                            .child(
+                                Button::new("remux-preset")
+                                    .label("リムックス (高速)")
+                                    .with_variant(if is_remux {
+                                        ButtonVariant::Primary
+                                    } else {
+                                        ButtonVariant::Ghost
+                                    })
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.apply_remux_preset(cx);
+                                    })),
+                            )
+                            // 監視フォルダの状態
+                            .child(
+                                div()
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(6.0))
+                                    .px(px(8.0))
+                                    .text_xs()
+                                    .text_color(rgb(0x6c7086))
+                                    .child(div().w(px(8.0)).h(px(8.0)).rounded_full().bg(
+                                        if watch_folder.is_some() {
+                                            rgb(0xa6e3a1)
+                                        } else {
+                                            rgb(0x45475a)
+                                        },
+                                    ))
+                                    .child(match watch_folder {
+                                        Some(name) => format!("監視中: {}", name),
+                                        None => "監視オフ".to_string(),
+                                    }),
                             ),
                     )
                     // 中央: タイトル
@@ -482,7 +2770,7 @@ impl Render for MainWindow {
                             )
                             .child(div().text_sm().text_color(rgb(0x6c7086)).child("鎌鼬")),
                     )
-                    // 右側: 開始・About
+                    // 右側: 開始・ログ・About
                     .child(
                         div()
                             .flex()
@@ -496,6 +2784,39 @@ impl Render for MainWindow {
                                         this.start_transcode(cx);
                                     })),
                             )
+                            // 完了後の動作（クリックで切り替え）
+                            .child(
+                                Button::new("post-action")
+                                    .label(format!("完了後: {}", post_action.display_name()))
+                                    .with_variant(if post_action.is_one_shot() {
+                                        ButtonVariant::Primary
+                                    } else {
+                                        ButtonVariant::Ghost
+                                    })
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.cycle_post_action(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("toggle-log")
+                                    .label("ログ")
+                                    .with_variant(if self.show_log {
+                                        ButtonVariant::Primary
+                                    } else {
+                                        ButtonVariant::Ghost
+                                    })
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.toggle_log(cx);
+                                    })),
+                            )
+                            .child(
+                                Button::new("history")
+                                    .label("履歴")
+                                    .with_variant(ButtonVariant::Ghost)
+                                    .on_click(cx.listener(|this, _, _, cx| {
+                                        this.show_history(cx);
+                                    })),
+                            )
                             .child(
                                 Button::new("about")
                                     .label("About")
@@ -513,6 +2834,16 @@ impl Render for MainWindow {
                     .w_full()
                     .flex()
                     .overflow_hidden()
+                    .when(self.dragging_divider, |this| this.cursor_col_resize())
+                    .on_mouse_move(cx.listener(|this, event: &MouseMoveEvent, window, cx| {
+                        this.drag_divider(event, window, cx);
+                    }))
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this, _, _, cx| {
+                            this.end_divider_drag(cx);
+                        }),
+                    )
                     // 左側: ファイルリスト
                     .child(
                         div()
@@ -520,20 +2851,46 @@ impl Render for MainWindow {
                             .h_full()
                             .flex()
                             .flex_col()
-                            .border_r_1()
-                            .border_color(rgb(0x313244))
                             .child(self.file_list.clone()),
                     )
+                    // 区切り線（ドラッグで設定パネルの幅を変更）
+                    .child(
+                        div()
+                            .w(px(4.0))
+                            .h_full()
+                            .flex()
+                            .justify_center()
+                            .cursor_col_resize()
+                            .hover(|s| s.bg(rgb(0x45475a)))
+                            .when(self.dragging_divider, |this| this.bg(rgb(0x89b4fa)))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _, _, cx| {
+                                    this.start_divider_drag(cx);
+                                }),
+                            )
+                            .child(div().w(px(1.0)).h_full().bg(rgb(0x313244))),
+                    )
                     // 右側: 設定パネル
                     .child(
                         div()
-                            .w(px(360.0))
+                            .w(px(self.settings_panel_width))
                             .h_full()
                             .flex()
                             .flex_col()
                             .child(self.settings_panel.clone()),
                     ),
             )
+            // FFmpegログ（ステータスバーの上に表示）
+            .when(self.show_log, |this| {
+                this.child(
+                    div()
+                        .w_full()
+                        .border_t_1()
+                        .border_color(rgb(0x313244))
+                        .child(self.log_view.clone()),
+                )
+            })
             // ステータスバー / 進捗
             .child(
                 div()
@@ -544,33 +2901,333 @@ impl Render for MainWindow {
             )
             // Aboutダイアログ（モーダル）
             .when(self.show_about, |this| {
-                this.child(
-                    div()
-                        .absolute()
-                        .inset_0()
-                        .flex()
-                        .items_center()
-                        .justify_center()
-                        .bg(rgba(0x00000080))
-                        .on_mouse_down(
-                            MouseButton::Left,
-                            cx.listener(|this, _, _, cx| {
-                                this.hide_about(cx);
-                            }),
-                        )
-                        .child(
-                            div()
-                                .on_mouse_down(MouseButton::Left, |_, _, cx| {
-                                    // ダイアログ内のクリックは伝播させない
-                                    cx.stop_propagation();
-                                })
-                                .child(AboutDialog::render_content(cx.listener(
-                                    |this, _, _, cx| {
-                                        this.hide_about(cx);
-                                    },
-                                ))),
-                        ),
-                )
+                let content = AboutDialog::render_content(cx.listener(|this, _, _, cx| {
+                    this.hide_about(cx);
+                }));
+                this.child(Self::render_modal(content, Self::hide_about, cx))
             })
+            // 変換履歴ダイアログ（モーダル）
+            .when(self.show_history, |this| {
+                let content = self.history_view.clone();
+                this.child(Self::render_modal(content, Self::hide_history, cx))
+            })
+            // FFmpegダウンロードダイアログ（モーダル）
+            .when(self.show_download, |this| {
+                let content = self.download_dialog.clone();
+                this.child(Self::render_modal(content, Self::hide_download_dialog, cx))
+            })
+            // フォルダ追加の確認ダイアログ（モーダル）
+            .when_some(self.pending_folder_files.clone(), |this, files| {
+                let content = Self::render_folder_confirm(&files, cx);
+                this.child(Self::render_modal(content, Self::cancel_folder_files, cx))
+            })
+            // 元ファイル削除の確認ダイアログ（モーダル）
+            .when_some(self.pending_source_deletion.clone(), |this, files| {
+                let content = Self::render_source_deletion_confirm(&files, cx);
+                this.child(Self::render_modal(
+                    content,
+                    Self::cancel_source_deletion,
+                    cx,
+                ))
+            })
+            // 空き容量不足の確認ダイアログ（モーダル）
+            .when_some(self.pending_space_shortage.clone(), |this, shortage| {
+                let content = Self::render_space_confirm(&shortage, cx);
+                this.child(Self::render_modal(content, Self::cancel_space_shortage, cx))
+            })
+            // 完了後の動作のカウントダウン（モーダル）
+            .when_some(
+                self.pending_post_action.clone(),
+                |this, (action, remaining)| {
+                    let content = Self::render_post_action_countdown(&action, remaining, cx);
+                    this.child(Self::render_modal(content, Self::cancel_post_action, cx))
+                },
+            )
+            // サンプルのエンコード結果（モーダル）
+            .when_some(self.sample_result.clone(), |this, result| {
+                let content = Self::render_sample_result(&result, cx);
+                this.child(Self::render_modal(content, Self::hide_sample_result, cx))
+            })
+            // 一定時間で消える通知（操作を妨げないよう右下に表示）
+            .when_some(self.toast.clone(), |this, (_, message)| {
+                this.child(Self::render_toast(message, cx))
+            })
+            // 警告ダイアログ（モーダル）
+            .when_some(self.alert_message.clone(), |this, message| {
+                let content = Self::render_alert(message, cx);
+                this.child(Self::render_modal(content, Self::hide_alert, cx))
+            })
+    }
+}
+
+/// エンコーダーを解決する
+/// 並列変換では同時セッション数が上限のときに使うソフトウェアエンコーダーも解決する
+fn resolve_batch_plan(
+    options: &QueueOptions,
+    parallel: bool,
+) -> (ResolvedEncodePlan, Option<ResolvedEncodePlan>) {
+    let plan = options.resolve_encode_plan();
+    let software = if parallel {
+        options.session_fallback(&plan)
+    } else {
+        None
+    };
+    (plan, software)
+}
+
+/// HWアクセラレーションの同時セッションの割り当て（バッチで最初に使うときに上限を調べる）
+async fn batch_session_slots(
+    batch: &RefCell<BatchState>,
+    options: &QueueOptions,
+    hwaccel: HwAccelType,
+) -> Rc<SessionSlots> {
+    let find = |batch: &BatchState| {
+        batch
+            .session_slots
+            .iter()
+            .find(|(slots_hwaccel, _)| *slots_hwaccel == hwaccel)
+            .map(|(_, slots)| slots.clone())
+    };
+    if let Some(slots) = find(&batch.borrow()) {
+        return slots;
+    }
+    // GPUの判定にnvidia-smiを実行するためバックグラウンドで調べる
+    let limit = {
+        let options = options.clone();
+        smol::unblock(move || options.session_limit(&hwaccel)).await
+    };
+    // 調べている間に他のジョブが作成していればそれを使う
+    let mut batch = batch.borrow_mut();
+    if let Some(slots) = find(&batch) {
+        return slots;
+    }
+    let slots = Rc::new(SessionSlots::new(limit));
+    batch.session_slots.push((hwaccel, slots.clone()));
+    slots
+}
+
+/// 既にキューにあるファイルを追加しなかったときの通知
+fn already_queued_message(count: usize) -> String {
+    if count == 1 {
+        "このファイルは既にキューにあります".to_string()
+    } else {
+        format!(
+            "{}件のファイルは既にキューにあるため追加しませんでした",
+            count
+        )
+    }
+}
+
+/// 監視フォルダから追加したファイルに、監視フォルダ用のプリセットを固定する
+/// 全体の設定は変えない（出力先とサフィックスは全体の設定のまま）
+fn apply_watch_preset(
+    app_state: &AppState,
+    name: &str,
+    paths: &[std::path::PathBuf],
+    cx: &mut App,
+) {
+    use crate::config::PresetStore;
+    use crate::transcoder::TranscodeSettings;
+
+    let preset = match PresetStore::open() {
+        Ok(store) => store.load_preset(name),
+        Err(e) => {
+            log::warn!("Failed to open presets: {}", e);
+            return;
+        }
+    };
+    let Some(preset) = preset else {
+        log::warn!("Watch folder preset not found: {}", name);
+        return;
+    };
+    let settings = app_state.transcode_settings.read(cx).clone();
+    let calibration = app_state.calibration.read(cx).clone();
+    let preset = TranscodeSettings {
+        output_dir: settings.output_dir.clone(),
+        output_suffix: settings.output_suffix.clone(),
+        ..preset
+    };
+    app_state.update_files(cx, |files, _| {
+        for f in files.iter_mut().filter(|f| paths.contains(&f.path)) {
+            f.settings_override = Some(preset.clone());
+            f.update_estimated_size(&settings, &calibration);
+        }
+    });
+}
+
+/// フォルダ直下の対応動画ファイルとそのサイズを列挙
+/// 出力先がこのフォルダの場合は変換済みファイルをサフィックスで除外する
+fn list_video_files(
+    dir: &std::path::Path,
+    output_dir: Option<&std::path::Path>,
+    output_suffix: &str,
+) -> Vec<(std::path::PathBuf, u64)> {
+    let canonical =
+        |path: &std::path::Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let skip_transcoded =
+        !output_suffix.is_empty() && output_dir.is_none_or(|out| canonical(out) == canonical(dir));
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let path = entry.path();
+            let is_transcoded = skip_transcoded && has_output_suffix(&path, output_suffix);
+            (metadata.is_file() && AppState::is_supported_format(&path) && !is_transcoded)
+                .then_some((path, metadata.len()))
+        })
+        .collect()
+}
+
+/// 変換済みファイルのサフィックスが付いているか（拡張子を除いた名前で判定）
+fn has_output_suffix(path: &std::path::Path, output_suffix: &str) -> bool {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().ends_with(output_suffix))
+        .unwrap_or(false)
+}
+
+/// フォルダ以下の対応動画ファイルを再帰的に収集
+/// 隠しフォルダと、フォルダ内にある出力先フォルダはスキップする
+fn collect_video_files(
+    root: &std::path::Path,
+    output_dir: Option<&std::path::Path>,
+    output_suffix: &str,
+) -> Vec<std::path::PathBuf> {
+    let canonical =
+        |path: &std::path::Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let output_dir = output_dir.map(canonical);
+
+    // 出力先が入力フォルダと同じ場合は変換済みファイルをサフィックスで除外する
+    let skip_transcoded = !output_suffix.is_empty()
+        && output_dir
+            .as_ref()
+            .is_none_or(|dir| *dir == canonical(root));
+
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                let is_output_dir = output_dir.as_ref() == Some(&canonical(&path));
+                if !is_hidden && !is_output_dir {
+                    stack.push(path);
+                }
+            } else if file_type.is_file() && AppState::is_supported_format(&path) {
+                let is_transcoded = skip_transcoded && has_output_suffix(&path, output_suffix);
+                if !is_transcoded {
+                    files.push(path);
+                }
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// ウィンドウを重なっている画面（なければ先頭の画面）の内側に収める
+/// 外部モニターを外した後でも、ウィンドウが見えない位置に開かないようにする
+fn fit_bounds_to_displays(bounds: Bounds<Pixels>, displays: &[Bounds<Pixels>]) -> Bounds<Pixels> {
+    let Some(display) = displays
+        .iter()
+        .find(|display| display.intersects(&bounds))
+        .or(displays.first())
+    else {
+        return bounds;
+    };
+
+    let width = f32::from(bounds.size.width).min(f32::from(display.size.width));
+    let height = f32::from(bounds.size.height).min(f32::from(display.size.height));
+    let left = f32::from(display.origin.x);
+    let top = f32::from(display.origin.y);
+    let x = f32::from(bounds.origin.x).clamp(left, left + f32::from(display.size.width) - width);
+    let y = f32::from(bounds.origin.y).clamp(top, top + f32::from(display.size.height) - height);
+    Bounds::new(point(px(x), px(y)), size(px(width), px(height)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fit_bounds_to_displays, list_video_files};
+    use gpui::{point, px, size, Bounds, Pixels};
+
+    fn bounds(x: f32, y: f32, width: f32, height: f32) -> Bounds<Pixels> {
+        Bounds::new(point(px(x), px(y)), size(px(width), px(height)))
+    }
+
+    #[test]
+    fn test_list_video_files_skips_outputs() {
+        let dir =
+            std::env::temp_dir().join(format!("kamaitachi-watch-outputs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.mkv"), b"a").unwrap();
+        std::fs::write(dir.join("a_transcoded.mp4"), b"out").unwrap();
+
+        let names = |files: Vec<(std::path::PathBuf, u64)>| {
+            let mut names: Vec<_> = files
+                .into_iter()
+                .map(|(path, _)| path.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // 出力先が未設定（入力と同じフォルダ）なら変換済みファイルは除外
+        assert_eq!(
+            names(list_video_files(&dir, None, "_transcoded")),
+            vec!["a.mkv"]
+        );
+        assert_eq!(
+            names(list_video_files(&dir, Some(&dir), "_transcoded")),
+            vec!["a.mkv"]
+        );
+        // 出力先が別のフォルダなら同じ名前のファイルも入力として扱う
+        let other = dir.join("out");
+        assert_eq!(
+            names(list_video_files(&dir, Some(&other), "_transcoded")),
+            vec!["a.mkv", "a_transcoded.mp4"]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fit_bounds_to_displays() {
+        let primary = bounds(0.0, 0.0, 1920.0, 1080.0);
+        let external = bounds(1920.0, 0.0, 2560.0, 1440.0);
+
+        // 画面内ならそのまま
+        let window = bounds(2200.0, 100.0, 1200.0, 800.0);
+        assert_eq!(fit_bounds_to_displays(window, &[primary, external]), window);
+
+        // 外部モニターを外したらメイン画面の内側へ
+        assert_eq!(
+            fit_bounds_to_displays(window, &[primary]),
+            bounds(720.0, 100.0, 1200.0, 800.0)
+        );
+
+        // 画面より大きいウィンドウは縮める
+        assert_eq!(
+            fit_bounds_to_displays(bounds(-50.0, -50.0, 2560.0, 1440.0), &[primary]),
+            primary
+        );
+
+        // 画面の情報が取れなければそのまま
+        assert_eq!(fit_bounds_to_displays(window, &[]), window);
     }
 }
@@ -1,13 +1,20 @@
 //! UIモジュール
 
 mod about_dialog;
+mod download_dialog;
 mod file_list;
+mod history_view;
+mod log_view;
 mod main_window;
 mod progress_view;
 mod settings_panel;
+mod slider;
 
 pub use about_dialog::AboutDialog;
-pub use file_list::FileList;
+pub use download_dialog::DownloadDialog;
+pub use file_list::{FileList, FileListEvent};
+pub use history_view::HistoryView;
+pub use log_view::LogView;
 pub use main_window::MainWindow;
 pub use progress_view::ProgressView;
 pub use settings_panel::SettingsPanel;
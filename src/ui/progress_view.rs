@@ -1,11 +1,152 @@
 //! 進捗表示
 
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 
-use crate::app::AppState;
-use crate::transcoder::format_duration;
+use crate::app::{AppState, FileEntry, FileStatus};
+use crate::transcoder::{format_bitrate, format_duration, format_size};
 use std::time::Duration;
 
+/// キュー全体の集計
+#[derive(Clone, Debug, Default, PartialEq)]
+struct QueueSummary {
+    /// 総ファイル数
+    total: usize,
+    /// 完了したファイル数
+    completed: usize,
+    /// 残りファイル数（処理中を含む）
+    remaining: usize,
+    /// 残りファイルの予測出力サイズ合計
+    remaining_size: u64,
+    /// キュー全体の進捗 (0.0 - 1.0)
+    progress: f32,
+    /// 残り時間（秒、速度が不明な場合はNone）
+    eta_secs: Option<f64>,
+}
+
+impl QueueSummary {
+    /// ファイル一覧と現在のファイルの進捗・FPSから集計
+    /// 並列変換では処理中のファイルごとの進捗（FileEntry::progress）を使う
+    fn compute(files: &[FileEntry], current_progress: f32, current_fps: f32) -> Self {
+        let mut summary = Self {
+            total: files.len(),
+            ..Default::default()
+        };
+        let mut total_duration = 0.0;
+        let mut done_duration = 0.0;
+        let mut remaining_duration = 0.0;
+        let mut speed = None;
+        let mut processing = 0;
+
+        for file in files {
+            let duration = file.metadata.duration.unwrap_or(0.0);
+            total_duration += duration;
+            match file.status {
+                FileStatus::Pending => {
+                    summary.remaining += 1;
+                    summary.remaining_size += file.estimated_size.unwrap_or(0);
+                    remaining_duration += duration;
+                }
+                FileStatus::Processing => {
+                    summary.remaining += 1;
+                    summary.remaining_size += file.estimated_size.unwrap_or(0);
+                    processing += 1;
+                    let progress = if file.progress > 0.0 {
+                        file.progress
+                    } else {
+                        current_progress
+                    };
+                    let done = duration * progress as f64;
+                    done_duration += done;
+                    remaining_duration += duration - done;
+                    // 変換速度の倍率（エンコードFPS / ソースFPS）
+                    if let Some(source_fps) = file.metadata.fps.filter(|fps| *fps > 0.0) {
+                        if current_fps > 0.0 {
+                            speed = Some(current_fps as f64 / source_fps);
+                        }
+                    }
+                }
+                FileStatus::Completed { .. } => {
+                    summary.completed += 1;
+                    done_duration += duration;
+                }
+                // エラー・キャンセル・スキップは処理済みとして扱う
+                _ => done_duration += duration,
+            }
+        }
+
+        if total_duration > 0.0 {
+            summary.progress = (done_duration / total_duration).clamp(0.0, 1.0) as f32;
+        } else if summary.total > 0 {
+            summary.progress = (summary.total - summary.remaining) as f32 / summary.total as f32;
+        }
+        // 並列変換では同じ速さのジョブが同時に進むとみなす
+        summary.eta_secs = speed.map(|speed| remaining_duration / (speed * processing as f64));
+
+        summary
+    }
+}
+
+/// 予測サイズを出すのに必要な最小の進捗（序盤はヘッダー分などで大きくぶれるため）
+const MIN_PROGRESS_FOR_SIZE_PREDICTION: f32 = 0.02;
+
+/// 事前の予測サイズに対して警告を出す超過率
+const SIZE_OVERRUN_RATIO: f64 = 1.25;
+
+/// 変換中のファイルの出力サイズ
+#[derive(Clone, Debug, Default, PartialEq)]
+struct OutputSizeReadout {
+    /// 現在の出力サイズ
+    current: u64,
+    /// 変換前に推定したサイズ
+    estimated: Option<u64>,
+    /// 現在の進捗から予測した最終サイズ
+    predicted: Option<u64>,
+    /// 最終サイズが事前の予測を大きく上回りそうな場合、その事前の予測サイズ
+    overrun_of: Option<u64>,
+}
+
+impl OutputSizeReadout {
+    /// 現在の出力サイズと進捗、変換前の予測サイズから集計
+    fn compute(current: u64, progress: f32, estimated: Option<u64>) -> Self {
+        let predicted = (current > 0 && progress >= MIN_PROGRESS_FOR_SIZE_PREDICTION)
+            .then(|| (current as f64 / progress as f64) as u64);
+        let overrun_of = match (predicted, estimated) {
+            (Some(predicted), Some(estimated))
+                if estimated > 0 && predicted as f64 > estimated as f64 * SIZE_OVERRUN_RATIO =>
+            {
+                Some(estimated)
+            }
+            _ => None,
+        };
+
+        Self {
+            current,
+            estimated: estimated.filter(|e| *e > 0),
+            predicted,
+            overrun_of,
+        }
+    }
+
+    /// 変換前の推定サイズに対する現在のサイズの割合（0.0 - 1.0）
+    fn size_progress(&self) -> Option<f32> {
+        self.estimated
+            .map(|estimated| (self.current as f64 / estimated as f64).min(1.0) as f32)
+    }
+
+    /// 表示用の文字列
+    fn label(&self) -> String {
+        let mut label = format!("出力サイズ: {}", format_size(self.current));
+        if let Some(estimated) = self.estimated {
+            label.push_str(&format!(" / 推定 {}", format_size(estimated)));
+        }
+        if let Some(predicted) = self.predicted {
+            label.push_str(&format!(" · 見込み {}", format_size(predicted)));
+        }
+        label
+    }
+}
+
 /// 進捗ビュー
 pub struct ProgressView {
     /// アプリケーション状態
@@ -17,12 +158,19 @@ impl ProgressView {
         Self { app_state }
     }
 
-    /// キャンセル処理
+    /// キャンセル処理（すべて中止）
     fn cancel_transcode(&mut self, cx: &mut Context<Self>) {
         log::info!("Cancel button clicked");
         self.app_state.current_progress.cancel();
         cx.notify();
     }
+
+    /// 現在のファイルのみスキップ
+    fn skip_current_file(&mut self, cx: &mut Context<Self>) {
+        log::info!("Skip button clicked");
+        self.app_state.current_progress.skip_current();
+        cx.notify();
+    }
 }
 
 impl Render for ProgressView {
@@ -41,6 +189,7 @@ impl Render for ProgressView {
                 let elapsed_secs = self.app_state.current_progress.get_elapsed_secs();
                 let remaining_secs = self.app_state.current_progress.get_remaining_secs();
                 let fps = self.app_state.current_progress.get_fps();
+                let speed = self.app_state.current_progress.get_speed();
                 
                 // 表示用の文字列を作成
                 let progress_percent = (progress * 100.0) as u32;
@@ -49,16 +198,59 @@ impl Render for ProgressView {
                     .map(|s| format_duration(Duration::from_secs_f32(s)))
                     .unwrap_or_else(|| "--:--".to_string());
                 
-                let status_text = if fps > 0.0 {
+                // キュー全体の集計
+                let files = self.app_state.files.read(cx);
+                let summary = QueueSummary::compute(files, progress, fps);
+
+                // 出力サイズ（変換前の予測と比較。サンプルはファイル全体の予測と比べない）
+                let is_sample = job.segment.is_some();
+                let estimated_size = files
+                    .iter()
+                    .find(|f| f.path == job.input_path)
+                    .and_then(|f| f.estimated_size)
+                    .filter(|_| !is_sample);
+                let output_size = self.app_state.current_progress.get_output_size();
+                let size_readout = (output_size > 0)
+                    .then(|| OutputSizeReadout::compute(output_size, progress, estimated_size));
+                // 推定サイズに対する出力サイズの進み具合（時間の進捗と並べて表示）
+                let size_progress = size_readout.as_ref().and_then(|r| r.size_progress());
+                let size_overrun = size_readout
+                    .as_ref()
+                    .is_some_and(|r| r.overrun_of.is_some());
+                let bitrate = self.app_state.current_progress.get_bitrate_bps();
+                let eta_str = summary
+                    .eta_secs
+                    .map(|s| format!("約 {}", format_duration(Duration::from_secs_f64(s))))
+                    .unwrap_or_else(|| "計算中...".to_string());
+                let queue_text = match job.segment {
+                    Some((_, secs)) => format!("サンプル（{:.0}秒）をエンコード中", secs),
+                    None => format!(
+                        "全体 {}/{} | 残り {} ファイル — {} | 残り予測 {}",
+                        summary.completed,
+                        summary.total,
+                        summary.remaining,
+                        eta_str,
+                        format_size(summary.remaining_size)
+                    ),
+                };
+
+                let status_text = if fps > 0.0 && speed > 0.0 {
+                    format!("{}% | {} 経過 | {} 残り | {:.1} fps | {:.2}x", progress_percent, elapsed_str, remaining_str, fps, speed)
+                } else if fps > 0.0 {
                     format!("{}% | {} 経過 | {} 残り | {:.1} fps", progress_percent, elapsed_str, remaining_str, fps)
                 } else {
                     format!("{}% | {} 経過 | {} 残り", progress_percent, elapsed_str, remaining_str)
                 };
+                let status_text = if bitrate > 0 {
+                    format!("{} | {}", status_text, format_bitrate(bitrate))
+                } else {
+                    status_text
+                };
 
                 // ジョブ実行中の進捗表示
                 div()
                     .w_full()
-                    .h(px(60.0))
+                    .h(px(84.0))
                     .px(px(16.0))
                     .flex()
                     .items_center()
@@ -77,13 +269,38 @@ impl Render for ProgressView {
                                     .items_center()
                                     .justify_between()
                                     .child(
-                                        div().text_sm().child(
-                                            job.input_path
-                                                .file_name()
-                                                .and_then(|n| n.to_str())
-                                                .unwrap_or("Processing...")
-                                                .to_string(),
-                                        ),
+                                        div()
+                                            .flex()
+                                            .items_center()
+                                            .gap(px(12.0))
+                                            .child(
+                                                div().text_sm().child(
+                                                    job.input_path
+                                                        .file_name()
+                                                        .and_then(|n| n.to_str())
+                                                        .unwrap_or("Processing...")
+                                                        .to_string(),
+                                                ),
+                                            )
+                                            .when_some(size_readout, |this, readout| {
+                                                this.child(
+                                                    div()
+                                                        .text_xs()
+                                                        .text_color(rgb(0xa6e3a1))
+                                                        .child(readout.label()),
+                                                )
+                                                .when_some(readout.overrun_of, |this, estimated| {
+                                                    this.child(
+                                                        div()
+                                                            .text_xs()
+                                                            .text_color(rgb(0xf9e2af))
+                                                            .child(format!(
+                                                                "⚠ 事前予測 {} を25%以上超過",
+                                                                format_size(estimated)
+                                                            )),
+                                                    )
+                                                })
+                                            }),
                                     )
                                     .child(
                                         div()
@@ -105,8 +322,61 @@ impl Render for ProgressView {
                                             .bg(rgb(0x89b4fa))
                                             .w(relative(progress)),
                                     ),
+                            )
+                            // 出力サイズの進捗（推定サイズに対する割合）
+                            .when_some(size_progress, |this, size_progress| {
+                                this.child(
+                                    div()
+                                        .w_full()
+                                        .h(px(2.0))
+                                        .rounded(px(1.0))
+                                        .bg(rgb(0x313244))
+                                        .child(
+                                            div()
+                                                .h_full()
+                                                .rounded(px(1.0))
+                                                .bg(if size_overrun {
+                                                    rgb(0xf9e2af)
+                                                } else {
+                                                    rgb(0x94e2d5)
+                                                })
+                                                .w(relative(size_progress)),
+                                        ),
+                                )
+                            })
+                            // キュー全体の進捗
+                            .child(div().text_xs().text_color(rgb(0xa6adc8)).child(queue_text))
+                            .child(
+                                div()
+                                    .w_full()
+                                    .h(px(4.0))
+                                    .rounded(px(2.0))
+                                    .bg(rgb(0x313244))
+                                    .child(
+                                        div()
+                                            .h_full()
+                                            .rounded(px(2.0))
+                                            .bg(rgb(0xa6e3a1))
+                                            .w(relative(summary.progress)),
+                                    ),
                             ),
                     )
+                    // スキップボタン
+                    .child(
+                        div()
+                            .id("skip-button")
+                            .px(px(12.0))
+                            .py(px(6.0))
+                            .rounded(px(4.0))
+                            .bg(rgb(0x313244))
+                            .text_sm()
+                            .cursor_pointer()
+                            .hover(|s| s.bg(rgb(0x45475a)))
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.skip_current_file(cx);
+                            }))
+                            .child("このファイルをスキップ"),
+                    )
                     // キャンセルボタン
                     .child(
                         div()
@@ -122,7 +392,7 @@ impl Render for ProgressView {
                             .on_click(cx.listener(|this, _, _, cx| {
                                 this.cancel_transcode(cx);
                             }))
-                            .child("キャンセル"),
+                            .child("すべて中止"),
                     )
             }
             None => {
@@ -151,3 +421,83 @@ impl Render for ProgressView {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{OutputSizeReadout, QueueSummary};
+    use crate::app::{FileEntry, FileStatus};
+    use std::path::PathBuf;
+
+    fn entry(status: FileStatus, duration: f64, estimated_size: u64) -> FileEntry {
+        let mut entry = FileEntry::new(PathBuf::from("video.mp4"));
+        entry.status = status;
+        entry.estimated_size = Some(estimated_size);
+        entry.metadata.duration = Some(duration);
+        entry.metadata.fps = Some(30.0);
+        entry
+    }
+
+    #[test]
+    fn test_queue_summary() {
+        let files = vec![
+            entry(FileStatus::completed(None, 0, None), 100.0, 10),
+            entry(FileStatus::Processing, 100.0, 20),
+            entry(FileStatus::Pending, 200.0, 40),
+        ];
+
+        // 現在のファイルが半分まで進み、ソースの2倍速（60fps）で変換中
+        let summary = QueueSummary::compute(&files, 0.5, 60.0);
+        assert_eq!(summary.total, 3);
+        assert_eq!(summary.completed, 1);
+        assert_eq!(summary.remaining, 2);
+        assert_eq!(summary.remaining_size, 60);
+        assert!((summary.progress - 150.0 / 400.0).abs() < 1e-6);
+        // 残り250秒分を2倍速で処理
+        assert_eq!(summary.eta_secs, Some(125.0));
+
+        // FPSがまだ取得できていない場合はETAを出さない
+        assert_eq!(QueueSummary::compute(&files, 0.0, 0.0).eta_secs, None);
+
+        // 並列変換ではファイルごとの進捗を使い、同時に進むジョブの数で割る
+        let mut files = files;
+        files[1].progress = 0.25;
+        let mut parallel = entry(FileStatus::Processing, 100.0, 20);
+        parallel.progress = 0.75;
+        files.push(parallel);
+        let summary = QueueSummary::compute(&files, 0.5, 60.0);
+        assert_eq!(summary.remaining, 3);
+        assert!((summary.progress - 200.0 / 500.0).abs() < 1e-6);
+        // 残り300秒分を2倍速の2ジョブで処理
+        assert_eq!(summary.eta_secs, Some(75.0));
+    }
+
+    #[test]
+    fn test_output_size_readout() {
+        const MB: u64 = 1024 * 1024;
+
+        // 25%地点で300MB → 最終1200MBの見込み
+        let readout = OutputSizeReadout::compute(300 * MB, 0.25, Some(1000 * MB));
+        assert_eq!(readout.predicted, Some(1200 * MB));
+        assert_eq!(readout.overrun_of, None);
+        assert_eq!(
+            readout.label(),
+            "出力サイズ: 300.00 MB / 推定 1000.00 MB · 見込み 1.17 GB"
+        );
+        assert_eq!(readout.size_progress(), Some(0.3));
+
+        // 事前予測の25%超過で警告
+        let readout = OutputSizeReadout::compute(400 * MB, 0.25, Some(1000 * MB));
+        assert_eq!(readout.overrun_of, Some(1000 * MB));
+
+        // 序盤は予測しない
+        let readout = OutputSizeReadout::compute(MB, 0.01, Some(1000 * MB));
+        assert_eq!(readout.predicted, None);
+        assert_eq!(readout.overrun_of, None);
+        assert_eq!(readout.label(), "出力サイズ: 1.00 MB / 推定 1000.00 MB");
+
+        // 推定サイズがなければ割合を出さない
+        let readout = OutputSizeReadout::compute(300 * MB, 0.25, None);
+        assert_eq!(readout.size_progress(), None);
+        assert_eq!(readout.label(), "出力サイズ: 300.00 MB · 見込み 1.17 GB");
+    }
+}
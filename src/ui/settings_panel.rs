@@ -3,43 +3,483 @@
 use gpui::prelude::*;
 use gpui::{InteractiveElement, *};
 use gpui_component::button::{Button, ButtonVariant, ButtonVariants};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::Disableable;
+use std::ops::Range;
 
-use crate::app::AppState;
+use super::slider::{SliderEvent, SliderWidget};
+use crate::app::{AppState, CancelToken};
+use crate::config::{
+    read_handbrake_preset, HandBrakeImport, PostFileAction, PostTranscodeAction, PresetStore,
+    Settings,
+};
+use crate::ffmpeg::{FfmpegDetector, FfmpegDownloader, FfmpegInfo, ProcessPriority};
 use crate::transcoder::{
-    AmfQuality, AmfUsage, AqMode, AudioCodec, ContainerFormat, HwAccelType, NvencBRefMode,
-    NvencMultipass, NvencTune, RateControlMode, TranscodeSettings, VideoCodec, VideoPreset,
-    VideoResolution, X264Profile, X264Tune,
+    crf_from_encoder_crf, encoder_crf, encoder_crf_max, parse_max_cll, AmfUsage, AudioChannels,
+    AudioCodec, ContainerFormat, CropMode, CropSettings, Deinterlace, FpsFilterMode, FrameRate,
+    HdrToneMappingMode, HwAccelDetector, HwAccelType, HwDecodeMode, LibaomUsage, NvencBRefMode,
+    NvencMultipass, NvencTune, OverwritePolicy, PixelFormat, RateControlMode, SvtAv1PredStructure,
+    SvtAv1Tune, TranscodeSettings, VideoCodec, VideoPreset, VideoResolution, Vp9Deadline,
+    X264Profile, X264Tune,
 };
 
+/// 組み込みプリセット
+#[derive(Clone, Copy)]
+enum FactoryPreset {
+    Fast1080p,
+    HqArchiveAv1,
+    Anime,
+    Web8Mb,
+}
+
+impl FactoryPreset {
+    /// 表示名を取得
+    fn display_name(&self) -> &'static str {
+        match self {
+            FactoryPreset::Fast1080p => "高速 1080p",
+            FactoryPreset::HqArchiveAv1 => "高画質アーカイブ (AV1)",
+            FactoryPreset::Anime => "アニメ",
+            FactoryPreset::Web8Mb => "Web/Discord (8MB)",
+        }
+    }
+
+    /// すべてのバリアントを取得
+    fn all() -> &'static [FactoryPreset] {
+        &[
+            FactoryPreset::Fast1080p,
+            FactoryPreset::HqArchiveAv1,
+            FactoryPreset::Anime,
+            FactoryPreset::Web8Mb,
+        ]
+    }
+}
+
+/// 検索語が含まれる範囲を返す（大文字小文字は区別しない）
+fn find_query(label: &str, query: &str) -> Option<Range<usize>> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    // 小文字化で長さが変わる文字があるため、元の文字列の位置で比較する
+    label.char_indices().find_map(|(start, _)| {
+        let mut lowered = String::new();
+        for (offset, ch) in label[start..].char_indices() {
+            lowered.extend(ch.to_lowercase());
+            if lowered == query {
+                return Some(start..start + offset + ch.len_utf8());
+            }
+            if !query.starts_with(&lowered) {
+                break;
+            }
+        }
+        None
+    })
+}
+
+/// 設定名が検索語に一致するか（検索語が空ならすべて一致）
+fn setting_matches_query(label: &str, query: &str) -> bool {
+    query.trim().is_empty() || find_query(label, query).is_some()
+}
+
+/// カスタム解像度の幅・高さの上限
+const MAX_CUSTOM_DIMENSION: u32 = 8192;
+
+/// クロップ設定の1辺の値を取り出す
+type CropSide = fn(&mut CropSettings) -> &mut u32;
+
+/// カスタム解像度の入力欄を読み取る（空欄・不正な値は0 = 比率から自動）
+fn parse_custom_resolution(
+    width: &Entity<InputState>,
+    height: &Entity<InputState>,
+    cx: &App,
+) -> (u32, u32) {
+    let parse = |input: &Entity<InputState>| {
+        input
+            .read(cx)
+            .value()
+            .trim()
+            .parse::<u32>()
+            .map_or(0, |v| v.min(MAX_CUSTOM_DIMENSION))
+    };
+    (parse(width), parse(height))
+}
+
 /// 設定パネル
 pub struct SettingsPanel {
     /// アプリケーション状態
     app_state: AppState,
+    /// 「自動検出」が実際に選ぶHWアクセラレーション（検出中はNone）
+    resolved_auto_hwaccel: Option<HwAccelType>,
+    /// AV1で実際に使われるエンコーダー（検出時のHWアクセラレーション設定と組）
+    av1_encoder: Option<(HwAccelType, String)>,
+    /// FFmpegパスの確認結果（成功時はバージョン、失敗時はエラー内容）
+    ffmpeg_status: Option<Result<String, String>>,
+    /// FFmpegを検出中か
+    detecting_ffmpeg: bool,
+    /// ユーザー定義プリセット
+    preset_store: PresetStore,
+    /// 最後に適用・保存したプリセット名
+    active_preset: Option<String>,
+    /// HandBrakeプリセットの読み込み結果（失敗時はエラー内容）
+    handbrake_import: Option<Result<HandBrakeImport, String>>,
+    /// CRFのスライダー（エンコーダーの尺度で表示）
+    crf_slider: Entity<SliderWidget>,
+    /// 設定の検索欄（初回表示時に作成）
+    search_input: Option<Entity<InputState>>,
+    /// カスタム解像度の幅・高さの入力欄（初回表示時に作成）
+    resolution_inputs: Option<(Entity<InputState>, Entity<InputState>)>,
+    /// x265のマスタリングディスプレイ・MaxCLLの入力欄（初回表示時に作成）
+    hdr_inputs: Option<(Entity<InputState>, Entity<InputState>)>,
+    /// 目標サイズ（MB）の入力欄（初回表示時に作成）
+    target_size_input: Option<Entity<InputState>>,
+    /// 設定の検索語
+    search_query: String,
 }
 
 impl SettingsPanel {
-    pub fn new(app_state: AppState, _cx: &mut Context<Self>) -> Self {
-        Self { app_state }
+    pub fn new(app_state: AppState, cx: &mut Context<Self>) -> Self {
+        // FFmpegが変わったら自動検出の結果も更新
+        cx.observe(&app_state.ffmpeg_path, |this, _, cx| {
+            this.resolve_auto_hwaccel(cx);
+            this.resolve_av1_encoder(cx);
+        })
+        .detach();
+
+        let crf_slider = cx.new(|_| SliderWidget::new(0.0, 51.0, 1.0));
+        cx.subscribe(&crf_slider, |this, _, event: &SliderEvent, cx| {
+            let SliderEvent::Change(value) = *event;
+            let settings = this.app_state.transcode_settings.read(cx);
+            let hwaccel = this.effective_hwaccel(settings.hwaccel);
+            let encoder = settings.video_codec.encoder_name(&hwaccel);
+            let crf = crf_from_encoder_crf(value.round() as u8, encoder);
+            this.app_state
+                .transcode_settings
+                .update(cx, |settings, _| settings.crf = crf);
+            // 予測サイズを更新
+            Self::update_estimated_sizes(&this.app_state, cx);
+            cx.notify();
+        })
+        .detach();
+
+        let mut panel = Self {
+            app_state,
+            resolved_auto_hwaccel: None,
+            av1_encoder: None,
+            ffmpeg_status: None,
+            detecting_ffmpeg: false,
+            preset_store: PresetStore::open().unwrap_or_else(|e| {
+                log::warn!("Failed to load presets: {}", e);
+                PresetStore::default()
+            }),
+            active_preset: None,
+            handbrake_import: None,
+            crf_slider,
+            search_input: None,
+            resolution_inputs: None,
+            hdr_inputs: None,
+            target_size_input: None,
+            search_query: String::new(),
+        };
+        panel.resolve_auto_hwaccel(cx);
+        panel.resolve_av1_encoder(cx);
+        panel
+    }
+
+    /// 実際に使われるHWアクセラレーション（「自動検出」は解決後の種類）
+    fn effective_hwaccel(&self, hwaccel: HwAccelType) -> HwAccelType {
+        match hwaccel {
+            HwAccelType::Auto => self.resolved_auto_hwaccel.unwrap_or(HwAccelType::Auto),
+            hwaccel => hwaccel,
+        }
+    }
+
+    /// 「自動検出」で使われるHWアクセラレーションをバックグラウンドで解決
+    fn resolve_auto_hwaccel(&mut self, cx: &mut Context<Self>) {
+        let ffmpeg_path = self.app_state.ffmpeg_path.read(cx).clone();
+        let workers = self.app_state.workers.clone();
+        cx.spawn(async move |this, cx| {
+            let Some(resolved) = workers
+                .run(&CancelToken::default(), move || {
+                    HwAccelDetector::resolve_auto(HwAccelType::Auto, ffmpeg_path.as_ref())
+                })
+                .await
+            else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                this.resolved_auto_hwaccel = Some(resolved);
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// AV1選択時に実際に使われるエンコーダーをバックグラウンドで解決
+    /// （libaom / SVT-AV1 / HWエンコーダーで使える設定が異なるため）
+    fn resolve_av1_encoder(&mut self, cx: &mut Context<Self>) {
+        let settings = self.app_state.transcode_settings.read(cx).clone();
+        if settings.video_codec != VideoCodec::Av1 {
+            return;
+        }
+        let hwaccel = settings.hwaccel;
+        let ffmpeg_path = self.app_state.ffmpeg_path.read(cx).clone();
+        let workers = self.app_state.workers.clone();
+        cx.spawn(async move |this, cx| {
+            let Some(encoder) = workers
+                .run(&CancelToken::default(), move || {
+                    let resolved = HwAccelDetector::resolve_auto(hwaccel, ffmpeg_path.as_ref());
+                    HwAccelDetector::get_available_encoder(
+                        &VideoCodec::Av1,
+                        &resolved,
+                        ffmpeg_path.as_ref(),
+                    )
+                    .0
+                })
+                .await
+            else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                this.av1_encoder = Some((hwaccel, encoder));
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// FFmpegの実行ファイルを選択して検証（使用可能ならカスタムパスとして保存）
+    fn select_ffmpeg_path(&mut self, cx: &mut Context<Self>) {
+        cx.spawn(async move |this, cx| {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_title("FFmpegの実行ファイルを選択")
+                .pick_file()
+                .await
+            else {
+                return;
+            };
+            let path = file.path().to_path_buf();
+            let check_path = path.clone();
+            let result =
+                smol::unblock(move || FfmpegDetector::check_ffmpeg_at_path(&check_path)).await;
+            this.update(cx, |this, cx| {
+                if this.apply_ffmpeg_result(result, cx) {
+                    Self::update_app_settings(&this.app_state, cx, |settings| {
+                        settings.ffmpeg_custom_path = Some(path);
+                    });
+                }
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// FFmpegの検出を再実行（カスタムパス → システム → ダウンロード済みの順）
+    fn redetect_ffmpeg(&mut self, cx: &mut Context<Self>) {
+        let custom_path = self.app_state.settings.read(cx).ffmpeg_custom_path.clone();
+        self.detecting_ffmpeg = true;
+        cx.notify();
+
+        cx.spawn(async move |this, cx| {
+            let result = smol::unblock(move || {
+                FfmpegDetector::detect_with_custom_path(custom_path.as_ref()).or_else(|e| {
+                    match FfmpegDownloader::is_downloaded().ok().flatten() {
+                        Some(path) => FfmpegDetector::check_ffmpeg_at_path(&path),
+                        None => Err(e),
+                    }
+                })
+            })
+            .await;
+            this.update(cx, |this, cx| {
+                this.detecting_ffmpeg = false;
+                this.apply_ffmpeg_result(result, cx);
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// FFmpegの検出結果を反映（使用可能ならtrue）
+    fn apply_ffmpeg_result(
+        &mut self,
+        result: anyhow::Result<FfmpegInfo>,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        match result {
+            Ok(info) if FfmpegDetector::check_version_requirement(&info, 7) => {
+                log::info!("Using FFmpeg {} at {:?}", info.version, info.ffmpeg_path);
+                // 選び直した・検出し直したバイナリのエンコーダーは調べ直す
+                FfmpegDetector::clear_encoder_cache(&info.ffmpeg_path);
+                self.ffmpeg_status = Some(Ok(format!("FFmpeg {}", info.version)));
+                self.app_state.set_ffmpeg(info, cx);
+                true
+            }
+            Ok(info) => {
+                log::warn!("FFmpeg {} found but version 7.0+ required", info.version);
+                self.ffmpeg_status = Some(Err(format!(
+                    "FFmpeg {} は古すぎます（7.0以上が必要です）",
+                    info.version
+                )));
+                false
+            }
+            Err(e) => {
+                log::warn!("FFmpeg check failed: {:#}", e);
+                self.ffmpeg_status = Some(Err(format!("{:#}", e)));
+                false
+            }
+        }
+    }
+
+    /// 現在の設定を新しいプリセットとして保存
+    fn save_current_preset(&mut self, cx: &mut Context<Self>) {
+        let settings = self.app_state.transcode_settings.read(cx).clone();
+        let name = self.preset_store.next_preset_name();
+        match self.preset_store.save_preset(&name, &settings) {
+            Ok(()) => self.active_preset = Some(name),
+            Err(e) => log::warn!("Failed to save preset: {}", e),
+        }
+        cx.notify();
+    }
+
+    /// ユーザー定義プリセットを適用
+    fn apply_preset(&mut self, name: &str, cx: &mut Context<Self>) {
+        let Some(preset) = self.preset_store.load_preset(name) else {
+            return;
+        };
+        self.replace_settings(preset, cx);
+        self.active_preset = Some(name.to_string());
+    }
+
+    /// 組み込みプリセットを適用
+    fn apply_factory_preset(&mut self, preset: FactoryPreset, cx: &mut Context<Self>) {
+        let settings = match preset {
+            FactoryPreset::Fast1080p => TranscodeSettings::preset_fast_1080p(),
+            FactoryPreset::HqArchiveAv1 => TranscodeSettings::preset_hq_archive_av1(),
+            FactoryPreset::Anime => TranscodeSettings::preset_anime(),
+            FactoryPreset::Web8Mb => {
+                // キュー内で最も長い動画でも上限に収まるようにする
+                let longest = self
+                    .app_state
+                    .files
+                    .read(cx)
+                    .iter()
+                    .filter_map(|f| f.metadata.duration)
+                    .reduce(f64::max);
+                TranscodeSettings::preset_web_8mb(longest)
+            }
+        };
+        self.replace_settings(settings, cx);
+        self.active_preset = None;
+    }
+
+    /// エンコード設定を置き換え（出力先とサフィックスは現在の値を維持）
+    fn replace_settings(&mut self, preset: TranscodeSettings, cx: &mut Context<Self>) {
+        self.app_state.transcode_settings.update(cx, |settings, _| {
+            *settings = TranscodeSettings {
+                output_dir: settings.output_dir.clone(),
+                output_suffix: settings.output_suffix.clone(),
+                ..preset
+            };
+        });
+        // 入力欄はプリセットの値で作り直す
+        self.target_size_input = None;
+        Self::update_estimated_sizes(&self.app_state, cx);
+        self.resolve_av1_encoder(cx);
+        cx.notify();
+    }
+
+    /// HandBrakeのプリセットファイルを選択して適用
+    fn import_handbrake_file(&mut self, cx: &mut Context<Self>) {
+        cx.spawn(async move |this, cx| {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_title("HandBrakeプリセットを選択")
+                .add_filter("JSON", &["json"])
+                .pick_file()
+                .await
+            else {
+                return;
+            };
+            let path = file.path().to_path_buf();
+            let result = smol::unblock(move || {
+                use anyhow::Context as _;
+                let json = std::fs::read_to_string(&path)
+                    .with_context(|| format!("{}を読み込めません", path.display()))?;
+                read_handbrake_preset(&json)
+            })
+            .await;
+            this.update(cx, |this, cx| {
+                match result {
+                    Ok(import) => {
+                        log::info!(
+                            "Imported HandBrake preset {:?} ({} mapped, {} ignored)",
+                            import.name,
+                            import.mapped.len(),
+                            import.ignored.len()
+                        );
+                        this.replace_settings(import.settings.clone(), cx);
+                        this.active_preset = None;
+                        this.handbrake_import = Some(Ok(import));
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to import HandBrake preset: {:#}", e);
+                        this.handbrake_import = Some(Err(format!("{:#}", e)));
+                    }
+                }
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// 選択中のプリセットを削除
+    fn delete_active_preset(&mut self, cx: &mut Context<Self>) {
+        let Some(name) = self.active_preset.take() else {
+            return;
+        };
+        if let Err(e) = self.preset_store.delete_preset(&name) {
+            log::warn!("Failed to delete preset: {}", e);
+        }
+        cx.notify();
     }
 
     /// すべてのファイルの予測サイズを更新
     fn update_estimated_sizes(app_state: &AppState, cx: &mut Context<Self>) {
         let settings = app_state.transcode_settings.read(cx).clone();
-        app_state.files.update(cx, |files, _| {
+        let calibration = app_state.calibration.read(cx).clone();
+        app_state.update_files(cx, |files, _| {
             for file in files.iter_mut() {
-                file.update_estimated_size(&settings);
+                file.update_estimated_size(&settings, &calibration);
             }
         });
     }
 
-    /// コンテナ形式ボタンをレンダリング
-    fn render_container_select(
-        &self,
-        current: ContainerFormat,
-        cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let app_state = self.app_state.clone();
-        let options = [(ContainerFormat::Mp4, "MP4"), (ContainerFormat::Mkv, "MKV")];
+    /// アプリケーション設定を更新して保存
+    pub(crate) fn update_app_settings(
+        app_state: &AppState,
+        cx: &mut App,
+        f: impl FnOnce(&mut Settings),
+    ) {
+        app_state.settings.update(cx, |settings, _| {
+            f(settings);
+            if let Err(e) = settings.save() {
+                log::warn!("Failed to save settings: {}", e);
+            }
+        });
+    }
+
+    /// プリセット選択行をレンダリング
+    fn render_preset_row(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let names: Vec<String> = self
+            .preset_store
+            .list_presets()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let has_active = self.active_preset.is_some();
 
         div()
             .w_full()
@@ -50,21 +490,54 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child("コンテナ形式"),
+                    .child(self.highlighted_text("プリセット")),
             )
+            // 組み込みプリセット（枠線付きでユーザー定義と区別）
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                FactoryPreset::all().iter().map(|&preset| {
+                    div()
+                        .id(SharedString::from(format!(
+                            "factory-preset-{}",
+                            preset.display_name()
+                        )))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .border_1()
+                        .border_color(rgb(0x45475a))
+                        .text_color(rgb(0xa6adc8))
+                        .hover(|s| s.bg(rgb(0x313244)))
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _, _, cx| {
+                                this.apply_factory_preset(preset, cx);
+                            }),
+                        )
+                        .child(preset.display_name())
+                }),
+            ))
             .child(
                 div()
                     .w_full()
                     .flex()
                     .flex_wrap()
                     .gap(px(4.0))
-                    .children(options.iter().map(|(value, name)| {
-                        let is_selected = *value == current;
-                        let value_clone = *value;
-                        let app_state_clone = app_state.clone();
+                    .when(names.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .py(px(4.0))
+                                .text_xs()
+                                .text_color(rgb(0x6c7086))
+                                .child("保存されたプリセットはありません"),
+                        )
+                    })
+                    .children(names.into_iter().enumerate().map(|(index, name)| {
+                        let is_selected = self.active_preset.as_deref() == Some(name.as_str());
 
                         div()
-                            .id(SharedString::from(format!("container-{}", name)))
+                            .id(SharedString::from(format!("preset-{}", index)))
                             .px(px(8.0))
                             .py(px(4.0))
                             .rounded(px(4.0))
@@ -81,37 +554,120 @@ impl SettingsPanel {
                                 rgb(0xcdd6f4)
                             })
                             .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
-                            .on_mouse_down(
-                                MouseButton::Left,
-                                cx.listener(move |_this, _, _, cx| {
-                                    app_state_clone
-                                        .transcode_settings
-                                        .update(cx, |settings, _| {
-                                            settings.container = value_clone;
-                                        });
-                                    // 予測サイズを更新
-                                    Self::update_estimated_sizes(&app_state_clone, cx);
-                                    cx.notify();
-                                }),
-                            )
-                            .child(name.to_string())
+                            .on_mouse_down(MouseButton::Left, {
+                                let name = name.clone();
+                                cx.listener(move |this, _, _, cx| {
+                                    this.apply_preset(&name, cx);
+                                })
+                            })
+                            .child(name)
                     })),
             )
+            .child(
+                div()
+                    .flex()
+                    .gap(px(8.0))
+                    .child(
+                        Button::new("save-preset")
+                            .label("現在の設定を保存")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.save_current_preset(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("delete-preset")
+                            .label("削除")
+                            .with_variant(ButtonVariant::Ghost)
+                            .disabled(!has_active)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.delete_active_preset(cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("import-handbrake-preset")
+                            .label("HandBrakeプリセットをインポート")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.import_handbrake_file(cx);
+                            })),
+                    ),
+            )
+            .when_some(self.handbrake_import.as_ref(), |this, result| {
+                this.child(Self::render_handbrake_import(result, cx))
+            })
     }
 
-    /// ビデオコーデックボタンをレンダリング
-    fn render_video_codec_select(
+    /// HandBrakeプリセットの読み込み結果（反映した項目と無視した項目）をレンダリング
+    fn render_handbrake_import(
+        result: &Result<HandBrakeImport, String>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let item =
+            |text: &String, color: u32| div().text_xs().text_color(rgb(color)).child(text.clone());
+
+        div()
+            .w_full()
+            .p(px(8.0))
+            .rounded(px(4.0))
+            .bg(rgb(0x181825))
+            .flex()
+            .flex_col()
+            .gap(px(2.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0xa6adc8))
+                            .child(match result {
+                                Ok(import) => format!(
+                                    "「{}」を適用しました（反映 {}件 / 無視 {}件）",
+                                    import.name,
+                                    import.mapped.len(),
+                                    import.ignored.len()
+                                ),
+                                Err(_) => "HandBrakeプリセットを読み込めません".to_string(),
+                            }),
+                    )
+                    .child(
+                        Button::new("dismiss-handbrake-import")
+                            .label("閉じる")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.handbrake_import = None;
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .map(|this| match result {
+                Ok(import) => this
+                    .children(import.mapped.iter().map(|text| item(text, 0xa6e3a1)))
+                    .when(!import.ignored.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .pt(px(4.0))
+                                .text_xs()
+                                .text_color(rgb(0xf9e2af))
+                                .child("反映できなかった項目"),
+                        )
+                        .children(import.ignored.iter().map(|text| item(text, 0xf9e2af)))
+                    }),
+                Err(message) => this.child(item(message, 0xf38ba8)),
+            })
+    }
+
+    /// コンテナ形式ボタンをレンダリング
+    fn render_container_select(
         &self,
-        current: VideoCodec,
+        current: ContainerFormat,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
         let app_state = self.app_state.clone();
-        let options = [
-            (VideoCodec::H264, "H.264"),
-            (VideoCodec::H265, "H.265"),
-            (VideoCodec::Vp9, "VP9"),
-            (VideoCodec::Av1, "AV1"),
-        ];
+        let options = [(ContainerFormat::Mp4, "MP4"), (ContainerFormat::Mkv, "MKV")];
 
         div()
             .w_full()
@@ -122,7 +678,7 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child("ビデオコーデック"),
+                    .child(self.highlighted_text("コンテナ形式")),
             )
             .child(
                 div()
@@ -136,7 +692,7 @@ impl SettingsPanel {
                         let app_state_clone = app_state.clone();
 
                         div()
-                            .id(SharedString::from(format!("video-codec-{}", name)))
+                            .id(SharedString::from(format!("container-{}", name)))
                             .px(px(8.0))
                             .py(px(4.0))
                             .rounded(px(4.0))
@@ -159,7 +715,7 @@ impl SettingsPanel {
                                     app_state_clone
                                         .transcode_settings
                                         .update(cx, |settings, _| {
-                                            settings.video_codec = value_clone;
+                                            settings.container = value_clone;
                                         });
                                     // 予測サイズを更新
                                     Self::update_estimated_sizes(&app_state_clone, cx);
@@ -171,19 +727,19 @@ impl SettingsPanel {
             )
     }
 
-    /// 解像度ボタンをレンダリング
-    fn render_resolution_select(
+    /// ビデオコーデックボタンをレンダリング
+    fn render_video_codec_select(
         &self,
-        current: VideoResolution,
+        current: VideoCodec,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
         let app_state = self.app_state.clone();
         let options = [
-            (VideoResolution::Original, "元の解像度"),
-            (VideoResolution::Uhd4K, "4K"),
-            (VideoResolution::Fhd1080, "1080p"),
-            (VideoResolution::Hd720, "720p"),
-            (VideoResolution::Sd480, "480p"),
+            (VideoCodec::H264, "H.264"),
+            (VideoCodec::H265, "H.265"),
+            (VideoCodec::Vp9, "VP9"),
+            (VideoCodec::Av1, "AV1"),
+            (VideoCodec::Copy, "コピー"),
         ];
 
         div()
@@ -191,7 +747,12 @@ impl SettingsPanel {
             .flex()
             .flex_col()
             .gap(px(4.0))
-            .child(div().text_xs().text_color(rgb(0x6c7086)).child("解像度"))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("ビデオコーデック")),
+            )
             .child(
                 div()
                     .w_full()
@@ -204,7 +765,7 @@ impl SettingsPanel {
                         let app_state_clone = app_state.clone();
 
                         div()
-                            .id(SharedString::from(format!("resolution-{}", name)))
+                            .id(SharedString::from(format!("video-codec-{}", name)))
                             .px(px(8.0))
                             .py(px(4.0))
                             .rounded(px(4.0))
@@ -223,36 +784,48 @@ impl SettingsPanel {
                             .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
                             .on_mouse_down(
                                 MouseButton::Left,
-                                cx.listener(move |_this, _, _, cx| {
+                                cx.listener(move |this, _, _, cx| {
                                     app_state_clone
                                         .transcode_settings
                                         .update(cx, |settings, _| {
-                                            settings.resolution = value_clone;
+                                            settings.video_codec = value_clone;
                                         });
                                     // 予測サイズを更新
                                     Self::update_estimated_sizes(&app_state_clone, cx);
+                                    this.resolve_av1_encoder(cx);
                                     cx.notify();
                                 }),
                             )
                             .child(name.to_string())
                     })),
             )
+            .when(current == VideoCodec::Copy, |this| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0x6c7086))
+                        .child("再エンコードしないため、映像の設定は適用されません"),
+                )
+            })
     }
 
-    /// プリセットボタンをレンダリング
-    fn render_preset_select(
+    /// 解像度ボタンをレンダリング
+    fn render_resolution_select(
         &self,
-        current: VideoPreset,
+        current: VideoResolution,
+        inputs: (Entity<InputState>, Entity<InputState>),
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
         let app_state = self.app_state.clone();
         let options = [
-            (VideoPreset::Ultrafast, "最速"),
-            (VideoPreset::Fast, "高速"),
-            (VideoPreset::Medium, "標準"),
-            (VideoPreset::Slow, "高品質"),
-            (VideoPreset::Veryslow, "最高品質"),
+            (VideoResolution::Original, "元の解像度"),
+            (VideoResolution::Uhd4K, "4K"),
+            (VideoResolution::Fhd1080, "1080p"),
+            (VideoResolution::Hd720, "720p"),
+            (VideoResolution::Sd480, "480p"),
         ];
+        let is_custom = matches!(current, VideoResolution::Custom(..));
+        let (width_input, height_input) = inputs;
 
         div()
             .w_full()
@@ -263,7 +836,7 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child("プリセット"),
+                    .child(self.highlighted_text("解像度")),
             )
             .child(
                 div()
@@ -277,7 +850,7 @@ impl SettingsPanel {
                         let app_state_clone = app_state.clone();
 
                         div()
-                            .id(SharedString::from(format!("preset-{}", name)))
+                            .id(SharedString::from(format!("resolution-{}", name)))
                             .px(px(8.0))
                             .py(px(4.0))
                             .rounded(px(4.0))
@@ -300,7 +873,7 @@ impl SettingsPanel {
                                     app_state_clone
                                         .transcode_settings
                                         .update(cx, |settings, _| {
-                                            settings.preset = value_clone;
+                                            settings.resolution = value_clone;
                                         });
                                     // 予測サイズを更新
                                     Self::update_estimated_sizes(&app_state_clone, cx);
@@ -308,96 +881,132 @@ impl SettingsPanel {
                                 }),
                             )
                             .child(name.to_string())
-                    })),
-            )
-    }
-
-    /// HWアクセラレーションボタンをレンダリング
-    fn render_hwaccel_select(
-        &self,
-        current: HwAccelType,
-        cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let app_state = self.app_state.clone();
-        let options = [
-            (HwAccelType::Auto, "自動"),
-            (HwAccelType::Nvenc, "NVIDIA"),
-            (HwAccelType::Qsv, "Intel"),
-            (HwAccelType::Amf, "AMD"),
-            (HwAccelType::Software, "ソフトウェア"),
-        ];
-
-        div()
-            .w_full()
-            .flex()
-            .flex_col()
-            .gap(px(4.0))
-            .child(
-                div()
-                    .text_xs()
-                    .text_color(rgb(0x6c7086))
-                    .child("HWアクセラレーション"),
-            )
-            .child(
-                div()
-                    .w_full()
-                    .flex()
-                    .flex_wrap()
-                    .gap(px(4.0))
-                    .children(options.iter().map(|(value, name)| {
-                        let is_selected = *value == current;
-                        let value_clone = *value;
+                    }))
+                    // カスタム（幅・高さを入力）
+                    .child({
                         let app_state_clone = app_state.clone();
+                        let width_input = width_input.clone();
+                        let height_input = height_input.clone();
 
                         div()
-                            .id(SharedString::from(format!("hwaccel-{}", name)))
+                            .id("resolution-custom")
                             .px(px(8.0))
                             .py(px(4.0))
                             .rounded(px(4.0))
                             .text_xs()
                             .cursor_pointer()
-                            .bg(if is_selected {
+                            .bg(if is_custom {
                                 rgb(0x89b4fa)
                             } else {
                                 rgb(0x313244)
                             })
-                            .text_color(if is_selected {
+                            .text_color(if is_custom {
                                 rgb(0x1e1e2e)
                             } else {
                                 rgb(0xcdd6f4)
                             })
-                            .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                            .hover(|s| if is_custom { s } else { s.bg(rgb(0x45475a)) })
                             .on_mouse_down(
                                 MouseButton::Left,
-                                cx.listener(move |_this, _, _, cx| {
+                                cx.listener(move |_this, _, window, cx| {
+                                    // 未入力なら720pの枠から始める
+                                    let mut size =
+                                        parse_custom_resolution(&width_input, &height_input, cx);
+                                    if size == (0, 0) {
+                                        size = VideoResolution::Hd720.dimensions();
+                                        width_input.update(cx, |input, cx| {
+                                            input.set_value(size.0.to_string(), window, cx)
+                                        });
+                                        height_input.update(cx, |input, cx| {
+                                            input.set_value(size.1.to_string(), window, cx)
+                                        });
+                                    }
                                     app_state_clone
                                         .transcode_settings
                                         .update(cx, |settings, _| {
-                                            settings.hwaccel = value_clone;
+                                            settings.resolution =
+                                                VideoResolution::Custom(size.0, size.1);
                                         });
-                                    // 予測サイズを更新
                                     Self::update_estimated_sizes(&app_state_clone, cx);
                                     cx.notify();
                                 }),
                             )
-                            .child(name.to_string())
-                    })),
+                            .child("カスタム")
+                    }),
             )
+            .when(is_custom, |this| {
+                this.child(
+                    div()
+                        .w_full()
+                        .flex()
+                        .items_center()
+                        .gap(px(4.0))
+                        .child(div().w(px(72.0)).child(Input::new(&width_input)))
+                        .child(div().text_xs().text_color(rgb(0x6c7086)).child("×"))
+                        .child(div().w(px(72.0)).child(Input::new(&height_input))),
+                )
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0x6c7086))
+                        .child("比率を保って縮小し、余白で指定サイズに合わせます（片方が空欄なら比率から自動）"),
+                )
+            })
     }
 
-    /// オーディオコーデックボタンをレンダリング
-    fn render_audio_codec_select(
+    /// カスタム解像度の入力欄を取得（初回は現在の設定値で作成）
+    fn resolution_inputs(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> (Entity<InputState>, Entity<InputState>) {
+        if let Some(inputs) = &self.resolution_inputs {
+            return inputs.clone();
+        }
+
+        let (width, height) = match self.app_state.transcode_settings.read(cx).resolution {
+            VideoResolution::Custom(w, h) => (w, h),
+            _ => (0, 0),
+        };
+        let value = |v: u32| if v == 0 { String::new() } else { v.to_string() };
+        let width_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("幅")
+                .default_value(value(width))
+        });
+        let height_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("高さ")
+                .default_value(value(height))
+        });
+
+        // 入力のたびにカスタム解像度を更新
+        for input in [&width_input, &height_input] {
+            let (width_input, height_input) = (width_input.clone(), height_input.clone());
+            cx.subscribe_in(input, window, move |this, _, event: &InputEvent, _, cx| {
+                if let InputEvent::Change = event {
+                    let (width, height) = parse_custom_resolution(&width_input, &height_input, cx);
+                    this.app_state.transcode_settings.update(cx, |settings, _| {
+                        settings.resolution = VideoResolution::Custom(width, height);
+                    });
+                    Self::update_estimated_sizes(&this.app_state, cx);
+                    cx.notify();
+                }
+            })
+            .detach();
+        }
+
+        self.resolution_inputs = Some((width_input.clone(), height_input.clone()));
+        (width_input, height_input)
+    }
+
+    /// フレームレートボタンをレンダリング
+    fn render_frame_rate_select(
         &self,
-        current: AudioCodec,
+        current: FrameRate,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
         let app_state = self.app_state.clone();
-        let options = [
-            (AudioCodec::Aac, "AAC"),
-            (AudioCodec::Mp3, "MP3"),
-            (AudioCodec::Flac, "FLAC"),
-            (AudioCodec::Copy, "コピー"),
-        ];
 
         div()
             .w_full()
@@ -408,11 +1017,781 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child("オーディオコーデック"),
+                    .child(self.highlighted_text("フレームレート")),
             )
-            .child(
-                div()
-                    .w_full()
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                FrameRate::all().iter().map(|value| {
+                    let is_selected = *value == current;
+                    let value_clone = *value;
+                    let app_state_clone = app_state.clone();
+                    let name = value.display_name();
+
+                    div()
+                        .id(SharedString::from(format!("frame-rate-{}", name)))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                app_state_clone
+                                    .transcode_settings
+                                    .update(cx, |settings, _| {
+                                        settings.frame_rate = value_clone;
+                                    });
+                                // 予測サイズを更新
+                                Self::update_estimated_sizes(&app_state_clone, cx);
+                                cx.notify();
+                            }),
+                        )
+                        .child(name)
+                }),
+            ))
+    }
+
+    /// フレームレート変換方式ボタンをレンダリング
+    fn render_fps_filter_mode_select(
+        &self,
+        current: FpsFilterMode,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("フレームレート変換方式")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                FpsFilterMode::all().iter().map(|value| {
+                    let is_selected = *value == current;
+                    let value_clone = *value;
+                    let app_state_clone = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!(
+                            "fps-filter-mode-{}",
+                            value.display_name()
+                        )))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                app_state_clone
+                                    .transcode_settings
+                                    .update(cx, |settings, _| {
+                                        settings.fps_filter_mode = value_clone;
+                                    });
+                                cx.notify();
+                            }),
+                        )
+                        .child(value.display_name())
+                }),
+            ))
+            .when(current == FpsFilterMode::Minterpolate, |this| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0xf9e2af))
+                        .child("動き補間はエンコードに非常に時間がかかります"),
+                )
+            })
+    }
+
+    /// HDR/色空間セクションをレンダリング（HDRのソースがある場合のみ表示）
+    fn render_hdr_settings(
+        &self,
+        settings: &TranscodeSettings,
+        is_x265: bool,
+        inputs: (Entity<InputState>, Entity<InputState>),
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let current = settings.tone_mapping_mode;
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(rgb(0xa6adc8))
+                            .child(self.highlighted_text("HDR/色空間")),
+                    )
+                    .child(div().w_full().h(px(1.0)).bg(rgb(0x313244))),
+            )
+            .child(self.render_encoder_toggle(
+                "hdr-to-sdr",
+                "HDRをSDRに変換（トーンマッピング）",
+                settings.hdr_to_sdr,
+                |settings, value| settings.hdr_to_sdr = value,
+                false,
+                cx,
+            ))
+            .when(settings.hdr_to_sdr, |this| {
+                this.child(
+                    div()
+                        .w_full()
+                        .flex()
+                        .flex_col()
+                        .gap(px(4.0))
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0x6c7086))
+                                .child(self.highlighted_text("トーンマッピング")),
+                        )
+                        .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                            HdrToneMappingMode::all().iter().map(|value| {
+                                let is_selected = *value == current;
+                                let value_clone = *value;
+                                let app_state_clone = app_state.clone();
+
+                                div()
+                                    .id(SharedString::from(format!(
+                                        "tone-mapping-{}",
+                                        value.display_name()
+                                    )))
+                                    .px(px(8.0))
+                                    .py(px(4.0))
+                                    .rounded(px(4.0))
+                                    .text_xs()
+                                    .cursor_pointer()
+                                    .bg(if is_selected {
+                                        rgb(0x89b4fa)
+                                    } else {
+                                        rgb(0x313244)
+                                    })
+                                    .text_color(if is_selected {
+                                        rgb(0x1e1e2e)
+                                    } else {
+                                        rgb(0xcdd6f4)
+                                    })
+                                    .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(move |_this, _, _, cx| {
+                                            app_state_clone.transcode_settings.update(
+                                                cx,
+                                                |settings, _| {
+                                                    settings.tone_mapping_mode = value_clone;
+                                                },
+                                            );
+                                            cx.notify();
+                                        }),
+                                    )
+                                    .child(value.display_name())
+                            }),
+                        ))
+                        .when(current == HdrToneMappingMode::Bt2390, |this| {
+                            this.child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(0xf9e2af))
+                                    .child("BT.2390はlibplacebo対応のFFmpegが必要です"),
+                            )
+                        }),
+                )
+            })
+            // x265のHDR10メタデータ（空欄なら元の動画から引き継ぐ）
+            .when(is_x265 && !settings.hdr_to_sdr, |this| {
+                let (master_display_input, max_cll_input) = inputs;
+                this.child(
+                    div()
+                        .w_full()
+                        .flex()
+                        .flex_col()
+                        .gap(px(4.0))
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0x6c7086))
+                                .child(self.highlighted_text("マスタリングディスプレイ")),
+                        )
+                        .child(Input::new(&master_display_input)),
+                )
+                .child(
+                    div()
+                        .w_full()
+                        .flex()
+                        .flex_col()
+                        .gap(px(4.0))
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0x6c7086))
+                                .child(self.highlighted_text("MaxCLL/MaxFALL")),
+                        )
+                        .child(Input::new(&max_cll_input)),
+                )
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0x6c7086))
+                        .child("空欄の場合は元の動画のHDR10メタデータを引き継ぎます"),
+                )
+            })
+    }
+
+    /// x265のHDR10メタデータの入力欄を取得（初回は現在の設定値で作成）
+    fn hdr_inputs(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> (Entity<InputState>, Entity<InputState>) {
+        if let Some(inputs) = &self.hdr_inputs {
+            return inputs.clone();
+        }
+
+        let settings = self.app_state.transcode_settings.read(cx);
+        let master_display = settings.x265_master_display.clone().unwrap_or_default();
+        let max_cll = settings
+            .x265_max_cll
+            .map(|(cll, fall)| format!("{},{}", cll, fall))
+            .unwrap_or_default();
+        let master_display_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("G(13250,34500)B(7500,3000)R(34000,16000)WP(15635,16450)L(10000000,1)")
+                .default_value(master_display)
+        });
+        let max_cll_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("1000,400")
+                .default_value(max_cll)
+        });
+
+        // 空欄・不正な値は元の動画から引き継ぐ
+        cx.subscribe_in(
+            &master_display_input,
+            window,
+            |this, input, event: &InputEvent, _, cx| {
+                if let InputEvent::Change = event {
+                    let value = input.read(cx).value().trim().to_string();
+                    this.app_state.transcode_settings.update(cx, |settings, _| {
+                        settings.x265_master_display = (!value.is_empty()).then_some(value);
+                    });
+                    cx.notify();
+                }
+            },
+        )
+        .detach();
+        cx.subscribe_in(
+            &max_cll_input,
+            window,
+            |this, input, event: &InputEvent, _, cx| {
+                if let InputEvent::Change = event {
+                    let value = parse_max_cll(&input.read(cx).value());
+                    this.app_state
+                        .transcode_settings
+                        .update(cx, |settings, _| settings.x265_max_cll = value);
+                    cx.notify();
+                }
+            },
+        )
+        .detach();
+
+        self.hdr_inputs = Some((master_display_input.clone(), max_cll_input.clone()));
+        (master_display_input, max_cll_input)
+    }
+
+    /// インターレース解除ボタンをレンダリング
+    fn render_deinterlace_select(
+        &self,
+        current: Deinterlace,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("インターレース解除")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                Deinterlace::all().iter().map(|value| {
+                    let is_selected = *value == current;
+                    let value_clone = *value;
+                    let app_state_clone = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!(
+                            "deinterlace-{}",
+                            value.display_name()
+                        )))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                app_state_clone
+                                    .transcode_settings
+                                    .update(cx, |settings, _| {
+                                        settings.deinterlace = value_clone;
+                                    });
+                                cx.notify();
+                            }),
+                        )
+                        .child(value.display_name())
+                }),
+            ))
+    }
+
+    /// 出力ピクセルフォーマットボタンをレンダリング
+    /// 現在のエンコーダーで出力できないフォーマットは選択不可
+    fn render_pixel_format_select(
+        &self,
+        settings: &TranscodeSettings,
+        hwaccel: HwAccelType,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let current = settings.pixel_format;
+        let encoder = settings.video_codec.encoder_name(&hwaccel);
+        let warning = settings.pixel_format_warning(encoder);
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("ピクセルフォーマット")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                PixelFormat::all().iter().map(|value| {
+                    let is_selected = *value == current;
+                    let is_supported = value.supported_by(encoder);
+                    let value_clone = *value;
+                    let app_state_clone = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!(
+                            "pixel-format-{}",
+                            value.display_name()
+                        )))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .when(!is_supported, |this| this.opacity(0.5))
+                        .when(is_supported, |this| {
+                            this.cursor_pointer()
+                                .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(move |_this, _, _, cx| {
+                                        app_state_clone.transcode_settings.update(
+                                            cx,
+                                            |settings, _| {
+                                                settings.pixel_format = value_clone;
+                                            },
+                                        );
+                                        // 予測サイズを更新
+                                        Self::update_estimated_sizes(&app_state_clone, cx);
+                                        cx.notify();
+                                    }),
+                                )
+                        })
+                        .child(value.display_name())
+                }),
+            ))
+            .when_some(warning, |this, warning| {
+                this.child(div().text_xs().text_color(rgb(0xf9e2af)).child(warning))
+            })
+    }
+
+    /// HWデコードボタンをレンダリング
+    fn render_hw_decode_select(
+        &self,
+        current: HwDecodeMode,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("HWデコード")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                HwDecodeMode::all().iter().map(|value| {
+                    let is_selected = *value == current;
+                    let value_clone = *value;
+                    let app_state_clone = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!(
+                            "hw-decode-{}",
+                            value.display_name()
+                        )))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                app_state_clone
+                                    .transcode_settings
+                                    .update(cx, |settings, _| {
+                                        settings.hw_decode = value_clone;
+                                    });
+                                cx.notify();
+                            }),
+                        )
+                        .child(value.display_name())
+                }),
+            ))
+            .when(current == HwDecodeMode::Auto, |this| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0x6c7086))
+                        .child("GPUのデコーダーが対応しているソースのみHWデコードします"),
+                )
+            })
+    }
+
+    /// クロップ設定をレンダリング
+    /// 手動の場合は各辺を8px単位で増減し、先頭のファイルに対する切り取り範囲を図示する
+    fn render_crop_settings(
+        &self,
+        mode: CropMode,
+        current: CropSettings,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        const STEP: u32 = 8;
+        let app_state = self.app_state.clone();
+        // 図示には先頭のファイルを使う（解像度不明なら1080p）
+        let (source, detected) = self
+            .app_state
+            .files
+            .read(cx)
+            .first()
+            .map(|f| (f.metadata.resolution, f.crop))
+            .unwrap_or_default();
+        let source = source.unwrap_or((1920, 1080));
+        let preview = mode.resolve(detected, current);
+        let sides: [(&str, &str, u32, CropSide); 4] = [
+            ("top", "上", current.top, |c| &mut c.top),
+            ("bottom", "下", current.bottom, |c| &mut c.bottom),
+            ("left", "左", current.left, |c| &mut c.left),
+            ("right", "右", current.right, |c| &mut c.right),
+        ];
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("クロップ")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                CropMode::all().iter().map(|value| {
+                    let is_selected = *value == mode;
+                    let value_clone = *value;
+                    let app_state_clone = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!(
+                            "crop-mode-{}",
+                            value.display_name()
+                        )))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                app_state_clone
+                                    .transcode_settings
+                                    .update(cx, |settings, _| {
+                                        settings.crop_mode = value_clone;
+                                    });
+                                // 自動検出に切り替えたら未検出のファイルを検出
+                                if value_clone == CropMode::AutoDetect {
+                                    let targets = app_state_clone
+                                        .files
+                                        .read(cx)
+                                        .iter()
+                                        .filter(|f| f.crop.is_none())
+                                        .map(|f| f.path.clone())
+                                        .collect();
+                                    app_state_clone.detect_crops(targets, cx);
+                                }
+                                cx.notify();
+                            }),
+                        )
+                        .child(value.display_name())
+                }),
+            ))
+            .when(mode == CropMode::AutoDetect, |this| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0x6c7086))
+                        .child("キューに追加したファイルの黒帯を自動で検出して切り取ります"),
+                )
+            })
+            .when(mode == CropMode::Manual, |this| {
+                this.child(
+                    div()
+                        .w_full()
+                        .flex()
+                        .flex_wrap()
+                        .items_center()
+                        .gap(px(8.0))
+                        .children(sides.into_iter().map(|(key, label, value, side)| {
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(4.0))
+                                .child(div().text_xs().text_color(rgb(0xa6adc8)).child(label))
+                                .child(self.render_crop_step_button(
+                                    SharedString::from(format!("crop-{}-minus", key)),
+                                    "−",
+                                    move |crop| {
+                                        let v = side(crop);
+                                        *v = v.saturating_sub(STEP);
+                                    },
+                                    cx,
+                                ))
+                                .child(div().w(px(32.0)).text_xs().child(value.to_string()))
+                                .child(self.render_crop_step_button(
+                                    SharedString::from(format!("crop-{}-plus", key)),
+                                    "+",
+                                    move |crop| *side(crop) += STEP,
+                                    cx,
+                                ))
+                        }))
+                        .when(!current.is_empty(), |this| {
+                            this.child(self.render_crop_step_button(
+                                "crop-reset".into(),
+                                "リセット",
+                                |crop| *crop = CropSettings::default(),
+                                cx,
+                            ))
+                        }),
+                )
+            })
+            .when(mode != CropMode::Off, |this| {
+                this.child(Self::render_crop_diagram(source, preview))
+            })
+    }
+
+    /// ソース解像度に対するクロップ範囲を図示
+    fn render_crop_diagram(source: (u32, u32), crop: CropSettings) -> impl IntoElement {
+        const WIDTH: f32 = 160.0;
+        let (source_w, source_h) = (source.0.max(1) as f32, source.1.max(1) as f32);
+        let scale = WIDTH / source_w;
+        let kept_w = source_w - (crop.left + crop.right) as f32;
+        let kept_h = source_h - (crop.top + crop.bottom) as f32;
+
+        div()
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .relative()
+                    .w(px(WIDTH))
+                    .h(px(source_h * scale))
+                    .bg(rgb(0x11111b))
+                    .border_1()
+                    .border_color(rgb(0x45475a))
+                    .child(
+                        div()
+                            .absolute()
+                            .left(px(crop.left as f32 * scale))
+                            .top(px(crop.top as f32 * scale))
+                            .w(px(kept_w.max(0.0) * scale))
+                            .h(px(kept_h.max(0.0) * scale))
+                            .bg(rgb(0x313244))
+                            .border_1()
+                            .border_color(rgb(0x89b4fa)),
+                    ),
+            )
+            .child(div().text_xs().text_color(rgb(0xa6adc8)).child(format!(
+                "{}x{} → {}x{}",
+                source.0,
+                source.1,
+                kept_w.max(0.0) as u32,
+                kept_h.max(0.0) as u32
+            )))
+    }
+
+    /// クロップ値を変更するボタンをレンダリング
+    fn render_crop_step_button(
+        &self,
+        id: SharedString,
+        label: &'static str,
+        apply: impl Fn(&mut CropSettings) + 'static,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .id(id)
+            .px(px(8.0))
+            .py(px(2.0))
+            .rounded(px(4.0))
+            .text_xs()
+            .cursor_pointer()
+            .bg(rgb(0x313244))
+            .text_color(rgb(0xcdd6f4))
+            .hover(|s| s.bg(rgb(0x45475a)))
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |_this, _, _, cx| {
+                    app_state.transcode_settings.update(cx, |settings, _| {
+                        apply(&mut settings.crop);
+                    });
+                    cx.notify();
+                }),
+            )
+            .child(label)
+    }
+
+    /// プリセットボタンをレンダリング
+    fn render_preset_select(
+        &self,
+        current: VideoPreset,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let options = [
+            (VideoPreset::Ultrafast, "最速"),
+            (VideoPreset::Fast, "高速"),
+            (VideoPreset::Medium, "標準"),
+            (VideoPreset::Slow, "高品質"),
+            (VideoPreset::Veryslow, "最高品質"),
+        ];
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("プリセット")),
+            )
+            .child(
+                div()
+                    .w_full()
                     .flex()
                     .flex_wrap()
                     .gap(px(4.0))
@@ -422,7 +1801,7 @@ impl SettingsPanel {
                         let app_state_clone = app_state.clone();
 
                         div()
-                            .id(SharedString::from(format!("audio-codec-{}", name)))
+                            .id(SharedString::from(format!("preset-{}", name)))
                             .px(px(8.0))
                             .py(px(4.0))
                             .rounded(px(4.0))
@@ -445,7 +1824,7 @@ impl SettingsPanel {
                                     app_state_clone
                                         .transcode_settings
                                         .update(cx, |settings, _| {
-                                            settings.audio_codec = value_clone;
+                                            settings.preset = value_clone;
                                         });
                                     // 予測サイズを更新
                                     Self::update_estimated_sizes(&app_state_clone, cx);
@@ -457,17 +1836,19 @@ impl SettingsPanel {
             )
     }
 
-    /// CRF選択ボタンをレンダリング
-    fn render_crf_select(&self, current: u8, cx: &mut Context<Self>) -> impl IntoElement {
+    /// HWアクセラレーションボタンをレンダリング
+    fn render_hwaccel_select(
+        &self,
+        current: HwAccelType,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
         let app_state = self.app_state.clone();
-        // CRFの選択肢（数値が低いほど高品質）
         let options = [
-            (18u8, "最高"),
-            (20u8, "高"),
-            (23u8, "標準"),
-            (26u8, "中"),
-            (28u8, "低"),
-            (32u8, "最低"),
+            (HwAccelType::Auto, "自動"),
+            (HwAccelType::Nvenc, "NVIDIA"),
+            (HwAccelType::Qsv, "Intel"),
+            (HwAccelType::Amf, "AMD"),
+            (HwAccelType::Software, "ソフトウェア"),
         ];
 
         div()
@@ -479,7 +1860,7 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child(format!("品質 (CRF: {})", current)),
+                    .child(self.highlighted_text("HWアクセラレーション")),
             )
             .child(
                 div()
@@ -493,7 +1874,7 @@ impl SettingsPanel {
                         let app_state_clone = app_state.clone();
 
                         div()
-                            .id(SharedString::from(format!("crf-{}", value)))
+                            .id(SharedString::from(format!("hwaccel-{}", name)))
                             .px(px(8.0))
                             .py(px(4.0))
                             .rounded(px(4.0))
@@ -512,14 +1893,23 @@ impl SettingsPanel {
                             .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
                             .on_mouse_down(
                                 MouseButton::Left,
-                                cx.listener(move |_this, _, _, cx| {
+                                cx.listener(move |this, _, _, cx| {
                                     app_state_clone
                                         .transcode_settings
                                         .update(cx, |settings, _| {
-                                            settings.crf = value_clone;
+                                            settings.hwaccel = value_clone;
+                                            // QSV専用モードは他のエンコーダーでは同等のモードに戻す
+                                            if !matches!(
+                                                value_clone,
+                                                HwAccelType::Qsv | HwAccelType::Auto
+                                            ) {
+                                                settings.rate_control =
+                                                    settings.rate_control.without_qsv();
+                                            }
                                         });
                                     // 予測サイズを更新
                                     Self::update_estimated_sizes(&app_state_clone, cx);
+                                    this.resolve_av1_encoder(cx);
                                     cx.notify();
                                 }),
                             )
@@ -528,18 +1918,18 @@ impl SettingsPanel {
             )
     }
 
-    /// オーディオビットレートボタンをレンダリング
-    fn render_audio_bitrate_select(
+    /// オーディオコーデックボタンをレンダリング
+    fn render_audio_codec_select(
         &self,
-        current: u32,
+        current: AudioCodec,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
         let app_state = self.app_state.clone();
         let options = [
-            (128u32, "128 kbps"),
-            (192u32, "192 kbps"),
-            (256u32, "256 kbps"),
-            (320u32, "320 kbps"),
+            (AudioCodec::Aac, "AAC"),
+            (AudioCodec::Mp3, "MP3"),
+            (AudioCodec::Flac, "FLAC"),
+            (AudioCodec::Copy, "コピー"),
         ];
 
         div()
@@ -551,7 +1941,7 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child("オーディオビットレート"),
+                    .child(self.highlighted_text("オーディオコーデック")),
             )
             .child(
                 div()
@@ -565,7 +1955,7 @@ impl SettingsPanel {
                         let app_state_clone = app_state.clone();
 
                         div()
-                            .id(SharedString::from(format!("audio-bitrate-{}", value)))
+                            .id(SharedString::from(format!("audio-codec-{}", name)))
                             .px(px(8.0))
                             .py(px(4.0))
                             .rounded(px(4.0))
@@ -588,7 +1978,7 @@ impl SettingsPanel {
                                     app_state_clone
                                         .transcode_settings
                                         .update(cx, |settings, _| {
-                                            settings.audio_bitrate = value_clone;
+                                            settings.audio_codec = value_clone;
                                         });
                                     // 予測サイズを更新
                                     Self::update_estimated_sizes(&app_state_clone, cx);
@@ -600,18 +1990,107 @@ impl SettingsPanel {
             )
     }
 
-    /// レートコントロールモードボタンをレンダリング
-    fn render_rate_control_select(
+    /// CRFのスライダーと選択ボタンをレンダリング
+    fn render_crf_select(
         &self,
-        current: RateControlMode,
+        current: u8,
+        encoder: &str,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
         let app_state = self.app_state.clone();
+        // VP9/AV1はエンコーダーの尺度に換算した値も表示
+        let effective = encoder_crf(current, encoder);
+        let label = if effective == current {
+            format!("品質 (CRF: {})", current)
+        } else {
+            format!("品質 (CRF: {}、{}では{})", current, encoder, effective)
+        };
+        // スライダーはエンコーダーの尺度（VP9/AV1は0-63）で表示
+        self.crf_slider.update(cx, |slider, cx| {
+            slider.set_range(0.0, encoder_crf_max(encoder) as f32, cx);
+            slider.set_value(effective as f32, cx);
+        });
+        // CRFの選択肢（数値が低いほど高品質）
         let options = [
-            (RateControlMode::Crf, "CRF (固定品質)"),
-            (RateControlMode::Cbr, "CBR (固定レート)"),
-            (RateControlMode::Vbr, "VBR (可変レート)"),
-            (RateControlMode::Cqp, "CQP (固定QP)"),
+            (18u8, "最高"),
+            (20u8, "高"),
+            (23u8, "標準"),
+            (26u8, "中"),
+            (28u8, "低"),
+            (32u8, "最低"),
+        ];
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text(label)),
+            )
+            .child(self.crf_slider.clone())
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(4.0))
+                    .children(options.iter().map(|(value, name)| {
+                        let is_selected = *value == current;
+                        let value_clone = *value;
+                        let app_state_clone = app_state.clone();
+
+                        div()
+                            .id(SharedString::from(format!("crf-{}", value)))
+                            .px(px(8.0))
+                            .py(px(4.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .bg(if is_selected {
+                                rgb(0x89b4fa)
+                            } else {
+                                rgb(0x313244)
+                            })
+                            .text_color(if is_selected {
+                                rgb(0x1e1e2e)
+                            } else {
+                                rgb(0xcdd6f4)
+                            })
+                            .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |_this, _, _, cx| {
+                                    app_state_clone
+                                        .transcode_settings
+                                        .update(cx, |settings, _| {
+                                            settings.crf = value_clone;
+                                        });
+                                    // 予測サイズを更新
+                                    Self::update_estimated_sizes(&app_state_clone, cx);
+                                    cx.notify();
+                                }),
+                            )
+                            .child(name.to_string())
+                    })),
+            )
+    }
+
+    /// オーディオビットレートボタンをレンダリング
+    fn render_audio_bitrate_select(
+        &self,
+        current: u32,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let options = [
+            (128u32, "128 kbps"),
+            (192u32, "192 kbps"),
+            (256u32, "256 kbps"),
+            (320u32, "320 kbps"),
         ];
 
         div()
@@ -623,7 +2102,80 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child("レートコントロールモード"),
+                    .child(self.highlighted_text("オーディオビットレート")),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(4.0))
+                    .children(options.iter().map(|(value, name)| {
+                        let is_selected = *value == current;
+                        let value_clone = *value;
+                        let app_state_clone = app_state.clone();
+
+                        div()
+                            .id(SharedString::from(format!("audio-bitrate-{}", value)))
+                            .px(px(8.0))
+                            .py(px(4.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .cursor_pointer()
+                            .bg(if is_selected {
+                                rgb(0x89b4fa)
+                            } else {
+                                rgb(0x313244)
+                            })
+                            .text_color(if is_selected {
+                                rgb(0x1e1e2e)
+                            } else {
+                                rgb(0xcdd6f4)
+                            })
+                            .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |_this, _, _, cx| {
+                                    app_state_clone
+                                        .transcode_settings
+                                        .update(cx, |settings, _| {
+                                            settings.audio_bitrate = value_clone;
+                                        });
+                                    // 予測サイズを更新
+                                    Self::update_estimated_sizes(&app_state_clone, cx);
+                                    cx.notify();
+                                }),
+                            )
+                            .child(name.to_string())
+                    })),
+            )
+    }
+
+    /// レートコントロールモードボタンをレンダリング
+    /// ICQ/AVBRはQSVの場合のみ表示
+    fn render_rate_control_select(
+        &self,
+        current: RateControlMode,
+        hwaccel: HwAccelType,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let options: Vec<_> = RateControlMode::all()
+            .iter()
+            .filter(|mode| !mode.is_qsv_only() || hwaccel == HwAccelType::Qsv)
+            .map(|mode| (*mode, mode.display_name()))
+            .collect();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("レートコントロールモード")),
             )
             .child(
                 div()
@@ -672,6 +2224,95 @@ impl SettingsPanel {
             )
     }
 
+    /// 目標サイズの入力欄と、キューのファイルごとに算出したCRFをレンダリング
+    fn render_target_size_input(
+        &self,
+        settings: &TranscodeSettings,
+        input: Entity<InputState>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let crfs: Vec<u8> = self
+            .app_state
+            .files
+            .read(cx)
+            .iter()
+            .filter(|f| f.size > 0)
+            .map(|f| settings.resolve_target_size(f.size, &f.metadata).crf)
+            .collect();
+        let crf_label = match (crfs.iter().min(), crfs.iter().max()) {
+            (Some(min), Some(max)) if min == max => format!("算出CRF: {}", min),
+            (Some(min), Some(max)) => format!("算出CRF: {}〜{}（ファイルごと）", min, max),
+            _ => "算出CRF: ファイルを追加すると表示されます".to_string(),
+        };
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text(format!(
+                        "目標サイズ: {} MB",
+                        settings.target_size_mb
+                    ))),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(div().w(px(96.0)).child(Input::new(&input)))
+                    .child(div().text_xs().text_color(rgb(0x6c7086)).child("MB"))
+                    .child(div().text_xs().text_color(rgb(0xa6e3a1)).child(crf_label)),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child("予測モデルから目標サイズに近づくCRFを選びます。元より大きい目標は元のサイズに抑えます"),
+            )
+    }
+
+    /// 目標サイズの入力欄を取得（初回のみ作成）
+    fn target_size_input(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Entity<InputState> {
+        if let Some(input) = &self.target_size_input {
+            return input.clone();
+        }
+
+        let target_size_mb = self.app_state.transcode_settings.read(cx).target_size_mb;
+        let input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("MB")
+                .default_value(target_size_mb.to_string())
+        });
+        // 正の整数が入力されたら目標サイズを更新
+        cx.subscribe_in(&input, window, |this, input, event: &InputEvent, _, cx| {
+            if let InputEvent::Change = event {
+                let Ok(value) = input.read(cx).value().trim().parse::<u32>() else {
+                    return;
+                };
+                if value == 0 {
+                    return;
+                }
+                this.app_state.transcode_settings.update(cx, |settings, _| {
+                    settings.target_size_mb = value;
+                });
+                Self::update_estimated_sizes(&this.app_state, cx);
+                cx.notify();
+            }
+        })
+        .detach();
+        self.target_size_input = Some(input.clone());
+        input
+    }
+
     /// ビットレート選択ボタンをレンダリング
     fn render_bitrate_select(
         &self,
@@ -701,7 +2342,7 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child(format!("{} ({} kbps)", label, current)),
+                    .child(self.highlighted_text(format!("{} ({} kbps)", label, current))),
             )
             .child(
                 div()
@@ -781,7 +2422,7 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child(format!("Bフレーム数: {}", current)),
+                    .child(self.highlighted_text(format!("Bフレーム数: {}", current))),
             )
             .child(
                 div()
@@ -851,7 +2492,7 @@ impl SettingsPanel {
                 div()
                     .text_xs()
                     .text_color(rgb(0x6c7086))
-                    .child(format!("参照フレーム数: {}", current)),
+                    .child(self.highlighted_text(format!("参照フレーム数: {}", current))),
             )
             .child(
                 div()
@@ -917,10 +2558,9 @@ impl SettingsPanel {
             .flex_col()
             .gap(px(4.0))
             .child(
-                div()
-                    .text_xs()
-                    .text_color(rgb(0x6c7086))
-                    .child(format!("GOPサイズ (キーフレーム間隔): {}", current)),
+                div().text_xs().text_color(rgb(0x6c7086)).child(
+                    self.highlighted_text(format!("GOPサイズ (キーフレーム間隔): {}", current)),
+                ),
             )
             .child(
                 div()
@@ -985,12 +2625,9 @@ impl SettingsPanel {
             .flex()
             .flex_col()
             .gap(px(4.0))
-            .child(
-                div()
-                    .text_xs()
-                    .text_color(rgb(0x6c7086))
-                    .child(format!("ルックアヘッド (先行読み込み): {}", current)),
-            )
+            .child(div().text_xs().text_color(rgb(0x6c7086)).child(
+                self.highlighted_text(format!("ルックアヘッド (先行読み込み): {}", current)),
+            ))
             .child(
                 div()
                     .w_full()
@@ -1044,6 +2681,24 @@ impl SettingsPanel {
         settings: &TranscodeSettings,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
+        const AQ_STRENGTH_OPTIONS: [(u8, &str); 15] = [
+            (1, "1"),
+            (2, "2"),
+            (3, "3"),
+            (4, "4"),
+            (5, "5"),
+            (6, "6"),
+            (7, "7"),
+            (8, "8"),
+            (9, "9"),
+            (10, "10"),
+            (11, "11"),
+            (12, "12"),
+            (13, "13"),
+            (14, "14"),
+            (15, "15"),
+        ];
+
         let app_state = self.app_state.clone();
 
         div()
@@ -1056,7 +2711,7 @@ impl SettingsPanel {
                     .text_xs()
                     .font_weight(FontWeight::MEDIUM)
                     .text_color(rgb(0xa6adc8))
-                    .child("NVENC設定"),
+                    .child(self.highlighted_text("NVENC設定")),
             )
             // プリセット (Tune)
             .child(
@@ -1069,7 +2724,7 @@ impl SettingsPanel {
                         div()
                             .text_xs()
                             .text_color(rgb(0x6c7086))
-                            .child("チューニング"),
+                            .child(self.highlighted_text("チューニング")),
                     )
                     .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
                         NvencTune::all().iter().map(|value| {
@@ -1117,7 +2772,7 @@ impl SettingsPanel {
                         div()
                             .text_xs()
                             .text_color(rgb(0x6c7086))
-                            .child("マルチパス"),
+                            .child(self.highlighted_text("マルチパス")),
                     )
                     .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
                         NvencMultipass::all().iter().map(|value| {
@@ -1154,53 +2809,26 @@ impl SettingsPanel {
                         }),
                     )),
             )
-    }
-
-    /// QSV設定をレンダリング
-    fn render_qsv_settings(
-        &self,
-        _settings: &TranscodeSettings,
-        _cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        // QSV設定の実装（現在はプレースホルダー）
-        div().w_full().child(
-            div()
-                .text_xs()
-                .text_color(rgb(0x6c7086))
-                .child("QSV設定 (標準設定を使用)"),
-        )
-    }
-
-    /// AMF設定をレンダリング
-    fn render_amf_settings(
-        &self,
-        settings: &TranscodeSettings,
-        cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let app_state = self.app_state.clone();
-
-        div()
-            .w_full()
-            .flex()
-            .flex_col()
-            .gap(px(8.0))
-            .child(
-                div()
-                    .text_xs()
-                    .font_weight(FontWeight::MEDIUM)
-                    .text_color(rgb(0xa6adc8))
-                    .child("AMF設定"),
-            )
+            // B参照モード（Bフレーム使用時のみ有効）
             .child(
                 div()
                     .w_full()
                     .flex()
                     .flex_col()
                     .gap(px(4.0))
-                    .child(div().text_xs().text_color(rgb(0x6c7086)).child("使用法"))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0x6c7086))
+                            .child(self.highlighted_text(if settings.bframes > 0 {
+                                "B参照モード"
+                            } else {
+                                "B参照モード (Bフレーム数が0のため無効)"
+                            })),
+                    )
                     .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
-                        AmfUsage::all().iter().map(|value| {
-                            let is_selected = *value == settings.amf_usage;
+                        NvencBRefMode::all().iter().map(|value| {
+                            let is_selected = *value == settings.nvenc_b_ref_mode;
                             let value_clone = *value;
                             let app_state_clone = app_state.clone();
 
@@ -1224,7 +2852,7 @@ impl SettingsPanel {
                                     MouseButton::Left,
                                     cx.listener(move |_this, _, _, cx| {
                                         app_state_clone.transcode_settings.update(cx, |s, _| {
-                                            s.amf_usage = value_clone;
+                                            s.nvenc_b_ref_mode = value_clone;
                                         });
                                         cx.notify();
                                     }),
@@ -1233,10 +2861,56 @@ impl SettingsPanel {
                         }),
                     )),
             )
+            // ルックアヘッド（共通設定とは別にNVENC用の値を使う）
+            .child(self.render_encoder_select(
+                "nvenc-rc-lookahead",
+                "ルックアヘッド (NVENC)",
+                &[(0, "オフ"), (8, "8"), (16, "16"), (20, "20"), (32, "32")],
+                settings.nvenc_rc_lookahead,
+                |s, value| s.nvenc_rc_lookahead = value,
+                false,
+                cx,
+            ))
+            // 空間AQ/時間AQ
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(12.0))
+                    .child(self.render_encoder_toggle(
+                        "nvenc-spatial-aq",
+                        "空間AQ",
+                        settings.nvenc_spatial_aq,
+                        |s, value| s.nvenc_spatial_aq = value,
+                        false,
+                        cx,
+                    ))
+                    .child(self.render_encoder_toggle(
+                        "nvenc-temporal-aq",
+                        "時間AQ",
+                        settings.nvenc_temporal_aq,
+                        |s, value| s.nvenc_temporal_aq = value,
+                        false,
+                        cx,
+                    )),
+            )
+            // AQ強度（空間AQ有効時のみ）
+            .when(settings.nvenc_spatial_aq, |this| {
+                this.child(self.render_encoder_select(
+                    "nvenc-aq-strength",
+                    "AQ強度",
+                    &AQ_STRENGTH_OPTIONS,
+                    settings.nvenc_aq_strength,
+                    |s, value| s.nvenc_aq_strength = value,
+                    false,
+                    cx,
+                ))
+            })
     }
 
-    /// ソフトウェアエンコード設定をレンダリング
-    fn render_software_settings(
+    /// QSV設定をレンダリング
+    fn render_qsv_settings(
         &self,
         settings: &TranscodeSettings,
         cx: &mut Context<Self>,
@@ -1253,8 +2927,9 @@ impl SettingsPanel {
                     .text_xs()
                     .font_weight(FontWeight::MEDIUM)
                     .text_color(rgb(0xa6adc8))
-                    .child("ソフトウェアエンコード設定"),
+                    .child(self.highlighted_text("QSV設定")),
             )
+            // 品質モード（ICQ: -global_quality / CQP: -q）
             .child(
                 div()
                     .w_full()
@@ -1265,29 +2940,446 @@ impl SettingsPanel {
                         div()
                             .text_xs()
                             .text_color(rgb(0x6c7086))
-                            .child("チューニング"),
+                            .child(self.highlighted_text("品質モード")),
                     )
-                    .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
-                        X264Tune::all().iter().map(|value| {
-                            let is_selected = *value == settings.x264_tune;
-                            let value_clone = *value;
-                            let app_state_clone = app_state.clone();
+                    .child(
+                        div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                            [
+                                (RateControlMode::IcqQsv, "ICQ"),
+                                (RateControlMode::Cqp, "CQP"),
+                            ]
+                            .into_iter()
+                            .map(|(value, name)| {
+                                let is_selected = value == settings.rate_control;
+                                let app_state_clone = app_state.clone();
 
-                            div()
-                                .px(px(8.0))
-                                .py(px(4.0))
-                                .rounded(px(4.0))
-                                .text_xs()
-                                .cursor_pointer()
-                                .bg(if is_selected {
-                                    rgb(0x89b4fa)
-                                } else {
-                                    rgb(0x313244)
-                                })
-                                .text_color(if is_selected {
-                                    rgb(0x1e1e2e)
-                                } else {
-                                    rgb(0xcdd6f4)
+                                div()
+                                    .px(px(8.0))
+                                    .py(px(4.0))
+                                    .rounded(px(4.0))
+                                    .text_xs()
+                                    .cursor_pointer()
+                                    .bg(if is_selected {
+                                        rgb(0x89b4fa)
+                                    } else {
+                                        rgb(0x313244)
+                                    })
+                                    .text_color(if is_selected {
+                                        rgb(0x1e1e2e)
+                                    } else {
+                                        rgb(0xcdd6f4)
+                                    })
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(move |_this, _, _, cx| {
+                                            app_state_clone.transcode_settings.update(
+                                                cx,
+                                                |s, _| {
+                                                    s.rate_control = value;
+                                                },
+                                            );
+                                            Self::update_estimated_sizes(&app_state_clone, cx);
+                                            cx.notify();
+                                        }),
+                                    )
+                                    .child(name)
+                            }),
+                        ),
+                    ),
+            )
+            // ルックアヘッド深度（0で無効）
+            .child(self.render_encoder_select(
+                "qsv-la-depth",
+                "ルックアヘッド深度",
+                &[
+                    (0, "オフ"),
+                    (20, "20"),
+                    (40, "40"),
+                    (60, "60"),
+                    (80, "80"),
+                    (100, "100"),
+                ],
+                settings.qsv_la_depth,
+                |s, value| s.qsv_la_depth = value,
+                false,
+                cx,
+            ))
+            // アダプティブI/B
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(12.0))
+                    .child(self.render_encoder_toggle(
+                        "qsv-adaptive-i",
+                        "アダプティブI",
+                        settings.qsv_adaptive_i,
+                        |s, value| s.qsv_adaptive_i = value,
+                        false,
+                        cx,
+                    ))
+                    .child(self.render_encoder_toggle(
+                        "qsv-adaptive-b",
+                        "アダプティブB",
+                        settings.qsv_adaptive_b,
+                        |s, value| s.qsv_adaptive_b = value,
+                        false,
+                        cx,
+                    ))
+                    .child(self.render_encoder_toggle(
+                        "qsv-extbrc",
+                        "外部ビットレート制御",
+                        settings.qsv_extbrc,
+                        |s, value| s.qsv_extbrc = value,
+                        false,
+                        cx,
+                    )),
+            )
+            .child(self.render_encoder_select(
+                "qsv-async-depth",
+                "非同期処理の深さ",
+                &[(1, "1"), (2, "2"), (4, "4"), (8, "8")],
+                settings.qsv_async_depth,
+                |s, value| s.qsv_async_depth = value,
+                false,
+                cx,
+            ))
+            // SEIはH.264/HEVCでのみ出力される
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(12.0))
+                    .child(self.render_encoder_toggle(
+                        "qsv-pic-timing-sei",
+                        "ピクチャタイミングSEI",
+                        settings.qsv_pic_timing_sei,
+                        |s, value| s.qsv_pic_timing_sei = value,
+                        false,
+                        cx,
+                    ))
+                    .child(self.render_encoder_toggle(
+                        "qsv-recovery-point-sei",
+                        "リカバリーポイントSEI",
+                        settings.qsv_recovery_point_sei,
+                        |s, value| s.qsv_recovery_point_sei = value,
+                        false,
+                        cx,
+                    )),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child("外部ビットレート制御は新しいドライバーが必要です。SEIはH.264/HEVCのみ"),
+            )
+    }
+
+    /// エンコーダー設定のON/OFFトグルをレンダリング
+    fn render_encoder_toggle(
+        &self,
+        id: &'static str,
+        label: &'static str,
+        current: bool,
+        apply: fn(&mut TranscodeSettings, bool),
+        disabled: bool,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .when(disabled, |this| this.opacity(0.5))
+            .when(!disabled, |this| {
+                this.cursor_pointer().on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |_this, _, _, cx| {
+                        app_state.transcode_settings.update(cx, |settings, _| {
+                            apply(settings, !current);
+                        });
+                        Self::update_estimated_sizes(&app_state, cx);
+                        cx.notify();
+                    }),
+                )
+            })
+            .child(
+                div()
+                    .px(px(8.0))
+                    .py(px(4.0))
+                    .rounded(px(4.0))
+                    .text_xs()
+                    .bg(if current {
+                        rgb(0x89b4fa)
+                    } else {
+                        rgb(0x313244)
+                    })
+                    .text_color(if current {
+                        rgb(0x1e1e2e)
+                    } else {
+                        rgb(0xcdd6f4)
+                    })
+                    .child(if current { "ON" } else { "OFF" }),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text(label)),
+            )
+    }
+
+    /// エンコーダー設定の選択ボタンをレンダリング
+    #[allow(clippy::too_many_arguments)]
+    fn render_encoder_select<T: Copy + PartialEq + 'static>(
+        &self,
+        id: &'static str,
+        label: &'static str,
+        options: &[(T, &'static str)],
+        current: T,
+        apply: fn(&mut TranscodeSettings, T),
+        disabled: bool,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text(label)),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(4.0))
+                    .when(disabled, |this| this.opacity(0.5))
+                    .children(options.iter().enumerate().map(|(i, &(value, name))| {
+                        let is_selected = value == current;
+                        let app_state_clone = app_state.clone();
+
+                        div()
+                            .id(SharedString::from(format!("{}-{}", id, i)))
+                            .px(px(8.0))
+                            .py(px(4.0))
+                            .rounded(px(4.0))
+                            .text_xs()
+                            .bg(if is_selected {
+                                rgb(0x89b4fa)
+                            } else {
+                                rgb(0x313244)
+                            })
+                            .text_color(if is_selected {
+                                rgb(0x1e1e2e)
+                            } else {
+                                rgb(0xcdd6f4)
+                            })
+                            .when(!disabled, |this| {
+                                this.cursor_pointer()
+                                    .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(move |_this, _, _, cx| {
+                                            app_state_clone
+                                                .transcode_settings
+                                                .update(cx, |settings, _| apply(settings, value));
+                                            Self::update_estimated_sizes(&app_state_clone, cx);
+                                            cx.notify();
+                                        }),
+                                    )
+                            })
+                            .child(name)
+                    })),
+            )
+    }
+
+    /// AMF設定をレンダリング
+    fn render_amf_settings(
+        &self,
+        settings: &TranscodeSettings,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(rgb(0xa6adc8))
+                    .child(self.highlighted_text("AMF設定")),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0x6c7086))
+                            .child(self.highlighted_text("使用法")),
+                    )
+                    .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                        AmfUsage::all().iter().map(|value| {
+                            let is_selected = *value == settings.amf_usage;
+                            let value_clone = *value;
+                            let app_state_clone = app_state.clone();
+
+                            div()
+                                .px(px(8.0))
+                                .py(px(4.0))
+                                .rounded(px(4.0))
+                                .text_xs()
+                                .cursor_pointer()
+                                .bg(if is_selected {
+                                    rgb(0x89b4fa)
+                                } else {
+                                    rgb(0x313244)
+                                })
+                                .text_color(if is_selected {
+                                    rgb(0x1e1e2e)
+                                } else {
+                                    rgb(0xcdd6f4)
+                                })
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(move |_this, _, _, cx| {
+                                        app_state_clone.transcode_settings.update(cx, |s, _| {
+                                            s.amf_usage = value_clone;
+                                        });
+                                        cx.notify();
+                                    }),
+                                )
+                                .child(value.display_name())
+                        }),
+                    )),
+            )
+            // プリアナリシスとVBAQ（NVENCのAQに相当）
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(12.0))
+                    .child(self.render_encoder_toggle(
+                        "amf-pre-analysis",
+                        "プリアナリシス",
+                        settings.amf_pre_analysis,
+                        |s, value| s.amf_pre_analysis = value,
+                        false,
+                        cx,
+                    ))
+                    .child(self.render_encoder_toggle(
+                        "amf-vbaq",
+                        "VBAQ",
+                        settings.amf_vbaq,
+                        |s, value| s.amf_vbaq = value,
+                        false,
+                        cx,
+                    ))
+                    .child(self.render_encoder_toggle(
+                        "amf-preencode",
+                        "プリエンコード",
+                        settings.amf_rate_control_preanalysis,
+                        |s, value| s.amf_rate_control_preanalysis = value,
+                        false,
+                        cx,
+                    )),
+            )
+            // Bフレームを使う場合のみ有効（H.264のみ）
+            .child(self.render_encoder_select(
+                "amf-bf-delta-qp",
+                "BフレームQP差分",
+                &[
+                    (-10, "-10"),
+                    (-8, "-8"),
+                    (-6, "-6"),
+                    (-4, "-4"),
+                    (-2, "-2"),
+                    (0, "0"),
+                    (2, "2"),
+                    (4, "4"),
+                    (6, "6"),
+                    (8, "8"),
+                    (10, "10"),
+                ],
+                settings.amf_b_frame_delta_qp,
+                |s, value| s.amf_b_frame_delta_qp = value,
+                settings.bframes == 0,
+                cx,
+            ))
+    }
+
+    /// ソフトウェアエンコード設定をレンダリング
+    fn render_software_settings(
+        &self,
+        settings: &TranscodeSettings,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(rgb(0xa6adc8))
+                    .child(self.highlighted_text("ソフトウェアエンコード設定")),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0x6c7086))
+                            .child(self.highlighted_text("チューニング")),
+                    )
+                    .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                        X264Tune::all().iter().map(|value| {
+                            let is_selected = *value == settings.x264_tune;
+                            let value_clone = *value;
+                            let app_state_clone = app_state.clone();
+
+                            div()
+                                .px(px(8.0))
+                                .py(px(4.0))
+                                .rounded(px(4.0))
+                                .text_xs()
+                                .cursor_pointer()
+                                .bg(if is_selected {
+                                    rgb(0x89b4fa)
+                                } else {
+                                    rgb(0x313244)
+                                })
+                                .text_color(if is_selected {
+                                    rgb(0x1e1e2e)
+                                } else {
+                                    rgb(0xcdd6f4)
                                 })
                                 .on_mouse_down(
                                     MouseButton::Left,
@@ -1312,7 +3404,7 @@ impl SettingsPanel {
                         div()
                             .text_xs()
                             .text_color(rgb(0x6c7086))
-                            .child("プロファイル"),
+                            .child(self.highlighted_text("プロファイル")),
                     )
                     .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
                         X264Profile::all().iter().map(|value| {
@@ -1351,38 +3443,1443 @@ impl SettingsPanel {
             )
     }
 
-    /// VP9設定をレンダリング
-    fn render_vp9_settings(
-        &self,
-        _settings: &TranscodeSettings,
-        _cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        div().w_full().child(
-            div()
-                .text_xs()
-                .text_color(rgb(0x6c7086))
-                .child("VP9設定 (標準設定を使用)"),
-        )
+    /// VP9設定をレンダリング
+    fn render_vp9_settings(
+        &self,
+        settings: &TranscodeSettings,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        const CPU_USED_LABELS: [&str; 17] = [
+            "-8", "-7", "-6", "-5", "-4", "-3", "-2", "-1", "0", "1", "2", "3", "4", "5", "6", "7",
+            "8",
+        ];
+
+        let deadline_options: Vec<_> = Vp9Deadline::all()
+            .iter()
+            .map(|deadline| (*deadline, deadline.display_name()))
+            .collect();
+        let cpu_used_options: Vec<(Option<i8>, &str)> = std::iter::once((None, "プリセット"))
+            .chain(
+                settings
+                    .vp9_deadline
+                    .cpu_used_range()
+                    .map(|value| (Some(value), CPU_USED_LABELS[(value + 8) as usize])),
+            )
+            .collect();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(rgb(0xa6adc8))
+                    .child(self.highlighted_text("VP9設定")),
+            )
+            .child(self.render_encoder_select(
+                "vp9-deadline",
+                "デッドライン",
+                &deadline_options,
+                settings.vp9_deadline,
+                |s, value| {
+                    s.vp9_deadline = value;
+                    // realtimeで使えない値は範囲内に収める
+                    let range = value.cpu_used_range();
+                    s.vp9_cpu_used = s
+                        .vp9_cpu_used
+                        .map(|v| v.clamp(*range.start(), *range.end()));
+                },
+                false,
+                cx,
+            ))
+            .child(self.render_encoder_select(
+                "vp9-cpu-used",
+                "cpu-used（大きいほど高速）",
+                &cpu_used_options,
+                settings.vp9_cpu_used,
+                |s, value| s.vp9_cpu_used = value,
+                false,
+                cx,
+            ))
+            // タイル分割（log2指定）
+            .child(self.render_encoder_select(
+                "vp9-tile-columns",
+                "タイル列 (log2)",
+                &[
+                    (0, "0"),
+                    (1, "1"),
+                    (2, "2"),
+                    (3, "3"),
+                    (4, "4"),
+                    (5, "5"),
+                    (6, "6"),
+                ],
+                settings.vp9_tile_columns,
+                |s, value| s.vp9_tile_columns = value,
+                false,
+                cx,
+            ))
+            .child(self.render_encoder_select(
+                "vp9-tile-rows",
+                "タイル行 (log2)",
+                &[(0, "0"), (1, "1"), (2, "2")],
+                settings.vp9_tile_rows,
+                |s, value| s.vp9_tile_rows = value,
+                false,
+                cx,
+            ))
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(12.0))
+                    .child(self.render_encoder_toggle(
+                        "vp9-frame-parallel",
+                        "フレーム並列",
+                        settings.vp9_frame_parallel,
+                        |s, value| s.vp9_frame_parallel = value,
+                        false,
+                        cx,
+                    ))
+                    .child(self.render_encoder_toggle(
+                        "vp9-auto-alt-ref",
+                        "自動ALTフレーム",
+                        settings.vp9_auto_alt_ref,
+                        |s, value| s.vp9_auto_alt_ref = value,
+                        false,
+                        cx,
+                    ))
+                    .child(self.render_encoder_toggle(
+                        "vp9-static-thresh",
+                        "static-thresh 0（画質優先）",
+                        settings.vp9_static_thresh_zero,
+                        |s, value| s.vp9_static_thresh_zero = value,
+                        false,
+                        cx,
+                    )),
+            )
+            // ラグインフレームは自動ALTフレーム有効時のみ使用される
+            .child(self.render_encoder_select(
+                "vp9-lag-in-frames",
+                "ラグインフレーム",
+                &[(0, "0"), (8, "8"), (16, "16"), (25, "25")],
+                settings.vp9_lag_in_frames,
+                |s, value| s.vp9_lag_in_frames = value,
+                !settings.vp9_auto_alt_ref,
+                cx,
+            ))
+    }
+
+    /// AV1設定をレンダリング
+    fn render_av1_settings(
+        &self,
+        settings: &TranscodeSettings,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        const TILE_OPTIONS: [(u8, &str); 5] = [(0, "0"), (1, "1"), (2, "2"), (3, "3"), (4, "4")];
+
+        // 現在の設定で使われるエンコーダー（検出中・設定変更直後はNone）
+        let encoder = self
+            .av1_encoder
+            .as_ref()
+            .filter(|(hwaccel, _)| *hwaccel == settings.hwaccel)
+            .map(|(_, encoder)| encoder.as_str());
+        // 検出が終わるまではすべて操作可能にしておく
+        let is_svtav1 = encoder.is_none_or(|e| e == "libsvtav1");
+        let is_software = encoder.is_none_or(|e| e == "libsvtav1" || e == "libaom-av1");
+        let is_libaom = encoder.is_none_or(|e| e == "libaom-av1");
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .text_xs()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(rgb(0xa6adc8))
+                            .child(self.highlighted_text("AV1設定")),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(rgb(0x6c7086))
+                            .child(match encoder {
+                                Some(encoder) => format!("使用エンコーダー: {}", encoder),
+                                None => "エンコーダーを確認中...".to_string(),
+                            }),
+                    ),
+            )
+            .when(!is_software, |this| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0x6c7086))
+                        .child("HWエンコーダーではフィルムグレイン・タイル設定は使用されません"),
+                )
+            })
+            // フィルムグレイン（libaomはノイズレベルとして使用）
+            .child(self.render_encoder_select(
+                "av1-film-grain",
+                "フィルムグレイン",
+                &[(0, "オフ"), (8, "8"), (16, "16"), (24, "24")],
+                settings.svtav1_film_grain,
+                |s, value| s.svtav1_film_grain = value,
+                !is_software,
+                cx,
+            ))
+            // グレイン合成前のノイズ除去はSVT-AV1のみ
+            .child(self.render_encoder_toggle(
+                "av1-film-grain-denoise",
+                "グレインのノイズ除去",
+                settings.svtav1_film_grain_denoise,
+                |s, value| s.svtav1_film_grain_denoise = value,
+                !is_svtav1 || settings.svtav1_film_grain == 0,
+                cx,
+            ))
+            // タイル分割（log2指定）
+            .child(self.render_encoder_select(
+                "av1-tile-columns",
+                "タイル列 (log2)",
+                &TILE_OPTIONS,
+                settings.av1_tile_columns,
+                |s, value| s.av1_tile_columns = value,
+                !is_software,
+                cx,
+            ))
+            .child(self.render_encoder_select(
+                "av1-tile-rows",
+                "タイル行 (log2)",
+                &TILE_OPTIONS,
+                settings.av1_tile_rows,
+                |s, value| s.av1_tile_rows = value,
+                !is_software,
+                cx,
+            ))
+            .when(is_svtav1, |this| {
+                this.child(self.render_svtav1_settings(settings, cx))
+            })
+            .when(is_libaom, |this| {
+                this.child(self.render_libaom_settings(settings, cx))
+            })
+    }
+
+    /// libaom-av1の詳細設定をレンダリング
+    fn render_libaom_settings(
+        &self,
+        settings: &TranscodeSettings,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let usage_options: Vec<_> = LibaomUsage::all()
+            .iter()
+            .map(|usage| (*usage, usage.display_name()))
+            .collect();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(rgb(0xa6adc8))
+                    .child(self.highlighted_text("libaom-av1詳細設定")),
+            )
+            .child(self.render_encoder_select(
+                "libaom-usage",
+                "用途",
+                &usage_options,
+                settings.libaom_usage,
+                |s, value| s.libaom_usage = value,
+                false,
+                cx,
+            ))
+            .child(self.render_encoder_select(
+                "libaom-lag-in-frames",
+                "ラグインフレーム",
+                &[(0, "0"), (16, "16"), (25, "25"), (35, "35")],
+                settings.libaom_lag_in_frames,
+                |s, value| s.libaom_lag_in_frames = value,
+                false,
+                cx,
+            ))
+            .child(self.render_encoder_toggle(
+                "libaom-auto-alt-ref",
+                "自動ALTフレーム",
+                settings.libaom_auto_alt_ref,
+                |s, value| s.libaom_auto_alt_ref = value,
+                false,
+                cx,
+            ))
+            // ARNRはALTフレームのノイズ除去に使われる
+            .child(self.render_encoder_select(
+                "libaom-arnr-maxframes",
+                "ARNR最大フレーム数",
+                &[(0, "0"), (3, "3"), (7, "7"), (15, "15")],
+                settings.libaom_arnr_maxframes,
+                |s, value| s.libaom_arnr_maxframes = value,
+                !settings.libaom_auto_alt_ref,
+                cx,
+            ))
+            .child(self.render_encoder_select(
+                "libaom-arnr-strength",
+                "ARNR強度",
+                &[(0, "0"), (2, "2"), (4, "4"), (5, "5"), (6, "6")],
+                settings.libaom_arnr_strength,
+                |s, value| s.libaom_arnr_strength = value,
+                !settings.libaom_auto_alt_ref,
+                cx,
+            ))
+            .child(
+                div().text_xs().text_color(rgb(0x6c7086)).child(
+                    "コンテンツの種類がアニメ・画面録画のファイルはそれに合わせて調整されます",
+                ),
+            )
+    }
+
+    /// SVT-AV1の詳細設定をレンダリング
+    fn render_svtav1_settings(
+        &self,
+        settings: &TranscodeSettings,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let tune_options: Vec<_> = SvtAv1Tune::all()
+            .iter()
+            .map(|tune| (*tune, tune.display_name()))
+            .collect();
+        let pred_options: Vec<_> = SvtAv1PredStructure::all()
+            .iter()
+            .map(|pred| (*pred, pred.display_name()))
+            .collect();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(rgb(0xa6adc8))
+                    .child(self.highlighted_text("SVT-AV1詳細設定")),
+            )
+            .child(self.render_encoder_select(
+                "svtav1-tune",
+                "チューニング",
+                &tune_options,
+                settings.svtav1_tune,
+                |s, value| s.svtav1_tune = value,
+                false,
+                cx,
+            ))
+            .child(self.render_encoder_toggle(
+                "svtav1-enable-dlf",
+                "デブロッキングフィルター",
+                settings.svtav1_enable_dlf,
+                |s, value| s.svtav1_enable_dlf = value,
+                false,
+                cx,
+            ))
+            .child(self.render_encoder_select(
+                "svtav1-pred-structure",
+                "予測構造",
+                &pred_options,
+                settings.svtav1_pred_structure,
+                |s, value| s.svtav1_pred_structure = value,
+                false,
+                cx,
+            ))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child("保存用にはランダムアクセス、ストリーミングには低遅延が適しています"),
+            )
+            .child(self.render_encoder_select(
+                "svtav1-hierarchical-levels",
+                "階層レベル",
+                &[(0, "自動"), (2, "2"), (3, "3"), (4, "4"), (5, "5")],
+                settings.svtav1_hierarchical_levels,
+                |s, value| s.svtav1_hierarchical_levels = value,
+                false,
+                cx,
+            ))
+    }
+
+    /// 完了後の動作ボタンをレンダリング
+    fn render_post_action_select(
+        &self,
+        current: &PostTranscodeAction,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let options = [
+            PostTranscodeAction::Nothing,
+            PostTranscodeAction::OpenFolder,
+            PostTranscodeAction::Sleep,
+            PostTranscodeAction::Shutdown,
+            PostTranscodeAction::CloseApp,
+            PostTranscodeAction::RunScript(String::new()),
+        ];
+        let script = match current {
+            PostTranscodeAction::RunScript(script) => Some(script.clone()),
+            _ => None,
+        };
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                options.into_iter().enumerate().map(|(i, value)| {
+                    let is_selected =
+                        std::mem::discriminant(&value) == std::mem::discriminant(current);
+                    let name = value.display_name();
+                    let app_state_clone = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!("post-action-{}", i)))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                if let PostTranscodeAction::RunScript(_) = value {
+                                    // 実行するスクリプトを選択
+                                    let app_state = app_state_clone.clone();
+                                    cx.spawn(async move |this, cx| {
+                                        let file = rfd::AsyncFileDialog::new()
+                                            .set_title("実行するスクリプトを選択")
+                                            .pick_file()
+                                            .await;
+                                        if let Some(file) = file {
+                                            let script = file.path().to_string_lossy().to_string();
+                                            cx.update(|cx| {
+                                                Self::update_app_settings(
+                                                    &app_state,
+                                                    cx,
+                                                    |settings| {
+                                                        settings.post_transcode_action =
+                                                            PostTranscodeAction::RunScript(script);
+                                                    },
+                                                );
+                                            })
+                                            .ok();
+                                            this.update(cx, |_, cx| cx.notify()).ok();
+                                        }
+                                    })
+                                    .detach();
+                                } else {
+                                    let value = value.clone();
+                                    Self::update_app_settings(&app_state_clone, cx, |settings| {
+                                        settings.post_transcode_action = value;
+                                    });
+                                    cx.notify();
+                                }
+                            }),
+                        )
+                        .child(name)
+                }),
+            ))
+            .when_some(script, |this, script| {
+                this.child(
+                    div()
+                        .w_full()
+                        .px(px(8.0))
+                        .py(px(6.0))
+                        .rounded(px(4.0))
+                        .bg(rgb(0x313244))
+                        .text_xs()
+                        .truncate()
+                        .child(script),
+                )
+            })
+            .when(current.is_one_shot(), |this| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0x6c7086))
+                        .child("エラーなく全ファイルが完了した場合のみ、60秒後に実行されます（次回起動時は「何もしない」に戻ります）"),
+                )
+            })
+    }
+
+    /// 元ファイルの処理ボタンをレンダリング
+    fn render_post_file_action_select(
+        &self,
+        current: &PostFileAction,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let options = [
+            PostFileAction::Nothing,
+            PostFileAction::DeleteSource,
+            PostFileAction::MoveSourceTo(std::path::PathBuf::new()),
+        ];
+        let move_to = match current {
+            PostFileAction::MoveSourceTo(dir) => Some(dir.to_string_lossy().to_string()),
+            _ => None,
+        };
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child("元ファイルの処理"),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                options.into_iter().enumerate().map(|(i, value)| {
+                    let is_selected =
+                        std::mem::discriminant(&value) == std::mem::discriminant(current);
+                    let name = value.display_name();
+                    let app_state_clone = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!("post-file-action-{}", i)))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                if let PostFileAction::MoveSourceTo(_) = value {
+                                    // 移動先のフォルダを選択
+                                    let app_state = app_state_clone.clone();
+                                    cx.spawn(async move |this, cx| {
+                                        let folder = rfd::AsyncFileDialog::new()
+                                            .set_title("元ファイルの移動先を選択")
+                                            .pick_folder()
+                                            .await;
+                                        if let Some(folder) = folder {
+                                            let dir = folder.path().to_path_buf();
+                                            cx.update(|cx| {
+                                                Self::update_app_settings(
+                                                    &app_state,
+                                                    cx,
+                                                    |settings| {
+                                                        settings.post_file_action =
+                                                            PostFileAction::MoveSourceTo(dir);
+                                                    },
+                                                );
+                                            })
+                                            .ok();
+                                            this.update(cx, |_, cx| cx.notify()).ok();
+                                        }
+                                    })
+                                    .detach();
+                                } else {
+                                    let value = value.clone();
+                                    Self::update_app_settings(&app_state_clone, cx, |settings| {
+                                        settings.post_file_action = value;
+                                    });
+                                    cx.notify();
+                                }
+                            }),
+                        )
+                        .child(name)
+                }),
+            ))
+            .when_some(move_to, |this, dir| {
+                this.child(
+                    div()
+                        .w_full()
+                        .px(px(8.0))
+                        .py(px(6.0))
+                        .rounded(px(4.0))
+                        .bg(rgb(0x313244))
+                        .text_xs()
+                        .truncate()
+                        .child(dir),
+                )
+            })
+            .when(*current == PostFileAction::DeleteSource, |this| {
+                this.child(div().text_xs().text_color(rgb(0xf9e2af)).child(
+                    "変換に成功したファイルのみ削除します。開始時に対象のファイルを確認します",
+                ))
+            })
+    }
+
+    /// アプリケーション設定のON/OFFトグルをレンダリング
+    fn render_app_toggle(
+        &self,
+        id: &'static str,
+        label: &'static str,
+        current: bool,
+        apply: fn(&mut Settings, bool),
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .id(id)
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .cursor_pointer()
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |_this, _, _, cx| {
+                    Self::update_app_settings(&app_state, cx, |settings| {
+                        apply(settings, !current);
+                    });
+                    cx.notify();
+                }),
+            )
+            .child(
+                div()
+                    .px(px(8.0))
+                    .py(px(4.0))
+                    .rounded(px(4.0))
+                    .text_xs()
+                    .bg(if current {
+                        rgb(0x89b4fa)
+                    } else {
+                        rgb(0x313244)
+                    })
+                    .text_color(if current {
+                        rgb(0x1e1e2e)
+                    } else {
+                        rgb(0xcdd6f4)
+                    })
+                    .child(if current { "ON" } else { "OFF" }),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text(label)),
+            )
+    }
+
+    /// 既存の出力ファイルの扱いボタンをレンダリング
+    fn render_overwrite_policy_select(
+        &self,
+        current: OverwritePolicy,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("同名の出力ファイルがある場合")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                OverwritePolicy::all().iter().map(|value| {
+                    let is_selected = *value == current;
+                    let value = *value;
+                    let app_state = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!("overwrite-{:?}", value)))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                app_state.transcode_settings.update(cx, |settings, _| {
+                                    settings.overwrite_policy = value;
+                                });
+                                // 次回起動時も同じ動作にする
+                                Self::update_app_settings(&app_state, cx, |settings| {
+                                    settings.overwrite_policy = value;
+                                });
+                                cx.notify();
+                            }),
+                        )
+                        .child(value.display_name())
+                }),
+            ))
+    }
+
+    /// 画質評価のサンプル数ボタンをレンダリング
+    fn render_quality_samples_select(
+        &self,
+        current: u32,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let options = [2u32, 4, 8, 16];
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div().text_xs().text_color(rgb(0x6c7086)).child(
+                    self.highlighted_text(format!("画質評価の区間数（5秒ずつ）: {}", current)),
+                ),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                options.into_iter().map(|value| {
+                    let is_selected = value == current;
+                    let app_state = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!("quality-samples-{}", value)))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                Self::update_app_settings(&app_state, cx, |settings| {
+                                    settings.quality_samples = value;
+                                });
+                                cx.notify();
+                            }),
+                        )
+                        .child(value.to_string())
+                }),
+            ))
+    }
+
+    /// 同時に変換するファイル数ボタンをレンダリング
+    fn render_parallel_jobs_select(
+        &self,
+        current: u32,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let options = [1u32, 2, 3, 4];
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("同時に変換するファイル数")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                options.into_iter().map(|value| {
+                    let is_selected = value == current;
+                    let app_state = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!("parallel-jobs-{}", value)))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                Self::update_app_settings(&app_state, cx, |settings| {
+                                    settings.parallel_jobs = value;
+                                });
+                                cx.notify();
+                            }),
+                        )
+                        .child(value.to_string())
+                }),
+            ))
+    }
+
+    /// 並列変換で使うNVENCの同時セッション数ボタンをレンダリング
+    fn render_nvenc_sessions_select(
+        &self,
+        current: u32,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        // 0は自動（GPUとドライバーから判定）
+        let options = [0u32, 3, 5, 8];
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("NVENCの同時セッション数（並列変換）")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                options.into_iter().map(|value| {
+                    let is_selected = value == current;
+                    let app_state = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!("nvenc-sessions-{}", value)))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                Self::update_app_settings(&app_state, cx, |settings| {
+                                    settings.nvenc_session_limit = value;
+                                });
+                                cx.notify();
+                            }),
+                        )
+                        .child(if value == 0 {
+                            "自動".to_string()
+                        } else {
+                            value.to_string()
+                        })
+                }),
+            ))
+    }
+
+    /// 失敗時の再試行回数と待ち時間の選択をレンダリング
+    fn render_retry_select(
+        &self,
+        current_count: u8,
+        current_delay: u32,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let counts = [0u8, 1, 2, 3, 5];
+        let delays = [5u32, 15, 30, 60];
+
+        let pill = |id: String, label: String, is_selected: bool| {
+            div()
+                .id(SharedString::from(id))
+                .px(px(8.0))
+                .py(px(4.0))
+                .rounded(px(4.0))
+                .text_xs()
+                .cursor_pointer()
+                .bg(if is_selected {
+                    rgb(0x89b4fa)
+                } else {
+                    rgb(0x313244)
+                })
+                .text_color(if is_selected {
+                    rgb(0x1e1e2e)
+                } else {
+                    rgb(0xcdd6f4)
+                })
+                .hover(move |s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                .child(label)
+        };
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("失敗時の再試行回数")),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap(px(4.0))
+                    .children(counts.into_iter().map(|value| {
+                        let app_state = app_state.clone();
+                        let label = if value == 0 {
+                            "しない".to_string()
+                        } else {
+                            format!("{}回", value)
+                        };
+                        pill(
+                            format!("retry-count-{}", value),
+                            label,
+                            value == current_count,
+                        )
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                Self::update_app_settings(&app_state, cx, |settings| {
+                                    settings.retry_on_failure = value;
+                                });
+                                cx.notify();
+                            }),
+                        )
+                    })),
+            )
+            .when(current_count > 0, |this| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(rgb(0x6c7086))
+                        .child(format!("再試行までの待ち時間: {}秒", current_delay)),
+                )
+                .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                    delays.into_iter().map(|value| {
+                        let app_state = app_state.clone();
+                        pill(
+                            format!("retry-delay-{}", value),
+                            format!("{}秒", value),
+                            value == current_delay,
+                        )
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                Self::update_app_settings(&app_state, cx, |settings| {
+                                    settings.retry_delay_secs = value;
+                                });
+                                cx.notify();
+                            }),
+                        )
+                    }),
+                ))
+            })
+    }
+
+    /// 変換プロセスの優先度選択をレンダリング
+    fn render_encode_priority_select(
+        &self,
+        current: ProcessPriority,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("変換の優先度")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                ProcessPriority::all().iter().map(|&priority| {
+                    let is_selected = priority == current;
+                    let app_state = app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!(
+                            "encode-priority-{:?}",
+                            priority
+                        )))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                Self::update_app_settings(&app_state, cx, |settings| {
+                                    settings.encode_priority = priority;
+                                });
+                                cx.notify();
+                            }),
+                        )
+                        .child(priority.display_name())
+                }),
+            ))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child("「低」では変換中もほかのアプリの操作を優先します"),
+            )
+    }
+
+    /// 監視フォルダ設定をレンダリング
+    fn render_watch_folder_settings(
+        &self,
+        folder: Option<std::path::PathBuf>,
+        auto_start: bool,
+        preset: Option<String>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let app_state_for_clear = self.app_state.clone();
+        let has_folder = folder.is_some();
+        // 「現在の設定」とユーザー定義プリセット
+        let preset_options: Vec<Option<String>> = std::iter::once(None)
+            .chain(
+                self.preset_store
+                    .list_presets()
+                    .into_iter()
+                    .map(|name| Some(name.to_string())),
+            )
+            .collect();
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .flex_1()
+                            .px(px(8.0))
+                            .py(px(6.0))
+                            .rounded(px(4.0))
+                            .bg(rgb(0x313244))
+                            .text_sm()
+                            .truncate()
+                            .child(
+                                folder
+                                    .map(|p| p.to_string_lossy().to_string())
+                                    .unwrap_or_else(|| "未設定".to_string()),
+                            ),
+                    )
+                    .child(
+                        Button::new("select-watch-folder")
+                            .label("選択")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(move |_this, _, _, cx| {
+                                let app_state = app_state.clone();
+                                cx.spawn(async move |this, cx| {
+                                    let folder = rfd::AsyncFileDialog::new()
+                                        .set_title("監視フォルダを選択")
+                                        .pick_folder()
+                                        .await;
+                                    if let Some(folder) = folder {
+                                        let path = folder.path().to_path_buf();
+                                        cx.update(|cx| {
+                                            Self::update_app_settings(&app_state, cx, |settings| {
+                                                settings.watch_folder = Some(path);
+                                            });
+                                        })
+                                        .ok();
+                                        this.update(cx, |_, cx| cx.notify()).ok();
+                                    }
+                                })
+                                .detach();
+                            })),
+                    )
+                    .when(has_folder, |this| {
+                        this.child(
+                            Button::new("clear-watch-folder")
+                                .label("解除")
+                                .with_variant(ButtonVariant::Ghost)
+                                .on_click(cx.listener(move |_this, _, _, cx| {
+                                    Self::update_app_settings(
+                                        &app_state_for_clear,
+                                        cx,
+                                        |settings| {
+                                            settings.watch_folder = None;
+                                        },
+                                    );
+                                    cx.notify();
+                                })),
+                        )
+                    }),
+            )
+            .child(self.render_app_toggle(
+                "watch-auto-start",
+                "追加されたら自動で変換開始",
+                auto_start,
+                |settings, value| settings.watch_folder_auto_start = value,
+                cx,
+            ))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("適用するプリセット")),
+            )
+            .child(div().w_full().flex().flex_wrap().gap(px(4.0)).children(
+                preset_options.into_iter().enumerate().map(|(i, value)| {
+                    let is_selected = value == preset;
+                    let label = value.clone().unwrap_or_else(|| "現在の設定".to_string());
+                    let app_state = self.app_state.clone();
+
+                    div()
+                        .id(SharedString::from(format!("watch-preset-{}", i)))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .text_xs()
+                        .cursor_pointer()
+                        .bg(if is_selected {
+                            rgb(0x89b4fa)
+                        } else {
+                            rgb(0x313244)
+                        })
+                        .text_color(if is_selected {
+                            rgb(0x1e1e2e)
+                        } else {
+                            rgb(0xcdd6f4)
+                        })
+                        .hover(|s| if is_selected { s } else { s.bg(rgb(0x45475a)) })
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |_this, _, _, cx| {
+                                let value = value.clone();
+                                Self::update_app_settings(&app_state, cx, |settings| {
+                                    settings.watch_folder_preset = value;
+                                });
+                                cx.notify();
+                            }),
+                        )
+                        .child(label)
+                }),
+            ))
+    }
+
+    /// FFmpegパス設定をレンダリング
+    fn render_ffmpeg_path_settings(
+        &self,
+        custom_path: Option<std::path::PathBuf>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let has_custom_path = custom_path.is_some();
+        let current_path = self.app_state.ffmpeg_path.read(cx).clone();
+        let path_text = match (custom_path, current_path) {
+            (Some(path), _) => path.to_string_lossy().to_string(),
+            (None, Some(path)) => format!("自動検出: {}", path.to_string_lossy()),
+            (None, None) => "未検出".to_string(),
+        };
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("FFmpegパス")),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .flex_1()
+                            .px(px(8.0))
+                            .py(px(6.0))
+                            .rounded(px(4.0))
+                            .bg(rgb(0x313244))
+                            .text_sm()
+                            .truncate()
+                            .child(path_text),
+                    )
+                    .child(
+                        Button::new("select-ffmpeg-path")
+                            .label("参照")
+                            .with_variant(ButtonVariant::Ghost)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.select_ffmpeg_path(cx);
+                            })),
+                    )
+                    .when(has_custom_path, |this| {
+                        this.child(
+                            Button::new("clear-ffmpeg-path")
+                                .label("解除")
+                                .with_variant(ButtonVariant::Ghost)
+                                .on_click(cx.listener(move |this, _, _, cx| {
+                                    Self::update_app_settings(&app_state, cx, |settings| {
+                                        settings.ffmpeg_custom_path = None;
+                                    });
+                                    this.redetect_ffmpeg(cx);
+                                })),
+                        )
+                    }),
+            )
+            // 確認結果
+            .when_some(self.ffmpeg_status.clone(), |this, status| {
+                let (icon, color, text) = match status {
+                    Ok(version) => ("✓", rgb(0xa6e3a1), version),
+                    Err(error) => ("✗", rgb(0xf38ba8), error),
+                };
+                this.child(
+                    div()
+                        .flex()
+                        .gap(px(6.0))
+                        .text_xs()
+                        .text_color(color)
+                        .child(icon)
+                        .child(text),
+                )
+            })
+            .child(
+                div().child(
+                    Button::new("redetect-ffmpeg")
+                        .label(if self.detecting_ffmpeg {
+                            "検出中..."
+                        } else {
+                            "検出を再実行"
+                        })
+                        .with_variant(ButtonVariant::Ghost)
+                        .disabled(self.detecting_ffmpeg)
+                        .on_click(cx.listener(|this, _, _, cx| {
+                            this.redetect_ffmpeg(cx);
+                        })),
+                ),
+            )
+    }
+
+    /// 予測サイズの補正をレンダリング
+    fn render_size_calibration_settings(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let app_state = self.app_state.clone();
+        let sample_count = self.app_state.calibration.read(cx).sample_count();
+        let status = if sample_count == 0 {
+            "変換結果はまだ記録されていません".to_string()
+        } else {
+            format!("{}件の変換結果から補正中", sample_count)
+        };
+
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child(self.highlighted_text("予測サイズの補正")),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(8.0))
+                    .child(div().text_sm().child(status))
+                    .child(
+                        Button::new("reset-size-calibration")
+                            .label("予測をリセット")
+                            .with_variant(ButtonVariant::Ghost)
+                            .disabled(sample_count == 0)
+                            .on_click(cx.listener(move |_, _, _, cx| {
+                                let result = app_state
+                                    .calibration
+                                    .update(cx, |calibration, _| calibration.clear());
+                                if let Err(e) = result {
+                                    log::warn!("Failed to clear size calibration: {:#}", e);
+                                }
+                                Self::update_estimated_sizes(&app_state, cx);
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(rgb(0x6c7086))
+                    .child("コーデック・HWアクセラレーション・コンテンツタイプごとに、完了した変換の実際のサイズで予測を補正します"),
+            )
+    }
+
+    /// 検索欄を取得（初回はフォーカスした状態で作成）
+    fn search_input(&mut self, window: &mut Window, cx: &mut Context<Self>) -> Entity<InputState> {
+        if let Some(input) = &self.search_input {
+            return input.clone();
+        }
+
+        let input = cx.new(|cx| InputState::new(window, cx).placeholder("設定を検索"));
+        cx.subscribe_in(&input, window, |this, input, event: &InputEvent, _, cx| {
+            if let InputEvent::Change = event {
+                this.search_query = input.read(cx).value().to_string();
+                cx.notify();
+            }
+        })
+        .detach();
+        window.focus(&input.focus_handle(cx));
+        self.search_input = Some(input.clone());
+        input
+    }
+
+    /// 検索語に一致した部分を強調したラベル
+    fn highlighted_text(&self, label: impl Into<SharedString>) -> StyledText {
+        let label = label.into();
+        let highlight = find_query(&label, &self.search_query).map(|range| {
+            (
+                range,
+                HighlightStyle {
+                    color: Some(rgb(0x89b4fa).into()),
+                    font_weight: Some(FontWeight::BOLD),
+                    ..Default::default()
+                },
+            )
+        });
+        StyledText::new(label).with_highlights(highlight)
     }
 
-    /// AV1設定をレンダリング
-    fn render_av1_settings(
-        &self,
-        _settings: &TranscodeSettings,
-        _cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        div().w_full().child(
-            div()
-                .text_xs()
-                .text_color(rgb(0x6c7086))
-                .child("AV1設定 (標準設定を使用)"),
-        )
+    /// セクション見出しをレンダリング
+    fn render_section_header(&self, label: &'static str) -> impl IntoElement {
+        div()
+            .w_full()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(rgb(0xa6adc8))
+                    .child(self.highlighted_text(label)),
+            )
+            .child(div().w_full().h(px(1.0)).bg(rgb(0x313244)))
     }
 }
 
 impl Render for SettingsPanel {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let search_input = self.search_input(window, cx);
+        let resolution_inputs = self.resolution_inputs(window, cx);
+        let hdr_inputs = self.hdr_inputs(window, cx);
+        let target_size_input = self.target_size_input(window, cx);
         let settings = self.app_state.transcode_settings.read(cx).clone();
+        let app_settings = self.app_state.settings.read(cx).clone();
+        let has_hdr_source = self
+            .app_state
+            .files
+            .read(cx)
+            .iter()
+            .any(|f| f.metadata.is_hdr());
+        let effective_hwaccel = self.effective_hwaccel(settings.hwaccel);
+
+        // 見出しに一致した場合はそのセクションの設定をすべて表示
+        let query = self.search_query.clone();
+        let searching = !query.trim().is_empty();
+        let visible = |section: Option<&str>, label: &str| {
+            section.is_some_and(|section| setting_matches_query(section, &query))
+                || setting_matches_query(label, &query)
+        };
+        let section_visible = |section: &str, labels: &[&str]| {
+            labels.iter().any(|&label| visible(Some(section), label))
+        };
+
+        const RATE_CONTROL: Option<&str> = Some("レートコントロール");
+        const ENCODER_DETAILS: Option<&str> = Some("エンコーダー詳細設定");
+        const METADATA: Option<&str> = Some("メタデータ");
+        const POST_ACTION: Option<&str> = Some("完了後の動作");
+        const WATCH_FOLDER: Option<&str> = Some("監視フォルダ");
+        const SYSTEM: Option<&str> = Some("システム設定");
 
         div()
             .size_full()
@@ -1406,6 +4903,16 @@ impl Render for SettingsPanel {
                             .child("エンコード設定"),
                     ),
             )
+            // 検索
+            .child(
+                div()
+                    .w_full()
+                    .px(px(16.0))
+                    .py(px(8.0))
+                    .border_b_1()
+                    .border_color(rgb(0x313244))
+                    .child(Input::new(&search_input)),
+            )
             // 設定項目
             .child(
                 div()
@@ -1417,42 +4924,139 @@ impl Render for SettingsPanel {
                     .flex()
                     .flex_col()
                     .gap(px(16.0))
+                    // プリセット
+                    .when(visible(None, "プリセット"), |this| {
+                        this.child(self.render_preset_row(cx))
+                    })
                     // 出力形式
-                    .child(self.render_container_select(settings.container, cx))
+                    .when(visible(None, "コンテナ形式"), |this| {
+                        this.child(self.render_container_select(settings.container, cx))
+                    })
                     // ビデオコーデック
-                    .child(self.render_video_codec_select(settings.video_codec, cx))
+                    .when(visible(None, "ビデオコーデック"), |this| {
+                        this.child(self.render_video_codec_select(settings.video_codec, cx))
+                    })
+                    // 元の映像が同じコーデックならコピー
+                    .when(
+                        settings.video_codec != VideoCodec::Copy
+                            && visible(None, "同じコーデックの映像はコピー"),
+                        |this| {
+                            this.child(self.render_encoder_toggle(
+                                "smart-video-copy",
+                                "同じコーデックの映像はコピー",
+                                settings.smart_video_copy,
+                                |settings, value| settings.smart_video_copy = value,
+                                false,
+                                cx,
+                            ))
+                        },
+                    )
                     // 解像度
-                    .child(self.render_resolution_select(settings.resolution, cx))
+                    .when(visible(None, "解像度"), |this| {
+                        this.child(self.render_resolution_select(
+                            settings.resolution,
+                            resolution_inputs,
+                            cx,
+                        ))
+                    })
+                    // フレームレート
+                    .when(visible(None, "フレームレート"), |this| {
+                        this.child(self.render_frame_rate_select(settings.frame_rate, cx))
+                    })
+                    // フレームレート変換方式（変換する場合のみ）
+                    .when(
+                        settings.frame_rate != FrameRate::Original
+                            && visible(None, "フレームレート変換方式"),
+                        |this| {
+                            this.child(
+                                self.render_fps_filter_mode_select(settings.fps_filter_mode, cx),
+                            )
+                        },
+                    )
+                    // クロップ
+                    .when(visible(None, "クロップ"), |this| {
+                        this.child(self.render_crop_settings(settings.crop_mode, settings.crop, cx))
+                    })
+                    // インターレース解除
+                    .when(visible(None, "インターレース解除"), |this| {
+                        this.child(self.render_deinterlace_select(settings.deinterlace, cx))
+                    })
                     // HWアクセラレーション
-                    .child(self.render_hwaccel_select(settings.hwaccel, cx))
+                    .when(visible(None, "HWアクセラレーション"), |this| {
+                        this.child(self.render_hwaccel_select(settings.hwaccel, cx))
+                    })
+                    // HWデコード（ソフトウェアエンコード時は使用しない）
+                    .when(
+                        settings.hwaccel != HwAccelType::Software && visible(None, "HWデコード"),
+                        |this| this.child(self.render_hw_decode_select(settings.hw_decode, cx)),
+                    )
+                    // HDR/色空間（HDRのソースがあり再エンコードする場合のみ）
+                    .when(
+                        has_hdr_source
+                            && settings.video_codec != VideoCodec::Copy
+                            && (visible(None, "HDR/色空間") || visible(None, "トーンマッピング")),
+                        |this| {
+                            this.child(self.render_hdr_settings(
+                                &settings,
+                                settings.video_codec.encoder_name(&effective_hwaccel) == "libx265",
+                                hdr_inputs,
+                                cx,
+                            ))
+                        },
+                    )
                     // セクション区切り
-                    .child(
-                        div()
-                            .w_full()
-                            .flex()
-                            .flex_col()
-                            .gap(px(4.0))
-                            .child(
-                                div()
-                                    .text_xs()
-                                    .font_weight(FontWeight::MEDIUM)
-                                    .text_color(rgb(0xa6adc8))
-                                    .child("レートコントロール"),
-                            )
-                            .child(div().w_full().h(px(1.0)).bg(rgb(0x313244))),
+                    .when(
+                        section_visible(
+                            "レートコントロール",
+                            &[
+                                "レートコントロールモード",
+                                "品質 (CRF)",
+                                "目標サイズ",
+                                "ターゲットビットレート",
+                                "最大ビットレート",
+                                "プリセット",
+                            ],
+                        ),
+                        |this| this.child(self.render_section_header("レートコントロール")),
                     )
                     // レートコントロールモード
-                    .child(self.render_rate_control_select(settings.rate_control, cx))
-                    // 品質 (CRF/QP) - CRFまたはCQPモードの時のみ
                     .when(
-                        settings.rate_control == RateControlMode::Crf
-                            || settings.rate_control == RateControlMode::Cqp,
-                        |this| this.child(self.render_crf_select(settings.crf, cx)),
+                        visible(RATE_CONTROL, "レートコントロールモード"),
+                        |this| {
+                            this.child(self.render_rate_control_select(
+                                settings.rate_control,
+                                effective_hwaccel,
+                                cx,
+                            ))
+                        },
+                    )
+                    // 品質 (CRF/QP/ICQ) - 品質指定のモードの時のみ
+                    .when(
+                        settings.rate_control.uses_quality() && visible(RATE_CONTROL, "品質 (CRF)"),
+                        |this| {
+                            this.child(self.render_crf_select(
+                                settings.crf,
+                                settings.video_codec.encoder_name(&effective_hwaccel),
+                                cx,
+                            ))
+                        },
+                    )
+                    // 目標サイズ - 目標サイズモードの時のみ
+                    .when(
+                        settings.rate_control == RateControlMode::TargetSize
+                            && visible(RATE_CONTROL, "目標サイズ"),
+                        |this| {
+                            this.child(self.render_target_size_input(
+                                &settings,
+                                target_size_input,
+                                cx,
+                            ))
+                        },
                     )
-                    // ターゲットビットレート - CBR/VBRモードの時
+                    // ターゲットビットレート - CBR/VBR/AVBRモードの時
                     .when(
-                        settings.rate_control == RateControlMode::Cbr
-                            || settings.rate_control == RateControlMode::Vbr,
+                        settings.rate_control.uses_bitrate()
+                            && visible(RATE_CONTROL, "ターゲットビットレート"),
                         |this| {
                             this.child(self.render_bitrate_select(
                                 settings.target_bitrate,
@@ -1463,138 +5067,497 @@ impl Render for SettingsPanel {
                         },
                     )
                     // 最大ビットレート - VBRモードの時のみ
-                    .when(settings.rate_control == RateControlMode::Vbr, |this| {
-                        this.child(self.render_max_bitrate_select(settings.max_bitrate, cx))
-                    })
+                    .when(
+                        settings.rate_control == RateControlMode::Vbr
+                            && visible(RATE_CONTROL, "最大ビットレート"),
+                        |this| this.child(self.render_max_bitrate_select(settings.max_bitrate, cx)),
+                    )
                     // プリセット
-                    .child(self.render_preset_select(settings.preset, cx))
+                    .when(visible(RATE_CONTROL, "プリセット"), |this| {
+                        this.child(self.render_preset_select(settings.preset, cx))
+                    })
                     // セクション区切り - エンコーダー詳細設定
-                    .child(
-                        div()
-                            .w_full()
-                            .flex()
-                            .flex_col()
-                            .gap(px(4.0))
-                            .child(
-                                div()
-                                    .text_xs()
-                                    .font_weight(FontWeight::MEDIUM)
-                                    .text_color(rgb(0xa6adc8))
-                                    .child("エンコーダー詳細設定"),
-                            )
-                            .child(div().w_full().h(px(1.0)).bg(rgb(0x313244))),
+                    .when(
+                        section_visible(
+                            "エンコーダー詳細設定",
+                            &[
+                                "Bフレーム数",
+                                "参照フレーム数",
+                                "GOPサイズ (キーフレーム間隔)",
+                                "ルックアヘッド (先行読み込み)",
+                                "ピクセルフォーマット",
+                                "NVENC設定",
+                                "QSV設定",
+                                "AMF設定",
+                                "ソフトウェアエンコード設定",
+                                "VP9設定",
+                                "AV1設定",
+                            ],
+                        ),
+                        |this| this.child(self.render_section_header("エンコーダー詳細設定")),
                     )
                     // 共通フレーム設定
-                    .child(self.render_bframes_select(settings.bframes, cx))
-                    .child(self.render_ref_frames_select(settings.ref_frames, cx))
-                    .child(self.render_gop_select(settings.gop_size, cx))
-                    .child(self.render_lookahead_select(settings.lookahead, cx))
-                    // エンコーダー固有設定
+                    .when(visible(ENCODER_DETAILS, "Bフレーム数"), |this| {
+                        this.child(self.render_bframes_select(settings.bframes, cx))
+                    })
+                    .when(visible(ENCODER_DETAILS, "参照フレーム数"), |this| {
+                        this.child(self.render_ref_frames_select(settings.ref_frames, cx))
+                    })
+                    .when(
+                        visible(ENCODER_DETAILS, "GOPサイズ (キーフレーム間隔)"),
+                        |this| this.child(self.render_gop_select(settings.gop_size, cx)),
+                    )
+                    .when(
+                        visible(ENCODER_DETAILS, "ルックアヘッド (先行読み込み)"),
+                        |this| this.child(self.render_lookahead_select(settings.lookahead, cx)),
+                    )
+                    // ピクセルフォーマット
+                    .when(
+                        settings.video_codec != VideoCodec::Copy
+                            && visible(ENCODER_DETAILS, "ピクセルフォーマット"),
+                        |this| {
+                            this.child(self.render_pixel_format_select(
+                                &settings,
+                                effective_hwaccel,
+                                cx,
+                            ))
+                        },
+                    )
+                    // エンコーダー固有設定（自動検出の場合は解決後の種類で表示）
                     .when(
-                        settings.hwaccel == HwAccelType::Nvenc
-                            || settings.hwaccel == HwAccelType::Auto,
+                        (effective_hwaccel == HwAccelType::Nvenc
+                            || effective_hwaccel == HwAccelType::Auto)
+                            && visible(ENCODER_DETAILS, "NVENC設定"),
                         |this| this.child(self.render_nvenc_settings(&settings, cx)),
                     )
-                    .when(settings.hwaccel == HwAccelType::Qsv, |this| {
-                        this.child(self.render_qsv_settings(&settings, cx))
-                    })
-                    .when(settings.hwaccel == HwAccelType::Amf, |this| {
-                        this.child(self.render_amf_settings(&settings, cx))
-                    })
-                    .when(settings.hwaccel == HwAccelType::Software, |this| {
-                        this.child(self.render_software_settings(&settings, cx))
-                    })
+                    .when(
+                        effective_hwaccel == HwAccelType::Qsv
+                            && visible(ENCODER_DETAILS, "QSV設定"),
+                        |this| this.child(self.render_qsv_settings(&settings, cx)),
+                    )
+                    .when(
+                        effective_hwaccel == HwAccelType::Amf
+                            && visible(ENCODER_DETAILS, "AMF設定"),
+                        |this| this.child(self.render_amf_settings(&settings, cx)),
+                    )
+                    .when(
+                        effective_hwaccel == HwAccelType::Software
+                            && visible(ENCODER_DETAILS, "ソフトウェアエンコード設定"),
+                        |this| this.child(self.render_software_settings(&settings, cx)),
+                    )
                     // VP9固有設定
-                    .when(settings.video_codec == VideoCodec::Vp9, |this| {
-                        this.child(self.render_vp9_settings(&settings, cx))
-                    })
+                    .when(
+                        settings.video_codec == VideoCodec::Vp9
+                            && visible(ENCODER_DETAILS, "VP9設定"),
+                        |this| this.child(self.render_vp9_settings(&settings, cx)),
+                    )
                     // AV1固有設定
-                    .when(settings.video_codec == VideoCodec::Av1, |this| {
-                        this.child(self.render_av1_settings(&settings, cx))
+                    .when(
+                        settings.video_codec == VideoCodec::Av1
+                            && (visible(ENCODER_DETAILS, "AV1設定")
+                                || visible(ENCODER_DETAILS, "SVT-AV1詳細設定")
+                                || visible(ENCODER_DETAILS, "libaom-av1詳細設定")),
+                        |this| this.child(self.render_av1_settings(&settings, cx)),
+                    )
+                    // セクション区切り（検索中は一致した設定だけを並べる）
+                    .when(!searching, |this| {
+                        this.child(div().w_full().h(px(1.0)).bg(rgb(0x313244)))
                     })
-                    // セクション区切り
-                    .child(div().w_full().h(px(1.0)).bg(rgb(0x313244)))
                     // オーディオコーデック
-                    .child(self.render_audio_codec_select(settings.audio_codec, cx))
+                    .when(visible(None, "オーディオコーデック"), |this| {
+                        this.child(self.render_audio_codec_select(settings.audio_codec, cx))
+                    })
+                    // 元の音声が同じコーデック・ビットレートならコピー
+                    .when(
+                        settings.audio_codec != AudioCodec::Copy
+                            && visible(None, "同じ形式の音声はコピー"),
+                        |this| {
+                            this.child(self.render_encoder_toggle(
+                                "smart-audio-copy",
+                                "同じ形式の音声はコピー",
+                                settings.smart_audio_copy,
+                                |settings, value| settings.smart_audio_copy = value,
+                                false,
+                                cx,
+                            ))
+                        },
+                    )
                     // オーディオビットレート
                     .when(
                         settings.audio_codec != AudioCodec::Copy
-                            && settings.audio_codec != AudioCodec::Flac,
+                            && settings.audio_codec != AudioCodec::Flac
+                            && visible(None, "オーディオビットレート"),
                         |this| {
                             this.child(self.render_audio_bitrate_select(settings.audio_bitrate, cx))
                         },
                     )
+                    // オーディオチャンネル（エンコーダーが出力できないチャンネル数は出さない）
+                    .when(
+                        settings.audio_codec != AudioCodec::Copy
+                            && visible(None, "オーディオチャンネル"),
+                        |this| {
+                            let max_channels = settings.audio_codec.max_channels();
+                            let options: Vec<_> = AudioChannels::all()
+                                .iter()
+                                .filter(|channels| {
+                                    channels
+                                        .count()
+                                        .zip(max_channels)
+                                        .is_none_or(|(count, max)| count <= max)
+                                })
+                                .map(|&channels| (channels, channels.display_name()))
+                                .collect();
+                            this.child(self.render_encoder_select(
+                                "audio-channels",
+                                "オーディオチャンネル",
+                                &options,
+                                settings.audio_channels,
+                                |settings, value| settings.audio_channels = value,
+                                false,
+                                cx,
+                            ))
+                        },
+                    )
+                    // オーディオサンプルレート
+                    .when(
+                        settings.audio_codec != AudioCodec::Copy
+                            && visible(None, "オーディオサンプルレート"),
+                        |this| {
+                            this.child(self.render_encoder_select(
+                                "audio-sample-rate",
+                                "オーディオサンプルレート",
+                                &[
+                                    (None, "元のまま"),
+                                    (Some(44_100), "44.1 kHz"),
+                                    (Some(48_000), "48 kHz"),
+                                ],
+                                settings.audio_sample_rate,
+                                |settings, value| settings.audio_sample_rate = value,
+                                false,
+                                cx,
+                            ))
+                        },
+                    )
+                    // セクション区切り - メタデータ
+                    .when(
+                        section_visible(
+                            "メタデータ",
+                            &["メタデータ（タイトルなど）を保持", "チャプターを保持"],
+                        ),
+                        |this| this.child(self.render_section_header("メタデータ")),
+                    )
+                    // メタデータ・チャプター
+                    .when(
+                        visible(METADATA, "メタデータ（タイトルなど）を保持"),
+                        |this| {
+                            this.child(self.render_encoder_toggle(
+                                "preserve-metadata",
+                                "メタデータ（タイトルなど）を保持",
+                                settings.preserve_metadata,
+                                |settings, value| settings.preserve_metadata = value,
+                                false,
+                                cx,
+                            ))
+                        },
+                    )
+                    .when(visible(METADATA, "チャプターを保持"), |this| {
+                        this.child(self.render_encoder_toggle(
+                            "preserve-chapters",
+                            "チャプターを保持",
+                            settings.preserve_chapters,
+                            |settings, value| settings.preserve_chapters = value,
+                            false,
+                            cx,
+                        ))
+                    })
                     // セクション区切り
-                    .child(div().w_full().h(px(1.0)).bg(rgb(0x313244)))
+                    .when(!searching, |this| {
+                        this.child(div().w_full().h(px(1.0)).bg(rgb(0x313244)))
+                    })
                     // 出力先
-                    .child(
-                        div()
-                            .w_full()
-                            .flex()
-                            .flex_col()
-                            .gap(px(4.0))
-                            .child(
-                                div()
-                                    .text_xs()
-                                    .text_color(rgb(0x6c7086))
-                                    .child("出力先フォルダ"),
+                    .when(visible(None, "出力先フォルダ"), |this| {
+                        this.child(
+                            div()
+                                .w_full()
+                                .flex()
+                                .flex_col()
+                                .gap(px(4.0))
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(0x6c7086))
+                                        .child(self.highlighted_text("出力先フォルダ")),
+                                )
+                                .child(
+                                    div()
+                                        .w_full()
+                                        .flex()
+                                        .items_center()
+                                        .gap(px(8.0))
+                                        .child(
+                                            div()
+                                                .flex_1()
+                                                .px(px(8.0))
+                                                .py(px(6.0))
+                                                .rounded(px(4.0))
+                                                .bg(rgb(0x313244))
+                                                .text_sm()
+                                                .truncate()
+                                                .child(
+                                                    settings
+                                                        .output_dir
+                                                        .as_ref()
+                                                        .map(|p| p.to_string_lossy().to_string())
+                                                        .unwrap_or_else(|| {
+                                                            "入力ファイルと同じ場所".to_string()
+                                                        }),
+                                                ),
+                                        )
+                                        .child(
+                                            Button::new("select-output")
+                                                .label("選択")
+                                                .with_variant(ButtonVariant::Ghost),
+                                        ),
+                                ),
+                        )
+                    })
+                    // 既存ファイルの扱い
+                    .when(
+                        visible(None, "同名の出力ファイルがある場合"),
+                        |this| {
+                            this.child(
+                                self.render_overwrite_policy_select(settings.overwrite_policy, cx),
                             )
-                            .child(
+                        },
+                    )
+                    // 出力サフィックス
+                    .when(
+                        visible(None, "出力ファイル名サフィックス"),
+                        |this| {
+                            this.child(
                                 div()
                                     .w_full()
                                     .flex()
-                                    .items_center()
-                                    .gap(px(8.0))
+                                    .flex_col()
+                                    .gap(px(4.0))
+                                    .child(
+                                        div().text_xs().text_color(rgb(0x6c7086)).child(
+                                            self.highlighted_text("出力ファイル名サフィックス"),
+                                        ),
+                                    )
                                     .child(
                                         div()
-                                            .flex_1()
+                                            .w_full()
                                             .px(px(8.0))
                                             .py(px(6.0))
                                             .rounded(px(4.0))
                                             .bg(rgb(0x313244))
                                             .text_sm()
-                                            .truncate()
-                                            .child(
-                                                settings
-                                                    .output_dir
-                                                    .as_ref()
-                                                    .map(|p| p.to_string_lossy().to_string())
-                                                    .unwrap_or_else(|| {
-                                                        "入力ファイルと同じ場所".to_string()
-                                                    }),
-                                            ),
-                                    )
-                                    .child(
-                                        Button::new("select-output")
-                                            .label("選択")
-                                            .with_variant(ButtonVariant::Ghost),
+                                            .child(settings.output_suffix.clone()),
                                     ),
-                            ),
+                            )
+                        },
                     )
-                    // 出力サフィックス
-                    .child(
-                        div()
-                            .w_full()
-                            .flex()
-                            .flex_col()
-                            .gap(px(4.0))
-                            .child(
-                                div()
-                                    .text_xs()
-                                    .text_color(rgb(0x6c7086))
-                                    .child("出力ファイル名サフィックス"),
+                    // エラー時の途中出力
+                    .when(
+                        visible(None, "エラー時に途中までの出力ファイルを残す"),
+                        |this| {
+                            this.child(self.render_app_toggle(
+                                "keep-partial-on-error",
+                                "エラー時に途中までの出力ファイルを残す",
+                                app_settings.keep_partial_on_error,
+                                |settings, value| settings.keep_partial_on_error = value,
+                                cx,
+                            ))
+                        },
+                    )
+                    // HWエンコーダーが失敗した場合のソフトウェアエンコードへの切り替え
+                    .when(
+                        visible(
+                            None,
+                            "HWエンコードに失敗したらソフトウェアエンコードで再試行",
+                        ),
+                        |this| {
+                            this.child(self.render_app_toggle(
+                                "auto-fallback-to-software",
+                                "HWエンコードに失敗したらソフトウェアエンコードで再試行",
+                                app_settings.auto_fallback_to_software,
+                                |settings, value| settings.auto_fallback_to_software = value,
+                                cx,
+                            ))
+                        },
+                    )
+                    // 失敗時の自動再試行
+                    .when(visible(None, "失敗時の再試行回数"), |this| {
+                        this.child(self.render_retry_select(
+                            app_settings.retry_on_failure,
+                            app_settings.retry_delay_secs,
+                            cx,
+                        ))
+                    })
+                    // 並列変換
+                    .when(
+                        visible(None, "同時に変換するファイル数"),
+                        |this| {
+                            this.child(
+                                self.render_parallel_jobs_select(app_settings.parallel_jobs, cx),
                             )
-                            .child(
-                                div()
-                                    .w_full()
-                                    .px(px(8.0))
-                                    .py(px(6.0))
-                                    .rounded(px(4.0))
-                                    .bg(rgb(0x313244))
-                                    .text_sm()
-                                    .child(settings.output_suffix.clone()),
-                            ),
-                    ),
+                        },
+                    )
+                    // NVENCの同時セッション数
+                    .when(
+                        visible(None, "NVENCの同時セッション数（並列変換）"),
+                        |this| {
+                            this.child(
+                                self.render_nvenc_sessions_select(
+                                    app_settings.nvenc_session_limit,
+                                    cx,
+                                ),
+                            )
+                        },
+                    )
+                    // 変換後の検証
+                    .when(
+                        visible(
+                            None,
+                            "変換後に出力ファイルを検証（長さ・コーデック・末尾のデコード）",
+                        ),
+                        |this| {
+                            this.child(self.render_app_toggle(
+                                "verify-output",
+                                "変換後に出力ファイルを検証（長さ・コーデック・末尾のデコード）",
+                                app_settings.verify_output,
+                                |settings, value| settings.verify_output = value,
+                                cx,
+                            ))
+                        },
+                    )
+                    // 変換後の画質評価
+                    .when(visible(None, "画質評価"), |this| {
+                        this.child(self.render_app_toggle(
+                            "quality-analysis",
+                            "変換後に画質評価（VMAF、非対応のFFmpegではSSIM）",
+                            app_settings.run_vmaf_after_encode,
+                            |settings, value| settings.run_vmaf_after_encode = value,
+                            cx,
+                        ))
+                    })
+                    .when(
+                        app_settings.run_vmaf_after_encode && visible(None, "画質評価の区間数"),
+                        |this| {
+                            this.child(
+                                self.render_quality_samples_select(
+                                    app_settings.quality_samples,
+                                    cx,
+                                ),
+                            )
+                        },
+                    )
+                    // セクション区切り - 完了後の動作
+                    .when(
+                        section_visible(
+                            "完了後の動作",
+                            &[
+                                "完了時に通知",
+                                "エラー時にファイルごとに通知",
+                                "完了後の処理",
+                                "元ファイルの処理",
+                            ],
+                        ),
+                        |this| this.child(self.render_section_header("完了後の動作")),
+                    )
+                    .when(visible(POST_ACTION, "完了時に通知"), |this| {
+                        this.child(self.render_app_toggle(
+                            "notify-on-complete",
+                            "完了時に通知",
+                            app_settings.notify_on_complete,
+                            |settings, value| settings.notify_on_complete = value,
+                            cx,
+                        ))
+                    })
+                    .when(
+                        visible(POST_ACTION, "エラー時にファイルごとに通知"),
+                        |this| {
+                            this.child(self.render_app_toggle(
+                                "notify-on-error",
+                                "エラー時にファイルごとに通知",
+                                app_settings.notify_on_error,
+                                |settings, value| settings.notify_on_error = value,
+                                cx,
+                            ))
+                        },
+                    )
+                    .when(visible(POST_ACTION, "完了後の処理"), |this| {
+                        this.child(
+                            self.render_post_action_select(&app_settings.post_transcode_action, cx),
+                        )
+                    })
+                    .when(visible(POST_ACTION, "元ファイルの処理"), |this| {
+                        this.child(
+                            self.render_post_file_action_select(&app_settings.post_file_action, cx),
+                        )
+                    })
+                    // セクション区切り - 監視フォルダ
+                    .when(
+                        section_visible("監視フォルダ", &["監視フォルダ"]),
+                        |this| this.child(self.render_section_header("監視フォルダ")),
+                    )
+                    .when(visible(WATCH_FOLDER, "監視フォルダ"), |this| {
+                        this.child(self.render_watch_folder_settings(
+                            app_settings.watch_folder.clone(),
+                            app_settings.watch_folder_auto_start,
+                            app_settings.watch_folder_preset.clone(),
+                            cx,
+                        ))
+                    })
+                    // セクション区切り - システム設定
+                    .when(
+                        section_visible(
+                            "システム設定",
+                            &["FFmpegパス", "変換の優先度", "予測サイズの補正"],
+                        ),
+                        |this| this.child(self.render_section_header("システム設定")),
+                    )
+                    .when(visible(SYSTEM, "FFmpegパス"), |this| {
+                        this.child(self.render_ffmpeg_path_settings(
+                            app_settings.ffmpeg_custom_path.clone(),
+                            cx,
+                        ))
+                    })
+                    .when(visible(SYSTEM, "変換の優先度"), |this| {
+                        this.child(
+                            self.render_encode_priority_select(app_settings.encode_priority, cx),
+                        )
+                    })
+                    .when(visible(SYSTEM, "予測サイズの補正"), |this| {
+                        this.child(self.render_size_calibration_settings(cx))
+                    }),
             )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{find_query, setting_matches_query};
+
+    #[test]
+    fn test_setting_matches_query() {
+        // 検索語が空ならすべて表示
+        assert!(setting_matches_query("コンテナ形式", ""));
+        assert!(setting_matches_query("コンテナ形式", "  "));
+
+        assert!(setting_matches_query("コンテナ形式", "コンテナ"));
+        assert!(!setting_matches_query("コンテナ形式", "コーデック"));
+        // 大文字小文字は区別しない
+        assert!(setting_matches_query("NVENC設定", "nvenc"));
+        assert!(setting_matches_query("GOPサイズ (キーフレーム間隔)", "Gop"));
+
+        // 強調する範囲は元の文字列のバイト位置
+        let label = "HWアクセラレーション";
+        let range = find_query(label, "hwア").unwrap();
+        assert_eq!(&label[range], "HWア");
+        let range = find_query("ビデオコーデック", "コーデック").unwrap();
+        assert_eq!(&"ビデオコーデック"[range], "コーデック");
+        assert_eq!(find_query("AV1設定", ""), None);
+    }
+}
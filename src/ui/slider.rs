@@ -0,0 +1,214 @@
+//! 数値スライダー
+
+use gpui::*;
+
+/// つまみの直径
+const THUMB_SIZE: f32 = 14.0;
+
+/// スライダーの値が変わった
+pub enum SliderEvent {
+    /// ドラッグ・クリックで選ばれた値
+    Change(f32),
+}
+
+/// ドラッグ中のつまみ（どのスライダーのものかを区別する）
+#[derive(Clone)]
+struct DragThumb(EntityId);
+
+impl Render for DragThumb {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        Empty
+    }
+}
+
+/// 数値スライダー（トラック・つまみ・数値ラベル）
+/// 値は親が `set_value` で反映し、操作された値は `SliderEvent::Change` で通知する
+pub struct SliderWidget {
+    /// 最小値
+    min: f32,
+    /// 最大値
+    max: f32,
+    /// 刻み幅
+    step: f32,
+    /// 現在の値
+    value: f32,
+    /// トラックの表示領域（マウス位置から値を求める）
+    bounds: Bounds<Pixels>,
+}
+
+impl EventEmitter<SliderEvent> for SliderWidget {}
+
+impl SliderWidget {
+    pub fn new(min: f32, max: f32, step: f32) -> Self {
+        Self {
+            min,
+            max,
+            step,
+            value: min,
+            bounds: Bounds::default(),
+        }
+    }
+
+    /// 範囲を変更（現在の値は範囲内に収める）
+    pub fn set_range(&mut self, min: f32, max: f32, cx: &mut Context<Self>) {
+        if self.min == min && self.max == max {
+            return;
+        }
+        self.min = min;
+        self.max = max;
+        self.value = self.value.clamp(min, max);
+        cx.notify();
+    }
+
+    /// 値を反映（イベントは発行しない）
+    pub fn set_value(&mut self, value: f32, cx: &mut Context<Self>) {
+        let value = value.clamp(self.min, self.max);
+        if self.value != value {
+            self.value = value;
+            cx.notify();
+        }
+    }
+
+    /// マウス位置の値に変更して通知
+    fn update_from_position(&mut self, position: Point<Pixels>, cx: &mut Context<Self>) {
+        let width = f32::from(self.bounds.size.width);
+        if width <= 0.0 {
+            return;
+        }
+        let ratio = f32::from(position.x - self.bounds.origin.x) / width;
+        let value = snap_value(self.min, self.max, self.step, ratio);
+        if self.value != value {
+            self.value = value;
+            cx.emit(SliderEvent::Change(value));
+            cx.notify();
+        }
+    }
+
+    /// 数値ラベル（刻み幅が整数なら整数で表示）
+    fn label(&self) -> String {
+        if self.step.fract() == 0.0 {
+            format!("{}", self.value.round() as i64)
+        } else {
+            format!("{:.1}", self.value)
+        }
+    }
+}
+
+/// トラック上の位置（0.0 - 1.0）を刻み幅に合わせた値に変換
+fn snap_value(min: f32, max: f32, step: f32, ratio: f32) -> f32 {
+    let value = min + (max - min) * ratio.clamp(0.0, 1.0);
+    let snapped = if step > 0.0 {
+        min + ((value - min) / step).round() * step
+    } else {
+        value
+    };
+    snapped.clamp(min, max)
+}
+
+impl Render for SliderWidget {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let ratio = if self.max > self.min {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            0.0
+        };
+        let entity = cx.entity();
+        let entity_id = cx.entity_id();
+
+        div()
+            .w_full()
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .id("slider-track")
+                    .flex_1()
+                    .h(px(THUMB_SIZE))
+                    .relative()
+                    .flex()
+                    .items_center()
+                    .cursor_pointer()
+                    // マウス位置から値を求めるためにトラックの領域を記録
+                    .child(
+                        canvas(
+                            move |bounds, _, cx| entity.update(cx, |this, _| this.bounds = bounds),
+                            |_, _, _, _| {},
+                        )
+                        .absolute()
+                        .size_full(),
+                    )
+                    // トラック
+                    .child(
+                        div()
+                            .w_full()
+                            .h(px(4.0))
+                            .rounded(px(2.0))
+                            .bg(rgb(0x313244))
+                            .child(
+                                div()
+                                    .h_full()
+                                    .w(relative(ratio))
+                                    .rounded(px(2.0))
+                                    .bg(rgb(0x89b4fa)),
+                            ),
+                    )
+                    // つまみ
+                    .child(
+                        div()
+                            .absolute()
+                            .left(relative(ratio))
+                            .ml(px(-THUMB_SIZE / 2.0))
+                            .size(px(THUMB_SIZE))
+                            .rounded_full()
+                            .bg(rgb(0x89b4fa))
+                            .border_2()
+                            .border_color(rgb(0x1e1e2e))
+                            .hover(|s| s.border_color(rgb(0xcdd6f4))),
+                    )
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, event: &MouseDownEvent, _, cx| {
+                            this.update_from_position(event.position, cx);
+                        }),
+                    )
+                    .on_drag(DragThumb(entity_id), |drag, _, _, cx| {
+                        cx.stop_propagation();
+                        cx.new(|_| drag.clone())
+                    })
+                    .on_drag_move(cx.listener(
+                        move |this, event: &DragMoveEvent<DragThumb>, _, cx| {
+                            // 別のスライダーのドラッグは無視
+                            if event.drag(cx).0 != entity_id {
+                                return;
+                            }
+                            this.update_from_position(event.event.position, cx);
+                        },
+                    )),
+            )
+            // 数値ラベル
+            .child(
+                div()
+                    .min_w(px(40.0))
+                    .text_xs()
+                    .text_color(rgb(0xcdd6f4))
+                    .child(self.label()),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::snap_value;
+
+    #[test]
+    fn test_snap_value() {
+        assert_eq!(snap_value(0.0, 51.0, 1.0, 0.0), 0.0);
+        assert_eq!(snap_value(0.0, 51.0, 1.0, 1.0), 51.0);
+        assert_eq!(snap_value(0.0, 51.0, 1.0, 0.5), 26.0);
+        // トラックの外は範囲内に収める
+        assert_eq!(snap_value(0.0, 63.0, 1.0, -0.2), 0.0);
+        assert_eq!(snap_value(0.0, 63.0, 1.0, 1.4), 63.0);
+        assert_eq!(snap_value(0.0, 2.0, 0.5, 0.3), 0.5);
+    }
+}